@@ -0,0 +1,72 @@
+/* The C API of chesshound, generated from src/ffi.rs by cbindgen. Don't edit by hand. */
+
+#ifndef CHESSHOUND_H
+#define CHESSHOUND_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Games parsed from PGN and arranged by their moves. Made by [`chesshound_games_parse`] and
+// freed by [`chesshound_games_free`].
+typedef struct ChesshoundGames ChesshoundGames;
+
+// How often each result happened in a set of games. The rates are fractions of `games`, and are
+// all zero when `games` is zero.
+typedef struct ChesshoundWinRates {
+  // The number of games.
+  size_t games;
+  // The fraction of games white won.
+  double white_wins;
+  // The fraction of games drawn.
+  double draws;
+  // The fraction of games black won.
+  double black_wins;
+} ChesshoundWinRates;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Parses the `length` bytes of PGN at `pgn`, returning the games, or null if the PGN can't be
+// read or a game has no result. The games must be freed with [`chesshound_games_free`].
+//
+// # Safety
+//
+// `pgn` must point to `length` readable bytes.
+struct ChesshoundGames *chesshound_games_parse(const uint8_t *pgn, size_t length);
+
+// Returns the number of games in `games`.
+//
+// # Safety
+//
+// `games` must have been returned by [`chesshound_games_parse`] and not yet freed.
+size_t chesshound_games_len(const struct ChesshoundGames *games);
+
+// Writes the win rates of the games in `games` that start with `moves` to `rates`, returning
+// whether they could be written. `moves` is a string of moves in SAN separated by spaces, such
+// as `"e4 c5 Nf3"`, and may be empty for the rates of every game. Returns false without writing
+// anything if one of the moves isn't valid SAN.
+//
+// # Safety
+//
+// `games` must have been returned by [`chesshound_games_parse`] and not yet freed, `moves` must
+// be a null terminated string, and `rates` must point to a writable `ChesshoundWinRates`.
+bool chesshound_games_win_rates(const struct ChesshoundGames *games,
+                                const char *moves,
+                                struct ChesshoundWinRates *rates);
+
+// Frees `games`. Does nothing if `games` is null.
+//
+// # Safety
+//
+// `games` must have been returned by [`chesshound_games_parse`] and not already freed.
+void chesshound_games_free(struct ChesshoundGames *games);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CHESSHOUND_H */
@@ -0,0 +1,639 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, BufRead, Write};
+
+use shakmaty::san::SanPlus;
+use shakmaty::{Chess, Position};
+
+use crate::engine::{Analysis, Candidate, Engine, Limit};
+use crate::eval::{self, Eval, Judgement, WinModel};
+use crate::game::{Color, GiveDate, GivePlayers, GiveRatings, GiveResult, ListMoves};
+use crate::graph::EvalGraph;
+use crate::moves::Move;
+use crate::phase::{self, Phase};
+use crate::position;
+
+/// A move that lost more evaluation than a given threshold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Blunder {
+    move_number: usize,
+    color: Color,
+    fen: String,
+    played: String,
+    best_move: Option<String>,
+    before: Eval,
+    after: Eval,
+    alternatives: Vec<Candidate>,
+}
+
+impl Blunder {
+    /// Returns the move number of the blunder.
+    pub fn move_number(&self) -> usize {
+        self.move_number
+    }
+
+    /// Returns the color of the player who blundered.
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /// Returns the FEN of the position the blunder was played from.
+    pub fn fen(&self) -> &str {
+        &self.fen
+    }
+
+    /// Returns the move that was played in SAN.
+    pub fn played(&self) -> &str {
+        &self.played
+    }
+
+    /// Returns the move the engine recommended instead in SAN, if it gave one.
+    pub fn best_move(&self) -> Option<&str> {
+        self.best_move.as_deref()
+    }
+
+    /// Returns the evaluation before the blunder, from white's point of view.
+    pub fn before(&self) -> Eval {
+        self.before
+    }
+
+    /// Returns the evaluation after the blunder, from white's point of view.
+    pub fn after(&self) -> Eval {
+        self.after
+    }
+
+    /// Returns the moves the engine found that were better than the one played, best first.
+    /// There is more than one only when the engine searched several lines.
+    pub fn alternatives(&self) -> &[Candidate] {
+        &self.alternatives
+    }
+
+    /// Returns how many centipawns the blunder lost.
+    pub fn centipawn_loss(&self) -> u32 {
+        eval::centipawn_loss(self.before, self.after, self.color)
+    }
+
+    /// Returns how much win probability the blunder lost according to `model`, from 0 to 1.
+    pub fn probability_lost(&self, model: &WinModel) -> f64 {
+        model.probability_lost(self.before, self.after, self.color)
+    }
+}
+
+/// Searches each position of `game` with `engine` within `limit`, returning the analysis of each
+/// position, or `None` for positions where the game is over. Positions after an illegal move are
+/// left out.
+pub fn analyse_game<M, G, R, W>(
+    engine: &mut Engine<R, W>,
+    game: &G,
+    limit: Limit,
+) -> io::Result<Vec<(Chess, Option<Analysis>)>>
+where
+    M: 'static + Clone + Move,
+    G: ListMoves<M>,
+    R: BufRead,
+    W: Write,
+{
+    position::replay(game.list_moves())
+        .into_iter()
+        .map(|position| {
+            let analysis = if position.is_game_over() {
+                None
+            } else {
+                Some(engine.analyse(&position, limit)?)
+            };
+
+            Ok((position, analysis))
+        })
+        .collect()
+}
+
+/// Returns the evaluation graph of `game` according to `engine` searching within `limit`.
+/// Positions where the game is drawn are evaluated as even, and checkmates are left without an
+/// evaluation.
+pub fn eval_graph<M, G, R, W>(
+    engine: &mut Engine<R, W>,
+    game: &G,
+    limit: Limit,
+) -> io::Result<EvalGraph>
+where
+    M: 'static + Clone + Move,
+    G: ListMoves<M>,
+    R: BufRead,
+    W: Write,
+{
+    let evals = analyse_game(engine, game, limit)?
+        .iter()
+        .map(|(position, analysis)| match analysis {
+            Some(analysis) => Some(analysis.eval()),
+            None if position.is_checkmate() => None,
+            None => Some(Eval::Centipawns(0)),
+        })
+        .collect::<Vec<_>>();
+
+    Ok(EvalGraph::new(game, &evals))
+}
+
+/// Returns the moves of `game` that lost at least `threshold` centipawns according to `engine`
+/// searching within `limit`. Moves the engine would have played itself are never counted, and
+/// neither are moves that deliver checkmate.
+pub fn find_blunders<M, G, R, W>(
+    engine: &mut Engine<R, W>,
+    game: &G,
+    limit: Limit,
+    threshold: u32,
+) -> io::Result<Vec<Blunder>>
+where
+    M: 'static + Clone + Move,
+    G: ListMoves<M>,
+    R: BufRead,
+    W: Write,
+{
+    let analysed = analyse_game(engine, game, limit)?;
+    let legal_moves = position::legal_moves(game.list_moves());
+    let mut blunders = Vec::new();
+
+    for (ply, legal_move) in legal_moves.iter().enumerate() {
+        let (position, analysis) = &analysed[ply];
+        let (next_position, next_analysis) = &analysed[ply + 1];
+
+        let analysis = match analysis {
+            Some(analysis) => analysis,
+            None => continue,
+        };
+
+        let after = match next_analysis {
+            Some(next_analysis) => next_analysis.eval(),
+            None if next_position.is_checkmate() => continue,
+            None => Eval::Centipawns(0),
+        };
+
+        let played = SanPlus::from_move(position.clone(), *legal_move).to_string();
+
+        if analysis.best_move() == Some(played.as_str()) {
+            continue;
+        }
+
+        let color = position.turn();
+
+        if eval::centipawn_loss(analysis.eval(), after, color) >= threshold {
+            let alternatives = analysis
+                .candidates()
+                .iter()
+                .filter(|candidate| {
+                    candidate.san() != played
+                        && candidate.eval().centipawns_for(color) > after.centipawns_for(color)
+                })
+                .cloned()
+                .collect();
+
+            blunders.push(Blunder {
+                move_number: ply / 2 + 1,
+                color,
+                fen: position::fen(position),
+                played,
+                best_move: analysis.best_move().map(String::from),
+                before: analysis.eval(),
+                after,
+                alternatives,
+            });
+        }
+    }
+
+    Ok(blunders)
+}
+
+/// The centipawns lost over a set of moves.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CentipawnLoss {
+    moves: usize,
+    total: u64,
+}
+
+impl CentipawnLoss {
+    fn add(&mut self, loss: u32) {
+        self.moves += 1;
+        self.total += loss as u64;
+    }
+
+    /// Returns the number of moves judged.
+    pub fn moves(&self) -> usize {
+        self.moves
+    }
+
+    /// Returns the total number of centipawns lost.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Returns the average number of centipawns lost per move, or `None` if no moves were judged.
+    pub fn average(&self) -> Option<f64> {
+        if self.moves > 0 {
+            Some(self.total as f64 / self.moves as f64)
+        } else {
+            None
+        }
+    }
+}
+
+/// The centipawns a player lost overall and in each phase of the game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayerCentipawnLoss {
+    player: String,
+    overall: CentipawnLoss,
+    phases: BTreeMap<Phase, CentipawnLoss>,
+}
+
+impl PlayerCentipawnLoss {
+    /// Returns the name of the player.
+    pub fn player(&self) -> &str {
+        &self.player
+    }
+
+    /// Returns the centipawns the player lost across all games.
+    pub fn overall(&self) -> CentipawnLoss {
+        self.overall
+    }
+
+    /// Returns the centipawns the player lost during `phase`.
+    pub fn phase(&self, phase: Phase) -> CentipawnLoss {
+        self.phases.get(&phase).copied().unwrap_or_default()
+    }
+}
+
+/// Returns the centipawns each player lost in the games of `game_iter` according to `engine`
+/// searching within `limit`, in order of player name. Moves that deliver checkmate lose nothing.
+///
+/// Positions reached in several games are only searched once, since the engine caches its
+/// analyses.
+pub fn average_centipawn_loss<'a, M, G, R, W>(
+    engine: &mut Engine<R, W>,
+    game_iter: &mut dyn Iterator<Item = &'a G>,
+    limit: Limit,
+) -> io::Result<Vec<PlayerCentipawnLoss>>
+where
+    M: 'static + Clone + Move,
+    G: 'a + GivePlayers + ListMoves<M>,
+    R: BufRead,
+    W: Write,
+{
+    let mut players: HashMap<String, (CentipawnLoss, BTreeMap<Phase, CentipawnLoss>)> =
+        HashMap::new();
+
+    for game in game_iter {
+        let analysed = analyse_game(engine, game, limit)?;
+        let game_phases = phase::phases(game.list_moves());
+
+        for (ply, window) in analysed.windows(2).enumerate() {
+            let (position, analysis) = &window[0];
+            let (next_position, next_analysis) = &window[1];
+
+            let before = match analysis {
+                Some(analysis) => analysis.eval(),
+                None => continue,
+            };
+
+            let color = position.turn();
+
+            let loss = match next_analysis {
+                Some(next_analysis) => eval::centipawn_loss(before, next_analysis.eval(), color),
+                None if next_position.is_checkmate() => 0,
+                None => eval::centipawn_loss(before, Eval::Centipawns(0), color),
+            };
+
+            let (overall, phases) = players
+                .entry(String::from(game.player_of(color).unwrap_or("?")))
+                .or_default();
+
+            overall.add(loss);
+            phases.entry(game_phases[ply]).or_default().add(loss);
+        }
+    }
+
+    let mut players = players
+        .into_iter()
+        .map(|(player, (overall, phases))| PlayerCentipawnLoss {
+            player,
+            overall,
+            phases,
+        })
+        .collect::<Vec<_>>();
+
+    players.sort_by(|a, b| a.player.cmp(&b.player));
+
+    Ok(players)
+}
+
+/// The longest line written when exporting PGN, as the PGN standard recommends.
+const PGN_LINE_LENGTH: usize = 80;
+
+/// Formats `eval` as a PGN `%eval` comment, as lichess does.
+fn eval_comment(eval: Eval) -> String {
+    match eval {
+        Eval::Centipawns(centipawns) => format!("{{ [%eval {:.2}] }}", centipawns as f64 / 100.),
+        Eval::Mate(moves) => format!("{{ [%eval #{}] }}", moves),
+    }
+}
+
+/// Joins `tokens` with spaces, breaking lines so none are longer than `PGN_LINE_LENGTH`.
+pub(crate) fn wrap_tokens(tokens: &[String]) -> String {
+    let mut output = String::new();
+    let mut line_length = 0;
+
+    for token in tokens {
+        if line_length > 0 && line_length + 1 + token.len() > PGN_LINE_LENGTH {
+            output.push('\n');
+            line_length = 0;
+        } else if line_length > 0 {
+            output.push(' ');
+            line_length += 1;
+        }
+
+        output += token;
+        line_length += token.len();
+    }
+
+    output
+}
+
+/// Returns `game` as PGN annotated by `engine` searching within `limit`, so it can be loaded into
+/// other tools. Each move is followed by a `%eval` comment giving the evaluation after it, and
+/// inaccuracies, mistakes, and blunders are marked with the `$6`, `$2`, and `$4` glyphs and
+/// followed by a variation with the engine's best move. With `blunders_only`, only inaccuracies,
+/// mistakes, and blunders are annotated.
+///
+/// Only the seven tag roster and the players' ratings are written, since other headers aren't
+/// kept. Moves after an illegal move are left out.
+pub fn annotate_game<M, G, R, W>(
+    engine: &mut Engine<R, W>,
+    game: &G,
+    limit: Limit,
+    blunders_only: bool,
+) -> io::Result<String>
+where
+    M: 'static + Clone + Move,
+    G: GiveDate + GivePlayers + GiveRatings + GiveResult + ListMoves<M>,
+    R: BufRead,
+    W: Write,
+{
+    let date = match game.date() {
+        Some(date) => date.format("%Y.%m.%d").to_string(),
+        None => String::from("????.??.??"),
+    };
+
+    let mut output = format!(
+        "[Event \"?\"]\n[Site \"?\"]\n[Date \"{}\"]\n[Round \"?\"]\n[White \"{}\"]\n\
+         [Black \"{}\"]\n[Result \"{}\"]\n",
+        date,
+        game.white_player().unwrap_or("?"),
+        game.black_player().unwrap_or("?"),
+        game.result()
+    );
+
+    for (tag, rating) in [
+        ("WhiteElo", game.white_rating()),
+        ("BlackElo", game.black_rating()),
+    ] {
+        if let Some(rating) = rating {
+            output += &format!("[{} \"{}\"]\n", tag, rating);
+        }
+    }
+
+    let analysed = analyse_game(engine, game, limit)?;
+    let legal_moves = position::legal_moves(game.list_moves());
+    let mut tokens = Vec::new();
+
+    // Black's move number is repeated when white's move is followed by an annotation.
+    let mut repeat_number = false;
+
+    for (ply, legal_move) in legal_moves.iter().enumerate() {
+        let (position, analysis) = &analysed[ply];
+        let (next_position, next_analysis) = &analysed[ply + 1];
+        let color = position.turn();
+
+        match color {
+            Color::White => tokens.push(format!("{}.", ply / 2 + 1)),
+            Color::Black if repeat_number => tokens.push(format!("{}...", ply / 2 + 1)),
+            Color::Black => {}
+        }
+
+        let played = SanPlus::from_move(position.clone(), *legal_move).to_string();
+        tokens.push(played.clone());
+
+        let after = match next_analysis {
+            Some(next_analysis) => Some(next_analysis.eval()),
+            None if next_position.is_checkmate() => None,
+            None => Some(Eval::Centipawns(0)),
+        };
+
+        let judged = match (analysis, after) {
+            (Some(analysis), Some(after)) => Judgement::of(analysis.eval(), after, color)
+                .map(|judgement| (judgement, analysis.best_move())),
+            _ => None,
+        };
+
+        let tokens_before = tokens.len();
+
+        if let Some((judgement, _)) = judged {
+            tokens.push(format!("${}", judgement.nag()));
+        }
+
+        if let Some(after) = after {
+            if judged.is_some() || !blunders_only {
+                tokens.push(eval_comment(after));
+            }
+        }
+
+        if let Some((_, Some(best_move))) = judged {
+            if best_move != played {
+                let number = match color {
+                    Color::White => format!("{}.", ply / 2 + 1),
+                    Color::Black => format!("{}...", ply / 2 + 1),
+                };
+
+                tokens.push(format!("({} {})", number, best_move));
+            }
+        }
+
+        repeat_number = tokens.len() > tokens_before;
+    }
+
+    tokens.push(game.result().to_string());
+
+    output += "\n";
+    output += &wrap_tokens(&tokens);
+    output += "\n";
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::{annotate_game, average_centipawn_loss, eval_graph, find_blunders};
+    use crate::engine::test_utils::fake_engine;
+    use crate::engine::Limit;
+    use crate::eval::{Eval, WinModel};
+    use crate::game::test_utils::*;
+    use crate::game::{Color, GiveEvals};
+    use crate::phase::Phase;
+
+    #[rstest]
+    fn find_blunders_should_flag_large_drops() {
+        let game =
+            parse_game("[White \"a\"]\n[Black \"b\"]\n[Result \"0-1\"]\n1. f3 e5 2. g4 Qh4# 0-1");
+
+        // One search for each position except the final checkmate.
+        let mut engine = fake_engine(&[
+            ("cp 30", "e2e4"),
+            ("cp 60", "e7e5"),
+            ("cp -80", "e2e4"),
+            ("mate 1", "d8h4"),
+        ]);
+
+        let blunders = find_blunders(&mut engine, &game, Limit::Depth(10), 200).unwrap();
+
+        assert_eq!(blunders.len(), 1);
+
+        let blunder = &blunders[0];
+
+        assert_eq!(blunder.move_number(), 2);
+        assert_eq!(blunder.color(), Color::White);
+        assert_eq!(blunder.played(), "g4");
+        assert_eq!(blunder.best_move(), Some("e4"));
+        assert_eq!(blunder.before(), Eval::Centipawns(-80));
+        assert_eq!(blunder.after(), Eval::Mate(-1));
+        assert_eq!(blunder.centipawn_loss(), 920);
+        assert!(blunder.probability_lost(&WinModel::lichess()) > 0.4);
+        assert_eq!(blunder.alternatives().len(), 1);
+        assert_eq!(blunder.alternatives()[0].san(), "e4");
+        assert_eq!(
+            blunder.fen(),
+            "rnbqkbnr/pppp1ppp/8/4p3/8/5P2/PPPPP1PP/RNBQKBNR w KQkq - 0 2"
+        );
+    }
+
+    #[rstest]
+    fn average_centipawn_loss_should_split_by_player_and_phase() {
+        let games = [
+            parse_game("[White \"a\"]\n[Black \"b\"]\n[Result \"1-0\"]\n1. e4 e5 2. Nf3 1-0"),
+            parse_game("[White \"b\"]\n[Black \"a\"]\n[Result \"0-1\"]\n1. e4 c5 0-1"),
+        ];
+
+        // The second game starts the same way, so only its final position needs searching.
+        let mut engine = fake_engine(&[
+            ("cp 30", "e2e4"),
+            ("cp -20", "e7e5"),
+            ("cp 40", "g1f3"),
+            ("cp -10", "b8c6"),
+            ("cp 90", "g1f3"),
+        ]);
+
+        let losses =
+            average_centipawn_loss(&mut engine, &mut games.iter(), Limit::Depth(10)).unwrap();
+
+        assert_eq!(losses.len(), 2);
+
+        let a = &losses[0];
+
+        assert_eq!(a.player(), "a");
+        // e4 loses 10, Nf3 loses 30, and c5 loses 70.
+        assert_eq!(a.overall().moves(), 3);
+        assert_eq!(a.overall().average(), Some(110. / 3.));
+        assert_eq!(a.phase(Phase::Opening).total(), 110);
+        assert_eq!(a.phase(Phase::Endgame).average(), None);
+
+        let b = &losses[1];
+
+        // e5 loses 20, and e4 loses 10.
+        assert_eq!(b.player(), "b");
+        assert_eq!(b.overall().total(), 30);
+        assert_eq!(engine.cached_analyses(), 5);
+    }
+
+    #[rstest]
+    fn annotate_game_should_write_evals_and_nags() {
+        let game = parse_game(
+            "[White \"a\"]\n[Black \"b\"]\n[WhiteElo \"1500\"]\n[Result \"0-1\"]\n\
+             1. f3 e5 2. g4 Qh4# 0-1",
+        );
+
+        let mut engine = fake_engine(&[
+            ("cp 30", "e2e4"),
+            ("cp 60", "e7e5"),
+            ("cp -80", "e2e4"),
+            ("mate 1", "d8h4"),
+        ]);
+
+        let pgn = annotate_game(&mut engine, &game, Limit::Depth(10), false).unwrap();
+
+        assert_eq!(
+            pgn,
+            "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n\
+             [White \"a\"]\n[Black \"b\"]\n[Result \"0-1\"]\n[WhiteElo \"1500\"]\n\n\
+             1. f3 $6 { [%eval -0.60] } (1. e4) 1... e5 { [%eval -0.80] } 2. g4 $4\n\
+             { [%eval #-1] } (2. e4) 2... Qh4# 0-1\n"
+        );
+
+        let evals = parse_game(&pgn).evals().to_vec();
+
+        assert_eq!(
+            evals,
+            vec![
+                Some(Eval::Centipawns(-60)),
+                Some(Eval::Centipawns(-80)),
+                Some(Eval::Mate(-1)),
+                None
+            ]
+        );
+    }
+
+    #[rstest]
+    fn annotate_game_should_only_annotate_blunders_when_asked() {
+        let game =
+            parse_game("[White \"a\"]\n[Black \"b\"]\n[Result \"0-1\"]\n1. e4 e5 2. g4 Qh4 0-1");
+
+        let mut engine = fake_engine(&[
+            ("cp 30", "e2e4"),
+            ("cp -30", "e7e5"),
+            ("cp 30", "g1f3"),
+            ("cp 400", "d8h4"),
+            ("cp -400", "f2f3"),
+        ]);
+
+        let pgn = annotate_game(&mut engine, &game, Limit::Depth(10), true).unwrap();
+
+        assert!(pgn.ends_with("\n1. e4 e5 2. g4 $4 { [%eval -4.00] } (2. Nf3) 2... Qh4 0-1\n"));
+    }
+
+    #[rstest]
+    fn eval_graph_should_use_engine_evals() {
+        let game =
+            parse_game("[White \"a\"]\n[Black \"b\"]\n[Result \"0-1\"]\n1. f3 e5 2. g4 Qh4# 0-1");
+
+        let mut engine = fake_engine(&[
+            ("cp 30", "e2e4"),
+            ("cp 60", "e7e5"),
+            ("cp -80", "e2e4"),
+            ("mate 1", "d8h4"),
+        ]);
+
+        let graph = eval_graph(&mut engine, &game, Limit::Depth(10)).unwrap();
+
+        let evals = graph
+            .points()
+            .iter()
+            .map(|point| point.eval())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            evals,
+            vec![
+                Some(Eval::Centipawns(30)),
+                Some(Eval::Centipawns(-60)),
+                Some(Eval::Centipawns(-80)),
+                Some(Eval::Mate(-1)),
+                None
+            ]
+        );
+    }
+}
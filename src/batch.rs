@@ -0,0 +1,346 @@
+use std::io::{self, BufRead, Write};
+use std::panic;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use shakmaty::Position;
+
+use crate::cancel::CancellationToken;
+use crate::engine::{Engine, Limit};
+use crate::game::ListMoves;
+use crate::moves::Move;
+use crate::position;
+
+/// Limits on how much searching a batch of games may do in total.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Budget {
+    time: Option<Duration>,
+    nodes: Option<u64>,
+    cancellation: Option<CancellationToken>,
+}
+
+impl Budget {
+    /// Creates a budget without any limits.
+    pub fn new() -> Budget {
+        Budget::default()
+    }
+
+    /// Returns this budget limited to `time` spent analysing in total.
+    pub fn with_time(self, time: Duration) -> Budget {
+        Budget {
+            time: Some(time),
+            ..self
+        }
+    }
+
+    /// Returns this budget limited to `nodes` searched across all engines in total.
+    pub fn with_nodes(self, nodes: u64) -> Budget {
+        Budget {
+            nodes: Some(nodes),
+            ..self
+        }
+    }
+
+    /// Returns this budget ended early once `token` is cancelled, as if it had run out.
+    pub fn with_cancellation(self, token: CancellationToken) -> Budget {
+        Budget {
+            cancellation: Some(token),
+            ..self
+        }
+    }
+
+    /// Returns the total time that may be spent analysing, if limited.
+    pub fn time(&self) -> Option<Duration> {
+        self.time
+    }
+
+    /// Returns the total number of nodes that may be searched, if limited.
+    pub fn nodes(&self) -> Option<u64> {
+        self.nodes
+    }
+
+    fn exhausted(&self, elapsed: Duration, nodes: u64) -> bool {
+        matches!(self.time, Some(time) if elapsed >= time)
+            || matches!(self.nodes, Some(limit) if nodes >= limit)
+            || matches!(&self.cancellation, Some(token) if token.is_cancelled())
+    }
+}
+
+/// How far the analysis of a batch of games has got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    games_analysed: usize,
+    games: usize,
+    nodes: u64,
+    elapsed: Duration,
+}
+
+impl Progress {
+    /// Returns the number of games analysed so far.
+    pub fn games_analysed(&self) -> usize {
+        self.games_analysed
+    }
+
+    /// Returns the number of games in the batch.
+    pub fn games(&self) -> usize {
+        self.games
+    }
+
+    /// Returns the number of nodes searched so far across all engines.
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    /// Returns the time spent analysing so far.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
+
+/// State shared between the threads analysing a batch.
+struct Batch<'a, G> {
+    games: &'a [G],
+    limit: Limit,
+    budget: Budget,
+    progress: &'a (dyn Fn(Progress) + Sync),
+    start: Instant,
+    next_game: AtomicUsize,
+    games_analysed: AtomicUsize,
+    nodes: AtomicU64,
+    stopped: AtomicBool,
+    analysed: Mutex<Vec<bool>>,
+}
+
+impl<'a, G> Batch<'a, G> {
+    /// Analyses games with `engine` until there are none left, the budget runs out, or another
+    /// thread fails.
+    fn work<M, R, W>(&self, engine: &mut Engine<R, W>) -> io::Result<()>
+    where
+        M: 'static + Clone + Move,
+        G: ListMoves<M>,
+        R: BufRead,
+        W: Write,
+    {
+        while !self.stopped.load(Ordering::SeqCst) {
+            let index = self.next_game.fetch_add(1, Ordering::SeqCst);
+
+            let game = match self.games.get(index) {
+                Some(game) => game,
+                None => break,
+            };
+
+            for position in position::replay(game.list_moves()) {
+                let nodes = self.nodes.load(Ordering::SeqCst);
+
+                if self.budget.exhausted(self.start.elapsed(), nodes) {
+                    self.stopped.store(true, Ordering::SeqCst);
+                    return Ok(());
+                }
+
+                if position.is_game_over() {
+                    continue;
+                }
+
+                let nodes_before = engine.nodes_searched();
+
+                if let Err(error) = engine.analyse(&position, self.limit) {
+                    self.stopped.store(true, Ordering::SeqCst);
+                    return Err(error);
+                }
+
+                self.nodes
+                    .fetch_add(engine.nodes_searched() - nodes_before, Ordering::SeqCst);
+            }
+
+            self.analysed.lock().unwrap()[index] = true;
+
+            (self.progress)(Progress {
+                games_analysed: self.games_analysed.fetch_add(1, Ordering::SeqCst) + 1,
+                games: self.games.len(),
+                nodes: self.nodes.load(Ordering::SeqCst),
+                elapsed: self.start.elapsed(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Analyses `games` concurrently, with each of `engines` searching positions within `limit` on
+/// its own thread, and `progress` called each time a game is finished. Games are started in
+/// order, and once `budget` runs out no more positions are searched.
+///
+/// Returns whether each game was fully analysed. Afterwards, the first engine's cache holds the
+/// analysis of every position searched, so those games can be looked at with it without searching
+/// again. This is so even when an engine fails, so that the analyses made before it did can still
+/// be saved.
+///
+/// # Errors
+///
+/// Returns the first error met by any of the engines, after the others have stopped.
+///
+/// # Panics
+///
+/// Panics if `engines` is empty.
+pub fn analyse_games<M, G, R, W>(
+    engines: &mut [Engine<R, W>],
+    games: &[G],
+    limit: Limit,
+    budget: Budget,
+    progress: &(dyn Fn(Progress) + Sync),
+) -> io::Result<Vec<bool>>
+where
+    M: 'static + Clone + Move,
+    G: ListMoves<M> + Sync,
+    R: BufRead + Send,
+    W: Write + Send,
+{
+    assert!(!engines.is_empty(), "At least one engine is needed");
+
+    let batch = Batch {
+        games,
+        limit,
+        budget,
+        progress,
+        start: Instant::now(),
+        next_game: AtomicUsize::new(0),
+        games_analysed: AtomicUsize::new(0),
+        nodes: AtomicU64::new(0),
+        stopped: AtomicBool::new(false),
+        analysed: Mutex::new(vec![false; games.len()]),
+    };
+
+    let results = thread::scope(|scope| {
+        let handles = engines
+            .iter_mut()
+            .map(|engine| {
+                let batch = &batch;
+                scope.spawn(move || batch.work(engine))
+            })
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|payload| panic::resume_unwind(payload))
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let (first, others) = engines.split_first_mut().unwrap();
+
+    for other in others {
+        first.merge_cache(other);
+    }
+
+    results.into_iter().collect::<io::Result<()>>()?;
+
+    Ok(batch.analysed.into_inner().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use std::sync::Mutex;
+
+    use super::{analyse_games, Budget};
+    use crate::cancel::CancellationToken;
+    use crate::engine::test_utils::{fake_engine, FakeEngine};
+    use crate::engine::Limit;
+    use crate::game::test_utils::*;
+    use crate::game::Game;
+    use crate::AlgebraicMove;
+
+    #[fixture]
+    fn games() -> Vec<Game<AlgebraicMove>> {
+        vec![
+            parse_game("[White \"a\"]\n[Black \"b\"]\n[Result \"1-0\"]\n1. e4 e5 1-0"),
+            parse_game("[White \"a\"]\n[Black \"b\"]\n[Result \"0-1\"]\n1. d4 d5 0-1"),
+            parse_game("[White \"a\"]\n[Black \"b\"]\n[Result \"1-0\"]\n1. c4 1-0"),
+        ]
+    }
+
+    /// Creates an engine with enough scripted searches for any of the games.
+    fn engine() -> FakeEngine {
+        fake_engine(&[("cp 20", "e2e4"); 8])
+    }
+
+    #[rstest]
+    fn analyse_games_should_share_work_between_engines(games: Vec<Game<AlgebraicMove>>) {
+        let mut engines = vec![engine(), engine()];
+        let finished = Mutex::new(Vec::new());
+
+        let analysed = analyse_games(
+            &mut engines,
+            &games,
+            Limit::Depth(10),
+            Budget::new(),
+            &|progress| finished.lock().unwrap().push(progress.games_analysed()),
+        )
+        .unwrap();
+
+        assert_eq!(analysed, vec![true, true, true]);
+        assert_eq!(finished.into_inner().unwrap(), vec![1, 2, 3]);
+        // The starting position is shared between all three games.
+        assert_eq!(engines[0].cached_analyses(), 6);
+    }
+
+    #[rstest]
+    fn analyse_games_should_keep_analyses_when_an_engine_fails(games: Vec<Game<AlgebraicMove>>) {
+        // The first engine has no searches scripted, so it stops responding on its first.
+        let mut engines = vec![fake_engine(&[]), engine()];
+
+        let result = analyse_games(
+            &mut engines,
+            &games,
+            Limit::Depth(10),
+            Budget::new(),
+            &|_| {},
+        );
+
+        assert!(result.is_err());
+        assert_eq!(engines[0].cached_analyses(), engines[1].cached_analyses());
+    }
+
+    #[rstest]
+    fn analyse_games_should_stop_when_budget_runs_out(games: Vec<Game<AlgebraicMove>>) {
+        let mut engines = vec![engine()];
+
+        // Each search takes 1000 nodes, so the budget runs out after the first game.
+        let analysed = analyse_games(
+            &mut engines,
+            &games,
+            Limit::Depth(10),
+            Budget::new().with_nodes(2500),
+            &|_| {},
+        )
+        .unwrap();
+
+        assert_eq!(analysed, vec![true, false, false]);
+        assert_eq!(engines[0].nodes_searched(), 3000);
+    }
+
+    #[rstest]
+    fn analyse_games_should_stop_when_cancelled(games: Vec<Game<AlgebraicMove>>) {
+        let mut engines = vec![engine()];
+        let token = CancellationToken::new();
+
+        // Cancelled once the first game is finished, as Ctrl-C would be part way through.
+        let analysed = analyse_games(
+            &mut engines,
+            &games,
+            Limit::Depth(10),
+            Budget::new().with_cancellation(token.clone()),
+            &|_| token.cancel(),
+        )
+        .unwrap();
+
+        assert_eq!(analysed, vec![true, false, false]);
+        assert_eq!(engines[0].nodes_searched(), 3000);
+    }
+}
@@ -0,0 +1,71 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// A handle for asking long-running work to stop early, such as when the user presses Ctrl-C.
+/// Clones of a token share whether it has been cancelled, so one can be kept by whatever decides
+/// to stop while the others are checked by the work itself between steps.
+///
+/// Cancelling is cooperative: work that checks the token stops at the next convenient point and
+/// keeps what it finished, rather than being interrupted part way through a step.
+///
+/// ```
+/// use chesshound::cancel::CancellationToken;
+///
+/// let token = CancellationToken::new();
+/// let worker = token.clone();
+///
+/// token.cancel();
+/// assert!(worker.is_cancelled());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Creates a token that hasn't been cancelled.
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Asks the work checking this token, or any of its clones, to stop. Only sets a flag, so
+    /// it's safe to call from a signal handler.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns whether this token, or any of its clones, has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Tokens are equal when they're clones of each other, so cancelling one cancels the other.
+impl PartialEq for CancellationToken {
+    fn eq(&self, other: &CancellationToken) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for CancellationToken {}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::CancellationToken;
+
+    #[rstest]
+    fn cancelling_a_token_should_cancel_its_clones_only() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        let other = CancellationToken::new();
+
+        assert!(!clone.is_cancelled());
+        assert_eq!(token, clone);
+        assert_ne!(token, other);
+
+        clone.cancel();
+
+        assert!(token.is_cancelled());
+        assert!(!other.is_cancelled());
+    }
+}
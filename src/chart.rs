@@ -0,0 +1,291 @@
+/// Colors given to the series of a chart, in order.
+const PALETTE: [&str; 5] = ["#1f77b4", "#d62728", "#2ca02c", "#ff7f0e", "#9467bd"];
+
+/// Space around the plotting area, in pixels, for the title and the axis labels.
+const MARGIN_LEFT: f64 = 50.;
+const MARGIN_RIGHT: f64 = 20.;
+const MARGIN_TOP: f64 = 30.;
+const MARGIN_BOTTOM: f64 = 30.;
+
+/// The number of labelled ticks on each axis.
+const TICKS: usize = 5;
+
+/// A named line of points in a chart.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    name: String,
+    points: Vec<(f64, f64)>,
+}
+
+impl Series {
+    /// Returns the name of the series, as shown in the legend.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the points of the series as `(x, y)` pairs, in the order they're joined.
+    pub fn points(&self) -> &[(f64, f64)] {
+        &self.points
+    }
+}
+
+/// A line chart of one or more series sharing their axes, drawn as SVG.
+pub struct LineChart {
+    title: String,
+    series: Vec<Series>,
+    y_range: Option<(f64, f64)>,
+    x_format: Box<dyn Fn(f64) -> String>,
+    y_format: Box<dyn Fn(f64) -> String>,
+}
+
+impl LineChart {
+    /// Creates a chart titled `title` with no series, labelling both axes with rounded numbers.
+    pub fn new(title: &str) -> LineChart {
+        LineChart {
+            title: String::from(title),
+            series: Vec::new(),
+            y_range: None,
+            x_format: Box::new(|x| format!("{:.0}", x)),
+            y_format: Box::new(|y| format!("{:.0}", y)),
+        }
+    }
+
+    /// Returns the chart with a series named `name` joining `points`, given as `(x, y)` pairs.
+    pub fn with_series(mut self, name: &str, points: Vec<(f64, f64)>) -> LineChart {
+        self.series.push(Series {
+            name: String::from(name),
+            points,
+        });
+
+        self
+    }
+
+    /// Returns the chart with its y axis running from `min` to `max`, instead of fitting the
+    /// points.
+    pub fn with_y_range(mut self, min: f64, max: f64) -> LineChart {
+        self.y_range = Some((min, max));
+        self
+    }
+
+    /// Returns the chart with the ticks on its x axis labelled by `format`, such as to show
+    /// numbers of days as dates.
+    pub fn with_x_format(mut self, format: impl Fn(f64) -> String + 'static) -> LineChart {
+        self.x_format = Box::new(format);
+        self
+    }
+
+    /// Returns the chart with the ticks on its y axis labelled by `format`, such as to show
+    /// fractions as percentages.
+    pub fn with_y_format(mut self, format: impl Fn(f64) -> String + 'static) -> LineChart {
+        self.y_format = Box::new(format);
+        self
+    }
+
+    /// Returns the series of the chart.
+    pub fn series(&self) -> &[Series] {
+        &self.series
+    }
+
+    /// Returns the chart as an SVG image `width` by `height` pixels, with a title, labelled axes,
+    /// and a legend when there's more than one series.
+    pub fn to_svg(&self, width: u32, height: u32) -> String {
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+             viewBox=\"0 0 {width} {height}\" font-family=\"sans-serif\" font-size=\"11\">{}</svg>",
+            self.svg_body(width as f64, height as f64),
+            width = width,
+            height = height
+        )
+    }
+
+    /// Returns the elements drawing the chart within `width` by `height` pixels.
+    fn svg_body(&self, width: f64, height: f64) -> String {
+        let points = self.series.iter().flat_map(|series| series.points.iter());
+        let (x_min, x_max) = spread(points.clone().map(|(x, _)| *x));
+        let (y_min, y_max) = self
+            .y_range
+            .unwrap_or_else(|| spread(points.map(|(_, y)| *y)));
+
+        let plot_width = width - MARGIN_LEFT - MARGIN_RIGHT;
+        let plot_height = height - MARGIN_TOP - MARGIN_BOTTOM;
+        let to_x = |x: f64| MARGIN_LEFT + (x - x_min) / (x_max - x_min) * plot_width;
+        let to_y = |y: f64| MARGIN_TOP + (y_max - y) / (y_max - y_min) * plot_height;
+
+        let mut output = format!(
+            "<text x=\"{:.1}\" y=\"18\" text-anchor=\"middle\" font-size=\"14\">{}</text>",
+            width / 2.,
+            escape(&self.title)
+        );
+
+        let mut previous_x_label = None;
+
+        for tick in 0..TICKS {
+            let fraction = tick as f64 / (TICKS - 1) as f64;
+            let y = y_min + (y_max - y_min) * fraction;
+            let x = x_min + (x_max - x_min) * fraction;
+
+            output += &format!(
+                "<line x1=\"{left:.1}\" y1=\"{y:.1}\" x2=\"{right:.1}\" y2=\"{y:.1}\" stroke=\"#ddd\"/>\
+                 <text x=\"{label:.1}\" y=\"{y:.1}\" text-anchor=\"end\" dominant-baseline=\"middle\">{}</text>",
+                escape(&(self.y_format)(y)),
+                left = MARGIN_LEFT,
+                right = width - MARGIN_RIGHT,
+                label = MARGIN_LEFT - 5.,
+                y = to_y(y)
+            );
+
+            // Ticks close together can round to the same label, which is only shown once.
+            let x_label = (self.x_format)(x);
+
+            if previous_x_label.as_ref() != Some(&x_label) {
+                output += &format!(
+                    "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>",
+                    to_x(x),
+                    height - MARGIN_BOTTOM + 15.,
+                    escape(&x_label)
+                );
+                previous_x_label = Some(x_label);
+            }
+        }
+
+        output += &format!(
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"none\" stroke=\"#999\"/>",
+            MARGIN_LEFT, MARGIN_TOP, plot_width, plot_height
+        );
+
+        for (index, series) in self.series.iter().enumerate() {
+            let color = PALETTE[index % PALETTE.len()];
+            let coordinates = series
+                .points
+                .iter()
+                .map(|(x, y)| (to_x(*x), to_y(*y)))
+                .collect::<Vec<_>>();
+
+            output += &format!(
+                "<polyline fill=\"none\" stroke=\"{}\" stroke-width=\"2\" points=\"{}\"/>",
+                color,
+                coordinates
+                    .iter()
+                    .map(|(x, y)| format!("{:.1},{:.1}", x, y))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            );
+
+            for (x, y) in coordinates {
+                output += &format!(
+                    "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"2.5\" fill=\"{}\"/>",
+                    x, y, color
+                );
+            }
+
+            if self.series.len() > 1 {
+                let y = MARGIN_TOP + 12. + 14. * index as f64;
+
+                output += &format!(
+                    "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"10\" height=\"10\" fill=\"{}\"/>\
+                     <text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\" dominant-baseline=\"middle\">{}</text>",
+                    width - MARGIN_RIGHT - 15.,
+                    y - 5.,
+                    color,
+                    width - MARGIN_RIGHT - 20.,
+                    y,
+                    escape(&series.name)
+                );
+            }
+        }
+
+        output
+    }
+}
+
+/// Returns `charts` drawn one above the other as a single SVG image, each `width` by `height`
+/// pixels.
+pub fn stack_svg(charts: &[LineChart], width: u32, height: u32) -> String {
+    let total_height = height as usize * charts.len();
+
+    let panels = charts
+        .iter()
+        .enumerate()
+        .map(|(index, chart)| {
+            format!(
+                "<g transform=\"translate(0 {})\">{}</g>",
+                index as u32 * height,
+                chart.svg_body(width as f64, height as f64)
+            )
+        })
+        .collect::<String>();
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+         viewBox=\"0 0 {width} {height}\" font-family=\"sans-serif\" font-size=\"11\">{}</svg>",
+        panels,
+        width = width,
+        height = total_height
+    )
+}
+
+/// Returns the smallest and largest of `values`, spread apart when they're equal or missing so
+/// that they can be scaled between.
+fn spread(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    let (min, max) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| {
+        (min.min(value), max.max(value))
+    });
+
+    if min > max {
+        (0., 1.)
+    } else if min == max {
+        (min - 1., max + 1.)
+    } else {
+        (min, max)
+    }
+}
+
+/// Returns `text` with the characters XML treats specially escaped.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::{stack_svg, LineChart};
+
+    #[rstest]
+    fn to_svg_should_scale_points_into_the_plot() {
+        let svg = LineChart::new("Score & rating")
+            .with_series("score", vec![(0., 0.), (1., 0.5), (2., 1.)])
+            .with_y_range(0., 1.)
+            .with_y_format(|y| format!("{:.0}%", y * 100.))
+            .to_svg(270, 160);
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("Score &amp; rating"));
+        assert!(svg.contains("points=\"50.0,130.0 150.0,80.0 250.0,30.0\""));
+        assert!(svg.contains(">50%</text>"));
+        // A single series needs no legend.
+        assert!(!svg.contains(">score</text>"));
+    }
+
+    #[rstest]
+    fn to_svg_should_handle_charts_without_spread() {
+        let svg = LineChart::new("Empty")
+            .with_series("a", vec![(3., 5.)])
+            .with_series("b", Vec::new())
+            .to_svg(270, 160);
+
+        assert!(svg.contains("points=\"150.0,80.0\""));
+        assert!(svg.contains(">a</text>") && svg.contains(">b</text>"));
+    }
+
+    #[rstest]
+    fn stack_svg_should_place_charts_below_each_other() {
+        let svg = stack_svg(&[LineChart::new("One"), LineChart::new("Two")], 300, 200);
+
+        assert!(svg.contains("height=\"400\""));
+        assert!(svg.contains("translate(0 200)"));
+    }
+}
@@ -0,0 +1,349 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// A structured representation of a PGN `TimeControl` tag, such as `180+2`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimeControl {
+    base: Duration,
+    increment: Duration,
+}
+
+impl TimeControl {
+    /// Creates a new `TimeControl` with the given starting time and increment per move.
+    pub fn new(base: Duration, increment: Duration) -> TimeControl {
+        TimeControl { base, increment }
+    }
+
+    /// Returns the time each player starts the game with.
+    pub fn base(&self) -> Duration {
+        self.base
+    }
+
+    /// Returns the time added to a player's clock after each of their moves.
+    pub fn increment(&self) -> Duration {
+        self.increment
+    }
+
+    /// Returns the speed category this time control falls into.
+    pub fn speed(&self) -> Speed {
+        Speed::from(*self)
+    }
+}
+
+/// The speed categories that online chess sites group time controls into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Speed {
+    Bullet,
+    Blitz,
+    Rapid,
+    Classical,
+}
+
+impl From<TimeControl> for Speed {
+    /// Categorizes time controls the same way lichess does, by the expected length of a 40 move
+    /// game: under 3 minutes is bullet, under 8 blitz, under 25 rapid, and anything longer
+    /// classical.
+    fn from(time_control: TimeControl) -> Speed {
+        let estimated = time_control.base().as_secs() + 40 * time_control.increment().as_secs();
+
+        if estimated < 180 {
+            Speed::Bullet
+        } else if estimated < 480 {
+            Speed::Blitz
+        } else if estimated < 1500 {
+            Speed::Rapid
+        } else {
+            Speed::Classical
+        }
+    }
+}
+
+impl fmt::Display for Speed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Speed::Bullet => "bullet",
+            Speed::Blitz => "blitz",
+            Speed::Rapid => "rapid",
+            Speed::Classical => "classical",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Speed {
+    type Err = TimeControlError;
+
+    /// Parses the lowercase names of speed categories, such as `blitz`.
+    fn from_str(speed: &str) -> Result<Speed, TimeControlError> {
+        match speed {
+            "bullet" => Ok(Speed::Bullet),
+            "blitz" => Ok(Speed::Blitz),
+            "rapid" => Ok(Speed::Rapid),
+            "classical" => Ok(Speed::Classical),
+            _ => Err(TimeControlError::new(format!("Invalid speed: {}", speed))),
+        }
+    }
+}
+
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}+{}", self.base.as_secs(), self.increment.as_secs())
+    }
+}
+
+/// Errors related to time controls that can't be represented by `TimeControl`.
+#[derive(Debug, PartialEq, Eq, Clone, thiserror::Error)]
+#[error("{message}")]
+pub struct TimeControlError {
+    message: String,
+}
+
+impl TimeControlError {
+    pub fn new(message: String) -> TimeControlError {
+        TimeControlError { message }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl FromStr for TimeControl {
+    type Err = TimeControlError;
+
+    /// Parses time controls of the form `base` or `base+increment`, both given in seconds.
+    /// Unknown (`-`, `?`), move-based (`40/7200`), and correspondence (`1/259200`) time controls
+    /// are rejected.
+    fn from_str(time_control: &str) -> Result<TimeControl, TimeControlError> {
+        let (base, increment) = match time_control.find('+') {
+            Some(index) => (&time_control[..index], &time_control[index + 1..]),
+            None => (time_control, "0"),
+        };
+
+        let parse_seconds = |seconds: &str| {
+            seconds.parse::<u64>().map_err(|_| {
+                TimeControlError::new(format!(
+                    "Invalid time control: {time_control}",
+                    time_control = time_control
+                ))
+            })
+        };
+
+        Ok(TimeControl::new(
+            Duration::from_secs(parse_seconds(base)?),
+            Duration::from_secs(parse_seconds(increment)?),
+        ))
+    }
+}
+
+/// Returns the clock time given by a `[%clk h:mm:ss]` command within a PGN comment, if any.
+pub fn parse_clock_comment(comment: &str) -> Option<Duration> {
+    let start = comment.find("[%clk ")? + "[%clk ".len();
+    let end = start + comment[start..].find(']')?;
+
+    parse_clock(comment[start..end].trim())
+}
+
+/// Formats `clock` as a PGN `[%clk h:mm:ss]` command, with tenths of a second when there are
+/// any, as lichess writes them.
+pub fn clock_comment(clock: Duration) -> String {
+    let seconds = clock.as_secs();
+    let tenths = clock.subsec_millis() / 100;
+
+    let mut comment = format!(
+        "[%clk {}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+
+    if tenths > 0 {
+        comment += &format!(".{}", tenths);
+    }
+
+    comment + "]"
+}
+
+/// Parses clock times of the form `h:mm:ss`, where seconds may include a fractional part.
+fn parse_clock(clock: &str) -> Option<Duration> {
+    let mut parts = clock.rsplit(':');
+
+    let seconds = parts.next()?.parse::<f64>().ok()?;
+    let minutes = match parts.next() {
+        Some(minutes) => minutes.parse::<u64>().ok()?,
+        None => 0,
+    };
+    let hours = match parts.next() {
+        Some(hours) => hours.parse::<u64>().ok()?,
+        None => 0,
+    };
+
+    if parts.next().is_some() || seconds < 0. {
+        return None;
+    }
+
+    Some(Duration::from_secs(hours * 3600 + minutes * 60) + Duration::from_secs_f64(seconds))
+}
+
+/// Returns the time spent on each ply, given the clock time remaining after each ply.
+///
+/// The time spent on a move is the difference between the mover's clock after their previous
+/// move (plus increment) and their clock after this one. Each side's first move can only be
+/// measured when `time_control` is known, and moves without a recorded clock on either end are
+/// `None`.
+pub fn move_times(
+    clocks: &[Option<Duration>],
+    time_control: Option<TimeControl>,
+) -> Vec<Option<Duration>> {
+    let increment = time_control.map_or(Duration::from_secs(0), |tc| tc.increment());
+
+    clocks
+        .iter()
+        .enumerate()
+        .map(|(ply, clock)| {
+            let previous_clock = if ply < 2 {
+                time_control.map(|tc| tc.base())
+            } else {
+                clocks[ply - 2]
+            };
+
+            match (previous_clock, clock) {
+                (Some(previous_clock), Some(clock)) => {
+                    Some((previous_clock + increment).saturating_sub(*clock))
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use std::time::Duration;
+
+    use super::{clock_comment, move_times, parse_clock_comment, Speed, TimeControl};
+
+    fn seconds(seconds: u64) -> Duration {
+        Duration::from_secs(seconds)
+    }
+
+    #[rstest(
+        time_control,
+        expected_base,
+        expected_increment,
+        case("180+2", 180, 2),
+        case("600", 600, 0),
+        case("5400+30", 5400, 30)
+    )]
+    fn time_control_should_parse_base_and_increment(
+        time_control: &str,
+        expected_base: u64,
+        expected_increment: u64,
+    ) {
+        let time_control = time_control.parse::<TimeControl>().unwrap();
+
+        assert_eq!(time_control.base(), seconds(expected_base));
+        assert_eq!(time_control.increment(), seconds(expected_increment));
+    }
+
+    #[rstest(
+        time_control,
+        case("-"),
+        case("?"),
+        case("1/259200"),
+        case("40/7200:3600"),
+        case("")
+    )]
+    fn time_control_should_reject_unsupported_formats(time_control: &str) {
+        assert!(time_control.parse::<TimeControl>().is_err());
+    }
+
+    #[rstest(
+        time_control,
+        expected_speed,
+        case("60+0", Speed::Bullet),
+        case("120+1", Speed::Bullet),
+        case("180+0", Speed::Blitz),
+        case("180+2", Speed::Blitz),
+        case("600+0", Speed::Rapid),
+        case("900+10", Speed::Rapid),
+        case("1800+0", Speed::Classical)
+    )]
+    fn time_controls_should_have_correct_speed(time_control: &str, expected_speed: Speed) {
+        let time_control = time_control.parse::<TimeControl>().unwrap();
+
+        assert_eq!(time_control.speed(), expected_speed);
+    }
+
+    #[rstest(
+        speed,
+        case(Speed::Bullet),
+        case(Speed::Blitz),
+        case(Speed::Rapid),
+        case(Speed::Classical)
+    )]
+    fn speeds_should_parse_their_names(speed: Speed) {
+        assert_eq!(speed.to_string().parse::<Speed>(), Ok(speed));
+    }
+
+    #[rstest(
+        comment,
+        expected_clock,
+        case(" [%clk 0:03:00] ", Some(Duration::from_secs(180))),
+        case("[%eval 0.17] [%clk 1:02:03]", Some(Duration::from_secs(3723))),
+        case("[%clk 0:00:09.5]", Some(Duration::from_millis(9500))),
+        case("Good move!", None),
+        case("[%clk 0:0x:00]", None)
+    )]
+    fn clock_comments_should_parse(comment: &str, expected_clock: Option<Duration>) {
+        assert_eq!(parse_clock_comment(comment), expected_clock);
+    }
+
+    #[rstest(
+        clock,
+        expected_comment,
+        case(Duration::from_secs(3723), "[%clk 1:02:03]"),
+        case(Duration::from_millis(9570), "[%clk 0:00:09.5]")
+    )]
+    fn clock_comments_should_be_written(clock: Duration, expected_comment: &str) {
+        assert_eq!(clock_comment(clock), expected_comment);
+    }
+
+    #[rstest]
+    fn move_times_should_account_for_increment() {
+        let clocks = vec![
+            Some(seconds(180)),
+            Some(seconds(178)),
+            Some(seconds(175)),
+            None,
+            Some(seconds(160)),
+        ];
+
+        let times = move_times(&clocks, Some(TimeControl::new(seconds(180), seconds(2))));
+
+        assert_eq!(
+            times,
+            vec![
+                Some(seconds(2)),
+                Some(seconds(4)),
+                Some(seconds(7)),
+                None,
+                Some(seconds(17))
+            ]
+        );
+    }
+
+    #[rstest]
+    fn move_times_should_skip_first_moves_without_time_control() {
+        let clocks = vec![Some(seconds(60)), Some(seconds(59)), Some(seconds(55))];
+
+        assert_eq!(
+            move_times(&clocks, None),
+            vec![None, None, Some(seconds(5))]
+        );
+    }
+}
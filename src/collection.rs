@@ -0,0 +1,349 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::iter::FromIterator;
+use std::slice;
+use std::vec;
+
+use chrono::NaiveDate;
+
+use crate::filter::GameFilter;
+use crate::game::{Color, GiveDate, GivePlayers, GiveRatings, ListMoves};
+use crate::move_tree::MoveTree;
+use crate::moves::Move;
+use crate::openings::{Opening, Openings};
+
+/// What to order the games of a collection by with [`sort_by`](GameCollection::sort_by).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SortKey {
+    /// The date and start time the games were played, oldest first.
+    Date,
+    /// The average rating of the players, lowest first, counting only known ratings.
+    Rating,
+    /// The number of moves played, shortest first.
+    Length,
+    /// The order the games were given in, which leaves them as they are.
+    Source,
+}
+
+/// A set of games with chainable operations for narrowing, splitting, and ordering them. Each
+/// operation takes the collection and returns the games it keeps, so collections can go
+/// straight into [`MoveTree::new`](crate::MoveTree::new) or the functions of
+/// [`stats`](crate::stats) once they hold the games wanted.
+///
+/// ```
+/// use chesshound::collection::GameCollection;
+/// use chesshound::parsing::read_games;
+///
+/// let pgn = "[White \"alice\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n[Date \"2021.06.01\"]\n\n\
+///            1. e4 e5 1-0\n\n\
+///            [White \"bob\"]\n[Black \"alice\"]\n[Result \"0-1\"]\n[Date \"2021.05.01\"]\n\n\
+///            1. d4 d5 0-1\n";
+/// let games = GameCollection::from(read_games(pgn.as_bytes()).unwrap());
+///
+/// let (as_white, as_black) = games
+///     .filter_player("alice")
+///     .sort_by_date()
+///     .partition_by_color("alice");
+///
+/// assert_eq!((as_white.len(), as_black.len()), (1, 1));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameCollection<G> {
+    games: Vec<G>,
+}
+
+impl<G> GameCollection<G> {
+    /// Creates a collection of `games`.
+    pub fn new(games: Vec<G>) -> GameCollection<G> {
+        GameCollection { games }
+    }
+
+    /// Returns the number of games in the collection.
+    pub fn len(&self) -> usize {
+        self.games.len()
+    }
+
+    /// Returns whether the collection has no games.
+    pub fn is_empty(&self) -> bool {
+        self.games.is_empty()
+    }
+
+    /// Returns an iterator over the games in the collection, in order.
+    pub fn iter(&self) -> slice::Iter<'_, G> {
+        self.games.iter()
+    }
+
+    /// Returns the games in the collection.
+    pub fn games(&self) -> &[G] {
+        &self.games
+    }
+
+    /// Returns the games in the collection as a `Vec`.
+    pub fn into_vec(self) -> Vec<G> {
+        self.games
+    }
+
+    /// Returns the games for which `predicate` is true.
+    pub fn retain(mut self, predicate: impl FnMut(&G) -> bool) -> GameCollection<G> {
+        self.games.retain(predicate);
+        self
+    }
+
+    /// Returns the games arranged in a move tree.
+    pub fn into_move_tree<M>(self) -> MoveTree<M, G>
+    where
+        M: Clone + Move + Eq + Hash,
+        G: ListMoves<M>,
+    {
+        MoveTree::new(self.games)
+    }
+}
+
+impl<G: 'static> GameCollection<G> {
+    /// Returns the games `filter` includes.
+    pub fn filter(self, filter: &GameFilter<G>) -> GameCollection<G> {
+        self.retain(|game| filter.matches(game))
+    }
+}
+
+impl<G: GivePlayers> GameCollection<G> {
+    /// Returns the games `player` played in, matching their name ignoring case.
+    pub fn filter_player(self, player: &str) -> GameCollection<G> {
+        self.retain(|game| game.color_of(player).is_some())
+    }
+
+    /// Splits the games `player` played in into the games they had white in and the games they
+    /// had black in. Games they didn't play in are dropped.
+    pub fn partition_by_color(self, player: &str) -> (GameCollection<G>, GameCollection<G>) {
+        let (as_white, as_black) = self
+            .games
+            .into_iter()
+            .filter_map(|game| Some((game.color_of(player)?, game)))
+            .partition::<Vec<_>, _>(|(color, _)| *color == Color::White);
+
+        (
+            as_white.into_iter().map(|(_, game)| game).collect(),
+            as_black.into_iter().map(|(_, game)| game).collect(),
+        )
+    }
+}
+
+impl<G: GiveDate> GameCollection<G> {
+    /// Returns the games played between `since` and `until` inclusive. A bound of `None` leaves
+    /// that side of the range open, and games with an unknown date are left out, as with
+    /// [`GameFilter::dates`].
+    pub fn filter_date_range(
+        self,
+        since: Option<NaiveDate>,
+        until: Option<NaiveDate>,
+    ) -> GameCollection<G> {
+        self.retain(|game| match game.date() {
+            Some(date) => {
+                date >= since.unwrap_or(NaiveDate::MIN) && date <= until.unwrap_or(NaiveDate::MAX)
+            }
+            None => false,
+        })
+    }
+
+    /// Returns the games in the order they were played, by date and start time. Games with an
+    /// unknown date come first, and games played at the same time keep their order.
+    pub fn sort_by_date(mut self) -> GameCollection<G> {
+        self.games.sort_by_key(|game| (game.date(), game.time()));
+        self
+    }
+}
+
+impl<G> GameCollection<G> {
+    /// Splits the games by the opening the embedded opening table classifies them under, with
+    /// the most played openings first. Games that never reach an opening in the table are
+    /// grouped under `None`, which comes last.
+    pub fn group_by_opening<M>(self) -> Vec<(Option<&'static Opening>, GameCollection<G>)>
+    where
+        M: 'static + Clone + Move,
+        G: ListMoves<M>,
+    {
+        let mut groups: Vec<(Option<&'static Opening>, GameCollection<G>)> = Vec::new();
+        let mut indices = HashMap::new();
+
+        for game in self.games {
+            let opening = Openings::embedded().classify(&game);
+            let index = *indices
+                .entry(opening.map(|opening| opening.to_string()))
+                .or_insert_with(|| {
+                    groups.push((opening, GameCollection::new(Vec::new())));
+                    groups.len() - 1
+                });
+
+            groups[index].1.games.push(game);
+        }
+
+        groups.sort_by_key(|(opening, games)| {
+            (
+                opening.is_none(),
+                Reverse(games.len()),
+                opening.map(|opening| opening.to_string()),
+            )
+        });
+
+        groups
+    }
+}
+
+impl<G: GiveDate + GiveRatings> GameCollection<G> {
+    /// Returns the games ordered by `key`. Games missing what they're ordered by, such as a date
+    /// or both ratings, come first, and games that are equal by `key` keep their order.
+    pub fn sort_by<M>(mut self, key: SortKey) -> GameCollection<G>
+    where
+        M: 'static + Clone + Move,
+        G: ListMoves<M>,
+    {
+        match key {
+            SortKey::Date => return self.sort_by_date(),
+            SortKey::Rating => self.games.sort_by_key(|game| game.average_rating()),
+            SortKey::Length => self
+                .games
+                .sort_by_cached_key(|game| game.list_moves().count()),
+            SortKey::Source => (),
+        }
+
+        self
+    }
+}
+
+impl<G> Default for GameCollection<G> {
+    fn default() -> GameCollection<G> {
+        GameCollection::new(Vec::new())
+    }
+}
+
+impl<G> From<Vec<G>> for GameCollection<G> {
+    fn from(games: Vec<G>) -> GameCollection<G> {
+        GameCollection::new(games)
+    }
+}
+
+impl<G> FromIterator<G> for GameCollection<G> {
+    fn from_iter<I: IntoIterator<Item = G>>(games: I) -> GameCollection<G> {
+        GameCollection::new(games.into_iter().collect())
+    }
+}
+
+impl<G> Extend<G> for GameCollection<G> {
+    fn extend<I: IntoIterator<Item = G>>(&mut self, games: I) {
+        self.games.extend(games);
+    }
+}
+
+impl<G> IntoIterator for GameCollection<G> {
+    type Item = G;
+    type IntoIter = vec::IntoIter<G>;
+
+    fn into_iter(self) -> vec::IntoIter<G> {
+        self.games.into_iter()
+    }
+}
+
+impl<'a, G> IntoIterator for &'a GameCollection<G> {
+    type Item = &'a G;
+    type IntoIter = slice::Iter<'a, G>;
+
+    fn into_iter(self) -> slice::Iter<'a, G> {
+        self.games.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use chrono::NaiveDate;
+
+    use super::{GameCollection, SortKey};
+    use crate::game::test_utils::*;
+    use crate::game::{Game, GivePlayers};
+    use crate::stats::statistic::{ResultRates, Statistic};
+    use crate::AlgebraicMove;
+
+    #[fixture]
+    fn games() -> GameCollection<Game<AlgebraicMove>> {
+        vec![
+            dated_game("alice", "bob", "1-0", "2021.06.01", "1. e4 c5"),
+            dated_game("Bob", "Alice", "1-0", "2021.03.01", "1. e4 c5"),
+            dated_game("carol", "bob", "1-0", "2021.04.01", "1. d4 d5"),
+            dated_game("alice", "carol", "1-0", "????.??.??", ""),
+        ]
+        .into()
+    }
+
+    #[rstest]
+    fn collections_should_chain_into_stats(games: GameCollection<Game<AlgebraicMove>>) {
+        let alice = games
+            .filter_player("alice")
+            .filter_date_range(NaiveDate::from_ymd_opt(2021, 1, 1), None)
+            .sort_by_date();
+
+        assert_eq!(
+            alice
+                .iter()
+                .map(|game| game.white_player().unwrap())
+                .collect::<Vec<_>>(),
+            vec!["Bob", "alice"]
+        );
+
+        let mut results = ResultRates::new();
+        alice.iter().for_each(|game| results.accumulate(game));
+        assert_eq!(results.games(), 2);
+
+        let (as_white, as_black) = alice.partition_by_color("alice");
+        assert_eq!((as_white.len(), as_black.len()), (1, 1));
+    }
+
+    #[rstest(
+        key,
+        expected,
+        case(SortKey::Date, vec!["alice", "Bob", "carol", "alice"]),
+        case(SortKey::Rating, vec!["carol", "alice", "alice", "Bob"]),
+        case(SortKey::Length, vec!["alice", "alice", "Bob", "carol"]),
+        case(SortKey::Source, vec!["alice", "Bob", "carol", "alice"])
+    )]
+    fn sort_by_should_order_games_stably(key: SortKey, expected: Vec<&str>) {
+        let games = [
+            ("alice", "2021.06.01", "1500", "1. e4 c5"),
+            ("Bob", "2021.03.01", "1900", "1. e4 c5 2. Nf3"),
+            ("carol", "2021.04.01", "?", "1. d4 d5 2. c4 e6"),
+            ("alice", "????.??.??", "1500", ""),
+        ]
+        .iter()
+        .map(|(white, date, elo, moves)| {
+            parse_game(&format!(
+                "[White \"{}\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n[Date \"{}\"]\n\
+                 [WhiteElo \"{}\"]\n{} 1-0",
+                white, date, elo, moves
+            ))
+        })
+        .collect::<GameCollection<Game<AlgebraicMove>>>();
+
+        assert_eq!(
+            games
+                .sort_by(key)
+                .iter()
+                .map(|game| game.white_player().unwrap())
+                .collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[rstest]
+    fn group_by_opening_should_put_most_played_first(games: GameCollection<Game<AlgebraicMove>>) {
+        let groups = games
+            .group_by_opening()
+            .into_iter()
+            .map(|(opening, games)| (opening.map(|opening| opening.eco()), games.len()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(groups[0], (Some("B20"), 2));
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[2].0, None);
+    }
+}
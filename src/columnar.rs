@@ -0,0 +1,405 @@
+use std::io::Write;
+use std::sync::Arc;
+
+use arrow_array::builder::{
+    Date32Builder, Float64Builder, Int32Builder, StringBuilder, UInt16Builder, UInt32Builder,
+};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_ipc::writer::FileWriter;
+use arrow_schema::{DataType, Field, Schema};
+use chrono::{Datelike, NaiveDate};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+
+use crate::error::Result;
+use crate::eval::Eval;
+use crate::game::{
+    GiveClocks, GiveDate, GiveEvals, GiveLink, GivePlayers, GiveRatings, GiveResult, GiveSource,
+    ListMoves,
+};
+use crate::moves::Move;
+use crate::openings::Openings;
+use crate::stats::terminations;
+
+/// The file formats tables of games can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableFormat {
+    /// Apache Parquet, compressed with Snappy.
+    Parquet,
+    /// The Apache Arrow IPC file format, also known as Feather.
+    Arrow,
+}
+
+/// Returns a table of `games` with a row per game, holding its headers along with statistics
+/// worked out from its moves:
+///
+/// | Column | Type | Holds |
+/// | --- | --- | --- |
+/// | `game` | `uint32` | The index of the game, which the table of moves refers to. |
+/// | `white`, `black` | `utf8` | The names of the players. |
+/// | `white_elo`, `black_elo` | `uint16` | The ratings of the players. |
+/// | `result` | `utf8` | The result, such as `1-0`. |
+/// | `date` | `date32` | The date the game was played. |
+/// | `time_control`, `speed` | `utf8` | The time control, such as `180+2`, and its speed. |
+/// | `eco`, `opening` | `utf8` | The ECO code and name of the opening. |
+/// | `plies` | `uint32` | The number of moves played, counting each side's moves separately. |
+/// | `termination` | `utf8` | How the game ended, such as `checkmate`. |
+/// | `link` | `utf8` | The URL of the game. |
+/// | `source`, `source_id` | `utf8` | Where the game came from, such as `lichess`, and its identifier there. |
+///
+/// Unknown values are null.
+pub fn games_table<M, G>(games: &[G]) -> RecordBatch
+where
+    M: 'static + Clone + Move,
+    G: GiveClocks
+        + GiveDate
+        + GiveLink
+        + GivePlayers
+        + GiveRatings
+        + GiveResult
+        + GiveSource
+        + ListMoves<M>,
+{
+    let mut index = UInt32Builder::new();
+    let mut white = StringBuilder::new();
+    let mut black = StringBuilder::new();
+    let mut white_elo = UInt16Builder::new();
+    let mut black_elo = UInt16Builder::new();
+    let mut result = StringBuilder::new();
+    let mut date = Date32Builder::new();
+    let mut time_control = StringBuilder::new();
+    let mut speed = StringBuilder::new();
+    let mut eco = StringBuilder::new();
+    let mut opening_name = StringBuilder::new();
+    let mut plies = UInt32Builder::new();
+    let mut termination = StringBuilder::new();
+    let mut link = StringBuilder::new();
+    let mut source = StringBuilder::new();
+    let mut source_id = StringBuilder::new();
+
+    for (game_index, game) in games.iter().enumerate() {
+        let opening = Openings::embedded().classify(game);
+
+        index.append_value(game_index as u32);
+        white.append_option(game.white_player());
+        black.append_option(game.black_player());
+        white_elo.append_option(game.white_rating());
+        black_elo.append_option(game.black_rating());
+        result.append_value(game.result().to_string());
+        date.append_option(game.date().map(days_since_epoch));
+        time_control.append_option(game.time_control().map(|control| control.to_string()));
+        speed.append_option(
+            game.time_control()
+                .map(|control| control.speed().to_string()),
+        );
+        eco.append_option(opening.map(|opening| opening.eco()));
+        opening_name.append_option(opening.map(|opening| opening.name()));
+        plies.append_value(game.list_moves().count() as u32);
+        termination.append_value(terminations::termination(game).to_string());
+        link.append_option(game.link());
+        source.append_option(game.source().map(|source| source.to_string()));
+        source_id.append_option(game.source_id());
+    }
+
+    table(vec![
+        ("game", DataType::UInt32, false, Arc::new(index.finish())),
+        ("white", DataType::Utf8, true, Arc::new(white.finish())),
+        ("black", DataType::Utf8, true, Arc::new(black.finish())),
+        (
+            "white_elo",
+            DataType::UInt16,
+            true,
+            Arc::new(white_elo.finish()),
+        ),
+        (
+            "black_elo",
+            DataType::UInt16,
+            true,
+            Arc::new(black_elo.finish()),
+        ),
+        ("result", DataType::Utf8, false, Arc::new(result.finish())),
+        ("date", DataType::Date32, true, Arc::new(date.finish())),
+        (
+            "time_control",
+            DataType::Utf8,
+            true,
+            Arc::new(time_control.finish()),
+        ),
+        ("speed", DataType::Utf8, true, Arc::new(speed.finish())),
+        ("eco", DataType::Utf8, true, Arc::new(eco.finish())),
+        (
+            "opening",
+            DataType::Utf8,
+            true,
+            Arc::new(opening_name.finish()),
+        ),
+        ("plies", DataType::UInt32, false, Arc::new(plies.finish())),
+        (
+            "termination",
+            DataType::Utf8,
+            false,
+            Arc::new(termination.finish()),
+        ),
+        ("link", DataType::Utf8, true, Arc::new(link.finish())),
+        ("source", DataType::Utf8, true, Arc::new(source.finish())),
+        (
+            "source_id",
+            DataType::Utf8,
+            true,
+            Arc::new(source_id.finish()),
+        ),
+    ])
+}
+
+/// Returns a table of the moves of `games` with a row per move:
+///
+/// | Column | Type | Holds |
+/// | --- | --- | --- |
+/// | `game` | `uint32` | The index of the game in `games`, as in [`games_table`]. |
+/// | `ply` | `uint32` | The number of moves played before this one. |
+/// | `color` | `utf8` | The side that played the move, `white` or `black`. |
+/// | `san` | `utf8` | The move in SAN. |
+/// | `clock` | `float64` | The seconds left on the player's clock after the move. |
+/// | `eval_cp` | `int32` | The evaluation after the move in centipawns, from white's side. |
+/// | `eval_mate` | `int32` | The moves to mate after the move, negative when black mates. |
+///
+/// Unknown values are null.
+pub fn moves_table<M, G>(games: &[G]) -> RecordBatch
+where
+    M: 'static + Clone + Move,
+    G: GiveClocks + GiveEvals + ListMoves<M>,
+{
+    let mut index = UInt32Builder::new();
+    let mut ply = UInt32Builder::new();
+    let mut color = StringBuilder::new();
+    let mut san = StringBuilder::new();
+    let mut clock = Float64Builder::new();
+    let mut eval_cp = Int32Builder::new();
+    let mut eval_mate = Int32Builder::new();
+
+    for (game_index, game) in games.iter().enumerate() {
+        for (move_ply, move_) in game.list_moves().enumerate() {
+            let mover = if move_ply % 2 == 0 { "white" } else { "black" };
+            let eval = game.evals().get(move_ply).copied().flatten();
+
+            index.append_value(game_index as u32);
+            ply.append_value(move_ply as u32);
+            color.append_value(mover);
+            san.append_value(move_.to_algebraic());
+            clock.append_option(
+                game.clocks()
+                    .get(move_ply)
+                    .copied()
+                    .flatten()
+                    .map(|clock| clock.as_secs_f64()),
+            );
+            eval_cp.append_option(match eval {
+                Some(Eval::Centipawns(centipawns)) => Some(centipawns),
+                _ => None,
+            });
+            eval_mate.append_option(match eval {
+                Some(Eval::Mate(moves)) => Some(moves),
+                _ => None,
+            });
+        }
+    }
+
+    table(vec![
+        ("game", DataType::UInt32, false, Arc::new(index.finish())),
+        ("ply", DataType::UInt32, false, Arc::new(ply.finish())),
+        ("color", DataType::Utf8, false, Arc::new(color.finish())),
+        ("san", DataType::Utf8, false, Arc::new(san.finish())),
+        ("clock", DataType::Float64, true, Arc::new(clock.finish())),
+        ("eval_cp", DataType::Int32, true, Arc::new(eval_cp.finish())),
+        (
+            "eval_mate",
+            DataType::Int32,
+            true,
+            Arc::new(eval_mate.finish()),
+        ),
+    ])
+}
+
+/// Writes `table` to `writer` in `format`.
+pub fn write_table<W: Write + Send>(
+    table: &RecordBatch,
+    format: TableFormat,
+    writer: W,
+) -> Result<()> {
+    match format {
+        TableFormat::Parquet => {
+            let properties = WriterProperties::builder()
+                .set_compression(Compression::SNAPPY)
+                .build();
+            let mut writer = ArrowWriter::try_new(writer, table.schema(), Some(properties))?;
+
+            writer.write(table)?;
+            writer.close()?;
+        }
+        TableFormat::Arrow => {
+            let mut writer = FileWriter::try_new(writer, &table.schema())?;
+
+            writer.write(table)?;
+            writer.finish()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns a table of `columns`, each given with its name, type, whether it can hold nulls, and
+/// its values.
+fn table(columns: Vec<(&str, DataType, bool, ArrayRef)>) -> RecordBatch {
+    let fields = columns
+        .iter()
+        .map(|(name, data_type, nullable, _)| Field::new(*name, data_type.clone(), *nullable))
+        .collect::<Vec<_>>();
+
+    let arrays = columns.into_iter().map(|(_, _, _, array)| array).collect();
+
+    // The columns are built together above, so they always match the schema.
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays).unwrap()
+}
+
+/// Returns the number of days from 1970-01-01 to `date`, as Arrow stores dates.
+fn days_since_epoch(date: NaiveDate) -> i32 {
+    date.num_days_from_ce()
+        - NaiveDate::from_ymd_opt(1970, 1, 1)
+            .unwrap()
+            .num_days_from_ce()
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Date32Type, Int32Type, UInt16Type, UInt32Type};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use rstest::*;
+
+    use super::{games_table, moves_table, write_table, TableFormat};
+    use crate::game::test_utils::*;
+    use crate::game::Game;
+    use crate::AlgebraicMove;
+
+    #[fixture]
+    fn games() -> Vec<Game<AlgebraicMove>> {
+        vec![
+            parse_game(
+                "[White \"alice\"]\n[Black \"bob\"]\n[Result \"0-1\"]\n[WhiteElo \"1500\"]\n\
+                 [Date \"1970.01.11\"]\n[TimeControl \"180+2\"]\n\
+                 1. f3 { [%eval -0.5] [%clk 0:03:00] } e5 2. g4 Qh4# { [%eval #-1] } 0-1",
+            ),
+            parse_game("[White \"carol\"]\n[Black \"dave\"]\n[Result \"1/2-1/2\"]\n1. e4 1/2-1/2"),
+        ]
+    }
+
+    #[rstest]
+    fn games_table_should_have_a_row_per_game(games: Vec<Game<AlgebraicMove>>) {
+        let table = games_table(&games);
+
+        assert_eq!(table.num_rows(), 2);
+        assert_eq!(
+            table
+                .column_by_name("white")
+                .unwrap()
+                .as_string::<i32>()
+                .value(1),
+            "carol"
+        );
+        assert_eq!(
+            table
+                .column_by_name("white_elo")
+                .unwrap()
+                .as_primitive::<UInt16Type>()
+                .value(0),
+            1500
+        );
+        assert!(table.column_by_name("black_elo").unwrap().is_null(0));
+        assert_eq!(
+            table
+                .column_by_name("date")
+                .unwrap()
+                .as_primitive::<Date32Type>()
+                .value(0),
+            10
+        );
+        assert_eq!(
+            table
+                .column_by_name("termination")
+                .unwrap()
+                .as_string::<i32>()
+                .value(0),
+            "checkmate"
+        );
+        assert_eq!(
+            table
+                .column_by_name("plies")
+                .unwrap()
+                .as_primitive::<UInt32Type>()
+                .value(0),
+            4
+        );
+    }
+
+    #[rstest]
+    fn moves_table_should_have_a_row_per_move(games: Vec<Game<AlgebraicMove>>) {
+        let table = moves_table(&games);
+
+        assert_eq!(table.num_rows(), 5);
+        assert_eq!(
+            table
+                .column_by_name("san")
+                .unwrap()
+                .as_string::<i32>()
+                .value(3),
+            "Qh4#"
+        );
+        assert_eq!(
+            table
+                .column_by_name("eval_cp")
+                .unwrap()
+                .as_primitive::<Int32Type>()
+                .value(0),
+            -50
+        );
+        assert_eq!(
+            table
+                .column_by_name("eval_mate")
+                .unwrap()
+                .as_primitive::<Int32Type>()
+                .value(3),
+            -1
+        );
+        assert_eq!(
+            table
+                .column_by_name("game")
+                .unwrap()
+                .as_primitive::<UInt32Type>()
+                .value(4),
+            1
+        );
+    }
+
+    #[rstest]
+    fn write_table_should_write_readable_parquet(games: Vec<Game<AlgebraicMove>>) {
+        let table = games_table(&games);
+        let mut parquet = Vec::new();
+
+        write_table(&table, TableFormat::Parquet, &mut parquet).unwrap();
+
+        let batches = ParquetRecordBatchReaderBuilder::try_new(bytes::Bytes::from(parquet))
+            .unwrap()
+            .build()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(batches, vec![table.clone()]);
+
+        let mut arrow = Vec::new();
+        write_table(&table, TableFormat::Arrow, &mut arrow).unwrap();
+
+        assert!(arrow.starts_with(b"ARROW1"));
+    }
+}
@@ -0,0 +1,259 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Defaults for the command-line tool, so that common invocations don't need the same flags every
+/// time. Values given on the command line take precedence over environment variables, which take
+/// precedence over the config file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Config {
+    player: Option<String>,
+    engine: Option<String>,
+    cache_dir: Option<String>,
+    format: Option<String>,
+    time_control: Option<String>,
+}
+
+/// The keys a config file can hold, with the environment variables that override them.
+const KEYS: [(&str, &str); 5] = [
+    ("player", "CHESSHOUND_PLAYER"),
+    ("engine", "CHESSHOUND_ENGINE"),
+    ("cache_dir", "CHESSHOUND_CACHE_DIR"),
+    ("format", "CHESSHOUND_FORMAT"),
+    ("time_control", "CHESSHOUND_TIME_CONTROL"),
+];
+
+impl Config {
+    /// Parses a config file written in a subset of TOML: `key = value` pairs, where values are
+    /// quoted strings or bare words and numbers, with `#` starting a comment. For example:
+    ///
+    /// ```toml
+    /// player = "magnus"
+    /// engine = "/usr/bin/stockfish"
+    /// time_control = "blitz"
+    /// ```
+    ///
+    /// Returns an error for lines that aren't pairs and for unknown keys, so typos don't go
+    /// unnoticed.
+    pub fn parse(text: &str) -> io::Result<Config> {
+        let mut config = Config::default();
+
+        for (index, line) in text.lines().enumerate() {
+            let invalid = |reason: String| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid config on line {}: {}", index + 1, reason),
+                )
+            };
+
+            let line = strip_comment(line).trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => return Err(invalid(String::from("expected key = value"))),
+            };
+
+            let value =
+                parse_value(value).ok_or_else(|| invalid(format!("bad value {}", value)))?;
+
+            if !config.set(key, value) {
+                return Err(invalid(format!("unknown key {}", key)));
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Loads the config file at `path`. A missing file gives an empty config when `required` is
+    /// false, so the default location doesn't need to exist.
+    pub fn load(path: &Path, required: bool) -> io::Result<Config> {
+        match fs::read_to_string(path) {
+            Ok(text) => Config::parse(&text),
+            Err(error) if error.kind() == io::ErrorKind::NotFound && !required => {
+                Ok(Config::default())
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Returns where the config file is looked for by default, which is
+    /// `$XDG_CONFIG_HOME/chesshound/config.toml`, falling back to `~/.config`, or `None` if
+    /// neither variable is set.
+    pub fn default_path() -> Option<PathBuf> {
+        let config_home = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+
+        Some(config_home.join("chesshound").join("config.toml"))
+    }
+
+    /// Returns this config with values replaced by the `CHESSHOUND_` variables in `variables`,
+    /// such as `CHESSHOUND_PLAYER` for `player`.
+    pub fn with_env(mut self, variables: impl IntoIterator<Item = (String, String)>) -> Config {
+        for (name, value) in variables {
+            if let Some((key, _)) = KEYS.iter().find(|(_, variable)| *variable == name) {
+                self.set(key, value);
+            }
+        }
+
+        self
+    }
+
+    /// Sets the value of `key`, returning `false` if there's no such key.
+    fn set(&mut self, key: &str, value: String) -> bool {
+        let field = match key {
+            "player" => &mut self.player,
+            "engine" => &mut self.engine,
+            "cache_dir" => &mut self.cache_dir,
+            "format" => &mut self.format,
+            "time_control" => &mut self.time_control,
+            _ => return false,
+        };
+
+        *field = Some(value);
+        true
+    }
+
+    /// Returns the player whose games are looked at when no player is given.
+    pub fn player(&self) -> Option<&str> {
+        self.player.as_deref()
+    }
+
+    /// Returns the path of the UCI engine used when no engine is given.
+    pub fn engine(&self) -> Option<&str> {
+        self.engine.as_deref()
+    }
+
+    /// Returns the directory engine evaluations are kept in between runs.
+    pub fn cache_dir(&self) -> Option<&str> {
+        self.cache_dir.as_deref()
+    }
+
+    /// Returns the output format used when no format is given, such as `json`.
+    pub fn format(&self) -> Option<&str> {
+        self.format.as_deref()
+    }
+
+    /// Returns the speed of the games looked at when no time control is given, such as `blitz`.
+    pub fn time_control(&self) -> Option<&str> {
+        self.time_control.as_deref()
+    }
+}
+
+/// Returns `line` without a trailing `#` comment, leaving `#` within quoted strings alone.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+
+    for (index, character) in line.char_indices() {
+        match (quote, character) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(open), character) if character == open && !escaped => quote = None,
+            (None, '"') | (None, '\'') => quote = Some(character),
+            (None, '#') => return &line[..index],
+            _ => {}
+        }
+
+        escaped = false;
+    }
+
+    line
+}
+
+/// Parses a TOML value that is a basic string, a literal string, or a bare word or number.
+fn parse_value(value: &str) -> Option<String> {
+    if let Some(literal) = value
+        .strip_prefix('\'')
+        .and_then(|value| value.strip_suffix('\''))
+    {
+        return Some(String::from(literal));
+    }
+
+    if let Some(basic) = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+    {
+        let mut output = String::new();
+        let mut characters = basic.chars();
+
+        while let Some(character) = characters.next() {
+            match character {
+                '\\' => match characters.next()? {
+                    '"' => output.push('"'),
+                    '\\' => output.push('\\'),
+                    'n' => output.push('\n'),
+                    't' => output.push('\t'),
+                    _ => return None,
+                },
+                '"' => return None,
+                character => output.push(character),
+            }
+        }
+
+        return Some(output);
+    }
+
+    if value.is_empty() || value.contains(['"', '\'', ' ']) {
+        None
+    } else {
+        Some(String::from(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::Config;
+
+    #[rstest]
+    fn parse_should_read_values_and_skip_comments() {
+        let config = Config::parse(
+            "# Defaults\n\
+             player = \"magnus\"   # my account\n\
+             engine = '/opt/engines/stock#fish'\n\
+             format = json\n\
+             \n\
+             time_control = \"blitz\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(config.player(), Some("magnus"));
+        assert_eq!(config.engine(), Some("/opt/engines/stock#fish"));
+        assert_eq!(config.format(), Some("json"));
+        assert_eq!(config.time_control(), Some("blitz"));
+        assert_eq!(config.cache_dir(), None);
+    }
+
+    #[rstest(
+        text,
+        case("player magnus"),
+        case("username = \"magnus\""),
+        case("player = \"magnus"),
+        case("player = two words")
+    )]
+    fn parse_should_reject_invalid_configs(text: &str) {
+        assert!(Config::parse(text).is_err());
+    }
+
+    #[rstest]
+    fn with_env_should_override_the_file() {
+        let config = Config::parse("player = \"magnus\"\nformat = \"csv\"")
+            .unwrap()
+            .with_env(vec![
+                (String::from("CHESSHOUND_PLAYER"), String::from("hikaru")),
+                (String::from("HOME"), String::from("/root")),
+            ]);
+
+        assert_eq!(config.player(), Some("hikaru"));
+        assert_eq!(config.format(), Some("csv"));
+    }
+}
@@ -0,0 +1,533 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::game::{Color, GiveMetadata, GivePlayers, GiveResult, PlayerResult};
+use crate::player::name_key;
+use crate::stats::statistic::Value;
+use crate::stats::Score;
+
+/// One game of a player in a tournament, as shown in a cell of its cross-table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pairing {
+    round: Option<String>,
+    opponent: usize,
+    color: Color,
+    result: PlayerResult,
+}
+
+impl Pairing {
+    /// Returns the round the game was played in, without its board number, or `None` if the game
+    /// had no Round header.
+    pub fn round(&self) -> Option<&str> {
+        self.round.as_deref()
+    }
+
+    /// Returns the rank of the opponent in the standings, counting from 1.
+    pub fn opponent(&self) -> usize {
+        self.opponent
+    }
+
+    /// Returns the color the player had.
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /// Returns the result of the game for the player.
+    pub fn result(&self) -> PlayerResult {
+        self.result
+    }
+}
+
+impl fmt::Display for Pairing {
+    /// Writes the pairing as cross-tables show it, such as `+W3` for a win with white against the
+    /// player ranked third.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let result = match self.result {
+            PlayerResult::Won => '+',
+            PlayerResult::Drew => '=',
+            PlayerResult::Lost => '-',
+        };
+
+        write!(
+            f,
+            "{}{}{}",
+            result,
+            self.color.char().to_ascii_uppercase(),
+            self.opponent
+        )
+    }
+}
+
+/// A player's place in the standings of a tournament.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Standing {
+    rank: usize,
+    player: String,
+    rating: Option<u16>,
+    score: Score,
+    buchholz: f64,
+    sonneborn_berger: f64,
+    pairings: Vec<Pairing>,
+}
+
+impl Standing {
+    /// Returns the player's rank, counting from 1.
+    pub fn rank(&self) -> usize {
+        self.rank
+    }
+
+    /// Returns the name of the player, as in the first game they were found in.
+    pub fn player(&self) -> &str {
+        &self.player
+    }
+
+    /// Returns the first rating given for the player in the tournament, if any.
+    pub fn rating(&self) -> Option<u16> {
+        self.rating
+    }
+
+    /// Returns the player's score over the tournament.
+    pub fn score(&self) -> Score {
+        self.score
+    }
+
+    /// Returns the sum of the points scored by each of the player's opponents, counted once for
+    /// each game against them.
+    pub fn buchholz(&self) -> f64 {
+        self.buchholz
+    }
+
+    /// Returns the sum of the points scored by the opponents the player beat, plus half the
+    /// points of the opponents they drew with.
+    pub fn sonneborn_berger(&self) -> f64 {
+        self.sonneborn_berger
+    }
+
+    /// Returns the player's games, in the order of their rounds.
+    pub fn pairings(&self) -> &[Pairing] {
+        &self.pairings
+    }
+
+    /// Returns the cell of the cross-table for `round`, with the pairings of every game the
+    /// player played in it separated by spaces.
+    fn cell(&self, round: &Option<String>) -> String {
+        self.pairings
+            .iter()
+            .filter(|pairing| &pairing.round == round)
+            .map(Pairing::to_string)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// The cross-table of a tournament, reconstructed from its games. Players are ranked by points,
+/// then by Buchholz, then by Sonneborn-Berger, and then by name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Crosstable {
+    event: Option<String>,
+    rounds: Vec<Option<String>>,
+    standings: Vec<Standing>,
+}
+
+impl Crosstable {
+    /// Creates the cross-table of `event` from its `games`. Games missing either player are
+    /// ignored, since they can't be placed in the table.
+    fn new<G>(event: Option<String>, games: &[&G]) -> Crosstable
+    where
+        G: GiveMetadata + GivePlayers + GiveResult,
+    {
+        let mut indices = HashMap::new();
+        let mut players: Vec<(String, Option<u16>, Score, Vec<Pairing>)> = Vec::new();
+        let mut rounds = Vec::new();
+
+        for game in games {
+            if game.white().is_unknown() || game.black().is_unknown() {
+                continue;
+            }
+
+            // Rounds such as `3.2` give the board after the dot, which cross-tables don't show.
+            let round = game
+                .metadata()
+                .round()
+                .and_then(|round| round.split('.').next())
+                .map(String::from);
+
+            if !rounds.contains(&round) {
+                rounds.push(round.clone());
+            }
+
+            let mut index = |color: Color| {
+                let name = game.player_of(color).unwrap_or_default();
+
+                *indices.entry(name_key(name)).or_insert_with(|| {
+                    players.push((String::from(name), None, Score::new(), Vec::new()));
+                    players.len() - 1
+                })
+            };
+            let (white, black) = (index(Color::White), index(Color::Black));
+
+            for (color, index, opponent) in
+                [(Color::White, white, black), (Color::Black, black, white)]
+            {
+                let result = game.result().for_color(color);
+                let (_, rating, score, pairings) = &mut players[index];
+
+                *rating = rating.or_else(|| game.player(color).rating());
+                score.add(result);
+                pairings.push(Pairing {
+                    round: round.clone(),
+                    opponent,
+                    color,
+                    result,
+                });
+            }
+        }
+
+        rounds.sort_by_key(|round| {
+            let number = round.as_ref().and_then(|round| round.parse::<u32>().ok());
+            (round.is_none(), number.is_none(), number, round.clone())
+        });
+
+        let points = players
+            .iter()
+            .map(|(_, _, score, _)| score.points())
+            .collect::<Vec<_>>();
+        let tie_breaks = players
+            .iter()
+            .map(|(_, _, _, pairings)| {
+                pairings
+                    .iter()
+                    .fold((0., 0.), |(buchholz, sonneborn_berger), pairing| {
+                        let opponent_points = points[pairing.opponent];
+
+                        (
+                            buchholz + opponent_points,
+                            sonneborn_berger + result_points(pairing.result) * opponent_points,
+                        )
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        let mut order = (0..players.len()).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| {
+            points[b]
+                .total_cmp(&points[a])
+                .then_with(|| tie_breaks[b].0.total_cmp(&tie_breaks[a].0))
+                .then_with(|| tie_breaks[b].1.total_cmp(&tie_breaks[a].1))
+                .then_with(|| players[a].0.cmp(&players[b].0))
+        });
+
+        let mut ranks = vec![0; players.len()];
+        for (rank, index) in order.iter().enumerate() {
+            ranks[*index] = rank + 1;
+        }
+
+        let mut players = players.into_iter().map(Some).collect::<Vec<_>>();
+        let standings = order
+            .into_iter()
+            .map(|index| {
+                let (player, rating, score, mut pairings) = players[index].take().unwrap();
+
+                for pairing in &mut pairings {
+                    pairing.opponent = ranks[pairing.opponent];
+                }
+
+                pairings
+                    .sort_by_key(|pairing| rounds.iter().position(|round| *round == pairing.round));
+
+                Standing {
+                    rank: ranks[index],
+                    player,
+                    rating,
+                    score,
+                    buchholz: tie_breaks[index].0,
+                    sonneborn_berger: tie_breaks[index].1,
+                    pairings,
+                }
+            })
+            .collect();
+
+        Crosstable {
+            event,
+            rounds,
+            standings,
+        }
+    }
+
+    /// Returns the name of the tournament, or `None` if its games had no Event header.
+    pub fn event(&self) -> Option<&str> {
+        self.event.as_deref()
+    }
+
+    /// Returns the rounds of the tournament in order, with `None` standing for games that had no
+    /// Round header, which come last.
+    pub fn rounds(&self) -> &[Option<String>] {
+        &self.rounds
+    }
+
+    /// Returns the players in order of their rank.
+    pub fn standings(&self) -> &[Standing] {
+        &self.standings
+    }
+
+    /// Returns the cross-table as a value for writing as JSON, with each player's games listed
+    /// under `pairings`.
+    pub fn to_value(&self) -> Value {
+        let standings = self
+            .standings
+            .iter()
+            .map(|standing| {
+                let pairings = standing
+                    .pairings
+                    .iter()
+                    .map(|pairing| {
+                        Value::map(vec![
+                            ("round", pairing.round().into()),
+                            ("opponent", pairing.opponent.into()),
+                            (
+                                "color",
+                                match pairing.color {
+                                    Color::White => "white",
+                                    Color::Black => "black",
+                                }
+                                .into(),
+                            ),
+                            ("points", result_points(pairing.result).into()),
+                        ])
+                    })
+                    .collect::<Vec<_>>();
+
+                Value::map(
+                    standing_entries(standing)
+                        .into_iter()
+                        .chain(std::iter::once(("pairings", Value::List(pairings)))),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        Value::map(vec![
+            ("event", self.event().into()),
+            (
+                "rounds",
+                Value::List(
+                    self.rounds
+                        .iter()
+                        .map(|round| round.clone().into())
+                        .collect(),
+                ),
+            ),
+            ("standings", Value::List(standings)),
+        ])
+    }
+
+    /// Returns a row for each player, for writing as a table. Each player's games are given in a
+    /// single `pairings` column, as cross-tables show them, so that tournaments with different
+    /// numbers of rounds share the same columns.
+    pub fn to_rows(&self) -> Vec<Value> {
+        self.standings
+            .iter()
+            .map(|standing| {
+                let pairings = self
+                    .rounds
+                    .iter()
+                    .map(|round| standing.cell(round))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+
+                Value::map(
+                    std::iter::once(("event", self.event().into()))
+                        .chain(standing_entries(standing))
+                        .chain(std::iter::once(("pairings", pairings.into()))),
+                )
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Crosstable {
+    /// Writes the cross-table with a column for each round and the tie-breaks last.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.event().unwrap_or("Unknown event"))?;
+
+        let mut rows = vec![[
+            vec![
+                String::from("Rank"),
+                String::from("Player"),
+                String::from("Rating"),
+            ],
+            self.rounds
+                .iter()
+                .map(|round| String::from(round.as_deref().unwrap_or("?")))
+                .collect(),
+            vec![
+                String::from("Points"),
+                String::from("Buchholz"),
+                String::from("SB"),
+            ],
+        ]
+        .concat()];
+
+        for standing in &self.standings {
+            rows.push(
+                [
+                    vec![
+                        standing.rank.to_string(),
+                        standing.player.clone(),
+                        standing
+                            .rating
+                            .map_or_else(String::new, |rating| rating.to_string()),
+                    ],
+                    self.rounds
+                        .iter()
+                        .map(|round| standing.cell(round))
+                        .collect(),
+                    vec![
+                        standing.score.points().to_string(),
+                        standing.buchholz.to_string(),
+                        standing.sonneborn_berger.to_string(),
+                    ],
+                ]
+                .concat(),
+            );
+        }
+
+        let widths = (0..rows[0].len())
+            .map(|column| {
+                rows.iter()
+                    .map(|row| row[column].chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect::<Vec<_>>();
+
+        for row in rows {
+            let line = row
+                .iter()
+                .zip(&widths)
+                .map(|(field, width)| format!("{:<width$}", field, width = width))
+                .collect::<Vec<_>>()
+                .join("  ");
+
+            writeln!(f, "{}", line.trim_end())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the names and values describing `standing` apart from its games.
+fn standing_entries(standing: &Standing) -> Vec<(&'static str, Value)> {
+    vec![
+        ("rank", standing.rank.into()),
+        ("player", standing.player().into()),
+        ("rating", standing.rating.map(usize::from).into()),
+        ("games", standing.score.games().into()),
+        ("points", standing.score.points().into()),
+        ("buchholz", standing.buchholz.into()),
+        ("sonneborn_berger", standing.sonneborn_berger.into()),
+    ]
+}
+
+/// Returns the points a player gets for `result`.
+fn result_points(result: PlayerResult) -> f64 {
+    match result {
+        PlayerResult::Won => 1.,
+        PlayerResult::Drew => 0.5,
+        PlayerResult::Lost => 0.,
+    }
+}
+
+/// Returns the cross-table of each tournament in the games of `game_iter`, telling tournaments
+/// apart by their Event header, in the order each was first found. Players are matched ignoring
+/// case, and games are placed in rounds by their Round header.
+pub fn crosstables<'a, G>(game_iter: &mut dyn Iterator<Item = &'a G>) -> Vec<Crosstable>
+where
+    G: 'a + GiveMetadata + GivePlayers + GiveResult,
+{
+    let mut events: Vec<(Option<String>, Vec<&G>)> = Vec::new();
+
+    for game in game_iter {
+        let event = game.metadata().event().map(String::from);
+
+        match events.iter_mut().find(|(name, _)| *name == event) {
+            Some((_, games)) => games.push(game),
+            None => events.push((event, vec![game])),
+        }
+    }
+
+    events
+        .into_iter()
+        .map(|(event, games)| Crosstable::new(event, &games))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::crosstables;
+    use crate::game::test_utils::*;
+    use crate::game::Game;
+    use crate::AlgebraicMove;
+
+    fn game(round: &str, white: &str, black: &str, result: &str) -> Game<AlgebraicMove> {
+        parse_game(&format!(
+            "[Event \"Club Championship\"]\n[Round \"{}\"]\n[White \"{}\"]\n[Black \"{}\"]\n\
+             [Result \"{}\"]\n1. e4 e5 {}",
+            round, white, black, result, result
+        ))
+    }
+
+    #[rstest]
+    fn crosstables_should_rank_by_points_and_tie_breaks() {
+        let games = [
+            game("1.1", "alice", "bob", "1-0"),
+            game("1.2", "carol", "dave", "1/2-1/2"),
+            game("2.1", "Bob", "carol", "1-0"),
+            game("2.2", "dave", "alice", "1-0"),
+            game("3.1", "alice", "carol", "1/2-1/2"),
+            game("3.2", "bob", "dave", "1/2-1/2"),
+            parse_game("[Event \"Casual\"]\n[White \"alice\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n1. d4 1-0"),
+        ];
+
+        let tables = crosstables(&mut games.iter());
+        assert_eq!(tables.len(), 2);
+
+        let table = &tables[0];
+        let standings = table
+            .standings()
+            .iter()
+            .map(|standing| {
+                (
+                    standing.player(),
+                    standing.score().points(),
+                    standing.buchholz(),
+                    standing.sonneborn_berger(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(table.event(), Some("Club Championship"));
+        assert_eq!(
+            standings,
+            vec![
+                ("dave", 2., 4., 2.75),
+                ("alice", 1.5, 4.5, 2.),
+                ("bob", 1.5, 4.5, 2.),
+                ("carol", 1., 5., 1.75),
+            ]
+        );
+        assert_eq!(
+            table.standings()[1]
+                .pairings()
+                .iter()
+                .map(|pairing| pairing.to_string())
+                .collect::<Vec<_>>(),
+            vec!["+W3", "-B1", "=W4"]
+        );
+        assert_eq!(
+            table.to_string().lines().nth(2),
+            Some("1     dave            =B4  +W2  =B3  2       4         2.75")
+        );
+    }
+}
@@ -0,0 +1,315 @@
+use std::path::Path;
+
+use rusqlite::{params, params_from_iter, Connection};
+use shakmaty::Setup;
+use tracing::info;
+
+use crate::error::{Error, Result};
+use crate::export::{self, PGN_HEADERS};
+use crate::game::{
+    GiveClocks, GiveDate, GiveEvals, GiveLink, GiveMetadata, GivePlayers, GiveRatings, GiveResult,
+    ListMoves,
+};
+use crate::game_id::GameId;
+use crate::moves::Move;
+use crate::position;
+use crate::stats::terminations;
+use crate::zobrist;
+
+/// The tables of a database, created when it's first opened.
+///
+/// `games` has a row per game with the headers chesshound reads and statistics worked out on
+/// import, which conditions given to [`Database::query`] can use:
+///
+/// | Column | Holds |
+/// | --- | --- |
+/// | `game_id` | The game's [`GameId`], which no two games share. |
+/// | `white`, `black` | The names of the players. |
+/// | `result` | The result, such as `1-0`. |
+/// | `date`, `time` | When the game was played, as `2021-06-15` and `12:00:00`. |
+/// | `time_control` | The time control, such as `180+2`. |
+/// | `white_elo`, `black_elo` | The ratings of the players. |
+/// | `plies` | The number of moves played, counting each side's moves separately. |
+/// | `termination` | How the game ended, such as `checkmate`. |
+///
+/// `positions` holds the Zobrist hash of every position reached in each game.
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS games (
+        id INTEGER PRIMARY KEY,
+        white TEXT NOT NULL,
+        black TEXT NOT NULL,
+        result TEXT NOT NULL,
+        date TEXT,
+        time TEXT,
+        time_control TEXT,
+        white_elo INTEGER,
+        black_elo INTEGER,
+        plies INTEGER NOT NULL,
+        termination TEXT NOT NULL,
+        pgn TEXT NOT NULL UNIQUE,
+        game_id TEXT
+    );
+    CREATE INDEX IF NOT EXISTS games_white ON games (white);
+    CREATE INDEX IF NOT EXISTS games_black ON games (black);
+    CREATE INDEX IF NOT EXISTS games_date ON games (date);
+    CREATE TABLE IF NOT EXISTS positions (
+        hash INTEGER NOT NULL,
+        game INTEGER NOT NULL REFERENCES games (id),
+        ply INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS positions_hash ON positions (hash);
+";
+
+/// A database of games kept in SQLite, so that large sets of games can be searched and filtered
+/// without parsing their PGN again each time.
+pub struct Database {
+    connection: Connection,
+}
+
+impl Database {
+    /// Opens the database at `path`, creating it if it doesn't exist.
+    pub fn open(path: &Path) -> Result<Database> {
+        Database::new(Connection::open(path)?)
+    }
+
+    /// Opens a database kept in memory, which is gone once dropped.
+    pub fn open_in_memory() -> Result<Database> {
+        Database::new(Connection::open_in_memory()?)
+    }
+
+    fn new(connection: Connection) -> Result<Database> {
+        connection.execute_batch(SCHEMA)?;
+
+        // Databases made before games had identifiers get the column added, leaving it empty for
+        // the games already in them.
+        let has_game_id = connection
+            .prepare("SELECT 1 FROM pragma_table_info('games') WHERE name = 'game_id'")?
+            .exists([])?;
+
+        if !has_game_id {
+            connection.execute_batch("ALTER TABLE games ADD COLUMN game_id TEXT")?;
+        }
+
+        connection
+            .execute_batch("CREATE UNIQUE INDEX IF NOT EXISTS games_game_id ON games (game_id)")?;
+
+        Ok(Database { connection })
+    }
+
+    /// Adds `games` to the database, returning how many were added. Games already in the
+    /// database, by their PGN or their [`GameId`], are skipped, so the same file can be imported
+    /// again as games are added to it, and games downloaded again from another site aren't added
+    /// twice.
+    pub fn import<'a, M, G>(&mut self, games: impl IntoIterator<Item = &'a G>) -> Result<usize>
+    where
+        M: 'static + Clone + Move,
+        G: 'a
+            + GiveClocks
+            + GiveDate
+            + GiveEvals
+            + GiveLink
+            + GiveMetadata
+            + GivePlayers
+            + GiveRatings
+            + GiveResult
+            + ListMoves<M>,
+    {
+        let transaction = self.connection.transaction()?;
+        let mut added = 0;
+        let mut skipped = 0;
+
+        for game in games {
+            let positions = position::replay(game.list_moves());
+
+            let inserted = transaction.execute(
+                "INSERT OR IGNORE INTO games (white, black, result, date, time, time_control, \
+                 white_elo, black_elo, plies, termination, pgn, game_id) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                params![
+                    game.white_player(),
+                    game.black_player(),
+                    game.result().to_string(),
+                    game.date().map(|date| date.to_string()),
+                    game.time().map(|time| time.to_string()),
+                    game.time_control()
+                        .map(|time_control| time_control.to_string()),
+                    game.white_rating(),
+                    game.black_rating(),
+                    positions.len() - 1,
+                    terminations::termination(game).to_string(),
+                    export::to_pgn(game, &PGN_HEADERS),
+                    GameId::of(game).as_str(),
+                ],
+            )?;
+
+            if inserted == 0 {
+                skipped += 1;
+                continue;
+            }
+
+            let id = transaction.last_insert_rowid();
+            let mut statement = transaction
+                .prepare_cached("INSERT INTO positions (hash, game, ply) VALUES (?1, ?2, ?3)")?;
+
+            for (ply, position) in positions.iter().enumerate() {
+                // SQLite only has signed integers, so hashes are stored with the same bits.
+                statement.execute(params![zobrist::hash_position(position) as i64, id, ply])?;
+            }
+
+            added += 1;
+        }
+
+        transaction.commit()?;
+
+        info!(added, skipped, "Imported games");
+
+        Ok(added)
+    }
+
+    /// Returns the number of games in the database.
+    pub fn len(&self) -> Result<usize> {
+        Ok(self
+            .connection
+            .query_row("SELECT COUNT(*) FROM games", [], |row| row.get(0))?)
+    }
+
+    /// Returns whether the database has no games.
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Returns the PGN of the games meeting `condition`, an SQL expression over the columns of
+    /// the `games` table such as `white_elo > 2000 AND result = '1-0'`, in the order they were
+    /// imported. When `position` is given, only games reaching it are returned.
+    pub fn query(&self, condition: Option<&str>, position: Option<&Setup>) -> Result<Vec<String>> {
+        let mut sql = String::from("SELECT pgn FROM games WHERE ");
+
+        sql += &format!("({})", condition.unwrap_or("1"));
+
+        if position.is_some() {
+            sql += " AND id IN (SELECT game FROM positions WHERE hash = ?1)";
+        }
+
+        sql += " ORDER BY id";
+
+        let mut statement = self
+            .connection
+            .prepare(&sql)
+            .map_err(|error| invalid_condition(condition, error))?;
+
+        let hash = position.map(|position| zobrist::hash(position) as i64);
+
+        statement
+            .query_map(params_from_iter(hash), |row| row.get(0))
+            .and_then(Iterator::collect)
+            .map_err(|error| invalid_condition(condition, error))
+    }
+}
+
+/// Turns an error from running a query with `condition` into an error blaming the condition.
+fn invalid_condition(condition: Option<&str>, error: rusqlite::Error) -> Error {
+    match condition {
+        Some(condition) => Error::InvalidCondition {
+            condition: String::from(condition),
+            source: error,
+        },
+        None => Error::Database(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use rusqlite::Connection;
+    use shakmaty::fen::Fen;
+
+    use super::{Database, SCHEMA};
+    use crate::game::test_utils::*;
+
+    #[fixture]
+    fn database() -> Database {
+        let games = [
+            parse_game("[White \"alice\"]\n[Black \"bob\"]\n[Result \"0-1\"]\n[WhiteElo \"1500\"]\n1. f3 e5 2. g4 Qh4# 0-1"),
+            parse_game("[White \"bob\"]\n[Black \"alice\"]\n[Result \"1/2-1/2\"]\n[WhiteElo \"2100\"]\n1. e4 e5 1/2-1/2"),
+            parse_game("[White \"carol\"]\n[Black \"alice\"]\n[Result \"1-0\"]\n1. d4 d5 1-0"),
+        ];
+
+        let mut database = Database::open_in_memory().unwrap();
+
+        assert_eq!(database.import(games.iter()).unwrap(), 3);
+        assert_eq!(database.import(games.iter()).unwrap(), 0);
+
+        // The same game downloaded again, with names written differently, has the same GameId.
+        let renamed =
+            parse_game("[White \"Carol\"]\n[Black \"ALICE\"]\n[Result \"1-0\"]\n1. d4 d5 1-0");
+        assert_eq!(database.import([renamed].iter()).unwrap(), 0);
+        assert_eq!(database.len().unwrap(), 3);
+
+        database
+    }
+
+    #[rstest]
+    fn query_should_filter_by_condition(database: Database) {
+        let games = database
+            .query(Some("black = 'alice' AND white_elo IS NOT NULL"), None)
+            .unwrap();
+
+        assert_eq!(games.len(), 1);
+        assert!(games[0].contains("[White \"bob\"]"));
+
+        let games = database
+            .query(Some("termination = 'checkmate'"), None)
+            .unwrap();
+
+        assert_eq!(games.len(), 1);
+        assert!(games[0].contains("Qh4#"));
+        assert_eq!(database.query(None, None).unwrap().len(), 3);
+    }
+
+    #[rstest]
+    fn query_should_find_games_reaching_a_position(database: Database) {
+        let setup = "rnbqkbnr/pppp1ppp/8/4p3/8/8/PPPPPPPP/RNBQKBNR w KQkq -"
+            .parse::<Fen>()
+            .unwrap();
+
+        let games = database.query(None, Some(setup.as_setup())).unwrap();
+
+        assert_eq!(games.len(), 0);
+
+        let setup = "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq -"
+            .parse::<Fen>()
+            .unwrap();
+
+        let games = database
+            .query(Some("result = '1/2-1/2'"), Some(setup.as_setup()))
+            .unwrap();
+
+        assert_eq!(games.len(), 1);
+        assert!(games[0].contains("[White \"bob\"]"));
+    }
+
+    #[rstest]
+    fn query_should_reject_invalid_conditions(database: Database) {
+        assert!(database.query(Some("no_such_column = 1"), None).is_err());
+    }
+
+    #[rstest]
+    fn open_should_add_game_ids_to_old_databases() {
+        let connection = Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch(&SCHEMA.replace(",\n        game_id TEXT", ""))
+            .unwrap();
+
+        let mut database = Database::new(connection).unwrap();
+        let game = parse_game("[White \"alice\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n1. e4 1-0");
+
+        assert_eq!(database.import([game].iter()).unwrap(), 1);
+        assert_eq!(
+            database
+                .query(Some("game_id IS NOT NULL"), None)
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+}
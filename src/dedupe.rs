@@ -0,0 +1,191 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use crate::game::{GiveDate, GiveLink, GivePlayers, GiveResult, ListMoves};
+use crate::game_id::GameId;
+use crate::moves::Move;
+
+/// Why a game was found to be a duplicate of one seen before.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum DuplicateReason {
+    /// The players, result, date, start time, and moves are all the same.
+    Identical,
+    /// The game has the same players, date, and moves as one before, and so the same
+    /// [`GameId`], but is written differently, such as with the case or spacing of the players'
+    /// names changed, as happens when the same game is downloaded from different sites.
+    SameGameId,
+    /// The game has the same link as one before but is written differently, such as one copy
+    /// exported with clock times and one without.
+    SameLink,
+}
+
+impl fmt::Display for DuplicateReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DuplicateReason::Identical => write!(f, "identical"),
+            DuplicateReason::SameGameId => write!(f, "same game written differently"),
+            DuplicateReason::SameLink => write!(f, "same link with different contents"),
+        }
+    }
+}
+
+/// Returns a fingerprint of `game` from its players, result, date, start time, and moves, exactly
+/// as written.
+fn fingerprint<M, G>(game: &G) -> u64
+where
+    M: Clone + Move,
+    G: GiveDate + GivePlayers + GiveResult + ListMoves<M>,
+{
+    let mut hasher = DefaultHasher::new();
+
+    game.white_player().hash(&mut hasher);
+    game.black_player().hash(&mut hasher);
+    game.result().to_string().hash(&mut hasher);
+    game.date().hash(&mut hasher);
+    game.time().hash(&mut hasher);
+
+    for move_ in game.list_moves() {
+        move_.to_algebraic().hash(&mut hasher);
+    }
+
+    hasher.finish()
+}
+
+/// Finds games that were already seen, by their [`GameId`]s from both their contents and their
+/// links. Only identifiers and fingerprints are kept, so games can be checked as they're read
+/// without holding them in memory.
+#[derive(Debug, Clone, Default)]
+pub struct Deduplicator {
+    identical: HashSet<u64>,
+    ids: HashSet<GameId>,
+}
+
+impl Deduplicator {
+    /// Creates a `Deduplicator` that hasn't seen any games.
+    pub fn new() -> Deduplicator {
+        Deduplicator::default()
+    }
+
+    /// Returns why `game` is a duplicate of a game checked before, or `None` if it's the first
+    /// of its kind, in which case later copies of it will be found.
+    pub fn check<M, G>(&mut self, game: &G) -> Option<DuplicateReason>
+    where
+        M: Clone + Move,
+        G: GiveDate + GiveLink + GivePlayers + GiveResult + ListMoves<M>,
+    {
+        if !self.identical.insert(fingerprint(game)) {
+            return Some(DuplicateReason::Identical);
+        }
+
+        // Both identifiers are remembered, so that copies with and without a link are found.
+        let new_contents = self.ids.insert(GameId::from_content(game));
+        let new_link = match game.link() {
+            Some(link) => self.ids.insert(GameId::from_link(link)),
+            None => true,
+        };
+
+        if !new_link {
+            Some(DuplicateReason::SameLink)
+        } else if !new_contents {
+            Some(DuplicateReason::SameGameId)
+        } else {
+            None
+        }
+    }
+
+    /// Returns [`DuplicateReason::Identical`] if `pgn` was checked before, ignoring whitespace
+    /// around it, or `None` if it's the first of its kind. This finds copies of games that can't
+    /// be read, such as unfinished games, which can only be compared as written.
+    pub fn check_pgn(&mut self, pgn: &[u8]) -> Option<DuplicateReason> {
+        let mut hasher = DefaultHasher::new();
+        String::from_utf8_lossy(pgn).trim().hash(&mut hasher);
+
+        if self.identical.insert(hasher.finish()) {
+            None
+        } else {
+            Some(DuplicateReason::Identical)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::{Deduplicator, DuplicateReason};
+    use crate::game::test_utils::*;
+
+    #[rstest]
+    fn check_should_find_duplicates_and_why() {
+        let games = [
+            dated_game("alice", "bob", "1-0", "2021.06.15", "1. e4 e5"),
+            dated_game("alice", "bob", "1-0", "2021.06.15", "1. e4 e5"),
+            dated_game("Alice ", "bob", "1-0", "2021.06.15", "1. e4 e5"),
+            dated_game("Alice ", "bob", "1-0", "2021.06.15", "1. e4 e5"),
+            dated_game("alice", "bob", "1-0", "2021.06.16", "1. e4 e5"),
+            dated_game("alice", "bob", "1-0", "2021.06.15", "1. e4 c5"),
+        ];
+
+        let mut deduplicator = Deduplicator::new();
+        let reasons = games
+            .iter()
+            .map(|game| deduplicator.check(game))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            reasons,
+            vec![
+                None,
+                Some(DuplicateReason::Identical),
+                Some(DuplicateReason::SameGameId),
+                Some(DuplicateReason::Identical),
+                None,
+                None,
+            ]
+        );
+    }
+
+    #[rstest]
+    fn check_should_find_copies_by_link() {
+        let games = [
+            parse_game(
+                "[White \"alice\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n\
+                 [Site \"https://lichess.org/abcd1234\"]\n1. e4 e5 1-0",
+            ),
+            parse_game(
+                "[White \"alice\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n[Date \"2021.06.15\"]\n\
+                 [Link \"https://www.lichess.org/abcd1234\"]\n1. e4 e5 1-0",
+            ),
+            parse_game("[White \"alice\"]\n[Black \"bob\"]\n[Result \"0-1\"]\n1. e4 e5 0-1"),
+        ];
+
+        let mut deduplicator = Deduplicator::new();
+        let reasons = games
+            .iter()
+            .map(|game| deduplicator.check(game))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            reasons,
+            vec![
+                None,
+                Some(DuplicateReason::SameLink),
+                Some(DuplicateReason::SameGameId),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn check_pgn_should_find_copies_as_written() {
+        let mut deduplicator = Deduplicator::new();
+
+        assert_eq!(deduplicator.check_pgn(b"[Result \"*\"]\n\n1. e4 *\n"), None);
+        assert_eq!(
+            deduplicator.check_pgn(b"[Result \"*\"]\n\n1. e4 *\n\n"),
+            Some(DuplicateReason::Identical)
+        );
+        assert_eq!(deduplicator.check_pgn(b"[Result \"*\"]\n\n1. d4 *\n"), None);
+    }
+}
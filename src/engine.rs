@@ -0,0 +1,752 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::str::FromStr;
+use std::time::Duration;
+
+use shakmaty::san::SanPlus;
+use shakmaty::uci::UciMove;
+use shakmaty::{Chess, Color, Position};
+use tracing::{debug, trace};
+
+use crate::eval::Eval;
+use crate::position;
+use crate::zobrist;
+
+/// How long an engine should search a position for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Limit {
+    /// Search to a fixed depth in plies.
+    Depth(u32),
+    /// Search for a fixed amount of time.
+    Time(Duration),
+    /// Search a fixed number of nodes.
+    Nodes(u64),
+}
+
+impl Limit {
+    /// Returns the arguments of the UCI `go` command for this limit, such as `depth 12`.
+    fn go_arguments(&self) -> String {
+        match self {
+            Limit::Depth(depth) => format!("depth {}", depth),
+            Limit::Time(time) => format!("movetime {}", time.as_millis()),
+            Limit::Nodes(nodes) => format!("nodes {}", nodes),
+        }
+    }
+
+    fn parse_go_arguments(name: &str, value: &str) -> Option<Limit> {
+        match name {
+            "depth" => value.parse().ok().map(Limit::Depth),
+            "movetime" => value
+                .parse()
+                .ok()
+                .map(Duration::from_millis)
+                .map(Limit::Time),
+            "nodes" => value.parse().ok().map(Limit::Nodes),
+            _ => None,
+        }
+    }
+}
+
+/// A move the engine considered, with the evaluation it leads to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    san: String,
+    eval: Eval,
+}
+
+impl Candidate {
+    /// Returns the move in SAN.
+    pub fn san(&self) -> &str {
+        &self.san
+    }
+
+    /// Returns the evaluation after the move, from white's point of view.
+    pub fn eval(&self) -> Eval {
+        self.eval
+    }
+
+    fn to_cache_field(&self) -> String {
+        match self.eval {
+            Eval::Centipawns(centipawns) => format!("{},cp,{}", self.san, centipawns),
+            Eval::Mate(moves) => format!("{},mate,{}", self.san, moves),
+        }
+    }
+
+    fn parse_cache_field(field: &str) -> Option<Candidate> {
+        let parts = field.split(',').collect::<Vec<_>>();
+
+        let eval = match parts.as_slice() {
+            [_, "cp", value] => Eval::Centipawns(value.parse().ok()?),
+            [_, "mate", value] => Eval::Mate(value.parse().ok()?),
+            _ => return None,
+        };
+
+        Some(Candidate {
+            san: String::from(parts[0]),
+            eval,
+        })
+    }
+}
+
+/// The result of an engine searching a position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Analysis {
+    eval: Eval,
+    best_move: Option<String>,
+    depth: Option<u32>,
+    nodes: Option<u64>,
+    candidates: Vec<Candidate>,
+}
+
+impl Analysis {
+    /// Returns the engine's evaluation of the position, from white's point of view.
+    pub fn eval(&self) -> Eval {
+        self.eval
+    }
+
+    /// Returns the move the engine recommends in SAN, or `None` if it didn't give one.
+    pub fn best_move(&self) -> Option<&str> {
+        self.best_move.as_deref()
+    }
+
+    /// Returns the depth the engine reached, if it reported one.
+    pub fn depth(&self) -> Option<u32> {
+        self.depth
+    }
+
+    /// Returns the number of nodes the engine searched, if it reported them.
+    pub fn nodes(&self) -> Option<u64> {
+        self.nodes
+    }
+
+    /// Returns the first move of each line the engine searched, best first. There is more than
+    /// one only when the engine's `MultiPV` option is set above 1.
+    pub fn candidates(&self) -> &[Candidate] {
+        &self.candidates
+    }
+}
+
+/// Converts `score`, given from the point of view of the side to move in `position`, to white's
+/// point of view.
+fn from_white_perspective(position: &Chess, score: Eval) -> Eval {
+    match (position.turn(), score) {
+        (Color::White, score) => score,
+        (Color::Black, Eval::Centipawns(centipawns)) => Eval::Centipawns(-centipawns),
+        (Color::Black, Eval::Mate(moves)) => Eval::Mate(-moves),
+    }
+}
+
+/// Converts `uci`, a move in `position`, to SAN, returning `None` if it isn't legal.
+fn uci_to_san(position: &Chess, uci: &str) -> Option<String> {
+    let legal_move = UciMove::from_ascii(uci.as_bytes())
+        .ok()?
+        .to_move(position)
+        .ok()?;
+
+    Some(SanPlus::from_move(position.clone(), legal_move).to_string())
+}
+
+fn protocol_error(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Formats an optional field of a cache entry, with `-` standing for `None`.
+fn cache_field<T: ToString>(value: Option<T>) -> String {
+    value.map_or_else(|| String::from("-"), |value| value.to_string())
+}
+
+/// Parses an optional field of a cache entry, returning `None` if it's invalid.
+fn parse_cache_field<T: FromStr>(field: &str) -> Option<Option<T>> {
+    if field == "-" {
+        Some(None)
+    } else {
+        field.parse().ok().map(Some)
+    }
+}
+
+/// The key analyses are cached by: the Zobrist hash of the position, the limit it was searched
+/// within, and the number of lines searched at once.
+type CacheKey = (u64, Limit, u32);
+
+/// Parses a line written by `Engine::write_cache`.
+fn parse_cache_entry(line: &str) -> Option<(CacheKey, Analysis)> {
+    let mut fields = line.split_whitespace().collect::<Vec<_>>();
+
+    // Entries written before the number of lines was cached don't have it, but give one
+    // candidate for each line searched.
+    let lines = if fields.get(3) == Some(&"multipv") {
+        let lines = fields.get(4)?.parse::<u32>().ok()?;
+        fields.drain(3..5);
+        Some(lines)
+    } else {
+        None
+    };
+
+    // Any fields after the first eight are candidate moves.
+    if fields.len() < 8 {
+        return None;
+    }
+
+    let hash = u64::from_str_radix(fields[0], 16).ok()?;
+    let limit = Limit::parse_go_arguments(fields[1], fields[2])?;
+    let eval = match fields[3] {
+        "cp" => Eval::Centipawns(fields[4].parse().ok()?),
+        "mate" => Eval::Mate(fields[4].parse().ok()?),
+        _ => return None,
+    };
+    let best_move = parse_cache_field(fields[5])?;
+    let depth = parse_cache_field(fields[6])?;
+    let nodes = parse_cache_field(fields[7])?;
+    let candidates = fields[8..]
+        .iter()
+        .map(|field| Candidate::parse_cache_field(field))
+        .collect::<Option<Vec<_>>>()?;
+    let lines = lines.unwrap_or_else(|| candidates.len().max(1) as u32);
+
+    Some((
+        (hash, limit, lines),
+        Analysis {
+            eval,
+            best_move,
+            depth,
+            nodes,
+            candidates,
+        },
+    ))
+}
+
+/// Parses the score from a UCI `info` line, from the point of view of the side to move.
+fn parse_score(tokens: &[&str]) -> Option<Eval> {
+    let index = tokens.iter().position(|token| *token == "score")?;
+
+    match (tokens.get(index + 1), tokens.get(index + 2)) {
+        (Some(&"cp"), Some(value)) => value.parse().ok().map(Eval::Centipawns),
+        (Some(&"mate"), Some(value)) => value.parse().ok().map(Eval::Mate),
+        _ => None,
+    }
+}
+
+/// Parses the value following `name` in a UCI `info` line, such as the search depth.
+fn parse_field<T: FromStr>(tokens: &[&str], name: &str) -> Option<T> {
+    let index = tokens.iter().position(|token| *token == name)?;
+
+    tokens.get(index + 1)?.parse().ok()
+}
+
+/// A chess engine speaking the UCI protocol.
+///
+/// Analyses are cached by the Zobrist hash of the position, so a position that is searched again
+/// with the same limit and number of lines, such as one reached in several games, is only
+/// searched once. The cache can be saved to disk with `write_cache` and loaded in later runs with
+/// `read_cache`.
+pub struct Engine<R: BufRead, W: Write> {
+    reader: R,
+    writer: W,
+    process: Option<Child>,
+    cache: HashMap<CacheKey, Analysis>,
+    lines: u32,
+    nodes_searched: u64,
+}
+
+impl Engine<BufReader<ChildStdout>, ChildStdin> {
+    /// Starts the engine executable at `path` and waits for it to be ready.
+    pub fn spawn(path: &str) -> io::Result<Engine<BufReader<ChildStdout>, ChildStdin>> {
+        let mut command = Command::new(path);
+        command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null());
+
+        // Ctrl-C in a terminal signals the whole process group. The engine is kept out of it so
+        // that it can finish the search it's on when the caller stops early, and is told to quit
+        // when dropped instead.
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+
+        let mut process = command.spawn()?;
+
+        let reader = BufReader::new(process.stdout.take().unwrap());
+        let writer = process.stdin.take().unwrap();
+
+        let mut engine = Engine::new(reader, writer)?;
+        engine.process = Some(process);
+
+        debug!(path, "Started engine");
+
+        Ok(engine)
+    }
+}
+
+impl<R: BufRead, W: Write> Engine<R, W> {
+    /// Creates a new `Engine` that reads the engine's output from `reader` and sends it commands
+    /// through `writer`, and waits for it to be ready.
+    pub fn new(reader: R, writer: W) -> io::Result<Engine<R, W>> {
+        let mut engine = Engine {
+            reader,
+            writer,
+            process: None,
+            cache: HashMap::new(),
+            lines: 1,
+            nodes_searched: 0,
+        };
+
+        engine.send("uci")?;
+        engine.wait_for("uciok")?;
+        engine.ready()?;
+
+        Ok(engine)
+    }
+
+    /// Sets the engine option `name` to `value`, such as `Threads` or `Hash`. Since options can
+    /// change the engine's analysis, this clears the cache.
+    pub fn set_option(&mut self, name: &str, value: &str) -> io::Result<()> {
+        self.cache.clear();
+        self.send(&format!("setoption name {} value {}", name, value))?;
+        self.ready()
+    }
+
+    /// Sets how many lines the engine searches at once, so that analyses give that many candidate
+    /// moves. This sets the engine's `MultiPV` option.
+    pub fn set_lines(&mut self, lines: u32) -> io::Result<()> {
+        self.set_option("MultiPV", &lines.to_string())?;
+        self.lines = lines;
+        Ok(())
+    }
+
+    /// Returns the number of analyses held in the cache.
+    pub fn cached_analyses(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// Adds the analyses cached by `other` to this engine's cache, so positions `other` has
+    /// already searched aren't searched again.
+    pub fn merge_cache<OR: BufRead, OW: Write>(&mut self, other: &Engine<OR, OW>) {
+        for (key, analysis) in &other.cache {
+            self.cache.entry(*key).or_insert_with(|| analysis.clone());
+        }
+    }
+
+    /// Loads analyses written by `write_cache` into the cache, so positions analysed in earlier
+    /// runs aren't searched again. Analyses searching a different number of lines are kept, so
+    /// they're written back, but never given by `analyse`. Otherwise, the analyses must have been
+    /// made with the same engine and options to be meaningful.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `reader` can't be read, or holds a line that isn't a cache entry.
+    pub fn read_cache<C: Read>(&mut self, reader: C) -> io::Result<()> {
+        for line in BufReader::new(reader).lines() {
+            let line = line?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let (key, analysis) = parse_cache_entry(&line).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid cache entry: {}", line),
+                )
+            })?;
+
+            self.cache.insert(key, analysis);
+        }
+
+        Ok(())
+    }
+
+    /// Writes every analysis in the cache to `writer`, one per line.
+    pub fn write_cache<C: Write>(&self, mut writer: C) -> io::Result<()> {
+        let mut entries = self.cache.iter().collect::<Vec<_>>();
+        // Sorted so the same cache is always written the same way.
+        entries.sort_by_key(|((hash, limit, lines), _)| (*hash, limit.go_arguments(), *lines));
+
+        for ((hash, limit, lines), analysis) in entries {
+            let eval = match analysis.eval {
+                Eval::Centipawns(centipawns) => format!("cp {}", centipawns),
+                Eval::Mate(moves) => format!("mate {}", moves),
+            };
+
+            write!(
+                writer,
+                "{:016x} {} multipv {} {} {} {} {}",
+                hash,
+                limit.go_arguments(),
+                lines,
+                eval,
+                cache_field(analysis.best_move.as_ref()),
+                cache_field(analysis.depth),
+                cache_field(analysis.nodes)
+            )?;
+
+            for candidate in &analysis.candidates {
+                write!(writer, " {}", candidate.to_cache_field())?;
+            }
+
+            writeln!(writer)?;
+        }
+
+        writer.flush()
+    }
+
+    /// Returns the total number of nodes the engine has reported searching. Analyses taken from
+    /// the cache don't add to this.
+    pub fn nodes_searched(&self) -> u64 {
+        self.nodes_searched
+    }
+
+    /// Searches `position` within `limit`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the engine can't be communicated with, or if it finishes searching
+    /// without giving an evaluation.
+    pub fn analyse(&mut self, position: &Chess, limit: Limit) -> io::Result<Analysis> {
+        let key = (zobrist::hash_position(position), limit, self.lines);
+
+        if let Some(analysis) = self.cache.get(&key) {
+            trace!(fen = %position::fen(position), "Found analysis in cache");
+            return Ok(analysis.clone());
+        }
+
+        let analysis = self.search(position, limit)?;
+
+        debug!(
+            fen = %position::fen(position),
+            depth = ?analysis.depth,
+            nodes = ?analysis.nodes,
+            "Searched position"
+        );
+
+        self.cache.insert(key, analysis.clone());
+
+        Ok(analysis)
+    }
+
+    fn search(&mut self, position: &Chess, limit: Limit) -> io::Result<Analysis> {
+        self.send(&format!("position fen {}", position::fen(position)))?;
+        self.send(&format!("go {}", limit.go_arguments()))?;
+
+        let mut score = None;
+        let mut depth = None;
+        let mut nodes = None;
+        // The latest score and first move of each line, by the line's rank.
+        let mut lines: BTreeMap<u32, (Eval, String)> = BTreeMap::new();
+
+        loop {
+            let line = self.read_line()?;
+            let tokens = line.split_whitespace().collect::<Vec<_>>();
+
+            match tokens.first() {
+                Some(&"info") => {
+                    let multipv = parse_field(&tokens, "multipv").unwrap_or(1);
+                    let line_score = parse_score(&tokens);
+
+                    if let (Some(line_score), Some(first_move)) =
+                        (line_score, parse_field::<String>(&tokens, "pv"))
+                    {
+                        lines.insert(multipv, (line_score, first_move));
+                    }
+
+                    // Only the principal line is used for the evaluation.
+                    if multipv == 1 {
+                        score = line_score.or(score);
+                        depth = parse_field(&tokens, "depth").or(depth);
+                        nodes = parse_field(&tokens, "nodes").or(nodes);
+                    }
+                }
+                Some(&"bestmove") => {
+                    let score = score.ok_or_else(|| {
+                        protocol_error(String::from("Engine gave no score for the position"))
+                    })?;
+
+                    let best_move = tokens.get(1).and_then(|uci| uci_to_san(position, uci));
+
+                    let candidates = lines
+                        .values()
+                        .filter_map(|(line_score, first_move)| {
+                            Some(Candidate {
+                                san: uci_to_san(position, first_move)?,
+                                eval: from_white_perspective(position, *line_score),
+                            })
+                        })
+                        .collect();
+
+                    self.nodes_searched += nodes.unwrap_or(0);
+
+                    return Ok(Analysis {
+                        eval: from_white_perspective(position, score),
+                        best_move,
+                        depth,
+                        nodes,
+                        candidates,
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn send(&mut self, command: &str) -> io::Result<()> {
+        trace!(command, "Sending to engine");
+        writeln!(self.writer, "{}", command)?;
+        self.writer.flush()
+    }
+
+    fn read_line(&mut self) -> io::Result<String> {
+        let mut line = String::new();
+
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Engine stopped responding",
+            ));
+        }
+
+        trace!(line = line.trim_end(), "Received from engine");
+
+        Ok(line)
+    }
+
+    fn wait_for(&mut self, response: &str) -> io::Result<()> {
+        while self.read_line()?.trim() != response {}
+
+        Ok(())
+    }
+
+    fn ready(&mut self) -> io::Result<()> {
+        self.send("isready")?;
+        self.wait_for("readyok")
+    }
+}
+
+impl<R: BufRead, W: Write> Drop for Engine<R, W> {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+
+        if let Some(process) = &mut self.process {
+            let _ = process.wait();
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use std::io::Cursor;
+
+    use super::Engine;
+
+    /// An engine that gives scripted responses instead of searching.
+    pub type FakeEngine = Engine<Cursor<Vec<u8>>, Vec<u8>>;
+
+    /// Creates an engine that answers each search with the next of `responses`, each holding
+    /// the raw `info` score (such as `cp 30` or `mate -2`) and best move in UCI.
+    pub fn fake_engine(responses: &[(&str, &str)]) -> FakeEngine {
+        let mut output = String::from("id name Fake\nuciok\nreadyok\n");
+
+        for (score, best_move) in responses {
+            output += &format!(
+                "info depth 10 nodes 1000 score {} pv {}\nbestmove {}\n",
+                score, best_move, best_move
+            );
+        }
+
+        Engine::new(Cursor::new(output.into_bytes()), Vec::new()).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use std::io::Cursor;
+    use std::time::Duration;
+
+    use shakmaty::san::San;
+    use shakmaty::{Chess, Position};
+
+    use super::test_utils::fake_engine;
+    use super::{Engine, Limit};
+    use crate::eval::Eval;
+    use crate::zobrist;
+
+    #[rstest]
+    fn engine_should_send_uci_commands() {
+        let output = "uciok\nreadyok\ninfo depth 1 score cp 20\n\
+                      info depth 12 nodes 5400 score cp 31 pv e2e4\nbestmove e2e4 ponder e7e5\n";
+        let mut writer = Vec::new();
+
+        {
+            let mut engine = Engine::new(Cursor::new(output.as_bytes()), &mut writer).unwrap();
+            let analysis = engine.analyse(&Chess::default(), Limit::Depth(12)).unwrap();
+
+            assert_eq!(analysis.eval(), Eval::Centipawns(31));
+            assert_eq!(analysis.best_move(), Some("e4"));
+            assert_eq!(analysis.depth(), Some(12));
+            assert_eq!(analysis.nodes(), Some(5400));
+            assert_eq!(engine.nodes_searched(), 5400);
+        }
+
+        let commands = String::from_utf8(writer).unwrap();
+
+        assert_eq!(
+            commands,
+            "uci\nisready\n\
+             position fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1\n\
+             go depth 12\nquit\n"
+        );
+    }
+
+    #[rstest]
+    fn engine_should_give_evals_from_white_perspective() {
+        let mut engine = fake_engine(&[("cp 45", "e7e5"), ("mate 2", "e7e5")]);
+
+        let mut position = Chess::default();
+        let e4 = "e4".parse::<San>().unwrap().to_move(&position).unwrap();
+        position.play_unchecked(e4);
+
+        let analysis = engine.analyse(&position, Limit::Nodes(1000)).unwrap();
+
+        assert_eq!(analysis.eval(), Eval::Centipawns(-45));
+        assert_eq!(analysis.best_move(), Some("e5"));
+
+        let analysis = engine.analyse(&position, Limit::Nodes(2000)).unwrap();
+
+        assert_eq!(analysis.eval(), Eval::Mate(-2));
+    }
+
+    #[rstest]
+    fn engine_should_rank_candidates() {
+        let output = "uciok\nreadyok\nreadyok\n\
+                      info depth 8 multipv 1 score cp 20 pv d2d4\n\
+                      info depth 8 multipv 2 score cp 10 pv e2e4\n\
+                      info depth 9 multipv 1 score cp 35 pv e2e4 e7e5\n\
+                      info depth 9 multipv 2 score mate -4 pv f2f3\n\
+                      bestmove e2e4\n";
+        let mut writer = Vec::new();
+
+        {
+            let mut engine = Engine::new(Cursor::new(output.as_bytes()), &mut writer).unwrap();
+            engine.set_lines(2).unwrap();
+
+            let analysis = engine.analyse(&Chess::default(), Limit::Depth(9)).unwrap();
+
+            let candidates = analysis
+                .candidates()
+                .iter()
+                .map(|candidate| (candidate.san(), candidate.eval()))
+                .collect::<Vec<_>>();
+
+            assert_eq!(analysis.eval(), Eval::Centipawns(35));
+            assert_eq!(
+                candidates,
+                vec![("e4", Eval::Centipawns(35)), ("f3", Eval::Mate(-4))]
+            );
+
+            let mut cache = Vec::new();
+            engine.write_cache(&mut cache).unwrap();
+
+            // Analyses of two lines aren't given when searching one.
+            let mut other = fake_engine(&[]);
+            other.read_cache(cache.as_slice()).unwrap();
+
+            assert_eq!(other.cached_analyses(), 1);
+            assert!(other.analyse(&Chess::default(), Limit::Depth(9)).is_err());
+
+            let output = "uciok\nreadyok\nreadyok\n";
+            let mut other = Engine::new(Cursor::new(output.as_bytes()), Vec::new()).unwrap();
+            other.set_lines(2).unwrap();
+            other.read_cache(cache.as_slice()).unwrap();
+
+            assert_eq!(
+                other.analyse(&Chess::default(), Limit::Depth(9)).unwrap(),
+                analysis
+            );
+        }
+
+        let commands = String::from_utf8(writer).unwrap();
+
+        assert!(commands.starts_with("uci\nisready\nsetoption name MultiPV value 2\nisready\n"));
+    }
+
+    #[rstest]
+    fn engine_should_fail_without_score() {
+        let output = "uciok\nreadyok\nbestmove e2e4\n";
+
+        let mut engine = Engine::new(Cursor::new(output.as_bytes()), Vec::new()).unwrap();
+
+        assert!(engine.analyse(&Chess::default(), Limit::Depth(1)).is_err());
+    }
+
+    #[rstest]
+    fn engine_should_cache_analyses() {
+        // Only one search is scripted, so searching again would fail.
+        let mut engine = fake_engine(&[("cp 25", "e2e4")]);
+
+        let first = engine.analyse(&Chess::default(), Limit::Depth(8)).unwrap();
+        let second = engine.analyse(&Chess::default(), Limit::Depth(8)).unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(engine.cached_analyses(), 1);
+        assert_eq!(engine.nodes_searched(), 1000);
+        assert!(engine.analyse(&Chess::default(), Limit::Depth(9)).is_err());
+    }
+
+    #[rstest]
+    fn engine_should_read_written_cache() {
+        let mut engine = fake_engine(&[("cp 25", "e2e4"), ("mate -3", "e7e5")]);
+
+        let mut position = Chess::default();
+        let start = engine.analyse(&position, Limit::Depth(8)).unwrap();
+
+        let e4 = "e4".parse::<San>().unwrap().to_move(&position).unwrap();
+        position.play_unchecked(e4);
+
+        let after_e4 = engine
+            .analyse(&position, Limit::Time(Duration::from_millis(500)))
+            .unwrap();
+
+        let mut cache = Vec::new();
+        engine.write_cache(&mut cache).unwrap();
+
+        // Nothing is scripted, so every analysis must come from the cache.
+        let mut other = fake_engine(&[]);
+        other.read_cache(cache.as_slice()).unwrap();
+
+        assert_eq!(other.cached_analyses(), 2);
+        assert_eq!(
+            other
+                .analyse(&position, Limit::Time(Duration::from_millis(500)))
+                .unwrap(),
+            after_e4
+        );
+        assert_eq!(
+            other.analyse(&Chess::default(), Limit::Depth(8)).unwrap(),
+            start
+        );
+    }
+
+    #[rstest]
+    fn engine_should_reject_invalid_cache() {
+        let mut engine = fake_engine(&[]);
+
+        assert!(engine.read_cache("0123 depth 8 cp".as_bytes()).is_err());
+    }
+
+    #[rstest]
+    fn engine_should_read_cache_written_without_lines() {
+        let mut engine = fake_engine(&[]);
+        let entry = format!(
+            "{:016x} depth 8 cp 25 e4 8 1000 e4,cp,25\n",
+            zobrist::hash_position(&Chess::default())
+        );
+
+        engine.read_cache(entry.as_bytes()).unwrap();
+
+        let analysis = engine.analyse(&Chess::default(), Limit::Depth(8)).unwrap();
+
+        assert_eq!(analysis.eval(), Eval::Centipawns(25));
+        assert_eq!(analysis.candidates().len(), 1);
+    }
+}
@@ -0,0 +1,112 @@
+use std::io;
+
+use crate::clock::TimeControlError;
+use crate::moves::SANError;
+
+/// A `Result` whose errors are chesshound's [`Error`].
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Everything that can go wrong in chesshound. Errors caused by another, such as a query failing
+/// in SQLite, give it as their [`source`](std::error::Error::source).
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// Reading or writing failed.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// A move wasn't in valid algebraic notation.
+    #[error(transparent)]
+    San(#[from] SANError),
+    /// A time control or speed couldn't be parsed.
+    #[error(transparent)]
+    TimeControl(#[from] TimeControlError),
+    /// A game had no Result header, or had `*` because it wasn't finished.
+    #[error("Game has no result")]
+    MissingResult,
+    /// A result wasn't `1-0`, `0-1`, or `1/2-1/2`.
+    #[error("Invalid result: {0}")]
+    InvalidResult(String),
+    /// A title wasn't one of the titles in [`Title`](crate::player::Title).
+    #[error("Invalid title: {0}")]
+    InvalidTitle(String),
+    /// A filter expression given to [`GameFilter::parse`](crate::filter::GameFilter::parse)
+    /// wasn't valid.
+    #[error("Invalid filter {0}")]
+    InvalidFilter(String),
+    /// An argument was out of range, such as asking for zero threads.
+    #[error("{0}")]
+    InvalidArgument(String),
+    /// The threads work is spread across couldn't be started.
+    #[error("Couldn't start threads")]
+    Threads(#[from] rayon::ThreadPoolBuildError),
+    /// A condition given to [`Database::query`](crate::db::Database::query) wasn't valid.
+    #[cfg(feature = "db")]
+    #[error("Invalid condition {condition}")]
+    InvalidCondition {
+        /// The condition as given.
+        condition: String,
+        /// Why SQLite rejected it.
+        source: rusqlite::Error,
+    },
+    /// A database couldn't be read or written.
+    #[cfg(feature = "db")]
+    #[error("Database error")]
+    Database(#[from] rusqlite::Error),
+    /// A table couldn't be built or written as Parquet.
+    #[cfg(feature = "columnar")]
+    #[error("Couldn't write Parquet")]
+    Parquet(#[from] parquet::errors::ParquetError),
+    /// A table couldn't be built or written as Arrow.
+    #[cfg(feature = "columnar")]
+    #[error("Couldn't write Arrow")]
+    Arrow(#[from] arrow_schema::ArrowError),
+}
+
+impl From<Error> for io::Error {
+    /// Turns `error` into an IO error, so that code working in IO errors can use `?` on
+    /// chesshound's. Invalid input such as a bad move gives `InvalidData`, and invalid arguments
+    /// give `InvalidInput`.
+    fn from(error: Error) -> io::Error {
+        let kind = match error {
+            Error::Io(error) => return error,
+            Error::San(_)
+            | Error::TimeControl(_)
+            | Error::MissingResult
+            | Error::InvalidResult(_)
+            | Error::InvalidTitle(_) => io::ErrorKind::InvalidData,
+            Error::InvalidArgument(_) | Error::InvalidFilter(_) => io::ErrorKind::InvalidInput,
+            #[cfg(feature = "db")]
+            Error::InvalidCondition { .. } => io::ErrorKind::InvalidInput,
+            _ => io::ErrorKind::Other,
+        };
+
+        io::Error::new(kind, error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+    use std::io;
+
+    use rstest::*;
+
+    use super::Error;
+    use crate::game::GameResult;
+
+    #[rstest]
+    fn into_io_error_should_keep_kind_and_source() {
+        let error = "*".parse::<GameResult>().unwrap_err();
+        assert_eq!(error.to_string(), "Invalid result: *");
+
+        let error = io::Error::from(error);
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let error = io::Error::from(Error::from(io::Error::new(
+            io::ErrorKind::NotFound,
+            "missing",
+        )));
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        assert!(error.source().is_none());
+    }
+}
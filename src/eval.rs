@@ -0,0 +1,338 @@
+use std::fmt;
+
+use crate::clock::Speed;
+use crate::game::Color;
+
+/// The largest advantage in centipawns counted when comparing evaluations. Anything larger,
+/// including forced mates, is treated as this advantage.
+const CENTIPAWN_CAP: i32 = 1000;
+
+/// How steeply winning chances rise with an advantage in centipawns, as fitted by lichess to games
+/// between players rated 2300 and above.
+const LICHESS_STEEPNESS: f64 = 0.00368208;
+
+/// An evaluation of a position from white's point of view, such as one given by a PGN `%eval`
+/// command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Eval {
+    /// An advantage measured in hundredths of a pawn.
+    Centipawns(i32),
+    /// A forced mate in the given number of moves, negative when black is the one mating.
+    Mate(i32),
+}
+
+impl Eval {
+    /// Returns the evaluation in centipawns from `color`'s point of view, with forced mates and
+    /// advantages over 1000 centipawns capped at 1000.
+    pub fn centipawns_for(self, color: Color) -> i32 {
+        let centipawns = match self {
+            Eval::Centipawns(centipawns) => centipawns.clamp(-CENTIPAWN_CAP, CENTIPAWN_CAP),
+            Eval::Mate(moves) => moves.signum() * CENTIPAWN_CAP,
+        };
+
+        match color {
+            Color::White => centipawns,
+            Color::Black => -centipawns,
+        }
+    }
+
+    /// Returns `color`'s chances of winning from this evaluation, from -1 for a certain loss to 1
+    /// for a certain win.
+    pub fn winning_chances(self, color: Color) -> f64 {
+        WinModel::lichess().winning_chances(self, color)
+    }
+}
+
+/// A model of how likely a player is to win from an evaluation. The same advantage is worth less
+/// to weaker players and at faster time controls, since they are less likely to convert it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WinModel {
+    steepness: f64,
+}
+
+impl WinModel {
+    /// Returns the model lichess uses, which fits players rated 2300 and above with plenty of
+    /// time.
+    pub fn lichess() -> WinModel {
+        WinModel {
+            steepness: LICHESS_STEEPNESS,
+        }
+    }
+
+    /// Returns a model for a player rated `rating` playing at `speed`, if known.
+    ///
+    /// The lichess curve is flattened by up to half for ratings falling from 2300 to 800, and by
+    /// a further fifth at bullet, a tenth at blitz, and a twentieth at rapid.
+    pub fn for_player(rating: u16, speed: Option<Speed>) -> WinModel {
+        let rating_factor = 0.5 + 0.5 * ((rating as f64 - 800.) / 1500.).clamp(0., 1.);
+
+        let speed_factor = match speed {
+            Some(Speed::Bullet) => 0.8,
+            Some(Speed::Blitz) => 0.9,
+            Some(Speed::Rapid) => 0.95,
+            Some(Speed::Classical) | None => 1.,
+        };
+
+        WinModel {
+            steepness: LICHESS_STEEPNESS * rating_factor * speed_factor,
+        }
+    }
+
+    /// Returns `color`'s chances of winning from `eval`, from -1 for a certain loss to 1 for a
+    /// certain win.
+    pub fn winning_chances(&self, eval: Eval, color: Color) -> f64 {
+        (self.steepness * eval.centipawns_for(color) as f64 / 2.).tanh()
+    }
+
+    /// Returns the probability from 0 to 1 that `color` wins from `eval`, counting draws as half
+    /// a win.
+    pub fn win_probability(&self, eval: Eval, color: Color) -> f64 {
+        (1. + self.winning_chances(eval, color)) / 2.
+    }
+
+    /// Returns how much win probability a move by `color` lost by changing the evaluation from
+    /// `before` to `after`, from 0 to 1.
+    pub fn probability_lost(&self, before: Eval, after: Eval, color: Color) -> f64 {
+        (self.win_probability(before, color) - self.win_probability(after, color)).max(0.)
+    }
+}
+
+impl Default for WinModel {
+    fn default() -> WinModel {
+        WinModel::lichess()
+    }
+}
+
+impl fmt::Display for Eval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Eval::Centipawns(centipawns) => write!(f, "{:+.2}", *centipawns as f64 / 100.),
+            Eval::Mate(moves) => write!(f, "#{}", moves),
+        }
+    }
+}
+
+/// How bad a move was, judged by how much it lowered the winning chances of the player who made
+/// it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Judgement {
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl Judgement {
+    /// Judges a move by `color` that changed the evaluation from `before` to `after`, returning
+    /// `None` for moves that were good enough.
+    pub fn of(before: Eval, after: Eval, color: Color) -> Option<Judgement> {
+        let drop = before.winning_chances(color) - after.winning_chances(color);
+
+        if drop >= 0.3 {
+            Some(Judgement::Blunder)
+        } else if drop >= 0.2 {
+            Some(Judgement::Mistake)
+        } else if drop >= 0.1 {
+            Some(Judgement::Inaccuracy)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the PGN numeric annotation glyph for this judgement, which is `$6` (`?!`) for an
+    /// inaccuracy, `$2` (`?`) for a mistake, and `$4` (`??`) for a blunder.
+    pub fn nag(self) -> u8 {
+        match self {
+            Judgement::Inaccuracy => 6,
+            Judgement::Mistake => 2,
+            Judgement::Blunder => 4,
+        }
+    }
+}
+
+/// Returns how many centipawns a move by `color` lost by changing the evaluation from `before` to
+/// `after`.
+pub fn centipawn_loss(before: Eval, after: Eval, color: Color) -> u32 {
+    (before.centipawns_for(color) - after.centipawns_for(color)).max(0) as u32
+}
+
+/// Returns the accuracy of a move by `color` that changed the evaluation from `before` to
+/// `after`, from 0 for a losing blunder to 100 for a move that kept every winning chance.
+pub fn move_accuracy(before: Eval, after: Eval, color: Color) -> f64 {
+    let win_percent = |eval: Eval| 50. + 50. * eval.winning_chances(color);
+    let drop = (win_percent(before) - win_percent(after)).max(0.);
+
+    (103.1668 * (-0.04354 * drop).exp() - 3.1669 + 1.).clamp(0., 100.)
+}
+
+/// Returns the evaluation given by a `[%eval ...]` command within a PGN comment, if any.
+pub fn parse_eval_comment(comment: &str) -> Option<Eval> {
+    let start = comment.find("[%eval ")? + "[%eval ".len();
+    let end = start + comment[start..].find(']')?;
+
+    // Some tools add the search depth after the evaluation, as in `[%eval 0.25,18]`.
+    let eval = comment[start..end].split(',').next()?.trim();
+
+    parse_eval(eval)
+}
+
+/// Parses evaluations of the form `0.25` in pawns or `#-3` for forced mates.
+fn parse_eval(eval: &str) -> Option<Eval> {
+    if let Some(moves) = eval.strip_prefix('#') {
+        moves.parse::<i32>().ok().map(Eval::Mate)
+    } else {
+        let pawns = eval.parse::<f64>().ok()?;
+
+        if pawns.is_finite() {
+            Some(Eval::Centipawns((pawns * 100.).round() as i32))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use float_cmp::approx_eq;
+
+    use super::{centipawn_loss, move_accuracy, parse_eval_comment, Eval, Judgement, WinModel};
+    use crate::clock::Speed;
+    use crate::game::Color;
+
+    #[rstest(
+        comment,
+        expected,
+        case("[%eval 0.25]", Some(Eval::Centipawns(25))),
+        case(" [%eval -1.5] [%clk 0:03:00] ", Some(Eval::Centipawns(-150))),
+        case("[%eval #-3]", Some(Eval::Mate(-3))),
+        case("[%eval 0.17,22]", Some(Eval::Centipawns(17))),
+        case("[%clk 0:03:00]", None),
+        case("[%eval mate]", None)
+    )]
+    fn parse_eval_comment_should_find_evals(comment: &str, expected: Option<Eval>) {
+        assert_eq!(parse_eval_comment(comment), expected);
+    }
+
+    #[rstest(
+        eval,
+        expected,
+        case(Eval::Centipawns(35), "+0.35"),
+        case(Eval::Centipawns(-120), "-1.20"),
+        case(Eval::Mate(3), "#3"),
+        case(Eval::Mate(-2), "#-2")
+    )]
+    fn eval_should_display_like_pgn(eval: Eval, expected: &str) {
+        assert_eq!(eval.to_string(), expected);
+    }
+
+    #[rstest]
+    fn eval_should_cap_mates_and_flip_for_black() {
+        assert_eq!(Eval::Mate(2).centipawns_for(Color::White), 1000);
+        assert_eq!(Eval::Mate(-2).centipawns_for(Color::White), -1000);
+        assert_eq!(Eval::Centipawns(2500).centipawns_for(Color::Black), -1000);
+        assert_eq!(Eval::Centipawns(30).centipawns_for(Color::Black), -30);
+    }
+
+    #[rstest(
+        before,
+        after,
+        color,
+        expected,
+        case(Eval::Centipawns(30), Eval::Centipawns(20), Color::White, None),
+        case(
+            Eval::Centipawns(40),
+            Eval::Centipawns(-200),
+            Color::White,
+            Some(Judgement::Blunder)
+        ),
+        case(
+            Eval::Centipawns(-100),
+            Eval::Centipawns(-10),
+            Color::Black,
+            Some(Judgement::Inaccuracy)
+        ),
+        case(Eval::Mate(-3), Eval::Centipawns(0), Color::Black, Some(Judgement::Blunder))
+    )]
+    fn judgement_should_grade_drops_in_winning_chances(
+        before: Eval,
+        after: Eval,
+        color: Color,
+        expected: Option<Judgement>,
+    ) {
+        assert_eq!(Judgement::of(before, after, color), expected);
+    }
+
+    #[rstest]
+    fn move_accuracy_should_fall_with_lost_centipawns() {
+        let best = move_accuracy(Eval::Centipawns(30), Eval::Centipawns(40), Color::White);
+        let small_loss = move_accuracy(Eval::Centipawns(30), Eval::Centipawns(0), Color::White);
+        let blunder = move_accuracy(Eval::Centipawns(30), Eval::Mate(-1), Color::White);
+
+        assert!(approx_eq!(f64, best, 100., epsilon = 0.00000001));
+        assert!(small_loss < best && small_loss > 85.);
+        assert!(blunder < 10.);
+        assert_eq!(
+            centipawn_loss(Eval::Centipawns(30), Eval::Centipawns(0), Color::White),
+            30
+        );
+    }
+
+    #[rstest]
+    fn win_model_should_match_lichess_curve() {
+        let model = WinModel::lichess();
+
+        assert!(approx_eq!(
+            f64,
+            model.win_probability(Eval::Centipawns(0), Color::White),
+            0.5,
+            epsilon = 0.00000001
+        ));
+        assert!(approx_eq!(
+            f64,
+            model.winning_chances(Eval::Centipawns(300), Color::Black),
+            Eval::Centipawns(300).winning_chances(Color::Black),
+            epsilon = 0.00000001
+        ));
+        assert_eq!(
+            WinModel::for_player(2400, Some(Speed::Classical)),
+            WinModel::lichess()
+        );
+    }
+
+    #[rstest(
+        rating,
+        speed,
+        case(1200, None),
+        case(2300, Some(Speed::Blitz)),
+        case(800, Some(Speed::Bullet))
+    )]
+    fn win_model_should_value_advantages_less_for_weaker_and_faster(
+        rating: u16,
+        speed: Option<Speed>,
+    ) {
+        let eval = Eval::Centipawns(300);
+        let model = WinModel::for_player(rating, speed);
+
+        assert!(
+            model.win_probability(eval, Color::White)
+                < WinModel::lichess().win_probability(eval, Color::White)
+        );
+        assert!(model.win_probability(eval, Color::White) > 0.5);
+    }
+
+    #[rstest]
+    fn win_model_should_measure_probability_lost() {
+        let model = WinModel::lichess();
+
+        let lost =
+            model.probability_lost(Eval::Centipawns(0), Eval::Centipawns(-200), Color::White);
+
+        // tanh(0.368208) / 2 is about 0.176.
+        assert!(approx_eq!(f64, lost, 0.17614, epsilon = 0.0001));
+        assert_eq!(
+            model.probability_lost(Eval::Centipawns(0), Eval::Centipawns(-200), Color::Black),
+            0.
+        );
+    }
+}
@@ -0,0 +1,736 @@
+use std::hash::Hash;
+
+use chrono::Datelike;
+use shakmaty::Position;
+
+use crate::analysis;
+use crate::clock::clock_comment;
+use crate::eval::Eval;
+use crate::game::{
+    GameResult, GiveClocks, GiveDate, GiveEvals, GiveLink, GiveMetadata, GivePlayers, GiveRatings,
+    GiveResult, GiveSource, ListMoves,
+};
+use crate::game_id::GameId;
+use crate::move_tree::MoveTreeView;
+use crate::moves::Move;
+use crate::openings::Openings;
+use crate::player::Player;
+use crate::position;
+use crate::source::Source;
+use crate::stats::statistic::Value;
+
+/// The PGN headers chesshound can write, in the order they're written.
+pub const PGN_HEADERS: [&str; 19] = [
+    "Event",
+    "Site",
+    "Date",
+    "Round",
+    "White",
+    "Black",
+    "Result",
+    "WhiteElo",
+    "BlackElo",
+    "WhiteTitle",
+    "BlackTitle",
+    "UTCDate",
+    "UTCTime",
+    "TimeControl",
+    "ECO",
+    "Termination",
+    "Link",
+    "Source",
+    "SourceId",
+];
+
+/// Returns the value of the PGN header `name` for `game`, or `None` if the header would have no
+/// value. Headers of the seven tag roster are always given, using `?` when their value isn't
+/// known.
+fn header_value<G>(game: &G, name: &str) -> Option<String>
+where
+    G: GiveMetadata + GivePlayers + GiveResult,
+{
+    let metadata = game.metadata();
+
+    match name {
+        "Event" | "Site" | "Round" => Some(metadata.tag(name).unwrap_or_else(|| String::from("?"))),
+        "Date" => Some(
+            metadata
+                .tag(name)
+                .unwrap_or_else(|| String::from("????.??.??")),
+        ),
+        "White" => Some(String::from(game.white_player().unwrap_or("?"))),
+        "Black" => Some(String::from(game.black_player().unwrap_or("?"))),
+        "Result" => Some(game.result().to_string()),
+        _ => metadata.tag(name),
+    }
+}
+
+/// Returns `game` as PGN with the headers named in `headers`, in the order of
+/// [`PGN_HEADERS`], followed by the extra tags of the game that are named in `headers`. Optional
+/// headers whose values aren't known are left out. Clock times and evaluations are kept as
+/// `%clk` and `%eval` comments.
+pub fn to_pgn<M, G>(game: &G, headers: &[&str]) -> String
+where
+    M: Clone + Move,
+    G: GiveClocks + GiveEvals + GiveMetadata + GivePlayers + GiveResult + ListMoves<M>,
+{
+    let mut output = String::new();
+
+    let extra_tags = game
+        .metadata()
+        .extra_tags()
+        .iter()
+        .filter(|(name, _)| headers.contains(&name.as_str()))
+        .map(|(name, value)| (name.as_str(), Some(value.clone())));
+
+    for (name, value) in PGN_HEADERS
+        .iter()
+        .filter(|name| headers.contains(name))
+        .map(|name| (*name, header_value(game, name)))
+        .chain(extra_tags)
+    {
+        if let Some(value) = value {
+            output += &format!("[{} \"{}\"]\n", name, value.replace('"', "\\\""));
+        }
+    }
+
+    let mut tokens = Vec::new();
+    let mut repeat_number = false;
+
+    for (ply, move_) in game.list_moves().enumerate() {
+        if ply % 2 == 0 {
+            tokens.push(format!("{}.", ply / 2 + 1));
+        } else if repeat_number {
+            tokens.push(format!("{}...", ply / 2 + 1));
+        }
+
+        tokens.push(move_.to_algebraic());
+
+        let commands = [
+            game.evals()
+                .get(ply)
+                .copied()
+                .flatten()
+                .map(|eval| match eval {
+                    Eval::Centipawns(centipawns) => {
+                        format!("[%eval {:.2}]", centipawns as f64 / 100.)
+                    }
+                    Eval::Mate(moves) => format!("[%eval #{}]", moves),
+                }),
+            game.clocks().get(ply).copied().flatten().map(clock_comment),
+        ]
+        .iter()
+        .flatten()
+        .cloned()
+        .collect::<Vec<_>>();
+
+        // A comment after white's move means black's move needs its number repeated.
+        repeat_number = !commands.is_empty();
+
+        if repeat_number {
+            tokens.push(format!("{{ {} }}", commands.join(" ")));
+        }
+    }
+
+    tokens.push(game.result().to_string());
+
+    output += "\n";
+    output += &analysis::wrap_tokens(&tokens);
+    output += "\n";
+
+    output
+}
+
+/// Returns `game` in chesshound's JSON schema, which keeps everything chesshound reads from PGN:
+///
+/// ```text
+/// {"id":"edac7cf174fd77c4","white":{"name":"alice","rating":1500,"title":"FM"},
+///  "black":{"name":"bob","rating":null,"title":null},
+///  "result":"1-0","event":"Rated Blitz game","site":null,"round":null,"date":"2021-06-15",
+///  "time":"12:00:00","time_control":"180+2","eco":"C20","termination":"Normal",
+///  "link":null,"source":null,"source_id":null,"tags":{"Variant":"Standard"},
+///  "moves":["e4","e5"],"clocks":[179.5,null],"evals":[{"cp":17},{"mate":-3}]}
+/// ```
+///
+/// The `id` is the game's [`GameId`]. Clocks are given in seconds, and evaluations from white's
+/// point of view. Unknown values are `null`, and `tags` holds the PGN tags chesshound doesn't
+/// understand.
+pub fn to_json<M, G>(game: &G) -> Value
+where
+    M: Clone + Move,
+    G: GiveClocks
+        + GiveDate
+        + GiveEvals
+        + GiveLink
+        + GiveMetadata
+        + GivePlayers
+        + GiveRatings
+        + GiveResult
+        + GiveSource
+        + ListMoves<M>,
+{
+    let player = |player: &Player| {
+        Value::map(vec![
+            ("name", player.name().into()),
+            (
+                "rating",
+                player
+                    .rating()
+                    .map_or(Value::Missing, |rating| Value::Count(rating.into())),
+            ),
+            (
+                "title",
+                player
+                    .title()
+                    .map_or(Value::Missing, |title| Value::Text(title.to_string())),
+            ),
+        ])
+    };
+
+    let text = |text: Option<String>| text.map_or(Value::Missing, Value::Text);
+    let metadata = game.metadata();
+
+    Value::map(vec![
+        ("id", Value::Text(GameId::of(game).to_string())),
+        ("white", player(game.white())),
+        ("black", player(game.black())),
+        ("result", Value::Text(game.result().to_string())),
+        ("event", text(metadata.event().map(String::from))),
+        ("site", text(metadata.site().map(String::from))),
+        ("round", text(metadata.round().map(String::from))),
+        ("date", text(game.date().map(|date| date.to_string()))),
+        ("time", text(game.time().map(|time| time.to_string()))),
+        (
+            "time_control",
+            text(
+                game.time_control()
+                    .map(|time_control| time_control.to_string()),
+            ),
+        ),
+        ("eco", text(metadata.eco().map(String::from))),
+        (
+            "termination",
+            text(metadata.termination().map(String::from)),
+        ),
+        ("link", text(game.link().map(String::from))),
+        (
+            "source",
+            text(game.source().map(|source| source.to_string())),
+        ),
+        ("source_id", text(game.source_id())),
+        (
+            "tags",
+            Value::Map(
+                metadata
+                    .extra_tags()
+                    .iter()
+                    .map(|(name, value)| (name.clone(), Value::Text(value.clone())))
+                    .collect(),
+            ),
+        ),
+        (
+            "moves",
+            Value::List(
+                game.list_moves()
+                    .map(|move_| Value::Text(move_.to_algebraic()))
+                    .collect(),
+            ),
+        ),
+        (
+            "clocks",
+            Value::List(
+                game.clocks()
+                    .iter()
+                    .map(|clock| clock.map_or(Value::Missing, |clock| clock.as_secs_f64().into()))
+                    .collect(),
+            ),
+        ),
+        (
+            "evals",
+            Value::List(
+                game.evals()
+                    .iter()
+                    .map(|eval| match eval {
+                        Some(Eval::Centipawns(centipawns)) => {
+                            Value::map(vec![("cp", Value::Number(*centipawns as f64))])
+                        }
+                        Some(Eval::Mate(moves)) => {
+                            Value::map(vec![("mate", Value::Number(*moves as f64))])
+                        }
+                        None => Value::Missing,
+                    })
+                    .collect(),
+            ),
+        ),
+    ])
+}
+
+/// Returns how the lichess game export API would describe the way `game` ended, from its
+/// Termination tag and final position, as lichess's own PGN exports map their status to the tag.
+fn lichess_status<M, G>(game: &G) -> &'static str
+where
+    M: Clone + Move,
+    G: GiveMetadata + GiveResult + ListMoves<M>,
+{
+    match game.metadata().termination() {
+        Some("Time forfeit") => return "outoftime",
+        Some("Abandoned") => return "timeout",
+        Some("Rules infraction") => return "cheat",
+        _ => {}
+    }
+
+    let position = position::replay(game.list_moves()).pop();
+
+    match game.result() {
+        _ if position.as_ref().is_some_and(Position::is_checkmate) => "mate",
+        _ if position.as_ref().is_some_and(Position::is_stalemate) => "stalemate",
+        GameResult::Draw => "draw",
+        GameResult::WhiteWon | GameResult::BlackWon => "resign",
+    }
+}
+
+/// Returns `game` in the style of the lichess game export API's NDJSON, so tools built for
+/// lichess exports can read it. Only the fields chesshound can fill are given, and fields with
+/// unknown values are left out as lichess does.
+///
+/// Everything [`input::lichess_json_to_pgn`](crate::input::lichess_json_to_pgn) reads back is
+/// kept: the lichess game ID of games from lichess, which gives back their site, whether a
+/// lichess-style event such as `Rated Blitz game` was rated, the status that gives back the
+/// termination, and clock times and evaluations.
+pub fn to_lichess_json<M, G>(game: &G) -> Value
+where
+    M: Clone + Move,
+    G: GiveClocks
+        + GiveDate
+        + GiveEvals
+        + GiveMetadata
+        + GivePlayers
+        + GiveRatings
+        + GiveResult
+        + GiveSource
+        + ListMoves<M>,
+{
+    // Players whose name isn't known are given without a user, as lichess gives anonymous players.
+    let player = |player: &Player| {
+        let mut entries = Vec::new();
+
+        if let Some(name) = player.name() {
+            let mut user = vec![("name", Value::Text(String::from(name)))];
+
+            if let Some(title) = player.title() {
+                user.push(("title", Value::Text(title.to_string())));
+            }
+
+            entries.push(("user", Value::map(user)));
+        }
+
+        if let Some(rating) = player.rating() {
+            entries.push(("rating", Value::Count(rating.into())));
+        }
+
+        Value::map(entries)
+    };
+
+    let mut entries = Vec::new();
+
+    if let (Some(Source::Lichess), Some(id)) = (game.source(), game.source_id()) {
+        entries.push(("id", Value::Text(id)));
+    }
+
+    let event = game.metadata().event().unwrap_or_default();
+
+    if event.starts_with("Rated ") || event.starts_with("Casual ") {
+        entries.push(("rated", Value::Bool(event.starts_with("Rated "))));
+    }
+
+    if let Some(time_control) = game.time_control() {
+        entries.push(("speed", Value::Text(time_control.speed().to_string())));
+    }
+
+    if let Some(date) = game.date() {
+        let started = date.and_time(game.time().unwrap_or_default());
+
+        entries.push((
+            "createdAt",
+            Value::Number(started.and_utc().timestamp_millis() as f64),
+        ));
+    }
+
+    entries.push(("status", Value::Text(String::from(lichess_status(game)))));
+
+    entries.push((
+        "players",
+        Value::map(vec![
+            ("white", player(game.white())),
+            ("black", player(game.black())),
+        ]),
+    ));
+
+    let winner = match game.result() {
+        GameResult::WhiteWon => Some("white"),
+        GameResult::BlackWon => Some("black"),
+        GameResult::Draw => None,
+    };
+
+    if let Some(winner) = winner {
+        entries.push(("winner", Value::Text(String::from(winner))));
+    }
+
+    entries.push((
+        "moves",
+        Value::Text(
+            game.list_moves()
+                .map(Move::to_algebraic)
+                .collect::<Vec<_>>()
+                .join(" "),
+        ),
+    ));
+
+    // Clocks are in centiseconds, and evaluations in centipawns from white's point of view.
+    if game.clocks().iter().any(Option::is_some) {
+        entries.push((
+            "clocks",
+            Value::List(
+                game.clocks()
+                    .iter()
+                    .map(|clock| {
+                        clock.map_or(Value::Missing, |clock| {
+                            Value::Count((clock.as_millis() / 10) as usize)
+                        })
+                    })
+                    .collect(),
+            ),
+        ));
+    }
+
+    if game.evals().iter().any(Option::is_some) {
+        entries.push((
+            "analysis",
+            Value::List(
+                game.evals()
+                    .iter()
+                    .map(|eval| match eval {
+                        Some(Eval::Centipawns(centipawns)) => {
+                            Value::map(vec![("eval", Value::Number(*centipawns as f64))])
+                        }
+                        Some(Eval::Mate(moves)) => {
+                            Value::map(vec![("mate", Value::Number(*moves as f64))])
+                        }
+                        None => Value::Missing,
+                    })
+                    .collect(),
+            ),
+        ));
+    }
+
+    if let Some(time_control) = game.time_control() {
+        entries.push((
+            "clock",
+            Value::map(vec![
+                (
+                    "initial",
+                    Value::Count(time_control.base().as_secs() as usize),
+                ),
+                (
+                    "increment",
+                    Value::Count(time_control.increment().as_secs() as usize),
+                ),
+            ]),
+        ));
+    }
+
+    Value::map(entries)
+}
+
+/// The number of games the lichess opening explorer lists under `topGames` and `recentGames`.
+const EXPLORER_EXAMPLE_GAMES: usize = 4;
+
+/// Returns the moves played after `view`, which is reached by `line`, in the schema of the lichess
+/// opening explorer API, so frontends and tools built for the explorer can show stats from a
+/// personal database. Moves played in fewer than `min_games` games are left out, and the rest
+/// come most played first. Games are identified by the last part of their link, as lichess game
+/// IDs are, and `topGames` holds the games between the highest rated players.
+pub fn to_explorer_json<M, G>(view: &MoveTreeView<'_, M, G>, line: &[M], min_games: usize) -> Value
+where
+    M: Clone + Move + Eq + Hash,
+    G: GiveClocks + GiveDate + GiveLink + GivePlayers + GiveRatings + GiveResult + ListMoves<M>,
+{
+    let position = position::replay(line.iter().cloned())
+        .pop()
+        .unwrap_or_default();
+    let uci = |move_: Option<M>| match move_ {
+        Some(move_) => position::to_uci(&position, move_).into(),
+        None => Value::Missing,
+    };
+    let opening = |line: &[M]| match Openings::embedded().classify_moves(line.iter().cloned()) {
+        Some(opening) => Value::map(vec![
+            ("eco", opening.eco().into()),
+            ("name", opening.name().into()),
+        ]),
+        None => Value::Missing,
+    };
+    let game_entry = |game: &G| {
+        let player = |player: &Player| {
+            Value::map(vec![
+                ("name", player.name().into()),
+                ("rating", player.rating().map(usize::from).into()),
+            ])
+        };
+        let winner = match game.result() {
+            GameResult::WhiteWon => Value::from("white"),
+            GameResult::BlackWon => Value::from("black"),
+            GameResult::Draw => Value::Missing,
+        };
+
+        Value::map(vec![
+            ("uci", uci(game.list_moves().nth(line.len()))),
+            (
+                "id",
+                game.link()
+                    .and_then(|link| link.trim_end_matches('/').rsplit('/').next())
+                    .into(),
+            ),
+            ("winner", winner),
+            (
+                "speed",
+                game.time_control()
+                    .map(|time_control| time_control.speed().to_string())
+                    .into(),
+            ),
+            ("white", player(game.white())),
+            ("black", player(game.black())),
+            ("year", game.date().map(|date| date.year() as usize).into()),
+            (
+                "month",
+                game.date()
+                    .map(|date| date.format("%Y-%m").to_string())
+                    .into(),
+            ),
+        ])
+    };
+    let results = |games: &[&G]| {
+        let count = |result: GameResult| {
+            games
+                .iter()
+                .filter(|game| game.result() == result)
+                .count()
+                .into()
+        };
+
+        vec![
+            ("white", count(GameResult::WhiteWon)),
+            ("draws", count(GameResult::Draw)),
+            ("black", count(GameResult::BlackWon)),
+        ]
+    };
+
+    let mut children = view
+        .children()
+        .into_iter()
+        .map(|(move_, child)| {
+            let san = move_.clone().to_algebraic();
+            (san, move_, child.iter().collect::<Vec<_>>())
+        })
+        .filter(|(_, _, games)| games.len() >= min_games)
+        .collect::<Vec<_>>();
+    children.sort_by(|(a, _, a_games), (b, _, b_games)| {
+        b_games.len().cmp(&a_games.len()).then_with(|| a.cmp(b))
+    });
+
+    let moves = children
+        .into_iter()
+        .map(|(san, move_, games)| {
+            let ratings = games
+                .iter()
+                .filter_map(|game| Some((game.white().rating()?, game.black().rating()?)))
+                .map(|(white, black)| (f64::from(white) + f64::from(black)) / 2.)
+                .collect::<Vec<_>>();
+            let average_rating = if ratings.is_empty() {
+                Value::Missing
+            } else {
+                Value::Count((ratings.iter().sum::<f64>() / ratings.len() as f64).round() as usize)
+            };
+            let mut child_line = line.to_vec();
+            child_line.push(move_.clone());
+
+            let mut entries = vec![
+                ("uci", uci(Some(move_.clone()))),
+                ("san", san.into()),
+                ("averageRating", average_rating),
+            ];
+            entries.extend(results(&games));
+            entries.push((
+                "game",
+                match games.as_slice() {
+                    [game] => game_entry(game),
+                    _ => Value::Missing,
+                },
+            ));
+            entries.push(("opening", opening(&child_line)));
+
+            Value::map(entries)
+        })
+        .collect::<Vec<_>>();
+
+    let games = view.iter().collect::<Vec<_>>();
+    let examples = view.examples(EXPLORER_EXAMPLE_GAMES);
+    let mut entries = results(&games);
+
+    entries.push(("moves", Value::List(moves)));
+    entries.push((
+        "topGames",
+        Value::List(
+            examples
+                .highest_rated()
+                .iter()
+                .map(|game| game_entry(game))
+                .collect(),
+        ),
+    ));
+    entries.push((
+        "recentGames",
+        Value::List(
+            examples
+                .most_recent()
+                .iter()
+                .map(|game| game_entry(game))
+                .collect(),
+        ),
+    ));
+    entries.push(("opening", opening(line)));
+
+    Value::map(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::{to_explorer_json, to_json, to_lichess_json, to_pgn, PGN_HEADERS};
+    use crate::game::test_utils::*;
+    use crate::game::Game;
+    use crate::game::{GiveClocks, GiveEvals, GiveLink, GiveMetadata};
+    use crate::input::lichess_json_to_pgn;
+    use crate::move_tree::MoveTree;
+    use crate::moves::Move;
+    use crate::AlgebraicMove;
+
+    #[fixture]
+    fn game() -> Game<AlgebraicMove> {
+        parse_game(
+            "[Event \"Rated Blitz game\"]\n[White \"alice\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n\
+             [UTCDate \"2021.06.15\"]\n[UTCTime \"12:00:00\"]\n[WhiteElo \"1500\"]\n\
+             [TimeControl \"180+2\"]\n[ECO \"C20\"]\n[Variant \"Standard\"]\n[WhiteTitle \"FM\"]\n\
+             1. e4 { [%eval 0.17] [%clk 0:03:00] } 1... e5 { [%clk 0:02:59.5] } 2. Qh5 Nc6 \
+             3. Bc4 Nf6 { [%eval #1] } 4. Qxf7# 1-0",
+        )
+    }
+
+    #[rstest]
+    fn to_pgn_should_round_trip(game: Game<AlgebraicMove>) {
+        let pgn = to_pgn(&game, &[&PGN_HEADERS[..], &["Variant"]].concat());
+
+        assert_eq!(
+            pgn,
+            "[Event \"Rated Blitz game\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n\
+             [White \"alice\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n[WhiteElo \"1500\"]\n\
+             [WhiteTitle \"FM\"]\n[UTCDate \"2021.06.15\"]\n[UTCTime \"12:00:00\"]\n[TimeControl \"180+2\"]\n\
+             [ECO \"C20\"]\n[Variant \"Standard\"]\n\n1. e4 { [%eval 0.17] [%clk 0:03:00] } 1... e5 \
+             { [%clk 0:02:59.5] } 2. Qh5 Nc6 3.\nBc4 Nf6 { [%eval #1] } 4. Qxf7# 1-0\n"
+        );
+
+        let reparsed = parse_game(&pgn);
+
+        assert_eq!(to_json(&reparsed), to_json(&game));
+    }
+
+    #[rstest]
+    fn to_pgn_should_write_only_selected_headers(game: Game<AlgebraicMove>) {
+        let pgn = to_pgn(&game, &["Variant", "Result", "BlackElo", "White"]);
+
+        assert!(
+            pgn.starts_with("[White \"alice\"]\n[Result \"1-0\"]\n[Variant \"Standard\"]\n\n1. e4")
+        );
+    }
+
+    #[rstest]
+    fn to_json_should_keep_everything_parsed(game: Game<AlgebraicMove>) {
+        assert_eq!(
+            to_json(&game).to_json(),
+            "{\"id\":\"d96a668eb4fb7ef9\",\"white\":{\"name\":\"alice\",\"rating\":1500,\"title\":\"FM\"},\
+             \"black\":{\"name\":\"bob\",\"rating\":null,\"title\":null},\"result\":\"1-0\",\
+             \"event\":\"Rated Blitz game\",\"site\":null,\"round\":null,\
+             \"date\":\"2021-06-15\",\"time\":\"12:00:00\",\"time_control\":\"180+2\",\
+             \"eco\":\"C20\",\"termination\":null,\"link\":null,\
+             \"source\":null,\"source_id\":null,\"tags\":{\"Variant\":\"Standard\"},\
+             \"moves\":[\"e4\",\"e5\",\"Qh5\",\"Nc6\",\"Bc4\",\"Nf6\",\"Qxf7#\"],\
+             \"clocks\":[180,179.5,null,null,null,null,null],\
+             \"evals\":[{\"cp\":17},null,null,null,null,{\"mate\":1},null]}"
+        );
+    }
+
+    #[rstest]
+    fn to_lichess_json_should_match_lichess_exports(game: Game<AlgebraicMove>) {
+        assert_eq!(
+            to_lichess_json(&game).to_json(),
+            "{\"rated\":true,\"speed\":\"blitz\",\"createdAt\":1623758400000,\"status\":\"mate\",\
+             \"players\":{\"white\":{\"user\":{\"name\":\"alice\",\"title\":\"FM\"},\"rating\":1500},\
+             \"black\":{\"user\":{\"name\":\"bob\"}}},\"winner\":\"white\",\
+             \"moves\":\"e4 e5 Qh5 Nc6 Bc4 Nf6 Qxf7#\",\
+             \"clocks\":[18000,17950,null,null,null,null,null],\
+             \"analysis\":[{\"eval\":17},null,null,null,null,{\"mate\":1},null],\
+             \"clock\":{\"initial\":180,\"increment\":2}}"
+        );
+    }
+
+    #[rstest]
+    fn to_lichess_json_should_read_back_the_same_game() {
+        let game = parse_game(
+            "[Event \"Casual Rapid game\"]\n[Site \"https://lichess.org/abcd1234\"]\n\
+             [White \"alice\"]\n[Black \"bob\"]\n[Result \"0-1\"]\n[TimeControl \"600+0\"]\n\
+             [Termination \"Time forfeit\"]\n\n\
+             1. e4 { [%eval 0.25] [%clk 0:10:00] } 1... e5 { [%eval #-3] [%clk 0:09:58.4] } 0-1",
+        );
+
+        let json = serde_json::from_str(&to_lichess_json(&game).to_json()).unwrap();
+        let reparsed = parse_game(&lichess_json_to_pgn(&json).unwrap());
+
+        assert_eq!(reparsed.clocks(), game.clocks());
+        assert_eq!(reparsed.evals(), game.evals());
+        assert_eq!(reparsed.link(), Some("https://lichess.org/abcd1234"));
+        assert_eq!(reparsed.metadata().event(), Some("Casual Rapid game"));
+        assert_eq!(reparsed.metadata().termination(), Some("Time forfeit"));
+    }
+
+    #[rstest]
+    fn to_explorer_json_should_match_the_lichess_explorer(game: Game<AlgebraicMove>) {
+        let games = vec![
+            game,
+            parse_game(
+                "[White \"bob\"]\n[Black \"carol\"]\n[Result \"1/2-1/2\"]\n\
+                 [WhiteElo \"1600\"]\n[BlackElo \"1800\"]\n\
+                 [Link \"https://lichess.org/abcd1234\"]\n1. e4 c5 1/2-1/2",
+            ),
+            parse_game("[Result \"0-1\"]\n1. e4 e5 2. Nf3 0-1"),
+        ];
+        let move_tree = MoveTree::new(games);
+        let line = vec![AlgebraicMove::from_algebraic(String::from("e4"))];
+        let view = move_tree.view().with_next(&line[0]);
+
+        let explorer = to_explorer_json(&view, &line, 1).to_json();
+
+        assert!(explorer.starts_with(
+            "{\"white\":1,\"draws\":1,\"black\":1,\"moves\":[{\"uci\":\"e7e5\",\"san\":\"e5\",\
+             \"averageRating\":null,\"white\":1,\"draws\":0,\"black\":1,\"game\":null,\
+             \"opening\":{\"eco\":\"C20\",\"name\":\"King's Pawn Game\"}},\
+             {\"uci\":\"c7c5\",\"san\":\"c5\",\"averageRating\":1700,\"white\":0,\"draws\":1,\
+             \"black\":0,\"game\":{\"uci\":\"c7c5\",\"id\":\"abcd1234\",\"winner\":null,"
+        ));
+        assert!(explorer.contains(",\"topGames\":[{\"uci\":\"c7c5\",\"id\":\"abcd1234\""));
+        assert!(explorer.contains("}],\"recentGames\":[{\"uci\":\"e7e5\""));
+        assert!(explorer.ends_with("\"opening\":{\"eco\":\"B00\",\"name\":\"King's Pawn Game\"}}"));
+        assert!(to_explorer_json(&view, &line, 2)
+            .to_json()
+            .contains("\"name\":\"King's Pawn Game\"}}],\"topGames\""));
+    }
+}
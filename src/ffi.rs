@@ -0,0 +1,223 @@
+//! The C API, declared in `include/chesshound.h`. To build it as a library other languages can
+//! link to, run:
+//!
+//! ```bash
+//! cargo rustc --release --lib --features ffi --crate-type cdylib
+//! ```
+//!
+//! After changing this module, regenerate the header with `cbindgen --output include/chesshound.h`.
+
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+use crate::parsing;
+use crate::stats::statistic::{ResultRates, Statistic};
+use crate::{AlgebraicMove, Game, Move, MoveTree};
+
+/// Games parsed from PGN and arranged by their moves. Made by [`chesshound_games_parse`] and
+/// freed by [`chesshound_games_free`].
+pub struct ChesshoundGames {
+    tree: MoveTree<AlgebraicMove, Game<AlgebraicMove>>,
+    length: usize,
+}
+
+/// How often each result happened in a set of games. The rates are fractions of `games`, and are
+/// all zero when `games` is zero.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ChesshoundWinRates {
+    /// The number of games.
+    pub games: usize,
+    /// The fraction of games white won.
+    pub white_wins: f64,
+    /// The fraction of games drawn.
+    pub draws: f64,
+    /// The fraction of games black won.
+    pub black_wins: f64,
+}
+
+/// Parses the `length` bytes of PGN at `pgn`, returning the games, or null if the PGN can't be
+/// read or a game has no result. The games must be freed with [`chesshound_games_free`].
+///
+/// # Safety
+///
+/// `pgn` must point to `length` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chesshound_games_parse(
+    pgn: *const u8,
+    length: usize,
+) -> *mut ChesshoundGames {
+    if pgn.is_null() {
+        return ptr::null_mut();
+    }
+
+    let pgn = slice::from_raw_parts(pgn, length);
+
+    // Panics can't unwind into C, so they're reported as failures too.
+    match panic::catch_unwind(|| parsing::read_games(pgn)) {
+        Ok(Ok(games)) => Box::into_raw(Box::new(ChesshoundGames {
+            length: games.len(),
+            tree: MoveTree::new(games),
+        })),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Returns the number of games in `games`.
+///
+/// # Safety
+///
+/// `games` must have been returned by [`chesshound_games_parse`] and not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn chesshound_games_len(games: *const ChesshoundGames) -> usize {
+    games.as_ref().map_or(0, |games| games.length)
+}
+
+/// Writes the win rates of the games in `games` that start with `moves` to `rates`, returning
+/// whether they could be written. `moves` is a string of moves in SAN separated by spaces, such
+/// as `"e4 c5 Nf3"`, and may be empty for the rates of every game. Returns false without writing
+/// anything if one of the moves isn't valid SAN.
+///
+/// # Safety
+///
+/// `games` must have been returned by [`chesshound_games_parse`] and not yet freed, `moves` must
+/// be a null terminated string, and `rates` must point to a writable `ChesshoundWinRates`.
+#[no_mangle]
+pub unsafe extern "C" fn chesshound_games_win_rates(
+    games: *const ChesshoundGames,
+    moves: *const c_char,
+    rates: *mut ChesshoundWinRates,
+) -> bool {
+    let (games, rates) = match (games.as_ref(), rates.as_mut()) {
+        (Some(games), Some(rates)) if !moves.is_null() => (games, rates),
+        _ => return false,
+    };
+
+    let moves = match CStr::from_ptr(moves).to_str() {
+        Ok(moves) => moves,
+        Err(_) => return false,
+    };
+
+    // Panics can't unwind into C, so they're reported as failures too.
+    let result = panic::catch_unwind(AssertUnwindSafe(|| win_rates(games, moves)));
+
+    match result {
+        Ok(Some(win_rates)) => {
+            *rates = win_rates;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Frees `games`. Does nothing if `games` is null.
+///
+/// # Safety
+///
+/// `games` must have been returned by [`chesshound_games_parse`] and not already freed.
+#[no_mangle]
+pub unsafe extern "C" fn chesshound_games_free(games: *mut ChesshoundGames) {
+    if !games.is_null() {
+        drop(Box::from_raw(games));
+    }
+}
+
+/// Returns the win rates of the games in `games` that start with `moves`, or `None` if one of
+/// the moves isn't valid SAN.
+fn win_rates(games: &ChesshoundGames, moves: &str) -> Option<ChesshoundWinRates> {
+    let mut view = games.tree.view();
+
+    for move_ in moves.split_whitespace() {
+        view = view.with_next(&AlgebraicMove::try_from_algebraic(String::from(move_)).ok()?);
+    }
+
+    let mut results = ResultRates::new();
+
+    for game in view.iter() {
+        results.accumulate(game);
+    }
+
+    let (white_wins, black_wins, draws) = results.rates().unwrap_or((0., 0., 0.));
+
+    Some(ChesshoundWinRates {
+        games: results.games(),
+        white_wins,
+        draws,
+        black_wins,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+    use std::ptr;
+
+    use rstest::*;
+
+    use super::{
+        chesshound_games_free, chesshound_games_len, chesshound_games_parse,
+        chesshound_games_win_rates, ChesshoundWinRates,
+    };
+
+    const PGN: &[u8] = b"[White \"alice\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n\n1. e4 c5 1-0\n\n\
+                         [White \"bob\"]\n[Black \"alice\"]\n[Result \"0-1\"]\n\n1. e4 e5 0-1\n\n\
+                         [White \"carol\"]\n[Black \"alice\"]\n[Result \"1/2-1/2\"]\n\n1. d4 1/2-1/2\n";
+
+    #[rstest]
+    fn win_rates_should_count_games_starting_with_moves() {
+        unsafe {
+            let games = chesshound_games_parse(PGN.as_ptr(), PGN.len());
+            let mut rates = ChesshoundWinRates::default();
+
+            assert_eq!(chesshound_games_len(games), 3);
+
+            let moves = CString::new("e4").unwrap();
+            assert!(chesshound_games_win_rates(
+                games,
+                moves.as_ptr(),
+                &mut rates
+            ));
+            assert_eq!(
+                rates,
+                ChesshoundWinRates {
+                    games: 2,
+                    white_wins: 0.5,
+                    draws: 0.,
+                    black_wins: 0.5,
+                }
+            );
+
+            let moves = CString::new("c4").unwrap();
+            assert!(chesshound_games_win_rates(
+                games,
+                moves.as_ptr(),
+                &mut rates
+            ));
+            assert_eq!(rates, ChesshoundWinRates::default());
+
+            assert!(!chesshound_games_win_rates(games, ptr::null(), &mut rates));
+
+            let moves = CString::new("e4 zz").unwrap();
+            assert!(!chesshound_games_win_rates(
+                games,
+                moves.as_ptr(),
+                &mut rates
+            ));
+
+            chesshound_games_free(games);
+        }
+    }
+
+    #[rstest]
+    fn parse_should_fail_without_headers() {
+        let pgn = b"1. e4 e5 *";
+
+        unsafe {
+            assert!(chesshound_games_parse(pgn.as_ptr(), pgn.len()).is_null());
+            assert!(chesshound_games_parse(ptr::null(), 0).is_null());
+        }
+    }
+}
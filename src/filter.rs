@@ -0,0 +1,1144 @@
+use std::fmt;
+
+use chrono::NaiveDate;
+
+use crate::clock::{Speed, TimeControl};
+use crate::error::{Error, Result};
+use crate::game::{
+    Color, GiveClocks, GiveDate, GivePlayers, GiveRatings, GiveResult, GiveSource, ListMoves,
+    PlayerResult,
+};
+use crate::moves::Move;
+use crate::openings::Openings;
+use crate::source::Source;
+
+/// A condition that decides which games to include in a set of games.
+///
+/// Filters are built from simple conditions, such as who played a game, and combined with
+/// [`and`](GameFilter::and), [`or`](GameFilter::or), and [`not`](GameFilter::not). Each filter
+/// keeps a description of its condition, so that output can say which games it covers.
+///
+/// # Examples
+///
+/// ```
+/// use chesshound::filter::GameFilter;
+/// use chesshound::game::{Color, PlayerResult};
+/// use chesshound::{AlgebraicMove, Game};
+///
+/// let filter = GameFilter::<Game<AlgebraicMove>>::color("magnus", Color::Black)
+///     .and(GameFilter::result("magnus", PlayerResult::Lost));
+///
+/// assert_eq!(filter.to_string(), "magnus played black and magnus lost");
+/// ```
+pub struct GameFilter<G> {
+    predicate: Box<dyn Fn(&G) -> bool + Send + Sync>,
+    description: Option<String>,
+}
+
+impl<G: 'static> GameFilter<G> {
+    /// Creates a filter that includes every game.
+    pub fn all() -> GameFilter<G> {
+        GameFilter {
+            predicate: Box::new(|_| true),
+            description: None,
+        }
+    }
+
+    /// Creates a filter that includes the games `predicate` returns `true` for, described by
+    /// `description`.
+    pub fn new(
+        description: &str,
+        predicate: impl Fn(&G) -> bool + Send + Sync + 'static,
+    ) -> GameFilter<G> {
+        GameFilter {
+            predicate: Box::new(predicate),
+            description: Some(String::from(description)),
+        }
+    }
+
+    /// Returns whether `game` is included by this filter.
+    pub fn matches(&self, game: &G) -> bool {
+        (self.predicate)(game)
+    }
+
+    /// Returns whether this filter includes every game without looking at it.
+    pub fn is_all(&self) -> bool {
+        self.description.is_none()
+    }
+
+    /// Returns a filter that includes the games included by both this filter and `other`.
+    pub fn and(self, other: GameFilter<G>) -> GameFilter<G> {
+        if self.is_all() {
+            return other;
+        }
+
+        if other.is_all() {
+            return self;
+        }
+
+        let description = format!("{} and {}", self, other);
+        let (predicate, other_predicate) = (self.predicate, other.predicate);
+
+        GameFilter {
+            predicate: Box::new(move |game| predicate(game) && other_predicate(game)),
+            description: Some(description),
+        }
+    }
+
+    /// Returns a filter that includes the games included by this filter, `other`, or both.
+    pub fn or(self, other: GameFilter<G>) -> GameFilter<G> {
+        if self.is_all() || other.is_all() {
+            return GameFilter::all();
+        }
+
+        let description = format!("({} or {})", self, other);
+        let (predicate, other_predicate) = (self.predicate, other.predicate);
+
+        GameFilter {
+            predicate: Box::new(move |game| predicate(game) || other_predicate(game)),
+            description: Some(description),
+        }
+    }
+
+    /// Returns a filter that includes exactly the games this filter doesn't.
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> GameFilter<G> {
+        let description = format!("not ({})", self);
+        let predicate = self.predicate;
+
+        GameFilter {
+            predicate: Box::new(move |game| !predicate(game)),
+            description: Some(description),
+        }
+    }
+}
+
+impl<G: 'static + GivePlayers> GameFilter<G> {
+    /// Creates a filter that includes the games `player` played in.
+    pub fn player(player: &str) -> GameFilter<G> {
+        let name = String::from(player);
+
+        GameFilter::new(&format!("{} played", player), move |game: &G| {
+            game.color_of(&name).is_some()
+        })
+    }
+
+    /// Creates a filter that includes the games `player` played as `color`.
+    pub fn color(player: &str, color: Color) -> GameFilter<G> {
+        let name = String::from(player);
+        let color_name = match color {
+            Color::White => "white",
+            Color::Black => "black",
+        };
+
+        GameFilter::new(
+            &format!("{} played {}", player, color_name),
+            move |game: &G| game.color_of(&name) == Some(color),
+        )
+    }
+}
+
+impl<G: 'static + GivePlayers + GiveResult> GameFilter<G> {
+    /// Creates a filter that includes the games where `player` got `result`.
+    pub fn result(player: &str, result: PlayerResult) -> GameFilter<G> {
+        let name = String::from(player);
+        let result_name = match result {
+            PlayerResult::Won => "won",
+            PlayerResult::Drew => "drew",
+            PlayerResult::Lost => "lost",
+        };
+
+        GameFilter::new(&format!("{} {}", player, result_name), move |game: &G| {
+            game.color_of(&name)
+                .map(|color| game.result().for_color(color))
+                == Some(result)
+        })
+    }
+}
+
+/// Returns whether `rating` is known and within `min` and `max`, where a missing bound is open.
+fn in_range(rating: Option<u16>, min: Option<u16>, max: Option<u16>) -> bool {
+    match rating {
+        Some(rating) => rating >= min.unwrap_or(u16::MIN) && rating <= max.unwrap_or(u16::MAX),
+        None => false,
+    }
+}
+
+/// Returns a description of the ratings between `min` and `max`, such as "rated 1500-2000".
+fn range_description(min: Option<u16>, max: Option<u16>) -> String {
+    match (min, max) {
+        (Some(min), Some(max)) => format!("rated {}-{}", min, max),
+        (Some(min), None) => format!("rated at least {}", min),
+        (None, Some(max)) => format!("rated at most {}", max),
+        (None, None) => String::from("rated"),
+    }
+}
+
+impl<G: 'static + GivePlayers + GiveRatings> GameFilter<G> {
+    /// Creates a filter that includes the games where both players were rated between `min` and
+    /// `max` inclusive. A bound of `None` leaves that side of the range open, and games with an
+    /// unknown rating are left out.
+    pub fn ratings(min: Option<u16>, max: Option<u16>) -> GameFilter<G> {
+        GameFilter::new(
+            &format!("both players {}", range_description(min, max)),
+            move |game: &G| {
+                in_range(game.white_rating(), min, max) && in_range(game.black_rating(), min, max)
+            },
+        )
+    }
+
+    /// Creates a filter that includes the games where `player` was rated between `min` and `max`
+    /// inclusive, like [`ratings`](GameFilter::ratings).
+    pub fn player_rating(player: &str, min: Option<u16>, max: Option<u16>) -> GameFilter<G> {
+        let name = String::from(player);
+
+        GameFilter::new(
+            &format!("{} {}", player, range_description(min, max)),
+            move |game: &G| match game.color_of(&name) {
+                Some(color) => in_range(game.rating_of(color), min, max),
+                None => false,
+            },
+        )
+    }
+
+    /// Creates a filter that includes the games where the opponent of `player` was rated between
+    /// `min` and `max` inclusive, like [`ratings`](GameFilter::ratings).
+    pub fn opponent_rating(player: &str, min: Option<u16>, max: Option<u16>) -> GameFilter<G> {
+        let name = String::from(player);
+
+        GameFilter::new(
+            &format!("{}'s opponent {}", player, range_description(min, max)),
+            move |game: &G| match game.color_of(&name) {
+                Some(color) => in_range(game.rating_of(!color), min, max),
+                None => false,
+            },
+        )
+    }
+}
+
+impl<G: 'static + GiveClocks> GameFilter<G> {
+    /// Creates a filter that includes the games played at `speed`. Games without a known time
+    /// control are left out.
+    pub fn speed(speed: Speed) -> GameFilter<G> {
+        GameFilter::new(&format!("played at {}", speed), move |game: &G| {
+            game.time_control().map(|time_control| time_control.speed()) == Some(speed)
+        })
+    }
+
+    /// Creates a filter that includes the games played with exactly `time_control`.
+    pub fn time_control(time_control: TimeControl) -> GameFilter<G> {
+        GameFilter::new(&format!("played at {}", time_control), move |game: &G| {
+            game.time_control() == Some(time_control)
+        })
+    }
+}
+
+impl<G: 'static + GiveDate> GameFilter<G> {
+    /// Creates a filter that includes the games played between `since` and `until` inclusive. A
+    /// bound of `None` leaves that side of the range open, and games with an unknown date are left
+    /// out.
+    pub fn dates(since: Option<NaiveDate>, until: Option<NaiveDate>) -> GameFilter<G> {
+        let description = match (since, until) {
+            (Some(since), Some(until)) => format!("played from {} to {}", since, until),
+            (Some(since), None) => format!("played since {}", since),
+            (None, Some(until)) => format!("played until {}", until),
+            (None, None) => String::from("played on a known date"),
+        };
+
+        GameFilter::new(&description, move |game: &G| match game.date() {
+            Some(date) => {
+                date >= since.unwrap_or(NaiveDate::MIN) && date <= until.unwrap_or(NaiveDate::MAX)
+            }
+            None => false,
+        })
+    }
+}
+
+impl<G: 'static + GiveSource> GameFilter<G> {
+    /// Creates a filter that includes the games that came from `source`. Games whose source
+    /// isn't known are left out.
+    pub fn source(source: Source) -> GameFilter<G> {
+        GameFilter::new(&format!("from {}", source), move |game: &G| {
+            game.source() == Some(source)
+        })
+    }
+}
+
+/// Parses a year (`2021`), month (`2021-06`), or day (`2021-06-15`) into the first and last days
+/// it covers. Parts may also be separated by dots, as in PGN dates.
+pub fn parse_date_range(date: &str) -> Option<(NaiveDate, NaiveDate)> {
+    let parts = date
+        .split(['-', '.', '/'])
+        .map(|part| part.parse::<u32>().ok())
+        .collect::<Option<Vec<_>>>()?;
+
+    match parts[..] {
+        [year] => Some((
+            NaiveDate::from_ymd_opt(year as i32, 1, 1)?,
+            NaiveDate::from_ymd_opt(year as i32, 12, 31)?,
+        )),
+        [year, month] => {
+            let first = NaiveDate::from_ymd_opt(year as i32, month, 1)?;
+            let next = match month {
+                12 => NaiveDate::from_ymd_opt(year as i32 + 1, 1, 1)?,
+                _ => NaiveDate::from_ymd_opt(year as i32, month + 1, 1)?,
+            };
+
+            Some((first, next.pred_opt()?))
+        }
+        [year, month, day] => {
+            let day = NaiveDate::from_ymd_opt(year as i32, month, day)?;
+
+            Some((day, day))
+        }
+        _ => None,
+    }
+}
+
+impl<G: 'static> GameFilter<G> {
+    /// Creates a filter that includes the games that lasted at least `plies` moves, counting each
+    /// side's moves separately, so that games aborted before both players moved can be left out.
+    pub fn min_plies<M>(plies: usize) -> GameFilter<G>
+    where
+        M: 'static + Clone + Move,
+        G: ListMoves<M>,
+    {
+        GameFilter::new(
+            &format!("lasted at least {} plies", plies),
+            move |game: &G| game.list_moves().count() >= plies,
+        )
+    }
+
+    /// Creates a filter that includes the games the embedded opening table classifies under an
+    /// ECO code starting with `prefix`, so that `B9` includes every code from B90 to B99.
+    pub fn eco<M>(prefix: &str) -> GameFilter<G>
+    where
+        M: 'static + Clone + Move,
+        G: ListMoves<M>,
+    {
+        let prefix = prefix.to_uppercase();
+
+        GameFilter::new(&format!("opened with ECO {}", prefix), move |game: &G| {
+            Openings::embedded()
+                .classify(game)
+                .is_some_and(|opening| opening.eco().starts_with(&prefix))
+        })
+    }
+
+    /// Creates a filter that includes the games the embedded opening table classifies under a
+    /// name containing `name`, ignoring case.
+    pub fn opening<M>(name: &str) -> GameFilter<G>
+    where
+        M: 'static + Clone + Move,
+        G: ListMoves<M>,
+    {
+        let lowercase_name = name.to_lowercase();
+
+        GameFilter::new(&format!("opened with {}", name), move |game: &G| {
+            Openings::embedded()
+                .classify(game)
+                .is_some_and(|opening| opening.name().to_lowercase().contains(&lowercase_name))
+        })
+    }
+}
+
+impl<G> GameFilter<G>
+where
+    G: 'static + GiveClocks + GiveDate + GivePlayers + GiveRatings + GiveResult + GiveSource,
+{
+    /// Parses a filter from an expression such as `color=white and elo>2000 and eco~B9*`.
+    ///
+    /// An expression is made of conditions of the form `field`, comparison, and value, combined
+    /// with `and`, `or`, and `not`, and grouped with parentheses. `and` binds tighter than `or`.
+    /// Values with spaces or symbols in them can be given in double quotes. The fields are:
+    ///
+    /// - `player`, `white`, and `black`, the names of the players, compared with `=` and `!=`.
+    /// - `color` (`white` or `black`) and `result` (`win`, `draw`, or `loss`), for `player`.
+    /// - `elo`, the rating of `player`, or of both players without one, and `opponent-elo`, the
+    ///   rating of the opponent of `player`, compared as numbers.
+    /// - `speed`, such as `blitz`, and `tc`, the exact time control such as `180+2`.
+    /// - `date`, a year, month, or day compared as a period, so `date<2021` means before 2021
+    ///   and `date=2021-06` means during June 2021.
+    /// - `eco` and `opening`, the ECO code and name of the opening the embedded opening table
+    ///   classifies the game under. Besides `=` and `!=`, which ignore case, they can be matched
+    ///   with `~` against a pattern where `*` stands for any text and `?` for any character.
+    /// - `source`, where the game came from: `lichess`, `chess.com`, or `local`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidFilter`] if the expression isn't valid, or uses `color`, `result`,
+    /// or `opponent-elo` without `player`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chesshound::filter::GameFilter;
+    /// use chesshound::{AlgebraicMove, Game};
+    ///
+    /// let filter = GameFilter::<Game<AlgebraicMove>>::parse(
+    ///     "color=white and (result=loss or opponent-elo>=2500)",
+    ///     Some("magnus"),
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(
+    ///     filter.to_string(),
+    ///     "magnus played white and (magnus lost or magnus's opponent rated at least 2500)"
+    /// );
+    /// ```
+    pub fn parse<M>(expression: &str, player: Option<&str>) -> Result<GameFilter<G>>
+    where
+        M: 'static + Clone + Move,
+        G: ListMoves<M>,
+    {
+        let invalid = |reason: String| Error::InvalidFilter(format!("{}: {}", expression, reason));
+
+        let mut parser = ExpressionParser {
+            tokens: tokenize(expression).map_err(invalid)?,
+            position: 0,
+            player,
+        };
+
+        let filter = parser.or::<M, G>().map_err(invalid)?;
+
+        match parser.tokens.get(parser.position) {
+            None => Ok(filter),
+            Some(token) => Err(invalid(format!("unexpected {}", token))),
+        }
+    }
+}
+
+/// How a field is compared with a value in a filter expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Matches,
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Comparison::Equal => "=",
+            Comparison::NotEqual => "!=",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+            Comparison::Matches => "~",
+        };
+
+        write!(f, "{}", symbol)
+    }
+}
+
+/// A token of a filter expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    /// A field, value, or keyword.
+    Word(String),
+    /// A value given in double quotes, which is never taken as a keyword.
+    Quoted(String),
+    Comparison(Comparison),
+    Open,
+    Close,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "{}", word),
+            Token::Quoted(value) => write!(f, "\"{}\"", value),
+            Token::Comparison(comparison) => write!(f, "{}", comparison),
+            Token::Open => write!(f, "("),
+            Token::Close => write!(f, ")"),
+        }
+    }
+}
+
+/// Splits a filter expression into tokens, or returns why it can't be.
+fn tokenize(expression: &str) -> std::result::Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '=' => Token::Comparison(Comparison::Equal),
+            '~' => Token::Comparison(Comparison::Matches),
+            '!' if chars.next_if_eq(&'=').is_some() => Token::Comparison(Comparison::NotEqual),
+            '<' if chars.next_if_eq(&'=').is_some() => Token::Comparison(Comparison::LessOrEqual),
+            '<' => Token::Comparison(Comparison::Less),
+            '>' if chars.next_if_eq(&'=').is_some() => {
+                Token::Comparison(Comparison::GreaterOrEqual)
+            }
+            '>' => Token::Comparison(Comparison::Greater),
+            '"' => {
+                let mut value = String::new();
+
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => value.extend(chars.next()),
+                        Some(c) => value.push(c),
+                        None => return Err(String::from("unclosed quote")),
+                    }
+                }
+
+                Token::Quoted(value)
+            }
+            '!' => return Err(String::from("unexpected !")),
+            c => {
+                let mut word = String::from(c);
+
+                while let Some(c) =
+                    chars.next_if(|c| !c.is_whitespace() && !"()=!<>~\"".contains(*c))
+                {
+                    word.push(c);
+                }
+
+                Token::Word(word)
+            }
+        };
+
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+/// A recursive descent parser of the tokens of a filter expression.
+struct ExpressionParser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    player: Option<&'a str>,
+}
+
+impl<'a> ExpressionParser<'a> {
+    /// Moves past the next token if it's the keyword `keyword`, and returns whether it was.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        match self.tokens.get(self.position) {
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword) => {
+                self.position += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the next token and moves past it, or returns an error if there are no more.
+    fn next(&mut self) -> std::result::Result<Token, String> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or_else(|| String::from("unexpected end"))?;
+        self.position += 1;
+
+        Ok(token)
+    }
+
+    /// Parses conditions joined by `or`.
+    fn or<M, G>(&mut self) -> std::result::Result<GameFilter<G>, String>
+    where
+        M: 'static + Clone + Move,
+        G: 'static
+            + GiveClocks
+            + GiveDate
+            + GivePlayers
+            + GiveRatings
+            + GiveResult
+            + GiveSource
+            + ListMoves<M>,
+    {
+        let mut filter = self.and::<M, G>()?;
+
+        while self.keyword("or") {
+            filter = filter.or(self.and::<M, G>()?);
+        }
+
+        Ok(filter)
+    }
+
+    /// Parses conditions joined by `and`.
+    fn and<M, G>(&mut self) -> std::result::Result<GameFilter<G>, String>
+    where
+        M: 'static + Clone + Move,
+        G: 'static
+            + GiveClocks
+            + GiveDate
+            + GivePlayers
+            + GiveRatings
+            + GiveResult
+            + GiveSource
+            + ListMoves<M>,
+    {
+        let mut filter = self.unary::<M, G>()?;
+
+        while self.keyword("and") {
+            filter = filter.and(self.unary::<M, G>()?);
+        }
+
+        Ok(filter)
+    }
+
+    /// Parses a condition, a negated condition, or an expression in parentheses.
+    fn unary<M, G>(&mut self) -> std::result::Result<GameFilter<G>, String>
+    where
+        M: 'static + Clone + Move,
+        G: 'static
+            + GiveClocks
+            + GiveDate
+            + GivePlayers
+            + GiveRatings
+            + GiveResult
+            + GiveSource
+            + ListMoves<M>,
+    {
+        if self.keyword("not") {
+            return Ok(self.unary::<M, G>()?.not());
+        }
+
+        match self.next()? {
+            Token::Open => {
+                let filter = self.or::<M, G>()?;
+
+                match self.next()? {
+                    Token::Close => Ok(filter),
+                    token => Err(format!("expected ) but found {}", token)),
+                }
+            }
+            Token::Word(field) => {
+                let comparison = match self.next()? {
+                    Token::Comparison(comparison) => comparison,
+                    token => return Err(format!("expected a comparison but found {}", token)),
+                };
+                let value = match self.next()? {
+                    Token::Word(value) | Token::Quoted(value) => value,
+                    token => return Err(format!("expected a value but found {}", token)),
+                };
+
+                self.condition::<M, G>(&field, comparison, &value)
+            }
+            token => Err(format!("expected a condition but found {}", token)),
+        }
+    }
+
+    /// Returns the filter for the condition that `field` compares with `value` by `comparison`.
+    fn condition<M, G>(
+        &self,
+        field: &str,
+        comparison: Comparison,
+        value: &str,
+    ) -> std::result::Result<GameFilter<G>, String>
+    where
+        M: 'static + Clone + Move,
+        G: 'static
+            + GiveClocks
+            + GiveDate
+            + GivePlayers
+            + GiveRatings
+            + GiveResult
+            + GiveSource
+            + ListMoves<M>,
+    {
+        let field = field.to_lowercase().replace('_', "-");
+        let unsupported = || format!("{} can't be compared with {}", field, comparison);
+        let player = || {
+            self.player
+                .ok_or_else(|| format!("{} needs a player to be given", field))
+        };
+
+        let filter = match field.as_str() {
+            "player" => GameFilter::player(value),
+            "white" => GameFilter::color(value, Color::White),
+            "black" => GameFilter::color(value, Color::Black),
+            "color" => match value.to_lowercase().as_str() {
+                "white" => GameFilter::color(player()?, Color::White),
+                "black" => GameFilter::color(player()?, Color::Black),
+                _ => return Err(format!("invalid color: {}", value)),
+            },
+            "result" => {
+                let result = match value.to_lowercase().as_str() {
+                    "win" => PlayerResult::Won,
+                    "draw" => PlayerResult::Drew,
+                    "loss" => PlayerResult::Lost,
+                    _ => return Err(format!("invalid result: {}", value)),
+                };
+
+                GameFilter::result(player()?, result)
+            }
+            "elo" | "opponent-elo" => {
+                let rating = value
+                    .parse::<u16>()
+                    .map_err(|_| format!("invalid {}: {}", field, value))?;
+                let (min, max) = match comparison {
+                    Comparison::Equal | Comparison::NotEqual => (Some(rating), Some(rating)),
+                    Comparison::Less => match rating.checked_sub(1) {
+                        Some(max) => (None, Some(max)),
+                        None => return Err(format!("no {} is below 0", field)),
+                    },
+                    Comparison::LessOrEqual => (None, Some(rating)),
+                    Comparison::Greater => match rating.checked_add(1) {
+                        Some(min) => (Some(min), None),
+                        None => return Err(format!("no {} is above {}", field, rating)),
+                    },
+                    Comparison::GreaterOrEqual => (Some(rating), None),
+                    Comparison::Matches => return Err(unsupported()),
+                };
+
+                let filter = match (field.as_str(), self.player) {
+                    ("elo", Some(player)) => GameFilter::player_rating(player, min, max),
+                    ("elo", None) => GameFilter::ratings(min, max),
+                    _ => GameFilter::opponent_rating(player()?, min, max),
+                };
+
+                return Ok(match comparison {
+                    Comparison::NotEqual => filter.not(),
+                    _ => filter,
+                });
+            }
+            "speed" => GameFilter::speed(
+                value
+                    .parse::<Speed>()
+                    .map_err(|_| format!("invalid speed: {}", value))?,
+            ),
+            "tc" => GameFilter::time_control(
+                value
+                    .parse::<TimeControl>()
+                    .map_err(|_| format!("invalid time control: {}", value))?,
+            ),
+            "source" => GameFilter::source(
+                value
+                    .parse::<Source>()
+                    .map_err(|_| format!("invalid source: {}", value))?,
+            ),
+            "date" => {
+                let (first, last) =
+                    parse_date_range(value).ok_or_else(|| format!("invalid date: {}", value))?;
+
+                return match comparison {
+                    Comparison::Equal => Ok(GameFilter::dates(Some(first), Some(last))),
+                    Comparison::NotEqual => Ok(GameFilter::dates(Some(first), Some(last)).not()),
+                    Comparison::Less => Ok(GameFilter::dates(None, first.pred_opt())),
+                    Comparison::LessOrEqual => Ok(GameFilter::dates(None, Some(last))),
+                    Comparison::Greater => Ok(GameFilter::dates(last.succ_opt(), None)),
+                    Comparison::GreaterOrEqual => Ok(GameFilter::dates(Some(first), None)),
+                    Comparison::Matches => Err(unsupported()),
+                };
+            }
+            "eco" | "opening" => {
+                let is_eco = field == "eco";
+                let pattern = value.to_lowercase();
+                let description = match (is_eco, comparison) {
+                    (true, Comparison::Matches) => format!("opened with ECO matching {}", value),
+                    (true, _) => format!("opened with ECO {}", value.to_uppercase()),
+                    (false, Comparison::Matches) => {
+                        format!("opened with an opening matching {}", value)
+                    }
+                    (false, _) => format!("opened with {}", value),
+                };
+                let glob = match comparison {
+                    Comparison::Equal | Comparison::NotEqual => false,
+                    Comparison::Matches => true,
+                    _ => return Err(unsupported()),
+                };
+
+                let filter = GameFilter::new(&description, move |game: &G| {
+                    Openings::embedded().classify(game).is_some_and(|opening| {
+                        let text = if is_eco {
+                            opening.eco()
+                        } else {
+                            opening.name()
+                        }
+                        .to_lowercase();
+
+                        if glob {
+                            glob_matches(&pattern, &text)
+                        } else {
+                            text == pattern
+                        }
+                    })
+                });
+
+                return Ok(match comparison {
+                    Comparison::NotEqual => filter.not(),
+                    _ => filter,
+                });
+            }
+            _ => return Err(format!("unknown field: {}", field)),
+        };
+
+        match comparison {
+            Comparison::Equal => Ok(filter),
+            Comparison::NotEqual => Ok(filter.not()),
+            _ => Err(unsupported()),
+        }
+    }
+}
+
+/// Returns whether `text` matches `pattern`, where `*` in `pattern` stands for any text and `?`
+/// for any one character.
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+    let (mut p, mut t) = (0, 0);
+    // The position of the last `*` in the pattern, and of the text it was matched at.
+    let mut star = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                // Let the last `*` take one more character and try again from there.
+                Some((star_p, star_t)) => {
+                    star = Some((star_p, star_t + 1));
+                    p = star_p + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+impl<G> fmt::Display for GameFilter<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.description.as_deref().unwrap_or("all games"))
+    }
+}
+
+impl<G> fmt::Debug for GameFilter<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("GameFilter")
+            .field(&self.to_string())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use chrono::NaiveDate;
+
+    use super::{parse_date_range, GameFilter};
+    use crate::clock::Speed;
+    use crate::game::test_utils::*;
+    use crate::game::{Color, Game, GameMoves, GivePlayers, PlayerResult};
+    use crate::source::Source;
+    use crate::AlgebraicMove;
+
+    #[fixture]
+    fn games() -> Vec<Game<AlgebraicMove>> {
+        vec![
+            game("alice", "bob", "1-0", "1. e4 e5"),
+            game("bob", "alice", "1-0", "1. e4 e5"),
+            game("carol", "alice", "1/2-1/2", "1. e4 e5"),
+            game("bob", "carol", "0-1", "1. e4 e5"),
+        ]
+    }
+
+    fn opponents(
+        games: &[Game<AlgebraicMove>],
+        filter: &GameFilter<Game<AlgebraicMove>>,
+    ) -> Vec<String> {
+        games
+            .iter()
+            .filter(|game| filter.matches(game))
+            .map(|game| {
+                format!(
+                    "{}-{}",
+                    game.white_player().unwrap(),
+                    game.black_player().unwrap()
+                )
+            })
+            .collect()
+    }
+
+    #[rstest]
+    fn filters_should_select_by_player_color_and_result(games: Vec<Game<AlgebraicMove>>) {
+        assert_eq!(
+            opponents(&games, &GameFilter::player("alice")),
+            vec!["alice-bob", "bob-alice", "carol-alice"]
+        );
+        assert_eq!(
+            opponents(&games, &GameFilter::color("alice", Color::Black)),
+            vec!["bob-alice", "carol-alice"]
+        );
+        assert_eq!(
+            opponents(&games, &GameFilter::result("alice", PlayerResult::Lost)),
+            vec!["bob-alice"]
+        );
+    }
+
+    #[rstest]
+    fn filters_should_combine(games: Vec<Game<AlgebraicMove>>) {
+        let filter = GameFilter::color("alice", Color::Black)
+            .and(GameFilter::result("alice", PlayerResult::Lost).not())
+            .or(GameFilter::player("bob").and(GameFilter::player("carol")));
+
+        assert_eq!(opponents(&games, &filter), vec!["carol-alice", "bob-carol"]);
+        assert_eq!(
+            filter.to_string(),
+            "(alice played black and not (alice lost) or bob played and carol played)"
+        );
+    }
+
+    #[rstest]
+    fn rating_filters_should_select_by_rating_range() {
+        let games = vec![
+            parse_game(
+                "[White \"alice\"]\n[Black \"bob\"]\n[WhiteElo \"1500\"]\n[BlackElo \"1900\"]\n\
+                 [Result \"1-0\"]\n1. e4 1-0",
+            ),
+            parse_game(
+                "[White \"carol\"]\n[Black \"alice\"]\n[WhiteElo \"1400\"]\n[BlackElo \"1600\"]\n\
+                 [Result \"1-0\"]\n1. e4 1-0",
+            ),
+            parse_game("[White \"alice\"]\n[Black \"dave\"]\n[Result \"1-0\"]\n1. e4 1-0"),
+        ];
+
+        assert_eq!(
+            opponents(
+                &games,
+                &GameFilter::player_rating("alice", Some(1550), None)
+            ),
+            vec!["carol-alice"]
+        );
+        assert_eq!(
+            opponents(
+                &games,
+                &GameFilter::opponent_rating("alice", Some(1800), Some(2000))
+            ),
+            vec!["alice-bob"]
+        );
+        assert_eq!(
+            opponents(&games, &GameFilter::ratings(None, Some(1600))),
+            vec!["carol-alice"]
+        );
+        assert_eq!(
+            GameFilter::<Game<AlgebraicMove>>::opponent_rating("alice", None, Some(2000))
+                .to_string(),
+            "alice's opponent rated at most 2000"
+        );
+    }
+
+    #[rstest]
+    fn time_control_filters_should_select_by_time_control() {
+        let games = ["180+2", "180+0", "600+5"]
+            .iter()
+            .map(|time_control| {
+                parse_game(&format!(
+                    "[White \"{}\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n\
+                     [TimeControl \"{}\"]\n1. e4 1-0",
+                    time_control, time_control
+                ))
+            })
+            .chain(std::iter::once(parse_game(
+                "[White \"-\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n1. e4 1-0",
+            )))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            opponents(&games, &GameFilter::speed(Speed::Blitz)),
+            vec!["180+2-bob", "180+0-bob"]
+        );
+        assert_eq!(
+            opponents(&games, &GameFilter::time_control("180+2".parse().unwrap())),
+            vec!["180+2-bob"]
+        );
+        assert_eq!(
+            GameFilter::<Game<AlgebraicMove>>::speed(Speed::Rapid).to_string(),
+            "played at rapid"
+        );
+    }
+
+    #[rstest]
+    fn date_filters_should_select_by_date_range() {
+        let games = ["2020.12.31", "2021.01.01", "2021.06.15", "????.??.??"]
+            .iter()
+            .map(|date| {
+                parse_game(&format!(
+                    "[White \"{}\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n[Date \"{}\"]\n\
+                     1. e4 1-0",
+                    date, date
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        let (since, until) = parse_date_range("2021").unwrap();
+
+        assert_eq!(
+            opponents(&games, &GameFilter::dates(Some(since), None)),
+            vec!["2021.01.01-bob", "2021.06.15-bob"]
+        );
+        assert_eq!(
+            opponents(&games, &GameFilter::dates(None, Some(until))),
+            vec!["2020.12.31-bob", "2021.01.01-bob", "2021.06.15-bob"]
+        );
+    }
+
+    #[rstest(
+        date,
+        expected,
+        case("2021", Some(("2021-01-01", "2021-12-31"))),
+        case("2020-02", Some(("2020-02-01", "2020-02-29"))),
+        case("2021.12", Some(("2021-12-01", "2021-12-31"))),
+        case("2021-06-15", Some(("2021-06-15", "2021-06-15"))),
+        case("2021-13", None),
+        case("last week", None)
+    )]
+    fn parse_date_range_should_cover_whole_periods(date: &str, expected: Option<(&str, &str)>) {
+        let day = |day: &str| day.parse::<NaiveDate>().unwrap();
+
+        assert_eq!(
+            parse_date_range(date),
+            expected.map(|(first, last)| (day(first), day(last)))
+        );
+    }
+
+    #[rstest]
+    fn opening_filters_should_select_by_classification() {
+        let games = [ruy_lopez(), italian_game(), sicilian_naijdorf()];
+        let count = |filter: GameFilter<GameMoves<AlgebraicMove>>| {
+            games.iter().filter(|game| filter.matches(game)).count()
+        };
+
+        assert_eq!(count(GameFilter::eco("C6")), 1);
+        assert_eq!(count(GameFilter::eco("c")), 2);
+        assert_eq!(count(GameFilter::eco("B")), 1);
+        assert_eq!(count(GameFilter::eco("A")), 0);
+        assert_eq!(count(GameFilter::opening("ruy")), 1);
+        assert_eq!(count(GameFilter::opening("Italian Game")), 1);
+    }
+
+    #[rstest(
+        expression,
+        player,
+        expected,
+        case("player=carol", None, vec!["carol-alice", "bob-carol"]),
+        case("white=bob and not black=alice", None, vec!["bob-carol"]),
+        case("color=black and result!=loss", Some("alice"), vec!["carol-alice"]),
+        case(
+            "(result=win OR result=draw) and black=\"alice\"",
+            Some("alice"),
+            vec!["carol-alice"]
+        ),
+        case(
+            "eco~C2? and opening~\"*king's pawn*\"",
+            None,
+            vec!["alice-bob", "bob-alice", "carol-alice", "bob-carol"]
+        ),
+        case("eco=c21", None, Vec::<&str>::new())
+    )]
+    fn parse_should_select_games(
+        games: Vec<Game<AlgebraicMove>>,
+        expression: &str,
+        player: Option<&str>,
+        expected: Vec<&str>,
+    ) {
+        let filter = GameFilter::parse(expression, player).unwrap();
+
+        assert_eq!(opponents(&games, &filter), expected);
+    }
+
+    #[rstest]
+    fn source_should_select_games_by_where_they_came_from() {
+        let games = [
+            "[White \"alice\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n\
+             [Site \"https://lichess.org/abcd1234\"]\n1. e4 1-0",
+            "[White \"carol\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n\
+             [Source \"local\"]\n[SourceId \"otb.pgn#1\"]\n1. e4 1-0",
+            "[White \"dave\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n1. e4 1-0",
+        ]
+        .iter()
+        .map(|pgn| parse_game(pgn))
+        .collect::<Vec<_>>();
+
+        assert_eq!(
+            opponents(&games, &GameFilter::source(Source::Lichess)),
+            vec!["alice-bob"]
+        );
+        assert_eq!(
+            opponents(&games, &GameFilter::parse("source!=local", None).unwrap()),
+            vec!["alice-bob", "dave-bob"]
+        );
+        assert!(GameFilter::<Game<AlgebraicMove>>::parse("source=fics", None).is_err());
+    }
+
+    #[rstest]
+    fn parse_should_compare_ratings_and_dates() {
+        let games = [
+            ("2020.12.31", "1500"),
+            ("2021.06.15", "2000"),
+            ("2022.01.01", "2100"),
+        ]
+        .iter()
+        .map(|(date, elo)| {
+            parse_game(&format!(
+                "[White \"{}\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n[Date \"{}\"]\n\
+                     [WhiteElo \"{}\"]\n[BlackElo \"{}\"]\n1. e4 1-0",
+                elo, date, elo, elo
+            ))
+        })
+        .collect::<Vec<_>>();
+        let parse = |expression: &str| {
+            opponents(&games, &GameFilter::parse(expression, Some("bob")).unwrap())
+        };
+
+        assert_eq!(parse("elo>=2000 and date<2022"), vec!["2000-bob"]);
+        assert_eq!(
+            parse("elo<2000 or date>2021-06"),
+            vec!["1500-bob", "2100-bob"]
+        );
+        assert_eq!(
+            parse("opponent-elo!=2000 and date=2021"),
+            Vec::<String>::new()
+        );
+    }
+
+    #[rstest(
+        expression,
+        case("elo>"),
+        case("color=white"),
+        case("(player=alice"),
+        case("player=alice bob"),
+        case("rating>2000"),
+        case("speed>blitz"),
+        case("date=yesterday"),
+        case("player=\"alice")
+    )]
+    fn parse_should_reject_invalid_expressions(expression: &str) {
+        assert!(GameFilter::<Game<AlgebraicMove>>::parse(expression, None).is_err());
+    }
+
+    #[rstest]
+    fn all_should_include_every_game_and_vanish_in_and(games: Vec<Game<AlgebraicMove>>) {
+        let all = GameFilter::all();
+
+        assert!(all.is_all());
+        assert_eq!(opponents(&games, &all).len(), 4);
+        assert_eq!(all.to_string(), "all games");
+
+        let filter = GameFilter::all().and(GameFilter::player("carol"));
+
+        assert_eq!(filter.to_string(), "carol played");
+        assert_eq!(opponents(&games, &filter).len(), 2);
+    }
+}
@@ -0,0 +1,259 @@
+use shakmaty::fen::Fen;
+
+use crate::analysis::Blunder;
+use crate::eval::WinModel;
+use crate::game::Color;
+use crate::moves::Move;
+use crate::position::{BoardStyle, Render};
+use crate::prep::{GapKind, PrepGap};
+use crate::stats::statistic::Value;
+
+/// A card for training on a position from a player's games: the position and a question on the
+/// front, and the move to find with why it matters on the back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Flashcard {
+    fen: String,
+    prompt: String,
+    answer: String,
+    tags: Vec<String>,
+}
+
+impl Flashcard {
+    /// Creates a card asking `prompt` about the position given by `fen`, answered by `answer`,
+    /// and tagged with `tags` for sorting cards into decks.
+    pub fn new(fen: &str, prompt: &str, answer: &str, tags: &[&str]) -> Flashcard {
+        Flashcard {
+            fen: String::from(fen),
+            prompt: String::from(prompt),
+            answer: String::from(answer),
+            tags: tags.iter().map(|tag| String::from(*tag)).collect(),
+        }
+    }
+
+    /// Returns a card asking for a better move than `blunder`, answered by the engine's choices
+    /// and what the blunder cost, in win probability judged by `model`. Returns `None` if the
+    /// engine didn't give a better move.
+    pub fn from_blunder(blunder: &Blunder, model: &WinModel) -> Option<Flashcard> {
+        let best = match blunder.alternatives() {
+            [] => String::from(blunder.best_move()?),
+            alternatives => alternatives
+                .iter()
+                .map(|candidate| format!("{} ({})", candidate.san(), candidate.eval()))
+                .collect::<Vec<_>>()
+                .join(", "),
+        };
+
+        Some(Flashcard {
+            fen: String::from(blunder.fen()),
+            prompt: format!(
+                "Move {}: {} to play. Find the best move.",
+                blunder.move_number(),
+                color_name(blunder.color())
+            ),
+            answer: format!(
+                "{}. {} was played, going from {} to {} and losing {:.0}% win probability.",
+                best,
+                blunder.played(),
+                blunder.before(),
+                blunder.after(),
+                blunder.probability_lost(model) * 100.
+            ),
+            tags: vec![
+                String::from("blunder"),
+                String::from(color_name(blunder.color())),
+            ],
+        })
+    }
+
+    /// Returns a card asking for the best move at the end of the opening line of `gap`, answered
+    /// by the engine's choice and how the player has scored in the line. Returns `None` if the
+    /// engine didn't give a move.
+    pub fn from_prep_gap<M: Clone + Move>(gap: &PrepGap<M>) -> Option<Flashcard> {
+        let best = gap.best_move()?;
+        let line = gap
+            .moves()
+            .iter()
+            .map(|move_| move_.clone().to_algebraic())
+            .collect::<Vec<_>>();
+        let to_play = match gap.fen().parse::<Fen>() {
+            Ok(fen) => fen.as_setup().turn,
+            Err(_) => gap.color(),
+        };
+
+        let kind = match gap.kind() {
+            GapKind::Understanding => "understanding",
+            GapKind::Repertoire => "repertoire",
+        };
+
+        Some(Flashcard {
+            fen: String::from(gap.fen()),
+            prompt: format!(
+                "After {}: {} to play. Find the best move.",
+                format_line(&line),
+                color_name(to_play)
+            ),
+            answer: format!(
+                "{} ({}). Playing {}, you scored {:.0}% over {} games here, where {:.0}% was \
+                 expected, which points to a {} gap.",
+                best,
+                gap.eval(),
+                color_name(gap.color()),
+                gap.score().rate().unwrap_or(0.) * 100.,
+                gap.score().games(),
+                gap.expected_rate() * 100.,
+                kind
+            ),
+            tags: vec![String::from("prep-gap"), String::from(kind)],
+        })
+    }
+
+    /// Returns the FEN of the position the card is about.
+    pub fn fen(&self) -> &str {
+        &self.fen
+    }
+
+    /// Returns the question on the front of the card.
+    pub fn prompt(&self) -> &str {
+        &self.prompt
+    }
+
+    /// Returns the answer on the back of the card.
+    pub fn answer(&self) -> &str {
+        &self.answer
+    }
+
+    /// Returns the tags of the card.
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    /// Returns the front of the card as HTML, with the board drawn above the question and the
+    /// FEN below it.
+    fn front_html(&self) -> String {
+        let board = match self.fen.parse::<Fen>() {
+            Ok(setup) => format!("<pre>{}</pre>", escape(&setup.render(BoardStyle::Unicode))),
+            Err(_) => String::new(),
+        };
+
+        format!(
+            "{}<p>{}</p><p><small>{}</small></p>",
+            board,
+            escape(&self.prompt),
+            escape(&self.fen)
+        )
+    }
+}
+
+/// Returns `color` as cards write it.
+fn color_name(color: Color) -> &'static str {
+    match color {
+        Color::White => "white",
+        Color::Black => "black",
+    }
+}
+
+/// Returns `line` with move numbers, as in `1. e4 e5 2. Nf3`.
+fn format_line(line: &[String]) -> String {
+    line.iter()
+        .enumerate()
+        .map(|(ply, move_)| match ply % 2 {
+            0 => format!("{}. {}", ply / 2 + 1, move_),
+            _ => move_.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Escapes `text` to be written in HTML, with line breaks as `<br>` so that each card stays on
+/// one line of an import file.
+fn escape(text: &str) -> String {
+    text.trim_end_matches('\n')
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\n', "<br>")
+}
+
+/// Returns `cards` as CSV with a header row, holding the FEN, question, answer, and tags of each
+/// card, with the tags separated by spaces.
+pub fn to_csv(cards: &[Flashcard]) -> String {
+    Value::List(
+        cards
+            .iter()
+            .map(|card| {
+                Value::map(vec![
+                    ("fen", card.fen().into()),
+                    ("front", card.prompt().into()),
+                    ("back", card.answer().into()),
+                    ("tags", card.tags().join(" ").into()),
+                ])
+            })
+            .collect(),
+    )
+    .to_table(',')
+}
+
+/// Returns `cards` as a text file Anki can import into a basic note type, with the board drawn on
+/// the front of each card. The file's header lines tell Anki how the file is laid out, so no
+/// options need choosing when importing it.
+pub fn to_anki(cards: &[Flashcard]) -> String {
+    let mut output = String::from("#separator:tab\n#html:true\n#tags column:3\n");
+
+    for card in cards {
+        output += &format!(
+            "{}\t{}\t{}\n",
+            card.front_html(),
+            escape(card.answer()),
+            card.tags().join(" ")
+        );
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::{to_anki, to_csv, Flashcard};
+    use crate::analysis::find_blunders;
+    use crate::engine::test_utils::fake_engine;
+    use crate::engine::Limit;
+    use crate::eval::WinModel;
+    use crate::game::test_utils::*;
+
+    #[rstest]
+    fn flashcards_should_ask_for_better_moves_than_blunders() {
+        let game =
+            parse_game("[White \"a\"]\n[Black \"b\"]\n[Result \"0-1\"]\n1. f3 e5 2. g4 Qh4# 0-1");
+        let mut engine = fake_engine(&[
+            ("cp 30", "e2e4"),
+            ("cp 60", "e7e5"),
+            ("cp -80", "e2e4"),
+            ("mate 1", "d8h4"),
+        ]);
+
+        let blunders = find_blunders(&mut engine, &game, Limit::Depth(10), 200).unwrap();
+        let card = Flashcard::from_blunder(&blunders[0], &WinModel::lichess()).unwrap();
+
+        assert_eq!(card.prompt(), "Move 2: white to play. Find the best move.");
+        assert!(card.answer().starts_with("e4 (-0.80). g4 was played"));
+        assert_eq!(card.tags(), ["blunder", "white"]);
+
+        let cards = [card];
+        let csv = to_csv(&cards);
+
+        assert!(csv.starts_with("fen,front,back,tags\n"));
+        assert!(csv.contains(",blunder white\n"));
+
+        let anki = to_anki(&cards);
+        let lines = anki.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "#separator:tab");
+        assert_eq!(lines[3].split('\t').count(), 3);
+        assert!(lines[3].starts_with("<pre>"));
+        assert!(lines[3].ends_with("\tblunder white"));
+    }
+}
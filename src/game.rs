@@ -1,30 +1,487 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::{NaiveDate, NaiveTime};
+
+use crate::clock::TimeControl;
+use crate::error::Error;
+use crate::eval::Eval;
+use crate::game_id::GameId;
 use crate::moves::Move;
-use crate::parsing::PGNGame;
+use crate::parsing::{self, PGNGame};
+use crate::player::Player;
+use crate::source::Source;
+
+/// The two sides of a chess game.
+pub use shakmaty::Color;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 /// A generic representation of a chess game.
 pub struct Game<M: Move> {
     result: GameResult,
     moves: GameMoves<M>,
-    white_player: String,
-    black_player: String,
+    clocks: Vec<Option<Duration>>,
+    evals: Vec<Option<Eval>>,
+    metadata: GameMetadata,
 }
 
-impl<M: Move> From<PGNGame> for Game<M> {
-    fn from(pgn_game: PGNGame) -> Game<M> {
-        let mut moves: Vec<M> = Vec::new();
+impl<M: Move> TryFrom<PGNGame> for Game<M> {
+    type Error = Error;
 
-        for san_move in pgn_game.moves().clone() {
-            moves.push(M::from_algebraic(san_move));
-        }
+    /// Builds a game from `pgn_game`, failing if it has no result, as with unfinished games
+    /// marked `*`, or if one of its moves isn't valid SAN. Players missing from its headers have
+    /// no name.
+    fn try_from(pgn_game: PGNGame) -> Result<Game<M>, Error> {
+        let result = pgn_game.result().ok_or(Error::MissingResult)?;
+        let moves = pgn_game
+            .moves()
+            .iter()
+            .map(|san_move| M::try_from_algebraic(san_move.clone()))
+            .collect::<Result<Vec<M>, _>>()?;
 
-        Game {
-            result: pgn_game.result().expect("No result in PGN"),
+        Ok(Game {
+            result,
             moves: GameMoves::new(moves),
-            white_player: String::from(pgn_game.white_player().expect("No white player in PGN")),
-            black_player: String::from(pgn_game.black_player().expect("No black player in PGN")),
+            clocks: pgn_game.clocks().clone(),
+            evals: pgn_game.evals().clone(),
+            metadata: pgn_game.metadata().clone(),
+        })
+    }
+}
+
+impl<M: Move> Game<M> {
+    /// Returns a builder for making a game from its parts rather than from PGN.
+    ///
+    /// ```
+    /// use chesshound::game::{GameResult, GivePlayers};
+    /// use chesshound::{AlgebraicMove, Game, Move};
+    ///
+    /// let game = Game::builder()
+    ///     .white("alice")
+    ///     .black("bob")
+    ///     .result(GameResult::Draw)
+    ///     .moves(vec![AlgebraicMove::from_algebraic(String::from("e4"))])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(game.white_player(), Some("alice"));
+    /// ```
+    pub fn builder() -> GameBuilder<M> {
+        GameBuilder {
+            result: None,
+            moves: Vec::new(),
+            clocks: Vec::new(),
+            evals: Vec::new(),
+            metadata: GameMetadata::new(),
         }
     }
+
+    /// Sets where the game came from and its identifier there, as when merging games from
+    /// several places.
+    pub fn set_source(&mut self, source: Source, source_id: &str) {
+        self.metadata.set_source(source, source_id);
+    }
+}
+
+/// A builder of games, made by [`Game::builder`]. Only the result is required; everything else
+/// is unknown unless set.
+#[derive(Debug, Clone)]
+pub struct GameBuilder<M: Move> {
+    result: Option<GameResult>,
+    moves: Vec<M>,
+    clocks: Vec<Option<Duration>>,
+    evals: Vec<Option<Eval>>,
+    metadata: GameMetadata,
+}
+
+impl<M: Move> GameBuilder<M> {
+    /// Sets the name of the player playing white.
+    pub fn white(mut self, player: &str) -> GameBuilder<M> {
+        self.metadata.white.set_name(Some(String::from(player)));
+        self
+    }
+
+    /// Sets the name of the player playing black.
+    pub fn black(mut self, player: &str) -> GameBuilder<M> {
+        self.metadata.black.set_name(Some(String::from(player)));
+        self
+    }
+
+    /// Sets the player playing white, with their rating and title.
+    pub fn white_player(mut self, player: Player) -> GameBuilder<M> {
+        self.metadata.white = player;
+        self
+    }
+
+    /// Sets the player playing black, with their rating and title.
+    pub fn black_player(mut self, player: Player) -> GameBuilder<M> {
+        self.metadata.black = player;
+        self
+    }
+
+    /// Sets the result of the game.
+    pub fn result(mut self, result: GameResult) -> GameBuilder<M> {
+        self.result = Some(result);
+        self
+    }
+
+    /// Sets the moves of the game, in the order they were played.
+    pub fn moves(mut self, moves: impl IntoIterator<Item = M>) -> GameBuilder<M> {
+        self.moves = moves.into_iter().collect();
+        self
+    }
+
+    /// Sets the time left on the mover's clock after each move, or `None` where unknown.
+    pub fn clocks(mut self, clocks: Vec<Option<Duration>>) -> GameBuilder<M> {
+        self.clocks = clocks;
+        self
+    }
+
+    /// Sets the engine evaluation after each move, or `None` where unknown.
+    pub fn evals(mut self, evals: Vec<Option<Eval>>) -> GameBuilder<M> {
+        self.evals = evals;
+        self
+    }
+
+    /// Sets the rating of the player playing white.
+    pub fn white_rating(mut self, rating: u16) -> GameBuilder<M> {
+        self.metadata.white.set_rating(Some(rating));
+        self
+    }
+
+    /// Sets the rating of the player playing black.
+    pub fn black_rating(mut self, rating: u16) -> GameBuilder<M> {
+        self.metadata.black.set_rating(Some(rating));
+        self
+    }
+
+    /// Sets the date the game was played.
+    pub fn date(mut self, date: NaiveDate) -> GameBuilder<M> {
+        self.metadata.date = Some(date);
+        self
+    }
+
+    /// Sets the time of day the game started, in UTC.
+    pub fn time(mut self, time: NaiveTime) -> GameBuilder<M> {
+        self.metadata.time = Some(time);
+        self
+    }
+
+    /// Sets the time control the game was played with.
+    pub fn time_control(mut self, time_control: TimeControl) -> GameBuilder<M> {
+        self.metadata.time_control = Some(time_control);
+        self
+    }
+
+    /// Sets the URL of the game.
+    pub fn link(mut self, link: &str) -> GameBuilder<M> {
+        self.metadata.link = Some(String::from(link));
+        self
+    }
+
+    /// Sets where the game came from and its identifier there.
+    pub fn source(mut self, source: Source, source_id: &str) -> GameBuilder<M> {
+        self.metadata.set_source(source, source_id);
+        self
+    }
+
+    /// Sets the PGN tag `name` to `value`, as [`GameMetadata::set_tag`] does.
+    pub fn tag(mut self, name: &str, value: &str) -> GameBuilder<M> {
+        self.metadata.set_tag(name, value);
+        self
+    }
+
+    /// Sets all the metadata of the game at once, replacing anything set before.
+    pub fn metadata(mut self, metadata: GameMetadata) -> GameBuilder<M> {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Returns the game, or [`Error::MissingResult`] if no result was set. Clocks and
+    /// evaluations are padded with `None`, or cut short, to match the number of moves.
+    pub fn build(mut self) -> Result<Game<M>, Error> {
+        let result = self.result.ok_or(Error::MissingResult)?;
+
+        self.clocks.resize(self.moves.len(), None);
+        self.evals.resize(self.moves.len(), None);
+
+        Ok(Game {
+            result,
+            moves: GameMoves::new(self.moves),
+            clocks: self.clocks,
+            evals: self.evals,
+            metadata: self.metadata,
+        })
+    }
+}
+
+/// What's known about a game besides its moves, taken from its PGN tags. Tags chesshound doesn't
+/// understand are kept as they are, so they aren't lost.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GameMetadata {
+    event: Option<String>,
+    site: Option<String>,
+    round: Option<String>,
+    date: Option<NaiveDate>,
+    utc_date: Option<NaiveDate>,
+    time: Option<NaiveTime>,
+    white: Player,
+    black: Player,
+    eco: Option<String>,
+    time_control: Option<TimeControl>,
+    termination: Option<String>,
+    link: Option<String>,
+    source: Option<Source>,
+    source_id: Option<String>,
+    extra_tags: Vec<(String, String)>,
+}
+
+impl GameMetadata {
+    /// Creates metadata with nothing known.
+    pub fn new() -> GameMetadata {
+        GameMetadata::default()
+    }
+
+    /// Sets the PGN tag `name` to `value`. Values of tags chesshound understands are parsed, and
+    /// ignored if they can't be, and `?` is taken to mean unknown. Other tags are kept as extra
+    /// tags. The Result tag belongs to the game rather than its metadata, so isn't kept.
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        let text = || match value {
+            "" | "?" => None,
+            _ => Some(String::from(value)),
+        };
+
+        match name {
+            "Result" => {}
+            "Event" => self.event = text(),
+            "Site" => self.site = text(),
+            "Round" => self.round = text(),
+            "Date" => self.date = parsing::log_unparsed(name, value, parsing::parse_date(value)),
+            "UTCDate" => {
+                self.utc_date = parsing::log_unparsed(name, value, parsing::parse_date(value))
+            }
+            "UTCTime" => {
+                self.time = parsing::log_unparsed(
+                    name,
+                    value,
+                    NaiveTime::parse_from_str(value, "%H:%M:%S").ok(),
+                )
+            }
+            "White" => self.white.set_name(text()),
+            "Black" => self.black.set_name(text()),
+            "WhiteElo" => {
+                self.white
+                    .set_rating(parsing::log_unparsed(name, value, value.parse().ok()))
+            }
+            "BlackElo" => {
+                self.black
+                    .set_rating(parsing::log_unparsed(name, value, value.parse().ok()))
+            }
+            "WhiteTitle" => {
+                self.white
+                    .set_title(parsing::log_unparsed(name, value, value.parse().ok()))
+            }
+            "BlackTitle" => {
+                self.black
+                    .set_title(parsing::log_unparsed(name, value, value.parse().ok()))
+            }
+            "ECO" => self.eco = text(),
+            "TimeControl" => {
+                self.time_control = parsing::log_unparsed(name, value, value.parse().ok())
+            }
+            "Termination" => self.termination = text(),
+            "Link" => self.link = text(),
+            "Source" => self.source = parsing::log_unparsed(name, value, value.parse().ok()),
+            "SourceId" => self.source_id = text(),
+            _ => match self.extra_tags.iter_mut().find(|(tag, _)| tag == name) {
+                Some((_, old_value)) => *old_value = String::from(value),
+                None => self
+                    .extra_tags
+                    .push((String::from(name), String::from(value))),
+            },
+        }
+    }
+
+    /// Returns the value of the PGN tag `name` as it would be written, or `None` if it isn't
+    /// known. Extra tags are given as they were set.
+    pub fn tag(&self, name: &str) -> Option<String> {
+        match name {
+            "Event" => self.event.clone(),
+            "Site" => self.site.clone(),
+            "Round" => self.round.clone(),
+            "Date" => self.date.map(|date| date.format("%Y.%m.%d").to_string()),
+            // Without a UTCDate tag, only games with a start time are known to have had one.
+            "UTCDate" => self
+                .utc_date()
+                .map(|date| date.format("%Y.%m.%d").to_string()),
+            "UTCTime" => self.time.map(|time| time.format("%H:%M:%S").to_string()),
+            "White" => self.white_player().map(String::from),
+            "Black" => self.black_player().map(String::from),
+            "WhiteElo" => self.white.rating().map(|rating| rating.to_string()),
+            "BlackElo" => self.black.rating().map(|rating| rating.to_string()),
+            "WhiteTitle" => self.white.title().map(|title| title.to_string()),
+            "BlackTitle" => self.black.title().map(|title| title.to_string()),
+            "ECO" => self.eco.clone(),
+            "TimeControl" => self
+                .time_control
+                .map(|time_control| time_control.to_string()),
+            "Termination" => self.termination.clone(),
+            "Link" => self.link.clone(),
+            "Source" => self.source.map(|source| source.to_string()),
+            "SourceId" => self.source_id.clone(),
+            _ => self
+                .extra_tags
+                .iter()
+                .find(|(tag, _)| tag == name)
+                .map(|(_, value)| value.clone()),
+        }
+    }
+
+    /// Returns the name of the event, or tournament, the game was played in.
+    pub fn event(&self) -> Option<&str> {
+        self.event.as_deref()
+    }
+
+    /// Returns where the game was played, which is a URL for games played online.
+    pub fn site(&self) -> Option<&str> {
+        self.site.as_deref()
+    }
+
+    /// Returns the round of the event the game was played in, such as `3` or `3.1`.
+    pub fn round(&self) -> Option<&str> {
+        self.round.as_deref()
+    }
+
+    /// Returns the date the game was played, preferring the UTCDate tag over the Date tag when
+    /// both are given. Partially unknown dates such as `2021.??.??` are treated as unknown.
+    pub fn date(&self) -> Option<NaiveDate> {
+        self.utc_date.or(self.date)
+    }
+
+    /// Returns the UTC date the game was played on, from the UTCDate tag, or from the Date tag
+    /// when the UTC time the game started at is known.
+    pub fn utc_date(&self) -> Option<NaiveDate> {
+        self.utc_date.or(self.time.and(self.date))
+    }
+
+    /// Returns the UTC time the game started at.
+    pub fn time(&self) -> Option<NaiveTime> {
+        self.time
+    }
+
+    /// Returns the player playing white.
+    pub fn white(&self) -> &Player {
+        &self.white
+    }
+
+    /// Returns the player playing black.
+    pub fn black(&self) -> &Player {
+        &self.black
+    }
+
+    /// Returns the name of the player playing white, if known.
+    pub fn white_player(&self) -> Option<&str> {
+        self.white.name()
+    }
+
+    /// Returns the name of the player playing black, if known.
+    pub fn black_player(&self) -> Option<&str> {
+        self.black.name()
+    }
+
+    /// Returns the rating of the player playing white.
+    pub fn white_rating(&self) -> Option<u16> {
+        self.white.rating()
+    }
+
+    /// Returns the rating of the player playing black.
+    pub fn black_rating(&self) -> Option<u16> {
+        self.black.rating()
+    }
+
+    /// Returns the ECO code of the opening, as given in the ECO tag.
+    pub fn eco(&self) -> Option<&str> {
+        self.eco.as_deref()
+    }
+
+    /// Returns the time control the game was played with.
+    pub fn time_control(&self) -> Option<TimeControl> {
+        self.time_control
+    }
+
+    /// Returns how the game ended as given in the Termination tag, such as `Normal` or `Time
+    /// forfeit`.
+    pub fn termination(&self) -> Option<&str> {
+        self.termination.as_deref()
+    }
+
+    /// Returns the URL of the game, taken from the Link tag that chess.com writes, or from the
+    /// Site tag when it holds a URL, as on lichess.
+    pub fn link(&self) -> Option<&str> {
+        self.link.as_deref().or_else(|| {
+            self.site
+                .as_deref()
+                .filter(|site| site.starts_with("http://") || site.starts_with("https://"))
+        })
+    }
+
+    /// Returns where the game came from, taken from the Source tag chesshound writes when
+    /// merging games, or else from the site its link is to.
+    pub fn source(&self) -> Option<Source> {
+        self.source
+            .or_else(|| Source::of_link(self.link()?).map(|(source, _)| source))
+    }
+
+    /// Returns the identifier of the game where it came from, such as its lichess game ID or
+    /// the file and number of a local game, taken from the SourceId tag or else from its link.
+    pub fn source_id(&self) -> Option<String> {
+        self.source_id
+            .clone()
+            .or_else(|| Source::of_link(self.link()?).map(|(_, source_id)| source_id))
+    }
+
+    /// Sets where the game came from and its identifier there, as written in the Source and
+    /// SourceId tags.
+    pub fn set_source(&mut self, source: Source, source_id: &str) {
+        self.source = Some(source);
+        self.source_id = Some(String::from(source_id));
+    }
+
+    /// Returns the tags chesshound doesn't understand, as name and value pairs in the order
+    /// they were set.
+    pub fn extra_tags(&self) -> &[(String, String)] {
+        &self.extra_tags
+    }
+}
+
+impl<M: 'static + Move + Clone> Game<M> {
+    /// Returns the identifier of this game, which stays the same wherever it's read from.
+    pub fn id(&self) -> GameId {
+        GameId::of(self)
+    }
+}
+
+impl<M: Move + Clone> Game<M> {
+    /// Returns this game as if it had reached the position after its first `ply` moves by
+    /// playing `line` instead, keeping the moves after it. This lets games that transpose into
+    /// the same position be grouped by the line that reaches it. Moves of `line` have no clock
+    /// times or evaluations.
+    pub fn transposed(mut self, line: Vec<M>, ply: usize) -> Game<M> {
+        let ply = ply.min(self.moves.moves.len());
+        let line_length = line.len();
+
+        self.moves.moves.splice(..ply, line);
+
+        let clock_ply = ply.min(self.clocks.len());
+        self.clocks.splice(..clock_ply, vec![None; line_length]);
+
+        let eval_ply = ply.min(self.evals.len());
+        self.evals.splice(..eval_ply, vec![None; line_length]);
+
+        self
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -36,7 +493,7 @@ pub struct GameMoves<M: Move> {
 impl<M: Move> GameMoves<M> {
     /// Constructs a new `GameMoves<M>` with no moves played.
     pub fn new(moves: Vec<M>) -> GameMoves<M> {
-        GameMoves { moves: moves }
+        GameMoves { moves }
     }
 }
 
@@ -46,6 +503,124 @@ pub trait GiveResult {
     fn result(&self) -> GameResult;
 }
 
+/// Interface for types that give the players of a chess game.
+pub trait GivePlayers {
+    /// Returns the player playing white.
+    fn white(&self) -> &Player;
+
+    /// Returns the player playing black.
+    fn black(&self) -> &Player;
+
+    /// Returns the player playing `color`.
+    fn player(&self, color: Color) -> &Player {
+        match color {
+            Color::White => self.white(),
+            Color::Black => self.black(),
+        }
+    }
+
+    /// Returns the name of the player playing white, if known.
+    fn white_player(&self) -> Option<&str> {
+        self.white().name()
+    }
+
+    /// Returns the name of the player playing black, if known.
+    fn black_player(&self) -> Option<&str> {
+        self.black().name()
+    }
+
+    /// Returns the name of the player playing `color`, if known.
+    fn player_of(&self, color: Color) -> Option<&str> {
+        self.player(color).name()
+    }
+
+    /// Returns the color `player` played as, or `None` if they didn't play in this game. Names
+    /// are matched ignoring case, and players whose name isn't known never match.
+    fn color_of(&self, player: &str) -> Option<Color> {
+        if self.white().is_named(player) {
+            Some(Color::White)
+        } else if self.black().is_named(player) {
+            Some(Color::Black)
+        } else {
+            None
+        }
+    }
+}
+
+/// Interface for types that give the ratings of the players in a chess game.
+pub trait GiveRatings {
+    /// Returns the rating of the player playing white, if known.
+    fn white_rating(&self) -> Option<u16>;
+
+    /// Returns the rating of the player playing black, if known.
+    fn black_rating(&self) -> Option<u16>;
+
+    /// Returns the rating of the player playing `color`, if known.
+    fn rating_of(&self, color: Color) -> Option<u16> {
+        match color {
+            Color::White => self.white_rating(),
+            Color::Black => self.black_rating(),
+        }
+    }
+
+    /// Returns the average rating of the players, counting only known ratings, or `None` if
+    /// neither is known.
+    fn average_rating(&self) -> Option<u16> {
+        match (self.white_rating(), self.black_rating()) {
+            (Some(white), Some(black)) => Some(((u32::from(white) + u32::from(black)) / 2) as u16),
+            (rating, None) | (None, rating) => rating,
+        }
+    }
+}
+
+/// Interface for types that give when a chess game was played.
+pub trait GiveDate {
+    /// Returns the date the game was played on, if known.
+    fn date(&self) -> Option<NaiveDate>;
+
+    /// Returns the time of day the game started at, if known.
+    fn time(&self) -> Option<NaiveTime>;
+}
+
+/// Interface for types that give where a chess game can be found online.
+pub trait GiveLink {
+    /// Returns the URL of the game, such as its page on lichess or chess.com, if known.
+    fn link(&self) -> Option<&str>;
+}
+
+/// Interface for types that give where a chess game came from, for games merged from several
+/// places.
+pub trait GiveSource {
+    /// Returns the site or kind of file the game came from, if known.
+    fn source(&self) -> Option<Source>;
+
+    /// Returns the identifier of the game where it came from, if known.
+    fn source_id(&self) -> Option<String>;
+}
+
+/// Interface for types that give the metadata of a chess game.
+pub trait GiveMetadata {
+    /// Returns what's known about the game besides its moves.
+    fn metadata(&self) -> &GameMetadata;
+}
+
+/// Interface for types that give the clock times of a chess game.
+pub trait GiveClocks {
+    /// Returns the clock time remaining after each move, or `None` for moves where no clock was
+    /// recorded.
+    fn clocks(&self) -> &[Option<Duration>];
+
+    /// Returns the time control the game was played with, if known.
+    fn time_control(&self) -> Option<TimeControl>;
+}
+
+/// Interface for types that give engine evaluations of the positions in a chess game.
+pub trait GiveEvals {
+    /// Returns the evaluation of the position after each move, or `None` for moves where no
+    /// evaluation was recorded.
+    fn evals(&self) -> &[Option<Eval>];
+}
+
 /// Enum representing the possible results in a game.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GameResult {
@@ -60,28 +635,219 @@ impl<M: Move> GiveResult for Game<M> {
     }
 }
 
+impl<M: Move> GivePlayers for Game<M> {
+    fn white(&self) -> &Player {
+        self.metadata.white()
+    }
+
+    fn black(&self) -> &Player {
+        self.metadata.black()
+    }
+}
+
+impl<M: Move> GiveRatings for Game<M> {
+    fn white_rating(&self) -> Option<u16> {
+        self.metadata.white_rating()
+    }
+
+    fn black_rating(&self) -> Option<u16> {
+        self.metadata.black_rating()
+    }
+}
+
+impl<M: Move> GiveDate for Game<M> {
+    fn date(&self) -> Option<NaiveDate> {
+        self.metadata.date()
+    }
+
+    fn time(&self) -> Option<NaiveTime> {
+        self.metadata.time()
+    }
+}
+
+impl<M: Move> GiveLink for Game<M> {
+    fn link(&self) -> Option<&str> {
+        self.metadata.link()
+    }
+}
+
+impl<M: Move> GiveSource for Game<M> {
+    fn source(&self) -> Option<Source> {
+        self.metadata.source()
+    }
+
+    fn source_id(&self) -> Option<String> {
+        self.metadata.source_id()
+    }
+}
+
+impl<M: Move> GiveMetadata for Game<M> {
+    fn metadata(&self) -> &GameMetadata {
+        &self.metadata
+    }
+}
+
+impl<M: Move> GiveClocks for Game<M> {
+    fn clocks(&self) -> &[Option<Duration>] {
+        &self.clocks
+    }
+
+    fn time_control(&self) -> Option<TimeControl> {
+        self.metadata.time_control()
+    }
+}
+
+impl<M: Move> GiveEvals for Game<M> {
+    fn evals(&self) -> &[Option<Eval>] {
+        &self.evals
+    }
+}
+
 impl<M: 'static + Clone + Move> ListMoves<M> for Game<M> {
     fn list_moves(&self) -> Box<dyn Iterator<Item = M>> {
         self.moves.list_moves()
     }
 }
 
+// Games held by reference, such as those handed out by a `GameStore`, give everything the games
+// themselves do, so that trees, filters, and statistics can work on them without cloning.
+
+impl<G: GiveResult> GiveResult for &G {
+    fn result(&self) -> GameResult {
+        (**self).result()
+    }
+}
+
+impl<G: GivePlayers> GivePlayers for &G {
+    fn white(&self) -> &Player {
+        (**self).white()
+    }
+
+    fn black(&self) -> &Player {
+        (**self).black()
+    }
+}
+
+impl<G: GiveRatings> GiveRatings for &G {
+    fn white_rating(&self) -> Option<u16> {
+        (**self).white_rating()
+    }
+
+    fn black_rating(&self) -> Option<u16> {
+        (**self).black_rating()
+    }
+}
+
+impl<G: GiveDate> GiveDate for &G {
+    fn date(&self) -> Option<NaiveDate> {
+        (**self).date()
+    }
+
+    fn time(&self) -> Option<NaiveTime> {
+        (**self).time()
+    }
+}
+
+impl<G: GiveLink> GiveLink for &G {
+    fn link(&self) -> Option<&str> {
+        (**self).link()
+    }
+}
+
+impl<G: GiveSource> GiveSource for &G {
+    fn source(&self) -> Option<Source> {
+        (**self).source()
+    }
+
+    fn source_id(&self) -> Option<String> {
+        (**self).source_id()
+    }
+}
+
+impl<G: GiveMetadata> GiveMetadata for &G {
+    fn metadata(&self) -> &GameMetadata {
+        (**self).metadata()
+    }
+}
+
+impl<G: GiveClocks> GiveClocks for &G {
+    fn clocks(&self) -> &[Option<Duration>] {
+        (**self).clocks()
+    }
+
+    fn time_control(&self) -> Option<TimeControl> {
+        (**self).time_control()
+    }
+}
+
+impl<G: GiveEvals> GiveEvals for &G {
+    fn evals(&self) -> &[Option<Eval>] {
+        (**self).evals()
+    }
+}
+
+impl<M: Clone + Move, G: ListMoves<M>> ListMoves<M> for &G {
+    fn list_moves(&self) -> Box<dyn Iterator<Item = M>> {
+        (**self).list_moves()
+    }
+}
+
+/// Enum representing the result of a game from the perspective of one of its players.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PlayerResult {
+    Won,
+    Lost,
+    Drew,
+}
+
+impl GameResult {
+    /// Returns this result from the perspective of the player playing `color`.
+    pub fn for_color(self, color: Color) -> PlayerResult {
+        match (self, color) {
+            (GameResult::Draw, _) => PlayerResult::Drew,
+            (GameResult::WhiteWon, Color::White) | (GameResult::BlackWon, Color::Black) => {
+                PlayerResult::Won
+            }
+            _ => PlayerResult::Lost,
+        }
+    }
+
+    /// Returns this result with the colors swapped, for games looked at with the colors reversed.
+    pub fn reversed(self) -> GameResult {
+        match self {
+            GameResult::WhiteWon => GameResult::BlackWon,
+            GameResult::BlackWon => GameResult::WhiteWon,
+            GameResult::Draw => GameResult::Draw,
+        }
+    }
+}
+
 impl GiveResult for GameResult {
     fn result(&self) -> GameResult {
         *self
     }
 }
 
-impl From<String> for GameResult {
-    fn from(string: String) -> GameResult {
-        if &string == "1-0" {
-            GameResult::WhiteWon
-        } else if &string == "0-1" {
-            GameResult::BlackWon
-        } else if &string == "1/2-1/2" {
-            GameResult::Draw
-        } else {
-            panic!("Invalid result format: {result}", result = string)
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameResult::WhiteWon => write!(f, "1-0"),
+            GameResult::BlackWon => write!(f, "0-1"),
+            GameResult::Draw => write!(f, "1/2-1/2"),
+        }
+    }
+}
+
+impl FromStr for GameResult {
+    type Err = Error;
+
+    /// Parses results as written in PGN. Unfinished games, written `*`, have no result.
+    fn from_str(result: &str) -> Result<GameResult, Error> {
+        match result {
+            "1-0" => Ok(GameResult::WhiteWon),
+            "0-1" => Ok(GameResult::BlackWon),
+            "1/2-1/2" => Ok(GameResult::Draw),
+            _ => Err(Error::InvalidResult(String::from(result))),
         }
     }
 }
@@ -108,6 +874,162 @@ mod tests {
 
     type AlgebraicGame = GameMoves<AlgebraicMove>;
 
+    #[rstest]
+    fn transposed_should_replace_the_line_to_the_position() {
+        let game = parse_game(
+            "[White \"a\"]\n[Black \"b\"]\n[Result \"1-0\"]\n\
+             1. c4 { [%clk 0:03:00] } e6 2. d4 Nf6 3. Nc3 { [%clk 0:02:50] } 1-0",
+        );
+        let line = ["d4", "Nf6", "c4", "e6"]
+            .iter()
+            .map(|move_| AlgebraicMove::from_algebraic(String::from(*move_)))
+            .collect();
+
+        let transposed = game.transposed(line, 4);
+
+        assert_eq!(
+            transposed
+                .list_moves()
+                .map(Move::to_algebraic)
+                .collect::<Vec<_>>(),
+            vec!["d4", "Nf6", "c4", "e6", "Nc3"]
+        );
+        assert_eq!(
+            transposed.clocks(),
+            &[None, None, None, None, Some(Duration::from_secs(170))]
+        );
+        assert_eq!(transposed.evals().len(), 5);
+    }
+
+    #[rstest]
+    fn builder_should_match_parsed_games() {
+        let parsed = parse_game(
+            "[White \"alice\"]\n[Black \"bob\"]\n[Result \"0-1\"]\n[WhiteElo \"1500\"]\n\
+             [TimeControl \"180+2\"]\n1. f3 { [%clk 0:03:00] } e5 0-1",
+        );
+        let moves = ["f3", "e5"]
+            .iter()
+            .map(|move_| AlgebraicMove::from_algebraic(String::from(*move_)));
+
+        let built = Game::builder()
+            .white("alice")
+            .black("bob")
+            .result(GameResult::BlackWon)
+            .white_rating(1500)
+            .time_control("180+2".parse().unwrap())
+            .moves(moves)
+            .clocks(vec![Some(Duration::from_secs(180))])
+            .build()
+            .unwrap();
+
+        assert_eq!(built, parsed);
+        assert!(Game::<AlgebraicMove>::builder()
+            .white("alice")
+            .build()
+            .is_err());
+    }
+
+    #[rstest(
+        pgn,
+        succeeds,
+        case("[Result \"1-0\"]\n1. e4 e5 1-0", true),
+        case("[White \"a\"]\n[Black \"b\"]\n1. e4 e5", false),
+        case("[White \"a\"]\n[Black \"b\"]\n[Result \"*\"]\n1. e4 e5 *", false)
+    )]
+    fn try_from_should_require_only_a_result(pgn: &str, succeeds: bool) {
+        let pgn_game = pgn_reader::Reader::new(pgn.as_bytes())
+            .read_game(&mut crate::GameParser::new())
+            .unwrap()
+            .unwrap();
+
+        match Game::<AlgebraicMove>::try_from(pgn_game) {
+            Ok(game) => {
+                assert!(succeeds);
+                assert_eq!(game.white_player(), None);
+                assert_eq!(game.color_of("?"), None);
+            }
+            Err(error) => {
+                assert!(!succeeds);
+                assert_eq!(error.to_string(), "Game has no result");
+            }
+        }
+    }
+
+    #[rstest]
+    fn metadata_should_parse_known_tags_and_keep_others() {
+        let mut metadata = GameMetadata::new();
+
+        metadata.set_tag("Event", "Rated Blitz game");
+        metadata.set_tag("Site", "https://lichess.org/abcdefgh");
+        metadata.set_tag("Round", "?");
+        metadata.set_tag("WhiteElo", "1500");
+        metadata.set_tag("BlackElo", "-");
+        metadata.set_tag("ECO", "B20");
+        metadata.set_tag("Variant", "Standard");
+        metadata.set_tag("Opening", "Sicilian");
+        metadata.set_tag("Variant", "Chess960");
+
+        assert_eq!(metadata.event(), Some("Rated Blitz game"));
+        assert_eq!(metadata.round(), None);
+        assert_eq!(metadata.white_rating(), Some(1500));
+        assert_eq!(metadata.black_rating(), None);
+        assert_eq!(metadata.eco(), Some("B20"));
+        assert_eq!(metadata.link(), Some("https://lichess.org/abcdefgh"));
+        assert_eq!(metadata.tag("WhiteElo"), Some(String::from("1500")));
+        assert_eq!(metadata.tag("Link"), None);
+        assert_eq!(
+            metadata.extra_tags(),
+            &[
+                (String::from("Variant"), String::from("Chess960")),
+                (String::from("Opening"), String::from("Sicilian"))
+            ]
+        );
+    }
+
+    #[rstest]
+    fn metadata_should_keep_the_date_and_utc_date_apart() {
+        let mut metadata = GameMetadata::new();
+
+        metadata.set_tag("Date", "2021.09.05");
+        metadata.set_tag("UTCDate", "2021.09.06");
+
+        assert_eq!(metadata.tag("Date"), Some(String::from("2021.09.05")));
+        assert_eq!(metadata.tag("UTCDate"), Some(String::from("2021.09.06")));
+        assert_eq!(metadata.date(), NaiveDate::from_ymd_opt(2021, 9, 6));
+
+        let mut metadata = GameMetadata::new();
+
+        metadata.set_tag("Date", "2021.09.05");
+
+        assert_eq!(metadata.utc_date(), None);
+
+        metadata.set_tag("UTCTime", "23:30:00");
+
+        assert_eq!(metadata.utc_date(), NaiveDate::from_ymd_opt(2021, 9, 5));
+    }
+
+    #[rstest]
+    fn metadata_should_tag_sources_or_find_them_from_links() {
+        let mut metadata = GameMetadata::new();
+
+        metadata.set_tag("Site", "https://lichess.org/abcd1234/black");
+
+        assert_eq!(metadata.source(), Some(Source::Lichess));
+        assert_eq!(metadata.source_id(), Some(String::from("abcd1234")));
+        assert_eq!(metadata.tag("Source"), None);
+
+        metadata.set_tag("Source", "Local");
+        metadata.set_tag("SourceId", "otb.pgn#3");
+
+        assert_eq!(metadata.source(), Some(Source::Local));
+        assert_eq!(metadata.source_id(), Some(String::from("otb.pgn#3")));
+        assert_eq!(metadata.tag("Source"), Some(String::from("local")));
+
+        metadata.set_tag("Source", "fics");
+
+        assert_eq!(metadata.source(), Some(Source::Lichess));
+    }
+
     #[rstest(
         game,
         same_game,
@@ -131,9 +1053,46 @@ mod tests {
 
 #[cfg(test)]
 pub mod test_utils {
-    use crate::game::GameMoves;
+    use std::convert::TryFrom;
+
+    use pgn_reader::Reader;
+
+    use crate::game::{Game, GameMoves};
     use crate::moves::Move;
-    use crate::AlgebraicMove;
+    use crate::{AlgebraicMove, GameParser};
+
+    /// Parses a single game from `pgn`, which must contain a Result header.
+    pub fn parse_game(pgn: &str) -> Game<AlgebraicMove> {
+        let mut reader = Reader::new(pgn.as_bytes());
+
+        let mut game_parser = GameParser::new();
+        let pgn_game = reader.read_game(&mut game_parser).unwrap().unwrap();
+
+        Game::try_from(pgn_game).unwrap()
+    }
+
+    /// Parses a game between `white` and `black` that ended in `result` after `moves`, which is
+    /// movetext such as `"1. e4 e5"`.
+    pub fn game(white: &str, black: &str, result: &str, moves: &str) -> Game<AlgebraicMove> {
+        parse_game(&format!(
+            "[White \"{}\"]\n[Black \"{}\"]\n[Result \"{}\"]\n{} {}",
+            white, black, result, moves, result
+        ))
+    }
+
+    /// Parses a game like [`game`] does, played on `date`, which is written as in a Date header.
+    pub fn dated_game(
+        white: &str,
+        black: &str,
+        result: &str,
+        date: &str,
+        moves: &str,
+    ) -> Game<AlgebraicMove> {
+        parse_game(&format!(
+            "[White \"{}\"]\n[Black \"{}\"]\n[Result \"{}\"]\n[Date \"{}\"]\n{} {}",
+            white, black, result, date, moves, result
+        ))
+    }
 
     pub mod results {
         use super::super::GameResult;
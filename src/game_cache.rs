@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+use crate::eval;
+use crate::game::{Color, GiveDate, GiveEvals, GiveLink, GivePlayers, ListMoves};
+use crate::game_id::GameId;
+use crate::moves::Move;
+use crate::openings::Openings;
+use crate::phase::{self, Phase};
+
+/// What replaying a game tells about it, worked out once and kept in a [`GameCache`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedGame {
+    opening: Option<(String, String)>,
+    middlegame: Option<usize>,
+    endgame: Option<usize>,
+    centipawn_loss: [Option<f64>; 2],
+}
+
+impl CachedGame {
+    /// Works out what's cached about `game`, classifying its opening with `openings`.
+    pub fn of<M, G>(game: &G, openings: &Openings) -> CachedGame
+    where
+        M: 'static + Clone + Move,
+        G: GiveEvals + ListMoves<M>,
+    {
+        let opening = openings
+            .classify(game)
+            .map(|opening| (String::from(opening.eco()), String::from(opening.name())));
+        let phases = phase::phases(game.list_moves());
+        let start = |phase: Phase| phases.iter().position(|reached| *reached == phase);
+
+        CachedGame {
+            opening,
+            middlegame: start(Phase::Middlegame),
+            endgame: start(Phase::Endgame),
+            centipawn_loss: [
+                average_centipawn_loss(game.evals(), Color::White),
+                average_centipawn_loss(game.evals(), Color::Black),
+            ],
+        }
+    }
+
+    /// Returns the ECO code of the game's opening, or `None` if it's of no known opening.
+    pub fn eco(&self) -> Option<&str> {
+        self.opening.as_ref().map(|(eco, _)| eco.as_str())
+    }
+
+    /// Returns the name of the game's opening, or `None` if it's of no known opening.
+    pub fn opening_name(&self) -> Option<&str> {
+        self.opening.as_ref().map(|(_, name)| name.as_str())
+    }
+
+    /// Returns the number of moves played before `phase` began, or `None` if the game never
+    /// reached it.
+    pub fn phase_start(&self, phase: Phase) -> Option<usize> {
+        match phase {
+            Phase::Opening => Some(0),
+            Phase::Middlegame => self.middlegame,
+            Phase::Endgame => self.endgame,
+        }
+    }
+
+    /// Returns the average number of centipawns `color` lost per move according to the
+    /// evaluations recorded in the game, or `None` if none of their moves could be judged.
+    pub fn average_centipawn_loss(&self, color: Color) -> Option<f64> {
+        self.centipawn_loss[(color == Color::Black) as usize]
+    }
+
+    /// Reads a game from a line written by `to_line`, along with its identifier.
+    fn from_line(line: &str) -> Option<(GameId, CachedGame)> {
+        let fields = line.split('\t').collect::<Vec<_>>();
+
+        let (id, eco, name, middlegame, endgame, white_loss, black_loss) = match fields[..] {
+            [id, eco, name, middlegame, endgame, white_loss, black_loss] => {
+                (id, eco, name, middlegame, endgame, white_loss, black_loss)
+            }
+            _ => return None,
+        };
+
+        let opening = match (field(eco), field(name)) {
+            (Some(eco), Some(name)) => Some((String::from(eco), String::from(name))),
+            _ => None,
+        };
+
+        Some((
+            GameId::from_written(id),
+            CachedGame {
+                opening,
+                middlegame: parse_field(middlegame)?,
+                endgame: parse_field(endgame)?,
+                centipawn_loss: [parse_field(white_loss)?, parse_field(black_loss)?],
+            },
+        ))
+    }
+
+    /// Returns the game as a line of tab-separated fields, with `-` for what isn't known.
+    fn to_line(&self, id: &GameId) -> String {
+        let known = |value: Option<String>| value.unwrap_or_else(|| String::from("-"));
+
+        [
+            String::from(id.as_str()),
+            known(self.eco().map(String::from)),
+            known(self.opening_name().map(String::from)),
+            known(self.middlegame.map(|ply| ply.to_string())),
+            known(self.endgame.map(|ply| ply.to_string())),
+            known(self.centipawn_loss[0].map(|loss| loss.to_string())),
+            known(self.centipawn_loss[1].map(|loss| loss.to_string())),
+        ]
+        .join("\t")
+    }
+}
+
+/// Returns the text of a field written by `CachedGame::to_line`, or `None` for `-`.
+fn field(text: &str) -> Option<&str> {
+    match text {
+        "-" => None,
+        text => Some(text),
+    }
+}
+
+/// Parses a field written by `CachedGame::to_line`, giving `Some(None)` for `-` and `None` for
+/// anything that doesn't parse.
+fn parse_field<T: std::str::FromStr>(text: &str) -> Option<Option<T>> {
+    match field(text) {
+        Some(text) => text.parse().ok().map(Some),
+        None => Some(None),
+    }
+}
+
+/// Returns the average number of centipawns `color` lost per move according to `evals`, the
+/// evaluation after each move. A move is only judged when there are evaluations both before and
+/// after it, so the first move of the game is never judged.
+fn average_centipawn_loss(evals: &[Option<eval::Eval>], color: Color) -> Option<f64> {
+    let first_ply = match color {
+        Color::White => 2,
+        Color::Black => 1,
+    };
+
+    let losses = (first_ply..evals.len())
+        .step_by(2)
+        .filter_map(|ply| match (evals[ply - 1], evals[ply]) {
+            (Some(before), Some(after)) => Some(eval::centipawn_loss(before, after, color)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    match losses.len() {
+        0 => None,
+        moves => Some(losses.iter().map(|loss| f64::from(*loss)).sum::<f64>() / moves as f64),
+    }
+}
+
+/// What replaying games tells about them, such as their openings, where their phases begin, and
+/// how accurately they were played, kept by [`GameId`] so that it's only worked out once for
+/// each game even across runs.
+///
+/// The cache is written as a line for each game, holding its identifier, its ECO code and
+/// opening name, the moves played before its middlegame and endgame, and the average centipawn
+/// loss of white and black, separated by tabs. Entries are only as good as the opening table
+/// they were classified with, so a cache should be cleared when the table changes.
+///
+/// ```
+/// use chesshound::game_cache::GameCache;
+/// use chesshound::parsing::read_games;
+///
+/// let pgn = "[White \"alice\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n\n1. e4 c5 1-0\n";
+/// let games = read_games(pgn.as_bytes()).unwrap();
+///
+/// let mut cache = GameCache::new();
+/// assert_eq!(cache.get_or_insert(&games[0]).eco(), Some("B20"));
+///
+/// let mut written = Vec::new();
+/// cache.write(&mut written).unwrap();
+///
+/// let mut reloaded = GameCache::new();
+/// reloaded.read(written.as_slice()).unwrap();
+/// assert_eq!(reloaded.len(), 1);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GameCache {
+    games: HashMap<GameId, CachedGame>,
+    added: usize,
+}
+
+impl GameCache {
+    /// Creates an empty cache.
+    pub fn new() -> GameCache {
+        GameCache::default()
+    }
+
+    /// Returns what's cached about `game`, working it out and adding it to the cache first if it
+    /// isn't there. Openings are classified with the embedded table.
+    pub fn get_or_insert<M, G>(&mut self, game: &G) -> &CachedGame
+    where
+        M: 'static + Clone + Move,
+        G: GiveDate + GiveEvals + GiveLink + GivePlayers + ListMoves<M>,
+    {
+        let added = &mut self.added;
+
+        self.games.entry(GameId::of(game)).or_insert_with(|| {
+            *added += 1;
+            CachedGame::of(game, Openings::embedded())
+        })
+    }
+
+    /// Returns what's cached about the game identified by `id`, or `None` if it isn't cached.
+    pub fn get(&self, id: &GameId) -> Option<&CachedGame> {
+        self.games.get(id)
+    }
+
+    /// Returns the number of games in the cache.
+    pub fn len(&self) -> usize {
+        self.games.len()
+    }
+
+    /// Returns whether the cache has no games.
+    pub fn is_empty(&self) -> bool {
+        self.games.is_empty()
+    }
+
+    /// Returns the number of games added since the cache was created, which is zero when nothing
+    /// needs writing back.
+    pub fn added(&self) -> usize {
+        self.added
+    }
+
+    /// Loads games written by `write` into the cache, keeping what's already cached for games
+    /// found in both.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `reader` can't be read, or holds a line that isn't a cache entry.
+    pub fn read<R: Read>(&mut self, reader: R) -> io::Result<()> {
+        for line in BufReader::new(reader).lines() {
+            let line = line?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let (id, game) = CachedGame::from_line(&line).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid game cache entry: {}", line),
+                )
+            })?;
+
+            self.games.entry(id).or_insert(game);
+        }
+
+        Ok(())
+    }
+
+    /// Writes every game in the cache to `writer`, one per line.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut entries = self.games.iter().collect::<Vec<_>>();
+        // Sorted so the same cache is always written the same way.
+        entries.sort_by_key(|(id, _)| *id);
+
+        for (id, game) in entries {
+            writeln!(writer, "{}", game.to_line(id))?;
+        }
+
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::GameCache;
+    use crate::game::test_utils::*;
+    use crate::game::Color;
+    use crate::game_id::GameId;
+    use crate::phase::Phase;
+
+    #[rstest]
+    fn game_cache_should_keep_what_games_tell_across_runs() {
+        let games = vec![
+            parse_game(
+                "[White \"alice\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n\
+                 [Site \"https://lichess.org/abcd1234\"]\n\
+                 1. e4 { [%eval 0.3] } 1... e5 { [%eval 0.3] } 2. Qh5 { [%eval 0.0] } \
+                 2... Nc6 { [%eval 0.1] } 3. Bc4 { [%eval 0.1] } 3... Nf6 { [%eval 5.0] } \
+                 4. Qxf7# 1-0",
+            ),
+            parse_game("[White \"carol\"]\n[Black \"dave\"]\n[Result \"0-1\"]\n1. a3 0-1"),
+        ];
+
+        let mut cache = GameCache::new();
+
+        for game in &games {
+            cache.get_or_insert(game);
+        }
+
+        let cached = cache.get_or_insert(&games[0]).clone();
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.added(), 2);
+        assert_eq!(cached.eco(), Some("C20"));
+        assert_eq!(cached.phase_start(Phase::Opening), Some(0));
+        assert_eq!(cached.phase_start(Phase::Endgame), None);
+        assert_eq!(cached.average_centipawn_loss(Color::White), Some(15.));
+        assert_eq!(cached.average_centipawn_loss(Color::Black), Some(500. / 3.));
+
+        let mut written = Vec::new();
+        cache.write(&mut written).unwrap();
+
+        let mut reloaded = GameCache::new();
+        reloaded.read(written.as_slice()).unwrap();
+
+        assert_eq!(reloaded.len(), 2);
+        assert_eq!(reloaded.added(), 0);
+        assert_eq!(
+            reloaded.get(&GameId::from_link("lichess.org/abcd1234")),
+            Some(&cached)
+        );
+        assert!(reloaded.read("not an entry".as_bytes()).is_err());
+    }
+}
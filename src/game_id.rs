@@ -0,0 +1,165 @@
+use std::fmt;
+
+use crate::game::{GiveDate, GiveLink, GivePlayers, ListMoves};
+use crate::moves::Move;
+
+/// An identifier for a game that stays the same wherever the game is read from, so the same game
+/// is recognized across downloads, files, and formats.
+///
+/// Games with a link are identified by it, written without its scheme, as in
+/// `lichess.org/abcd1234`, since sites give each game its own page. Other games are identified by
+/// a fingerprint of their players, date, and moves, written as 16 hexadecimal digits. Player
+/// names are compared ignoring case and spacing, and the fingerprint doesn't depend on the
+/// version of chesshound or Rust that made it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GameId(String);
+
+impl GameId {
+    /// Returns the identifier of `game`, from its link if it has one and otherwise from its
+    /// players, date, and moves.
+    pub fn of<M, G>(game: &G) -> GameId
+    where
+        M: Clone + Move,
+        G: GiveDate + GiveLink + GivePlayers + ListMoves<M>,
+    {
+        match game.link() {
+            Some(link) => GameId::from_link(link),
+            None => GameId::from_content(game),
+        }
+    }
+
+    /// Returns the identifier of the game at `link`. Links differing only in their scheme, a
+    /// leading `www.`, the case of the site's name, a query, or a trailing slash give the same
+    /// identifier.
+    pub fn from_link(link: &str) -> GameId {
+        let link = link.trim();
+        let link = link
+            .split_once("://")
+            .map_or(link, |(_, without_scheme)| without_scheme);
+        let link = link.split(['?', '#']).next().unwrap_or_default();
+        let (site, path) = link.split_once('/').unwrap_or((link, ""));
+        let site = site.to_lowercase();
+        let site = site.strip_prefix("www.").unwrap_or(&site);
+        let path = path.trim_end_matches('/');
+
+        if path.is_empty() {
+            GameId(String::from(site))
+        } else {
+            GameId(format!("{}/{}", site, path))
+        }
+    }
+
+    /// Returns the identifier of `game` from its players, date, and moves, ignoring any link.
+    pub fn from_content<M, G>(game: &G) -> GameId
+    where
+        M: Clone + Move,
+        G: GiveDate + GivePlayers + ListMoves<M>,
+    {
+        let mut hasher = Fnv::new();
+
+        // Unknown players are hashed as PGN writes them, so that identifiers stay the same.
+        hasher.write(&normalize_name(game.white_player().unwrap_or("?")));
+        hasher.write(&normalize_name(game.black_player().unwrap_or("?")));
+        hasher.write(&game.date().map(|date| date.to_string()).unwrap_or_default());
+
+        for move_ in game.list_moves() {
+            hasher.write(&move_.to_algebraic());
+        }
+
+        GameId(format!("{:016x}", hasher.finish()))
+    }
+
+    /// Returns the identifier written as `id`, as `as_str` gives it, such as when reading it back
+    /// from a cache.
+    pub(crate) fn from_written(id: &str) -> GameId {
+        GameId(String::from(id))
+    }
+
+    /// Returns the identifier as text.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for GameId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Returns `name` in lowercase with runs of whitespace collapsed, so that names written
+/// differently by different sites compare equal.
+pub(crate) fn normalize_name(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// The 64-bit FNV-1a hash, used since `std`'s hashers may change between Rust versions, which
+/// would change identifiers kept in databases and caches.
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Fnv {
+        Fnv(0xcbf29ce484222325)
+    }
+
+    /// Adds `field` to the hash, followed by a separator so that fields can't run together.
+    fn write(&mut self, field: &str) {
+        for byte in field.bytes().chain(std::iter::once(0)) {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::GameId;
+    use crate::game::test_utils::*;
+
+    #[rstest(
+        link,
+        expected,
+        case("https://lichess.org/abcd1234", "lichess.org/abcd1234"),
+        case(
+            "http://WWW.Lichess.org/abcd1234/?ref=share#12",
+            "lichess.org/abcd1234"
+        ),
+        case("https://www.chess.com/game/live/123", "chess.com/game/live/123")
+    )]
+    fn from_link_should_normalize_links(link: &str, expected: &str) {
+        assert_eq!(GameId::from_link(link).as_str(), expected);
+    }
+
+    #[rstest]
+    fn game_ids_should_ignore_how_games_are_written() {
+        let game = parse_game(
+            "[White \"Alice\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n[Date \"2021.06.15\"]\n\
+             1. e4 { [%clk 0:03:00] } e5 1-0",
+        );
+        let same_game = parse_game(
+            "[White \"alice \"]\n[Black \"BOB\"]\n[Result \"1-0\"]\n[Date \"2021.06.15\"]\n\
+             [Event \"Casual\"]\n1. e4 e5 1-0",
+        );
+        let other_game = parse_game(
+            "[White \"alice\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n[Date \"2021.06.16\"]\n\
+             1. e4 e5 1-0",
+        );
+
+        assert_eq!(game.id(), same_game.id());
+        assert_ne!(game.id(), other_game.id());
+        assert_eq!(game.id().as_str(), "edac7cf174fd77c4");
+
+        let linked =
+            parse_game("[Site \"https://lichess.org/abcd1234\"]\n[Result \"1-0\"]\n1. e4 e5 1-0");
+        assert_eq!(linked.id().to_string(), "lichess.org/abcd1234");
+    }
+}
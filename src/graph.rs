@@ -0,0 +1,249 @@
+use shakmaty::san::SanPlus;
+
+use crate::eval::Eval;
+use crate::game::{Color, GiveEvals, ListMoves};
+use crate::moves::Move;
+use crate::position;
+
+/// The evaluation of a position within a game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvalPoint {
+    ply: usize,
+    san: Option<String>,
+    eval: Option<Eval>,
+}
+
+impl EvalPoint {
+    /// Returns the number of moves played to reach this position.
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+
+    /// Returns the move that reached this position in SAN, or `None` for the starting position.
+    pub fn san(&self) -> Option<&str> {
+        self.san.as_deref()
+    }
+
+    /// Returns the evaluation of this position from white's point of view, if known.
+    pub fn eval(&self) -> Option<Eval> {
+        self.eval
+    }
+}
+
+/// The evaluation of each position of a game, as plotted in an advantage graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvalGraph {
+    points: Vec<EvalPoint>,
+}
+
+impl EvalGraph {
+    /// Creates the graph of `game`, where `evals` holds the evaluation of the starting position
+    /// followed by the evaluation after each move. Moves from the first illegal move onwards are
+    /// left out, as are evaluations beyond the end of the game.
+    pub fn new<M, G>(game: &G, evals: &[Option<Eval>]) -> EvalGraph
+    where
+        M: 'static + Clone + Move,
+        G: ListMoves<M>,
+    {
+        let positions = position::replay(game.list_moves());
+        let legal_moves = position::legal_moves(game.list_moves());
+
+        let points = (0..positions.len())
+            .map(|ply| EvalPoint {
+                ply,
+                san: match ply {
+                    0 => None,
+                    _ => Some(
+                        SanPlus::from_move(positions[ply - 1].clone(), legal_moves[ply - 1])
+                            .to_string(),
+                    ),
+                },
+                eval: evals.get(ply).copied().flatten(),
+            })
+            .collect();
+
+        EvalGraph { points }
+    }
+
+    /// Creates the graph of `game` from the evaluations recorded in it, such as lichess `%eval`
+    /// comments. The starting position never has an evaluation.
+    pub fn from_game_evals<M, G>(game: &G) -> EvalGraph
+    where
+        M: 'static + Clone + Move,
+        G: GiveEvals + ListMoves<M>,
+    {
+        let evals = std::iter::once(None)
+            .chain(game.evals().iter().copied())
+            .collect::<Vec<_>>();
+
+        EvalGraph::new(game, &evals)
+    }
+
+    /// Returns the evaluation of each position, starting with the starting position.
+    pub fn points(&self) -> &[EvalPoint] {
+        &self.points
+    }
+
+    /// Returns the graph as a JSON array with an object for each position, holding its `ply`, the
+    /// `move` that reached it, and its evaluation as `cp` or `mate` from white's point of view.
+    /// Unknown values are `null`.
+    pub fn to_json(&self) -> String {
+        let points = self
+            .points
+            .iter()
+            .map(|point| {
+                let (centipawns, mate) = eval_fields(point.eval);
+
+                format!(
+                    "{{\"ply\":{},\"move\":{},\"cp\":{},\"mate\":{}}}",
+                    point.ply,
+                    point
+                        .san
+                        .as_ref()
+                        .map_or_else(|| String::from("null"), |san| format!("\"{}\"", san)),
+                    centipawns.unwrap_or_else(|| String::from("null")),
+                    mate.unwrap_or_else(|| String::from("null"))
+                )
+            })
+            .collect::<Vec<_>>();
+
+        format!("[{}]", points.join(","))
+    }
+
+    /// Returns the graph as CSV with a header and a row for each position, holding the same
+    /// columns as `to_json`. Unknown values are left empty.
+    pub fn to_csv(&self) -> String {
+        let mut output = String::from("ply,move,cp,mate\n");
+
+        for point in &self.points {
+            let (centipawns, mate) = eval_fields(point.eval);
+
+            output += &format!(
+                "{},{},{},{}\n",
+                point.ply,
+                point.san.as_deref().unwrap_or(""),
+                centipawns.unwrap_or_default(),
+                mate.unwrap_or_default()
+            );
+        }
+
+        output
+    }
+
+    /// Returns the graph as an SVG sparkline `width` by `height` pixels, plotting white's winning
+    /// chances from the bottom edge (black winning) to the top edge (white winning). Positions
+    /// without an evaluation are skipped.
+    pub fn to_svg(&self, width: u32, height: u32) -> String {
+        let last_ply = self.points.last().map_or(0, |point| point.ply).max(1);
+
+        let coordinates = self
+            .points
+            .iter()
+            .filter_map(|point| {
+                let chances = point.eval?.winning_chances(Color::White);
+                let x = point.ply as f64 / last_ply as f64 * width as f64;
+                let y = (1. - chances) / 2. * height as f64;
+
+                Some(format!("{:.1},{:.1}", x, y))
+            })
+            .collect::<Vec<_>>();
+
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+             viewBox=\"0 0 {width} {height}\">\
+             <line x1=\"0\" y1=\"{middle}\" x2=\"{width}\" y2=\"{middle}\" stroke=\"#999\"/>\
+             <polyline fill=\"none\" stroke=\"#000\" points=\"{points}\"/></svg>",
+            width = width,
+            height = height,
+            middle = height as f64 / 2.,
+            points = coordinates.join(" ")
+        )
+    }
+}
+
+/// Returns the centipawns and mate fields of `eval` for exporting, where at most one is set.
+fn eval_fields(eval: Option<Eval>) -> (Option<String>, Option<String>) {
+    match eval {
+        Some(Eval::Centipawns(centipawns)) => (Some(centipawns.to_string()), None),
+        Some(Eval::Mate(moves)) => (None, Some(moves.to_string())),
+        None => (None, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::EvalGraph;
+    use crate::eval::Eval;
+    use crate::game::test_utils::*;
+    use crate::game::Game;
+    use crate::AlgebraicMove;
+
+    #[fixture]
+    fn evaluated_game() -> Game<AlgebraicMove> {
+        parse_game(
+            "[White \"a\"]\n[Black \"b\"]\n[Result \"0-1\"]\n\
+             1. f3 { [%eval -0.6] } 1... e5 { [%eval -0.8] } 2. g4 { [%eval #-1] } 2... Qh4# 0-1",
+        )
+    }
+
+    #[rstest]
+    fn from_game_evals_should_shift_evals_by_one_ply(evaluated_game: Game<AlgebraicMove>) {
+        let graph = EvalGraph::from_game_evals(&evaluated_game);
+
+        let points = graph
+            .points()
+            .iter()
+            .map(|point| (point.ply(), point.san(), point.eval()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            points,
+            vec![
+                (0, None, None),
+                (1, Some("f3"), Some(Eval::Centipawns(-60))),
+                (2, Some("e5"), Some(Eval::Centipawns(-80))),
+                (3, Some("g4"), Some(Eval::Mate(-1))),
+                (4, Some("Qh4#"), None),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn eval_graph_should_export_json_and_csv(evaluated_game: Game<AlgebraicMove>) {
+        let graph = EvalGraph::from_game_evals(&evaluated_game);
+
+        assert_eq!(
+            graph.to_json(),
+            "[{\"ply\":0,\"move\":null,\"cp\":null,\"mate\":null},\
+             {\"ply\":1,\"move\":\"f3\",\"cp\":-60,\"mate\":null},\
+             {\"ply\":2,\"move\":\"e5\",\"cp\":-80,\"mate\":null},\
+             {\"ply\":3,\"move\":\"g4\",\"cp\":null,\"mate\":-1},\
+             {\"ply\":4,\"move\":\"Qh4#\",\"cp\":null,\"mate\":null}]"
+        );
+        assert_eq!(
+            graph.to_csv(),
+            "ply,move,cp,mate\n0,,,\n1,f3,-60,\n2,e5,-80,\n3,g4,,-1\n4,Qh4#,,\n"
+        );
+    }
+
+    #[rstest]
+    fn eval_graph_should_draw_sparkline() {
+        let game = parse_game("[White \"a\"]\n[Black \"b\"]\n[Result \"1-0\"]\n1. e4 e5 1-0");
+        let graph = EvalGraph::new(
+            &game,
+            &[
+                Some(Eval::Centipawns(0)),
+                Some(Eval::Mate(3)),
+                Some(Eval::Centipawns(-1000)),
+            ],
+        );
+
+        let svg = graph.to_svg(100, 50);
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("points=\"0.0,25.0 50.0,"));
+        assert!(svg.ends_with("</svg>"));
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use shakmaty::Setup;
+
+use crate::game::ListMoves;
+use crate::moves::Move;
+use crate::position;
+use crate::zobrist;
+
+/// A place a position was reached within a set of games.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Occurrence {
+    game: usize,
+    ply: usize,
+    reversed: bool,
+}
+
+impl Occurrence {
+    /// Returns the index of the game the position was reached in, within the games the index was
+    /// built from.
+    pub fn game(&self) -> usize {
+        self.game
+    }
+
+    /// Returns the number of moves played before the position was reached.
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+
+    /// Returns whether the game reached the position with the colors reversed, which is only
+    /// looked for when asked for with `PositionIndex::find_with`.
+    pub fn reversed(&self) -> bool {
+        self.reversed
+    }
+}
+
+/// An index of the positions reached in a set of games, so that every game reaching a position
+/// can be found however it got there.
+///
+/// Positions are keyed by their Zobrist hash, so move counters don't matter, and positions after
+/// an illegal move aren't indexed.
+#[derive(Debug, Clone, Default)]
+pub struct PositionIndex {
+    occurrences: HashMap<u64, Vec<Occurrence>>,
+}
+
+impl PositionIndex {
+    /// Builds an index of the positions reached in `games`.
+    pub fn new<M, G>(games: &[G]) -> PositionIndex
+    where
+        M: Clone + Move,
+        G: ListMoves<M>,
+    {
+        let mut occurrences: HashMap<u64, Vec<Occurrence>> = HashMap::new();
+
+        for (game, moves) in games.iter().enumerate() {
+            for (ply, position) in position::replay(moves.list_moves()).iter().enumerate() {
+                let game_occurrences = occurrences
+                    .entry(zobrist::hash_position(position))
+                    .or_default();
+
+                // Positions repeated within a game are only counted the first time.
+                if game_occurrences.last().map(|occurrence| occurrence.game) != Some(game) {
+                    game_occurrences.push(Occurrence {
+                        game,
+                        ply,
+                        reversed: false,
+                    });
+                }
+            }
+        }
+
+        PositionIndex { occurrences }
+    }
+
+    /// Returns where `setup` was first reached in each game that reached it, in the order the
+    /// games were given.
+    pub fn find(&self, setup: &Setup) -> &[Occurrence] {
+        self.occurrences
+            .get(&zobrist::hash(setup))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns where `setup` was first reached in each game that reached it, like `find`. With
+    /// `color_reversed`, games reaching the position with the colors reversed are found as well,
+    /// so that a structure can be studied whichever color the player had. A game reaching the
+    /// position both ways is found where it first reached either.
+    pub fn find_with(&self, setup: &Setup, color_reversed: bool) -> Vec<Occurrence> {
+        let mut found = self.find(setup).to_vec();
+        let reversed_hash = zobrist::reversed_hash(setup);
+
+        // Positions that are their own reverse are only found as they are.
+        if color_reversed && reversed_hash != zobrist::hash(setup) {
+            let reversed = self
+                .occurrences
+                .get(&reversed_hash)
+                .map_or(&[][..], Vec::as_slice);
+
+            found.extend(reversed.iter().map(|occurrence| Occurrence {
+                reversed: true,
+                ..*occurrence
+            }));
+            found.sort_by_key(|occurrence| (occurrence.game, occurrence.ply));
+            found.dedup_by_key(|occurrence| occurrence.game);
+        }
+
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use shakmaty::fen::Fen;
+
+    use super::PositionIndex;
+    use crate::game::test_utils::*;
+
+    #[rstest]
+    fn find_should_include_transpositions() {
+        let games = vec![
+            parse_game("[White \"a\"]\n[Black \"b\"]\n[Result \"1-0\"]\n1. e4 e5 2. Nf3 Nc6 1-0"),
+            parse_game("[White \"a\"]\n[Black \"b\"]\n[Result \"1-0\"]\n1. Nf3 Nc6 2. e4 e5 1-0"),
+            parse_game("[White \"a\"]\n[Black \"b\"]\n[Result \"1-0\"]\n1. d4 d5 1-0"),
+        ];
+
+        let index = PositionIndex::new(&games);
+        let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3"
+            .parse::<Fen>()
+            .unwrap();
+
+        let found = index
+            .find(fen.as_setup())
+            .iter()
+            .map(|occurrence| (occurrence.game(), occurrence.ply()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(found, vec![(0, 4), (1, 4)]);
+        assert_eq!(index.find(&shakmaty::Setup::initial()).len(), 3);
+    }
+
+    #[rstest]
+    fn find_with_should_include_color_reversed_positions() {
+        let games = vec![
+            parse_game("[White \"a\"]\n[Black \"b\"]\n[Result \"1-0\"]\n1. e4 e5 2. Nf3 Nc6 1-0"),
+            parse_game(
+                "[White \"b\"]\n[Black \"a\"]\n[Result \"0-1\"]\n1. e3 e5 2. e4 Nf6 3. Nc3 0-1",
+            ),
+            parse_game("[White \"a\"]\n[Black \"b\"]\n[Result \"1-0\"]\n1. d4 d5 1-0"),
+        ];
+
+        let index = PositionIndex::new(&games);
+        let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3"
+            .parse::<Fen>()
+            .unwrap();
+        let found = |color_reversed| {
+            index
+                .find_with(fen.as_setup(), color_reversed)
+                .iter()
+                .map(|occurrence| (occurrence.game(), occurrence.ply(), occurrence.reversed()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(found(false), vec![(0, 4, false)]);
+        assert_eq!(found(true), vec![(0, 4, false), (1, 5, true)]);
+    }
+}
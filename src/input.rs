@@ -0,0 +1,386 @@
+use std::io::{self, BufRead, BufReader, Cursor, Read};
+use std::time::Duration;
+
+use chrono::DateTime;
+use flate2::read::MultiGzDecoder;
+use ruzstd::StreamingDecoder;
+use serde_json::Value as Json;
+use tracing::debug;
+
+use crate::clock::clock_comment;
+
+/// The bytes gzip streams start with.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// The bytes zstd frames start with.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// How games given as input are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputFormat {
+    /// Games in PGN.
+    Pgn,
+    /// One game per line in the JSON of the lichess game export API.
+    Ndjson,
+}
+
+/// How input is compressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// Not compressed.
+    None,
+    /// Compressed with gzip, as `.pgn.gz` files are.
+    Gzip,
+    /// Compressed with zstd, as the lichess database dumps are.
+    Zstd,
+}
+
+/// Returns how input starting with `start` is compressed, judged by its magic bytes.
+pub fn detect_compression(start: &[u8]) -> Compression {
+    if start.starts_with(&GZIP_MAGIC) {
+        Compression::Gzip
+    } else if start.starts_with(&ZSTD_MAGIC) {
+        Compression::Zstd
+    } else {
+        Compression::None
+    }
+}
+
+/// Returns how uncompressed input starting with `start` is written. Input whose first character
+/// other than whitespace is `{` is taken to be NDJSON, and anything else PGN.
+pub fn detect_format(start: &[u8]) -> InputFormat {
+    match start.iter().find(|byte| !byte.is_ascii_whitespace()) {
+        Some(b'{') => InputFormat::Ndjson,
+        _ => InputFormat::Pgn,
+    }
+}
+
+/// Returns a reader of the games in `input` as PGN, whatever format `input` is in. Compression
+/// and format are detected from the first bytes of `input`, so gzip or zstd compressed PGN and
+/// lichess NDJSON can all be read like PGN.
+pub fn decode<'a, R: Read + 'a>(input: R) -> io::Result<Box<dyn Read + 'a>> {
+    let mut input = BufReader::new(input);
+    let compression = detect_compression(input.fill_buf()?);
+
+    let mut decompressed: BufReader<Box<dyn Read + 'a>> = match compression {
+        Compression::None => BufReader::new(Box::new(input)),
+        Compression::Gzip => BufReader::new(Box::new(MultiGzDecoder::new(input))),
+        Compression::Zstd => {
+            BufReader::new(Box::new(StreamingDecoder::new(input).map_err(|error| {
+                io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+            })?))
+        }
+    };
+
+    let format = detect_format(decompressed.fill_buf()?);
+
+    debug!(?compression, ?format, "Detected input");
+
+    Ok(match format {
+        InputFormat::Pgn => Box::new(decompressed),
+        InputFormat::Ndjson => Box::new(NdjsonToPgn {
+            lines: decompressed,
+            pgn: Vec::new(),
+            position: 0,
+        }),
+    })
+}
+
+/// Returns a reader of the games in `bytes` as PGN, like `decode`. Uncompressed PGN is read
+/// straight from `bytes` rather than through another buffer, so that games already in memory, as
+/// in a memory-mapped file, are only copied by the PGN reader itself.
+pub fn decode_bytes<'a, T: AsRef<[u8]> + 'a>(bytes: T) -> io::Result<Box<dyn Read + 'a>> {
+    let start = bytes.as_ref();
+
+    if detect_compression(start) == Compression::None && detect_format(start) == InputFormat::Pgn {
+        debug!("Reading uncompressed PGN in place");
+
+        return Ok(Box::new(Cursor::new(bytes)));
+    }
+
+    decode(Cursor::new(bytes))
+}
+
+/// A reader turning the lichess NDJSON read from `lines` into PGN, a game at a time.
+struct NdjsonToPgn<R: BufRead> {
+    lines: R,
+    pgn: Vec<u8>,
+    position: usize,
+}
+
+impl<R: BufRead> Read for NdjsonToPgn<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.pgn.len() {
+            let mut line = String::new();
+
+            if self.lines.read_line(&mut line)? == 0 {
+                return Ok(0);
+            }
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let game = serde_json::from_str::<Json>(&line)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+
+            match lichess_json_to_pgn(&game) {
+                Some(pgn) => {
+                    self.pgn = pgn.into_bytes();
+                    self.position = 0;
+                }
+                None => debug!(id = ?game["id"], "Skipping game without a result"),
+            }
+        }
+
+        let length = buffer.len().min(self.pgn.len() - self.position);
+        buffer[..length].copy_from_slice(&self.pgn[self.position..self.position + length]);
+        self.position += length;
+
+        Ok(length)
+    }
+}
+
+/// Returns the name of a player in a lichess game export, which is their username, the level of
+/// the lichess AI, or `Anonymous`.
+fn lichess_player_name(player: &Json) -> String {
+    if let Some(name) = player["user"]["name"].as_str() {
+        String::from(name)
+    } else if let Some(level) = player["aiLevel"].as_u64() {
+        format!("Stockfish level {}", level)
+    } else {
+        String::from("Anonymous")
+    }
+}
+
+/// Returns the PGN Termination tag lichess gives games that ended with `status`.
+fn lichess_termination(status: &str) -> &'static str {
+    match status {
+        "outoftime" => "Time forfeit",
+        "timeout" => "Abandoned",
+        "cheat" => "Rules infraction",
+        _ => "Normal",
+    }
+}
+
+/// Returns the game `game`, in the JSON of the lichess game export API, as PGN, or `None` if the
+/// game has no result because it was aborted or is still being played.
+pub fn lichess_json_to_pgn(game: &Json) -> Option<String> {
+    let result = match (game["winner"].as_str(), game["status"].as_str()) {
+        (Some("white"), _) => "1-0",
+        (Some("black"), _) => "0-1",
+        (_, Some("created")) | (_, Some("started")) | (_, Some("aborted")) => return None,
+        _ => "1/2-1/2",
+    };
+
+    let mut headers = Vec::new();
+
+    if let (Some(rated), Some(speed)) = (game["rated"].as_bool(), game["speed"].as_str()) {
+        let mut speed = speed.chars();
+        let speed = speed
+            .next()
+            .map(|first| first.to_uppercase().chain(speed).collect::<String>())
+            .unwrap_or_default();
+
+        headers.push((
+            "Event",
+            format!("{} {} game", if rated { "Rated" } else { "Casual" }, speed),
+        ));
+    }
+
+    if let Some(id) = game["id"].as_str() {
+        headers.push(("Site", format!("https://lichess.org/{}", id)));
+    }
+
+    if let Some(started) = game["createdAt"]
+        .as_i64()
+        .and_then(DateTime::from_timestamp_millis)
+    {
+        headers.push(("Date", started.format("%Y.%m.%d").to_string()));
+        headers.push(("UTCDate", started.format("%Y.%m.%d").to_string()));
+        headers.push(("UTCTime", started.format("%H:%M:%S").to_string()));
+    }
+
+    let players = &game["players"];
+    headers.push(("White", lichess_player_name(&players["white"])));
+    headers.push(("Black", lichess_player_name(&players["black"])));
+    headers.push(("Result", String::from(result)));
+
+    for (color, header) in [("white", "WhiteElo"), ("black", "BlackElo")].iter() {
+        if let Some(rating) = players[*color]["rating"].as_u64() {
+            headers.push((*header, rating.to_string()));
+        }
+    }
+
+    let clock = &game["clock"];
+
+    if let (Some(initial), Some(increment)) =
+        (clock["initial"].as_u64(), clock["increment"].as_u64())
+    {
+        headers.push(("TimeControl", format!("{}+{}", initial, increment)));
+    }
+
+    if let Some(status) = game["status"].as_str() {
+        headers.push(("Termination", String::from(lichess_termination(status))));
+    }
+
+    let mut pgn = headers
+        .iter()
+        .map(|(name, value)| format!("[{} \"{}\"]\n", name, value.replace('"', "\\\"")))
+        .collect::<String>();
+
+    pgn += "\n";
+
+    // Clocks are in centiseconds, and evaluations in centipawns from white's point of view.
+    let clocks = game["clocks"].as_array();
+    let analysis = game["analysis"].as_array();
+
+    for (ply, move_) in game["moves"]
+        .as_str()
+        .unwrap_or("")
+        .split_whitespace()
+        .enumerate()
+    {
+        if ply % 2 == 0 {
+            pgn += &format!("{}. ", ply / 2 + 1);
+        }
+
+        pgn += move_;
+        pgn += " ";
+
+        let mut commands = Vec::new();
+
+        if let Some(eval) = analysis.and_then(|analysis| analysis.get(ply)) {
+            if let Some(centipawns) = eval["eval"].as_i64() {
+                commands.push(format!("[%eval {:.2}]", centipawns as f64 / 100.));
+            } else if let Some(mate) = eval["mate"].as_i64() {
+                commands.push(format!("[%eval #{}]", mate));
+            }
+        }
+
+        if let Some(centiseconds) = clocks
+            .and_then(|clocks| clocks.get(ply))
+            .and_then(Json::as_u64)
+        {
+            commands.push(clock_comment(Duration::from_millis(centiseconds * 10)));
+        }
+
+        if !commands.is_empty() {
+            pgn += &format!("{{ {} }} ", commands.join(" "));
+
+            if ply % 2 == 0 {
+                pgn += &format!("{}... ", ply / 2 + 1);
+            }
+        }
+    }
+
+    Some(pgn + result + "\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+    use std::io::{Read, Write};
+    use std::time::Duration;
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression as GzipLevel;
+    use pgn_reader::Reader;
+    use rstest::*;
+
+    use super::{
+        decode, decode_bytes, detect_compression, detect_format, Compression, InputFormat,
+    };
+    use crate::eval::Eval;
+    use crate::game::{GiveClocks, GiveDate, GiveEvals, GiveLink, GivePlayers, GiveRatings};
+    use crate::{AlgebraicMove, Game, GameParser};
+
+    const NDJSON: &str = "{\"id\":\"abcdefgh\",\"status\":\"mate\",\"createdAt\":1623758400000,\
+        \"players\":{\"white\":{\"user\":{\"name\":\"alice\"},\"rating\":1500},\
+        \"black\":{\"aiLevel\":3}},\"winner\":\"white\",\
+        \"moves\":\"e4 e5 Qh5 Nc6 Bc4 Nf6 Qxf7#\",\"clocks\":[18000,17950],\
+        \"analysis\":[{\"eval\":30},{\"mate\":-2}],\
+        \"clock\":{\"initial\":180,\"increment\":2}}\n\
+        {\"id\":\"aborted1\",\"status\":\"aborted\",\"players\":{},\"moves\":\"\"}\n";
+
+    fn read_games(input: &[u8]) -> Vec<Game<AlgebraicMove>> {
+        let mut reader = Reader::new(decode(input).unwrap());
+        let mut games = Vec::new();
+
+        while let Some(pgn_game) = reader.read_game(&mut GameParser::new()).unwrap() {
+            games.push(Game::try_from(pgn_game).unwrap());
+        }
+
+        games
+    }
+
+    #[rstest(
+        start,
+        compression,
+        format,
+        case(b"[Event \"?\"]", Compression::None, InputFormat::Pgn),
+        case(b"\n {\"id\":1}", Compression::None, InputFormat::Ndjson),
+        case(b"1. e4 e5", Compression::None, InputFormat::Pgn),
+        case(&[0x1f, 0x8b, 0x08], Compression::Gzip, InputFormat::Pgn),
+        case(&[0x28, 0xb5, 0x2f, 0xfd], Compression::Zstd, InputFormat::Pgn)
+    )]
+    fn detect_should_recognize_inputs(start: &[u8], compression: Compression, format: InputFormat) {
+        assert_eq!(detect_compression(start), compression);
+
+        if compression == Compression::None {
+            assert_eq!(detect_format(start), format);
+        }
+    }
+
+    #[rstest]
+    fn decode_should_read_lichess_ndjson() {
+        let games = read_games(NDJSON.as_bytes());
+
+        assert_eq!(games.len(), 1);
+
+        let game = &games[0];
+        assert_eq!(game.white_player(), Some("alice"));
+        assert_eq!(game.black_player(), Some("Stockfish level 3"));
+        assert_eq!(game.white_rating(), Some(1500));
+        assert_eq!(game.date().unwrap().to_string(), "2021-06-15");
+        assert_eq!(game.link(), Some("https://lichess.org/abcdefgh"));
+        assert_eq!(game.clocks()[1], Some(Duration::from_millis(179_500)));
+        assert_eq!(
+            game.evals()[..2],
+            [Some(Eval::Centipawns(30)), Some(Eval::Mate(-2))]
+        );
+        assert_eq!(game.time_control().unwrap().to_string(), "180+2");
+    }
+
+    #[rstest]
+    fn decode_should_decompress_gzip() {
+        let mut encoder = GzEncoder::new(Vec::new(), GzipLevel::default());
+        encoder
+            .write_all(b"[White \"a\"]\n[Black \"b\"]\n[Result \"1-0\"]\n1. e4 e5 1-0\n")
+            .unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        assert_eq!(read_games(&compressed).len(), 1);
+
+        let mut text = String::new();
+        decode_bytes(compressed)
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        assert!(text.starts_with("[White \"a\"]"));
+
+        let mut text = String::new();
+        decode(&b"plain"[..])
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(text, "plain");
+
+        let mut text = String::new();
+        decode_bytes(&b"[Black \"b\"]"[..])
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(text, "[Black \"b\"]");
+    }
+}
@@ -0,0 +1,251 @@
+use std::cmp::Ordering;
+use std::hash::Hash;
+
+use shakmaty::{Chess, Position};
+
+use crate::game::{GivePlayers, GiveResult, ListMoves};
+use crate::move_tree::MoveTreeView;
+use crate::moves::Move;
+use crate::position;
+use crate::stats::Score;
+
+/// How `key_positions` chooses positions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ranking {
+    /// The positions reached in the most games.
+    MostPlayed,
+    /// The positions scored worst from, with the most played first among equal scores.
+    WorstScoring,
+}
+
+/// A position reached in a set of games, with how the games went from it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyPosition {
+    line: Vec<String>,
+    epd: String,
+    score: Score,
+}
+
+impl KeyPosition {
+    /// Returns the moves leading to the position in SAN.
+    pub fn line(&self) -> &[String] {
+        &self.line
+    }
+
+    /// Returns the position as the four fields of an EPD record, without any operations.
+    pub fn epd(&self) -> &str {
+        &self.epd
+    }
+
+    /// Returns the number of games that reached the position.
+    pub fn games(&self) -> usize {
+        self.score.games()
+    }
+
+    /// Returns the score from the position, for the side scored by `key_positions`.
+    pub fn score(&self) -> Score {
+        self.score
+    }
+
+    /// Returns the position as an EPD record, with the line leading to it as its `id` and the
+    /// `games` and `score` opcodes giving how many games reached it and the fraction of points
+    /// scored from it.
+    pub fn to_epd(&self) -> String {
+        format!(
+            "{} id \"{}\"; games {}; score {:.3};",
+            self.epd,
+            format_line(&self.line),
+            self.games(),
+            self.score.rate().unwrap_or(0.)
+        )
+    }
+}
+
+/// Returns `line` with move numbers, as in `1. e4 e5 2. Nf3`.
+fn format_line(line: &[String]) -> String {
+    line.iter()
+        .enumerate()
+        .map(|(ply, move_)| match ply % 2 {
+            0 => format!("{}. {}", ply / 2 + 1, move_),
+            _ => move_.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Returns up to `limit` positions reached after `view`, which is reached by `line`, down to
+/// `depth` more plies, chosen by `ranking`. Positions reached in fewer than `min_games` games are
+/// left out, and positions reached by different move orders are given once for each order.
+///
+/// Scores are for `player` when given, counting only the games they played in, and otherwise for
+/// the side to move in the position.
+pub fn key_positions<M, G>(
+    view: &MoveTreeView<'_, M, G>,
+    line: &[M],
+    depth: usize,
+    min_games: usize,
+    player: Option<&str>,
+    ranking: Ranking,
+    limit: usize,
+) -> Vec<KeyPosition>
+where
+    M: Clone + Move + Eq + Hash,
+    G: GivePlayers + GiveResult + ListMoves<M>,
+{
+    let mut positions = Vec::new();
+    let position = position::replay(line.iter().cloned())
+        .pop()
+        .unwrap_or_default();
+
+    collect(
+        view,
+        &position,
+        &mut line
+            .iter()
+            .map(|move_| move_.clone().to_algebraic())
+            .collect(),
+        depth,
+        min_games,
+        player,
+        &mut positions,
+    );
+
+    positions.sort_by(|a, b| {
+        let by_games = b.games().cmp(&a.games());
+
+        match ranking {
+            Ranking::MostPlayed => by_games,
+            Ranking::WorstScoring => a
+                .score
+                .rate()
+                .partial_cmp(&b.score.rate())
+                .unwrap_or(Ordering::Equal)
+                .then(by_games),
+        }
+        .then_with(|| a.line.cmp(&b.line))
+    });
+    positions.truncate(limit);
+
+    positions
+}
+
+/// Adds the positions after `view`, whose position is `position` and which is reached by `line`,
+/// to `positions`, as described in `key_positions`.
+fn collect<M, G>(
+    view: &MoveTreeView<'_, M, G>,
+    position: &Chess,
+    line: &mut Vec<String>,
+    depth: usize,
+    min_games: usize,
+    player: Option<&str>,
+    positions: &mut Vec<KeyPosition>,
+) where
+    M: Clone + Move + Eq + Hash,
+    G: GivePlayers + GiveResult + ListMoves<M>,
+{
+    if depth == 0 {
+        return;
+    }
+
+    for (move_, child) in view.children() {
+        // Games can hold illegal moves, which have no position after them to give.
+        let next_position = match position::play_from(position, move_.clone()) {
+            Some(next_position) => next_position,
+            None => continue,
+        };
+
+        let mut score = Score::new();
+
+        for game in child.iter() {
+            let color = match player {
+                Some(player) => match game.color_of(player) {
+                    Some(color) => color,
+                    None => continue,
+                },
+                None => next_position.turn(),
+            };
+
+            score.add(game.result().for_color(color));
+        }
+
+        if score.games() < min_games {
+            continue;
+        }
+
+        line.push(move_.clone().to_algebraic());
+
+        positions.push(KeyPosition {
+            line: line.clone(),
+            epd: position::epd(&next_position),
+            score,
+        });
+
+        collect(
+            &child,
+            &next_position,
+            line,
+            depth - 1,
+            min_games,
+            player,
+            positions,
+        );
+
+        line.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::{key_positions, Ranking};
+    use crate::game::test_utils::*;
+    use crate::move_tree::MoveTree;
+
+    #[rstest]
+    fn key_positions_should_rank_positions_by_games_and_score() {
+        let move_tree = MoveTree::new(vec![
+            game("alice", "bob", "0-1", "1. e4 e5 2. Nf3"),
+            game("alice", "carol", "0-1", "1. e4 e5 2. Nf3"),
+            game("dave", "alice", "0-1", "1. e4 c5"),
+            game("alice", "bob", "1-0", "1. d4 d5"),
+        ]);
+
+        let most_played =
+            key_positions(&move_tree.view(), &[], 4, 2, None, Ranking::MostPlayed, 10);
+
+        assert_eq!(
+            most_played
+                .iter()
+                .map(|position| position.to_epd())
+                .collect::<Vec<_>>(),
+            vec![
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - id \"1. e4\"; games 3; \
+                 score 1.000;",
+                "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - id \"1. e4 e5\"; \
+                 games 2; score 0.000;",
+                "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - id \"1. e4 e5 2. Nf3\"; \
+                 games 2; score 1.000;",
+            ]
+        );
+
+        let worst = key_positions(
+            &move_tree.view(),
+            &[],
+            4,
+            1,
+            Some("alice"),
+            Ranking::WorstScoring,
+            2,
+        );
+
+        assert_eq!(
+            worst
+                .iter()
+                .map(|position| position.line().join(" "))
+                .collect::<Vec<_>>(),
+            vec!["e4 e5", "e4 e5 Nf3"]
+        );
+        assert_eq!(worst[0].score().losses(), 2);
+    }
+}
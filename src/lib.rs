@@ -12,21 +12,123 @@
 //! ```
 //!
 //! For comprehensive documentation of the CLI tool, see `chesshound --help`.
+//!
+//! ## Features
+//! - `cli` (default): the command-line tool.
+//! - `columnar` (default): writing games to Parquet and Arrow files.
+//! - `db` (default): keeping games in a SQLite database.
+//! - `ffi`: a C API for embedding chesshound in programs written in other languages.
+//! - `wasm`: bindings for using chesshound from JavaScript through `wasm-bindgen`.
 
+/// Analysis of games with a chess engine.
+pub mod analysis;
+/// Analysis of many games at once across several chess engines.
+pub mod batch;
+/// Tokens for asking long-running work, such as reading or analysing many games, to stop early
+/// and keep what it finished.
+pub mod cancel;
+/// Line charts of statistics, such as scores and ratings over time, drawn as SVG.
+pub mod chart;
+/// Types for chess clocks and time controls.
+pub mod clock;
+/// Collections of games with chainable operations for filtering, splitting, and ordering them.
+pub mod collection;
+/// Tables of games and their moves in columnar formats, Parquet and Arrow, for data analysis
+/// tools.
+#[cfg(feature = "columnar")]
+pub mod columnar;
+/// Defaults for the command-line tool kept in a config file.
+pub mod config;
+/// Cross-tables and standings of tournaments, reconstructed from their games.
+pub mod crosstable;
+/// A database of games kept in SQLite, for searching large sets of games without parsing them
+/// again.
+#[cfg(feature = "db")]
+pub mod db;
+/// Finding games that appear more than once in a set of games.
+pub mod dedupe;
+/// Communication with chess engines over the UCI protocol.
+pub mod engine;
+/// The errors chesshound's functions return.
+pub mod error;
+/// Engine evaluations and how they judge the moves of a game.
+pub mod eval;
+/// Writing games in formats other tools can read.
+pub mod export;
+/// A C API for parsing games and querying their win rates from other languages.
+#[cfg(feature = "ffi")]
+pub mod ffi;
+/// Conditions for choosing which games to include in a set of games.
+pub mod filter;
+/// Flashcards for training on positions from players' games, such as their blunders and the
+/// gaps in their opening preparation, written for Anki or as CSV.
+pub mod flashcards;
 /// Types and traits for different representations of chess games.
 pub mod game;
+/// A cache of what replaying games tells about them, such as their openings and phases, kept
+/// between runs.
+pub mod game_cache;
+/// Identifiers that recognize the same game wherever it's read from.
+pub mod game_id;
+/// Evaluation graphs of games, as data or pictures.
+pub mod graph;
+/// An index of the positions reached in games, for searching games by position.
+pub mod index;
+/// Reading games from input in whichever format it comes in, such as compressed PGN or NDJSON.
+pub mod input;
+/// Positions from a set of games worth studying, such as the most played or the worst scoring,
+/// with how the games went from them.
+pub mod key_positions;
+/// Statistics on master games, kept from the lichess masters explorer, for comparing players'
+/// choices with theory.
+pub mod masters;
 /// A structure for organizing games based on their moves.
 pub mod move_tree;
 /// Type and traits for different representations of chess moves.
 pub mod moves;
+/// Classification of games into named openings by ECO code.
+pub mod openings;
 /// Utilities for parsing games from PGN.
 pub mod parsing;
+/// Detection of the opening, middlegame, and endgame within games.
+pub mod phase;
+/// Players, their titles, and matching their names.
+pub mod player;
+/// Utilities for replaying games into board positions and drawing them.
+pub mod position;
+/// Finding the gaps in a player's opening preparation.
+pub mod prep;
+/// Puzzles made from tactics players missed in their games.
+pub mod puzzles;
+/// Ratings estimated for the players of a set of games from their results against one another.
+pub mod ratings;
+/// Reports on how a set of games went after a line of moves, as the CLI's `stats` gives them.
+pub mod report;
+/// Random samples of games, for estimating statistics on large sets of games quickly.
+pub mod sample;
+/// Where games came from, such as lichess, chess.com, or local PGN files.
+pub mod source;
 /// Functions for getting statistics from sets of games.
 pub mod stats;
+/// A store holding games once, with indexes shared by the views of them that trees, filters, and
+/// statistics work on.
+pub mod store;
+/// Middlegame pawn structures, such as the isolated queen's pawn, recognized in games.
+pub mod structure;
+/// Control over how many threads work done in parallel uses.
+pub mod threads;
+/// Bindings for parsing games, building move trees, and querying statistics from JavaScript.
+#[cfg(feature = "wasm")]
+pub mod wasm;
+/// Zobrist hashing of chess positions.
+pub mod zobrist;
 
-pub use game::Game;
+pub use error::{Error, Result};
+pub use game::{Game, GameMetadata};
+pub use game_id::GameId;
 pub use move_tree::MoveTree;
 pub use move_tree::MoveTreeView;
 pub use moves::AlgebraicMove;
 pub use moves::Move;
 pub use parsing::GameParser;
+pub use player::Player;
@@ -1,122 +1,5846 @@
-use io::Read;
-use std::io;
-
-use clap::{App, Arg, SubCommand};
-use pgn_reader::BufferedReader;
-
-use chesshound::{stats, AlgebraicMove, Game, GameParser, Move, MoveTree};
-
-fn main() -> io::Result<()> {
-    let matches = App::new("Chesshound")
-        .version("0.1.0")
-        .author("Gage C. <github.com/grchristensen>")
-        .about("Chesshound is a CLI tool for finding patterns in sets of chess games")
-        .subcommand(
-            SubCommand::with_name("stats")
-                .about("Takes PGN from standard input and gives statistics on games found")
-                .arg(
-                    Arg::with_name("branches")
-                        .help("Show all moves that occur after this one in the game set")
-                        .short("b")
-                        .long("branches"),
-                )
-                .arg(
-                    Arg::with_name("MOVES")
-                        .help("Filters games by moves played")
-                        .index(1)
-                        .multiple(true),
+use io::{Read, Write};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::env;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use std::process;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use chrono::{Datelike, NaiveDate};
+use clap::{App, AppSettings, Arg, ArgGroup, Shell, SubCommand};
+use memmap2::Mmap;
+use pgn_reader::Reader;
+use rayon::prelude::*;
+use shakmaty::fen::Fen;
+use shakmaty::{Role, Square};
+use tracing::level_filters::LevelFilter;
+use tracing::{debug, info, warn};
+
+use chesshound::analysis::{annotate_game, average_centipawn_loss, eval_graph, find_blunders};
+use chesshound::batch::{analyse_games, Budget};
+use chesshound::cancel::CancellationToken;
+use chesshound::chart::{self, LineChart};
+use chesshound::clock::Speed;
+use chesshound::collection::{GameCollection, SortKey};
+use chesshound::columnar::{self, TableFormat};
+use chesshound::config::Config;
+use chesshound::crosstable::{crosstables, Crosstable};
+use chesshound::db::Database;
+use chesshound::dedupe::{Deduplicator, DuplicateReason};
+use chesshound::engine::{Engine, Limit};
+use chesshound::eval::WinModel;
+use chesshound::export::{self, PGN_HEADERS};
+use chesshound::filter::{parse_date_range, GameFilter};
+use chesshound::flashcards::{self, Flashcard};
+use chesshound::game::{
+    Color, GiveClocks, GiveDate, GiveLink, GivePlayers, GiveRatings, GiveResult, GiveSource,
+    ListMoves, PlayerResult,
+};
+use chesshound::game_cache::GameCache;
+use chesshound::index::PositionIndex;
+use chesshound::input;
+use chesshound::key_positions::{key_positions, KeyPosition, Ranking};
+use chesshound::masters::MastersCache;
+use chesshound::moves::parse_line;
+use chesshound::openings::{Opening, Openings};
+use chesshound::parsing::{PGNGame, RawGameReader};
+use chesshound::phase::Phase;
+use chesshound::position::{self, BoardStyle, Render};
+use chesshound::prep::{prep_gaps, GapKind};
+use chesshound::puzzles::find_missed_tactics;
+use chesshound::ratings::{Elo, EstimatedRating};
+use chesshound::report::{describe_results, transpose_to_line, white_score, AnalysisRequest};
+use chesshound::sample::{SampleSize, Sampler};
+use chesshound::source::Source;
+use chesshound::stats::compare::compare;
+use chesshound::stats::development::{development, Development, Milestone};
+use chesshound::stats::fast_losses::{fast_losses, OpeningLosses};
+use chesshound::stats::heatmaps::{self, piece_heatmaps, Heatmap};
+use chesshound::stats::increments::simulate_increments;
+use chesshound::stats::lines::{predictable_moves, worst_lines, LineScore, PredictableMove};
+use chesshound::stats::opponents::{opponents, OpponentRecord};
+use chesshound::stats::pawn_structures::{pawn_structures, StructureScore};
+use chesshound::stats::rating_history::rating_history;
+use chesshound::stats::sessions::{score_by_game_number, session_lengths, sessions};
+use chesshound::stats::sharpness::branch_sharpness;
+use chesshound::stats::statistic::{compute_parallel, Merge, ResultRates, Statistic, Value};
+use chesshound::stats::streaks::streaks;
+use chesshound::stats::survival::survival_curves;
+use chesshound::stats::terminations::terminations;
+use chesshound::stats::time_forfeits::time_forfeits;
+use chesshound::stats::time_usage::time_usage;
+use chesshound::stats::traps::{traps, Trap};
+use chesshound::stats::upsets::{upsets, Upset};
+use chesshound::stats::Score;
+use chesshound::store::GameStore;
+use chesshound::threads;
+use chesshound::{AlgebraicMove, Game, GameParser, Move, MoveTree, MoveTreeView};
+
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    let error_format = ErrorFormat::from_args(&args);
+
+    match run(&args) {
+        // Output piped into a command that stopped reading it, such as `head`, isn't a failure.
+        Err(Failure::Io(error)) if error.kind() == io::ErrorKind::BrokenPipe => {}
+        Err(failure) => {
+            failure.report(error_format);
+            process::exit(failure.exit_code());
+        }
+        Ok(()) => {}
+    }
+}
+
+/// Returns the token cancelled when the user presses Ctrl-C, which long-running commands check so
+/// that they stop early while still saving their caches and printing what they finished.
+fn interruption() -> &'static CancellationToken {
+    static INTERRUPTION: OnceLock<CancellationToken> = OnceLock::new();
+    INTERRUPTION.get_or_init(CancellationToken::new)
+}
+
+/// Returns the number of plies games need for the subcommand to read them, set from
+/// `--min-plies` before it runs. Shorter games count as aborted and are left out while reading,
+/// so that they don't count towards scores. Unset for subcommands that keep aborted games.
+fn min_plies() -> &'static OnceLock<usize> {
+    static MIN_PLIES: OnceLock<usize> = OnceLock::new();
+    &MIN_PLIES
+}
+
+/// Returns whether `game` is too short for [`min_plies`], and so is left out while reading.
+fn is_aborted(game: &Game<AlgebraicMove>) -> bool {
+    min_plies()
+        .get()
+        .is_some_and(|&plies| game.list_moves().count() < plies)
+}
+
+/// Makes the first Ctrl-C cancel [`interruption`] instead of exiting. A second Ctrl-C exits
+/// straight away as usual, for when stopping early takes too long.
+#[cfg(unix)]
+fn handle_interrupts() {
+    extern "C" fn on_interrupt(_: libc::c_int) {
+        interruption().cancel();
+
+        // SAFETY: restoring the default action is async-signal-safe.
+        unsafe {
+            libc::signal(libc::SIGINT, libc::SIG_DFL);
+        }
+    }
+
+    // Created before the handler is installed, so that the handler never allocates.
+    interruption();
+
+    // SAFETY: the handler only stores to an atomic and restores the default action, both of
+    // which are async-signal-safe.
+    unsafe {
+        libc::signal(
+            libc::SIGINT,
+            on_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+}
+
+#[cfg(not(unix))]
+fn handle_interrupts() {}
+
+/// How failures are reported on standard error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ErrorFormat {
+    /// A message meant for people to read.
+    Text,
+    /// A JSON object with the kind of failure, the exit code, and the message, for scripts.
+    Json,
+}
+
+impl ErrorFormat {
+    /// Returns the format chosen with `--error-format` in `args`. Arguments are looked at before
+    /// they're parsed so that failures to parse them are reported in the chosen format too.
+    fn from_args(args: &[String]) -> ErrorFormat {
+        let json = args.iter().enumerate().any(|(index, arg)| {
+            arg == "--error-format=json"
+                || (arg == "--error-format"
+                    && args.get(index + 1).map(String::as_str) == Some("json"))
+        });
+
+        if json {
+            ErrorFormat::Json
+        } else {
+            ErrorFormat::Text
+        }
+    }
+}
+
+/// Why the command-line tool failed.
+#[derive(Debug)]
+enum Failure {
+    /// The arguments couldn't be parsed.
+    Arguments(clap::Error),
+    /// Running the subcommand failed.
+    Io(io::Error),
+}
+
+impl From<clap::Error> for Failure {
+    fn from(error: clap::Error) -> Failure {
+        Failure::Arguments(error)
+    }
+}
+
+impl From<io::Error> for Failure {
+    fn from(error: io::Error) -> Failure {
+        Failure::Io(error)
+    }
+}
+
+impl From<chesshound::Error> for Failure {
+    fn from(error: chesshound::Error) -> Failure {
+        Failure::Io(error.into())
+    }
+}
+
+impl Failure {
+    /// Returns the kind of failure, as reported in JSON.
+    fn kind(&self) -> &'static str {
+        match self {
+            Failure::Arguments(_) => "invalid_arguments",
+            Failure::Io(error) => match error.kind() {
+                io::ErrorKind::InvalidInput => "invalid_arguments",
+                io::ErrorKind::InvalidData => "parse",
+                io::ErrorKind::ConnectionRefused
+                | io::ErrorKind::ConnectionReset
+                | io::ErrorKind::ConnectionAborted
+                | io::ErrorKind::NotConnected
+                | io::ErrorKind::AddrNotAvailable
+                | io::ErrorKind::TimedOut => "network",
+                _ => "io",
+            },
+        }
+    }
+
+    /// Returns the code to exit with: 2 for invalid arguments, 3 for input that couldn't be
+    /// parsed, 4 for network failures, and 1 for anything else.
+    fn exit_code(&self) -> i32 {
+        match self.kind() {
+            "invalid_arguments" => 2,
+            "parse" => 3,
+            "network" => 4,
+            _ => 1,
+        }
+    }
+
+    /// Returns the message describing the failure, without clap's usage.
+    fn message(&self) -> String {
+        match self {
+            Failure::Arguments(error) => error
+                .message
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .trim_start_matches("error: ")
+                .to_string(),
+            Failure::Io(error) => error_chain(error),
+        }
+    }
+
+    /// Writes the failure to standard error in `format`.
+    fn report(&self, format: ErrorFormat) {
+        match (format, self) {
+            (ErrorFormat::Text, Failure::Arguments(error)) => eprintln!("{}", error.message),
+            (ErrorFormat::Text, Failure::Io(error)) => eprintln!("error: {}", error_chain(error)),
+            (ErrorFormat::Json, _) => {
+                let value = Value::map(vec![(
+                    "error",
+                    Value::map(vec![
+                        ("kind", self.kind().into()),
+                        ("code", (self.exit_code() as usize).into()),
+                        ("message", self.message().into()),
+                    ]),
+                )]);
+
+                eprintln!("{}", value.to_json());
+            }
+        }
+    }
+}
+
+/// Returns `error` followed by each error that caused it, such as `Invalid condition x: no such
+/// column: x`.
+fn error_chain(error: &dyn Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+
+    while let Some(error) = source {
+        message += &format!(": {}", error);
+        source = error.source();
+    }
+
+    message
+}
+
+/// Returns the matches of the subcommand chosen within `matches`, however deeply nested. Global
+/// arguments are passed down to subcommands, so these matches have them all.
+fn innermost<'a>(matches: &'a clap::ArgMatches<'a>) -> &'a clap::ArgMatches<'a> {
+    let mut matches = matches;
+
+    while let (_, Some(subcommand_matches)) = matches.subcommand() {
+        matches = subcommand_matches;
+    }
+
+    matches
+}
+
+/// Returns the `--min-plies` argument, for the number of plies games need to not count as aborted.
+fn min_plies_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("min-plies")
+        .help("Number of moves, counting each side's separately, games need to not count as aborted. Aborted games are left out, except that stats reports how many there were and subcommands that write games back out keep them unless --exclude-aborted is given")
+        .long("min-plies")
+        .takes_value(true)
+        .default_value("2")
+}
+
+/// Sends the library's logs to standard error, at the level chosen with `-v` and `-q` and in the
+/// format chosen with `--log-format`. Only warnings are logged by default.
+fn init_logging(matches: &clap::ArgMatches) {
+    let matches = innermost(matches);
+    let level = if matches.is_present("quiet") {
+        LevelFilter::OFF
+    } else {
+        match matches.occurrences_of("verbose") {
+            0 => LevelFilter::WARN,
+            1 => LevelFilter::INFO,
+            2 => LevelFilter::DEBUG,
+            _ => LevelFilter::TRACE,
+        }
+    };
+
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(io::stderr)
+        .with_ansi(io::stderr().is_terminal())
+        .with_max_level(level);
+
+    if matches.value_of("log-format") == Some("json") {
+        subscriber.json().init();
+    } else {
+        subscriber.init();
+    }
+}
+
+/// Runs the subcommand chosen by `args`.
+fn run(args: &[String]) -> Result<(), Failure> {
+    let config = load_config(args)?;
+    let cache_default = config.cache_dir().map(|cache_dir| {
+        Path::new(cache_dir)
+            .join("engine.cache")
+            .to_string_lossy()
+            .into_owned()
+    });
+    let game_cache_default = config.cache_dir().map(|cache_dir| {
+        Path::new(cache_dir)
+            .join("games.cache")
+            .to_string_lossy()
+            .into_owned()
+    });
+
+    let matches = match app(
+        &config,
+        cache_default.as_deref(),
+        game_cache_default.as_deref(),
+    )
+    .get_matches_from_safe(args)
+    {
+        Ok(matches) => matches,
+        Err(error)
+            if error.kind == clap::ErrorKind::HelpDisplayed
+                || error.kind == clap::ErrorKind::VersionDisplayed =>
+        {
+            error.exit()
+        }
+        Err(error) => return Err(error.into()),
+    };
+
+    init_logging(&matches);
+
+    // Interactive commands run until the user stops them, so Ctrl-C exits them as usual.
+    if !matches!(matches.subcommand_name(), Some("repl") | Some("watch")) {
+        handle_interrupts();
+    }
+
+    let threads = match innermost(&matches).value_of("threads") {
+        Some(threads) => parse_number(threads, "threads")?,
+        None => threads::default_threads(),
+    };
+
+    threads::set_threads(threads)?;
+
+    // stats counts aborted games in its report, and subcommands that write games back out keep
+    // them unless --exclude-aborted is given, so only the rest leave them out while reading.
+    if !matches!(
+        matches.subcommand_name(),
+        Some("stats") | Some("convert") | Some("export") | Some("annotate") | Some("db")
+    ) {
+        if let Some(plies) = innermost(&matches).value_of("min-plies") {
+            let _ = min_plies().set(parse_number(plies, "min-plies")?);
+        }
+    }
+
+    if let Some(matches) = matches.subcommand_matches("completions") {
+        let shell = matches.value_of("SHELL").unwrap().parse::<Shell>().unwrap();
+
+        // Generated in memory first, since clap panics if it can't write them.
+        let mut completions = Vec::new();
+        app(
+            &config,
+            cache_default.as_deref(),
+            game_cache_default.as_deref(),
+        )
+        .gen_completions_to("chesshound", shell, &mut completions);
+
+        io::stdout().lock().write_all(&completions)?;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("stats") {
+        let moves: Vec<String> = if let Some(values) = matches.values_of("MOVES") {
+            values.map(String::from).collect::<Vec<_>>()
+        } else {
+            Vec::new()
+        };
+
+        let show_branches = matches.is_present("branches");
+
+        let sharpness = match matches.value_of("min-sharpness") {
+            Some(min_sharpness) => Some(parse_number(min_sharpness, "min-sharpness")?),
+            None if matches.is_present("sharpness") => Some(0.),
+            None => None,
+        };
+
+        let request = AnalysisRequest::new(game_filter(matches)?)
+            .moves(moves)
+            .by_position(matches.is_present("by-position"))
+            .branches(show_branches)
+            .examples(matches.is_present("examples"))
+            .min_sharpness(sharpness)
+            .min_plies(parse_number(
+                matches.value_of("min-plies").unwrap(),
+                "min-plies",
+            )?)
+            .masters(match matches.value_of("masters") {
+                Some(path) => Some(MastersCache::load(Path::new(path))?),
+                None => None,
+            });
+
+        let output = run_stats(
+            stdin_games()?,
+            &request,
+            game_sampler(matches)?,
+            Format::from_matches(matches),
+        )?;
+
+        // Tables already end with a line break.
+        writeln!(io::stdout().lock(), "{}", output.trim_end_matches('\n'))?;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("tree") {
+        let moves = matches
+            .values_of("MOVES")
+            .map_or_else(Vec::new, |values| values.map(String::from).collect());
+
+        let output = run_tree(
+            stdin_games()?,
+            &game_filter(matches)?,
+            game_sampler(matches)?,
+            moves,
+            matches.is_present("by-position"),
+            parse_number(matches.value_of("depth").unwrap(), "depth")?,
+            parse_number(matches.value_of("min-games").unwrap(), "min-games")?,
+            Format::from_matches(matches),
+            matches.is_present("explorer"),
+        )?;
+
+        writeln!(io::stdout().lock(), "{}", output.trim_end_matches('\n'))?;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("positions") {
+        let moves = matches
+            .values_of("MOVES")
+            .map_or_else(Vec::new, |values| values.map(String::from).collect());
+        let ranking = match matches.value_of("by") {
+            Some("score") => Ranking::WorstScoring,
+            _ => Ranking::MostPlayed,
+        };
+
+        let output = run_positions(
+            stdin_games()?,
+            &game_filter(matches)?,
+            moves,
+            parse_number(matches.value_of("depth").unwrap(), "depth")?,
+            parse_number(matches.value_of("min-games").unwrap(), "min-games")?,
+            matches.value_of("player"),
+            ranking,
+            parse_number(matches.value_of("limit").unwrap(), "limit")?,
+        )?;
+
+        writeln!(io::stdout().lock(), "{}", output.trim_end_matches('\n'))?;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("traps") {
+        let player = matches.value_of("player").ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Traps are found for a player, given with --player or in the config",
+            )
+        })?;
+
+        let output = run_traps(
+            stdin_games()?,
+            &game_filter(matches)?,
+            player,
+            parse_number(matches.value_of("max-moves").unwrap(), "max-moves")?,
+            parse_number(matches.value_of("limit").unwrap(), "limit")?,
+            Format::from_matches(matches),
+            board_style(matches),
+        )?;
+
+        writeln!(io::stdout().lock(), "{}", output.trim_end_matches('\n'))?;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("search") {
+        let output = run_search(
+            stdin_games()?,
+            &game_filter(matches)?,
+            game_sampler(matches)?,
+            matches.value_of("fen").unwrap(),
+            matches.is_present("color-reversed"),
+            Format::from_matches(matches),
+            board_style(matches),
+        )?;
+
+        writeln!(io::stdout().lock(), "{}", output.trim_end_matches('\n'))?;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("fen") {
+        let moves = match matches.value_of("game") {
+            Some(game) => {
+                let ply = match matches.value_of("ply") {
+                    Some(ply) => Some(parse_number(ply, "ply")?),
+                    None => None,
+                };
+
+                game_moves(stdin_games()?, parse_number(game, "game")?, ply)?
+            }
+            None => matches
+                .values_of("MOVES")
+                .map(|values| values.map(String::from).collect())
+                .unwrap_or_default(),
+        };
+
+        write!(
+            io::stdout().lock(),
+            "{}",
+            run_fen(&moves, board_style(matches))?
+        )?;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("report") {
+        let player = matches.value_of("player").ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "The report needs a player, given with --player or in the config",
+            )
+        })?;
+
+        let cache_path = matches.value_of("game-cache");
+        let mut cache = GameCache::new();
+
+        if let Some(file) = open_cache(cache_path)? {
+            cache.read(file)?;
+        }
+
+        let output = run_report(
+            stdin_games()?,
+            &game_filter(matches)?,
+            player,
+            parse_number(matches.value_of("limit").unwrap(), "limit")?,
+            Format::from_matches(matches),
+            matches.value_of("chart").map(Path::new),
+            &mut cache,
+        )?;
+
+        writeln!(io::stdout().lock(), "{}", output.trim_end_matches('\n'))?;
+
+        if let Some(cache_path) = cache_path.filter(|_| cache.added() > 0) {
+            if let Some(directory) = Path::new(cache_path).parent() {
+                fs::create_dir_all(directory)?;
+            }
+
+            cache.write(io::BufWriter::new(File::create(cache_path)?))?;
+        }
+    }
+
+    if let Some(matches) = matches.subcommand_matches("losses") {
+        let player = matches.value_of("player").ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Losses are found for a player, given with --player or in the config",
+            )
+        })?;
+
+        let output = run_losses(
+            stdin_games()?,
+            &game_filter(matches)?,
+            player,
+            parse_number(matches.value_of("quick").unwrap(), "quick")?,
+            parse_number(matches.value_of("min-losses").unwrap(), "min-losses")?,
+            parse_number(matches.value_of("limit").unwrap(), "limit")?,
+            Format::from_matches(matches),
+        )?;
+
+        writeln!(io::stdout().lock(), "{}", output.trim_end_matches('\n'))?;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("increments") {
+        let player = matches.value_of("player").ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Increments are simulated for a player, given with --player or in the config",
+            )
+        })?;
+
+        let increments = matches
+            .value_of("increments")
+            .unwrap()
+            .split(',')
+            .map(|increment| parse_number(increment.trim(), "increments").map(Duration::from_secs))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let output = run_increments(
+            stdin_games()?,
+            &game_filter(matches)?,
+            player,
+            &increments,
+            Format::from_matches(matches),
+        )?;
+
+        writeln!(io::stdout().lock(), "{}", output.trim_end_matches('\n'))?;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("sessions") {
+        let player = matches.value_of("player").ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Sessions are of a player's games, given with --player or in the config",
+            )
+        })?;
+
+        let gap = parse_number::<u64>(matches.value_of("gap").unwrap(), "gap")?;
+
+        let output = run_sessions(
+            stdin_games()?,
+            &game_filter(matches)?,
+            player,
+            Duration::from_secs(gap * 60),
+            Format::from_matches(matches),
+        )?;
+
+        writeln!(io::stdout().lock(), "{}", output.trim_end_matches('\n'))?;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("development") {
+        let player = matches.value_of("player").ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Development is of a player's pieces, given with --player or in the config",
+            )
+        })?;
+
+        let output = run_development(
+            stdin_games()?,
+            &game_filter(matches)?,
+            player,
+            Format::from_matches(matches),
+        )?;
+
+        writeln!(io::stdout().lock(), "{}", output.trim_end_matches('\n'))?;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("heatmap") {
+        let player = matches.value_of("player").ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Heatmaps are of a player's pieces, given with --player or in the config",
+            )
+        })?;
+
+        let output = run_heatmaps(
+            stdin_games()?,
+            &game_filter(matches)?,
+            player,
+            matches.value_of("piece").map(piece_role),
+            parse_number(matches.value_of("min-games").unwrap(), "min-games")?,
+            parse_number(matches.value_of("limit").unwrap(), "limit")?,
+            Format::from_matches(matches),
+            matches.value_of("svg").map(Path::new),
+        )?;
+
+        writeln!(io::stdout().lock(), "{}", output.trim_end_matches('\n'))?;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("survival") {
+        let output = run_survival(
+            stdin_games()?,
+            &game_filter(matches)?,
+            parse_number(matches.value_of("min-games").unwrap(), "min-games")?,
+            parse_number(matches.value_of("limit").unwrap(), "limit")?,
+            parse_number(matches.value_of("step").unwrap(), "step")?,
+            parse_number(matches.value_of("max-moves").unwrap(), "max-moves")?,
+            Format::from_matches(matches),
+        )?;
+
+        writeln!(io::stdout().lock(), "{}", output.trim_end_matches('\n'))?;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("prep") {
+        let output = run_dossier(
+            stdin_games()?,
+            &game_filter(matches)?,
+            matches.value_of("player").unwrap(),
+            parse_number(matches.value_of("depth").unwrap(), "depth")?,
+            parse_number(matches.value_of("min-games").unwrap(), "min-games")?,
+            parse_number(matches.value_of("limit").unwrap(), "limit")?,
+            parse_number(matches.value_of("recent").unwrap(), "recent")?,
+            Format::from_matches(matches),
+        )?;
+
+        writeln!(io::stdout().lock(), "{}", output.trim_end_matches('\n'))?;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("watch") {
+        let player = matches.value_of("player").ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Watching needs a player, given with --player or in the config",
+            )
+        })?;
+
+        run_watch(
+            Path::new(matches.value_of("FILE").unwrap()),
+            &game_filter(matches)?,
+            player,
+            Duration::from_secs(parse_number(
+                matches.value_of("interval").unwrap(),
+                "interval",
+            )?),
+            Format::from_matches(matches),
+            &mut io::stdout(),
+        )?;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("db") {
+        if let Some(matches) = matches.subcommand_matches("import") {
+            let mut database = Database::open(Path::new(matches.value_of("DATABASE").unwrap()))?;
+            let games = read_filtered_games(
+                &mut Reader::new(stdin_games()?),
+                &game_filter(matches)?,
+                None,
+            )?;
+
+            let added = database.import(&games)?;
+
+            eprintln!(
+                "Imported {} new games, {} games in total",
+                added,
+                database.len()?
+            );
+        }
+
+        if let Some(matches) = matches.subcommand_matches("query") {
+            run_db_query(
+                Path::new(matches.value_of("DATABASE").unwrap()),
+                matches.value_of("where"),
+                matches.value_of("fen"),
+                matches.is_present("count"),
+                &mut io::BufWriter::new(io::stdout().lock()),
+            )?;
+        }
+    }
+
+    if let Some(matches) = matches.subcommand_matches("repl") {
+        let pgn = file_games(matches.value_of("FILE").unwrap())?;
+        let games = read_games(&mut Reader::new(pgn))?;
+
+        run_repl(games, io::stdin().lock(), &mut io::stdout().lock())?;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("convert") {
+        let headers = matches
+            .values_of("headers")
+            .map_or_else(|| PGN_HEADERS.to_vec(), |values| values.collect());
+
+        let format = match matches.value_of("to") {
+            Some("ndjson") => GameFormat::Ndjson,
+            Some("json") => GameFormat::Json,
+            _ => GameFormat::Pgn,
+        };
+
+        run_convert(
+            stdin_games()?,
+            &mut io::BufWriter::new(io::stdout().lock()),
+            &game_filter(matches)?,
+            format,
+            &headers,
+            sort_key(matches),
+        )?;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("export") {
+        let format = match matches.value_of("format") {
+            Some("arrow") => TableFormat::Arrow,
+            _ => TableFormat::Parquet,
+        };
+
+        run_export(
+            stdin_games()?,
+            &game_filter(matches)?,
+            format,
+            Path::new(matches.value_of("output").unwrap()),
+            matches.value_of("moves").map(Path::new),
+            sort_key(matches),
+        )?;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("dedupe") {
+        let preserve = matches.is_present("preserve");
+        let (removed, unread) = match matches.value_of("output") {
+            Some(path) => run_dedupe(
+                stdin_games()?,
+                &mut io::BufWriter::new(File::create(path)?),
+                preserve,
+            )?,
+            None => run_dedupe(
+                stdin_games()?,
+                &mut io::BufWriter::new(io::stdout().lock()),
+                preserve,
+            )?,
+        };
+
+        // The report goes to standard error so it doesn't mix with the games.
+        eprintln!(
+            "Removed {} duplicate games",
+            removed.values().sum::<usize>()
+        );
+
+        for (reason, count) in removed {
+            eprintln!("  {}: {}", reason, count);
+        }
+
+        if unread > 0 {
+            eprintln!("Left out {} games that couldn't be read", unread);
+        }
+    }
+
+    if let Some(matches) = matches.subcommand_matches("split") {
+        let split_by = match matches.value_of("by") {
+            Some("month") => SplitBy::Month,
+            Some("opening") => SplitBy::Opening,
+            Some("source") => SplitBy::Source,
+            Some("color") => SplitBy::Color(String::from(matches.value_of("player").unwrap())),
+            _ => SplitBy::Count(parse_number(matches.value_of("games").unwrap(), "games")?),
+        };
+
+        let (written, unread) = run_split(
+            stdin_games()?,
+            &split_by,
+            Path::new(matches.value_of("directory").unwrap()),
+            matches.is_present("preserve"),
+        )?;
+
+        for (name, count) in written {
+            eprintln!("{}: {} games", name, count);
+        }
+
+        if unread > 0 {
+            return Err(unread_games_error(unread).into());
+        }
+    }
+
+    if let Some(matches) = matches.subcommand_matches("merge") {
+        let files = matches
+            .values_of("FILES")
+            .unwrap()
+            .map(|path| Ok((path, file_games(path)?)))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let (merged, unread) = match matches.value_of("output") {
+            Some(path) => run_merge(files, &mut io::BufWriter::new(File::create(path)?))?,
+            None => run_merge(files, &mut io::BufWriter::new(io::stdout().lock()))?,
+        };
+
+        for (source, count) in merged {
+            eprintln!("{}: {} games", source, count);
+        }
+
+        if unread > 0 {
+            return Err(unread_games_error(unread).into());
+        }
+    }
+
+    if let Some(matches) = matches.subcommand_matches("compare") {
+        let before = file_games(matches.value_of("BEFORE").unwrap())?;
+        let after = file_games(matches.value_of("AFTER").unwrap())?;
+
+        writeln!(
+            io::stdout().lock(),
+            "{}",
+            run_compare(
+                before,
+                after,
+                matches.value_of("player"),
+                Format::from_matches(matches)
+            )?
+        )?;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("opponents") {
+        let player = matches.value_of("player").ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Opponents are of a player, given with --player or in the config",
+            )
+        })?;
+
+        let output = run_opponents(
+            stdin_games()?,
+            &game_filter(matches)?,
+            player,
+            parse_number(matches.value_of("limit").unwrap(), "limit")?,
+            Format::from_matches(matches),
+        )?;
+
+        writeln!(io::stdout().lock(), "{}", output.trim_end_matches('\n'))?;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("crosstable") {
+        let output = run_crosstable(
+            stdin_games()?,
+            &game_filter(matches)?,
+            Format::from_matches(matches),
+        )?;
+
+        writeln!(io::stdout().lock(), "{}", output.trim_end_matches('\n'))?;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("ratings") {
+        let elo = Elo::new()
+            .initial(parse_number(
+                matches.value_of("initial").unwrap(),
+                "initial",
+            )?)
+            .k_factor(parse_number(
+                matches.value_of("k-factor").unwrap(),
+                "k-factor",
+            )?);
+
+        let output = run_ratings(
+            stdin_games()?,
+            &game_filter(matches)?,
+            elo,
+            Format::from_matches(matches),
+        )?;
+
+        writeln!(io::stdout().lock(), "{}", output.trim_end_matches('\n'))?;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("annotate") {
+        let limit = match matches.value_of("movetime") {
+            Some(movetime) => {
+                Limit::Time(Duration::from_millis(parse_number(movetime, "movetime")?))
+            }
+            None => Limit::Depth(parse_number(matches.value_of("depth").unwrap(), "depth")?),
+        };
+
+        let mut engine = Engine::spawn(matches.value_of("engine").unwrap())?;
+        let filter = game_filter(matches)?;
+        let blunders_only = matches.is_present("blunders-only");
+
+        match matches.value_of("output") {
+            Some(path) => run_annotate(
+                stdin_games()?,
+                &mut io::BufWriter::new(File::create(path)?),
+                &mut engine,
+                &filter,
+                limit,
+                blunders_only,
+            )?,
+            None => run_annotate(
+                stdin_games()?,
+                &mut io::stdout().lock(),
+                &mut engine,
+                &filter,
+                limit,
+                blunders_only,
+            )?,
+        }
+    }
+
+    if let Some(matches) = matches.subcommand_matches("analyze") {
+        let limit = match matches.value_of("movetime") {
+            Some(movetime) => {
+                Limit::Time(Duration::from_millis(parse_number(movetime, "movetime")?))
+            }
+            None => Limit::Depth(parse_number(matches.value_of("depth").unwrap(), "depth")?),
+        };
+
+        let mut budget = Budget::new().with_cancellation(interruption().clone());
+
+        if let Some(max_time) = matches.value_of("max-time") {
+            budget = budget.with_time(Duration::from_secs(parse_number(max_time, "max-time")?));
+        }
+
+        if let Some(max_nodes) = matches.value_of("max-nodes") {
+            budget = budget.with_nodes(parse_number(max_nodes, "max-nodes")?);
+        }
+
+        let engine_count = match matches.value_of("engines") {
+            Some(engines) => parse_number(engines, "engines")?,
+            None => threads::threads(),
+        };
+
+        if engine_count == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "At least one engine is needed",
+            )
+            .into());
+        }
+
+        let mut engines = (0..engine_count)
+            .map(|_| Engine::spawn(matches.value_of("engine").unwrap()))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let lines: u32 = parse_number(matches.value_of("multipv").unwrap(), "multipv")?;
+
+        if lines > 1 {
+            for engine in &mut engines {
+                engine.set_lines(lines)?;
+            }
+        }
+
+        let cache_path = matches.value_of("cache");
+
+        if let Some(cache) = open_cache(cache_path)? {
+            let (first, others) = engines.split_first_mut().unwrap();
+            first.read_cache(cache)?;
+
+            for other in others {
+                other.merge_cache(first);
+            }
+        }
+
+        let analysed = run_analyses(matches, &mut engines, limit, budget);
+
+        // Saved even when the analysis failed part way, so that the positions searched before it
+        // did aren't searched again next time.
+        let saved = match cache_path {
+            Some(cache_path) => write_engine_cache(&engines[0], Path::new(cache_path)),
+            None => Ok(()),
+        };
+
+        analysed?;
+        saved?;
+    }
+
+    Ok(())
+}
+
+/// Creates the command-line interface, with defaults taken from `config`, `cache_default` as the
+/// default `--cache` file, and `game_cache_default` as the default `--game-cache` file.
+fn app<'a>(
+    config: &'a Config,
+    cache_default: Option<&'a str>,
+    game_cache_default: Option<&'a str>,
+) -> App<'a, 'a> {
+    App::new("Chesshound")
+    .version("0.1.0")
+    .author("Gage C. <github.com/grchristensen>")
+    .about("Chesshound is a CLI tool for finding patterns in sets of chess games")
+    .arg(
+        Arg::with_name("error-format")
+            .help("Format to report failures in on standard error")
+            .long("error-format")
+            .takes_value(true)
+            .possible_values(&["text", "json"])
+            .global(true),
+    )
+    .arg(
+        Arg::with_name("verbose")
+            .help("Logs what chesshound is doing to standard error, in more detail each time it's given")
+            .short("v")
+            .long("verbose")
+            .multiple(true)
+            .global(true),
+    )
+    .arg(
+        Arg::with_name("quiet")
+            .help("Logs nothing, not even warnings")
+            .short("q")
+            .long("quiet")
+            .conflicts_with("verbose")
+            .global(true),
+    )
+    .arg(
+        Arg::with_name("log-format")
+            .help("Format to log in")
+            .long("log-format")
+            .takes_value(true)
+            .possible_values(&["text", "json"])
+            .global(true),
+    )
+    .arg(
+        Arg::with_name("threads")
+            .help("Number of threads to work with, and of engines to analyze with at once, instead of one per core")
+            .long("threads")
+            .takes_value(true)
+            .global(true),
+    )
+    .arg(
+        Arg::with_name("config")
+            .help("Config file with default options, instead of ~/.config/chesshound/config.toml")
+            .long("config")
+            .takes_value(true)
+            .global(true),
+    )
+    .subcommand(
+        SubCommand::with_name("completions")
+            .about("Prints a script completing chesshound's subcommands and options in a shell")
+            .after_help("The script is generated from chesshound's options, so it completes subcommands, options, and the fixed choices some options take, such as --format. Values that depend on your games or config, such as player names, aren't completed.")
+            .arg(
+                Arg::with_name("SHELL")
+                    .help("Shell to print the completion script for")
+                    .required(true)
+                    .index(1)
+                    .possible_values(&Shell::variants()),
+            ),
+    )
+    .subcommand(
+        SubCommand::with_name("stats")
+            .about("Takes PGN from standard input and gives statistics on games found")
+            .arg(
+                Arg::with_name("branches")
+                    .help("Show all moves that occur after this one in the game set")
+                    .short("b")
+                    .long("branches"),
+            )
+            .arg(
+                Arg::with_name("sharpness")
+                    .help("Show how often games end decisively, listing the sharpest branches first")
+                    .short("s")
+                    .long("sharpness"),
+            )
+            .arg(
+                Arg::with_name("min-sharpness")
+                    .help("Hide branches where fewer than this fraction of games end decisively")
+                    .long("min-sharpness")
+                    .takes_value(true)
+                    .requires("branches"),
+            )
+            .arg(
+                Arg::with_name("examples")
+                    .help("Show the most recently played game of each branch")
+                    .long("examples")
+                    .requires("branches"),
+            )
+            .arg(
+                Arg::with_name("masters")
+                    .help("JSON file of cached lichess masters explorer responses keyed by FEN, to compare the line and its branches with master games, flagging branches that diverge from them")
+                    .long("masters")
+                    .takes_value(true),
+            )
+            .arg(format_arg(config))
+            .args(&filter_args(config))
+            .args(&sample_args())
+            .arg(
+                Arg::with_name("by-position")
+                    .help("Includes games reaching the position after MOVES by a different move order")
+                    .long("by-position"),
+            )
+            .arg(
+                Arg::with_name("MOVES")
+                    .help("Filters games by moves played")
+                    .index(1)
+                    .multiple(true),
+            ),
+    )
+    .subcommand(
+        SubCommand::with_name("tree")
+            .about("Takes PGN from standard input and prints the tree of moves played, like an opening explorer")
+            .arg(
+                Arg::with_name("depth")
+                    .help("Number of plies to show after the given moves")
+                    .short("d")
+                    .long("depth")
+                    .takes_value(true)
+                    .default_value("4"),
+            )
+            .arg(
+                Arg::with_name("min-games")
+                    .help("Hides moves played in fewer than this many games")
+                    .short("m")
+                    .long("min-games")
+                    .takes_value(true)
+                    .default_value("1"),
+            )
+            .arg(format_arg(config))
+            .arg(
+                Arg::with_name("explorer")
+                    .help("Prints the moves after MOVES as JSON in the schema of the lichess opening explorer API, in place of the tree")
+                    .long("explorer"),
+            )
+            .args(&filter_args(config))
+            .args(&sample_args())
+            .arg(
+                Arg::with_name("by-position")
+                    .help("Includes games reaching the position after MOVES by a different move order")
+                    .long("by-position"),
+            )
+            .arg(
+                Arg::with_name("MOVES")
+                    .help("Moves leading to the position to show the tree from")
+                    .index(1)
+                    .multiple(true),
+            ),
+    )
+    .subcommand(
+        SubCommand::with_name("positions")
+            .about("Takes PGN from standard input and writes the most played or worst scoring positions after some moves as EPD, with their game count and score, for engines and trainers")
+            .arg(
+                Arg::with_name("by")
+                    .help("Whether to choose the most played positions or the worst scoring ones, scored for --player or else the side to move")
+                    .long("by")
+                    .takes_value(true)
+                    .possible_values(&["games", "score"])
+                    .default_value("games"),
+            )
+            .arg(
+                Arg::with_name("depth")
+                    .help("Number of plies after the given moves to look for positions in")
+                    .short("d")
+                    .long("depth")
+                    .takes_value(true)
+                    .default_value("12"),
+            )
+            .arg(
+                Arg::with_name("min-games")
+                    .help("Leaves out positions reached in fewer than this many games")
+                    .short("m")
+                    .long("min-games")
+                    .takes_value(true)
+                    .default_value("5"),
+            )
+            .arg(
+                Arg::with_name("limit")
+                    .help("Number of positions to write")
+                    .short("n")
+                    .long("limit")
+                    .takes_value(true)
+                    .default_value("20"),
+            )
+            .args(&filter_args(config))
+            .arg(
+                Arg::with_name("MOVES")
+                    .help("Moves leading to the position to look for positions after")
+                    .index(1)
+                    .multiple(true),
+            ),
+    )
+    .subcommand(
+        SubCommand::with_name("traps")
+            .about("Takes PGN from standard input and finds the traps a player sprang or fell into in short decisive games, grouped by the position before the game was decided")
+            .arg(
+                Arg::with_name("max-moves")
+                    .help("Longest game, in moves, to look for traps in")
+                    .short("m")
+                    .long("max-moves")
+                    .takes_value(true)
+                    .default_value("20"),
+            )
+            .arg(
+                Arg::with_name("limit")
+                    .help("Number of traps to list")
+                    .short("n")
+                    .long("limit")
+                    .takes_value(true)
+                    .default_value("10"),
+            )
+            .arg(board_arg())
+            .arg(format_arg(config))
+            .args(&filter_args(config)),
+    )
+    .subcommand(
+        SubCommand::with_name("search")
+            .about("Takes PGN from standard input and finds the games that reached a position, however they got there")
+            .arg(
+                Arg::with_name("fen")
+                    .help("FEN of the position to search for")
+                    .long("fen")
+                    .takes_value(true)
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("color-reversed")
+                    .help("Also finds games reaching the position with the colors reversed, giving their moves and results as if the colors were swapped")
+                    .long("color-reversed"),
+            )
+            .arg(format_arg(config))
+            .arg(board_arg())
+            .args(&filter_args(config))
+            .args(&sample_args()),
+    )
+    .subcommand(
+        SubCommand::with_name("fen")
+            .about("Prints the FEN of the position reached by moves, or by a game from PGN on standard input")
+            .arg(
+                Arg::with_name("game")
+                    .help("Number of the game in the PGN to play through, counting from 1")
+                    .short("g")
+                    .long("game")
+                    .takes_value(true)
+                    .conflicts_with("MOVES"),
+            )
+            .arg(
+                Arg::with_name("ply")
+                    .help("Number of plies of the game to play, instead of the whole game")
+                    .long("ply")
+                    .takes_value(true)
+                    .requires("game"),
+            )
+            .arg(board_arg())
+            .arg(
+                Arg::with_name("MOVES")
+                    .help("Moves in SAN leading to the position")
+                    .index(1)
+                    .multiple(true),
+            ),
+    )
+    .subcommand(
+        SubCommand::with_name("report")
+            .about("Takes PGN from standard input and reports on one player's games: scores, openings, pawn structures, terminations, time controls, time forfeits, monthly trend, streaks, and upsets")
+            .arg(
+                Arg::with_name("chart")
+                    .help("SVG file to draw the player's monthly score and rating progression in")
+                    .long("chart")
+                    .takes_value(true),
+            )
+            .arg(with_default(
+                Arg::with_name("game-cache")
+                    .help("File to keep each game's opening, phases, and accuracy in between runs, so they're only worked out once")
+                    .long("game-cache")
+                    .takes_value(true),
+                game_cache_default,
+            ))
+            .arg(
+                Arg::with_name("limit")
+                    .help("Number of openings, pawn structures, and upsets to list")
+                    .short("n")
+                    .long("limit")
+                    .takes_value(true)
+                    .default_value("10"),
+            )
+            .arg(format_arg(config))
+            .args(&filter_args(config)),
+    )
+    .subcommand(
+        SubCommand::with_name("losses")
+            .about("Takes PGN from standard input and ranks the openings a player loses quickest in")
+            .arg(
+                Arg::with_name("quick")
+                    .help("Number of moves under which a loss counts as quick")
+                    .short("k")
+                    .long("quick")
+                    .takes_value(true)
+                    .default_value("25"),
+            )
+            .arg(
+                Arg::with_name("min-losses")
+                    .help("Leaves out openings with fewer than this many losses")
+                    .short("m")
+                    .long("min-losses")
+                    .takes_value(true)
+                    .default_value("2"),
+            )
+            .arg(
+                Arg::with_name("limit")
+                    .help("Number of openings to list")
+                    .short("n")
+                    .long("limit")
+                    .takes_value(true)
+                    .default_value("10"),
+            )
+            .arg(format_arg(config))
+            .args(&filter_args(config)),
+    )
+    .subcommand(
+        SubCommand::with_name("increments")
+            .about("Takes PGN with clock times from standard input and simulates how a player's games would have gone with other increments, to show how much of their score comes down to the clock")
+            .arg(
+                Arg::with_name("increments")
+                    .help("Comma-separated increments to simulate, in seconds")
+                    .short("i")
+                    .long("increments")
+                    .takes_value(true)
+                    .default_value("0,1,2,3,5"),
+            )
+            .arg(format_arg(config))
+            .args(&filter_args(config)),
+    )
+    .subcommand(
+        SubCommand::with_name("sessions")
+            .about("Takes PGN with start times from standard input and groups a player's games into sessions played one after another, reporting how they score in each game of a session and how long their sessions are")
+            .arg(
+                Arg::with_name("gap")
+                    .help("Minutes without playing after which the next game starts a new session")
+                    .short("g")
+                    .long("gap")
+                    .takes_value(true)
+                    .default_value("30"),
+            )
+            .arg(format_arg(config))
+            .args(&filter_args(config)),
+    )
+    .subcommand(
+        SubCommand::with_name("development")
+            .about("Takes PGN from standard input and reports how soon a player first moves each of their pieces, castles, and captures, compared with the other sides of the games")
+            .arg(format_arg(config))
+            .args(&filter_args(config)),
+    )
+    .subcommand(
+        SubCommand::with_name("heatmap")
+            .about("Takes PGN from standard input and maps the squares a player's pieces stood on and moved to, for each opening and color")
+            .arg(
+                Arg::with_name("piece")
+                    .help("Kind of piece to map in text and SVG, instead of every piece")
+                    .long("piece")
+                    .takes_value(true)
+                    .possible_values(&["pawn", "knight", "bishop", "rook", "queen", "king"]),
+            )
+            .arg(
+                Arg::with_name("min-games")
+                    .help("Leaves out openings played in fewer than this many games with a color")
+                    .short("m")
+                    .long("min-games")
+                    .takes_value(true)
+                    .default_value("5"),
+            )
+            .arg(
+                Arg::with_name("limit")
+                    .help("Number of openings to map")
+                    .short("n")
+                    .long("limit")
+                    .takes_value(true)
+                    .default_value("5"),
+            )
+            .arg(
+                Arg::with_name("svg")
+                    .help("SVG file to draw the heatmaps in")
+                    .long("svg")
+                    .takes_value(true),
+            )
+            .arg(format_arg(config))
+            .args(&filter_args(config)),
+    )
+    .subcommand(
+        SubCommand::with_name("survival")
+            .about("Takes PGN from standard input and shows, for each opening, how likely games are to still be undecided at each move, and the moves white and black win at")
+            .arg(
+                Arg::with_name("min-games")
+                    .help("Leaves out openings played in fewer than this many games")
+                    .short("m")
+                    .long("min-games")
+                    .takes_value(true)
+                    .default_value("10"),
+            )
+            .arg(
+                Arg::with_name("limit")
+                    .help("Number of openings to list")
+                    .short("n")
+                    .long("limit")
+                    .takes_value(true)
+                    .default_value("10"),
+            )
+            .arg(
+                Arg::with_name("step")
+                    .help("Number of moves between the points of each curve written as text")
+                    .short("s")
+                    .long("step")
+                    .takes_value(true)
+                    .default_value("10"),
+            )
+            .arg(
+                Arg::with_name("max-moves")
+                    .help("Move number to end the curves at")
+                    .long("max-moves")
+                    .takes_value(true)
+                    .default_value("60"),
+            )
+            .arg(format_arg(config))
+            .args(&filter_args(config)),
+    )
+    .subcommand(
+        SubCommand::with_name("prep")
+            .about("Takes an opponent's games as PGN from standard input and writes a dossier for preparing against them: their repertoire as white and black, the lines they score worst in, the moves they play most predictably, how they use their clock, and their recent form")
+            .arg(
+                Arg::with_name("player")
+                    .help("Opponent to prepare against")
+                    .required(true)
+                    .index(1),
+            )
+            .arg(
+                Arg::with_name("depth")
+                    .help("Number of plies to show the repertoire to and to look for predictable moves in")
+                    .short("d")
+                    .long("depth")
+                    .takes_value(true)
+                    .default_value("8"),
+            )
+            .arg(
+                Arg::with_name("min-games")
+                    .help("Leaves out moves and lines played in fewer than this many games")
+                    .short("m")
+                    .long("min-games")
+                    .takes_value(true)
+                    .default_value("2"),
+            )
+            .arg(
+                Arg::with_name("limit")
+                    .help("Number of lines and moves to list in each section")
+                    .short("n")
+                    .long("limit")
+                    .takes_value(true)
+                    .default_value("5"),
+            )
+            .arg(
+                Arg::with_name("recent")
+                    .help("Number of most recent games to judge the opponent's form by")
+                    .long("recent")
+                    .takes_value(true)
+                    .default_value("10"),
+            )
+            .arg(format_arg(config))
+            .args(&player_filter_args(config)),
+    )
+    .subcommand(
+        SubCommand::with_name("watch")
+            .about("Watches a PGN file that games are being added to, such as during a tournament, and prints the player's updated score after each new game")
+            .arg(
+                Arg::with_name("interval")
+                    .help("Seconds to wait between checks for new games")
+                    .long("interval")
+                    .takes_value(true)
+                    .default_value("10"),
+            )
+            .arg(format_arg(config))
+            .args(&filter_args(config))
+            .arg(
+                Arg::with_name("FILE")
+                    .help("PGN file to watch")
+                    .required(true)
+                    .index(1),
+            ),
+    )
+    .subcommand(
+        SubCommand::with_name("db")
+            .about("Keeps games in an SQLite database, so that large sets of games can be searched without parsing their PGN each time")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(
+                SubCommand::with_name("import")
+                    .about("Takes PGN from standard input and adds its games to the database, skipping games already in it")
+                    .args(&filter_args(config))
+                    .arg(
+                        Arg::with_name("DATABASE")
+                            .help("Database file, created if it doesn't exist")
+                            .required(true)
+                            .index(1),
+                    ),
+            )
+            .subcommand(
+                SubCommand::with_name("query")
+                    .about("Prints the games in the database meeting a condition as PGN")
+                    .arg(
+                        Arg::with_name("where")
+                            .help("SQL condition on the columns white, black, result, date, time, time_control, white_elo, black_elo, plies, and termination, such as \"white_elo > 2000 AND result = '1-0'\"")
+                            .long("where")
+                            .takes_value(true),
+                    )
+                    .arg(
+                        Arg::with_name("fen")
+                            .help("FEN of a position the games must reach")
+                            .long("fen")
+                            .takes_value(true),
+                    )
+                    .arg(
+                        Arg::with_name("count")
+                            .help("Prints the number of games instead of the games")
+                            .long("count"),
+                    )
+                    .arg(
+                        Arg::with_name("DATABASE")
+                            .help("Database file")
+                            .required(true)
+                            .index(1),
+                    ),
+            ),
+    )
+    .subcommand(
+        SubCommand::with_name("repl")
+            .about("Loads a PGN file once and answers commands about its games interactively")
+            .arg(min_plies_arg())
+            .arg(
+                Arg::with_name("FILE")
+                    .help("PGN file with the games to explore")
+                    .required(true)
+                    .index(1),
+            ),
+    )
+    .subcommand(
+        SubCommand::with_name("convert")
+            .about("Takes PGN from standard input and writes the games in another format")
+            .arg(
+                Arg::with_name("to")
+                    .help("Format to write the games in: PGN, lichess-style NDJSON, or chesshound's JSON")
+                    .long("to")
+                    .takes_value(true)
+                    .possible_values(&["pgn", "ndjson", "json"])
+                    .default_value("pgn"),
+            )
+            .arg(
+                Arg::with_name("headers")
+                    .help("Comma separated PGN headers to write")
+                    .long("headers")
+                    .takes_value(true)
+                    .use_delimiter(true)
+                    .possible_values(&PGN_HEADERS),
+            )
+            .arg(sort_arg())
+            .args(&filter_args(config)),
+    )
+    .subcommand(
+        SubCommand::with_name("export")
+            .about("Takes PGN from standard input and writes a table of the games, and optionally their moves, for data analysis tools such as Polars, pandas, or DuckDB")
+            .arg(
+                Arg::with_name("format")
+                    .help("File format to write the tables in")
+                    .long("format")
+                    .takes_value(true)
+                    .possible_values(&["parquet", "arrow"])
+                    .default_value("parquet"),
+            )
+            .arg(
+                Arg::with_name("output")
+                    .help("File to write the table of games to, with a row per game")
+                    .short("o")
+                    .long("output")
+                    .takes_value(true)
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("moves")
+                    .help("File to write a table of the moves to, with a row per move")
+                    .long("moves")
+                    .takes_value(true),
+            )
+            .arg(sort_arg())
+            .args(&filter_args(config)),
+    )
+    .subcommand(
+        SubCommand::with_name("dedupe")
+            .about("Takes PGN from standard input and writes it back without duplicate games, reporting what was removed")
+            .arg(
+                Arg::with_name("output")
+                    .help("File to write the games to instead of standard output")
+                    .short("o")
+                    .long("output")
+                    .takes_value(true),
+            )
+            .arg(preserve_arg()),
+    )
+    .subcommand(
+        SubCommand::with_name("split")
+            .about("Takes PGN from standard input and splits it into a PGN file for each month, opening, color, source, or group of games")
+            .arg(
+                Arg::with_name("by")
+                    .help("What to split the games by")
+                    .long("by")
+                    .takes_value(true)
+                    .possible_values(&["month", "opening", "color", "source", "count"])
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("games")
+                    .help("Number of games in each file when splitting by count")
+                    .short("n")
+                    .long("games")
+                    .takes_value(true)
+                    .required_if("by", "count"),
+            )
+            .arg(
+                Arg::with_name("player")
+                    .help("Player whose color to split by when splitting by color")
+                    .short("p")
+                    .long("player")
+                    .takes_value(true)
+                    .required_if("by", "color"),
+            )
+            .arg(
+                Arg::with_name("directory")
+                    .help("Directory to write the files to, which is created if needed")
+                    .short("d")
+                    .long("directory")
+                    .takes_value(true)
+                    .default_value("."),
+            )
+            .arg(preserve_arg()),
+    )
+    .subcommand(
+        SubCommand::with_name("merge")
+            .about("Merges PGN files into one, with the games in the order they were played, tagging each with where it came from")
+            .arg(
+                Arg::with_name("output")
+                    .help("File to write the games to instead of standard output")
+                    .short("o")
+                    .long("output")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("FILES")
+                    .help("PGN files to merge")
+                    .required(true)
+                    .index(1)
+                    .multiple(true),
+            ),
+    )
+    .subcommand(
+        SubCommand::with_name("compare")
+            .about("Compares the scores of two PGN files and whether the difference is significant")
+            .arg(
+                Arg::with_name("player")
+                    .help("Scores games from this player's perspective instead of white's")
+                    .short("p")
+                    .long("player")
+                    .takes_value(true),
+            )
+            .arg(format_arg(config))
+            .arg(min_plies_arg())
+            .arg(
+                Arg::with_name("BEFORE")
+                    .help("PGN file with the first set of games")
+                    .required(true)
+                    .index(1),
+            )
+            .arg(
+                Arg::with_name("AFTER")
+                    .help("PGN file with the second set of games")
+                    .required(true)
+                    .index(2),
+            ),
+    )
+    .subcommand(
+        SubCommand::with_name("opponents")
+            .about("Takes PGN from standard input and lists the opponents a player played most, with their score, colors, and when they last played each")
+            .arg(
+                Arg::with_name("limit")
+                    .help("Number of opponents to list")
+                    .short("n")
+                    .long("limit")
+                    .takes_value(true)
+                    .default_value("10"),
+            )
+            .arg(format_arg(config))
+            .args(&filter_args(config)),
+    )
+    .subcommand(
+        SubCommand::with_name("crosstable")
+            .about("Takes PGN of a tournament from standard input and prints its cross-table and standings, with Buchholz and Sonneborn-Berger tie-breaks")
+            .arg(format_arg(config))
+            .args(&filter_args(config)),
+    )
+    .subcommand(
+        SubCommand::with_name("ratings")
+            .about("Takes PGN from standard input and estimates Elo ratings for its players from their results against one another, as for a club league without official ratings")
+            .arg(
+                Arg::with_name("initial")
+                    .help("Rating every player starts at")
+                    .long("initial")
+                    .takes_value(true)
+                    .default_value("1500"),
+            )
+            .arg(
+                Arg::with_name("k-factor")
+                    .help("Most a rating can change by in one game")
+                    .short("k")
+                    .long("k-factor")
+                    .takes_value(true)
+                    .default_value("32"),
+            )
+            .arg(format_arg(config))
+            .args(&filter_args(config)),
+    )
+    .subcommand(
+        SubCommand::with_name("annotate")
+            .about("Takes PGN from standard input and writes it back annotated by a UCI engine, with evaluations, glyphs, and better moves")
+            .arg(engine_arg(config))
+            .arg(
+                Arg::with_name("depth")
+                    .help("Depth in plies to search each position to")
+                    .short("d")
+                    .long("depth")
+                    .takes_value(true)
+                    .default_value("12"),
+            )
+            .arg(
+                Arg::with_name("movetime")
+                    .help("Milliseconds to search each position for, instead of a fixed depth")
+                    .long("movetime")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("blunders-only")
+                    .help("Only annotates inaccuracies, mistakes, and blunders")
+                    .long("blunders-only"),
+            )
+            .arg(
+                Arg::with_name("output")
+                    .help("File to write the games to instead of standard output")
+                    .short("o")
+                    .long("output")
+                    .takes_value(true),
+            )
+            .args(&filter_args(config)),
+    )
+    .subcommand(
+        SubCommand::with_name("analyze")
+            .about("Takes PGN from standard input and analyzes the games with a UCI engine")
+            .arg(engine_arg(config))
+            .arg(
+                Arg::with_name("blunders")
+                    .help("Lists moves that lose at least the threshold in evaluation")
+                    .long("blunders"),
+            )
+            .arg(board_arg())
+            .arg(
+                Arg::with_name("acpl")
+                    .help("Shows the average centipawn loss of each player in each phase")
+                    .long("acpl"),
+            )
+            .arg(
+                Arg::with_name("annotate")
+                    .help("Writes the games back out as PGN annotated with the engine's evaluations")
+                    .long("annotate"),
+            )
+            .arg(
+                Arg::with_name("puzzles")
+                    .help("Exports positions where a tactic was missed as puzzles")
+                    .long("puzzles"),
+            )
+            .arg(
+                Arg::with_name("graph")
+                    .help("Exports the evaluation after each move of each game")
+                    .long("graph"),
+            )
+            .arg(
+                Arg::with_name("prep")
+                    .help("Lists the opening lines the player scores worse in than the engine expects")
+                    .long("prep")
+                    .requires("player"),
+            )
+            .arg(
+                Arg::with_name("flashcards")
+                    .help("Exports the player's blunders, and the opening lines they score worse in than the engine expects, as flashcards asking for the best move")
+                    .long("flashcards"),
+            )
+            .arg(
+                Arg::with_name("flashcard-format")
+                    .help("Format to export flashcards in, a text file Anki imports or CSV")
+                    .long("flashcard-format")
+                    .takes_value(true)
+                    .possible_values(&["anki", "csv"])
+                    .default_value("anki"),
+            )
+            .arg(
+                Arg::with_name("graph-format")
+                    .help("Format to export evaluation graphs in")
+                    .long("graph-format")
+                    .takes_value(true)
+                    .possible_values(&["json", "csv", "svg"])
+                    .default_value("json"),
+            )
+            .arg(
+                Arg::with_name("puzzle-format")
+                    .help("Format to export puzzles in")
+                    .long("puzzle-format")
+                    .takes_value(true)
+                    .possible_values(&["epd", "pgn"])
+                    .default_value("epd"),
+            )
+            .arg(
+                Arg::with_name("player")
+                    .help("Player whose missed tactics to export, opening lines to list, or positions to make flashcards of")
+                    .short("p")
+                    .long("player")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("line-length")
+                    .help("Number of plies that make up an opening line")
+                    .long("line-length")
+                    .takes_value(true)
+                    .default_value("8"),
+            )
+            .arg(
+                Arg::with_name("min-games")
+                    .help("Number of games a player needs in an opening line to list it")
+                    .long("min-games")
+                    .takes_value(true)
+                    .default_value("3"),
+            )
+            .arg(
+                Arg::with_name("depth")
+                    .help("Depth in plies to search each position to")
+                    .short("d")
+                    .long("depth")
+                    .takes_value(true)
+                    .default_value("12"),
+            )
+            .arg(
+                Arg::with_name("movetime")
+                    .help("Milliseconds to search each position for, instead of a fixed depth")
+                    .long("movetime")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("multipv")
+                    .help("Number of lines the engine searches, to list several better moves")
+                    .long("multipv")
+                    .takes_value(true)
+                    .default_value("1"),
+            )
+            .arg(
+                Arg::with_name("engines")
+                    .help("Number of engine instances to analyze games with at once, instead of one per thread")
+                    .short("j")
+                    .long("engines")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("max-time")
+                    .help("Stops analyzing new positions after this many seconds in total")
+                    .long("max-time")
+                    .takes_value(true),
+            )
+            .arg(with_default(
+                Arg::with_name("cache")
+                    .help("File to keep engine evaluations in between runs")
+                    .long("cache")
+                    .takes_value(true),
+                cache_default,
+            ))
+            .arg(
+                Arg::with_name("max-nodes")
+                    .help("Stops analyzing new positions after this many nodes in total")
+                    .long("max-nodes")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("threshold")
+                    .help("Centipawns a move must lose to count as a blunder or missed tactic, or an opening line must be down to need replacing")
+                    .long("threshold")
+                    .takes_value(true)
+                    .default_value("200"),
+            )
+            .group(
+                ArgGroup::with_name("mode")
+                    .args(&[
+                        "blunders",
+                        "acpl",
+                        "annotate",
+                        "puzzles",
+                        "graph",
+                        "prep",
+                        "flashcards",
+                    ])
+                    .required(true),
+            ),
+    )
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str, name: &str) -> io::Result<T> {
+    value.parse().map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid {}: {}", name, value),
+        )
+    })
+}
+
+/// Reads the next game from `reader`, skipping games that can't be analysed, such as unfinished
+/// games without a result.
+fn read_game<R: Read>(reader: &mut Reader<R>) -> io::Result<Option<Game<AlgebraicMove>>> {
+    while let Some(pgn_game) = reader.read_game(&mut GameParser::new())? {
+        if let Some(game) = convert_game(pgn_game) {
+            return Ok(Some(game));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Returns `pgn_game` as a game, or `None` after warning that it was skipped if it can't be
+/// analysed.
+fn convert_game(pgn_game: PGNGame) -> Option<Game<AlgebraicMove>> {
+    match Game::try_from(pgn_game) {
+        Ok(game) => Some(game),
+        Err(error) => {
+            warn!(%error, "Skipping game");
+            None
+        }
+    }
+}
+
+/// A game read by [`PgnGames`].
+enum PgnGame {
+    /// A game that could be read, along with its PGN as it was written if that's being kept.
+    Read(Box<Game<AlgebraicMove>>, Option<Vec<u8>>),
+    /// A game that couldn't be read, such as an unfinished game without a result, along with its
+    /// PGN as it was written if that's being kept, so that it can still be written unchanged.
+    Unread(Option<Vec<u8>>),
+}
+
+/// Games read from PGN, for subcommands that write games back as PGN.
+enum PgnGames<R: Read> {
+    /// Games written back as chesshound writes them.
+    Parsed(Reader<R>),
+    /// Games written back exactly as they were read, as `--preserve` asks.
+    Preserved(RawGameReader<io::BufReader<R>>),
+}
+
+impl<R: Read> PgnGames<R> {
+    /// Creates a reader of the games in `pgn`, which keeps the PGN of each game as it was written
+    /// if `preserve` is set.
+    fn new(pgn: R, preserve: bool) -> PgnGames<R> {
+        if preserve {
+            PgnGames::Preserved(RawGameReader::new(io::BufReader::new(pgn)))
+        } else {
+            PgnGames::Parsed(Reader::new(pgn))
+        }
+    }
+
+    /// Returns the next game, along with its PGN as it was written if it's being kept. Games that
+    /// can't be analysed are given as [`PgnGame::Unread`].
+    fn read_game(&mut self) -> io::Result<Option<PgnGame>> {
+        match self {
+            PgnGames::Parsed(reader) => {
+                Ok(reader.read_game(&mut GameParser::new())?.map(|pgn_game| {
+                    match convert_game(pgn_game) {
+                        Some(game) => PgnGame::Read(Box::new(game), None),
+                        None => PgnGame::Unread(None),
+                    }
+                }))
+            }
+            PgnGames::Preserved(reader) => match reader.read_raw_game()? {
+                // Games that can't be read are still written, so there's nothing to warn about.
+                Some(pgn) => Ok(Some(
+                    match Reader::new(&pgn[..])
+                        .read_game(&mut GameParser::new())?
+                        .map(Game::try_from)
+                    {
+                        Some(Ok(game)) => PgnGame::Read(Box::new(game), Some(pgn)),
+                        _ => PgnGame::Unread(Some(pgn)),
+                    },
+                )),
+                None => Ok(None),
+            },
+        }
+    }
+}
+
+/// Returns the failure reported after writing games back as PGN when `unread` games couldn't be
+/// read, so that they aren't lost without notice.
+fn unread_games_error(unread: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Left out {} games that couldn't be read", unread),
+    )
+}
+
+/// Writes `game` to `output` as PGN, after a blank line unless it's the `first` game written, or
+/// its PGN as it was read unchanged if that was kept. Games that couldn't be read are only
+/// written if their PGN was kept.
+fn write_pgn_game<W: io::Write>(output: &mut W, game: &PgnGame, first: bool) -> io::Result<()> {
+    match game {
+        PgnGame::Read(_, Some(pgn)) | PgnGame::Unread(Some(pgn)) => output.write_all(pgn),
+        PgnGame::Read(game, None) => {
+            if !first {
+                writeln!(output)?;
+            }
+
+            write!(output, "{}", export::to_pgn(&**game, &PGN_HEADERS))
+        }
+        PgnGame::Unread(None) => Ok(()),
+    }
+}
+
+fn read_games<R: Read>(reader: &mut Reader<R>) -> io::Result<Vec<Game<AlgebraicMove>>> {
+    let mut games = Vec::new();
+
+    let mut aborted = 0;
+
+    while let Some(game) = read_game(reader)? {
+        if is_aborted(&game) {
+            aborted += 1;
+        } else {
+            games.push(game);
+        }
+
+        if interruption().is_cancelled() {
+            warn_interrupted(games.len() + aborted);
+            break;
+        }
+    }
+
+    if aborted > 0 {
+        info!(aborted, "Left out aborted games");
+    }
+
+    Ok(games)
+}
+
+/// Warns that reading games was interrupted, so only the `read` games read so far are included.
+fn warn_interrupted(read: usize) {
+    warn!(
+        read,
+        "Interrupted, so only the games read so far are included"
+    );
+}
+
+/// How subcommands write their output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    /// Text meant for people to read.
+    Text,
+    /// A JSON object meant for scripts, whose fields stay the same between runs.
+    Json,
+    /// A table of comma separated values, for spreadsheets.
+    Csv,
+    /// A table of tab separated values, for spreadsheets.
+    Tsv,
+}
+
+impl Format {
+    /// Returns the format chosen with `--format` in `matches`.
+    fn from_matches(matches: &clap::ArgMatches) -> Format {
+        match matches.value_of("format") {
+            Some("json") => Format::Json,
+            Some("csv") => Format::Csv,
+            Some("tsv") => Format::Tsv,
+            _ => Format::Text,
+        }
+    }
+
+    /// Returns the character separating the fields of a table, or `None` if the format isn't a
+    /// table.
+    fn separator(self) -> Option<char> {
+        match self {
+            Format::Csv => Some(','),
+            Format::Tsv => Some('\t'),
+            Format::Text | Format::Json => None,
+        }
+    }
+}
+
+/// The values `--format` takes.
+const FORMATS: [&str; 4] = ["text", "json", "csv", "tsv"];
+
+/// The values `--time-control` takes.
+const SPEEDS: [&str; 4] = ["bullet", "blitz", "rapid", "classical"];
+
+/// Creates the `--format` argument shared by subcommands, defaulting to the format in `config`.
+fn format_arg<'a, 'b>(config: &'a Config) -> Arg<'a, 'b> {
+    Arg::with_name("format")
+        .help("Format to write the output in")
+        .long("format")
+        .takes_value(true)
+        .possible_values(&FORMATS)
+        .default_value(config.format().unwrap_or("text"))
+}
+
+/// Creates the `--engine` argument of subcommands that use an engine, which is only required when
+/// `config` doesn't name one.
+fn engine_arg<'a, 'b>(config: &'a Config) -> Arg<'a, 'b> {
+    with_default(
+        Arg::with_name("engine")
+            .help("Path to the UCI engine executable, such as stockfish")
+            .short("e")
+            .long("engine")
+            .takes_value(true)
+            .required(config.engine().is_none()),
+        config.engine(),
+    )
+}
+
+/// Returns `arg` with `value` as its default value, if there is one.
+fn with_default<'a, 'b>(arg: Arg<'a, 'b>, value: Option<&'a str>) -> Arg<'a, 'b> {
+    match value {
+        Some(value) => arg.default_value(value),
+        None => arg,
+    }
+}
+
+/// Loads the config file given with `--config` in `args`, or the one in the default location if
+/// there is one, with the `CHESSHOUND_` environment variables applied on top.
+fn load_config(args: &[String]) -> io::Result<Config> {
+    let given = args.iter().enumerate().find_map(|(index, arg)| {
+        if arg == "--config" {
+            args.get(index + 1).cloned()
+        } else {
+            arg.strip_prefix("--config=").map(String::from)
+        }
+    });
+
+    let config = match (given, Config::default_path()) {
+        (Some(path), _) => Config::load(Path::new(&path), true)?,
+        (None, Some(path)) => Config::load(&path, false)?,
+        (None, None) => Config::default(),
+    };
+
+    let config =
+        config.with_env(env::vars_os().filter_map(|(name, value)| {
+            Some((name.into_string().ok()?, value.into_string().ok()?))
+        }));
+
+    let invalid = |name: &str, value: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid {} in config: {}", name, value),
+        )
+    };
+
+    if let Some(format) = config.format().filter(|format| !FORMATS.contains(format)) {
+        return Err(invalid("format", format));
+    }
+
+    if let Some(speed) = config
+        .time_control()
+        .filter(|speed| !SPEEDS.contains(speed))
+    {
+        return Err(invalid("time_control", speed));
+    }
+
+    Ok(config)
+}
+
+/// Creates the `--board` argument of subcommands that can draw the positions they report on. The
+/// style is optional and must follow an equals sign, so that `--board` can come before moves.
+fn board_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("board")
+        .help("Draws positions as boards with letters, chess symbols, or chess symbols on colored squares, given as --board=STYLE [default: unicode]")
+        .long("board")
+        .value_name("STYLE")
+        .takes_value(true)
+        .min_values(0)
+        .require_equals(true)
+        .possible_values(&["ascii", "unicode", "color"])
+}
+
+/// Returns the style chosen with `--board` in `matches`, which is unicode if none is given, or
+/// `None` if boards aren't drawn.
+fn board_style(matches: &clap::ArgMatches) -> Option<BoardStyle> {
+    if !matches.is_present("board") {
+        return None;
+    }
+
+    match matches.value_of("board") {
+        Some("ascii") => Some(BoardStyle::Ascii),
+        Some("color") => Some(BoardStyle::Color),
+        _ => Some(BoardStyle::Unicode),
+    }
+}
+
+/// Returns `text` with every line indented by `indent`.
+fn indent_lines(text: &str, indent: &str) -> String {
+    text.lines()
+        .map(|line| format!("{}{}\n", indent, line))
+        .collect()
+}
+
+/// The formats `convert` can write games in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GameFormat {
+    /// PGN, separated by blank lines.
+    Pgn,
+    /// A lichess-style JSON object on each line.
+    Ndjson,
+    /// A JSON array of games in chesshound's schema.
+    Json,
+}
+
+/// Creates the argument that chooses the order subcommands write games in.
+fn sort_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("sort-by")
+        .help("Order to write the games in: by date, by average rating, by number of moves, or as given. Games that are equal keep their order")
+        .long("sort-by")
+        .takes_value(true)
+        .possible_values(&["date", "rating", "length", "source"])
+        .default_value("source")
+}
+
+/// Creates the argument that has subcommands writing games back as PGN write them as they were
+/// read, for using chesshound in PGN pipelines without losing what other tools wrote.
+fn preserve_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("preserve")
+        .help("Writes each game byte for byte as it was read, keeping comments, NAGs, variations, unknown tags, and formatting")
+        .long("preserve")
+}
+
+/// Returns the order chosen by the argument from `sort_arg` in `matches`.
+fn sort_key(matches: &clap::ArgMatches) -> SortKey {
+    match matches.value_of("sort-by") {
+        Some("date") => SortKey::Date,
+        Some("rating") => SortKey::Rating,
+        Some("length") => SortKey::Length,
+        _ => SortKey::Source,
+    }
+}
+
+/// Creates the arguments that choose which games subcommands look at, defaulting to the player and
+/// time control in `config`.
+fn filter_args<'a, 'b>(config: &'a Config) -> Vec<Arg<'a, 'b>> {
+    let mut args = vec![with_default(
+        Arg::with_name("player")
+            .help("Only includes games this player played in")
+            .short("p")
+            .long("player")
+            .takes_value(true),
+        config.player(),
+    )];
+
+    args.extend(player_filter_args(config));
+    args
+}
+
+/// Creates the arguments from `filter_args` other than `--player`, for subcommands that take the
+/// player another way.
+fn player_filter_args<'a, 'b>(config: &'a Config) -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("color")
+            .help("Only includes games the player played with this color")
+            .long("color")
+            .takes_value(true)
+            .possible_values(&["white", "black"])
+            .requires("player"),
+        Arg::with_name("result")
+            .help("Only includes games the player got this result in")
+            .long("result")
+            .takes_value(true)
+            .possible_values(&["win", "draw", "loss"])
+            .requires("player"),
+        Arg::with_name("min-elo")
+            .help("Only includes games where the player, or both players without --player, were rated at least this")
+            .long("min-elo")
+            .takes_value(true),
+        Arg::with_name("max-elo")
+            .help("Only includes games where the player, or both players without --player, were rated at most this")
+            .long("max-elo")
+            .takes_value(true),
+        Arg::with_name("min-opponent-elo")
+            .help("Only includes games where the player's opponent was rated at least this")
+            .long("min-opponent-elo")
+            .takes_value(true)
+            .requires("player"),
+        Arg::with_name("max-opponent-elo")
+            .help("Only includes games where the player's opponent was rated at most this")
+            .long("max-opponent-elo")
+            .takes_value(true)
+            .requires("player"),
+        with_default(
+            Arg::with_name("time-control")
+                .help("Only includes games played at this speed")
+                .long("time-control")
+                .takes_value(true)
+                .possible_values(&SPEEDS),
+            config.time_control(),
+        ),
+        Arg::with_name("tc")
+            .help("Only includes games played with this exact time control, such as 180+2")
+            .long("tc")
+            .takes_value(true),
+        Arg::with_name("since")
+            .help("Only includes games played on or after this year, month, or day, such as 2021-06")
+            .long("since")
+            .takes_value(true),
+        Arg::with_name("until")
+            .help("Only includes games played on or before this year, month, or day, such as 2021-06")
+            .long("until")
+            .takes_value(true),
+        Arg::with_name("eco")
+            .help("Only includes games in openings whose ECO code starts with this, such as B9")
+            .long("eco")
+            .takes_value(true),
+        Arg::with_name("opening")
+            .help("Only includes games in openings whose name contains this, such as Najdorf")
+            .long("opening")
+            .takes_value(true),
+        min_plies_arg(),
+        Arg::with_name("exclude-aborted")
+            .help("Leaves games shorter than --min-plies out altogether, as if they weren't given")
+            .long("exclude-aborted"),
+        Arg::with_name("source")
+            .help("Only includes games from this source, as tagged by merge or found from their links")
+            .long("source")
+            .takes_value(true)
+            .possible_values(&["lichess", "chess.com", "local"]),
+        Arg::with_name("where")
+            .help("Only includes games meeting this condition, such as \"color=white and elo>2000 and eco~B9*\", where color, result, and elo are for the player")
+            .long("where")
+            .takes_value(true),
+    ]
+}
+
+/// Creates the arguments that choose a random sample of the games subcommands look at.
+fn sample_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("sample")
+            .help("Only looks at a random sample of this many of the games, to estimate statistics quickly")
+            .long("sample")
+            .takes_value(true),
+        Arg::with_name("sample-rate")
+            .help("Only looks at each game with this probability, such as 0.1, to estimate statistics quickly")
+            .long("sample-rate")
+            .takes_value(true)
+            .conflicts_with("sample"),
+        Arg::with_name("seed")
+            .help("Seed for choosing the random sample, to choose the same games again")
+            .long("seed")
+            .takes_value(true),
+    ]
+}
+
+/// A sampler of the games subcommands look at.
+type GameSampler = Sampler<Game<AlgebraicMove>>;
+
+/// Returns the sampler chosen by the arguments from `sample_args` in `matches`, or `None` if
+/// every game is looked at. Without a seed, samples are seeded from the current time.
+fn game_sampler(matches: &clap::ArgMatches) -> io::Result<Option<GameSampler>> {
+    let size = match (matches.value_of("sample"), matches.value_of("sample-rate")) {
+        (Some(count), _) => SampleSize::Count(parse_number(count, "sample")?),
+        (None, Some(rate)) => {
+            let rate: f64 = parse_number(rate, "sample-rate")?;
+
+            if !(0. ..=1.).contains(&rate) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Invalid sample-rate: {}", rate),
+                ));
+            }
+
+            SampleSize::Rate(rate)
+        }
+        (None, None) => return Ok(None),
+    };
+
+    let seed = match matches.value_of("seed") {
+        Some(seed) => parse_number(seed, "seed")?,
+        None => SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_nanos() as u64),
+    };
+
+    Ok(Some(Sampler::new(size, seed)))
+}
+
+/// Returns the filter chosen by the arguments from `filter_args` in `matches`.
+fn game_filter(matches: &clap::ArgMatches) -> io::Result<GameFilter<Game<AlgebraicMove>>> {
+    let mut filter = GameFilter::all();
+
+    if let Some(player) = matches.value_of("player") {
+        filter = filter.and(match matches.value_of("color") {
+            Some("white") => GameFilter::color(player, Color::White),
+            Some("black") => GameFilter::color(player, Color::Black),
+            _ => GameFilter::player(player),
+        });
+
+        let result = match matches.value_of("result") {
+            Some("win") => Some(PlayerResult::Won),
+            Some("draw") => Some(PlayerResult::Drew),
+            Some("loss") => Some(PlayerResult::Lost),
+            _ => None,
+        };
+
+        if let Some(result) = result {
+            filter = filter.and(GameFilter::result(player, result));
+        }
+    }
+
+    let elo = |name: &str| -> io::Result<Option<u16>> {
+        matches
+            .value_of(name)
+            .map(|value| parse_number(value, name))
+            .transpose()
+    };
+
+    let (min_elo, max_elo) = (elo("min-elo")?, elo("max-elo")?);
+
+    if min_elo.is_some() || max_elo.is_some() {
+        filter = filter.and(match matches.value_of("player") {
+            Some(player) => GameFilter::player_rating(player, min_elo, max_elo),
+            None => GameFilter::ratings(min_elo, max_elo),
+        });
+    }
+
+    let (min_opponent_elo, max_opponent_elo) = (elo("min-opponent-elo")?, elo("max-opponent-elo")?);
+
+    if let Some(player) = matches.value_of("player") {
+        if min_opponent_elo.is_some() || max_opponent_elo.is_some() {
+            filter = filter.and(GameFilter::opponent_rating(
+                player,
+                min_opponent_elo,
+                max_opponent_elo,
+            ));
+        }
+    }
+
+    if let Some(speed) = matches.value_of("time-control") {
+        filter = filter.and(GameFilter::speed(parse_number(speed, "time-control")?));
+    }
+
+    if let Some(time_control) = matches.value_of("tc") {
+        filter = filter.and(GameFilter::time_control(parse_number(time_control, "tc")?));
+    }
+
+    let date = |name: &str| -> io::Result<Option<(NaiveDate, NaiveDate)>> {
+        matches
+            .value_of(name)
+            .map(|value| {
+                parse_date_range(value).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("Invalid {}: {}", name, value),
+                    )
+                })
+            })
+            .transpose()
+    };
+
+    let since = date("since")?.map(|(first, _)| first);
+    let until = date("until")?.map(|(_, last)| last);
+
+    if since.is_some() || until.is_some() {
+        filter = filter.and(GameFilter::dates(since, until));
+    }
+
+    if let Some(eco) = matches.value_of("eco") {
+        filter = filter.and(GameFilter::eco(eco));
+    }
+
+    if let Some(opening) = matches.value_of("opening") {
+        filter = filter.and(GameFilter::opening(opening));
+    }
+
+    if matches.is_present("exclude-aborted") {
+        filter = filter.and(GameFilter::min_plies(parse_number(
+            matches.value_of("min-plies").unwrap(),
+            "min-plies",
+        )?));
+    }
+
+    if let Some(source) = matches.value_of("source") {
+        filter = filter.and(GameFilter::source(parse_number(source, "source")?));
+    }
+
+    if let Some(expression) = matches.value_of("where") {
+        filter = filter.and(GameFilter::parse(expression, matches.value_of("player"))?);
+    }
+
+    Ok(filter)
+}
+
+/// The number of games read from PGN before they're converted and filtered on several threads.
+const READ_BATCH_SIZE: usize = 4096;
+
+/// How many centipawns up by its final evaluation a player must be for the report to count a
+/// game they lost on time as lost from a winning position.
+const WINNING_EVAL: u32 = 300;
+
+/// How many games a batch read, how many it left out as aborted, and the games it kept.
+type GameBatch = (usize, usize, Vec<Game<AlgebraicMove>>);
+
+/// Reads up to [`READ_BATCH_SIZE`] games from `reader`, returning how many were read, how many of
+/// those `filter` includes were left out as aborted, and the rest it includes, in order, or `None`
+/// if there are no games left. Games are read one at a time
+/// but converted and filtered in parallel, since filters such as openings replay every game.
+fn read_game_batch<R: Read>(
+    reader: &mut Reader<R>,
+    filter: &GameFilter<Game<AlgebraicMove>>,
+) -> io::Result<Option<GameBatch>> {
+    let mut pgn_games = Vec::new();
+
+    while pgn_games.len() < READ_BATCH_SIZE && !interruption().is_cancelled() {
+        match reader.read_game(&mut GameParser::new())? {
+            Some(pgn_game) => pgn_games.push(pgn_game),
+            None => break,
+        }
+    }
+
+    if pgn_games.is_empty() {
+        return Ok(None);
+    }
+
+    let read = pgn_games.len();
+    let (aborted, games): (Vec<_>, Vec<_>) = pgn_games
+        .into_par_iter()
+        .filter_map(convert_game)
+        .filter(|game| filter.matches(game))
+        .partition(is_aborted);
+
+    Ok(Some((read, aborted.len(), games)))
+}
+
+/// Returns a reader of the games piped to standard input as PGN, decompressing them and
+/// converting them from NDJSON first when needed.
+fn stdin_games() -> io::Result<Box<dyn Read>> {
+    input::decode(io::stdin().lock())
+}
+
+/// Returns a reader of the games in the file at `path` as PGN, decompressing and converting them
+/// like `stdin_games`. The file is memory-mapped rather than read into buffers, so that the
+/// operating system pages in multi-gigabyte databases as they're parsed and can drop the pages
+/// again once they have been.
+fn file_games(path: &str) -> io::Result<Box<dyn Read>> {
+    let file = File::open(path)?;
+
+    // Empty files can't be mapped on every platform, and have no games anyway.
+    if file.metadata()?.len() == 0 {
+        return Ok(Box::new(io::empty()));
+    }
+
+    // SAFETY: the mapping is only ever read. Changing the file while it's being read may give
+    // garbled games, as it would when reading it through buffers, but the commands reading files
+    // this way don't expect their input to change under them, unlike `watch`.
+    let mapping = unsafe { Mmap::map(&file)? };
+
+    #[cfg(unix)]
+    mapping.advise(memmap2::Advice::Sequential)?;
+
+    input::decode_bytes(mapping)
+}
+
+/// Returns the games read from `reader` that `filter` includes, leaving out aborted ones, or a
+/// random sample of them taken by `sampler`, if given.
+fn read_filtered_games<R: Read>(
+    reader: &mut Reader<R>,
+    filter: &GameFilter<Game<AlgebraicMove>>,
+    mut sampler: Option<GameSampler>,
+) -> io::Result<Vec<Game<AlgebraicMove>>> {
+    let start = Instant::now();
+    let mut games = Vec::new();
+    let mut read = 0;
+    let mut aborted = 0;
+
+    while let Some((batch_read, batch_aborted, batch)) = read_game_batch(reader, filter)? {
+        read += batch_read;
+        aborted += batch_aborted;
+        debug!(read, "Reading games");
+
+        match &mut sampler {
+            Some(sampler) => batch.into_iter().for_each(|game| sampler.offer(game)),
+            None => games.extend(batch),
+        }
+
+        if interruption().is_cancelled() {
+            warn_interrupted(read);
+            break;
+        }
+    }
+
+    let games = sampler.map_or(games, Sampler::into_sample);
+
+    info!(
+        read,
+        aborted,
+        kept = games.len(),
+        elapsed = ?start.elapsed(),
+        "Read games"
+    );
+
+    Ok(games)
+}
+
+/// Writes a table of the games in `pgn` that `filter` includes to the file at `output` in
+/// `format`, along with a table of their moves to the file at `moves` when given.
+fn run_export<R: Read>(
+    pgn: R,
+    filter: &GameFilter<Game<AlgebraicMove>>,
+    format: TableFormat,
+    output: &Path,
+    moves: Option<&Path>,
+    sort_by: SortKey,
+) -> io::Result<()> {
+    let games = GameCollection::from(read_filtered_games(&mut Reader::new(pgn), filter, None)?)
+        .sort_by(sort_by)
+        .into_vec();
+
+    columnar::write_table(
+        &columnar::games_table(&games),
+        format,
+        File::create(output)?,
+    )?;
+
+    if let Some(moves) = moves {
+        columnar::write_table(&columnar::moves_table(&games), format, File::create(moves)?)?;
+    }
+
+    info!(games = games.len(), "Exported games");
+
+    Ok(())
+}
+
+/// Writes the games in `pgn` that `filter` includes to `output` in `format`, ordered by `sort_by`.
+/// Games kept in the order given are written one at a time so that large files don't need to fit
+/// in memory. `headers` chooses the headers written to PGN.
+fn run_convert<R: Read, W: io::Write>(
+    pgn: R,
+    output: &mut W,
+    filter: &GameFilter<Game<AlgebraicMove>>,
+    format: GameFormat,
+    headers: &[&str],
+    sort_by: SortKey,
+) -> io::Result<()> {
+    let mut reader = Reader::new(pgn);
+    let mut written = 0;
+
+    if format == GameFormat::Json {
+        write!(output, "[")?;
+    }
+
+    let mut write_game = |game: &Game<AlgebraicMove>| -> io::Result<()> {
+        match format {
+            GameFormat::Pgn if written > 0 => {
+                write!(output, "\n{}", export::to_pgn(game, headers))?
+            }
+            GameFormat::Pgn => write!(output, "{}", export::to_pgn(game, headers))?,
+            GameFormat::Ndjson => writeln!(output, "{}", export::to_lichess_json(game).to_json())?,
+            GameFormat::Json if written > 0 => {
+                write!(output, ",\n{}", export::to_json(game).to_json())?
+            }
+            GameFormat::Json => write!(output, "{}", export::to_json(game).to_json())?,
+        }
+
+        written += 1;
+
+        Ok(())
+    };
+
+    if sort_by == SortKey::Source {
+        while let Some(game) = read_game(&mut reader)? {
+            if filter.matches(&game) {
+                write_game(&game)?;
+            }
+        }
+    } else {
+        let games = GameCollection::from(read_filtered_games(&mut reader, filter, None)?);
+
+        for game in games.sort_by(sort_by).iter() {
+            write_game(game)?;
+        }
+    }
+
+    if format == GameFormat::Json {
+        writeln!(output, "]")?;
+    }
+
+    output.flush()
+}
+
+/// Writes the games in `pgn` that `filter` includes to `output` annotated by `engine` searching
+/// within `limit`, one game at a time so each is written as soon as it's analyzed.
+fn run_annotate<R: Read, W: Write, ER: io::BufRead, EW: Write>(
+    pgn: R,
+    output: &mut W,
+    engine: &mut Engine<ER, EW>,
+    filter: &GameFilter<Game<AlgebraicMove>>,
+    limit: Limit,
+    blunders_only: bool,
+) -> io::Result<()> {
+    let mut reader = Reader::new(pgn);
+    let mut written = 0;
+
+    while let Some(game) = read_game(&mut reader)? {
+        if !filter.matches(&game) {
+            continue;
+        }
+
+        if written > 0 {
+            writeln!(output)?;
+        }
+
+        write!(
+            output,
+            "{}",
+            annotate_game(engine, &game, limit, blunders_only)?
+        )?;
+        output.flush()?;
+        written += 1;
+    }
+
+    Ok(())
+}
+
+/// What `split` puts games into separate files by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SplitBy {
+    /// The month the game was played in, such as `2021-06.pgn`.
+    Month,
+    /// The ECO code of the game's opening, such as `B90.pgn`.
+    Opening,
+    /// The color the given player played, as `white.pgn` or `black.pgn`.
+    Color(String),
+    /// Where the game came from, such as `lichess.pgn` or `local.pgn`.
+    Source,
+    /// Groups of the given number of games, in the order they were read, as `1.pgn`, `2.pgn`,
+    /// and so on.
+    Count(usize),
+}
+
+/// Returns the name of the file `split_by` puts `game` in without its extension, given that
+/// `index` games were read before it. Games the name can't be found for, including games that
+/// couldn't be read, go in `unknown`.
+fn split_file_name(split_by: &SplitBy, game: Option<&Game<AlgebraicMove>>, index: usize) -> String {
+    let name = match split_by {
+        SplitBy::Month => game
+            .and_then(GiveDate::date)
+            .map(|date| date.format("%Y-%m").to_string()),
+        SplitBy::Opening => game
+            .and_then(|game| Openings::embedded().classify(game))
+            .map(|opening| String::from(opening.eco())),
+        SplitBy::Color(player) => {
+            game.and_then(|game| game.color_of(player))
+                .map(|color| match color {
+                    Color::White => String::from("white"),
+                    Color::Black => String::from("black"),
+                })
+        }
+        SplitBy::Source => game
+            .and_then(GiveSource::source)
+            .map(|source| source.to_string()),
+        SplitBy::Count(games) => Some((index / games.max(&1) + 1).to_string()),
+    };
+
+    name.unwrap_or_else(|| String::from("unknown"))
+}
+
+/// Writes the games in `pgn` to PGN files in `directory` chosen by `split_by`, and returns how
+/// many games went to each file, along with how many games were left out because they couldn't
+/// be read. Files that already exist are replaced. With `preserve`, games are written exactly as
+/// they were read, including games that can't be read, which are written to `unknown.pgn` unless
+/// splitting by count.
+fn run_split<R: Read>(
+    pgn: R,
+    split_by: &SplitBy,
+    directory: &Path,
+    preserve: bool,
+) -> io::Result<(BTreeMap<String, usize>, usize)> {
+    std::fs::create_dir_all(directory)?;
+
+    let mut games = PgnGames::new(pgn, preserve);
+    let mut files = HashMap::new();
+    let mut written = BTreeMap::new();
+    let mut unread = 0;
+    let mut index = 0;
+
+    while let Some(game) = games.read_game()? {
+        let read = match &game {
+            PgnGame::Read(game, _) => Some(&**game),
+            PgnGame::Unread(Some(_)) => None,
+            PgnGame::Unread(None) => {
+                unread += 1;
+                continue;
+            }
+        };
+
+        let name = format!("{}.pgn", split_file_name(split_by, read, index));
+        index += 1;
+
+        let file = match files.entry(name.clone()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                entry.insert(io::BufWriter::new(File::create(directory.join(&name))?))
+            }
+        };
+
+        let count = written.entry(name).or_insert(0);
+
+        write_pgn_game(file, &game, *count == 0)?;
+        *count += 1;
+    }
+
+    for file in files.values_mut() {
+        file.flush()?;
+    }
+
+    Ok((written, unread))
+}
+
+/// Writes the games in each of `pgn_files`, given with their names, to `output` as a single PGN,
+/// ordered by when they were played, and returns how many games came from each source, along with
+/// how many games were left out because they couldn't be read. Games without a date come last,
+/// and games played at the same time keep the order they were read in.
+///
+/// Each game is tagged with where it came from, so that the games of each source can still be
+/// told apart. Games already tagged keep their tags, games with a lichess or chess.com link are
+/// tagged with that site and their ID there, and other games are tagged as local, identified by
+/// their file and their number in it, as in `otb.pgn#3`.
+fn run_merge<R: Read, W: io::Write>(
+    pgn_files: Vec<(&str, R)>,
+    output: &mut W,
+) -> io::Result<(BTreeMap<Source, usize>, usize)> {
+    let mut games = Vec::new();
+    let mut merged = BTreeMap::new();
+    let mut unread = 0;
+
+    for (name, pgn) in pgn_files {
+        let mut reader = Reader::new(pgn);
+        let mut number = 0;
+
+        while let Some(pgn_game) = reader.read_game(&mut GameParser::new())? {
+            let mut game = match convert_game(pgn_game) {
+                Some(game) => game,
+                None => {
+                    unread += 1;
+                    continue;
+                }
+            };
+
+            number += 1;
+
+            match (game.source(), game.source_id()) {
+                (Some(source), Some(source_id)) => game.set_source(source, &source_id),
+                _ => game.set_source(Source::Local, &format!("{}#{}", name, number)),
+            }
+
+            *merged.entry(game.source().unwrap()).or_insert(0) += 1;
+            games.push(game);
+        }
+    }
+
+    games.sort_by_key(|game| (game.date().is_none(), game.date(), game.time()));
+
+    for (index, game) in games.iter().enumerate() {
+        if index > 0 {
+            writeln!(output)?;
+        }
+
+        write!(output, "{}", export::to_pgn(game, &PGN_HEADERS))?;
+    }
+
+    output.flush()?;
+
+    Ok((merged, unread))
+}
+
+/// Writes the games in `pgn` to `output` as PGN, leaving out games already written, and returns
+/// how many duplicates were left out for each reason, along with how many games were left out
+/// because they couldn't be read. With `preserve`, games are written exactly as they were read,
+/// and games that can't be read are kept too, found to be duplicates only if written the same.
+fn run_dedupe<R: Read, W: io::Write>(
+    pgn: R,
+    output: &mut W,
+    preserve: bool,
+) -> io::Result<(BTreeMap<DuplicateReason, usize>, usize)> {
+    let mut games = PgnGames::new(pgn, preserve);
+    let mut deduplicator = Deduplicator::new();
+    let mut removed = BTreeMap::new();
+    let mut unread = 0;
+    let mut written = 0;
+
+    while let Some(game) = games.read_game()? {
+        let duplicate = match &game {
+            PgnGame::Read(game, _) => deduplicator.check(&**game),
+            PgnGame::Unread(Some(pgn)) => deduplicator.check_pgn(pgn),
+            PgnGame::Unread(None) => {
+                unread += 1;
+                continue;
+            }
+        };
+
+        if let Some(reason) = duplicate {
+            *removed.entry(reason).or_insert(0) += 1;
+            continue;
+        }
+
+        write_pgn_game(output, &game, written == 0)?;
+        written += 1;
+    }
+
+    output.flush()?;
+
+    Ok((removed, unread))
+}
+
+/// Returns the report `request` asks for on the games in `pgn`, sampled by `sampler` if given.
+fn run_stats<R: Read>(
+    pgn: R,
+    request: &AnalysisRequest,
+    sampler: Option<GameSampler>,
+    format: Format,
+) -> io::Result<String> {
+    let mut reader = Reader::new(pgn);
+
+    // Without the games themselves to look at, they can be counted as they are read instead of
+    // being kept around in a move tree, so memory use doesn't grow with the number of games.
+    let report = if !request.needs_games() && sampler.is_none() {
+        let mut result_rates = ResultRates::new();
+        let mut short_games = 0;
+        let mut read = 0;
+
+        while let Some((batch_read, _, games)) = read_game_batch(&mut reader, request.filter())? {
+            let (games, short): (Vec<_>, Vec<_>) =
+                games.into_iter().partition(|game| !request.is_short(game));
+
+            result_rates.merge(compute_parallel(&games, &ResultRates::new(), 256));
+            short_games += short.len();
+            read += batch_read;
+
+            if interruption().is_cancelled() {
+                warn_interrupted(read);
+                break;
+            }
+        }
+
+        request.summarize(result_rates, short_games)
+    } else {
+        request.analyze(read_filtered_games(&mut reader, request.filter(), sampler)?)?
+    };
+
+    Ok(match format {
+        Format::Text => report.to_string(),
+        _ => match format.separator() {
+            Some(separator) => report.to_table(separator),
+            None => report.to_value().to_json(),
+        },
+    })
+}
+
+/// Returns `moves` in SAN as a line with move numbers, such as "1. e4 e5 2. Nf3".
+fn format_line(moves: impl Iterator<Item = String>) -> String {
+    moves
+        .enumerate()
+        .map(|(ply, move_)| match ply % 2 {
+            0 => format!("{}. {}", ply / 2 + 1, move_),
+            _ => move_,
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Returns the move played at `ply` in SAN with its move number, such as "2. Nf3" or "2... Nc6".
+fn format_move(ply: usize, move_: &str) -> String {
+    match ply % 2 {
+        0 => format!("{}. {}", ply / 2 + 1, move_),
+        _ => format!("{}... {}", ply / 2 + 1, move_),
+    }
+}
+
+/// A move in the tree printed by the `tree` subcommand, with the moves played after it.
+struct TreeNode {
+    move_: String,
+    ply: usize,
+    games: usize,
+    white_score: Option<f64>,
+    opening: Option<&'static Opening>,
+    children: Vec<TreeNode>,
+}
+
+/// Returns the opening reached by `line`, which holds moves in SAN, or `None` if it doesn't reach
+/// one or isn't valid SAN.
+fn classify_line(line: &[String]) -> Option<&'static Opening> {
+    Openings::embedded().classify_moves(parse_line(line).ok()?)
+}
+
+/// Returns the moves played after `view`, which is reached by `line`, down to `depth` more plies,
+/// leaving out moves played in fewer than `min_games` games. The most played moves come first.
+fn tree_nodes(
+    view: &MoveTreeView<AlgebraicMove, Game<AlgebraicMove>>,
+    line: &[String],
+    depth: usize,
+    min_games: usize,
+) -> Vec<TreeNode> {
+    if depth == 0 {
+        return Vec::new();
+    }
+
+    let mut nodes = view
+        .children()
+        .into_iter()
+        .map(|(move_, child)| (move_.clone().to_algebraic(), child.iter().count(), child))
+        .filter(|(_, games, _)| *games >= min_games)
+        .map(|(move_, games, child)| {
+            let mut child_line = line.to_vec();
+            child_line.push(move_.clone());
+
+            TreeNode {
+                move_,
+                ply: line.len(),
+                games,
+                white_score: white_score(&child),
+                opening: classify_line(&child_line),
+                children: tree_nodes(&child, &child_line, depth - 1, min_games),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    nodes.sort_by(|a, b| b.games.cmp(&a.games).then_with(|| a.move_.cmp(&b.move_)));
+    nodes
+}
+
+/// Returns the name of `opening` to show after a move in the tree, which is left out if the move
+/// didn't change the opening from `previous`.
+fn opening_label(opening: Option<&Opening>, previous: Option<&Opening>) -> String {
+    match opening {
+        Some(opening) if Some(opening) != previous => format!(" {}", opening),
+        _ => String::new(),
+    }
+}
+
+/// Adds a line for each of `nodes` and their children to `output`, drawing the branches of the
+/// tree after `prefix`. The opening is shown wherever it differs from `parent_opening`.
+fn draw_tree(
+    nodes: &[TreeNode],
+    prefix: &str,
+    parent_opening: Option<&Opening>,
+    output: &mut String,
+) {
+    for (index, node) in nodes.iter().enumerate() {
+        let last = index + 1 == nodes.len();
+
+        *output += &format!(
+            "{}{} {} ({} games, white scores {:.1}%){}\n",
+            prefix,
+            if last { "└──" } else { "├──" },
+            format_move(node.ply, &node.move_),
+            node.games,
+            node.white_score.unwrap_or(0.) * 100.,
+            opening_label(node.opening, parent_opening)
+        );
+
+        let child_prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+
+        draw_tree(&node.children, &child_prefix, node.opening, output);
+    }
+}
+
+/// Adds a row for each of `nodes` and their children to `rows`, where `line` holds the moves
+/// leading up to them.
+fn tree_rows(nodes: &[TreeNode], line: &[String], rows: &mut Vec<Value>) {
+    for node in nodes {
+        let mut node_line = line.to_vec();
+        node_line.push(node.move_.clone());
+
+        rows.push(Value::map(vec![
+            ("line", format_line(node_line.iter().cloned()).into()),
+            ("move", node.move_.as_str().into()),
+            ("ply", (node.ply + 1).into()),
+            ("games", node.games.into()),
+            ("white_score", node.white_score.into()),
+            ("eco", node.opening.map(Opening::eco).into()),
+            ("opening", node.opening.map(Opening::name).into()),
+        ]));
+
+        tree_rows(&node.children, &node_line, rows);
+    }
+}
+
+/// Returns `nodes` and their children as JSON values.
+fn tree_values(nodes: &[TreeNode]) -> Value {
+    Value::List(
+        nodes
+            .iter()
+            .map(|node| {
+                Value::map(vec![
+                    ("move", node.move_.as_str().into()),
+                    ("ply", (node.ply + 1).into()),
+                    ("games", node.games.into()),
+                    ("white_score", node.white_score.into()),
+                    ("eco", node.opening.map(Opening::eco).into()),
+                    ("opening", node.opening.map(Opening::name).into()),
+                    ("children", tree_values(&node.children)),
+                ])
+            })
+            .collect(),
+    )
+}
+
+/// Returns the tree of moves played after `moves` in the games of `pgn` that `filter` includes,
+/// down to `depth` plies, leaving out moves played in fewer than `min_games` games. With
+/// `explorer`, only the next moves are given, as the lichess opening explorer API gives them.
+#[allow(clippy::too_many_arguments)]
+fn run_tree<R: Read>(
+    pgn: R,
+    filter: &GameFilter<Game<AlgebraicMove>>,
+    sampler: Option<GameSampler>,
+    moves: Vec<String>,
+    by_position: bool,
+    depth: usize,
+    min_games: usize,
+    format: Format,
+    explorer: bool,
+) -> io::Result<String> {
+    let line = parse_line(&moves)?;
+    let mut games = read_filtered_games(&mut Reader::new(pgn), filter, sampler)?;
+
+    if by_position {
+        games = transpose_to_line(games, &moves)?;
+    }
+
+    let move_tree = MoveTree::new(games);
+    let mut view = move_tree.view();
+
+    for move_ in &line {
+        view = view.with_next(move_);
+    }
+
+    if explorer {
+        return Ok(export::to_explorer_json(&view, &line, min_games).to_json());
+    }
+
+    let games = view.iter().count();
+    let opening = classify_line(&moves);
+    let nodes = tree_nodes(&view, &moves, depth, min_games);
+
+    if let Some(separator) = format.separator() {
+        let mut rows = Vec::new();
+        tree_rows(&nodes, &moves, &mut rows);
+
+        return Ok(Value::List(rows).to_table(separator));
+    }
+
+    if format == Format::Json {
+        let value = Value::map(vec![
+            (
+                "filters",
+                Value::map(vec![
+                    ("games", filter.to_string().into()),
+                    ("moves", moves.into()),
+                    ("depth", depth.into()),
+                    ("min_games", min_games.into()),
+                ]),
+            ),
+            ("games", games.into()),
+            ("white_score", white_score(&view).into()),
+            ("eco", opening.map(Opening::eco).into()),
+            ("opening", opening.map(Opening::name).into()),
+            ("children", tree_values(&nodes)),
+        ]);
+
+        return Ok(value.to_json());
+    }
+
+    let mut output = format!(
+        "{} ({} games, white scores {:.1}%){}\n",
+        if moves.is_empty() {
+            String::from("Starting position")
+        } else {
+            format_line(moves.into_iter())
+        },
+        games,
+        white_score(&view).unwrap_or(0.) * 100.,
+        opening_label(opening, None)
+    );
+
+    draw_tree(&nodes, "", opening, &mut output);
+
+    Ok(output)
+}
+
+/// Returns the positions after `moves` in the games of `pgn` that `filter` includes, chosen by
+/// `ranking`, as EPD records.
+#[allow(clippy::too_many_arguments)]
+fn run_positions<R: Read>(
+    pgn: R,
+    filter: &GameFilter<Game<AlgebraicMove>>,
+    moves: Vec<String>,
+    depth: usize,
+    min_games: usize,
+    player: Option<&str>,
+    ranking: Ranking,
+    limit: usize,
+) -> io::Result<String> {
+    let line = parse_line(&moves)?;
+    let games = read_filtered_games(&mut Reader::new(pgn), filter, None)?;
+    let move_tree = MoveTree::new(games);
+    let mut view = move_tree.view();
+
+    for move_ in &line {
+        view = view.with_next(move_);
+    }
+
+    let positions = key_positions(&view, &line, depth, min_games, player, ranking, limit);
+
+    Ok(positions
+        .iter()
+        .map(KeyPosition::to_epd)
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// Returns the `limit` most common traps `player` sprang or fell into in the games in `pgn` that
+/// `filter` includes and that lasted at most `max_moves` moves. Text output draws each trap's
+/// position in `board`, if given.
+#[allow(clippy::too_many_arguments)]
+fn run_traps<R: Read>(
+    pgn: R,
+    filter: &GameFilter<Game<AlgebraicMove>>,
+    player: &str,
+    max_moves: usize,
+    limit: usize,
+    format: Format,
+    board: Option<BoardStyle>,
+) -> io::Result<String> {
+    let games = read_filtered_games(&mut Reader::new(pgn), filter, None)?;
+    let mut traps = traps(&mut games.iter(), player, max_moves);
+    traps.truncate(limit);
+
+    let line = |trap: &Trap| format_line(trap.line().iter().cloned());
+
+    if format != Format::Text {
+        let values = traps
+            .iter()
+            .map(|trap| {
+                Value::map(vec![
+                    ("line", line(trap).into()),
+                    ("fen", trap.fen().into()),
+                    ("reply", trap.reply().into()),
+                    ("sprung", trap.sprung().into()),
+                    ("fallen_into", trap.fallen_into().into()),
+                ])
+            })
+            .collect();
+
+        return Ok(match format.separator() {
+            Some(separator) => Value::List(values).to_table(separator),
+            None => Value::List(values).to_json(),
+        });
+    }
+
+    let mut output = String::new();
+
+    for trap in &traps {
+        let reply = match trap.reply() {
+            Some(reply) => format!(", answered by {}", format_move(trap.line().len(), reply)),
+            None => String::new(),
+        };
+
+        output += &format!(
+            "{}{}: sprung {}, fallen into {}\n",
+            line(trap),
+            reply,
+            trap.sprung(),
+            trap.fallen_into()
+        );
+
+        if let Some(style) = board {
+            output += &indent_lines(&trap.position().render(style), "  ");
+        }
+    }
+
+    Ok(output)
+}
+
+/// Returns the first `ply` moves of the game numbered `number` in `pgn`, counting from 1, or all of
+/// its moves if `ply` is `None`.
+fn game_moves<R: Read>(pgn: R, number: usize, ply: Option<usize>) -> io::Result<Vec<String>> {
+    let mut reader = Reader::new(pgn);
+    let mut game = None;
+
+    for _ in 0..number {
+        game = read_game(&mut reader)?;
+
+        if game.is_none() {
+            break;
+        }
+    }
+
+    let moves = match game {
+        Some(game) => game
+            .list_moves()
+            .map(Move::to_algebraic)
+            .collect::<Vec<_>>(),
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("There is no game {}", number),
+            ))
+        }
+    };
+
+    match ply {
+        Some(ply) if ply > moves.len() => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Game {} only has {} plies", number, moves.len()),
+        )),
+        Some(ply) => Ok(moves[..ply].to_vec()),
+        None => Ok(moves),
+    }
+}
+
+/// Returns the FEN of the position reached by `moves`, followed by the position drawn in `board`,
+/// if given.
+fn run_fen(moves: &[String], board: Option<BoardStyle>) -> io::Result<String> {
+    let positions = position::replay(parse_line(moves)?);
+    let position = positions.last().unwrap();
+
+    if let Some(illegal) = moves.get(positions.len() - 1) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Illegal move: {}",
+                format_move(positions.len() - 1, illegal)
+            ),
+        ));
+    }
+
+    let mut output = format!("{}\n", position::fen(position));
+
+    if let Some(style) = board {
+        output += &format!("\n{}", position.render(style));
+    }
+
+    Ok(output)
+}
+
+/// Returns the games in `pgn` that `filter` includes and that reach the position given by `fen`,
+/// along with how the games continued from it. Games that reach the position more than once are
+/// only counted at the first time. With `color_reversed`, games reaching the position with the
+/// colors reversed are included, with their continuations and results reversed to match. Text
+/// output starts with the position drawn in `board`, if given.
+#[allow(clippy::too_many_arguments)]
+fn run_search<R: Read>(
+    pgn: R,
+    filter: &GameFilter<Game<AlgebraicMove>>,
+    sampler: Option<GameSampler>,
+    fen: &str,
+    color_reversed: bool,
+    format: Format,
+    board: Option<BoardStyle>,
+) -> io::Result<String> {
+    let setup = position::parse_setup(fen)?;
+    let games = read_filtered_games(&mut Reader::new(pgn), filter, sampler)?;
+    let index = PositionIndex::new(&games);
+
+    let mut found = Vec::new();
+    let mut continuations: HashMap<Option<String>, ResultRates> = HashMap::new();
+
+    for occurrence in index.find_with(&setup, color_reversed) {
+        let game = &games[occurrence.game()];
+        let moves = game
+            .list_moves()
+            .map(Move::to_algebraic)
+            .collect::<Vec<_>>();
+        let next = moves.get(occurrence.ply()).cloned();
+
+        if occurrence.reversed() {
+            continuations
+                .entry(next.as_deref().map(reversed_san))
+                .or_default()
+                .accumulate(&game.result().reversed());
+        } else {
+            continuations
+                .entry(next.clone())
+                .or_default()
+                .accumulate(game);
+        }
+
+        found.push((game, occurrence, moves, next));
+    }
+
+    // The most played continuations first, with the end of the game after every move.
+    let mut continuations = continuations.into_iter().collect::<Vec<_>>();
+    continuations.sort_by(|(move_a, a), (move_b, b)| {
+        b.games()
+            .cmp(&a.games())
+            .then_with(|| move_a.is_none().cmp(&move_b.is_none()))
+            .then_with(|| move_a.cmp(move_b))
+    });
+
+    let score = |result_rates: &ResultRates| {
+        result_rates
+            .rates()
+            .map(|(white_wins, _, draws)| white_wins + draws / 2.)
+    };
+
+    let continuation_values = continuations
+        .iter()
+        .map(|(move_, result_rates)| {
+            Value::map(vec![
+                ("move", move_.clone().into()),
+                ("games", result_rates.games().into()),
+                ("white_score", score(result_rates).into()),
+            ])
+        })
+        .collect::<Vec<_>>();
+
+    if let Some(separator) = format.separator() {
+        return Ok(Value::List(continuation_values).to_table(separator));
+    }
+
+    if format == Format::Json {
+        let value = Value::map(vec![
+            (
+                "filters",
+                Value::map(vec![
+                    ("games", filter.to_string().into()),
+                    ("fen", fen.into()),
+                ]),
+            ),
+            (
+                "games",
+                Value::List(
+                    found
+                        .iter()
+                        .map(|(game, occurrence, _, next)| {
+                            Value::map(vec![
+                                ("white", game.white_player().into()),
+                                ("black", game.black_player().into()),
+                                ("result", game.result().to_string().into()),
+                                ("date", game.date().map(|date| date.to_string()).into()),
+                                ("ply", occurrence.ply().into()),
+                                (
+                                    "colors",
+                                    match occurrence.reversed() {
+                                        true => "reversed",
+                                        false => "same",
+                                    }
+                                    .into(),
+                                ),
+                                ("next", next.clone().into()),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            ("continuations", Value::List(continuation_values)),
+        ]);
+
+        return Ok(value.to_json());
+    }
+
+    let mut output = match board {
+        Some(style) => format!("{}\n", setup.render(style)),
+        None => String::new(),
+    };
+
+    output += &format!("{} games reached the position\n", found.len());
+
+    for (game, occurrence, moves, next) in &found {
+        let ply = occurrence.ply();
+        let date = match game.date() {
+            Some(date) => date.format("%Y.%m.%d").to_string(),
+            None => String::from("????.??.??"),
+        };
+
+        let mut reached = match ply.checked_sub(1) {
+            Some(last) => format!("after {}", format_move(last, &moves[last])),
+            None => String::from("at the start"),
+        };
+
+        if occurrence.reversed() {
+            reached += " with the colors reversed";
+        }
+
+        let continued = match next {
+            Some(next) => format!("continued {}", format_move(ply, next)),
+            None => String::from("game ended"),
+        };
+
+        output += &format!(
+            "{} - {}, {}, {}: reached {}, {}\n",
+            game.white_player().unwrap_or("?"),
+            game.black_player().unwrap_or("?"),
+            game.result(),
+            date,
+            reached,
+            continued
+        );
+    }
+
+    if !continuations.is_empty() {
+        output += "\nContinuations:\n";
+    }
+
+    for (move_, result_rates) in &continuations {
+        output += &format!(
+            "{}: {} games, white scores {:.1}%\n",
+            move_.as_deref().unwrap_or("(game ended)"),
+            result_rates.games(),
+            score(result_rates).unwrap_or(0.) * 100.
+        );
+    }
+
+    Ok(output)
+}
+
+/// Returns `san` as the move would be written with the colors reversed, with the board flipped
+/// top to bottom.
+fn reversed_san(san: &str) -> String {
+    san.chars()
+        .map(|character| match character {
+            '1'..='8' => char::from(b'1' + b'8' - character as u8),
+            character => character,
+        })
+        .collect()
+}
+
+/// The commands understood by the `repl` subcommand.
+const REPL_HELP: &str = "\
+Commands:
+  next MOVES...       Plays moves from the current position
+  back [N]            Takes back the last move, or the last N moves
+  stats               Shows results of the games reaching the current position
+  branches            Shows the moves played from the current position, most played first
+  examples            Shows the highest rated and most recent games reaching the current position
+  filter KEY=VALUE... Only looks at games matching filters, such as player=alice color=black,
+                      with keys named after the filter options of stats
+  filter clear        Looks at every game again
+  filter              Shows the current filter
+  help                Shows this message
+  quit                Ends the session";
+
+/// The number of games of each kind the `examples` command of `repl` shows.
+const REPL_EXAMPLE_GAMES: usize = 3;
+
+/// Returns a line describing `game` by its players, date, and result, with its link if known.
+fn describe_example(game: &Game<AlgebraicMove>) -> String {
+    let player = |name: &str, rating: Option<u16>| match rating {
+        Some(rating) => format!("{} ({})", name, rating),
+        None => String::from(name),
+    };
+    let mut description = format!(
+        "{} - {}, {}, {}",
+        player(game.white_player().unwrap_or("?"), game.white_rating()),
+        player(game.black_player().unwrap_or("?"), game.black_rating()),
+        game.date()
+            .map_or_else(|| String::from("unknown date"), |date| date.to_string()),
+        game.result()
+    );
+
+    if let Some(link) = game.link() {
+        description += &format!(", {}", link);
+    }
+
+    description
+}
+
+/// Returns a filter built from `arguments` of the form `key=value`, where the keys are the long
+/// names of the options made by `filter_args`.
+fn parse_repl_filter(arguments: &[&str]) -> io::Result<GameFilter<Game<AlgebraicMove>>> {
+    let mut options = vec![String::from("filter")];
+
+    for argument in arguments {
+        match argument.split_once('=') {
+            Some((key, value)) => {
+                options.push(format!("--{}", key));
+                options.push(String::from(value));
+            }
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Expected KEY=VALUE, found {}", argument),
+                ))
+            }
+        }
+    }
+
+    // Filters in the REPL are always explicit, so `filter clear` looks at every game.
+    let config = Config::default();
+    let matches = App::new("filter")
+        .args(&filter_args(&config))
+        .get_matches_from_safe(options)
+        .map_err(|error| {
+            // The usage clap adds is about command-line options, so it's left out.
+            let message = error
+                .message
+                .lines()
+                .take_while(|line| !line.starts_with("USAGE:"))
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                message.trim_start_matches("error: "),
+            )
+        })?;
+
+    game_filter(&matches)
+}
+
+/// Returns the view of `move_tree` at the position reached by `line`, along with a description of
+/// the position.
+fn walk_line<'a, 'b>(
+    move_tree: &'a MoveTree<AlgebraicMove, &'b Game<AlgebraicMove>>,
+    line: &[AlgebraicMove],
+) -> (
+    String,
+    MoveTreeView<'a, AlgebraicMove, &'b Game<AlgebraicMove>>,
+) {
+    let mut view = move_tree.view();
+
+    for move_ in line {
+        view = view.with_next(move_);
+    }
+
+    let position = if line.is_empty() {
+        String::from("Starting position")
+    } else {
+        format_line(line.iter().cloned().map(Move::to_algebraic))
+    };
+
+    (position, view)
+}
+
+/// Runs a session of the `repl` subcommand on `games`, reading commands from `input` and writing
+/// the answers to `output` until `quit` or the end of input. Mistakes in commands are reported
+/// without ending the session.
+fn run_repl<R: io::BufRead, W: Write>(
+    games: Vec<Game<AlgebraicMove>>,
+    input: R,
+    output: &mut W,
+) -> io::Result<()> {
+    let store = GameStore::new(games);
+    let mut filter = GameFilter::all();
+    let mut move_tree = store.move_tree(&store.ids());
+    let mut line: Vec<AlgebraicMove> = Vec::new();
+
+    writeln!(
+        output,
+        "Loaded {} games. Type help for commands.",
+        store.len()
+    )?;
+    write!(output, "> ")?;
+    output.flush()?;
+
+    for command in input.lines() {
+        let command = command?;
+        let words = command.split_whitespace().collect::<Vec<_>>();
+        let (position, view) = walk_line(&move_tree, &line);
+
+        // Commands that move through the tree or change the filter answer with `None`, and are
+        // followed by the number of games left at the new position.
+        let answer = match words[..] {
+            [] => {
+                write!(output, "> ")?;
+                output.flush()?;
+                continue;
+            }
+            ["quit"] | ["exit"] => break,
+            ["help"] => Some(String::from(REPL_HELP)),
+            ["next", ref moves @ ..] if !moves.is_empty() => {
+                let moves = moves
+                    .iter()
+                    .map(|move_| String::from(*move_))
+                    .collect::<Vec<_>>();
+
+                match parse_line(&moves) {
+                    Ok(moves) => {
+                        line.extend(moves);
+                        None
+                    }
+                    Err(error) => Some(error.to_string()),
+                }
+            }
+            ["back"] => {
+                line.pop();
+                None
+            }
+            ["back", count] => match count.parse::<usize>() {
+                Ok(count) => {
+                    line.truncate(line.len().saturating_sub(count));
+                    None
+                }
+                Err(_) => Some(format!("Invalid number of moves: {}", count)),
+            },
+            ["stats"] => {
+                let mut result_rates = ResultRates::new();
+
+                for game in view.iter() {
+                    result_rates.accumulate(game);
+                }
+
+                Some(format!("{}\n{}", position, describe_results(&result_rates)))
+            }
+            ["branches"] => {
+                let mut branches = branch_sharpness(&view, 1);
+                branches.sort_by(|(move_a, a), (move_b, b)| {
+                    b.games().cmp(&a.games()).then_with(|| {
+                        move_a
+                            .clone()
+                            .to_algebraic()
+                            .cmp(&move_b.clone().to_algebraic())
+                    })
+                });
+
+                let branches = branches
+                    .into_iter()
+                    .map(|(move_, sharpness)| {
+                        format!("{} ({} games)", move_.to_algebraic(), sharpness.games())
+                    })
+                    .collect::<Vec<_>>();
+
+                if branches.is_empty() {
+                    Some(format!("{}\nNo moves", position))
+                } else {
+                    Some(format!("{}\nMoves: {}", position, branches.join(", ")))
+                }
+            }
+            ["examples"] => {
+                let examples = view.examples(REPL_EXAMPLE_GAMES);
+                let list = |games: &[&&Game<AlgebraicMove>]| {
+                    games
+                        .iter()
+                        .map(|game| format!("\n  {}", describe_example(game)))
+                        .collect::<String>()
+                };
+
+                if examples.most_recent().is_empty() {
+                    Some(format!("{}\nNo games", position))
+                } else {
+                    Some(format!(
+                        "{}\nHighest rated:{}\nMost recent:{}",
+                        position,
+                        list(examples.highest_rated()),
+                        list(examples.most_recent())
+                    ))
+                }
+            }
+            ["filter"] => Some(format!("Filter: {}", filter)),
+            ["filter", "clear"] => {
+                filter = GameFilter::all();
+                move_tree = store.move_tree(&store.ids());
+                None
+            }
+            ["filter", ref arguments @ ..] => match parse_repl_filter(arguments) {
+                Ok(new_filter) => {
+                    filter = new_filter;
+                    move_tree = store.move_tree(&store.filter(&store.ids(), &filter));
+                    None
+                }
+                Err(error) => Some(error.to_string()),
+            },
+            _ => Some(format!(
+                "Unknown command: {}. Type help for commands.",
+                command.trim()
+            )),
+        };
+
+        let answer = answer.unwrap_or_else(|| {
+            let (position, view) = walk_line(&move_tree, &line);
+
+            format!("{}: {} games", position, view.iter().count())
+        });
+
+        writeln!(output, "{}", answer)?;
+        write!(output, "> ")?;
+        output.flush()?;
+    }
+
+    writeln!(output)
+}
+
+/// Returns `score` as the number of games and results in it, with the percentage scored.
+fn describe_score(score: Score) -> String {
+    format!(
+        "{} games, {} wins, {} draws, {} losses, scoring {:.1}%",
+        score.games(),
+        score.wins(),
+        score.draws(),
+        score.losses(),
+        score.rate().unwrap_or(0.) * 100.
+    )
+}
+
+/// Writes the games in the database at `path` that meet `condition` and reach the position given
+/// by `fen` to `output` as PGN, or only their number if `count` is set.
+fn run_db_query<W: Write>(
+    path: &Path,
+    condition: Option<&str>,
+    fen: Option<&str>,
+    count: bool,
+    output: &mut W,
+) -> io::Result<()> {
+    // Opening a database creates it, which a typo in the path shouldn't do.
+    if !path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No database at {}", path.display()),
+        ));
+    }
+
+    let setup = fen.map(position::parse_setup).transpose()?;
+    let games = Database::open(path)?.query(condition, setup.as_ref())?;
+
+    if count {
+        writeln!(output, "{}", games.len())?;
+    } else {
+        write!(output, "{}", games.join("\n"))?;
+    }
+
+    Ok(())
+}
+
+/// Returns the part of `pgn` made of complete games, leaving out a last game that is still being
+/// written and doesn't end with its result yet.
+fn complete_games(pgn: &str) -> &str {
+    let ended = ["1-0", "0-1", "1/2-1/2", "*"]
+        .iter()
+        .any(|result| pgn.trim_end().ends_with(result));
+
+    if ended {
+        return pgn;
+    }
+
+    match pgn.rfind("\n\n[") {
+        Some(end) => &pgn[..end + 1],
+        None => "",
+    }
+}
+
+/// Checks the PGN file at `path` for new games every `interval`, writing `player`'s score over
+/// the games `filter` includes to `output` in `format` whenever games are added, along with the
+/// new games. Runs until reading the file fails. A file that shrinks is taken to have been
+/// replaced, and its games are counted again.
+fn run_watch<W: Write>(
+    path: &Path,
+    filter: &GameFilter<Game<AlgebraicMove>>,
+    player: &str,
+    interval: Duration,
+    format: Format,
+    output: &mut W,
+) -> io::Result<()> {
+    if format.separator().is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Scores can only be watched as text or JSON",
+        ));
+    }
+
+    let mut seen = None;
+
+    loop {
+        let pgn = fs::read_to_string(path)?;
+        let games = read_filtered_games(
+            &mut Reader::new(complete_games(&pgn).as_bytes()),
+            filter,
+            None,
+        )?
+        .into_iter()
+        .filter(|game| game.color_of(player).is_some())
+        .collect::<Vec<_>>();
+
+        // Games already in the file when watching starts aren't listed as new.
+        let new_games = match seen {
+            Some(seen) if seen <= games.len() => &games[seen..],
+            Some(_) => &games[..],
+            None => &[],
+        };
+
+        if seen.is_none() || !new_games.is_empty() {
+            let mut score = Score::new();
+
+            for game in &games {
+                score.add(game.result().for_color(game.color_of(player).unwrap()));
+            }
+
+            if format == Format::Json {
+                let value = Value::map(vec![
+                    ("player", player.into()),
+                    (
+                        "new_games",
+                        Value::List(
+                            new_games
+                                .iter()
+                                .map(|game| {
+                                    Value::map(vec![
+                                        ("white", game.white_player().into()),
+                                        ("black", game.black_player().into()),
+                                        ("result", game.result().to_string().into()),
+                                    ])
+                                })
+                                .collect(),
+                        ),
+                    ),
+                    ("score", score.into()),
+                ]);
+
+                writeln!(output, "{}", value.to_json())?;
+            } else {
+                for game in new_games {
+                    writeln!(
+                        output,
+                        "New game: {} - {}, {}",
+                        game.white_player().unwrap_or("?"),
+                        game.black_player().unwrap_or("?"),
+                        game.result()
+                    )?;
+                }
+
+                writeln!(output, "{}: {}", player, describe_score(score))?;
+            }
+
+            output.flush()?;
+        }
+
+        seen = Some(games.len());
+        thread::sleep(interval);
+    }
+}
+
+/// Returns `date` as a number of days, for plotting dates on charts.
+fn chart_day(date: NaiveDate) -> f64 {
+    date.num_days_from_ce() as f64
+}
+
+/// Returns an SVG image charting `player`'s score in each month of `by_month` and their rating
+/// at each speed over `games`.
+fn report_charts(
+    games: &[Game<AlgebraicMove>],
+    player: &str,
+    by_month: &BTreeMap<NaiveDate, Score>,
+) -> String {
+    let date_label = |x: f64| match NaiveDate::from_num_days_from_ce_opt(x.round() as i32) {
+        Some(date) => date.format("%Y-%m").to_string(),
+        None => String::new(),
+    };
+
+    let score = LineChart::new(&format!("{}'s score by month", player))
+        .with_series(
+            "score",
+            by_month
+                .iter()
+                .filter_map(|(month, score)| Some((chart_day(*month), score.rate()?)))
+                .collect(),
+        )
+        .with_y_range(0., 1.)
+        .with_x_format(date_label)
+        .with_y_format(|y| format!("{:.0}%", y * 100.));
+
+    let mut ratings = LineChart::new(&format!("{}'s rating", player)).with_x_format(date_label);
+
+    for (speed, history) in rating_history(&mut games.iter(), player) {
+        let name = speed.map_or_else(|| String::from("unknown"), |speed| speed.to_string());
+
+        ratings = ratings.with_series(
+            &name,
+            history
+                .iter()
+                .map(|point| (chart_day(point.date()), point.rating() as f64))
+                .collect(),
+        );
+    }
+
+    chart::stack_svg(&[score, ratings], 640, 320)
+}
+
+/// Returns a report on `player`'s games in `pgn` that `filter` includes, with their scores overall,
+/// with each color, in each opening, for each way games ended, at each speed, and in each month,
+/// along with their streaks and their biggest upsets. At most `limit` openings and upsets of each
+/// kind are listed. Charts of the player's monthly score and ratings are drawn to `chart`, if
+/// given.
+fn run_report<R: Read>(
+    pgn: R,
+    filter: &GameFilter<Game<AlgebraicMove>>,
+    player: &str,
+    limit: usize,
+    format: Format,
+    chart: Option<&Path>,
+    cache: &mut GameCache,
+) -> io::Result<String> {
+    if format.separator().is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "The report can only be written as text or JSON",
+        ));
+    }
+
+    let games = read_filtered_games(&mut Reader::new(pgn), filter, None)?
+        .into_iter()
+        .filter(|game| game.color_of(player).is_some())
+        .collect::<Vec<_>>();
+
+    let mut overall = Score::new();
+    let mut by_color = [Score::new(), Score::new()];
+    let mut by_opening: HashMap<Option<(&str, &str)>, Score> = HashMap::new();
+    let mut by_speed: BTreeMap<Option<Speed>, Score> = BTreeMap::new();
+    let mut by_month: BTreeMap<NaiveDate, Score> = BTreeMap::new();
+
+    let cached = games
+        .iter()
+        .map(|game| cache.get_or_insert(game).clone())
+        .collect::<Vec<_>>();
+
+    for (game, cached) in games.iter().zip(&cached) {
+        let color = game.color_of(player).unwrap();
+        let result = game.result().for_color(color);
+        let opening = cached.eco().zip(cached.opening_name());
+
+        overall.add(result);
+        by_color[(color == Color::Black) as usize].add(result);
+        by_opening.entry(opening).or_default().add(result);
+        by_speed
+            .entry(game.time_control().map(|time_control| time_control.speed()))
+            .or_default()
+            .add(result);
+
+        if let Some(date) = game.date() {
+            by_month
+                .entry(date.with_day(1).unwrap())
+                .or_default()
+                .add(result);
+        }
+    }
+
+    let mut by_opening = by_opening.into_iter().collect::<Vec<_>>();
+    by_opening.sort_by(|(opening_a, a), (opening_b, b)| {
+        b.games()
+            .cmp(&a.games())
+            .then_with(|| opening_a.is_none().cmp(&opening_b.is_none()))
+            .then_with(|| opening_a.cmp(opening_b))
+    });
+    by_opening.truncate(limit);
+
+    // Games with an unknown time control last.
+    let (known_speeds, unknown_speed): (Vec<_>, Vec<_>) =
+        by_speed.into_iter().partition(|(speed, _)| speed.is_some());
+    let by_speed = known_speeds
+        .into_iter()
+        .chain(unknown_speed)
+        .collect::<Vec<_>>();
+
+    if let Some(path) = chart {
+        fs::write(path, report_charts(&games, player, &by_month))?;
+    }
+
+    let mut structures = pawn_structures(&mut games.iter(), player);
+    structures.truncate(limit);
+
+    let terminations = terminations(&mut games.iter(), player);
+    let time_forfeits = time_forfeits(&mut games.iter(), player, WINNING_EVAL)
+        .into_iter()
+        .filter(|forfeits| forfeits.lost_on_time() > 0 || forfeits.won_on_time() > 0)
+        .collect::<Vec<_>>();
+    let streaks = streaks(&mut games.iter(), player);
+    let upsets = upsets(&mut games.iter(), player, 0, limit);
+
+    let speed_name = |speed: &Option<Speed>| match speed {
+        Some(speed) => speed.to_string(),
+        None => String::from("unknown"),
+    };
+
+    let opening_name = |opening: &Option<(&str, &str)>| match opening {
+        Some((eco, name)) => format!("{} {}", eco, name),
+        None => String::from("Unknown opening"),
+    };
+
+    // Whose pawns make up a structure, the player's or their opponent's.
+    let structure_side = |structure: &StructureScore| match structure.own() {
+        true => "own",
+        false => "opponent",
+    };
+
+    let current = streaks.current().map(|(result, games)| {
+        let result = match result {
+            PlayerResult::Won => "win",
+            PlayerResult::Drew => "draw",
+            PlayerResult::Lost => "loss",
+        };
+
+        (result, games)
+    });
+
+    // The opponent's name and rating, the rating gap, and the date of an upset.
+    let upset_details = |upset: &Upset<Game<AlgebraicMove>>| {
+        let game = upset.game();
+        let color = game.color_of(player).unwrap();
+
+        (
+            String::from(game.player_of(!color).unwrap_or("?")),
+            game.rating_of(!color),
+            upset.rating_gap(),
+            game.date().map(|date| date.format("%Y.%m.%d").to_string()),
+        )
+    };
+
+    if format == Format::Json {
+        let upset_values = |upsets: &[Upset<Game<AlgebraicMove>>]| {
+            Value::List(
+                upsets
+                    .iter()
+                    .map(|upset| {
+                        let (opponent, rating, gap, date) = upset_details(upset);
+
+                        Value::map(vec![
+                            ("opponent", opponent.into()),
+                            ("opponent_rating", rating.map(usize::from).into()),
+                            ("rating_gap", usize::from(gap).into()),
+                            ("date", date.into()),
+                        ])
+                    })
+                    .collect(),
+            )
+        };
+
+        let value = Value::map(vec![
+            ("player", player.into()),
+            ("filters", filter.to_string().into()),
+            (
+                "score",
+                Value::map(vec![
+                    ("overall", overall.into()),
+                    ("white", by_color[0].into()),
+                    ("black", by_color[1].into()),
+                ]),
+            ),
+            (
+                "openings",
+                Value::List(
+                    by_opening
+                        .iter()
+                        .map(|(opening, score)| {
+                            Value::map(vec![
+                                ("eco", opening.map(|(eco, _)| eco).into()),
+                                ("name", opening.map(|(_, name)| name).into()),
+                                ("score", (*score).into()),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "pawn_structures",
+                Value::List(
+                    structures
+                        .iter()
+                        .map(|structure| {
+                            Value::map(vec![
+                                ("structure", structure.structure().to_string().into()),
+                                ("side", structure_side(structure).into()),
+                                ("score", structure.score().into()),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "terminations",
+                Value::List(
+                    terminations
+                        .iter()
+                        .map(|(termination, score)| {
+                            Value::map(vec![
+                                ("termination", termination.to_string().into()),
+                                ("score", (*score).into()),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "time_controls",
+                Value::List(
+                    by_speed
+                        .iter()
+                        .map(|(speed, score)| {
+                            Value::map(vec![
+                                ("speed", speed.map(|speed| speed.to_string()).into()),
+                                ("score", (*score).into()),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "time_forfeits",
+                Value::List(
+                    time_forfeits
+                        .iter()
+                        .map(|forfeits| {
+                            Value::map(vec![
+                                (
+                                    "time_control",
+                                    forfeits
+                                        .time_control()
+                                        .map(|time_control| time_control.to_string())
+                                        .into(),
+                                ),
+                                ("games", forfeits.games().into()),
+                                ("lost_on_time", forfeits.lost_on_time().into()),
+                                (
+                                    "lost_on_time_winning",
+                                    forfeits.lost_on_time_winning().into(),
+                                ),
+                                ("won_on_time", forfeits.won_on_time().into()),
+                                ("won_on_time_losing", forfeits.won_on_time_losing().into()),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "months",
+                Value::List(
+                    by_month
+                        .iter()
+                        .map(|(month, score)| {
+                            Value::map(vec![
+                                ("month", month.format("%Y-%m").to_string().into()),
+                                ("score", (*score).into()),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "streaks",
+                Value::map(vec![
+                    ("longest_wins", streaks.longest_wins().into()),
+                    ("longest_losses", streaks.longest_losses().into()),
+                    ("longest_unbeaten", streaks.longest_unbeaten().into()),
+                    (
+                        "current",
+                        current
+                            .map(|(result, games)| {
+                                Value::map(vec![("result", result.into()), ("games", games.into())])
+                            })
+                            .into(),
+                    ),
+                ]),
+            ),
+            (
+                "upsets",
+                Value::map(vec![
+                    ("wins", upset_values(upsets.biggest_wins())),
+                    ("losses", upset_values(upsets.biggest_losses())),
+                ]),
+            ),
+        ]);
+
+        return Ok(value.to_json());
+    }
+
+    let mut output = format!("Report for {}\n", player);
+
+    output += &format!("\nScore\n  Overall: {}\n", describe_score(overall));
+    output += &format!("  White: {}\n", describe_score(by_color[0]));
+    output += &format!("  Black: {}\n", describe_score(by_color[1]));
+
+    output += "\nOpenings\n";
+
+    for (opening, score) in &by_opening {
+        output += &format!("  {}: {}\n", opening_name(opening), describe_score(*score));
+    }
+
+    output += "\nPawn structures\n";
+
+    for structure in &structures {
+        output += &format!(
+            "  {} ({}): {}\n",
+            structure.structure(),
+            structure_side(structure),
+            describe_score(structure.score())
+        );
+    }
+
+    output += "\nTerminations\n";
+
+    for (termination, score) in &terminations {
+        output += &format!("  {}: {}\n", termination, describe_score(*score));
+    }
+
+    output += "\nTime controls\n";
+
+    for (speed, score) in &by_speed {
+        output += &format!("  {}: {}\n", speed_name(speed), describe_score(*score));
+    }
+
+    output += "\nTime forfeits\n";
+
+    for forfeits in &time_forfeits {
+        output += &format!(
+            "  {}: lost {} on time ({} while winning), won {} on time ({} while losing), of {} games\n",
+            forfeits
+                .time_control()
+                .map_or_else(|| String::from("unknown"), |time_control| time_control.to_string()),
+            forfeits.lost_on_time(),
+            forfeits.lost_on_time_winning(),
+            forfeits.won_on_time(),
+            forfeits.won_on_time_losing(),
+            forfeits.games()
+        );
+    }
+
+    output += "\nMonthly trend\n";
+
+    for (month, score) in &by_month {
+        output += &format!("  {}: {}\n", month.format("%Y-%m"), describe_score(*score));
+    }
+
+    output += "\nStreaks\n";
+    output += &format!("  Longest winning streak: {}\n", streaks.longest_wins());
+    output += &format!("  Longest losing streak: {}\n", streaks.longest_losses());
+    output += &format!(
+        "  Longest unbeaten streak: {}\n",
+        streaks.longest_unbeaten()
+    );
+
+    if let Some((result, games)) = current {
+        let result = match (result, games) {
+            ("win", 1) => "win",
+            ("win", _) => "wins",
+            ("draw", 1) => "draw",
+            ("draw", _) => "draws",
+            (_, 1) => "loss",
+            _ => "losses",
+        };
+
+        output += &format!("  Current streak: {} {} in a row\n", games, result);
+    }
+
+    output += "\nBiggest upsets\n";
+
+    for (verb, upsets) in [
+        ("Beat", upsets.biggest_wins()),
+        ("Lost to", upsets.biggest_losses()),
+    ] {
+        for upset in upsets {
+            let (opponent, rating, gap, date) = upset_details(upset);
+
+            output += &format!(
+                "  {} {} ({}), rated {} points {}, on {}\n",
+                verb,
+                opponent,
+                rating.unwrap_or_default(),
+                gap,
+                if verb == "Beat" { "higher" } else { "lower" },
+                date.as_deref().unwrap_or("an unknown date")
+            );
+        }
+    }
+
+    Ok(output)
+}
+
+/// Returns how the scores of the games in `before` and `after` compare, from `player`'s
+/// perspective if given and white's otherwise.
+fn run_compare<R: Read>(
+    before: R,
+    after: R,
+    player: Option<&str>,
+    format: Format,
+) -> io::Result<String> {
+    let before = read_games(&mut Reader::new(before))?;
+    let after = read_games(&mut Reader::new(after))?;
+
+    let comparison = compare(&mut before.iter(), &mut after.iter(), player);
+
+    if format != Format::Text {
+        let value = comparison.to_value();
+
+        return Ok(match format.separator() {
+            Some(separator) => value.to_table(separator),
+            None => value.to_json(),
+        });
+    }
+
+    fn format_score(name: &str, games: usize, rate: Option<f64>) -> String {
+        match rate {
+            Some(rate) => format!("{}: {} games, {:.2}% score", name, games, rate * 100.),
+            None => format!("{}: 0 games", name),
+        }
+    }
+
+    let mut output = vec![
+        format_score(
+            "Before",
+            comparison.score_a().games(),
+            comparison.score_a().rate(),
+        ),
+        format_score(
+            "After",
+            comparison.score_b().games(),
+            comparison.score_b().rate(),
+        ),
+    ];
+
+    if let Some(difference) = comparison.score_difference() {
+        output.push(format!("Difference: {:+.2}%", difference * 100.));
+    }
+
+    if let (Some(z_score), Some(p_value)) = (comparison.z_score(), comparison.z_test_p_value()) {
+        output.push(format!("Z-test: z = {:.3}, p = {:.4}", z_score, p_value));
+    }
+
+    if let (Some((chi_square, degrees_of_freedom)), Some(p_value)) =
+        (comparison.chi_square(), comparison.chi_square_p_value())
+    {
+        output.push(format!(
+            "Chi-square: {:.3} with {} degrees of freedom, p = {:.4}",
+            chi_square, degrees_of_freedom, p_value
+        ));
+    }
+
+    Ok(output.join("\n"))
+}
+
+/// Returns the `limit` openings `player` loses quickest in among the games in `pgn` that `filter`
+/// includes, leaving out openings with fewer than `min_losses` losses. Losses in under
+/// `quick_moves` moves count as quick.
+fn run_losses<R: Read>(
+    pgn: R,
+    filter: &GameFilter<Game<AlgebraicMove>>,
+    player: &str,
+    quick_moves: usize,
+    min_losses: usize,
+    limit: usize,
+    format: Format,
+) -> io::Result<String> {
+    let games = read_filtered_games(&mut Reader::new(pgn), filter, None)?;
+    let mut losses = fast_losses(
+        &mut games.iter(),
+        player,
+        Openings::embedded(),
+        quick_moves,
+        min_losses,
+    );
+    losses.truncate(limit);
+
+    let opening_name = |losses: &OpeningLosses| match losses.opening() {
+        Some(opening) => format!("{} {}", opening.eco(), opening.name()),
+        None => String::from("Unknown opening"),
+    };
+
+    if format != Format::Text {
+        let values = losses
+            .iter()
+            .map(|losses| {
+                Value::map(vec![
+                    ("eco", losses.opening().map(Opening::eco).into()),
+                    ("name", losses.opening().map(Opening::name).into()),
+                    ("games", losses.games().into()),
+                    ("losses", losses.losses().into()),
+                    ("median_loss_moves", losses.median_loss_length().into()),
+                    ("quick_losses", losses.quick_losses().into()),
+                    ("quick_loss_rate", losses.quick_loss_rate().into()),
+                ])
+            })
+            .collect();
+
+        return Ok(match format.separator() {
+            Some(separator) => Value::List(values).to_table(separator),
+            None => Value::List(values).to_json(),
+        });
+    }
+
+    Ok(losses
+        .iter()
+        .map(|losses| {
+            format!(
+                "{}: lost {} of {} games, in {} moves at the median, {:.1}% under {} moves",
+                opening_name(losses),
+                losses.losses(),
+                losses.games(),
+                losses.median_loss_length().unwrap_or(0),
+                losses.quick_loss_rate().unwrap_or(0.) * 100.,
+                quick_moves
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// Returns how `player`'s games in `pgn` that `filter` includes would have gone with each of
+/// `increments` instead of the increment they were played with, for each time control.
+fn run_increments<R: Read>(
+    pgn: R,
+    filter: &GameFilter<Game<AlgebraicMove>>,
+    player: &str,
+    increments: &[Duration],
+    format: Format,
+) -> io::Result<String> {
+    let games = read_filtered_games(&mut Reader::new(pgn), filter, None)?;
+    let simulations = simulate_increments(&mut games.iter(), player, increments);
+
+    if format != Format::Text {
+        let values = simulations
+            .iter()
+            .map(|simulation| {
+                Value::map(vec![
+                    ("time_control", simulation.time_control().to_string().into()),
+                    (
+                        "increment",
+                        (simulation.increment().as_secs() as usize).into(),
+                    ),
+                    ("games", simulation.games().into()),
+                    ("score", simulation.score().rate().into()),
+                    (
+                        "simulated_score",
+                        simulation.simulated_score().rate().into(),
+                    ),
+                    ("lost_on_time", simulation.lost_on_time().into()),
+                    (
+                        "simulated_lost_on_time",
+                        simulation.simulated_lost_on_time().into(),
+                    ),
+                    ("won_on_time", simulation.won_on_time().into()),
+                    (
+                        "simulated_won_on_time",
+                        simulation.simulated_won_on_time().into(),
+                    ),
+                    ("undecided", simulation.undecided().into()),
+                ])
+            })
+            .collect();
+
+        return Ok(match format.separator() {
+            Some(separator) => Value::List(values).to_table(separator),
+            None => Value::List(values).to_json(),
+        });
+    }
+
+    let percentage = |rate: Option<f64>| match rate {
+        Some(rate) => format!("{:.1}%", rate * 100.),
+        None => String::from("-"),
+    };
+
+    Ok(simulations
+        .iter()
+        .map(|simulation| {
+            let mut line = format!(
+                "{} at +{} ({} games): would score {} instead of {}, losing {} on time instead of {} and winning {} on time instead of {}",
+                simulation.time_control(),
+                simulation.increment().as_secs(),
+                simulation.games(),
+                percentage(simulation.simulated_score().rate()),
+                percentage(simulation.score().rate()),
+                simulation.simulated_lost_on_time(),
+                simulation.lost_on_time(),
+                simulation.simulated_won_on_time(),
+                simulation.won_on_time()
+            );
+
+            if simulation.undecided() > 0 {
+                line += &format!(
+                    ", with {} time forfeits avoided left out",
+                    simulation.undecided()
+                );
+            }
+
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// Returns how `player` did over the sessions of games they played among the games in `pgn` that
+/// `filter` includes, where a session ends after `max_gap` without playing. Text and JSON give
+/// the number of sessions, the score in each game of a session by its number, and how many
+/// sessions had each number of games. Tables give a row for each game number.
+fn run_sessions<R: Read>(
+    pgn: R,
+    filter: &GameFilter<Game<AlgebraicMove>>,
+    player: &str,
+    max_gap: Duration,
+    format: Format,
+) -> io::Result<String> {
+    let games = read_filtered_games(&mut Reader::new(pgn), filter, None)?;
+    let sessions = sessions(&mut games.iter(), player, max_gap);
+    let by_game_number = score_by_game_number(&sessions);
+    let lengths = session_lengths(&sessions);
+
+    let mut score = Score::new();
+
+    for session in &sessions {
+        score.merge(session.score());
+    }
+
+    let by_game_values = by_game_number
+        .iter()
+        .enumerate()
+        .map(|(index, score)| {
+            Value::map(vec![
+                ("game", (index + 1).into()),
+                ("games", score.games().into()),
+                ("score", score.rate().into()),
+            ])
+        })
+        .collect::<Vec<_>>();
+
+    if format != Format::Text {
+        return Ok(match format.separator() {
+            Some(separator) => Value::List(by_game_values).to_table(separator),
+            None => Value::map(vec![
+                ("sessions", sessions.len().into()),
+                ("games", score.games().into()),
+                ("score", score.rate().into()),
+                ("by_game", Value::List(by_game_values)),
+                (
+                    "lengths",
+                    Value::List(
+                        lengths
+                            .iter()
+                            .map(|(games, count)| {
+                                Value::map(vec![
+                                    ("games", (*games).into()),
+                                    ("sessions", (*count).into()),
+                                ])
+                            })
+                            .collect(),
+                    ),
                 ),
-        )
-        .get_matches();
+            ])
+            .to_json(),
+        });
+    }
 
-    if let Some(matches) = matches.subcommand_matches("stats") {
-        let pgn = io::stdin()
-            .lock()
-            .bytes()
-            .map(|b| b.unwrap())
+    if sessions.is_empty() {
+        return Ok(format!(
+            "No sessions with a known start time for {}",
+            player
+        ));
+    }
+
+    let mut output = format!(
+        "{} sessions: {}\n\nBy game in session:\n",
+        sessions.len(),
+        describe_score(score)
+    );
+
+    for (index, score) in by_game_number.iter().enumerate() {
+        output += &format!("  Game {}: {}\n", index + 1, describe_score(*score));
+    }
+
+    output += "\nSession lengths:\n";
+
+    for (games, count) in lengths {
+        output += &format!("  {} games: {} sessions\n", games, count);
+    }
+
+    Ok(output)
+}
+
+/// Returns how soon `player` developed their pieces in the games in `pgn` that `filter`
+/// includes, next to how soon the other sides of the games did. Text and JSON give the average
+/// move each milestone was reached on and the fraction of games it was reached in, for the player
+/// and the others. Tables give a row for each milestone.
+fn run_development<R: Read>(
+    pgn: R,
+    filter: &GameFilter<Game<AlgebraicMove>>,
+    player: &str,
+    format: Format,
+) -> io::Result<String> {
+    let games = read_filtered_games(&mut Reader::new(pgn), filter, None)?;
+    let (player_development, reference) = development(&mut games.iter(), player);
+
+    if format != Format::Text {
+        let values = Milestone::ALL
+            .iter()
+            .map(|milestone| {
+                Value::map(vec![
+                    ("milestone", milestone.to_string().into()),
+                    (
+                        "player_move",
+                        player_development.average_move(*milestone).into(),
+                    ),
+                    (
+                        "player_rate",
+                        player_development.reach_rate(*milestone).into(),
+                    ),
+                    ("others_move", reference.average_move(*milestone).into()),
+                    ("others_rate", reference.reach_rate(*milestone).into()),
+                ])
+            })
             .collect::<Vec<_>>();
 
-        let moves: Vec<String> = if let Some(values) = matches.values_of("MOVES") {
-            values.map(|move_| String::from(move_)).collect::<Vec<_>>()
-        } else {
-            Vec::new()
+        return Ok(match format.separator() {
+            Some(separator) => Value::List(values).to_table(separator),
+            None => Value::map(vec![
+                ("games", player_development.games().into()),
+                ("others_games", reference.games().into()),
+                ("milestones", Value::List(values)),
+            ])
+            .to_json(),
+        });
+    }
+
+    if player_development.games() == 0 {
+        return Ok(format!("No games found for {}", player));
+    }
+
+    let describe = |development: &Development, milestone: Milestone| match (
+        development.average_move(milestone),
+        development.reach_rate(milestone),
+    ) {
+        (Some(average), Some(rate)) => {
+            format!("move {:.1} in {:.1}% of games", average, rate * 100.)
+        }
+        _ => String::from("never"),
+    };
+
+    let mut output = format!(
+        "{} over {} games, against {} other sides:\n",
+        player,
+        player_development.games(),
+        reference.games()
+    );
+
+    for milestone in Milestone::ALL {
+        output += &format!(
+            "  {}: {} (others {})\n",
+            milestone,
+            describe(&player_development, milestone),
+            describe(&reference, milestone)
+        );
+    }
+
+    Ok(output)
+}
+
+/// The kinds of pieces, in the order heatmaps list them.
+const ROLES: [Role; 6] = [
+    Role::Pawn,
+    Role::Knight,
+    Role::Bishop,
+    Role::Rook,
+    Role::Queen,
+    Role::King,
+];
+
+/// Returns the kind of piece named `name`, one of the values `--piece` accepts.
+fn piece_role(name: &str) -> Role {
+    match name {
+        "pawn" => Role::Pawn,
+        "knight" => Role::Knight,
+        "bishop" => Role::Bishop,
+        "rook" => Role::Rook,
+        "queen" => Role::Queen,
+        _ => Role::King,
+    }
+}
+
+/// Returns the heatmaps of where `player`'s pieces went in the `limit` openings and colors they
+/// played most among the games in `pgn` that `filter` includes, leaving out those played in fewer
+/// than `min_games` games. Text shows, from the player's side of the board, the percentage of
+/// positions pieces of type `role` stood on each square, or pieces of any type when `role` is
+/// `None`, and `svg` is given the same as shaded boards. Tables give a row for each square and
+/// kind of piece, and JSON gives a matrix of ranks 8 to 1 for each kind of piece.
+#[allow(clippy::too_many_arguments)]
+fn run_heatmaps<R: Read>(
+    pgn: R,
+    filter: &GameFilter<Game<AlgebraicMove>>,
+    player: &str,
+    role: Option<Role>,
+    min_games: usize,
+    limit: usize,
+    format: Format,
+    svg: Option<&Path>,
+) -> io::Result<String> {
+    let games = read_filtered_games(&mut Reader::new(pgn), filter, None)?;
+    let mut heatmaps = piece_heatmaps(&mut games.iter(), player, Openings::embedded(), min_games);
+    heatmaps.truncate(limit);
+
+    if let Some(path) = svg {
+        fs::write(path, heatmaps::stack_svg(&heatmaps, role, 40))?;
+    }
+
+    let color_name = |heatmap: &Heatmap| match heatmap.color() {
+        Color::White => "white",
+        Color::Black => "black",
+    };
+    let role_name = |role: Role| format!("{:?}", role).to_lowercase();
+
+    if let Some(separator) = format.separator() {
+        let values = heatmaps
+            .iter()
+            .flat_map(|heatmap| {
+                ROLES.iter().flat_map(move |role| {
+                    (0..64).map(Square::new).map(move |square| {
+                        Value::map(vec![
+                            ("eco", heatmap.opening().map(Opening::eco).into()),
+                            ("name", heatmap.opening().map(Opening::name).into()),
+                            ("color", color_name(heatmap).into()),
+                            ("games", heatmap.games().into()),
+                            ("piece", role_name(*role).into()),
+                            ("square", square.to_string().into()),
+                            ("visits", heatmap.visits(Some(*role), square).into()),
+                            (
+                                "occupancy_rate",
+                                heatmap.occupancy_rate(Some(*role), square).into(),
+                            ),
+                        ])
+                    })
+                })
+            })
+            .collect();
+
+        return Ok(Value::List(values).to_table(separator));
+    }
+
+    // Ranks from 8 down to 1, each with files from a to h.
+    let ranks = || {
+        (0..8)
+            .rev()
+            .map(|rank| (0..8).map(move |file| Square::new(rank * 8 + file)))
+    };
+
+    if format == Format::Json {
+        let matrix = |count: &dyn Fn(Square) -> Value| {
+            Value::List(
+                ranks()
+                    .map(|rank| Value::List(rank.map(count).collect()))
+                    .collect(),
+            )
         };
 
-        let show_branches = matches.is_present("branches");
+        let values = heatmaps
+            .iter()
+            .map(|heatmap| {
+                let pieces = ROLES
+                    .iter()
+                    .map(|role| {
+                        Value::map(vec![
+                            ("piece", role_name(*role).into()),
+                            (
+                                "visits",
+                                matrix(&|square| heatmap.visits(Some(*role), square).into()),
+                            ),
+                            (
+                                "occupancy_rate",
+                                matrix(&|square| {
+                                    heatmap.occupancy_rate(Some(*role), square).into()
+                                }),
+                            ),
+                        ])
+                    })
+                    .collect();
 
-        println!("{}", run_stats(&pgn, moves, show_branches)?);
+                Value::map(vec![
+                    ("eco", heatmap.opening().map(Opening::eco).into()),
+                    ("name", heatmap.opening().map(Opening::name).into()),
+                    ("color", color_name(heatmap).into()),
+                    ("games", heatmap.games().into()),
+                    ("positions", heatmap.positions().into()),
+                    ("pieces", Value::List(pieces)),
+                ])
+            })
+            .collect();
+
+        return Ok(Value::List(values).to_json());
     }
 
-    Ok(())
+    let mut output = String::new();
+
+    for heatmap in &heatmaps {
+        output += &format!(
+            "{} as {} ({} games), percentage of positions {} stood on each square:\n",
+            match heatmap.opening() {
+                Some(opening) => format!("{} {}", opening.eco(), opening.name()),
+                None => String::from("Unknown opening"),
+            },
+            color_name(heatmap),
+            heatmap.games(),
+            match role {
+                Some(role) => format!("{}s", role_name(role)),
+                None => String::from("pieces"),
+            }
+        );
+
+        let mut rows = ranks().map(Iterator::collect::<Vec<_>>).collect::<Vec<_>>();
+
+        // The player's side of the board goes at the bottom.
+        if heatmap.color() == Color::Black {
+            rows.reverse();
+            rows.iter_mut().for_each(|row| row.reverse());
+        }
+
+        for row in &rows {
+            output += &format!("  {}", row[0].rank().char());
+
+            for square in row {
+                output += &match heatmap.occupancy_rate(role, *square) {
+                    Some(rate) if rate > 0. => format!("{:>4.0}", rate * 100.),
+                    _ => format!("{:>4}", "."),
+                };
+            }
+
+            output += "\n";
+        }
+
+        output += "   ";
+
+        for square in &rows[0] {
+            output += &format!("{:>4}", square.file().char());
+        }
+
+        output += "\n\n";
+    }
+
+    Ok(output)
 }
 
-fn run_stats(pgn: &[u8], moves: Vec<String>, show_branches: bool) -> io::Result<String> {
-    let mut reader = BufferedReader::new_cursor(&pgn[..]);
+/// Returns the survival curves of the `limit` most played openings among the games in `pgn` that
+/// `filter` includes, leaving out openings played in fewer than `min_games` games. Text gives the
+/// fraction of games undecided every `step` moves up to `max_moves`, while tables and JSON give a
+/// row for every move up to `max_moves`, with the number of games won at that move by each color.
+fn run_survival<R: Read>(
+    pgn: R,
+    filter: &GameFilter<Game<AlgebraicMove>>,
+    min_games: usize,
+    limit: usize,
+    step: usize,
+    max_moves: usize,
+    format: Format,
+) -> io::Result<String> {
+    if step == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "The step between points must be positive",
+        ));
+    }
+
+    let games = read_filtered_games(&mut Reader::new(pgn), filter, None)?;
+    let mut curves = survival_curves(&mut games.iter(), Openings::embedded(), min_games);
+    curves.truncate(limit);
 
-    fn read_game<R: Read>(
-        reader: &mut BufferedReader<R>,
-    ) -> io::Result<Option<Game<AlgebraicMove>>> {
-        let mut game_parser = GameParser::new();
-        let pgn_game = reader.read_game(&mut game_parser)?;
+    if format != Format::Text {
+        let values = curves
+            .iter()
+            .flat_map(|curve| {
+                (1..=max_moves).map(move |move_number| {
+                    Value::map(vec![
+                        ("eco", curve.opening().map(Opening::eco).into()),
+                        ("name", curve.opening().map(Opening::name).into()),
+                        ("games", curve.games().into()),
+                        ("move", move_number.into()),
+                        ("undecided_rate", curve.undecided_at(move_number).into()),
+                        (
+                            "white_wins",
+                            curve.wins_at(Color::White, move_number).into(),
+                        ),
+                        (
+                            "black_wins",
+                            curve.wins_at(Color::Black, move_number).into(),
+                        ),
+                    ])
+                })
+            })
+            .collect();
 
-        Ok(pgn_game.map(|g| Game::<AlgebraicMove>::from(g)))
+        return Ok(match format.separator() {
+            Some(separator) => Value::List(values).to_table(separator),
+            None => Value::List(values).to_json(),
+        });
     }
 
-    let mut games: Vec<Game<AlgebraicMove>> = Vec::new();
+    let median = |median: Option<usize>| match median {
+        Some(moves) => format!("{} moves", moves),
+        None => String::from("none"),
+    };
 
-    while let Some(game) = read_game(&mut reader)? {
-        games.push(game);
+    Ok(curves
+        .iter()
+        .map(|curve| {
+            let points = (step..=max_moves)
+                .step_by(step)
+                .map(|move_number| {
+                    format!(
+                        "{:.1}% at move {}",
+                        curve.undecided_at(move_number) * 100.,
+                        move_number
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            format!(
+                "{} ({} games): undecided {}; median wins: white {}, black {}",
+                match curve.opening() {
+                    Some(opening) => format!("{} {}", opening.eco(), opening.name()),
+                    None => String::from("Unknown opening"),
+                },
+                curve.games(),
+                points.join(", "),
+                median(curve.median_win_length(Color::White)),
+                median(curve.median_win_length(Color::Black))
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// The number of plies of the lines the `prep` dossier scores the opponent in.
+const DOSSIER_LINE_LENGTH: usize = 6;
+
+/// Returns a dossier on `player` from their games in `pgn` that `filter` includes, for preparing
+/// against them. Their repertoire trees go down to `depth` plies, leaving out moves played in
+/// fewer than `min_games` games, and `limit` of their worst lines and most predictable moves are
+/// listed. Their form is judged by their `recent` most recent games.
+#[allow(clippy::too_many_arguments)]
+fn run_dossier<R: Read>(
+    pgn: R,
+    filter: &GameFilter<Game<AlgebraicMove>>,
+    player: &str,
+    depth: usize,
+    min_games: usize,
+    limit: usize,
+    recent: usize,
+    format: Format,
+) -> io::Result<String> {
+    if format.separator().is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "The dossier can only be written as text or JSON",
+        ));
     }
 
-    let move_tree = MoveTree::new(games);
-    let mut move_tree_view = move_tree.view();
+    let games = GameCollection::from(read_filtered_games(&mut Reader::new(pgn), filter, None)?)
+        .filter_player(player)
+        .sort_by_date();
+
+    let (as_white, as_black) = games.clone().partition_by_color(player);
+    let (white_tree, black_tree) = (as_white.into_move_tree(), as_black.into_move_tree());
+    let white_nodes = tree_nodes(&white_tree.view(), &[], depth, min_games);
+    let black_nodes = tree_nodes(&black_tree.view(), &[], depth, min_games);
+
+    let mut worst = worst_lines(&mut games.iter(), player, DOSSIER_LINE_LENGTH, min_games);
+    worst.truncate(limit);
+
+    let mut predictable =
+        predictable_moves(&mut games.iter(), player, depth.div_ceil(2), min_games);
+    predictable.truncate(limit);
+
+    let usage = time_usage(&mut games.iter(), player, DOSSIER_LINE_LENGTH);
+
+    let recent_games = &games.games()[games.len().saturating_sub(recent)..];
+    let mut form = Score::new();
+
+    for game in recent_games {
+        form.add(game.result().for_color(game.color_of(player).unwrap()));
+    }
+
+    let ratings = recent_games
+        .iter()
+        .filter_map(|game| game.rating_of(game.color_of(player)?))
+        .collect::<Vec<_>>();
+    let current = streaks(&mut games.iter(), player)
+        .current()
+        .map(|(result, games)| {
+            let result = match result {
+                PlayerResult::Won => "win",
+                PlayerResult::Drew => "draw",
+                PlayerResult::Lost => "loss",
+            };
+
+            (result, games)
+        });
+
+    let color_name = |color: Color| match color {
+        Color::White => "white",
+        Color::Black => "black",
+    };
+    let line_san = |moves: &[AlgebraicMove]| {
+        moves
+            .iter()
+            .map(|move_| move_.clone().to_algebraic())
+            .collect::<Vec<_>>()
+    };
+    let seconds = |duration: Option<Duration>| duration.map(|duration| duration.as_secs_f64());
+
+    if format == Format::Json {
+        let value = Value::map(vec![
+            ("player", player.into()),
+            ("filters", filter.to_string().into()),
+            ("games", games.len().into()),
+            ("white_repertoire", tree_values(&white_nodes)),
+            ("black_repertoire", tree_values(&black_nodes)),
+            (
+                "worst_lines",
+                Value::List(
+                    worst
+                        .iter()
+                        .map(|line: &LineScore<AlgebraicMove>| {
+                            Value::map(vec![
+                                (
+                                    "line",
+                                    format_line(line_san(line.moves()).into_iter()).into(),
+                                ),
+                                ("color", color_name(line.color()).into()),
+                                ("score", line.score().into()),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "predictable_moves",
+                Value::List(
+                    predictable
+                        .iter()
+                        .map(|predictable: &PredictableMove<AlgebraicMove>| {
+                            Value::map(vec![
+                                (
+                                    "line",
+                                    format_line(line_san(predictable.line()).into_iter()).into(),
+                                ),
+                                ("color", color_name(predictable.color()).into()),
+                                ("move", predictable.move_().clone().to_algebraic().into()),
+                                ("games", predictable.games().into()),
+                                ("total", predictable.total().into()),
+                                ("rate", predictable.rate().into()),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "time_usage",
+                Value::map(vec![
+                    (
+                        "average_move_time",
+                        seconds(usage.average_move_time()).into(),
+                    ),
+                    (
+                        "opening_move_time",
+                        seconds(usage.average_phase_time(Phase::Opening)).into(),
+                    ),
+                    (
+                        "middlegame_move_time",
+                        seconds(usage.average_phase_time(Phase::Middlegame)).into(),
+                    ),
+                    (
+                        "endgame_move_time",
+                        seconds(usage.average_phase_time(Phase::Endgame)).into(),
+                    ),
+                    (
+                        "clock_at_move_40",
+                        seconds(usage.average_clock_at_move_40()).into(),
+                    ),
+                    ("clock_at_end", seconds(usage.average_clock_at_end()).into()),
+                ]),
+            ),
+            (
+                "recent_form",
+                Value::map(vec![
+                    ("score", form.into()),
+                    (
+                        "first_rating",
+                        ratings.first().map(|rating| usize::from(*rating)).into(),
+                    ),
+                    (
+                        "last_rating",
+                        ratings.last().map(|rating| usize::from(*rating)).into(),
+                    ),
+                    (
+                        "streak",
+                        current
+                            .map(|(result, games)| {
+                                Value::map(vec![("result", result.into()), ("games", games.into())])
+                            })
+                            .into(),
+                    ),
+                ]),
+            ),
+        ]);
+
+        return Ok(value.to_json());
+    }
+
+    let mut output = format!(
+        "Dossier on {} ({} games)
+",
+        player,
+        games.len()
+    );
+
+    for (color, nodes) in [("white", &white_nodes), ("black", &black_nodes)] {
+        output += &format!("\nRepertoire as {}\n", color);
+        draw_tree(nodes, "  ", None, &mut output);
+    }
+
+    output += "\nWorst lines\n";
 
-    for move_ in moves {
-        move_tree_view = move_tree_view.with_next(&AlgebraicMove::from_algebraic(move_));
+    for line in &worst {
+        output += &format!(
+            "  {} ({}): {}\n",
+            format_line(line_san(line.moves()).into_iter()),
+            color_name(line.color()),
+            describe_score(line.score())
+        );
     }
 
-    let branches: Vec<String> = if show_branches {
-        let mut branches = match move_tree_view.branches() {
-            Some(branches) => branches.map(|move_| move_.clone().to_algebraic()).collect(),
-            None => Vec::new(),
+    output += "\nMost predictable moves\n";
+
+    for predictable in &predictable {
+        let position = match predictable.line() {
+            [] => String::from("At the start"),
+            line => format!("After {}", format_line(line_san(line).into_iter())),
         };
 
-        branches.sort();
+        output += &format!(
+            "  {} ({}): {} in {} of {} games ({:.1}%)\n",
+            position,
+            color_name(predictable.color()),
+            format_move(
+                predictable.line().len(),
+                &predictable.move_().clone().to_algebraic()
+            ),
+            predictable.games(),
+            predictable.total(),
+            predictable.rate() * 100.
+        );
+    }
 
-        branches
-    } else {
-        Vec::new()
+    let describe_seconds = |duration: Option<Duration>| match seconds(duration) {
+        Some(seconds) => format!("{:.1}s", seconds),
+        None => String::from("unknown"),
     };
 
-    let filtered_games = move_tree_view.iter().collect::<Vec<_>>();
-    let game_count = filtered_games.len();
+    output += "\nTime usage\n";
+    output += &format!(
+        "  Average time per move: {} (opening {}, middlegame {}, endgame {})\n",
+        describe_seconds(usage.average_move_time()),
+        describe_seconds(usage.average_phase_time(Phase::Opening)),
+        describe_seconds(usage.average_phase_time(Phase::Middlegame)),
+        describe_seconds(usage.average_phase_time(Phase::Endgame))
+    );
+    output += &format!(
+        "  Average clock after move 40: {}\n",
+        describe_seconds(usage.average_clock_at_move_40())
+    );
+    output += &format!(
+        "  Average clock at the end: {}\n",
+        describe_seconds(usage.average_clock_at_end())
+    );
 
-    let (white_win_rate, black_win_rate, draw_rate) =
-        stats::results(&mut filtered_games.into_iter());
-    let white_win_percent = white_win_rate * 100.;
-    let black_win_percent = black_win_rate * 100.;
-    let draw_percent = draw_rate * 100.;
+    output += "\nRecent form\n";
+    output += &format!("  Last {}: {}\n", recent_games.len(), describe_score(form));
 
-    let mut output: String = format!(
-        "{} games\nWhite Wins: {:.2}%\nBlack Wins: {:.2}%\nDraw: {:.2}%",
-        game_count, white_win_percent, black_win_percent, draw_percent
-    )
-    .to_owned();
+    if let (Some(first), Some(last)) = (ratings.first(), ratings.last()) {
+        output += &format!("  Rating: {} to {}\n", first, last);
+    }
+
+    if let Some((result, games)) = current {
+        let result = match (result, games) {
+            ("win", 1) => "win",
+            ("win", _) => "wins",
+            ("draw", 1) => "draw",
+            ("draw", _) => "draws",
+            (_, 1) => "loss",
+            _ => "losses",
+        };
+
+        output += &format!("  Current streak: {} {} in a row\n", games, result);
+    }
+
+    Ok(output)
+}
+
+/// Returns `player`'s record against the `limit` opponents they played most in the games of `pgn`
+/// that `filter` includes.
+fn run_opponents<R: Read>(
+    pgn: R,
+    filter: &GameFilter<Game<AlgebraicMove>>,
+    player: &str,
+    limit: usize,
+    format: Format,
+) -> io::Result<String> {
+    let games = read_filtered_games(&mut Reader::new(pgn), filter, None)?;
+    let records = opponents(&mut games.iter(), player, limit);
+
+    if format != Format::Text {
+        let values = records
+            .iter()
+            .map(|record| {
+                Value::map(vec![
+                    ("opponent", record.opponent().into()),
+                    ("games", record.games().into()),
+                    ("wins", record.score().wins().into()),
+                    ("draws", record.score().draws().into()),
+                    ("losses", record.score().losses().into()),
+                    ("score", record.score().rate().into()),
+                    ("games_as_white", record.games_as_white().into()),
+                    ("games_as_black", record.games_as_black().into()),
+                    (
+                        "last_played",
+                        record.last_played().map(|date| date.to_string()).into(),
+                    ),
+                ])
+            })
+            .collect();
+
+        return Ok(match format.separator() {
+            Some(separator) => Value::List(values).to_table(separator),
+            None => Value::List(values).to_json(),
+        });
+    }
+
+    let describe = |record: &OpponentRecord| {
+        let last_played = match record.last_played() {
+            Some(date) => format!(", last played {}", date),
+            None => String::new(),
+        };
+
+        format!(
+            "{}: {}, {} as white and {} as black{}",
+            record.opponent(),
+            describe_score(record.score()),
+            record.games_as_white(),
+            record.games_as_black(),
+            last_played
+        )
+    };
+
+    Ok(records.iter().map(describe).collect::<Vec<_>>().join("\n"))
+}
+
+/// Returns the cross-table of each tournament in the games of `pgn` that `filter` includes.
+fn run_crosstable<R: Read>(
+    pgn: R,
+    filter: &GameFilter<Game<AlgebraicMove>>,
+    format: Format,
+) -> io::Result<String> {
+    let games = read_filtered_games(&mut Reader::new(pgn), filter, None)?;
+    let tables = crosstables(&mut games.iter());
 
-    if show_branches {
-        let branches_output = if branches.len() > 0 {
-            let mut branches_output = "Moves:".to_owned();
+    if let Some(separator) = format.separator() {
+        let rows = tables.iter().flat_map(Crosstable::to_rows).collect();
+        return Ok(Value::List(rows).to_table(separator));
+    }
+
+    if format == Format::Json {
+        let tables = tables.iter().map(Crosstable::to_value).collect();
+        return Ok(Value::List(tables).to_json());
+    }
+
+    Ok(tables
+        .iter()
+        .map(Crosstable::to_string)
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// Returns the ratings `elo` estimates for the players of the games of `pgn` that `filter`
+/// includes, highest first.
+fn run_ratings<R: Read>(
+    pgn: R,
+    filter: &GameFilter<Game<AlgebraicMove>>,
+    elo: Elo,
+    format: Format,
+) -> io::Result<String> {
+    let games = read_filtered_games(&mut Reader::new(pgn), filter, None)?;
+    let ratings = elo.rate(&mut games.iter());
+
+    if format != Format::Text {
+        let value = Value::List(ratings.iter().map(EstimatedRating::to_value).collect());
+
+        return Ok(match format.separator() {
+            Some(separator) => value.to_table(separator),
+            None => value.to_json(),
+        });
+    }
+
+    if ratings.is_empty() {
+        return Ok(String::from("No rated games"));
+    }
+
+    Ok(ratings
+        .iter()
+        .enumerate()
+        .map(|(rank, rating)| {
+            format!(
+                "{}. {}: {:.0} (peak {:.0}, scored {}/{})",
+                rank + 1,
+                rating.player(),
+                rating.rating(),
+                rating.peak(),
+                rating.score().points(),
+                rating.score().games()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// Analyzes the games piped to standard input with `engines`, searching within `limit` until
+/// `budget` runs out, and prints the reports `matches` asks for. The first of `engines` is left
+/// holding the analysis of every position searched, even if this fails.
+fn run_analyses<E: io::BufRead + Send, W: io::Write + Send>(
+    matches: &clap::ArgMatches,
+    engines: &mut [Engine<E, W>],
+    limit: Limit,
+    budget: Budget,
+) -> io::Result<()> {
+    let games = read_games(&mut Reader::new(stdin_games()?))?;
+    let games = run_batch(engines, games, limit, budget)?;
+    let engine = &mut engines[0];
+
+    if matches.is_present("blunders") {
+        let threshold = parse_number(matches.value_of("threshold").unwrap(), "threshold")?;
+
+        write!(
+            io::stdout().lock(),
+            "{}",
+            run_blunders(&games, engine, limit, threshold, board_style(matches))?
+        )?;
+    }
+
+    if matches.is_present("acpl") {
+        write!(io::stdout().lock(), "{}", run_acpl(&games, engine, limit)?)?;
+    }
+
+    if matches.is_present("annotate") {
+        for game in &games {
+            writeln!(
+                io::stdout().lock(),
+                "{}",
+                annotate_game(engine, game, limit, false)?
+            )?;
+        }
+    }
+
+    if matches.is_present("puzzles") {
+        let threshold = parse_number(matches.value_of("threshold").unwrap(), "threshold")?;
+        let as_pgn = matches.value_of("puzzle-format") == Some("pgn");
+
+        for game in &games {
+            for puzzle in
+                find_missed_tactics(engine, game, limit, threshold, matches.value_of("player"))?
+            {
+                if as_pgn {
+                    writeln!(io::stdout().lock(), "{}", puzzle.to_pgn())?;
+                } else {
+                    writeln!(io::stdout().lock(), "{}", puzzle.to_epd())?;
+                }
+            }
+        }
+    }
+
+    if matches.is_present("graph") {
+        for game in &games {
+            let graph = eval_graph(engine, game, limit)?;
 
-            for branch in branches {
-                branches_output += &(" ".to_owned() + &branch);
+            match matches.value_of("graph-format") {
+                Some("csv") => writeln!(io::stdout().lock(), "{}", graph.to_csv())?,
+                Some("svg") => writeln!(io::stdout().lock(), "{}", graph.to_svg(600, 120))?,
+                _ => writeln!(io::stdout().lock(), "{}", graph.to_json())?,
             }
+        }
+    }
+
+    if matches.is_present("prep") {
+        write!(
+            io::stdout().lock(),
+            "{}",
+            run_prep(
+                &games,
+                engine,
+                limit,
+                matches.value_of("player").unwrap(),
+                parse_number(matches.value_of("line-length").unwrap(), "line-length")?,
+                parse_number(matches.value_of("min-games").unwrap(), "min-games")?,
+                parse_number(matches.value_of("threshold").unwrap(), "threshold")?
+            )?
+        )?;
+    }
+
+    if matches.is_present("flashcards") {
+        let cards = run_flashcards(
+            &games,
+            engine,
+            limit,
+            matches.value_of("player"),
+            parse_number(matches.value_of("line-length").unwrap(), "line-length")?,
+            parse_number(matches.value_of("min-games").unwrap(), "min-games")?,
+            parse_number(matches.value_of("threshold").unwrap(), "threshold")?,
+        )?;
+
+        match matches.value_of("flashcard-format") {
+            Some("csv") => write!(io::stdout().lock(), "{}", flashcards::to_csv(&cards))?,
+            _ => write!(io::stdout().lock(), "{}", flashcards::to_anki(&cards))?,
+        }
+    }
 
-            branches_output
+    Ok(())
+}
+
+/// Opens the cache file at `path`, or returns `None` if no path is given or there's no file there
+/// yet. Other failures to open it are returned, rather than quietly starting over without it.
+fn open_cache(path: Option<&str>) -> io::Result<Option<File>> {
+    match path.map(File::open) {
+        Some(Ok(file)) => Ok(Some(file)),
+        Some(Err(error)) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+        Some(Err(error)) => Err(error),
+        None => Ok(None),
+    }
+}
+
+/// Writes the analyses cached by `engine` to the file at `path`, creating its directory if needed.
+fn write_engine_cache<E: io::BufRead, W: io::Write>(
+    engine: &Engine<E, W>,
+    path: &Path,
+) -> io::Result<()> {
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+
+    engine.write_cache(io::BufWriter::new(File::create(path)?))
+}
+
+/// Analyses `games` with `engines`, reporting progress on standard error, and returns the games
+/// that were fully analysed before `budget` ran out.
+fn run_batch<E: io::BufRead + Send, W: io::Write + Send>(
+    engines: &mut [Engine<E, W>],
+    games: Vec<Game<AlgebraicMove>>,
+    limit: Limit,
+    budget: Budget,
+) -> io::Result<Vec<Game<AlgebraicMove>>> {
+    let analysed = analyse_games(engines, &games, limit, budget, &|progress| {
+        eprint!(
+            "\rAnalyzed {}/{} games",
+            progress.games_analysed(),
+            progress.games()
+        )
+    })?;
+
+    let total = games.len();
+
+    let games = games
+        .into_iter()
+        .zip(analysed)
+        .filter(|(_, analysed)| *analysed)
+        .map(|(game, _)| game)
+        .collect::<Vec<_>>();
+
+    if total > 0 {
+        eprintln!();
+    }
+
+    if games.len() < total {
+        let reason = if interruption().is_cancelled() {
+            "Interrupted"
         } else {
-            "No moves".to_owned()
+            "Budget ran out"
+        };
+
+        eprintln!(
+            "{}, so only {} of {} games are included",
+            reason,
+            games.len(),
+            total
+        );
+    }
+
+    Ok(games)
+}
+
+fn run_blunders<E: io::BufRead, W: io::Write>(
+    games: &[Game<AlgebraicMove>],
+    engine: &mut Engine<E, W>,
+    limit: Limit,
+    threshold: u32,
+    board: Option<BoardStyle>,
+) -> io::Result<String> {
+    let mut output = String::new();
+
+    for game in games {
+        let blunders = find_blunders(engine, game, limit, threshold)?;
+
+        if blunders.is_empty() {
+            continue;
+        }
+
+        output += &format!("{} vs {}\n", game.white(), game.black());
+
+        let speed = game.time_control().map(|time_control| time_control.speed());
+
+        for blunder in blunders {
+            let model = match game.rating_of(blunder.color()) {
+                Some(rating) => WinModel::for_player(rating, speed),
+                None => WinModel::lichess(),
+            };
+
+            let dots = match blunder.color() {
+                Color::White => ".",
+                Color::Black => "...",
+            };
+
+            let better = match blunder.alternatives() {
+                [] => format!("best was {}", blunder.best_move().unwrap_or("unknown")),
+                [best] => format!("best was {} ({})", best.san(), best.eval()),
+                alternatives => format!(
+                    "better were {}",
+                    alternatives
+                        .iter()
+                        .map(|candidate| format!("{} ({})", candidate.san(), candidate.eval()))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            };
+
+            output += &format!(
+                "  {}{} {} ({} -> {}, cost {:.0}% win probability), {}\n    {}\n",
+                blunder.move_number(),
+                dots,
+                blunder.played(),
+                blunder.before(),
+                blunder.after(),
+                blunder.probability_lost(&model) * 100.,
+                better,
+                blunder.fen()
+            );
+
+            if let Some(style) = board {
+                if let Ok(setup) = blunder.fen().parse::<Fen>() {
+                    output += &indent_lines(&setup.render(style), "    ");
+                }
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+fn run_acpl<E: io::BufRead, W: io::Write>(
+    games: &[Game<AlgebraicMove>],
+    engine: &mut Engine<E, W>,
+    limit: Limit,
+) -> io::Result<String> {
+    let losses = average_centipawn_loss(engine, &mut games.iter(), limit)?;
+
+    let format_average = |average: Option<f64>| match average {
+        Some(average) => format!("{:.1}", average),
+        None => String::from("-"),
+    };
+
+    let mut output = String::new();
+
+    for loss in losses {
+        output += &format!(
+            "{}: {} over {} moves (opening {}, middlegame {}, endgame {})\n",
+            loss.player(),
+            format_average(loss.overall().average()),
+            loss.overall().moves(),
+            format_average(loss.phase(Phase::Opening).average()),
+            format_average(loss.phase(Phase::Middlegame).average()),
+            format_average(loss.phase(Phase::Endgame).average())
+        );
+    }
+
+    Ok(output)
+}
+
+/// Returns flashcards asking for the best move in place of each blunder in `games`, and at the
+/// end of each opening line `player` scores worse in than expected, according to `engine`
+/// searching within `limit`. Blunders are only those of `player` when given, and opening lines
+/// are only looked at when a player is given.
+fn run_flashcards<E: io::BufRead, W: io::Write>(
+    games: &[Game<AlgebraicMove>],
+    engine: &mut Engine<E, W>,
+    limit: Limit,
+    player: Option<&str>,
+    line_length: usize,
+    min_games: usize,
+    threshold: u32,
+) -> io::Result<Vec<Flashcard>> {
+    let mut cards = Vec::new();
+
+    for game in games {
+        let speed = game.time_control().map(|time_control| time_control.speed());
+
+        for blunder in find_blunders(engine, game, limit, threshold)? {
+            if matches!(player, Some(player) if !game.player(blunder.color()).is_named(player)) {
+                continue;
+            }
+
+            let model = match game.rating_of(blunder.color()) {
+                Some(rating) => WinModel::for_player(rating, speed),
+                None => WinModel::lichess(),
+            };
+
+            cards.extend(Flashcard::from_blunder(&blunder, &model));
+        }
+    }
+
+    if let Some(player) = player {
+        let gaps = prep_gaps(
+            engine,
+            &mut games.iter(),
+            player,
+            line_length,
+            min_games,
+            limit,
+            &WinModel::lichess(),
+            threshold,
+        )?;
+
+        cards.extend(gaps.iter().filter_map(Flashcard::from_prep_gap));
+    }
+
+    Ok(cards)
+}
+
+fn run_prep<E: io::BufRead, W: io::Write>(
+    games: &[Game<AlgebraicMove>],
+    engine: &mut Engine<E, W>,
+    limit: Limit,
+    player: &str,
+    line_length: usize,
+    min_games: usize,
+    threshold: u32,
+) -> io::Result<String> {
+    let gaps = prep_gaps(
+        engine,
+        &mut games.iter(),
+        player,
+        line_length,
+        min_games,
+        limit,
+        &WinModel::lichess(),
+        threshold,
+    )?;
+
+    let mut output = String::new();
+
+    for gap in gaps {
+        let line = format_line(gap.moves().iter().map(|move_| move_.clone().to_algebraic()));
+
+        let color = match gap.color() {
+            Color::White => "white",
+            Color::Black => "black",
+        };
+
+        let kind = match gap.kind() {
+            GapKind::Understanding => "understanding gap",
+            GapKind::Repertoire => "repertoire gap",
         };
 
-        output = output + "\n" + &branches_output;
+        output += &format!(
+            "{} as {}: scored {:.0}% over {} games, expected {:.0}% at {}, {}\n    {}\n",
+            line,
+            color,
+            gap.score().rate().unwrap_or(0.) * 100.,
+            gap.score().games(),
+            gap.expected_rate() * 100.,
+            gap.eval(),
+            kind,
+            gap.fen()
+        );
     }
 
     Ok(output)
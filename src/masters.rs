@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde_json::Value as Json;
+use shakmaty::fen::Fen;
+use shakmaty::{CastlingMode, Chess};
+
+use crate::error::{Error, Result};
+use crate::position;
+
+/// How master games went after a move, as the lichess masters explorer counts them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MasterMove {
+    san: String,
+    white: usize,
+    draws: usize,
+    black: usize,
+}
+
+impl MasterMove {
+    /// Returns the move in SAN.
+    pub fn san(&self) -> &str {
+        &self.san
+    }
+
+    /// Returns the number of master games with the move.
+    pub fn games(&self) -> usize {
+        self.white + self.draws + self.black
+    }
+
+    /// Returns the fraction of points white scored in master games with the move, or `None` if
+    /// there are none.
+    pub fn white_score(&self) -> Option<f64> {
+        white_score(self.white, self.draws, self.black)
+    }
+}
+
+/// How master games went from a position, as the lichess masters explorer counts them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MasterStats {
+    white: usize,
+    draws: usize,
+    black: usize,
+    moves: Vec<MasterMove>,
+}
+
+impl MasterStats {
+    /// Reads the statistics from a response of the lichess masters explorer, or returns `None` if
+    /// `json` isn't one.
+    pub fn from_json(json: &Json) -> Option<MasterStats> {
+        let counts = |json: &Json| {
+            Some((
+                json["white"].as_u64()? as usize,
+                json["draws"].as_u64()? as usize,
+                json["black"].as_u64()? as usize,
+            ))
+        };
+
+        let (white, draws, black) = counts(json)?;
+        let moves = json["moves"]
+            .as_array()
+            .map_or(&[][..], Vec::as_slice)
+            .iter()
+            .map(|move_| {
+                let (white, draws, black) = counts(move_)?;
+
+                Some(MasterMove {
+                    san: String::from(move_["san"].as_str()?),
+                    white,
+                    draws,
+                    black,
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(MasterStats {
+            white,
+            draws,
+            black,
+            moves,
+        })
+    }
+
+    /// Returns the number of master games that reached the position.
+    pub fn games(&self) -> usize {
+        self.white + self.draws + self.black
+    }
+
+    /// Returns the fractions of master games white won, black won, and that were drawn, or
+    /// `None` if there are none.
+    pub fn rates(&self) -> Option<(f64, f64, f64)> {
+        let games = self.games() as f64;
+
+        match self.games() {
+            0 => None,
+            _ => Some((
+                self.white as f64 / games,
+                self.black as f64 / games,
+                self.draws as f64 / games,
+            )),
+        }
+    }
+
+    /// Returns the moves masters played from the position, most played first.
+    pub fn moves(&self) -> &[MasterMove] {
+        &self.moves
+    }
+
+    /// Returns how master games went after `san`, or `None` if masters never played it.
+    pub fn move_(&self, san: &str) -> Option<&MasterMove> {
+        self.moves.iter().find(|move_| move_.san == san)
+    }
+}
+
+/// Master game statistics for many positions, kept from earlier requests to the lichess masters
+/// explorer so that reports can compare a player's choices with theory without going online.
+///
+/// The cache is a JSON object with the FEN of each position as a key and the explorer's response
+/// for the position as its value. Positions are matched whatever their move counters are.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MastersCache {
+    positions: HashMap<String, MasterStats>,
+}
+
+impl MastersCache {
+    /// Reads a cache from `json`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `json` isn't valid JSON, or if one of its keys isn't a valid FEN or
+    /// one of its values isn't an explorer response.
+    pub fn parse(json: &str) -> Result<MastersCache> {
+        let json = serde_json::from_str::<Json>(json)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        let entries = json.as_object().ok_or_else(|| {
+            Error::InvalidArgument(String::from("The masters cache isn't a JSON object"))
+        })?;
+
+        let mut positions = HashMap::new();
+
+        for (key, response) in entries {
+            let position = key
+                .parse::<Fen>()
+                .ok()
+                .and_then(|fen| fen.into_position::<Chess>(CastlingMode::Standard).ok())
+                .ok_or_else(|| {
+                    Error::InvalidArgument(format!("Invalid FEN in the masters cache: {}", key))
+                })?;
+            let stats = MasterStats::from_json(response).ok_or_else(|| {
+                Error::InvalidArgument(format!(
+                    "Invalid explorer response in the masters cache for {}",
+                    key
+                ))
+            })?;
+
+            positions.insert(position::epd(&position), stats);
+        }
+
+        Ok(MastersCache { positions })
+    }
+
+    /// Reads the cache in the file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or isn't a valid cache.
+    pub fn load(path: &Path) -> Result<MastersCache> {
+        MastersCache::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Returns the master statistics for `position`, or `None` if the cache doesn't have them.
+    pub fn get(&self, position: &Chess) -> Option<&MasterStats> {
+        self.positions.get(&position::epd(position))
+    }
+
+    /// Returns the number of positions in the cache.
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Returns whether the cache has no positions.
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+}
+
+/// Returns the fraction of points white scored from the counts of each result, or `None` if
+/// there are no games.
+fn white_score(white: usize, draws: usize, black: usize) -> Option<f64> {
+    match white + draws + black {
+        0 => None,
+        games => Some((white as f64 + draws as f64 / 2.) / games as f64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use shakmaty::Chess;
+
+    use super::MastersCache;
+    use crate::moves::Move;
+    use crate::position;
+    use crate::AlgebraicMove;
+
+    #[rstest]
+    fn masters_cache_should_find_positions_by_fen() {
+        let cache = MastersCache::parse(
+            r#"{
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1": {
+                    "white": 30, "draws": 50, "black": 20,
+                    "moves": [
+                        {"uci": "c7c5", "san": "c5", "white": 20, "draws": 30, "black": 10},
+                        {"uci": "e7e5", "san": "e5", "white": 10, "draws": 20, "black": 10}
+                    ],
+                    "topGames": []
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let e4 = position::replay(vec![AlgebraicMove::from_algebraic(String::from("e4"))]);
+        let stats = cache.get(&e4[1]).unwrap();
+
+        assert_eq!(cache.len(), 1);
+        assert_eq!(stats.games(), 100);
+        assert_eq!(stats.rates(), Some((0.3, 0.2, 0.5)));
+        assert_eq!(stats.move_("c5").unwrap().games(), 60);
+        assert_eq!(stats.move_("e5").unwrap().white_score(), Some(0.5));
+        assert!(stats.move_("e6").is_none());
+        assert!(cache.get(&Chess::default()).is_none());
+        assert!(MastersCache::parse("{\"not a fen\": {}}").is_err());
+    }
+}
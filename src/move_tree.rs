@@ -1,9 +1,15 @@
+use std::cmp::Reverse;
 use std::collections::hash_map;
 use std::collections::HashMap;
 use std::hash::Hash;
 use std::slice;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
 
-use crate::game::ListMoves;
+#[cfg(not(target_arch = "wasm32"))]
+use tracing::debug;
+
+use crate::game::{GiveDate, GiveRatings, ListMoves};
 use crate::moves::Move;
 
 /// A data structure for storing games by moves played. Useful for creating opening explorers.
@@ -47,6 +53,9 @@ pub struct MoveTree<M: Clone + Move + Eq + Hash, G: ListMoves<M>> {
 impl<M: Clone + Move + Eq + Hash, G: ListMoves<M>> MoveTree<M, G> {
     /// Creates a new `MoveTree<M, G>` from the provided `games`.
     pub fn new(games: Vec<G>) -> MoveTree<M, G> {
+        // WebAssembly in browsers has no clock, so building trees there isn't timed.
+        #[cfg(not(target_arch = "wasm32"))]
+        let (start, game_count) = (Instant::now(), games.len());
         let mut empty_games: Vec<G> = Vec::new();
         let mut game_tree: HashMap<M, Box<MoveTree<M, G>>> = HashMap::new();
 
@@ -72,7 +81,7 @@ impl<M: Clone + Move + Eq + Hash, G: ListMoves<M>> MoveTree<M, G> {
                 };
 
                 for move_ in moves_iter {
-                    if let None = current_position.game_tree.get(&move_) {
+                    if !current_position.game_tree.contains_key(&move_) {
                         // Have to construct hashmap for the first time.
                         current_position.game_tree.insert(
                             move_.clone(),
@@ -92,9 +101,16 @@ impl<M: Clone + Move + Eq + Hash, G: ListMoves<M>> MoveTree<M, G> {
             }
         }
 
+        #[cfg(not(target_arch = "wasm32"))]
+        debug!(
+            games = game_count,
+            elapsed = ?start.elapsed(),
+            "Built move tree"
+        );
+
         MoveTree {
             games: empty_games,
-            game_tree: game_tree,
+            game_tree,
         }
     }
 
@@ -103,7 +119,7 @@ impl<M: Clone + Move + Eq + Hash, G: ListMoves<M>> MoveTree<M, G> {
     // games.
     /// Returns a subset of the move tree where only games with the next move being `chess_move`
     /// are included.
-    pub fn with_next(&self, chess_move: &M) -> MoveTreeView<M, G> {
+    pub fn with_next(&self, chess_move: &M) -> MoveTreeView<'_, M, G> {
         if let Some(next_position) = self.game_tree.get(chess_move) {
             MoveTreeView::new(Some(next_position))
         } else {
@@ -113,22 +129,26 @@ impl<M: Clone + Move + Eq + Hash, G: ListMoves<M>> MoveTree<M, G> {
 
     /// Returns a view of this `MoveTree<M, G>`, which can be iterated over to find all games in
     /// the `MoveTree<M, G>`.
-    pub fn view(&self) -> MoveTreeView<M, G> {
+    pub fn view(&self) -> MoveTreeView<'_, M, G> {
         MoveTreeView::new(Some(self))
     }
 }
 
-#[derive(Clone)]
 /// A view of a subtree within a `MoveTree<M, G>`.
 pub struct MoveTreeView<'a, M: Clone + Move + Eq + Hash, G: ListMoves<M>> {
     game_tree: Option<&'a MoveTree<M, G>>,
 }
 
+// Deriving `Clone` would needlessly require games to be `Clone`, since a view only borrows them.
+impl<'a, M: Clone + Move + Eq + Hash, G: ListMoves<M>> Clone for MoveTreeView<'a, M, G> {
+    fn clone(&self) -> Self {
+        MoveTreeView::new(self.game_tree)
+    }
+}
+
 impl<'a, M: Clone + Move + Eq + Hash, G: ListMoves<M>> MoveTreeView<'a, M, G> {
     fn new(game_tree: Option<&'a MoveTree<M, G>>) -> MoveTreeView<'a, M, G> {
-        MoveTreeView {
-            game_tree: game_tree,
-        }
+        MoveTreeView { game_tree }
     }
 
     /// Behaves in the same way as `MoveTree::with_next`.
@@ -150,9 +170,80 @@ impl<'a, M: Clone + Move + Eq + Hash, G: ListMoves<M>> MoveTreeView<'a, M, G> {
     }
 
     /// Returns all moves that occur within the `MoveTreeView<M, G>` after this one.
-    pub fn branches(&self) -> Option<hash_map::Keys<M, Box<MoveTree<M, G>>>> {
+    pub fn branches(&self) -> Option<hash_map::Keys<'_, M, Box<MoveTree<M, G>>>> {
         self.game_tree.map(|tree| tree.game_tree.keys())
     }
+
+    /// Returns each move that occurs after this one together with a view of the games that
+    /// continue with it, in no particular order.
+    pub fn children(&self) -> Vec<(&'a M, MoveTreeView<'a, M, G>)> {
+        match self.game_tree {
+            Some(tree) => tree
+                .game_tree
+                .iter()
+                .map(|(move_, subtree)| (move_, MoveTreeView::new(Some(subtree))))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+impl<'a, M, G> MoveTreeView<'a, M, G>
+where
+    M: Clone + Move + Eq + Hash,
+    G: GiveDate + GiveRatings + ListMoves<M>,
+{
+    /// Returns up to `count` games of each kind that represent the games of the view, for
+    /// showing how the position was handled in practice.
+    pub fn examples(&self, count: usize) -> Examples<'a, G> {
+        let mut rated = self
+            .iter()
+            .filter(|game| game.average_rating().is_some())
+            .collect::<Vec<_>>();
+        let mut recent = self.iter().collect::<Vec<_>>();
+
+        rated.sort_by_key(|game| Reverse((game.average_rating(), game.date(), game.time())));
+        recent.sort_by_key(|game| Reverse((game.date(), game.time(), game.average_rating())));
+        rated.truncate(count);
+        recent.truncate(count);
+
+        Examples {
+            highest_rated: rated,
+            most_recent: recent,
+        }
+    }
+}
+
+/// Games chosen to represent the games of a `MoveTreeView<M, G>`, as given by
+/// [`MoveTreeView::examples`].
+#[derive(Debug)]
+pub struct Examples<'a, G> {
+    highest_rated: Vec<&'a G>,
+    most_recent: Vec<&'a G>,
+}
+
+// Deriving `Clone` would needlessly require games to be `Clone`, since examples only borrow them.
+impl<'a, G> Clone for Examples<'a, G> {
+    fn clone(&self) -> Self {
+        Examples {
+            highest_rated: self.highest_rated.clone(),
+            most_recent: self.most_recent.clone(),
+        }
+    }
+}
+
+impl<'a, G> Examples<'a, G> {
+    /// Returns the games between the highest rated players by their average rating, highest
+    /// first. Games without a known rating are left out.
+    pub fn highest_rated(&self) -> &[&'a G] {
+        &self.highest_rated
+    }
+
+    /// Returns the games played most recently, by date and start time, newest first. Games with
+    /// an unknown date come last.
+    pub fn most_recent(&self) -> &[&'a G] {
+        &self.most_recent
+    }
 }
 
 /// An iterator over games in a `MoveTree<M, G>`.
@@ -195,7 +286,7 @@ impl<'a, M: Clone + Move + Eq + Hash, G: ListMoves<M>> InternalIter<'a, M, G> {
 
         InternalIter {
             vec_iter: game_tree.games.iter(),
-            node_stack: node_stack,
+            node_stack,
         }
     }
 }
@@ -232,7 +323,7 @@ mod tests {
     use super::{MoveTree, MoveTreeView};
     use crate::game::test_utils::*;
 
-    use crate::game::GameMoves;
+    use crate::game::{Game, GameMoves, GivePlayers};
     use crate::moves::Move;
     use crate::AlgebraicMove;
 
@@ -264,7 +355,7 @@ mod tests {
             }
         }
 
-        games.len() == 0
+        games.is_empty()
     }
 
     #[fixture]
@@ -347,6 +438,31 @@ mod tests {
         assert_eq!(branches, expected_branches);
     }
 
+    #[rstest]
+    fn children_should_view_games_after_each_move(move_tree: AlgebraicGameTree) {
+        let e4 = AlgebraicMove::from_algebraic(String::from("e4"));
+
+        let mut children = move_tree
+            .view()
+            .with_next(&e4)
+            .children()
+            .into_iter()
+            .map(|(move_, view)| (move_.clone().to_algebraic(), view.iter().count()))
+            .collect::<Vec<_>>();
+
+        children.sort();
+
+        assert_eq!(
+            children,
+            vec![(String::from("c5"), 2), (String::from("e5"), 2)]
+        );
+        assert!(move_tree
+            .view()
+            .with_next(&AlgebraicMove::from_algebraic(String::from("c4")))
+            .children()
+            .is_empty());
+    }
+
     #[rstest]
     fn move_trees_should_allow_unplayed_games(move_tree_with_aborts: AlgebraicGameTree) {
         assert!(contains_same_games(
@@ -354,4 +470,35 @@ mod tests {
             vec![unplayed_game(), ruy_lopez(), unplayed_game()]
         ));
     }
+
+    #[rstest]
+    fn examples_should_pick_highest_rated_and_most_recent_games() {
+        let game = |white: &str, elo: &str, date: &str, moves: &str| {
+            parse_game(&format!(
+                "[White \"{}\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n[Date \"{}\"]\n\
+                 [WhiteElo \"{}\"]\n{} 1-0",
+                white, date, elo, moves
+            ))
+        };
+        let move_tree = MoveTree::new(vec![
+            game("alice", "1500", "2021.06.01", "1. e4 e5"),
+            game("carol", "2400", "2019.01.01", "1. e4 c5"),
+            game("dave", "?", "2022.01.01", "1. e4 e6"),
+            game("erin", "2000", "????.??.??", "1. e4 d5"),
+            game("frank", "2800", "2023.01.01", "1. d4 d5"),
+        ]);
+        let examples = move_tree
+            .view()
+            .with_next(&AlgebraicMove::from_algebraic(String::from("e4")))
+            .examples(2);
+        let names = |games: &[&Game<AlgebraicMove>]| {
+            games
+                .iter()
+                .map(|game| String::from(game.white_player().unwrap()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names(examples.highest_rated()), vec!["carol", "erin"]);
+        assert_eq!(names(examples.most_recent()), vec!["dave", "alice"]);
+    }
 }
@@ -1,3 +1,5 @@
+use crate::error::{self, Error};
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 /// A simple way to represent chess moves by a string containing the moves in algebraic notation.
 pub struct AlgebraicMove(String);
@@ -27,14 +29,15 @@ pub trait Move {
 }
 
 /// Errors related to invalid algebraic notation.
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, thiserror::Error)]
+#[error("{message}")]
 pub struct SANError {
     message: String,
 }
 
 impl SANError {
     pub fn new(message: String) -> SANError {
-        SANError { message: message }
+        SANError { message }
     }
 
     pub fn message(&self) -> &str {
@@ -58,22 +61,16 @@ impl AlgebraicMove {
     }
 
     fn is_file(file: char) -> Result<(), SANError> {
-        if file < 'a' || file > 'h' {
-            Err(SANError::new(String::from(format!(
-                "Invalid file: {file}",
-                file = file
-            ))))
+        if !('a'..='h').contains(&file) {
+            Err(SANError::new(format!("Invalid file: {file}", file = file)))
         } else {
             Ok(())
         }
     }
 
     fn is_rank(rank: char) -> Result<(), SANError> {
-        if rank < '1' || rank > '8' {
-            Err(SANError::new(String::from(format!(
-                "Invalid rank: {rank}",
-                rank = rank
-            ))))
+        if !('1'..='8').contains(&rank) {
+            Err(SANError::new(format!("Invalid rank: {rank}", rank = rank)))
         } else {
             Ok(())
         }
@@ -84,10 +81,10 @@ impl AlgebraicMove {
         let is_file = AlgebraicMove::is_file(rank).is_ok();
 
         if !is_rank && !is_file {
-            Err(SANError::new(String::from(format!(
+            Err(SANError::new(format!(
                 "Invalid rank/file: {rank}",
                 rank = rank
-            ))))
+            )))
         } else {
             Ok(())
         }
@@ -95,10 +92,10 @@ impl AlgebraicMove {
 
     fn is_piece(piece: char) -> Result<(), SANError> {
         if !"NBRQK".contains(piece) {
-            Err(SANError::new(String::from(format!(
+            Err(SANError::new(format!(
                 "Invalid piece: {piece}",
                 piece = piece
-            ))))
+            )))
         } else {
             Ok(())
         }
@@ -109,10 +106,10 @@ impl AlgebraicMove {
         let is_file = AlgebraicMove::is_file(piece).is_ok();
 
         if !is_piece && !is_file {
-            Err(SANError::new(String::from(format!(
+            Err(SANError::new(format!(
                 "Invalid piece/file: {piece}",
                 piece = piece
-            ))))
+            )))
         } else {
             Ok(())
         }
@@ -139,10 +136,10 @@ impl AlgebraicMove {
 
     fn is_promotion(symbol: char) -> Result<(), SANError> {
         if symbol != '=' {
-            Err(SANError::new(String::from(format!(
+            Err(SANError::new(format!(
                 "Invalid promotion symbol: {symbol}",
                 symbol = symbol
-            ))))
+            )))
         } else {
             Ok(())
         }
@@ -158,13 +155,21 @@ impl Move for AlgebraicMove {
                 test_algebraic.pop();
             }
 
+            // Moves are checked a byte at a time, which only works for ASCII.
+            if !test_algebraic.is_ascii() {
+                return Err(SANError::new(format!(
+                    "Invalid characters: {algebraic}",
+                    algebraic = algebraic
+                )));
+            }
+
             let move_length = test_algebraic.len();
 
             if move_length == 0 || move_length == 1 {
-                return Err(SANError::new(String::from(format!(
+                return Err(SANError::new(format!(
                     "Move is too short: {algebraic}",
                     algebraic = algebraic
-                ))));
+                )));
             } else if move_length == 2 {
                 // Should be a typical pawn move, so only a coordinate is specified.
                 AlgebraicMove::is_coordinate(&test_algebraic)?;
@@ -209,16 +214,16 @@ impl Move for AlgebraicMove {
                 AlgebraicMove::is_file(test_algebraic.remove(0))?;
                 AlgebraicMove::is_coordinate(&test_algebraic)?;
             } else {
-                return Err(SANError::new(String::from(format!(
+                return Err(SANError::new(format!(
                     "Move is too long: {test_algebraic}",
                     test_algebraic = test_algebraic
-                ))));
+                )));
             }
         } else {
             return Err(SANError::new(String::from("Empty string")));
         }
 
-        Ok(AlgebraicMove(String::from(algebraic)))
+        Ok(AlgebraicMove(algebraic))
     }
 
     fn to_algebraic(self) -> String {
@@ -226,6 +231,21 @@ impl Move for AlgebraicMove {
     }
 }
 
+/// Returns the moves of `line`, given in SAN, such as a line of moves someone asked about.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidArgument`] if one of the moves isn't valid SAN.
+pub fn parse_line(line: &[String]) -> error::Result<Vec<AlgebraicMove>> {
+    line.iter()
+        .map(|move_| {
+            AlgebraicMove::try_from_algebraic(move_.clone()).map_err(|error| {
+                Error::InvalidArgument(format!("Invalid move {}: {}", move_, error))
+            })
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use rstest::*;
@@ -269,6 +289,7 @@ mod tests {
         case("b8=a", "Invalid piece: a"),
         case("d8/", "Invalid piece: d"),
         case("Nxa8=B", "Invalid file: N"),
+        case("é4", "Invalid characters: é4"),
         case("", "Empty string")
     )]
     fn algebraic_move_should_reject_invalid_san(invalid_san_move: &str, expected_message: &str) {
@@ -0,0 +1,315 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::sync::OnceLock;
+
+use crate::game::ListMoves;
+use crate::moves::{AlgebraicMove, Move};
+use crate::position;
+use crate::zobrist;
+
+/// The table of openings shipped with chesshound, as tab separated ECO codes, names, and the
+/// moves that define each opening.
+const EMBEDDED_TABLE: &str = include_str!("openings.tsv");
+
+/// A named opening and the moves that define it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Opening {
+    eco: String,
+    name: String,
+    moves: Vec<String>,
+}
+
+impl Opening {
+    /// Returns the opening's code in the Encyclopaedia of Chess Openings, such as "B90".
+    pub fn eco(&self) -> &str {
+        &self.eco
+    }
+
+    /// Returns the opening's name, such as "Sicilian Defense: Najdorf Variation".
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the moves that define the opening in SAN.
+    pub fn moves(&self) -> &[String] {
+        &self.moves
+    }
+
+    /// Returns the opening's name split into its family and variations.
+    pub fn opening_name(&self) -> OpeningName {
+        OpeningName::from(self.name.as_str())
+    }
+}
+
+impl fmt::Display for Opening {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.eco, self.name)
+    }
+}
+
+/// The name of an opening, split into the family it belongs to, such as "Caro-Kann Defense", and
+/// the variations within it from broadest to narrowest, such as "Advance Variation". Names are
+/// written as the opening table writes them: `Family: Variation, Subvariation`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OpeningName {
+    family: String,
+    variations: Vec<String>,
+}
+
+impl OpeningName {
+    /// Returns the family of openings this one belongs to.
+    pub fn family(&self) -> &str {
+        &self.family
+    }
+
+    /// Returns the variations of the family this opening is in, from broadest to narrowest.
+    pub fn variations(&self) -> &[String] {
+        &self.variations
+    }
+
+    /// Returns the narrowest variation this opening is in, or `None` if it's the main line of its
+    /// family.
+    pub fn variation(&self) -> Option<&str> {
+        self.variations.last().map(String::as_str)
+    }
+}
+
+impl From<&str> for OpeningName {
+    fn from(name: &str) -> OpeningName {
+        match name.split_once(':') {
+            Some((family, variations)) => OpeningName {
+                family: String::from(family.trim()),
+                variations: variations
+                    .split(',')
+                    .map(|variation| String::from(variation.trim()))
+                    .collect(),
+            },
+            None => OpeningName {
+                family: String::from(name.trim()),
+                variations: Vec::new(),
+            },
+        }
+    }
+}
+
+impl fmt::Display for OpeningName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.family)?;
+
+        if !self.variations.is_empty() {
+            write!(f, ": {}", self.variations.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the name of the deepest opening in the embedded table that `moves` reach, or `None`
+/// if they never reach one, so that lines can be labeled with names rather than their moves.
+pub fn name_for(moves: &[AlgebraicMove]) -> Option<OpeningName> {
+    Openings::embedded()
+        .classify_moves(moves.iter().cloned())
+        .map(Opening::opening_name)
+}
+
+/// A table of openings that games can be classified by.
+///
+/// Openings are recognized by the position their moves reach, so games that transpose into an
+/// opening are classified the same as games that play its moves in order.
+#[derive(Debug, Clone)]
+pub struct Openings {
+    openings: Vec<Opening>,
+    by_position: HashMap<u64, usize>,
+    max_plies: usize,
+}
+
+impl Openings {
+    /// Returns the table of openings shipped with chesshound, which covers the main lines of
+    /// every ECO volume.
+    pub fn embedded() -> &'static Openings {
+        static EMBEDDED: OnceLock<Openings> = OnceLock::new();
+
+        EMBEDDED.get_or_init(|| Openings::parse(EMBEDDED_TABLE).expect("invalid opening table"))
+    }
+
+    /// Parses a table of openings with a header row followed by a row for each opening, holding
+    /// its ECO code, its name, and its moves in PGN movetext separated by tabs. When two openings
+    /// reach the same position, the first one is kept.
+    ///
+    /// Returns an error if a row is missing a column or holds an illegal move.
+    pub fn parse(table: &str) -> io::Result<Openings> {
+        let mut openings = Vec::new();
+        let mut by_position = HashMap::new();
+        let mut max_plies = 0;
+
+        for (index, row) in table.lines().enumerate().skip(1) {
+            if row.trim().is_empty() {
+                continue;
+            }
+
+            let invalid = |reason: &str| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid opening on line {}: {}", index + 1, reason),
+                )
+            };
+
+            let columns = row.split('\t').collect::<Vec<_>>();
+
+            let (eco, name, movetext) = match columns[..] {
+                [eco, name, movetext] => (eco, name, movetext),
+                _ => return Err(invalid("expected 3 columns")),
+            };
+
+            let moves = movetext
+                .split_whitespace()
+                .filter(|token| !token.ends_with('.'))
+                .map(|token| String::from(token.trim_end_matches(['+', '#'])))
+                .collect::<Vec<_>>();
+
+            let positions = position::replay(
+                moves
+                    .iter()
+                    .map(|move_| AlgebraicMove::from_algebraic(move_.clone())),
+            );
+
+            if positions.len() != moves.len() + 1 {
+                return Err(invalid("illegal move"));
+            }
+
+            by_position
+                .entry(zobrist::hash_position(positions.last().unwrap()))
+                .or_insert(openings.len());
+
+            max_plies = max_plies.max(moves.len());
+
+            openings.push(Opening {
+                eco: String::from(eco),
+                name: String::from(name),
+                moves,
+            });
+        }
+
+        Ok(Openings {
+            openings,
+            by_position,
+            max_plies,
+        })
+    }
+
+    /// Returns every opening in the table, in the order they were given.
+    pub fn openings(&self) -> &[Opening] {
+        &self.openings
+    }
+
+    /// Returns the deepest opening reached by `moves`, or `None` if they never reach a position
+    /// in the table. Positions after an illegal move aren't considered.
+    pub fn classify_moves<M, I>(&self, moves: I) -> Option<&Opening>
+    where
+        M: Move,
+        I: IntoIterator<Item = M>,
+    {
+        position::replay(moves.into_iter().take(self.max_plies))
+            .iter()
+            .rev()
+            .find_map(|position| self.by_position.get(&zobrist::hash_position(position)))
+            .map(|index| &self.openings[*index])
+    }
+
+    /// Returns the deepest opening reached in `game`, or `None` if it never reaches a position
+    /// in the table.
+    pub fn classify<M, G>(&self, game: &G) -> Option<&Opening>
+    where
+        M: 'static + Clone + Move,
+        G: ListMoves<M>,
+    {
+        self.classify_moves(game.list_moves())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::{name_for, Openings};
+    use crate::game::test_utils::*;
+    use crate::moves::Move;
+    use crate::AlgebraicMove;
+
+    fn moves(san_moves: &str) -> Vec<AlgebraicMove> {
+        san_moves
+            .split_whitespace()
+            .map(|san| AlgebraicMove::from_algebraic(String::from(san)))
+            .collect()
+    }
+
+    #[rstest]
+    fn embedded_openings_should_classify_their_own_moves() {
+        let openings = Openings::embedded();
+
+        for opening in openings.openings() {
+            let classified = openings.classify_moves(
+                opening
+                    .moves()
+                    .iter()
+                    .map(|move_| AlgebraicMove::from_algebraic(move_.clone())),
+            );
+
+            assert_eq!(classified, Some(opening));
+        }
+    }
+
+    #[rstest(san_moves, expected,
+        case(
+            "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 a6 f3 e5",
+            Some("B90 Sicilian Defense: Najdorf Variation")
+        ),
+        // The Four Knights reached by transposition.
+        case(
+            "Nf3 Nf6 Nc3 Nc6 e4 e5 Bb5",
+            Some("C47 Four Knights Game")
+        ),
+        case("a3", None),
+    )]
+    fn classify_moves_should_find_deepest_opening(san_moves: &str, expected: Option<&str>) {
+        let opening = Openings::embedded().classify_moves(moves(san_moves));
+
+        assert_eq!(
+            opening.map(|opening| opening.to_string()),
+            expected.map(String::from)
+        );
+    }
+
+    #[rstest]
+    fn name_for_should_split_family_and_variations() {
+        let name = name_for(&moves("e4 c6 d4 d5 e5 Bf5")).unwrap();
+
+        assert_eq!(name.family(), "Caro-Kann Defense");
+        assert_eq!(name.variation(), Some("Advance Variation"));
+        assert_eq!(name.to_string(), "Caro-Kann Defense: Advance Variation");
+
+        let name = name_for(&moves("e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 a6 Be3")).unwrap();
+
+        assert_eq!(name.variations(), ["Najdorf Variation", "English Attack"]);
+        assert_eq!(name_for(&moves("e4")).unwrap().variation(), None);
+        assert_eq!(name_for(&moves("a3")), None);
+    }
+
+    #[rstest]
+    fn classify_should_use_game_moves() {
+        let opening = Openings::embedded().classify(&ruy_lopez()).unwrap();
+
+        assert_eq!(opening.eco(), "C60");
+        assert_eq!(opening.name(), "Ruy Lopez");
+    }
+
+    #[rstest(
+        table,
+        case("eco\tname\tpgn\nC20\tKing's Pawn Game\n"),
+        case("eco\tname\tpgn\nC20\tKing's Pawn Game\t1. e4 e4\n")
+    )]
+    fn parse_should_reject_invalid_tables(table: &str) {
+        assert!(Openings::parse(table).is_err());
+    }
+}
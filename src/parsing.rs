@@ -1,39 +1,68 @@
-use std::mem;
+use std::convert::TryFrom;
+use std::io::{self, BufRead, Read};
+use std::ops::ControlFlow;
+use std::time::Duration;
 
-use pgn_reader::{RawHeader, SanPlus, Skip, Visitor};
+use chrono::NaiveDate;
+use pgn_reader::{RawComment, RawTag, Reader, SanPlus, Skip, Visitor};
+use tracing::{debug, trace};
 
-use crate::game::GameResult;
+use crate::clock;
+use crate::error::Result;
+use crate::eval::{self, Eval};
+use crate::game::{Game, GameMetadata, GameResult};
+use crate::moves::AlgebraicMove;
 
 /// A visitor designed to work with the `pgn_reader` crate. Extracts relevant game information from
 /// pgn input.
-pub struct GameParser {
-    pgn_game: PGNGame,
-}
+pub struct GameParser;
 
 impl GameParser {
     /// Creates a new `GameParser`.
     pub fn new() -> GameParser {
-        GameParser {
-            pgn_game: PGNGame::new(),
-        }
+        GameParser
+    }
+}
+
+impl Default for GameParser {
+    fn default() -> GameParser {
+        GameParser::new()
     }
 }
 
+/// Returns every game in the PGN read from `input`.
+///
+/// # Errors
+///
+/// Returns an error if `input` can't be read, or if a game has no result.
+pub fn read_games<R: Read>(input: R) -> Result<Vec<Game<AlgebraicMove>>> {
+    let mut reader = Reader::new(input);
+    let mut games = Vec::new();
+
+    while let Some(pgn_game) = reader.read_game(&mut GameParser::new())? {
+        games.push(Game::try_from(pgn_game)?);
+    }
+
+    Ok(games)
+}
+
 /// The output of GameParser.
 pub struct PGNGame {
     moves: Vec<String>,
+    clocks: Vec<Option<Duration>>,
+    evals: Vec<Option<Eval>>,
     result: Option<GameResult>,
-    white_player: Option<String>,
-    black_player: Option<String>,
+    metadata: GameMetadata,
 }
 
 impl PGNGame {
     fn new() -> PGNGame {
         PGNGame {
             moves: Vec::new(),
+            clocks: Vec::new(),
+            evals: Vec::new(),
             result: None,
-            white_player: None,
-            black_player: None,
+            metadata: GameMetadata::new(),
         }
     }
 
@@ -42,63 +71,263 @@ impl PGNGame {
         &self.moves
     }
 
+    /// Returns the clock time remaining after each move, taken from `[%clk]` comments. Contains
+    /// one entry per move, which is `None` if no clock was recorded for that move.
+    pub fn clocks(&self) -> &Vec<Option<Duration>> {
+        &self.clocks
+    }
+
+    /// Returns the evaluation after each move, taken from `[%eval]` comments. Contains one entry
+    /// per move, which is `None` if no evaluation was recorded for that move.
+    pub fn evals(&self) -> &Vec<Option<Eval>> {
+        &self.evals
+    }
+
     /// Returns the result found within the PGN input.
     pub fn result(&self) -> Option<GameResult> {
         self.result
     }
 
-    /// Returns the name of the player playing white found within the PGN input.
-    pub fn white_player(&self) -> Option<&str> {
-        match &self.white_player {
-            Some(string) => Some(&string),
-            None => None,
-        }
+    /// Returns the metadata found within the headers of the PGN input.
+    pub fn metadata(&self) -> &GameMetadata {
+        &self.metadata
     }
+}
 
-    /// Returns the name of the player playing black found within the PGN input.
-    pub fn black_player(&self) -> Option<&str> {
-        match &self.black_player {
-            Some(string) => Some(&string),
-            None => None,
-        }
+/// Returns `parsed`, logging that the value of the header `key` was ignored if it couldn't be
+/// parsed. Placeholders for unknown values, such as `?`, aren't logged.
+pub(crate) fn log_unparsed<T>(key: &str, value: &str, parsed: Option<T>) -> Option<T> {
+    if parsed.is_none() && !value.contains('?') && value != "-" {
+        debug!(
+            header = key,
+            value, "Ignoring header value that couldn't be parsed"
+        );
     }
+
+    parsed
+}
+
+/// Parses dates in the PGN `yyyy.mm.dd` format.
+pub(crate) fn parse_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y.%m.%d").ok()
 }
 
 impl Visitor for GameParser {
-    type Result = PGNGame;
-
-    fn header(&mut self, key: &[u8], value: RawHeader<'_>) {
-        if key == b"Result" {
-            self.pgn_game.result =
-                Some(GameResult::from(String::from(value.decode_utf8().unwrap())));
-        } else if key == b"White" {
-            self.pgn_game.white_player = Some(String::from(value.decode_utf8().unwrap()));
-        } else if key == b"Black" {
-            self.pgn_game.black_player = Some(String::from(value.decode_utf8().unwrap()));
+    type Tags = PGNGame;
+    type Movetext = PGNGame;
+    type Output = PGNGame;
+
+    fn begin_tags(&mut self) -> ControlFlow<PGNGame, PGNGame> {
+        ControlFlow::Continue(PGNGame::new())
+    }
+
+    fn tag(
+        &mut self,
+        pgn_game: &mut PGNGame,
+        key: &[u8],
+        value: RawTag<'_>,
+    ) -> ControlFlow<PGNGame> {
+        let key = String::from_utf8_lossy(key);
+        let value = value.decode_utf8_lossy();
+
+        if key == "Result" {
+            pgn_game.result = log_unparsed(&key, &value, value.parse().ok());
+        } else {
+            pgn_game.metadata.set_tag(&key, &value);
         }
+
+        ControlFlow::Continue(())
+    }
+
+    fn begin_movetext(&mut self, pgn_game: PGNGame) -> ControlFlow<PGNGame, PGNGame> {
+        ControlFlow::Continue(pgn_game)
+    }
+
+    fn san(&mut self, pgn_game: &mut PGNGame, san_plus: SanPlus) -> ControlFlow<PGNGame> {
+        pgn_game.moves.push(san_plus.to_string());
+        pgn_game.clocks.push(None);
+        pgn_game.evals.push(None);
+
+        ControlFlow::Continue(())
+    }
+
+    fn comment(&mut self, pgn_game: &mut PGNGame, comment: RawComment<'_>) -> ControlFlow<PGNGame> {
+        let comment = String::from_utf8_lossy(comment.as_bytes());
+
+        if let (Some(clock), Some(last_clock)) = (
+            clock::parse_clock_comment(&comment),
+            pgn_game.clocks.last_mut(),
+        ) {
+            *last_clock = Some(clock);
+        }
+
+        if let (Some(eval), Some(last_eval)) = (
+            eval::parse_eval_comment(&comment),
+            pgn_game.evals.last_mut(),
+        ) {
+            *last_eval = Some(eval);
+        }
+
+        ControlFlow::Continue(())
+    }
+
+    fn begin_variation(&mut self, _: &mut PGNGame) -> ControlFlow<PGNGame, Skip> {
+        ControlFlow::Continue(Skip(true))
     }
 
-    fn san(&mut self, san_plus: SanPlus) {
-        self.pgn_game.moves.push(san_plus.to_string());
+    fn end_game(&mut self, pgn_game: PGNGame) -> PGNGame {
+        trace!(
+            white = ?pgn_game.metadata.white_player(),
+            black = ?pgn_game.metadata.black_player(),
+            moves = pgn_game.moves.len(),
+            "Parsed game"
+        );
+
+        pgn_game
     }
+}
+
+/// Reads the games of PGN one at a time exactly as they were written, with their comments, NAGs,
+/// variations, unknown tags, and formatting, so that games chesshound doesn't change can be
+/// written back byte for byte.
+///
+/// Each game is given with the blank lines and anything else between it and the next game, so
+/// that writing every game read gives back the input as it was.
+///
+/// # Examples
+///
+/// ```
+/// use chesshound::parsing::RawGameReader;
+///
+/// let pgn = "[White \"alice\"]\n\n1. e4 { [%clk 0:03:00] } e5 $1 1-0\n\n1. d4 *\n";
+/// let mut reader = RawGameReader::new(pgn.as_bytes());
+///
+/// assert_eq!(
+///     reader.read_raw_game().unwrap().unwrap(),
+///     b"[White \"alice\"]\n\n1. e4 { [%clk 0:03:00] } e5 $1 1-0\n\n"
+/// );
+/// assert_eq!(reader.read_raw_game().unwrap().unwrap(), b"1. d4 *\n");
+/// assert_eq!(reader.read_raw_game().unwrap(), None);
+/// ```
+pub struct RawGameReader<R> {
+    input: R,
+    next_line: Option<Vec<u8>>,
+}
 
-    fn begin_variation(&mut self) -> Skip {
-        Skip(true)
+impl<R: BufRead> RawGameReader<R> {
+    /// Creates a reader of the games in `input`.
+    pub fn new(input: R) -> RawGameReader<R> {
+        RawGameReader {
+            input,
+            next_line: None,
+        }
     }
 
-    fn end_game(&mut self) -> Self::Result {
-        mem::replace(&mut self.pgn_game, PGNGame::new())
+    /// Returns the next game as it was written, or `None` once there are no games left.
+    ///
+    /// A game ends where a tag line follows its moves, or where moves follow its result, outside
+    /// of comments. Games aren't checked to be valid PGN.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the input can't be read.
+    pub fn read_raw_game(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut game = Vec::new();
+        let mut in_comment = false;
+        let mut has_moves = false;
+        let mut has_result = false;
+
+        loop {
+            let line = match self.next_line.take() {
+                Some(line) => line,
+                None => {
+                    let mut line = Vec::new();
+
+                    if self.input.read_until(b'\n', &mut line)? == 0 {
+                        break;
+                    }
+
+                    line
+                }
+            };
+
+            let text = line.trim_ascii_start();
+
+            if !in_comment {
+                if text.is_empty() || line.starts_with(b"%") {
+                    game.extend_from_slice(&line);
+                    continue;
+                }
+
+                if text.starts_with(b"[") && !has_moves {
+                    game.extend_from_slice(&line);
+                    continue;
+                }
+
+                if text.starts_with(b"[") || has_result {
+                    self.next_line = Some(line);
+                    break;
+                }
+            }
+
+            has_moves = true;
+            has_result |= scan_movetext(&line, &mut in_comment);
+            game.extend_from_slice(&line);
+        }
+
+        Ok(Some(game).filter(|game| !game.trim_ascii().is_empty()))
+    }
+}
+
+/// Scans a `line` of movetext, keeping track of whether it ends within a `{}` comment in
+/// `in_comment`, and returns whether a game result is given in it outside of comments.
+fn scan_movetext(line: &[u8], in_comment: &mut bool) -> bool {
+    let mut has_result = false;
+    let mut token = Vec::new();
+
+    for byte in line {
+        if *in_comment {
+            *in_comment = *byte != b'}';
+            continue;
+        }
+
+        match byte {
+            b'{' | b';' | b'(' | b')' => {}
+            byte if !byte.is_ascii_whitespace() => {
+                token.push(*byte);
+                continue;
+            }
+            _ => {}
+        }
+
+        has_result |= matches!(&token[..], b"1-0" | b"0-1" | b"1/2-1/2" | b"*");
+        token.clear();
+
+        match byte {
+            b'{' => *in_comment = true,
+            // The rest of the line is a comment.
+            b';' => return has_result,
+            _ => {}
+        }
     }
+
+    has_result || matches!(&token[..], b"1-0" | b"0-1" | b"1/2-1/2" | b"*")
 }
 
 #[cfg(test)]
 mod tests {
     use rstest::*;
 
-    use super::GameParser;
+    use super::{GameParser, RawGameReader};
+
+    use pgn_reader::Reader;
+
+    use std::time::Duration;
 
-    use pgn_reader::BufferedReader;
+    use chrono::{NaiveDate, NaiveTime};
 
+    use crate::clock::TimeControl;
+    use crate::eval::Eval;
     use crate::game::GameResult;
 
     #[rstest(pgn, expected_moves,
@@ -123,7 +352,7 @@ mod tests {
         )
     )]
     fn game_visitor_should_find_correct_moves(pgn: &[u8], expected_moves: Vec<String>) {
-        let mut reader = BufferedReader::new_cursor(&pgn[..]);
+        let mut reader = Reader::new(pgn);
 
         let mut game_parser = GameParser::new();
         let pgn_game = reader.read_game(&mut game_parser).unwrap().unwrap();
@@ -142,7 +371,7 @@ mod tests {
         case(b"1. e4 e5 2. Nf3 Nf6 3. Nxe5", None)
     )]
     fn game_visitor_should_find_correct_result(pgn: &[u8], expected_result: Option<GameResult>) {
-        let mut reader = BufferedReader::new_cursor(&pgn[..]);
+        let mut reader = Reader::new(pgn);
 
         let mut game_parser = GameParser::new();
         let pgn_game = reader.read_game(&mut game_parser).unwrap().unwrap();
@@ -171,12 +400,179 @@ mod tests {
         expected_white_player: Option<&str>,
         expected_black_player: Option<&str>,
     ) {
-        let mut reader = BufferedReader::new_cursor(&pgn[..]);
+        let mut reader = Reader::new(pgn);
+
+        let mut game_parser = GameParser::new();
+        let pgn_game = reader.read_game(&mut game_parser).unwrap().unwrap();
+
+        assert_eq!(pgn_game.metadata().white_player(), expected_white_player);
+        assert_eq!(pgn_game.metadata().black_player(), expected_black_player);
+    }
+
+    #[rstest(pgn, expected_clocks,
+        case(
+            b"1. e4 { [%clk 0:03:00] } 1... e5 { [%clk 0:02:58] } 2. Nf3 { [%clk 0:02:55.5] }",
+            vec![
+                Some(Duration::from_secs(180)),
+                Some(Duration::from_secs(178)),
+                Some(Duration::from_millis(175500)),
+            ]
+        ),
+        case(
+            b"1. e4 { [%eval 0.2] } e5 { [%eval 0.3] [%clk 0:00:59] } 2. Nf3",
+            vec![None, Some(Duration::from_secs(59)), None]
+        ),
+        case(
+            b"{ [%clk 0:10:00] } 1. d4 d5",
+            vec![None, None]
+        )
+    )]
+    fn game_visitor_should_find_correct_clocks(pgn: &[u8], expected_clocks: Vec<Option<Duration>>) {
+        let mut reader = Reader::new(pgn);
+
+        let mut game_parser = GameParser::new();
+        let pgn_game = reader.read_game(&mut game_parser).unwrap().unwrap();
+
+        assert_eq!(pgn_game.clocks(), &expected_clocks);
+    }
+
+    #[rstest(pgn, expected_evals,
+        case(
+            b"1. e4 { [%eval 0.2] } 1... e5 { [%eval 0.3] [%clk 0:00:59] } 2. Nf3",
+            vec![Some(Eval::Centipawns(20)), Some(Eval::Centipawns(30)), None]
+        ),
+        case(
+            b"1. f3 e5 2. g4 { [%eval #-1] } 2... Qh4#",
+            vec![None, None, Some(Eval::Mate(-1)), None]
+        )
+    )]
+    fn game_visitor_should_find_correct_evals(pgn: &[u8], expected_evals: Vec<Option<Eval>>) {
+        let mut reader = Reader::new(pgn);
+
+        let mut game_parser = GameParser::new();
+        let pgn_game = reader.read_game(&mut game_parser).unwrap().unwrap();
+
+        assert_eq!(pgn_game.evals(), &expected_evals);
+    }
+
+    #[rstest(
+        pgn,
+        expected_time_control,
+        case(
+            b"[TimeControl \"180+2\"]\n1. e4 e5",
+            Some(TimeControl::new(Duration::from_secs(180), Duration::from_secs(2)))
+        ),
+        case(b"[TimeControl \"-\"]\n1. e4 e5", None),
+        case(b"1. e4 e5", None)
+    )]
+    fn game_visitor_should_find_correct_time_control(
+        pgn: &[u8],
+        expected_time_control: Option<TimeControl>,
+    ) {
+        let mut reader = Reader::new(pgn);
+
+        let mut game_parser = GameParser::new();
+        let pgn_game = reader.read_game(&mut game_parser).unwrap().unwrap();
+
+        assert_eq!(pgn_game.metadata().time_control(), expected_time_control);
+    }
+
+    #[rstest(
+        pgn,
+        expected_white_rating,
+        expected_black_rating,
+        case(
+            b"[WhiteElo \"1500\"]\n[BlackElo \"2830\"]\n1. e4 e5",
+            Some(1500),
+            Some(2830)
+        ),
+        case(b"[WhiteElo \"?\"]\n[BlackElo \"\"]\n1. e4 e5", None, None),
+        case(b"1. e4 e5", None, None)
+    )]
+    fn game_visitor_should_find_correct_ratings(
+        pgn: &[u8],
+        expected_white_rating: Option<u16>,
+        expected_black_rating: Option<u16>,
+    ) {
+        let mut reader = Reader::new(pgn);
+
+        let mut game_parser = GameParser::new();
+        let pgn_game = reader.read_game(&mut game_parser).unwrap().unwrap();
+
+        assert_eq!(pgn_game.metadata().white_rating(), expected_white_rating);
+        assert_eq!(pgn_game.metadata().black_rating(), expected_black_rating);
+    }
+
+    #[rstest(
+        pgn,
+        expected_date,
+        expected_time,
+        case(
+            b"[Date \"2021.09.05\"]\n1. e4 e5",
+            Some(NaiveDate::from_ymd_opt(2021, 9, 5).unwrap()),
+            None
+        ),
+        case(
+            b"[Date \"2021.09.05\"]\n[UTCDate \"2021.09.06\"]\n[UTCTime \"01:30:00\"]\n1. e4 e5",
+            Some(NaiveDate::from_ymd_opt(2021, 9, 6).unwrap()),
+            Some(NaiveTime::from_hms_opt(1, 30, 0).unwrap())
+        ),
+        case(b"[Date \"2021.??.??\"]\n1. e4 e5", None, None)
+    )]
+    fn game_visitor_should_find_correct_date(
+        pgn: &[u8],
+        expected_date: Option<NaiveDate>,
+        expected_time: Option<NaiveTime>,
+    ) {
+        let mut reader = Reader::new(pgn);
 
         let mut game_parser = GameParser::new();
         let pgn_game = reader.read_game(&mut game_parser).unwrap().unwrap();
 
-        assert_eq!(pgn_game.white_player(), expected_white_player);
-        assert_eq!(pgn_game.black_player(), expected_black_player);
+        assert_eq!(pgn_game.metadata().date(), expected_date);
+        assert_eq!(pgn_game.metadata().time(), expected_time);
+    }
+
+    #[rstest(
+        pgn,
+        expected_link,
+        case(
+            b"[Site \"https://lichess.org/abcdefgh\"]\n1. e4 e5",
+            Some("https://lichess.org/abcdefgh")
+        ),
+        case(
+            b"[Site \"Chess.com\"]\n[Link \"https://www.chess.com/game/live/1\"]\n1. e4 e5",
+            Some("https://www.chess.com/game/live/1")
+        ),
+        case(b"[Site \"?\"]\n1. e4 e5", None)
+    )]
+    fn game_visitor_should_find_correct_link(pgn: &[u8], expected_link: Option<&str>) {
+        let mut reader = Reader::new(pgn);
+
+        let mut game_parser = GameParser::new();
+        let pgn_game = reader.read_game(&mut game_parser).unwrap().unwrap();
+
+        assert_eq!(pgn_game.metadata().link(), expected_link);
+    }
+
+    #[rstest]
+    fn raw_game_reader_should_give_back_games_as_written() {
+        let pgn = "\n[Event \"Club {1}\"]\r\n[Annotator \"carol\"]\r\n\r\n\
+                   1. e4 $1 {A comment\n[that looks like a tag]\n1-0} (1. d4 d5) e5 ; 0-1\n\
+                   2. Nf3   1-0\n\n\n\
+                   % An escaped line\n\
+                   1. d4 d5 1/2-1/2\n\
+                   1. c4 *";
+        let mut reader = RawGameReader::new(pgn.as_bytes());
+        let mut games = Vec::new();
+
+        while let Some(game) = reader.read_raw_game().unwrap() {
+            games.push(String::from_utf8(game).unwrap());
+        }
+
+        assert_eq!(games.len(), 3);
+        assert!(games[0].ends_with("2. Nf3   1-0\n\n\n% An escaped line\n"));
+        assert_eq!(games[1], "1. d4 d5 1/2-1/2\n");
+        assert_eq!(games.concat(), pgn);
     }
 }
@@ -0,0 +1,90 @@
+use shakmaty::{Chess, Position, Rank};
+
+use crate::moves::Move;
+use crate::position;
+
+/// The phases a chess game passes through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Phase {
+    Opening,
+    Middlegame,
+    Endgame,
+}
+
+impl Phase {
+    /// Returns the phase of a single position, judged only by the material on the board.
+    ///
+    /// Follows the same rules as the lichess game divider: the endgame starts once there are six
+    /// or fewer major and minor pieces left, and the middlegame once there are ten or fewer, or
+    /// either side has moved most of its pieces off its back rank.
+    fn of_position(position: &Chess) -> Phase {
+        let board = position.board();
+        let majors_and_minors = (board.occupied() ^ board.pawns() ^ board.kings()).count();
+
+        let white_back_rank = (board.white() & Rank::First).count();
+        let black_back_rank = (board.black() & Rank::Eighth).count();
+        let back_rank_sparse = white_back_rank < 4 || black_back_rank < 4;
+
+        if majors_and_minors <= 6 {
+            Phase::Endgame
+        } else if majors_and_minors <= 10 || back_rank_sparse {
+            Phase::Middlegame
+        } else {
+            Phase::Opening
+        }
+    }
+}
+
+/// Returns the phase of the game before each move in `moves`. Phases never go backwards, so once
+/// the middlegame is reached, later positions are never considered part of the opening.
+///
+/// The result is shorter than `moves` if the game contains an illegal move, since the positions
+/// after it can't be known.
+pub fn phases<M: Move, I: IntoIterator<Item = M>>(moves: I) -> Vec<Phase> {
+    let mut positions = position::replay(moves);
+    // The final position has no move played from it.
+    positions.pop();
+
+    let mut current_phase = Phase::Opening;
+
+    positions
+        .iter()
+        .map(|position| {
+            current_phase = current_phase.max(Phase::of_position(position));
+            current_phase
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::{phases, Phase};
+    use crate::game::test_utils::*;
+    use crate::game::ListMoves;
+
+    #[rstest]
+    fn openings_should_start_in_opening_phase() {
+        let phases = phases(queens_gambit().list_moves());
+
+        assert_eq!(phases, vec![Phase::Opening; 3]);
+    }
+
+    #[rstest]
+    fn phases_should_progress_as_material_is_traded() {
+        let game = parse_game(
+            "[White \"a\"]\n[Black \"b\"]\n[Result \"1/2-1/2\"]\n\
+             1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Bxc6 dxc6 5. d4 exd4 6. Qxd4 Qxd4 7. Nxd4 Bd6 \
+             8. Nc3 Ne7 9. Be3 O-O 10. O-O-O Re8 11. Rhe1 Bd7 12. Nf5 Bxf5 13. exf5 Nxf5 \
+             14. Bg5 Rxe1 15. Rxe1 f6 16. Be3 Nxe3 17. Rxe3 Kf7 1/2-1/2",
+        );
+
+        let phases = phases(game.list_moves());
+
+        assert_eq!(phases.len(), 34);
+        assert_eq!(phases[0], Phase::Opening);
+        assert_eq!(phases[17], Phase::Middlegame);
+        assert_eq!(phases[33], Phase::Endgame);
+    }
+}
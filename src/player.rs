@@ -0,0 +1,197 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::Error;
+
+/// A title awarded by FIDE, a national federation, or a site, as written in the WhiteTitle and
+/// BlackTitle PGN tags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Title {
+    /// Grandmaster.
+    GM,
+    /// International Master.
+    IM,
+    /// FIDE Master.
+    FM,
+    /// Candidate Master.
+    CM,
+    /// National Master.
+    NM,
+    /// Woman Grandmaster.
+    WGM,
+    /// Woman International Master.
+    WIM,
+    /// Woman FIDE Master.
+    WFM,
+    /// Woman Candidate Master.
+    WCM,
+    /// Woman National Master.
+    WNM,
+    /// Lichess Master, given to some lichess accounts.
+    LM,
+    /// A computer account.
+    BOT,
+}
+
+impl fmt::Display for Title {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl FromStr for Title {
+    type Err = Error;
+
+    /// Parses titles as written in PGN, ignoring case.
+    fn from_str(title: &str) -> Result<Title, Error> {
+        match title.to_uppercase().as_str() {
+            "GM" => Ok(Title::GM),
+            "IM" => Ok(Title::IM),
+            "FM" => Ok(Title::FM),
+            "CM" => Ok(Title::CM),
+            "NM" => Ok(Title::NM),
+            "WGM" => Ok(Title::WGM),
+            "WIM" => Ok(Title::WIM),
+            "WFM" => Ok(Title::WFM),
+            "WCM" => Ok(Title::WCM),
+            "WNM" => Ok(Title::WNM),
+            "LM" => Ok(Title::LM),
+            "BOT" => Ok(Title::BOT),
+            _ => Err(Error::InvalidTitle(String::from(title))),
+        }
+    }
+}
+
+/// A player in a game, as named in its headers.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Player {
+    name: Option<String>,
+    rating: Option<u16>,
+    title: Option<Title>,
+}
+
+impl Player {
+    /// Creates a player named `name`, with no known rating or title.
+    pub fn new(name: &str) -> Player {
+        Player {
+            name: Some(String::from(name)),
+            rating: None,
+            title: None,
+        }
+    }
+
+    /// Returns the player with their rating set to `rating`.
+    pub fn with_rating(mut self, rating: Option<u16>) -> Player {
+        self.rating = rating;
+        self
+    }
+
+    /// Returns the player with their title set to `title`.
+    pub fn with_title(mut self, title: Option<Title>) -> Player {
+        self.title = title;
+        self
+    }
+
+    /// Returns the name of the player, if known.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the rating of the player, if known.
+    pub fn rating(&self) -> Option<u16> {
+        self.rating
+    }
+
+    /// Returns the title of the player, if they have one.
+    pub fn title(&self) -> Option<Title> {
+        self.title
+    }
+
+    /// Returns whether the player's name isn't known.
+    pub fn is_unknown(&self) -> bool {
+        self.name.is_none()
+    }
+
+    /// Returns whether the player is named `name`, ignoring case, as sites such as lichess and
+    /// chess.com do. Players whose name isn't known are never named anything.
+    pub fn is_named(&self, name: &str) -> bool {
+        self.name
+            .as_deref()
+            .is_some_and(|own_name| names_match(own_name, name))
+    }
+
+    pub(crate) fn set_name(&mut self, name: Option<String>) {
+        self.name = name;
+    }
+
+    pub(crate) fn set_rating(&mut self, rating: Option<u16>) {
+        self.rating = rating;
+    }
+
+    pub(crate) fn set_title(&mut self, title: Option<Title>) {
+        self.title = title;
+    }
+}
+
+impl Default for Player {
+    /// Returns a player whose name isn't known.
+    fn default() -> Player {
+        Player {
+            name: None,
+            rating: None,
+            title: None,
+        }
+    }
+}
+
+impl fmt::Display for Player {
+    /// Writes the player as reports show them, such as `GM alice (2700)`, with `?` for a name
+    /// that isn't known, as PGN writes it.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(title) = self.title {
+            write!(f, "{} ", title)?;
+        }
+
+        write!(f, "{}", self.name.as_deref().unwrap_or("?"))?;
+
+        if let Some(rating) = self.rating {
+            write!(f, " ({})", rating)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns whether `a` and `b` name the same player, ignoring case.
+pub fn names_match(a: &str, b: &str) -> bool {
+    a == b || name_key(a) == name_key(b)
+}
+
+/// Returns `name` in the form it's compared in, for keying maps of players by name so that
+/// names written in different cases are counted together.
+pub fn name_key(name: &str) -> String {
+    name.to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::{names_match, Player, Title};
+
+    #[rstest]
+    fn players_should_match_names_ignoring_case() {
+        let player = Player::new("DrNykterstein")
+            .with_rating(Some(3200))
+            .with_title("gm".parse().ok());
+
+        assert!(player.is_named("drnykterstein"));
+        assert!(!player.is_named("drnyk"));
+        assert!(names_match("Émile", "éMILE"));
+        assert_eq!(player.title(), Some(Title::GM));
+        assert_eq!(player.to_string(), "GM DrNykterstein (3200)");
+        assert_eq!(Player::default().to_string(), "?");
+        assert!(!Player::default().is_named("?"));
+        assert!("GrandMaster".parse::<Title>().is_err());
+    }
+}
@@ -0,0 +1,314 @@
+use shakmaty::fen::{Epd, Fen};
+use shakmaty::san::San;
+use shakmaty::{
+    Board, CastlingMode, Chess, Color, EnPassantMode, File, Piece, Position, Rank, Role, Setup,
+    Square,
+};
+
+use crate::error::{Error, Result};
+use crate::moves::Move;
+
+/// Plays `move_` from `position`, returning the move as understood by `shakmaty` along with the
+/// resulting position. Returns `None` if the move isn't legal in `position`.
+fn play<M: Move>(position: &Chess, move_: M) -> Option<(shakmaty::Move, Chess)> {
+    let san = move_.to_algebraic().parse::<San>().ok()?;
+    let legal_move = san.to_move(position).ok()?;
+    let next_position = position.clone().play(legal_move).ok()?;
+
+    Some((legal_move, next_position))
+}
+
+/// Returns the starting position followed by the position after each move in `moves`. Replaying
+/// stops at the first move that isn't legal in the position it is played from.
+pub fn replay<M: Move, I: IntoIterator<Item = M>>(moves: I) -> Vec<Chess> {
+    let mut positions = vec![Chess::default()];
+
+    for move_ in moves {
+        match play(positions.last().unwrap(), move_) {
+            Some((_, next_position)) => positions.push(next_position),
+            None => break,
+        }
+    }
+
+    positions
+}
+
+/// Returns the position after `move_` is played from `position`, or `None` if it isn't legal in
+/// `position`.
+pub fn play_from<M: Move>(position: &Chess, move_: M) -> Option<Chess> {
+    play(position, move_).map(|(_, next_position)| next_position)
+}
+
+/// Returns each move in `moves` as a `shakmaty` move, which carries details such as the piece
+/// moved and the piece captured. Replaying stops at the first move that isn't legal in the
+/// position it is played from.
+pub fn legal_moves<M: Move, I: IntoIterator<Item = M>>(moves: I) -> Vec<shakmaty::Move> {
+    let mut position = Chess::default();
+    let mut legal_moves = Vec::new();
+
+    for move_ in moves {
+        match play(&position, move_) {
+            Some((legal_move, next_position)) => {
+                legal_moves.push(legal_move);
+                position = next_position;
+            }
+            None => break,
+        }
+    }
+
+    legal_moves
+}
+
+/// Returns `move_` played from `position` in UCI notation, such as `e2e4`, or `None` if it isn't
+/// legal in `position`. Castling is written as the king's move, as in `e1g1`.
+pub fn to_uci<M: Move>(position: &Chess, move_: M) -> Option<String> {
+    play(position, move_)
+        .map(|(legal_move, _)| legal_move.to_uci(CastlingMode::Standard).to_string())
+}
+
+/// Returns `position` in FEN. The en passant square is only written when a pawn can capture on
+/// it, so that the same position always gets the same FEN.
+pub fn fen(position: &Chess) -> String {
+    Fen::from_position(position, EnPassantMode::Legal).to_string()
+}
+
+/// Returns `position` in EPD, which is FEN without the move counters, written like `fen`.
+pub fn epd(position: &Chess) -> String {
+    Epd::from_position(position, EnPassantMode::Legal).to_string()
+}
+
+/// Returns the position given by `fen` to look games up by. The move counters may be left out,
+/// but the side to move, castling rights and en passant square can't, as the position isn't
+/// known without them.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidArgument`] if `fen` isn't valid FEN or only gives the board.
+pub fn parse_setup(fen: &str) -> Result<Setup> {
+    let setup = fen
+        .parse::<Fen>()
+        .map_err(|_| Error::InvalidArgument(format!("Invalid fen: {}", fen)))?;
+
+    if fen.split_whitespace().count() < 4 {
+        return Err(Error::InvalidArgument(format!(
+            "Incomplete fen: {} needs the side to move, castling rights and en passant square",
+            fen
+        )));
+    }
+
+    Ok(setup.into_setup())
+}
+
+/// Returns `color`'s material advantage on `board` in points, counting pawns as 1, knights and
+/// bishops as 3, rooks as 5, and queens as 9.
+pub fn material_balance(board: &Board, color: Color) -> i32 {
+    let points = |color: Color| {
+        let side = board.material_side(color);
+
+        side.pawn as i32
+            + 3 * (side.knight as i32 + side.bishop as i32)
+            + 5 * side.rook as i32
+            + 9 * side.queen as i32
+    };
+
+    points(color) - points(!color)
+}
+
+/// How boards are drawn by `Render`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoardStyle {
+    /// Letters for pieces, as in FEN, and dots for empty squares.
+    Ascii,
+    /// Chess symbols for pieces.
+    Unicode,
+    /// Chess symbols for pieces on squares shaded with ANSI colors, for terminals.
+    Color,
+}
+
+/// Drawing positions as boards people can read, with white at the bottom.
+pub trait Render {
+    /// Returns the board of this position drawn in `style`, one rank per line, with the ranks and
+    /// files labelled.
+    fn render(&self, style: BoardStyle) -> String;
+}
+
+impl Render for Board {
+    fn render(&self, style: BoardStyle) -> String {
+        let mut output = String::new();
+
+        for rank in (0..8).rev().map(Rank::new) {
+            output.push(rank.char());
+
+            if style == BoardStyle::Color {
+                output.push(' ');
+            }
+
+            for file in (0..8).map(File::new) {
+                let square = Square::from_coords(file, rank);
+                let piece = self.piece_at(square);
+
+                match style {
+                    BoardStyle::Ascii => {
+                        output.push(' ');
+                        output.push(piece.map_or('.', Piece::char));
+                    }
+                    BoardStyle::Unicode => {
+                        output.push(' ');
+                        output.push(piece.map_or('·', |piece| symbol(piece, piece.color)));
+                    }
+                    BoardStyle::Color => {
+                        // Filled symbols for both sides, told apart by their color.
+                        let background = if square.is_light() { 180 } else { 137 };
+                        let foreground = match piece.map(|piece| piece.color) {
+                            Some(Color::Black) => 16,
+                            _ => 231,
+                        };
+                        let symbol = piece.map_or(' ', |piece| symbol(piece, Color::Black));
+
+                        output += &format!(
+                            "\x1b[48;5;{};38;5;{}m {} \x1b[0m",
+                            background, foreground, symbol
+                        );
+                    }
+                }
+            }
+
+            output.push('\n');
+        }
+
+        output += match style {
+            BoardStyle::Ascii | BoardStyle::Unicode => "  a b c d e f g h\n",
+            BoardStyle::Color => "   a  b  c  d  e  f  g  h\n",
+        };
+
+        output
+    }
+}
+
+impl Render for Setup {
+    fn render(&self, style: BoardStyle) -> String {
+        self.board.render(style)
+    }
+}
+
+impl Render for Fen {
+    fn render(&self, style: BoardStyle) -> String {
+        self.as_setup().render(style)
+    }
+}
+
+impl Render for Chess {
+    fn render(&self, style: BoardStyle) -> String {
+        self.board().render(style)
+    }
+}
+
+/// Returns the chess symbol for the role of `piece` in the outlined style for white or the filled
+/// style for black.
+fn symbol(piece: Piece, style: Color) -> char {
+    match (style, piece.role) {
+        (Color::White, Role::King) => '♔',
+        (Color::White, Role::Queen) => '♕',
+        (Color::White, Role::Rook) => '♖',
+        (Color::White, Role::Bishop) => '♗',
+        (Color::White, Role::Knight) => '♘',
+        (Color::White, Role::Pawn) => '♙',
+        (Color::Black, Role::King) => '♚',
+        (Color::Black, Role::Queen) => '♛',
+        (Color::Black, Role::Rook) => '♜',
+        (Color::Black, Role::Bishop) => '♝',
+        (Color::Black, Role::Knight) => '♞',
+        (Color::Black, Role::Pawn) => '♟',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use shakmaty::fen::Fen;
+    use shakmaty::Role;
+
+    use super::{fen, legal_moves, parse_setup, replay, BoardStyle, Render};
+    use crate::game::test_utils::*;
+    use crate::game::{GameMoves, ListMoves};
+    use crate::moves::Move;
+    use crate::AlgebraicMove;
+
+    #[rstest]
+    fn replay_should_include_every_position() {
+        let positions = replay(italian_game().list_moves());
+
+        assert_eq!(positions.len(), 6);
+        assert_eq!(
+            fen(positions.last().unwrap()),
+            "r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R b KQkq - 3 3"
+        );
+    }
+
+    #[rstest]
+    fn replay_should_stop_at_illegal_moves() {
+        let game = GameMoves::new(vec![
+            AlgebraicMove::from_algebraic(String::from("e4")),
+            AlgebraicMove::from_algebraic(String::from("e4")),
+            AlgebraicMove::from_algebraic(String::from("Nf3")),
+        ]);
+
+        assert_eq!(replay(game.list_moves()).len(), 2);
+        assert_eq!(legal_moves(game.list_moves()).len(), 1);
+    }
+
+    #[rstest]
+    fn legal_moves_should_know_captured_pieces() {
+        let game = GameMoves::new(vec![
+            AlgebraicMove::from_algebraic(String::from("e4")),
+            AlgebraicMove::from_algebraic(String::from("d5")),
+            AlgebraicMove::from_algebraic(String::from("exd5")),
+            AlgebraicMove::from_algebraic(String::from("Qxd5")),
+        ]);
+
+        let legal_moves = legal_moves(game.list_moves());
+
+        assert_eq!(legal_moves.len(), 4);
+        assert_eq!(legal_moves[1].capture(), None);
+        assert_eq!(legal_moves[2].role(), Role::Pawn);
+        assert_eq!(legal_moves[2].capture(), Some(Role::Pawn));
+        assert_eq!(legal_moves[3].role(), Role::Queen);
+        assert_eq!(legal_moves[3].capture(), Some(Role::Pawn));
+    }
+
+    #[rstest]
+    fn parse_setup_should_need_everything_but_the_move_counters() {
+        let board = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR";
+        let full = parse_setup(&format!("{} b KQkq - 0 1", board)).unwrap();
+
+        assert_eq!(parse_setup(&format!("{} b KQkq -", board)).unwrap(), full);
+        assert!(parse_setup(board).is_err());
+        assert!(parse_setup(&format!("{} b", board)).is_err());
+        assert!(parse_setup("not a fen").is_err());
+    }
+
+    #[rstest]
+    fn render_should_draw_the_board_from_white() {
+        let setup = "r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R b KQkq - 3 3"
+            .parse::<Fen>()
+            .unwrap();
+
+        assert_eq!(
+            setup.render(BoardStyle::Ascii),
+            "8 r . b q k b n r\n\
+             7 p p p p . p p p\n\
+             6 . . n . . . . .\n\
+             5 . . . . p . . .\n\
+             4 . . B . P . . .\n\
+             3 . . . . . N . .\n\
+             2 P P P P . P P P\n\
+             1 R N B Q K . . R\n  \
+             a b c d e f g h\n"
+        );
+        assert_eq!(
+            setup.render(BoardStyle::Unicode).lines().next(),
+            Some("8 ♜ · ♝ ♛ ♚ ♝ ♞ ♜")
+        );
+    }
+}
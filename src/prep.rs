@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::io::{self, BufRead, Write};
+
+use shakmaty::Position;
+
+use crate::engine::{Engine, Limit};
+use crate::eval::{Eval, WinModel};
+use crate::game::{Color, GivePlayers, GiveResult, ListMoves};
+use crate::moves::Move;
+use crate::position;
+use crate::stats::Score;
+
+/// Why a player scores poorly in an opening line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GapKind {
+    /// The position is fine for the player, so their results point to a gap in their
+    /// understanding or preparation of it.
+    Understanding,
+    /// The position is objectively bad for the player, so the line needs replacing in their
+    /// repertoire.
+    Repertoire,
+}
+
+/// An opening line where a player scores worse than the position deserves.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrepGap<M: Move> {
+    moves: Vec<M>,
+    color: Color,
+    fen: String,
+    score: Score,
+    eval: Eval,
+    best_move: Option<String>,
+    expected_rate: f64,
+    kind: GapKind,
+}
+
+impl<M: Move> PrepGap<M> {
+    /// Returns the moves that make up this line.
+    pub fn moves(&self) -> &[M] {
+        &self.moves
+    }
+
+    /// Returns the color the player had in this line.
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /// Returns the FEN of the position at the end of this line.
+    pub fn fen(&self) -> &str {
+        &self.fen
+    }
+
+    /// Returns the player's score in this line.
+    pub fn score(&self) -> Score {
+        self.score
+    }
+
+    /// Returns the engine's evaluation of the position at the end of this line, from white's
+    /// point of view.
+    pub fn eval(&self) -> Eval {
+        self.eval
+    }
+
+    /// Returns the move the engine recommended in the position at the end of this line in SAN,
+    /// if it gave one.
+    pub fn best_move(&self) -> Option<&str> {
+        self.best_move.as_deref()
+    }
+
+    /// Returns the fraction of points the player would be expected to score from the position
+    /// at the end of this line, judging by its evaluation.
+    pub fn expected_rate(&self) -> f64 {
+        self.expected_rate
+    }
+
+    /// Returns why the player scores poorly in this line.
+    pub fn kind(&self) -> GapKind {
+        self.kind
+    }
+
+    /// Returns how many fewer points the player scored in this line than expected.
+    pub fn points_dropped(&self) -> f64 {
+        self.expected_rate * self.score.games() as f64 - self.score.points()
+    }
+}
+
+/// Returns the opening lines where `player` scored worse than expected in the games of
+/// `game_iter`, with the lines where they dropped the most points first, as positions to study.
+/// Games `player` didn't play in are ignored.
+///
+/// Lines are identified by the first `line_length` moves of each game, and only lines `player`
+/// played at least `min_games` times with the same color are looked at. The position at the end
+/// of each line is searched by `engine` within `limit`, and its evaluation turned into an
+/// expected score with `model`. Lines where the evaluation has `player` at least
+/// `bad_threshold` centipawns down are repertoire gaps, and the rest are understanding gaps.
+#[allow(clippy::too_many_arguments)]
+pub fn prep_gaps<'a, M, G, R, W>(
+    engine: &mut Engine<R, W>,
+    game_iter: &mut dyn Iterator<Item = &'a G>,
+    player: &str,
+    line_length: usize,
+    min_games: usize,
+    limit: Limit,
+    model: &WinModel,
+    bad_threshold: u32,
+) -> io::Result<Vec<PrepGap<M>>>
+where
+    M: 'static + Clone + Move + Eq + Hash,
+    G: 'a + GivePlayers + GiveResult + ListMoves<M>,
+    R: BufRead,
+    W: Write,
+{
+    let mut lines: HashMap<(Color, Vec<M>), Score> = HashMap::new();
+
+    for game in game_iter {
+        let color = match game.color_of(player) {
+            Some(color) => color,
+            None => continue,
+        };
+
+        let line = game.list_moves().take(line_length).collect::<Vec<_>>();
+
+        lines
+            .entry((color, line))
+            .or_default()
+            .add(game.result().for_color(color));
+    }
+
+    let mut gaps = Vec::new();
+
+    for ((color, moves), score) in lines {
+        if score.games() < min_games {
+            continue;
+        }
+
+        let positions = position::replay(moves.clone());
+
+        // Lines with an illegal move can't be evaluated.
+        if positions.len() != moves.len() + 1 {
+            continue;
+        }
+
+        let position = &positions[moves.len()];
+
+        if position.is_game_over() {
+            continue;
+        }
+
+        let analysis = engine.analyse(position, limit)?;
+        let eval = analysis.eval();
+        let expected_rate = model.win_probability(eval, color);
+
+        let kind = if eval.centipawns_for(color) <= -(bad_threshold as i32) {
+            GapKind::Repertoire
+        } else {
+            GapKind::Understanding
+        };
+
+        let gap = PrepGap {
+            moves,
+            color,
+            fen: position::fen(position),
+            score,
+            eval,
+            best_move: analysis.best_move().map(String::from),
+            expected_rate,
+            kind,
+        };
+
+        if gap.points_dropped() > 0. {
+            gaps.push(gap);
+        }
+    }
+
+    gaps.sort_by(|a, b| b.points_dropped().total_cmp(&a.points_dropped()));
+
+    Ok(gaps)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::{prep_gaps, GapKind};
+    use crate::engine::test_utils::fake_engine;
+    use crate::engine::Limit;
+    use crate::eval::{Eval, WinModel};
+    use crate::game::test_utils::*;
+    use crate::game::{Color, Game};
+    use crate::moves::Move;
+    use crate::AlgebraicMove;
+
+    #[fixture]
+    fn games() -> Vec<Game<AlgebraicMove>> {
+        vec![
+            // alice loses twice in a sound line.
+            game("alice", "bob", "0-1", "1. e4 e5 2. Nf3"),
+            game("alice", "carol", "0-1", "1. e4 e5 2. Nf3"),
+            // alice loses twice and draws once in a bad line.
+            game("dave", "alice", "1-0", "1. d4 f6 2. e4"),
+            game("erin", "alice", "1-0", "1. d4 f6 2. e4"),
+            game("frank", "alice", "1/2-1/2", "1. d4 f6 2. e4"),
+            // alice scores as expected in a line.
+            game("alice", "bob", "1-0", "1. c4 e5 2. Nc3"),
+            game("alice", "bob", "1/2-1/2", "1. c4 e5 2. Nc3"),
+            // alice only played this line once.
+            game("alice", "bob", "0-1", "1. b3 e5 2. Bb2"),
+        ]
+    }
+
+    #[rstest]
+    fn prep_gaps_should_prioritize_and_classify_lines(games: Vec<Game<AlgebraicMove>>) {
+        // Lines are searched in no particular order, so every line gets the same evaluation.
+        let mut engine = fake_engine(&[("cp -150", "e7e5"); 3]);
+
+        let gaps = prep_gaps(
+            &mut engine,
+            &mut games.iter(),
+            "alice",
+            3,
+            2,
+            Limit::Depth(10),
+            &WinModel::lichess(),
+            100,
+        )
+        .unwrap();
+
+        assert_eq!(gaps.len(), 2);
+
+        // After 2. Nf3 or 2. e4 black is to move, so the evaluation is +1.50 for white.
+        let first = &gaps[0];
+
+        assert_eq!(first.moves()[0].clone().to_algebraic(), "e4");
+        assert_eq!(first.color(), Color::White);
+        assert_eq!(first.eval(), Eval::Centipawns(150));
+        assert_eq!(first.kind(), GapKind::Understanding);
+        assert!(first.expected_rate() > 0.6);
+
+        let second = &gaps[1];
+
+        assert_eq!(second.moves()[0].clone().to_algebraic(), "d4");
+        assert_eq!(second.color(), Color::Black);
+        assert_eq!(second.kind(), GapKind::Repertoire);
+        assert_eq!(second.score().games(), 3);
+        assert_eq!(second.best_move(), Some("e5"));
+        assert!(first.points_dropped() > second.points_dropped());
+    }
+}
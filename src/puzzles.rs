@@ -0,0 +1,252 @@
+use std::io::{self, BufRead, Write};
+
+use shakmaty::san::SanPlus;
+use shakmaty::Position;
+
+use crate::analysis::analyse_game;
+use crate::engine::{Engine, Limit};
+use crate::eval::{self, Eval};
+use crate::game::{Color, GivePlayers, ListMoves};
+use crate::moves::Move;
+use crate::position;
+
+/// A position where a player missed a tactic, with the move they should have found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Puzzle {
+    white_player: String,
+    black_player: String,
+    move_number: usize,
+    color: Color,
+    fen: String,
+    epd: String,
+    solution: String,
+    played: String,
+    eval: Eval,
+}
+
+impl Puzzle {
+    /// Returns the name of the player who had white in the game the puzzle came from.
+    pub fn white_player(&self) -> &str {
+        &self.white_player
+    }
+
+    /// Returns the name of the player who had black in the game the puzzle came from.
+    pub fn black_player(&self) -> &str {
+        &self.black_player
+    }
+
+    /// Returns the move number of the puzzle's position.
+    pub fn move_number(&self) -> usize {
+        self.move_number
+    }
+
+    /// Returns the side to move, which is the side that missed the tactic.
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /// Returns the FEN of the puzzle's position.
+    pub fn fen(&self) -> &str {
+        &self.fen
+    }
+
+    /// Returns the move the engine found in SAN.
+    pub fn solution(&self) -> &str {
+        &self.solution
+    }
+
+    /// Returns the move that was played in the game instead in SAN.
+    pub fn played(&self) -> &str {
+        &self.played
+    }
+
+    /// Returns the evaluation of the position with the solution played, from white's point of
+    /// view.
+    pub fn eval(&self) -> Eval {
+        self.eval
+    }
+
+    /// Returns the puzzle as an EPD record, with the solution as its best move.
+    pub fn to_epd(&self) -> String {
+        format!(
+            "{} bm {}; id \"{} vs {}, move {}\";",
+            self.epd, self.solution, self.white_player, self.black_player, self.move_number
+        )
+    }
+
+    /// Returns the puzzle as a PGN game starting from its position, with the solution as the
+    /// only move and the move played in the game given in a comment.
+    pub fn to_pgn(&self) -> String {
+        let move_number = match self.color {
+            Color::White => format!("{}.", self.move_number),
+            Color::Black => format!("{}...", self.move_number),
+        };
+
+        format!(
+            "[Event \"Missed tactic\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n\
+             [White \"{}\"]\n[Black \"{}\"]\n[Result \"*\"]\n[SetUp \"1\"]\n[FEN \"{}\"]\n\n\
+             {} {} {{ Played in the game: {} }} *\n",
+            self.white_player, self.black_player, self.fen, move_number, self.solution, self.played
+        )
+    }
+}
+
+/// Returns whether `eval` is a forced mate for `color`.
+fn is_mate_for(eval: Eval, color: Color) -> bool {
+    match (eval, color) {
+        (Eval::Mate(moves), Color::White) => moves > 0,
+        (Eval::Mate(moves), Color::Black) => moves < 0,
+        _ => false,
+    }
+}
+
+/// Returns the positions of `game` where the side to move missed a tactic according to `engine`
+/// searching within `limit`, as puzzles. If `player` is given, only their misses are returned.
+///
+/// A tactic was missed when the side to move had a forced mate, or when the opponent's last move
+/// had handed them at least `threshold` centipawns, and the move played instead gave up at least
+/// `threshold` centipawns of it or let the mate slip.
+pub fn find_missed_tactics<M, G, R, W>(
+    engine: &mut Engine<R, W>,
+    game: &G,
+    limit: Limit,
+    threshold: u32,
+    player: Option<&str>,
+) -> io::Result<Vec<Puzzle>>
+where
+    M: 'static + Clone + Move,
+    G: GivePlayers + ListMoves<M>,
+    R: BufRead,
+    W: Write,
+{
+    let analysed = analyse_game(engine, game, limit)?;
+    let legal_moves = position::legal_moves(game.list_moves());
+    let mut puzzles = Vec::new();
+
+    for (ply, legal_move) in legal_moves.iter().enumerate() {
+        let (position, analysis) = &analysed[ply];
+        let (next_position, next_analysis) = &analysed[ply + 1];
+        let color = position.turn();
+
+        if matches!(player, Some(player) if !game.player(color).is_named(player)) {
+            continue;
+        }
+
+        let (best, solution) = match analysis {
+            Some(analysis) => match analysis.best_move() {
+                Some(best_move) => (analysis.eval(), best_move),
+                None => continue,
+            },
+            None => continue,
+        };
+
+        let played = SanPlus::from_move(position.clone(), *legal_move).to_string();
+
+        if played == solution {
+            continue;
+        }
+
+        let after = match next_analysis {
+            Some(next_analysis) => next_analysis.eval(),
+            None if next_position.is_checkmate() => continue,
+            None => Eval::Centipawns(0),
+        };
+
+        // The evaluation before the opponent's last move, to tell how much it handed over.
+        let previous = match ply {
+            0 => None,
+            _ => analysed[ply - 1].1.as_ref().map(|analysis| analysis.eval()),
+        };
+
+        let gain = previous.map_or(0, |previous| {
+            best.centipawns_for(color) - previous.centipawns_for(color)
+        });
+
+        let had_mate = is_mate_for(best, color);
+        let had_chance = had_mate || gain >= threshold as i32;
+        let missed = eval::centipawn_loss(best, after, color) >= threshold
+            || (had_mate && !is_mate_for(after, color));
+
+        if had_chance && missed {
+            puzzles.push(Puzzle {
+                white_player: String::from(game.white_player().unwrap_or("?")),
+                black_player: String::from(game.black_player().unwrap_or("?")),
+                move_number: ply / 2 + 1,
+                color,
+                fen: position::fen(position),
+                epd: position::epd(position),
+                solution: String::from(solution),
+                played,
+                eval: best,
+            });
+        }
+    }
+
+    Ok(puzzles)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::find_missed_tactics;
+    use crate::engine::test_utils::{fake_engine, FakeEngine};
+    use crate::engine::Limit;
+    use crate::eval::Eval;
+    use crate::game::test_utils::*;
+    use crate::game::{Color, Game};
+    use crate::AlgebraicMove;
+
+    #[fixture]
+    fn game() -> Game<AlgebraicMove> {
+        parse_game(
+            "[White \"alice\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n\
+             1. e4 e5 2. Qh5 Ke7 3. d3 1-0",
+        )
+    }
+
+    /// Creates an engine that finds the mate white missed after 2... Ke7.
+    fn engine() -> FakeEngine {
+        fake_engine(&[
+            ("cp 30", "e2e4"),
+            ("cp -30", "e7e5"),
+            ("cp 30", "g1f3"),
+            ("cp -20", "b8c6"),
+            ("mate 1", "h5e5"),
+            ("cp 0", "d8e8"),
+        ])
+    }
+
+    #[rstest]
+    fn find_missed_tactics_should_find_missed_mate(game: Game<AlgebraicMove>) {
+        let puzzles =
+            find_missed_tactics(&mut engine(), &game, Limit::Depth(10), 200, None).unwrap();
+
+        assert_eq!(puzzles.len(), 1);
+
+        let puzzle = &puzzles[0];
+
+        assert_eq!(puzzle.move_number(), 3);
+        assert_eq!(puzzle.color(), Color::White);
+        assert_eq!(puzzle.solution(), "Qxe5#");
+        assert_eq!(puzzle.played(), "d3");
+        assert_eq!(puzzle.eval(), Eval::Mate(1));
+        assert_eq!(
+            puzzle.to_epd(),
+            "rnbq1bnr/ppppkppp/8/4p2Q/4P3/8/PPPP1PPP/RNB1KBNR w KQ - bm Qxe5#; \
+             id \"alice vs bob, move 3\";"
+        );
+        assert!(puzzle.to_pgn().contains(
+            "[FEN \"rnbq1bnr/ppppkppp/8/4p2Q/4P3/8/PPPP1PPP/RNB1KBNR w KQ - 2 3\"]\n\n\
+             3. Qxe5# { Played in the game: d3 } *"
+        ));
+    }
+
+    #[rstest]
+    fn find_missed_tactics_should_only_include_player(game: Game<AlgebraicMove>) {
+        let puzzles =
+            find_missed_tactics(&mut engine(), &game, Limit::Depth(10), 200, Some("bob")).unwrap();
+
+        assert!(puzzles.is_empty());
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+
+use crate::game::{Color, GiveDate, GivePlayers, GiveResult, PlayerResult};
+use crate::player::name_key;
+use crate::stats::statistic::Value;
+use crate::stats::Score;
+
+/// A player's rating as estimated from a set of games.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EstimatedRating {
+    player: String,
+    rating: f64,
+    peak: f64,
+    score: Score,
+}
+
+impl EstimatedRating {
+    /// Returns the name of the player, as in the first game they were found in.
+    pub fn player(&self) -> &str {
+        &self.player
+    }
+
+    /// Returns the player's rating after their last game.
+    pub fn rating(&self) -> f64 {
+        self.rating
+    }
+
+    /// Returns the highest rating the player reached, counting the rating they started with.
+    pub fn peak(&self) -> f64 {
+        self.peak
+    }
+
+    /// Returns the player's score over the games rated.
+    pub fn score(&self) -> Score {
+        self.score
+    }
+
+    /// Returns the rating as a value for writing as JSON or as a row of a table, with ratings
+    /// rounded to whole points.
+    pub fn to_value(&self) -> Value {
+        Value::map(vec![
+            ("player", self.player().into()),
+            ("rating", self.rating.round().into()),
+            ("peak", self.peak.round().into()),
+            ("games", self.score.games().into()),
+            ("points", self.score.points().into()),
+        ])
+    }
+}
+
+/// Estimates ratings for the players of a closed set of games, such as a club's internal league,
+/// with the Elo system. Every player starts at the same rating, and games are rated one at a time
+/// in the order they were played, so the ratings are relative to the players in the set rather
+/// than to any official list.
+///
+/// ```
+/// use chesshound::parsing::read_games;
+/// use chesshound::ratings::Elo;
+///
+/// let pgn = "[White \"alice\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n\n1. e4 e5 1-0\n";
+/// let games = read_games(pgn.as_bytes()).unwrap();
+///
+/// let ratings = Elo::new().k_factor(20.).rate(&mut games.iter());
+///
+/// assert_eq!(ratings[0].player(), "alice");
+/// assert_eq!(ratings[0].rating(), 1510.);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Elo {
+    initial: f64,
+    k_factor: f64,
+}
+
+impl Elo {
+    /// Creates an Elo system starting players at 1500, with a K-factor of 32.
+    pub fn new() -> Elo {
+        Elo {
+            initial: 1500.,
+            k_factor: 32.,
+        }
+    }
+
+    /// Sets the rating every player starts at.
+    pub fn initial(mut self, initial: f64) -> Elo {
+        self.initial = initial;
+        self
+    }
+
+    /// Sets the K-factor, the most a rating can change by in one game.
+    pub fn k_factor(mut self, k_factor: f64) -> Elo {
+        self.k_factor = k_factor;
+        self
+    }
+
+    /// Returns the rating `player` is expected to score against `opponent`, as a fraction of a
+    /// point.
+    pub fn expected_score(player: f64, opponent: f64) -> f64 {
+        1. / (1. + 10_f64.powf((opponent - player) / 400.))
+    }
+
+    /// Returns the ratings of the players of the games of `game_iter`, highest first. Games are
+    /// rated in order of their date and start time, with undated games first in the order given,
+    /// and games missing either player are ignored. Players are matched ignoring case.
+    pub fn rate<'a, G>(&self, game_iter: &mut dyn Iterator<Item = &'a G>) -> Vec<EstimatedRating>
+    where
+        G: 'a + GiveDate + GivePlayers + GiveResult,
+    {
+        let mut games = game_iter
+            .filter(|game| !game.white().is_unknown() && !game.black().is_unknown())
+            .collect::<Vec<_>>();
+        games.sort_by_key(|game| (game.date(), game.time()));
+
+        let mut indices = HashMap::new();
+        let mut ratings: Vec<EstimatedRating> = Vec::new();
+
+        for game in games {
+            let mut index = |color: Color| {
+                let name = game.player_of(color).unwrap_or_default();
+
+                *indices.entry(name_key(name)).or_insert_with(|| {
+                    ratings.push(EstimatedRating {
+                        player: String::from(name),
+                        rating: self.initial,
+                        peak: self.initial,
+                        score: Score::new(),
+                    });
+                    ratings.len() - 1
+                })
+            };
+            let (white, black) = (index(Color::White), index(Color::Black));
+
+            // Both changes come from the ratings going into the game, so neither player's
+            // update affects the other's.
+            let expected = Elo::expected_score(ratings[white].rating, ratings[black].rating);
+            let scored = match game.result().for_color(Color::White) {
+                PlayerResult::Won => 1.,
+                PlayerResult::Drew => 0.5,
+                PlayerResult::Lost => 0.,
+            };
+            let change = self.k_factor * (scored - expected);
+
+            for (index, change, color) in [
+                (white, change, Color::White),
+                (black, -change, Color::Black),
+            ] {
+                let rating = &mut ratings[index];
+
+                rating.rating += change;
+                rating.peak = rating.peak.max(rating.rating);
+                rating.score.add(game.result().for_color(color));
+            }
+        }
+
+        ratings.sort_by(|a, b| {
+            b.rating
+                .total_cmp(&a.rating)
+                .then_with(|| a.player.cmp(&b.player))
+        });
+
+        ratings
+    }
+}
+
+impl Default for Elo {
+    fn default() -> Elo {
+        Elo::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use float_cmp::approx_eq;
+
+    use super::Elo;
+    use crate::game::test_utils::*;
+
+    #[rstest]
+    fn elo_should_rate_games_in_the_order_played() {
+        let games = [
+            dated_game("bob", "carol", "1/2-1/2", "2021.02.01", "1. e4 e5"),
+            dated_game("alice", "bob", "1-0", "2021.01.01", "1. e4 e5"),
+            dated_game("Carol", "alice", "0-1", "2021.03.01", "1. e4 e5"),
+            dated_game("alice", "?", "0-1", "2021.04.01", "1. e4 e5"),
+        ];
+
+        let ratings = Elo::new().rate(&mut games.iter());
+        let names = ratings
+            .iter()
+            .map(|rating| rating.player())
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, vec!["alice", "bob", "carol"]);
+        assert_eq!(ratings[0].score().games(), 2);
+        assert!(approx_eq!(
+            f64,
+            ratings[0].rating(),
+            1531.23,
+            epsilon = 0.01
+        ));
+        assert!(approx_eq!(
+            f64,
+            ratings.iter().map(|rating| rating.rating()).sum::<f64>(),
+            4500.,
+            epsilon = 0.000001
+        ));
+        assert!(approx_eq!(
+            f64,
+            ratings[2].peak(),
+            1500.,
+            epsilon = 0.000001
+        ));
+        assert!(approx_eq!(
+            f64,
+            Elo::expected_score(1900., 1500.),
+            10. / 11.,
+            epsilon = 0.000001
+        ));
+    }
+}
@@ -0,0 +1,858 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use shakmaty::{EnPassantMode, Position};
+
+use crate::error::{Error, Result};
+use crate::filter::GameFilter;
+use crate::game::{GiveDate, GiveLink, GivePlayers, GiveResult, ListMoves};
+use crate::index::PositionIndex;
+use crate::masters::{MasterMove, MasterStats, MastersCache};
+use crate::moves::parse_line;
+use crate::openings::{name_for, OpeningName};
+use crate::position;
+use crate::stats::sharpness::{branch_sharpness, sharpness, Sharpness};
+use crate::stats::statistic::{ResultRates, Statistic, Value};
+use crate::{AlgebraicMove, Game, Move, MoveTree, MoveTreeView};
+
+/// How much more or less often a player plays a move than masters do, as a fraction of games,
+/// for the move to be flagged as diverging from theory.
+const DIVERGENT_SHARE: f64 = 0.25;
+
+/// How much more or less white scores with a move in a player's games than in master games, as a
+/// fraction of points, for the move to be flagged as diverging from theory.
+const DIVERGENT_SCORE: f64 = 0.25;
+
+/// The number of plies games need by default to count towards the results of a report. Games
+/// with fewer, where a player never moved, are almost always aborted games, which say nothing
+/// about how a line goes.
+pub const DEFAULT_MIN_PLIES: usize = 2;
+
+/// What to find out about a set of games: how they went after a line of moves, and optionally
+/// how sharp the line is and how each move played next went. This is what the `stats`
+/// subcommand reports.
+///
+/// ```
+/// use chesshound::filter::GameFilter;
+/// use chesshound::parsing::read_games;
+/// use chesshound::report::AnalysisRequest;
+///
+/// let pgn = "[Result \"1-0\"]\n\n1. e4 e5 1-0\n\n[Result \"1/2-1/2\"]\n\n1. e4 c5 1/2-1/2\n";
+/// let request = AnalysisRequest::new(GameFilter::all())
+///     .moves(vec![String::from("e4")])
+///     .branches(true);
+///
+/// let report = request.analyze(read_games(pgn.as_bytes()).unwrap()).unwrap();
+///
+/// assert_eq!(report.results().games(), 2);
+/// assert_eq!(report.branches().unwrap().len(), 2);
+/// ```
+pub struct AnalysisRequest {
+    filter: GameFilter<Game<AlgebraicMove>>,
+    moves: Vec<String>,
+    by_position: bool,
+    branches: bool,
+    examples: bool,
+    min_sharpness: Option<f64>,
+    masters: Option<MastersCache>,
+    min_plies: usize,
+}
+
+impl AnalysisRequest {
+    /// Creates a request for how the games `filter` includes went, with nothing else set.
+    pub fn new(filter: GameFilter<Game<AlgebraicMove>>) -> AnalysisRequest {
+        AnalysisRequest {
+            filter,
+            moves: Vec::new(),
+            by_position: false,
+            branches: false,
+            examples: false,
+            min_sharpness: None,
+            masters: None,
+            min_plies: DEFAULT_MIN_PLIES,
+        }
+    }
+
+    /// Only looks at games starting with `moves`, given in SAN.
+    pub fn moves(mut self, moves: Vec<String>) -> AnalysisRequest {
+        self.moves = moves;
+        self
+    }
+
+    /// Looks at games reaching the position after the moves by any order of moves, rather than
+    /// only games playing them in order.
+    pub fn by_position(mut self, by_position: bool) -> AnalysisRequest {
+        self.by_position = by_position;
+        self
+    }
+
+    /// Reports how each move played next went.
+    pub fn branches(mut self, branches: bool) -> AnalysisRequest {
+        self.branches = branches;
+        self
+    }
+
+    /// Gives the most recent game of each move played next as an example.
+    pub fn examples(mut self, examples: bool) -> AnalysisRequest {
+        self.examples = examples;
+        self
+    }
+
+    /// Reports how sharp the line is, and hides moves played next that are less sharp than
+    /// `min_sharpness`, the fraction of their games that were decisive.
+    pub fn min_sharpness(mut self, min_sharpness: Option<f64>) -> AnalysisRequest {
+        self.min_sharpness = min_sharpness;
+        self
+    }
+
+    /// Compares the line and each move played next with master games from `masters`, when it
+    /// has the position after the line.
+    pub fn masters(mut self, masters: Option<MastersCache>) -> AnalysisRequest {
+        self.masters = masters;
+        self
+    }
+
+    /// Leaves games of fewer than `min_plies` plies out of the results, counting them separately
+    /// instead, so that aborted games don't skew how often each side wins. This is
+    /// [`DEFAULT_MIN_PLIES`] unless set.
+    pub fn min_plies(mut self, min_plies: usize) -> AnalysisRequest {
+        self.min_plies = min_plies;
+        self
+    }
+
+    /// Returns whether `game` is too short to count towards the results, and is counted
+    /// separately instead.
+    pub fn is_short(&self, game: &Game<AlgebraicMove>) -> bool {
+        game.list_moves().count() < self.min_plies
+    }
+
+    /// Returns the filter games must meet to be looked at.
+    pub fn filter(&self) -> &GameFilter<Game<AlgebraicMove>> {
+        &self.filter
+    }
+
+    /// Returns whether the request needs the games themselves. When it doesn't, only their
+    /// results are, so games can be counted as they're read with
+    /// [`summarize`](AnalysisRequest::summarize) instead of being kept in memory.
+    pub fn needs_games(&self) -> bool {
+        !self.moves.is_empty() || self.branches || self.min_sharpness.is_some()
+    }
+
+    /// Returns the report on `games`, which should be the games [`filter`](Self::filter)
+    /// includes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if one of the moves isn't valid SAN, or if the request is by position
+    /// and one of its moves is illegal.
+    pub fn analyze(&self, games: Vec<Game<AlgebraicMove>>) -> Result<AnalysisReport> {
+        let line = parse_line(&self.moves)?;
+        let (games, short_games): (Vec<_>, Vec<_>) =
+            games.into_iter().partition(|game| !self.is_short(game));
+
+        let games = if self.by_position {
+            transpose_to_line(games, &self.moves)?
+        } else {
+            games
+        };
+
+        let move_tree = MoveTree::new(games);
+        let view = line
+            .iter()
+            .fold(move_tree.view(), |view, move_| view.with_next(move_));
+
+        let mut results = ResultRates::new();
+
+        for game in view.iter() {
+            results.accumulate(game);
+        }
+
+        let mut report = self.summarize(results, short_games.len());
+        report.opening = name_for(&line);
+
+        if self.min_sharpness.is_some() {
+            report.decisive = Some(sharpness(&mut view.iter()));
+        }
+
+        if self.branches {
+            report.branches = Some(self.branches_of(&view, &line, report.masters.as_ref()));
+        }
+
+        Ok(report)
+    }
+
+    /// Returns the report on games whose results are `results`, along with `short_games` games
+    /// left out of them for being [too short](AnalysisRequest::is_short), for requests that
+    /// don't [need the games](AnalysisRequest::needs_games).
+    pub fn summarize(&self, results: ResultRates, short_games: usize) -> AnalysisReport {
+        AnalysisReport {
+            filter: self.filter.to_string(),
+            moves: self.moves.clone(),
+            opening: None,
+            min_sharpness: self.min_sharpness,
+            results,
+            min_plies: self.min_plies,
+            short_games,
+            decisive: None,
+            branches: None,
+            masters: self.master_stats(),
+        }
+    }
+
+    /// Returns the master statistics for the position after the line, if there are masters to
+    /// compare with and they have the position.
+    fn master_stats(&self) -> Option<MasterStats> {
+        // A line that isn't valid SAN, or is illegal, has no position to look up.
+        let line = parse_line(&self.moves).ok()?;
+        let positions = position::replay(line.iter().cloned());
+
+        if positions.len() <= line.len() {
+            return None;
+        }
+
+        self.masters.as_ref()?.get(positions.last()?).cloned()
+    }
+
+    /// Returns how each move played from `view`, which is reached by `line`, went, sharpest first when a minimum sharpness
+    /// is given and most played first otherwise.
+    fn branches_of(
+        &self,
+        view: &MoveTreeView<AlgebraicMove, Game<AlgebraicMove>>,
+        line: &[AlgebraicMove],
+        masters: Option<&MasterStats>,
+    ) -> Vec<Branch> {
+        let games = view.iter().count();
+        let mut move_branches = branch_sharpness(view, 1);
+
+        match self.min_sharpness {
+            Some(min_sharpness) => move_branches
+                .retain(|(_, sharpness)| sharpness.rate().unwrap_or(0.) >= min_sharpness),
+            None => move_branches.sort_by(|(move_a, a), (move_b, b)| {
+                b.games().cmp(&a.games()).then_with(|| {
+                    move_a
+                        .clone()
+                        .to_algebraic()
+                        .cmp(&move_b.clone().to_algebraic())
+                })
+            }),
+        }
+
+        move_branches
+            .into_iter()
+            .map(|(move_, sharpness)| {
+                let child = view.clone().with_next(&move_);
+                let mut child_line = line.to_vec();
+                child_line.push(move_.clone());
+                let move_ = move_.to_algebraic();
+                let white_score = white_score(&child);
+                let master_move = masters.and_then(|masters| masters.move_(&move_)).cloned();
+                let diverges = masters.is_some_and(|masters| {
+                    diverges(
+                        sharpness.games() as f64 / games as f64,
+                        white_score,
+                        masters,
+                        master_move.as_ref(),
+                    )
+                });
+
+                Branch {
+                    move_,
+                    opening: name_for(&child_line),
+                    sharpness,
+                    white_score,
+                    masters: master_move,
+                    diverges,
+                    example: if self.examples {
+                        child
+                            .iter()
+                            .max_by_key(|game| (game.date(), game.time()))
+                            .cloned()
+                    } else {
+                        None
+                    },
+                }
+            })
+            .collect()
+    }
+}
+
+/// A move played after the line of an [`AnalysisRequest`], with how its games went.
+#[derive(Debug, Clone)]
+pub struct Branch {
+    move_: String,
+    opening: Option<OpeningName>,
+    sharpness: Sharpness,
+    white_score: Option<f64>,
+    masters: Option<MasterMove>,
+    diverges: bool,
+    example: Option<Game<AlgebraicMove>>,
+}
+
+impl Branch {
+    /// Returns the move in SAN.
+    pub fn move_(&self) -> &str {
+        &self.move_
+    }
+
+    /// Returns the name of the opening the move leads to, if it's in the embedded opening table.
+    pub fn opening(&self) -> Option<&OpeningName> {
+        self.opening.as_ref()
+    }
+
+    /// Returns how sharp the games with the move were, which also counts them.
+    pub fn sharpness(&self) -> Sharpness {
+        self.sharpness
+    }
+
+    /// Returns the fraction of points white scored in games with the move.
+    pub fn white_score(&self) -> Option<f64> {
+        self.white_score
+    }
+
+    /// Returns how master games went with the move, if masters were compared with and played it.
+    pub fn masters(&self) -> Option<&MasterMove> {
+        self.masters.as_ref()
+    }
+
+    /// Returns whether the move is played much more or less often than masters play it, or
+    /// scores much better or worse for white than in master games. Moves are only flagged when
+    /// masters were compared with and had the position.
+    pub fn diverges(&self) -> bool {
+        self.diverges
+    }
+
+    /// Returns the most recent game with the move, if examples were asked for.
+    pub fn example(&self) -> Option<&Game<AlgebraicMove>> {
+        self.example.as_ref()
+    }
+}
+
+/// The answer to an [`AnalysisRequest`], which can be shown as text with `Display`, or as
+/// JSON or a table through [`to_value`](AnalysisReport::to_value).
+#[derive(Debug, Clone)]
+pub struct AnalysisReport {
+    filter: String,
+    moves: Vec<String>,
+    opening: Option<OpeningName>,
+    min_sharpness: Option<f64>,
+    results: ResultRates,
+    min_plies: usize,
+    short_games: usize,
+    decisive: Option<Sharpness>,
+    branches: Option<Vec<Branch>>,
+    masters: Option<MasterStats>,
+}
+
+impl AnalysisReport {
+    /// Returns the name of the opening the line leads to, if the games were looked at and the
+    /// opening is in the embedded opening table.
+    pub fn opening(&self) -> Option<&OpeningName> {
+        self.opening.as_ref()
+    }
+
+    /// Returns the results of the games looked at.
+    pub fn results(&self) -> &ResultRates {
+        &self.results
+    }
+
+    /// Returns the number of games left out of the results for being too short, such as aborted
+    /// games.
+    pub fn short_games(&self) -> usize {
+        self.short_games
+    }
+
+    /// Returns how sharp the line is, if a minimum sharpness was asked for.
+    pub fn decisive(&self) -> Option<Sharpness> {
+        self.decisive
+    }
+
+    /// Returns how each move played next went, if branches were asked for.
+    pub fn branches(&self) -> Option<&[Branch]> {
+        self.branches.as_deref()
+    }
+
+    /// Returns how master games went from the position after the line, if masters were compared
+    /// with and had the position.
+    pub fn masters(&self) -> Option<&MasterStats> {
+        self.masters.as_ref()
+    }
+
+    /// Returns the report as a value, for writing as JSON. It holds what was asked for under
+    /// `filters`, the results, the branches, and how master games went when compared with.
+    pub fn to_value(&self) -> Value {
+        let filters = Value::map(vec![
+            ("games", self.filter.clone().into()),
+            ("moves", self.moves.clone().into()),
+            ("min_sharpness", self.min_sharpness.into()),
+            ("min_plies", self.min_plies.into()),
+        ]);
+
+        Value::map(
+            vec![
+                ("filters", filters),
+                (
+                    "opening",
+                    self.opening
+                        .as_ref()
+                        .map(|opening| opening.to_string())
+                        .into(),
+                ),
+            ]
+            .into_iter()
+            .chain(self.summary())
+            .chain(std::iter::once(("branches", self.branches_value().into())))
+            .chain(std::iter::once(("masters", self.masters_value()))),
+        )
+    }
+
+    /// Returns the report as a table with columns separated by `separator`. Tables hold the
+    /// branches when there are any, since they're the rows people want to sort and chart, and
+    /// the results otherwise.
+    pub fn to_table(&self, separator: char) -> String {
+        match self.branches_value() {
+            Some(branches) => branches.to_table(separator),
+            None => Value::map(self.summary()).to_table(separator),
+        }
+    }
+
+    /// Returns the names and values of the results of the games looked at.
+    fn summary(&self) -> Vec<(&'static str, Value)> {
+        let rates = self.results.rates();
+
+        vec![
+            ("games", self.results.games().into()),
+            ("white_wins", rates.map(|rates| rates.0).into()),
+            ("black_wins", rates.map(|rates| rates.1).into()),
+            ("draws", rates.map(|rates| rates.2).into()),
+            ("short_games", self.short_games.into()),
+            (
+                "decisive",
+                self.decisive.and_then(|decisive| decisive.rate()).into(),
+            ),
+        ]
+    }
+
+    /// Returns the master results and the moves diverging from them as a value, or
+    /// `Value::Missing` if masters weren't compared with.
+    fn masters_value(&self) -> Value {
+        let masters = match &self.masters {
+            Some(masters) => masters,
+            None => return Value::Missing,
+        };
+        let rates = masters.rates();
+
+        Value::map(vec![
+            ("games", masters.games().into()),
+            ("white_wins", rates.map(|rates| rates.0).into()),
+            ("black_wins", rates.map(|rates| rates.1).into()),
+            ("draws", rates.map(|rates| rates.2).into()),
+            (
+                "diverging_moves",
+                self.branches
+                    .iter()
+                    .flatten()
+                    .filter(|branch| branch.diverges)
+                    .map(|branch| branch.move_.clone())
+                    .collect::<Vec<_>>()
+                    .into(),
+            ),
+        ])
+    }
+
+    /// Returns the branches as a list of values, if branches were asked for.
+    fn branches_value(&self) -> Option<Value> {
+        let branches = self.branches.as_ref()?;
+
+        Some(Value::List(
+            branches
+                .iter()
+                .map(|branch| {
+                    Value::map(vec![
+                        ("move", branch.move_.clone().into()),
+                        (
+                            "opening",
+                            branch
+                                .opening
+                                .as_ref()
+                                .map(|opening| opening.to_string())
+                                .into(),
+                        ),
+                        ("games", branch.sharpness.games().into()),
+                        ("white_score", branch.white_score.into()),
+                        ("decisive", branch.sharpness.rate().into()),
+                        (
+                            "masters_share",
+                            self.masters
+                                .as_ref()
+                                .map(|masters| master_share(masters, branch.masters.as_ref()))
+                                .into(),
+                        ),
+                        (
+                            "masters_white_score",
+                            branch
+                                .masters
+                                .as_ref()
+                                .and_then(MasterMove::white_score)
+                                .into(),
+                        ),
+                        (
+                            "example",
+                            branch.example.as_ref().map_or(Value::Missing, |game| {
+                                Value::map(vec![
+                                    ("id", game.id().to_string().into()),
+                                    ("white", game.white_player().into()),
+                                    ("black", game.black_player().into()),
+                                    ("result", game.result().to_string().into()),
+                                    ("date", game.date().map(|date| date.to_string()).into()),
+                                    ("link", game.link().into()),
+                                ])
+                            }),
+                        ),
+                    ])
+                })
+                .collect(),
+        ))
+    }
+}
+
+impl fmt::Display for AnalysisReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", describe_results(&self.results))?;
+
+        if self.short_games > 0 {
+            write!(
+                f,
+                "\nLeft out {} games shorter than {} plies",
+                self.short_games, self.min_plies
+            )?;
+        }
+
+        if let Some(decisive) = self.decisive {
+            write!(
+                f,
+                "\nDecisive: {:.2}%",
+                decisive.rate().unwrap_or(0.) * 100.
+            )?;
+        }
+
+        if let Some(masters) = &self.masters {
+            let (white_win_rate, black_win_rate, draw_rate) =
+                masters.rates().unwrap_or((0., 0., 0.));
+
+            write!(
+                f,
+                "\nMasters: {} games, White Wins: {:.2}%, Black Wins: {:.2}%, Draw: {:.2}%",
+                masters.games(),
+                white_win_rate * 100.,
+                black_win_rate * 100.,
+                draw_rate * 100.
+            )?;
+        }
+
+        let branches = match &self.branches {
+            Some(branches) => branches,
+            None => return Ok(()),
+        };
+
+        if branches.is_empty() {
+            write!(f, "\nNo moves")?;
+        } else {
+            write!(f, "\nMoves:")?;
+        }
+
+        for branch in branches {
+            write!(
+                f,
+                "\n  {} ({} games, white scores {:.1}%",
+                branch.move_,
+                branch.sharpness.games(),
+                branch.white_score.unwrap_or(0.) * 100.
+            )?;
+
+            if self.min_sharpness.is_some() {
+                write!(
+                    f,
+                    ", {:.2}% decisive",
+                    branch.sharpness.rate().unwrap_or(0.) * 100.
+                )?;
+            }
+
+            if let Some(masters) = &self.masters {
+                write!(
+                    f,
+                    "; masters {:.1}% of games",
+                    master_share(masters, branch.masters.as_ref()) * 100.
+                )?;
+
+                if let Some(white_score) = branch.masters.as_ref().and_then(MasterMove::white_score)
+                {
+                    write!(f, ", white scores {:.1}%", white_score * 100.)?;
+                }
+            }
+
+            write!(f, ")")?;
+
+            // As in trees, openings are only named where the move changes them.
+            if let Some(opening) = &branch.opening {
+                if Some(opening) != self.opening.as_ref() {
+                    write!(f, " {}", opening)?;
+                }
+            }
+
+            if let Some(game) = &branch.example {
+                write!(
+                    f,
+                    " e.g. {} - {}, {}",
+                    game.white_player().unwrap_or("?"),
+                    game.black_player().unwrap_or("?"),
+                    game.result()
+                )?;
+
+                if let Some(date) = game.date() {
+                    write!(f, ", {}", date)?;
+                }
+
+                if let Some(link) = game.link() {
+                    write!(f, ", {}", link)?;
+                }
+            }
+
+            if branch.diverges {
+                write!(f, " [diverges from masters]")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns `results` as the number of games and the rate of each result, one to a line.
+pub fn describe_results(results: &ResultRates) -> String {
+    let (white_win_rate, black_win_rate, draw_rate) = results.rates().unwrap_or((0., 0., 0.));
+
+    format!(
+        "{} games\nWhite Wins: {:.2}%\nBlack Wins: {:.2}%\nDraw: {:.2}%",
+        results.games(),
+        white_win_rate * 100.,
+        black_win_rate * 100.,
+        draw_rate * 100.
+    )
+}
+
+/// Returns the fraction of points white scored in the games of `view`, or `None` if there are
+/// none.
+pub fn white_score(view: &MoveTreeView<AlgebraicMove, Game<AlgebraicMove>>) -> Option<f64> {
+    let mut results = ResultRates::new();
+
+    for game in view.iter() {
+        results.accumulate(game);
+    }
+
+    results
+        .rates()
+        .map(|(white_wins, _, draws)| white_wins + draws / 2.)
+}
+
+/// Returns the fraction of master games from a position with `masters` that played `move_`,
+/// where `move_` is `None` for a move masters never played.
+fn master_share(masters: &MasterStats, move_: Option<&MasterMove>) -> f64 {
+    match masters.games() {
+        0 => 0.,
+        games => move_.map_or(0, MasterMove::games) as f64 / games as f64,
+    }
+}
+
+/// Returns whether a move played in `share` of a player's games, with white scoring
+/// `white_score` in them, diverges from master games from the same position, which went as
+/// `masters` says and as `master_move` says for the move.
+fn diverges(
+    share: f64,
+    white_score: Option<f64>,
+    masters: &MasterStats,
+    master_move: Option<&MasterMove>,
+) -> bool {
+    if masters.games() == 0 {
+        return false;
+    }
+
+    let by_share = (share - master_share(masters, master_move)).abs() >= DIVERGENT_SHARE;
+    let by_score = match (white_score, master_move.and_then(MasterMove::white_score)) {
+        (Some(score), Some(master_score)) => (score - master_score).abs() >= DIVERGENT_SCORE,
+        _ => false,
+    };
+
+    by_share || by_score
+}
+
+/// Returns the games of `games` that reach the position after `moves`, each rewritten as if it
+/// got there by playing `moves`, so that games transposing into the position are counted along
+/// with the games that played `moves` in order. Games reaching the position more than once are
+/// rewritten at the first time.
+///
+/// # Errors
+///
+/// Returns an error if one of `moves` isn't valid SAN or is illegal.
+pub fn transpose_to_line(
+    games: Vec<Game<AlgebraicMove>>,
+    moves: &[String],
+) -> Result<Vec<Game<AlgebraicMove>>> {
+    let line = parse_line(moves)?;
+    let positions = position::replay(line.clone());
+
+    if positions.len() <= line.len() {
+        return Err(Error::InvalidArgument(format!(
+            "Illegal move {}",
+            moves[positions.len() - 1]
+        )));
+    }
+
+    let index = PositionIndex::new(&games);
+    let plies = index
+        .find(&positions.last().unwrap().to_setup(EnPassantMode::Legal))
+        .iter()
+        .map(|occurrence| (occurrence.game(), occurrence.ply()))
+        .collect::<HashMap<_, _>>();
+
+    Ok(games
+        .into_iter()
+        .enumerate()
+        .filter_map(|(game, moves)| Some(moves.transposed(line.clone(), *plies.get(&game)?)))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use std::io;
+
+    use super::AnalysisRequest;
+    use crate::error::Error;
+    use crate::filter::GameFilter;
+    use crate::game::test_utils::*;
+    use crate::game::Game;
+    use crate::masters::MastersCache;
+    use crate::AlgebraicMove;
+
+    #[fixture]
+    fn games() -> Vec<Game<AlgebraicMove>> {
+        vec![
+            parse_game("[Result \"1-0\"]\n1. e4 e5 2. Nf3 1-0"),
+            parse_game("[Result \"0-1\"]\n1. e4 c5 0-1"),
+            parse_game("[Result \"1/2-1/2\"]\n1. e4 e5 1/2-1/2"),
+            parse_game("[Result \"1-0\"]\n1. Nf3 e5 2. e4 1-0"),
+        ]
+    }
+
+    #[rstest]
+    fn analyze_should_report_branches_after_the_line(games: Vec<Game<AlgebraicMove>>) {
+        let request = AnalysisRequest::new(GameFilter::all())
+            .moves(vec![String::from("e4")])
+            .branches(true)
+            .min_sharpness(Some(0.));
+
+        let report = request.analyze(games).unwrap();
+
+        assert_eq!(report.results().games(), 3);
+        assert_eq!(report.decisive().unwrap().decisive(), 2);
+        assert_eq!(report.opening().unwrap().family(), "King's Pawn Game");
+        assert_eq!(
+            report.to_string(),
+            "3 games\nWhite Wins: 33.33%\nBlack Wins: 33.33%\nDraw: 33.33%\n\
+             Decisive: 66.67%\nMoves:\n  c5 (1 games, white scores 0.0%, 100.00% decisive) \
+             Sicilian Defense\n  e5 (2 games, white scores 75.0%, 50.00% decisive)"
+        );
+    }
+
+    #[rstest]
+    fn analyze_should_flag_branches_diverging_from_masters(games: Vec<Game<AlgebraicMove>>) {
+        let masters = MastersCache::parse(
+            r#"{"rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1": {
+                "white": 40, "draws": 40, "black": 10,
+                "moves": [
+                    {"san": "e5", "white": 30, "draws": 30, "black": 0},
+                    {"san": "c5", "white": 10, "draws": 10, "black": 10}
+                ]
+            }}"#,
+        )
+        .unwrap();
+        let request = AnalysisRequest::new(GameFilter::all())
+            .moves(vec![String::from("e4")])
+            .branches(true)
+            .masters(Some(masters));
+
+        let report = request.analyze(games).unwrap();
+        let branches = report.branches().unwrap();
+
+        assert_eq!(report.masters().unwrap().games(), 90);
+        assert_eq!(branches[0].move_(), "e5");
+        assert_eq!(branches[0].masters().unwrap().games(), 60);
+        assert!(!branches[0].diverges());
+        assert_eq!(branches[1].move_(), "c5");
+        assert!(branches[1].diverges());
+        assert!(report.to_string().ends_with(
+            "(1 games, white scores 0.0%; masters 33.3% of games, white scores 50.0%) \
+                        Sicilian Defense [diverges from masters]"
+        ));
+    }
+
+    #[rstest]
+    fn analyze_should_count_short_games_separately(mut games: Vec<Game<AlgebraicMove>>) {
+        games.push(parse_game("[Result \"0-1\"]\n0-1"));
+        games.push(parse_game("[Result \"1-0\"]\n1. d4 1-0"));
+
+        let report = AnalysisRequest::new(GameFilter::all())
+            .analyze(games.clone())
+            .unwrap();
+
+        assert_eq!(report.results().games(), 4);
+        assert_eq!(report.short_games(), 2);
+        assert!(report
+            .to_string()
+            .ends_with("Draw: 25.00%\nLeft out 2 games shorter than 2 plies"));
+
+        let report = AnalysisRequest::new(GameFilter::all())
+            .min_plies(0)
+            .analyze(games)
+            .unwrap();
+
+        assert_eq!(report.results().games(), 6);
+        assert_eq!(report.short_games(), 0);
+    }
+
+    #[rstest]
+    fn analyze_should_count_transpositions_by_position(games: Vec<Game<AlgebraicMove>>) {
+        let request = AnalysisRequest::new(GameFilter::all())
+            .moves(
+                ["e4", "e5", "Nf3"]
+                    .iter()
+                    .map(|move_| move_.to_string())
+                    .collect(),
+            )
+            .by_position(true);
+
+        assert!(request.needs_games());
+        assert_eq!(request.analyze(games.clone()).unwrap().results().games(), 2);
+        assert!(request
+            .moves(vec![String::from("e5")])
+            .analyze(games)
+            .is_err());
+    }
+
+    #[rstest(by_position, case(false), case(true))]
+    fn analyze_should_reject_moves_that_are_not_valid_san(
+        games: Vec<Game<AlgebraicMove>>,
+        by_position: bool,
+    ) {
+        let request = AnalysisRequest::new(GameFilter::all())
+            .moves(vec![String::from("e4"), String::from("zz")])
+            .by_position(by_position)
+            .branches(true);
+
+        let error = request.analyze(games).unwrap_err();
+
+        assert!(matches!(error, Error::InvalidArgument(_)));
+        assert_eq!(error.to_string(), "Invalid move zz: Invalid file: z");
+        assert_eq!(io::Error::from(error).kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,143 @@
+/// How many of the items offered to a `Sampler` it keeps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SampleSize {
+    /// At most this many items, each as likely to be kept as any other.
+    Count(usize),
+    /// Each item with this probability, between 0 and 1.
+    Rate(f64),
+}
+
+/// A small pseudorandom number generator (SplitMix64), so that samples taken with the same seed
+/// are the same on every platform.
+#[derive(Debug, Clone)]
+struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..bound`.
+    fn below(&mut self, bound: usize) -> usize {
+        ((self.next_u64() as u128 * bound as u128) >> 64) as usize
+    }
+
+    /// Returns a number in `0.0..1.0`.
+    fn fraction(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Takes a random sample of a stream of items, such as the games in a large database, without
+/// holding more than the sample in memory. Samples of a count are taken by reservoir sampling, so
+/// the length of the stream doesn't need to be known up front.
+#[derive(Debug, Clone)]
+pub struct Sampler<T> {
+    size: SampleSize,
+    rng: Rng,
+    seen: usize,
+    kept: Vec<(usize, T)>,
+}
+
+impl<T> Sampler<T> {
+    /// Creates a `Sampler` keeping `size` items, chosen by a generator seeded with `seed`.
+    pub fn new(size: SampleSize, seed: u64) -> Sampler<T> {
+        Sampler {
+            size,
+            rng: Rng { state: seed },
+            seen: 0,
+            kept: Vec::new(),
+        }
+    }
+
+    /// Offers the next item of the stream to the sample.
+    pub fn offer(&mut self, item: T) {
+        let index = self.seen;
+        self.seen += 1;
+
+        match self.size {
+            SampleSize::Count(count) if self.kept.len() < count => self.kept.push((index, item)),
+            SampleSize::Count(count) => {
+                let slot = self.rng.below(index + 1);
+
+                if slot < count {
+                    self.kept[slot] = (index, item);
+                }
+            }
+            SampleSize::Rate(rate) => {
+                if self.rng.fraction() < rate {
+                    self.kept.push((index, item));
+                }
+            }
+        }
+    }
+
+    /// Returns the number of items offered so far.
+    pub fn seen(&self) -> usize {
+        self.seen
+    }
+
+    /// Returns the items kept, in the order they were offered.
+    pub fn into_sample(self) -> Vec<T> {
+        let mut kept = self.kept;
+        kept.sort_by_key(|(index, _)| *index);
+
+        kept.into_iter().map(|(_, item)| item).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::{SampleSize, Sampler};
+
+    fn sample(size: SampleSize, seed: u64, items: usize) -> Vec<usize> {
+        let mut sampler = Sampler::new(size, seed);
+
+        for item in 0..items {
+            sampler.offer(item);
+        }
+
+        sampler.into_sample()
+    }
+
+    #[rstest]
+    fn count_samples_should_keep_that_many_in_order() {
+        let kept = sample(SampleSize::Count(10), 7, 1000);
+
+        assert_eq!(kept.len(), 10);
+        assert!(kept.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(kept, sample(SampleSize::Count(10), 7, 1000));
+        assert_ne!(kept, sample(SampleSize::Count(10), 8, 1000));
+        assert_eq!(sample(SampleSize::Count(10), 7, 4), vec![0, 1, 2, 3]);
+    }
+
+    #[rstest]
+    fn count_samples_should_be_uniform() {
+        let mut counts = [0; 10];
+
+        for seed in 0..2000 {
+            for item in sample(SampleSize::Count(3), seed, 10) {
+                counts[item] += 1;
+            }
+        }
+
+        // Each item is expected in 600 of the samples.
+        assert!(counts.iter().all(|count| (500..700).contains(count)));
+    }
+
+    #[rstest(rate, case(0.), case(0.25), case(1.))]
+    fn rate_samples_should_keep_about_that_fraction(rate: f64) {
+        let kept = sample(SampleSize::Rate(rate), 3, 10000).len() as f64;
+
+        assert!((kept / 10000. - rate).abs() < 0.02);
+    }
+}
@@ -0,0 +1,103 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::Error;
+use crate::game_id::GameId;
+
+/// Where a game came from, so that sets of games merged from several places can be told apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Source {
+    /// Played on lichess.
+    Lichess,
+    /// Played on chess.com.
+    ChessCom,
+    /// Read from a PGN file kept locally, such as games played over the board.
+    Local,
+}
+
+impl Source {
+    /// Returns the site of the game at `link`, along with the game's identifier on that site,
+    /// or `None` if the link isn't to lichess or chess.com.
+    ///
+    /// Lichess identifies games by the first part of their path, as in
+    /// `https://lichess.org/abcd1234/black`, and chess.com by the last, as in
+    /// `https://www.chess.com/game/live/123456`.
+    pub fn of_link(link: &str) -> Option<(Source, String)> {
+        let id = GameId::from_link(link);
+        let (site, path) = id.as_str().split_once('/')?;
+        let mut parts = path.split('/').filter(|part| !part.is_empty());
+
+        let (source, source_id) = match site {
+            "lichess.org" => (Source::Lichess, parts.next()?),
+            "chess.com" => (Source::ChessCom, parts.next_back()?),
+            _ => return None,
+        };
+
+        Some((source, String::from(source_id)))
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Source::Lichess => "lichess",
+            Source::ChessCom => "chess.com",
+            Source::Local => "local",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Source {
+    type Err = Error;
+
+    /// Parses the names sources are written with, such as `lichess` or `chess.com`, ignoring
+    /// case.
+    fn from_str(source: &str) -> Result<Source, Error> {
+        match source.to_lowercase().as_str() {
+            "lichess" => Ok(Source::Lichess),
+            "chess.com" | "chesscom" => Ok(Source::ChessCom),
+            "local" => Ok(Source::Local),
+            _ => Err(Error::InvalidArgument(format!(
+                "Invalid source: {}",
+                source
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::Source;
+
+    #[rstest(
+        link,
+        expected,
+        case("https://lichess.org/abcd1234", Some((Source::Lichess, "abcd1234"))),
+        case("https://lichess.org/abcd1234/black#12", Some((Source::Lichess, "abcd1234"))),
+        case(
+            "https://www.chess.com/game/live/123456",
+            Some((Source::ChessCom, "123456"))
+        ),
+        case("https://www.chess.com/", None),
+        case("https://example.com/games/1", None)
+    )]
+    fn of_link_should_find_site_and_id(link: &str, expected: Option<(Source, &str)>) {
+        assert_eq!(
+            Source::of_link(link),
+            expected.map(|(source, id)| (source, String::from(id)))
+        );
+    }
+
+    #[rstest]
+    fn sources_should_round_trip_through_names() {
+        for source in [Source::Lichess, Source::ChessCom, Source::Local] {
+            assert_eq!(source.to_string().parse::<Source>().unwrap(), source);
+        }
+
+        assert!("fics".parse::<Source>().is_err());
+    }
+}
@@ -0,0 +1,251 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+use crate::eval::{self, Eval, Judgement};
+use crate::game::{Color, GiveEvals, GivePlayers, ListMoves};
+use crate::moves::Move;
+use crate::phase::{self, Phase};
+
+/// How accurately a set of moves was played, judged by the evaluations before and after each.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Accuracy {
+    moves: usize,
+    centipawn_loss: u64,
+    accuracy: f64,
+    inaccuracies: usize,
+    mistakes: usize,
+    blunders: usize,
+}
+
+impl Accuracy {
+    fn add(&mut self, before: Eval, after: Eval, color: Color) {
+        self.moves += 1;
+        self.centipawn_loss += eval::centipawn_loss(before, after, color) as u64;
+        self.accuracy += eval::move_accuracy(before, after, color);
+
+        match Judgement::of(before, after, color) {
+            Some(Judgement::Inaccuracy) => self.inaccuracies += 1,
+            Some(Judgement::Mistake) => self.mistakes += 1,
+            Some(Judgement::Blunder) => self.blunders += 1,
+            None => {}
+        }
+    }
+
+    /// Returns the number of moves judged.
+    pub fn moves(&self) -> usize {
+        self.moves
+    }
+
+    /// Returns the average number of centipawns lost per move, or `None` if no moves were judged.
+    pub fn average_centipawn_loss(&self) -> Option<f64> {
+        if self.moves > 0 {
+            Some(self.centipawn_loss as f64 / self.moves as f64)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the average accuracy of the moves from 0 to 100, or `None` if no moves were
+    /// judged.
+    pub fn accuracy(&self) -> Option<f64> {
+        if self.moves > 0 {
+            Some(self.accuracy / self.moves as f64)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the number of moves judged to be inaccuracies.
+    pub fn inaccuracies(&self) -> usize {
+        self.inaccuracies
+    }
+
+    /// Returns the number of moves judged to be mistakes.
+    pub fn mistakes(&self) -> usize {
+        self.mistakes
+    }
+
+    /// Returns the number of moves judged to be blunders.
+    pub fn blunders(&self) -> usize {
+        self.blunders
+    }
+}
+
+/// How accurately a player played in games starting with a particular line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineAccuracy<M: Move> {
+    moves: Vec<M>,
+    accuracy: Accuracy,
+}
+
+impl<M: Move> LineAccuracy<M> {
+    /// Returns the moves that make up this line.
+    pub fn moves(&self) -> &[M] {
+        &self.moves
+    }
+
+    /// Returns how accurately the player played in this line.
+    pub fn accuracy(&self) -> Accuracy {
+        self.accuracy
+    }
+}
+
+/// How accurately a player played overall, in each phase of the game, and in each opening.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccuracyStats<M: Move> {
+    overall: Accuracy,
+    phases: BTreeMap<Phase, Accuracy>,
+    lines: Vec<LineAccuracy<M>>,
+}
+
+impl<M: Move> AccuracyStats<M> {
+    /// Returns how accurately the player played across all games.
+    pub fn overall(&self) -> Accuracy {
+        self.overall
+    }
+
+    /// Returns how accurately the player played during `phase`.
+    pub fn phase(&self, phase: Phase) -> Accuracy {
+        self.phases.get(&phase).copied().unwrap_or_default()
+    }
+
+    /// Returns how accurately the player played in each opening line, with the lines with the
+    /// most judged moves first.
+    pub fn lines(&self) -> &[LineAccuracy<M>] {
+        &self.lines
+    }
+}
+
+/// Returns how accurately `player` played in the games of `game_iter`, based on the evaluations
+/// recorded in the games, such as lichess `%eval` comments. Games `player` didn't play in are
+/// ignored.
+///
+/// A move is only judged when there are evaluations both before and after it, so the first move
+/// of each game is never judged. Opening lines are identified by the first `line_length` moves of
+/// each game.
+pub fn accuracy_stats<'a, M, G>(
+    game_iter: &mut dyn Iterator<Item = &'a G>,
+    player: &str,
+    line_length: usize,
+) -> AccuracyStats<M>
+where
+    M: 'static + Clone + Move + Eq + Hash,
+    G: 'a + GiveEvals + GivePlayers + ListMoves<M>,
+{
+    let mut overall = Accuracy::default();
+    let mut phases: BTreeMap<Phase, Accuracy> = BTreeMap::new();
+    let mut lines: HashMap<Vec<M>, Accuracy> = HashMap::new();
+
+    for game in game_iter {
+        let color = match game.color_of(player) {
+            Some(color) => color,
+            None => continue,
+        };
+
+        let evals = game.evals();
+
+        if evals.iter().all(Option::is_none) {
+            continue;
+        }
+
+        let game_phases = phase::phases(game.list_moves());
+        let line = game.list_moves().take(line_length).collect::<Vec<_>>();
+        let line_accuracy = lines.entry(line).or_default();
+
+        let first_ply = match color {
+            Color::White => 2,
+            Color::Black => 1,
+        };
+
+        for ply in (first_ply..evals.len()).step_by(2) {
+            let (before, after) = match (evals[ply - 1], evals[ply]) {
+                (Some(before), Some(after)) => (before, after),
+                _ => continue,
+            };
+
+            overall.add(before, after, color);
+            line_accuracy.add(before, after, color);
+
+            if let Some(phase) = game_phases.get(ply) {
+                phases.entry(*phase).or_default().add(before, after, color);
+            }
+        }
+    }
+
+    let mut lines = lines
+        .into_iter()
+        .filter(|(_, accuracy)| accuracy.moves > 0)
+        .map(|(moves, accuracy)| LineAccuracy { moves, accuracy })
+        .collect::<Vec<_>>();
+
+    lines.sort_by_key(|line| Reverse(line.accuracy.moves));
+
+    AccuracyStats {
+        overall,
+        phases,
+        lines,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use float_cmp::approx_eq;
+
+    use super::accuracy_stats;
+    use crate::game::test_utils::*;
+    use crate::game::Game;
+    use crate::phase::Phase;
+    use crate::AlgebraicMove;
+
+    #[fixture]
+    fn evaluated_games() -> Vec<Game<AlgebraicMove>> {
+        vec![
+            parse_game(
+                "[White \"alice\"]\n[Black \"bob\"]\n[Result \"0-1\"]\n\
+                 1. e4 { [%eval 0.3] } 1... e5 { [%eval 0.35] } \
+                 2. Nf3 { [%eval 0.3] } 2... Nc6 { [%eval 0.4] } \
+                 3. Bc4 { [%eval -2.0] } 3... Nf6 { [%eval -2.1] } 0-1",
+            ),
+            parse_game(
+                "[White \"carol\"]\n[Black \"alice\"]\n[Result \"1-0\"]\n\
+                 1. d4 { [%eval 0.2] } 1... d5 { [%eval 0.25] } 2. c4 1-0",
+            ),
+            parse_game("[White \"alice\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n1. e4 e5 1-0"),
+        ]
+    }
+
+    #[rstest]
+    fn accuracy_stats_should_judge_player_moves(evaluated_games: Vec<Game<AlgebraicMove>>) {
+        let stats = accuracy_stats(&mut evaluated_games.iter(), "alice", 1);
+
+        let overall = stats.overall();
+
+        // Nf3 and Bc4 as white, and d5 as black.
+        assert_eq!(overall.moves(), 3);
+        assert!(approx_eq!(
+            f64,
+            overall.average_centipawn_loss().unwrap(),
+            (5. + 240. + 5.) / 3.,
+            epsilon = 0.00000001
+        ));
+        assert_eq!(overall.blunders(), 1);
+        assert_eq!(overall.mistakes(), 0);
+        // Two nearly perfect moves and one blunder.
+        assert!(overall.accuracy().unwrap() > 75.);
+        assert!(overall.accuracy().unwrap() < 85.);
+    }
+
+    #[rstest]
+    fn accuracy_stats_should_split_by_phase_and_line(evaluated_games: Vec<Game<AlgebraicMove>>) {
+        let stats = accuracy_stats(&mut evaluated_games.iter(), "bob", 1);
+
+        assert_eq!(stats.phase(Phase::Opening).moves(), 3);
+        assert_eq!(stats.phase(Phase::Endgame).moves(), 0);
+        assert_eq!(stats.lines().len(), 1);
+        assert_eq!(stats.lines()[0].accuracy().blunders(), 0);
+        assert_eq!(stats.overall().average_centipawn_loss(), Some(5.));
+    }
+}
@@ -0,0 +1,301 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+use crate::eval::Judgement;
+use crate::game::{Color, GiveEvals, GivePlayers, ListMoves};
+use crate::moves::Move;
+use crate::phase::{self, Phase};
+
+/// How often a player blundered within a range of move numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlunderBucket {
+    first_move: usize,
+    last_move: usize,
+    moves: usize,
+    blunders: usize,
+}
+
+impl BlunderBucket {
+    /// Returns the first move number within this bucket.
+    pub fn first_move(&self) -> usize {
+        self.first_move
+    }
+
+    /// Returns the last move number within this bucket.
+    pub fn last_move(&self) -> usize {
+        self.last_move
+    }
+
+    /// Returns the number of the player's moves judged within this bucket.
+    pub fn moves(&self) -> usize {
+        self.moves
+    }
+
+    /// Returns the number of blunders within this bucket.
+    pub fn blunders(&self) -> usize {
+        self.blunders
+    }
+
+    /// Returns the fraction of judged moves within this bucket that were blunders.
+    pub fn blunder_rate(&self) -> f64 {
+        self.blunders as f64 / self.moves as f64
+    }
+}
+
+/// Where a player's blunders fall in games starting with a particular line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineBlunders<M: Move> {
+    moves: Vec<M>,
+    blunders: usize,
+    buckets: Vec<BlunderBucket>,
+}
+
+impl<M: Move> LineBlunders<M> {
+    /// Returns the moves that make up this line.
+    pub fn moves(&self) -> &[M] {
+        &self.moves
+    }
+
+    /// Returns the number of blunders in games starting with this line.
+    pub fn blunders(&self) -> usize {
+        self.blunders
+    }
+
+    /// Returns the player's blunders in this line by move number, earliest moves first.
+    pub fn buckets(&self) -> &[BlunderBucket] {
+        &self.buckets
+    }
+}
+
+/// Where a player's blunders cluster, by move number, game phase, and opening.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlunderHeat<M: Move> {
+    blunders: usize,
+    buckets: Vec<BlunderBucket>,
+    phases: BTreeMap<Phase, usize>,
+    lines: Vec<LineBlunders<M>>,
+}
+
+impl<M: Move> BlunderHeat<M> {
+    /// Returns the total number of blunders.
+    pub fn blunders(&self) -> usize {
+        self.blunders
+    }
+
+    /// Returns the player's blunders by move number, earliest moves first.
+    pub fn buckets(&self) -> &[BlunderBucket] {
+        &self.buckets
+    }
+
+    /// Returns the number of blunders made during `phase`.
+    pub fn phase(&self, phase: Phase) -> usize {
+        self.phases.get(&phase).copied().unwrap_or(0)
+    }
+
+    /// Returns the player's blunders in each opening line, with the lines with the most blunders
+    /// first. Lines without any blunders are left out.
+    pub fn lines(&self) -> &[LineBlunders<M>] {
+        &self.lines
+    }
+
+    /// Returns the line and range of move numbers with the most blunders, if there were any. Ties
+    /// go to the line with more blunders overall, then to earlier moves.
+    pub fn hotspot(&self) -> Option<(&LineBlunders<M>, &BlunderBucket)> {
+        self.lines
+            .iter()
+            .flat_map(|line| line.buckets.iter().map(move |bucket| (line, bucket)))
+            .filter(|(_, bucket)| bucket.blunders > 0)
+            .min_by_key(|(_, bucket)| (Reverse(bucket.blunders), bucket.first_move))
+    }
+}
+
+#[derive(Default)]
+struct Tally {
+    blunders: usize,
+    buckets: BTreeMap<usize, (usize, usize)>,
+}
+
+impl Tally {
+    fn add(&mut self, bucket: usize, blunder: bool) {
+        let (moves, blunders) = self.buckets.entry(bucket).or_default();
+
+        *moves += 1;
+        *blunders += blunder as usize;
+        self.blunders += blunder as usize;
+    }
+
+    fn buckets(&self, bucket_size: usize) -> Vec<BlunderBucket> {
+        self.buckets
+            .iter()
+            .map(|(bucket, (moves, blunders))| BlunderBucket {
+                first_move: bucket * bucket_size + 1,
+                last_move: (bucket + 1) * bucket_size,
+                moves: *moves,
+                blunders: *blunders,
+            })
+            .collect()
+    }
+}
+
+/// Returns where `player`'s blunders cluster in the games of `game_iter`, based on the
+/// evaluations recorded in the games. Games `player` didn't play in are ignored.
+///
+/// Move numbers are grouped into buckets `bucket_size` moves wide, starting from move 1. Opening
+/// lines are identified by the first `line_length` moves of each game.
+///
+/// # Panics
+///
+/// Panics if `bucket_size` is zero.
+pub fn blunder_heat<'a, M, G>(
+    game_iter: &mut dyn Iterator<Item = &'a G>,
+    player: &str,
+    bucket_size: usize,
+    line_length: usize,
+) -> BlunderHeat<M>
+where
+    M: 'static + Clone + Move + Eq + Hash,
+    G: 'a + GiveEvals + GivePlayers + ListMoves<M>,
+{
+    assert!(bucket_size > 0, "Bucket size must be positive");
+
+    let mut overall = Tally::default();
+    let mut phases: BTreeMap<Phase, usize> = BTreeMap::new();
+    let mut lines: HashMap<Vec<M>, Tally> = HashMap::new();
+
+    for game in game_iter {
+        let color = match game.color_of(player) {
+            Some(color) => color,
+            None => continue,
+        };
+
+        let evals = game.evals();
+
+        if evals.iter().all(Option::is_none) {
+            continue;
+        }
+
+        let game_phases = phase::phases(game.list_moves());
+        let line = game.list_moves().take(line_length).collect::<Vec<_>>();
+        let line_tally = lines.entry(line).or_default();
+
+        let first_ply = match color {
+            Color::White => 2,
+            Color::Black => 1,
+        };
+
+        for ply in (first_ply..evals.len()).step_by(2) {
+            let (before, after) = match (evals[ply - 1], evals[ply]) {
+                (Some(before), Some(after)) => (before, after),
+                _ => continue,
+            };
+
+            let blunder = Judgement::of(before, after, color) == Some(Judgement::Blunder);
+            let bucket = ply / 2 / bucket_size;
+
+            overall.add(bucket, blunder);
+            line_tally.add(bucket, blunder);
+
+            if let (true, Some(phase)) = (blunder, game_phases.get(ply)) {
+                *phases.entry(*phase).or_default() += 1;
+            }
+        }
+    }
+
+    let mut lines = lines
+        .into_iter()
+        .filter(|(_, tally)| tally.blunders > 0)
+        .map(|(moves, tally)| LineBlunders {
+            moves,
+            blunders: tally.blunders,
+            buckets: tally.buckets(bucket_size),
+        })
+        .collect::<Vec<_>>();
+
+    lines.sort_by_cached_key(|line| {
+        (
+            Reverse(line.blunders),
+            line.moves
+                .iter()
+                .map(|move_| move_.clone().to_algebraic())
+                .collect::<Vec<_>>(),
+        )
+    });
+
+    BlunderHeat {
+        blunders: overall.blunders,
+        buckets: overall.buckets(bucket_size),
+        phases,
+        lines,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::blunder_heat;
+    use crate::game::test_utils::*;
+    use crate::game::Game;
+    use crate::moves::Move;
+    use crate::phase::Phase;
+    use crate::AlgebraicMove;
+
+    #[fixture]
+    fn evaluated_games() -> Vec<Game<AlgebraicMove>> {
+        vec![
+            // Blunders on moves 2 and 3.
+            parse_game(
+                "[White \"alice\"]\n[Black \"bob\"]\n[Result \"0-1\"]\n\
+                 1. e4 { [%eval 0.3] } 1... e5 { [%eval 0.3] } \
+                 2. Nf3 { [%eval -3.0] } 2... Nc6 { [%eval 0.0] } \
+                 3. Bc4 { [%eval -3.0] } 3... Nf6 { [%eval -3.0] } 0-1",
+            ),
+            // A blunder on move 1 as black.
+            parse_game(
+                "[White \"carol\"]\n[Black \"alice\"]\n[Result \"1-0\"]\n\
+                 1. d4 { [%eval 0.2] } 1... d5 { [%eval 3.0] } 2. c4 { [%eval 3.0] } 1-0",
+            ),
+            // No blunders.
+            parse_game(
+                "[White \"alice\"]\n[Black \"dave\"]\n[Result \"1-0\"]\n\
+                 1. e4 { [%eval 0.3] } 1... c5 { [%eval 0.4] } 2. Nf3 { [%eval 0.4] } 1-0",
+            ),
+        ]
+    }
+
+    #[rstest]
+    fn blunder_heat_should_bucket_move_numbers(evaluated_games: Vec<Game<AlgebraicMove>>) {
+        let heat = blunder_heat(&mut evaluated_games.iter(), "alice", 2, 1);
+
+        let buckets = heat
+            .buckets()
+            .iter()
+            .map(|bucket| {
+                (
+                    bucket.first_move(),
+                    bucket.last_move(),
+                    bucket.moves(),
+                    bucket.blunders(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(heat.blunders(), 3);
+        assert_eq!(buckets, vec![(1, 2, 3, 2), (3, 4, 1, 1)]);
+        assert_eq!(heat.phase(Phase::Opening), 3);
+    }
+
+    #[rstest]
+    fn blunder_heat_should_find_hotspot(evaluated_games: Vec<Game<AlgebraicMove>>) {
+        let heat = blunder_heat(&mut evaluated_games.iter(), "alice", 3, 1);
+
+        assert_eq!(heat.lines().len(), 2);
+
+        let (line, bucket) = heat.hotspot().unwrap();
+
+        assert_eq!(line.moves()[0].clone().to_algebraic(), "e4");
+        assert_eq!((bucket.first_move(), bucket.last_move()), (1, 3));
+        assert_eq!(bucket.blunders(), 2);
+    }
+}
@@ -0,0 +1,272 @@
+use crate::game::{Color, GivePlayers, GiveResult};
+use crate::stats::statistic::Value;
+use crate::stats::Score;
+
+/// How the scores of two sets of games differ, and how likely the difference is to be noise.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Comparison {
+    score_a: Score,
+    score_b: Score,
+}
+
+impl Comparison {
+    /// Returns the score over the first set of games.
+    pub fn score_a(&self) -> Score {
+        self.score_a
+    }
+
+    /// Returns the score over the second set of games.
+    pub fn score_b(&self) -> Score {
+        self.score_b
+    }
+
+    /// Returns how much higher the score rate of the second set is than that of the first, or
+    /// `None` if either set is empty.
+    pub fn score_difference(&self) -> Option<f64> {
+        Some(self.score_b.rate()? - self.score_a.rate()?)
+    }
+
+    /// Returns the z statistic of a two-proportion z-test on the score rates, counting each game
+    /// as one trial and each point scored as a success. Returns `None` if either set is empty or
+    /// every game in both sets was won, or every game lost.
+    pub fn z_score(&self) -> Option<f64> {
+        let games_a = self.score_a.games() as f64;
+        let games_b = self.score_b.games() as f64;
+        let pooled_rate = (self.score_a.points() + self.score_b.points()) / (games_a + games_b);
+        let standard_error =
+            (pooled_rate * (1. - pooled_rate) * (1. / games_a + 1. / games_b)).sqrt();
+
+        if standard_error > 0. && standard_error.is_finite() {
+            Some(self.score_difference()? / standard_error)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the two-sided p-value of the z-test given by [`z_score`](Comparison::z_score).
+    pub fn z_test_p_value(&self) -> Option<f64> {
+        Some(erfc(self.z_score()?.abs() / 2f64.sqrt()))
+    }
+
+    /// Returns the chi-square statistic for whether wins, draws, and losses are distributed
+    /// differently between the two sets, along with its degrees of freedom. Returns `None` if
+    /// either set is empty or only one kind of result occurs.
+    pub fn chi_square(&self) -> Option<(f64, usize)> {
+        let rows = [self.score_a, self.score_b];
+        let total = (self.score_a.games() + self.score_b.games()) as f64;
+
+        if self.score_a.games() == 0 || self.score_b.games() == 0 {
+            return None;
+        }
+
+        let columns: [fn(&Score) -> usize; 3] = [Score::wins, Score::draws, Score::losses];
+
+        let mut statistic = 0.;
+        let mut used_columns = 0;
+
+        for column in columns.iter() {
+            let column_total = rows.iter().map(column).sum::<usize>() as f64;
+
+            if column_total == 0. {
+                continue;
+            }
+
+            used_columns += 1;
+
+            for row in rows.iter() {
+                let expected = row.games() as f64 * column_total / total;
+                let observed = column(row) as f64;
+
+                statistic += (observed - expected).powi(2) / expected;
+            }
+        }
+
+        if used_columns > 1 {
+            Some((statistic, used_columns - 1))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the p-value of the chi-square test given by
+    /// [`chi_square`](Comparison::chi_square).
+    pub fn chi_square_p_value(&self) -> Option<f64> {
+        match self.chi_square()? {
+            (statistic, 1) => Some(erfc((statistic / 2.).sqrt())),
+            (statistic, 2) => Some((-statistic / 2.).exp()),
+            _ => None,
+        }
+    }
+
+    /// Returns the comparison as a value for writing as JSON or as a row of a table, with the
+    /// games and score rate of each set and the statistics that are known.
+    pub fn to_value(&self) -> Value {
+        let (chi_square, degrees_of_freedom) = self.chi_square().unzip();
+
+        Value::map(vec![
+            ("games_a", self.score_a.games().into()),
+            ("rate_a", self.score_a.rate().into()),
+            ("games_b", self.score_b.games().into()),
+            ("rate_b", self.score_b.rate().into()),
+            ("difference", self.score_difference().into()),
+            ("z_score", self.z_score().into()),
+            ("z_test_p_value", self.z_test_p_value().into()),
+            ("chi_square", chi_square.into()),
+            ("degrees_of_freedom", degrees_of_freedom.into()),
+            ("chi_square_p_value", self.chi_square_p_value().into()),
+        ])
+    }
+}
+
+/// Returns the complementary error function of `x`, accurate to within about `1.2e-7`.
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1. / (1. + 0.5 * z);
+
+    let coefficients = [
+        -1.265_512_23,
+        1.000_023_68,
+        0.374_091_96,
+        0.096_784_18,
+        -0.186_288_06,
+        0.278_868_07,
+        -1.135_203_98,
+        1.488_515_87,
+        -0.822_152_23,
+        0.170_872_77,
+    ];
+    let polynomial = coefficients
+        .iter()
+        .rev()
+        .fold(0., |sum, coefficient| sum * t + coefficient);
+
+    let result = t * (-z * z + polynomial).exp();
+
+    if x >= 0. {
+        result
+    } else {
+        2. - result
+    }
+}
+
+fn score<'a, G>(game_iter: &mut dyn Iterator<Item = &'a G>, player: Option<&str>) -> Score
+where
+    G: 'a + GivePlayers + GiveResult,
+{
+    let mut score = Score::new();
+
+    for game in game_iter {
+        let color = match player {
+            Some(player) => match game.color_of(player) {
+                Some(color) => color,
+                None => continue,
+            },
+            None => Color::White,
+        };
+
+        score.add(game.result().for_color(color));
+    }
+
+    score
+}
+
+/// Compares the scores of the games in `set_a` and `set_b`.
+///
+/// When `player` is given, scores are from their perspective and games they didn't play in are
+/// ignored. Otherwise scores are from white's perspective.
+pub fn compare<'a, G>(
+    set_a: &mut dyn Iterator<Item = &'a G>,
+    set_b: &mut dyn Iterator<Item = &'a G>,
+    player: Option<&str>,
+) -> Comparison
+where
+    G: 'a + GivePlayers + GiveResult,
+{
+    Comparison {
+        score_a: score(set_a, player),
+        score_b: score(set_b, player),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use float_cmp::approx_eq;
+
+    use super::{compare, erfc};
+    use crate::game::test_utils::*;
+    use crate::game::Game;
+    use crate::AlgebraicMove;
+
+    fn close(a: f64, b: f64) -> bool {
+        approx_eq!(f64, a, b, epsilon = 0.0001)
+    }
+
+    fn games(white: &str, black: &str, result: &str, count: usize) -> Vec<Game<AlgebraicMove>> {
+        (0..count)
+            .map(|_| game(white, black, result, "1. e4 e5"))
+            .collect()
+    }
+
+    #[rstest(x, expected, case(0., 1.), case(1., 0.157299), case(-1., 1.842701))]
+    fn erfc_should_approximate_complementary_error_function(x: f64, expected: f64) {
+        assert!(close(erfc(x), expected));
+    }
+
+    #[rstest]
+    fn compare_should_test_score_difference() {
+        let before = [
+            games("alice", "bob", "1-0", 10),
+            games("alice", "bob", "0-1", 10),
+        ]
+        .concat();
+        let after = [
+            games("bob", "alice", "0-1", 15),
+            games("bob", "alice", "1-0", 5),
+        ]
+        .concat();
+
+        let comparison = compare(&mut before.iter(), &mut after.iter(), Some("alice"));
+
+        assert_eq!(comparison.score_a().wins(), 10);
+        assert_eq!(comparison.score_b().wins(), 15);
+        assert!(close(comparison.score_difference().unwrap(), 0.25));
+        assert!(close(comparison.z_score().unwrap(), 1.632993));
+        assert!(close(comparison.z_test_p_value().unwrap(), 0.102470));
+
+        // Without draws, the chi-square test is the same as the z-test.
+        let (chi_square, degrees_of_freedom) = comparison.chi_square().unwrap();
+
+        assert!(close(chi_square, 1.632993f64.powi(2)));
+        assert_eq!(degrees_of_freedom, 1);
+        assert!(close(comparison.chi_square_p_value().unwrap(), 0.102470));
+    }
+
+    #[rstest]
+    fn compare_should_use_white_perspective_without_player() {
+        let set_a = [games("a", "b", "1-0", 2), games("a", "b", "1/2-1/2", 2)].concat();
+        let set_b = [games("c", "d", "0-1", 2), games("c", "d", "1/2-1/2", 2)].concat();
+
+        let comparison = compare(&mut set_a.iter(), &mut set_b.iter(), None);
+
+        assert!(close(comparison.score_difference().unwrap(), -0.5));
+        assert_eq!(comparison.chi_square().unwrap().1, 2);
+    }
+
+    #[rstest]
+    fn compare_should_give_nothing_for_empty_sets() {
+        let set_a = games("alice", "bob", "1-0", 3);
+
+        let comparison = compare(&mut set_a.iter(), &mut Vec::new().iter(), None);
+
+        assert_eq!(comparison.score_difference(), None);
+        assert_eq!(comparison.z_test_p_value(), None);
+        assert_eq!(comparison.chi_square_p_value(), None);
+        assert_eq!(
+            comparison.to_value().to_table(','),
+            "games_a,rate_a,games_b,rate_b,difference,z_score,z_test_p_value,chi_square,\
+             degrees_of_freedom,chi_square_p_value\n3,1,0,,,,,,,\n"
+        );
+    }
+}
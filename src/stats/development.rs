@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use shakmaty::{File, Square};
+
+use crate::game::{Color, GivePlayers, ListMoves};
+use crate::moves::Move;
+use crate::position;
+
+/// Something a player does for the first time in a game while developing their pieces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Milestone {
+    /// The rook starting on the a-file first moves.
+    QueenRook,
+    /// The knight starting on the b-file first moves.
+    QueenKnight,
+    /// The bishop starting on the c-file first moves.
+    QueenBishop,
+    /// The queen first moves.
+    Queen,
+    /// The king first moves, including by castling.
+    King,
+    /// The bishop starting on the f-file first moves.
+    KingBishop,
+    /// The knight starting on the g-file first moves.
+    KingKnight,
+    /// The rook starting on the h-file first moves.
+    KingRook,
+    /// The player castles, to either side.
+    Castling,
+    /// The player captures a piece for the first time.
+    FirstCapture,
+}
+
+impl Milestone {
+    /// Every milestone, with the pieces in the order they stand on the back rank.
+    pub const ALL: [Milestone; 10] = [
+        Milestone::QueenRook,
+        Milestone::QueenKnight,
+        Milestone::QueenBishop,
+        Milestone::Queen,
+        Milestone::King,
+        Milestone::KingBishop,
+        Milestone::KingKnight,
+        Milestone::KingRook,
+        Milestone::Castling,
+        Milestone::FirstCapture,
+    ];
+
+    /// Returns the file the piece the milestone is the first move of starts on, or `None` for
+    /// milestones that aren't the first move of a piece.
+    fn file(self) -> Option<File> {
+        Milestone::ALL[..8]
+            .iter()
+            .position(|piece| *piece == self)
+            .map(|file| File::new(file as u32))
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl fmt::Display for Milestone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Milestone::QueenRook => "queen's rook",
+            Milestone::QueenKnight => "queen's knight",
+            Milestone::QueenBishop => "queen's bishop",
+            Milestone::Queen => "queen",
+            Milestone::King => "king",
+            Milestone::KingBishop => "king's bishop",
+            Milestone::KingKnight => "king's knight",
+            Milestone::KingRook => "king's rook",
+            Milestone::Castling => "castling",
+            Milestone::FirstCapture => "first capture",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// How soon the pieces were developed in a set of games, counting one side of each game.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Development {
+    games: usize,
+    reached: [usize; 10],
+    move_totals: [usize; 10],
+}
+
+impl Development {
+    /// Returns the number of games counted.
+    pub fn games(&self) -> usize {
+        self.games
+    }
+
+    /// Returns the number of games `milestone` was reached in.
+    pub fn reached(&self, milestone: Milestone) -> usize {
+        self.reached[milestone.index()]
+    }
+
+    /// Returns the fraction of games `milestone` was reached in, or `None` if no games were
+    /// counted.
+    pub fn reach_rate(&self, milestone: Milestone) -> Option<f64> {
+        match self.games {
+            0 => None,
+            games => Some(self.reached(milestone) as f64 / games as f64),
+        }
+    }
+
+    /// Returns the average move number `milestone` was reached on, counting only the games it
+    /// was reached in, or `None` if it never was.
+    pub fn average_move(&self, milestone: Milestone) -> Option<f64> {
+        match self.reached(milestone) {
+            0 => None,
+            reached => Some(self.move_totals[milestone.index()] as f64 / reached as f64),
+        }
+    }
+
+    /// Counts the side of the game played with `moves` that had `color`.
+    fn add(&mut self, moves: &[shakmaty::Move], color: Color) {
+        self.games += 1;
+
+        for (milestone, move_number) in Milestone::ALL.iter().zip(milestones(moves, color)) {
+            if let Some(move_number) = move_number {
+                self.reached[milestone.index()] += 1;
+                self.move_totals[milestone.index()] += move_number;
+            }
+        }
+    }
+}
+
+/// Returns the move number each milestone was reached on by the side with `color` in the game
+/// played with `moves`, in the order of `Milestone::ALL`.
+fn milestones(moves: &[shakmaty::Move], color: Color) -> [Option<usize>; 10] {
+    let mut reached = [None; 10];
+
+    // The pieces still to make their first move, by the square they stand on.
+    let mut undeveloped = Milestone::ALL
+        .iter()
+        .filter_map(|milestone| {
+            let file = milestone.file()?;
+
+            Some((Square::from_coords(file, color.backrank()), *milestone))
+        })
+        .collect::<HashMap<_, _>>();
+
+    for (ply, legal_move) in moves.iter().enumerate() {
+        let mover = if ply % 2 == 0 {
+            Color::White
+        } else {
+            Color::Black
+        };
+
+        if mover != color {
+            // Pieces taken before they moved never reach their milestone.
+            if legal_move.is_capture() {
+                undeveloped.remove(&legal_move.to());
+            }
+
+            continue;
+        }
+
+        let mut reach = |milestone: Milestone| {
+            reached[milestone.index()].get_or_insert(ply / 2 + 1);
+        };
+
+        if legal_move.castling_side().is_some() {
+            reach(Milestone::Castling);
+        }
+
+        if legal_move.is_capture() {
+            reach(Milestone::FirstCapture);
+        }
+
+        // Castling moves the king from its square and the rook from the square it's written to.
+        let rook = legal_move.castling_side().map(|_| legal_move.to());
+
+        for square in legal_move.from().into_iter().chain(rook) {
+            if let Some(milestone) = undeveloped.remove(&square) {
+                reach(milestone);
+            }
+        }
+    }
+
+    reached
+}
+
+/// Returns how soon `player` developed their pieces in their games among the games of
+/// `game_iter`, followed by the same for every other side of the games, as a reference to compare
+/// them with. Moves after an illegal move in a game are ignored.
+pub fn development<'a, M, G>(
+    game_iter: &mut dyn Iterator<Item = &'a G>,
+    player: &str,
+) -> (Development, Development)
+where
+    M: 'static + Clone + Move,
+    G: 'a + GivePlayers + ListMoves<M>,
+{
+    let mut player_development = Development::default();
+    let mut reference = Development::default();
+
+    for game in game_iter {
+        let moves = position::legal_moves(game.list_moves());
+
+        for color in [Color::White, Color::Black] {
+            if game.player(color).is_named(player) {
+                player_development.add(&moves, color);
+            } else {
+                reference.add(&moves, color);
+            }
+        }
+    }
+
+    (player_development, reference)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::{development, Milestone};
+    use crate::game::test_utils::*;
+
+    #[rstest]
+    fn development_should_find_first_moves_of_each_piece() {
+        let games = [
+            game(
+                "alice",
+                "bob",
+                "1-0",
+                "1. e4 e5 2. Nf3 Nc6 3. Bc4 Nf6 4. O-O Bc5 5. Nxe5 Nxe5",
+            ),
+            game("bob", "alice", "1-0", "1. d4 Nf6 2. Bg5 h6 3. Bxf6 exf6"),
+        ];
+
+        let (alice, others) = development(&mut games.iter(), "alice");
+
+        assert_eq!(alice.games(), 2);
+        assert_eq!(others.games(), 2);
+        assert_eq!(alice.average_move(Milestone::KingKnight), Some(1.5));
+        assert_eq!(alice.average_move(Milestone::KingBishop), Some(3.));
+        assert_eq!(alice.reached(Milestone::KingBishop), 1);
+        assert_eq!(alice.average_move(Milestone::Castling), Some(4.));
+        assert_eq!(alice.average_move(Milestone::KingRook), Some(4.));
+        assert_eq!(alice.average_move(Milestone::King), Some(4.));
+        assert_eq!(alice.average_move(Milestone::FirstCapture), Some(4.));
+        assert_eq!(alice.reach_rate(Milestone::Queen), Some(0.));
+        assert_eq!(others.average_move(Milestone::QueenKnight), Some(2.));
+        assert_eq!(others.average_move(Milestone::QueenBishop), Some(2.));
+        assert_eq!(others.reach_rate(Milestone::FirstCapture), Some(1.));
+    }
+}
@@ -0,0 +1,236 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+use crate::game::{GameResult, GiveRatings, GiveResult, ListMoves};
+use crate::moves::Move;
+
+/// The draw rate among games whose players' ratings differ by a similar amount.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DrawRateBucket {
+    rating_difference: u16,
+    games: usize,
+    draws: usize,
+}
+
+impl DrawRateBucket {
+    /// Returns the smallest rating difference within this bucket.
+    pub fn rating_difference(&self) -> u16 {
+        self.rating_difference
+    }
+
+    /// Returns the number of games within this bucket.
+    pub fn games(&self) -> usize {
+        self.games
+    }
+
+    /// Returns the number of drawn games within this bucket.
+    pub fn draws(&self) -> usize {
+        self.draws
+    }
+
+    /// Returns the fraction of games within this bucket that were drawn.
+    pub fn draw_rate(&self) -> f64 {
+        self.draws as f64 / self.games as f64
+    }
+}
+
+/// The draw rate among games starting with a particular line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineDrawRate<M: Move> {
+    moves: Vec<M>,
+    games: usize,
+    draws: usize,
+    by_rating_difference: Vec<DrawRateBucket>,
+}
+
+impl<M: Move> LineDrawRate<M> {
+    /// Returns the moves that make up this line.
+    pub fn moves(&self) -> &[M] {
+        &self.moves
+    }
+
+    /// Returns the number of games starting with this line.
+    pub fn games(&self) -> usize {
+        self.games
+    }
+
+    /// Returns the number of drawn games starting with this line.
+    pub fn draws(&self) -> usize {
+        self.draws
+    }
+
+    /// Returns the fraction of games starting with this line that were drawn.
+    pub fn draw_rate(&self) -> f64 {
+        self.draws as f64 / self.games as f64
+    }
+
+    /// Returns the draw rate of rated games in this line by rating difference, smallest
+    /// difference first.
+    pub fn by_rating_difference(&self) -> &[DrawRateBucket] {
+        &self.by_rating_difference
+    }
+}
+
+/// Draw rates across a set of games, by rating difference and by opening line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrawRates<M: Move> {
+    by_rating_difference: Vec<DrawRateBucket>,
+    lines: Vec<LineDrawRate<M>>,
+}
+
+impl<M: Move> DrawRates<M> {
+    /// Returns the draw rate of rated games by rating difference, smallest difference first.
+    pub fn by_rating_difference(&self) -> &[DrawRateBucket] {
+        &self.by_rating_difference
+    }
+
+    /// Returns the draw rate for each opening line, with the most frequently played lines first.
+    pub fn lines(&self) -> &[LineDrawRate<M>] {
+        &self.lines
+    }
+}
+
+#[derive(Default)]
+struct Tally {
+    games: usize,
+    draws: usize,
+    buckets: BTreeMap<u16, (usize, usize)>,
+}
+
+impl Tally {
+    fn add(&mut self, drawn: bool, bucket: Option<u16>) {
+        self.games += 1;
+        self.draws += drawn as usize;
+
+        if let Some(bucket) = bucket {
+            let (games, draws) = self.buckets.entry(bucket).or_default();
+
+            *games += 1;
+            *draws += drawn as usize;
+        }
+    }
+
+    fn curve(&self) -> Vec<DrawRateBucket> {
+        self.buckets
+            .iter()
+            .map(|(rating_difference, (games, draws))| DrawRateBucket {
+                rating_difference: *rating_difference,
+                games: *games,
+                draws: *draws,
+            })
+            .collect()
+    }
+}
+
+/// Returns the draw rates of the games in `game_iter` as a function of the rating difference
+/// between the players, and of the opening played.
+///
+/// Rating differences are grouped into buckets `bucket_size` points wide, and games where either
+/// rating is unknown are left out of the rating difference curves. Opening lines are identified
+/// by the first `line_length` moves of each game.
+///
+/// # Panics
+///
+/// Panics if `bucket_size` is zero.
+pub fn draw_rates<'a, M, G>(
+    game_iter: &mut dyn Iterator<Item = &'a G>,
+    bucket_size: u16,
+    line_length: usize,
+) -> DrawRates<M>
+where
+    M: 'static + Clone + Move + Eq + Hash,
+    G: 'a + GiveRatings + GiveResult + ListMoves<M>,
+{
+    assert!(bucket_size > 0, "Bucket size must be positive");
+
+    let mut overall = Tally::default();
+    let mut lines: HashMap<Vec<M>, Tally> = HashMap::new();
+
+    for game in game_iter {
+        let drawn = game.result() == GameResult::Draw;
+        let bucket = match (game.white_rating(), game.black_rating()) {
+            (Some(white_rating), Some(black_rating)) => {
+                Some(white_rating.abs_diff(black_rating) / bucket_size * bucket_size)
+            }
+            _ => None,
+        };
+
+        overall.add(drawn, bucket);
+
+        let line = game.list_moves().take(line_length).collect::<Vec<_>>();
+        lines.entry(line).or_default().add(drawn, bucket);
+    }
+
+    let mut lines = lines
+        .into_iter()
+        .map(|(moves, tally)| LineDrawRate {
+            moves,
+            games: tally.games,
+            draws: tally.draws,
+            by_rating_difference: tally.curve(),
+        })
+        .collect::<Vec<_>>();
+
+    lines.sort_by_key(|line| Reverse(line.games));
+
+    DrawRates {
+        by_rating_difference: overall.curve(),
+        lines,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::draw_rates;
+    use crate::game::test_utils::*;
+    use crate::game::Game;
+    use crate::AlgebraicMove;
+
+    fn rated_game(elos: (u16, u16), result: &str, moves: &str) -> String {
+        format!(
+            "[White \"a\"]\n[Black \"b\"]\n[Result \"{}\"]\n[WhiteElo \"{}\"]\n\
+             [BlackElo \"{}\"]\n{} {}",
+            result, elos.0, elos.1, moves, result
+        )
+    }
+
+    #[fixture]
+    fn rated_games() -> Vec<Game<AlgebraicMove>> {
+        vec![
+            parse_game(&rated_game((1500, 1520), "1/2-1/2", "1. d4 d5")),
+            parse_game(&rated_game((1500, 1450), "1-0", "1. d4 d5")),
+            parse_game(&rated_game((1800, 1550), "1/2-1/2", "1. e4 e5")),
+            parse_game(&rated_game((1550, 1800), "0-1", "1. e4 c5")),
+            parse_game("[White \"a\"]\n[Black \"b\"]\n[Result \"1/2-1/2\"]\n1. d4 1/2-1/2"),
+        ]
+    }
+
+    #[rstest]
+    fn draw_rates_should_bucket_rating_differences(rated_games: Vec<Game<AlgebraicMove>>) {
+        let draw_rates = draw_rates(&mut rated_games.iter(), 100, 1);
+
+        let curve = draw_rates
+            .by_rating_difference()
+            .iter()
+            .map(|bucket| (bucket.rating_difference(), bucket.games(), bucket.draws()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(curve, vec![(0, 2, 1), (200, 2, 1)]);
+    }
+
+    #[rstest]
+    fn draw_rates_should_group_by_line(rated_games: Vec<Game<AlgebraicMove>>) {
+        let draw_rates = draw_rates(&mut rated_games.iter(), 100, 1);
+
+        let d4 = &draw_rates.lines()[0];
+
+        assert_eq!(d4.games(), 3);
+        assert_eq!(d4.draws(), 2);
+        assert_eq!(d4.by_rating_difference().len(), 1);
+        assert_eq!(d4.by_rating_difference()[0].draw_rate(), 0.5);
+        assert_eq!(draw_rates.lines()[1].draw_rate(), 0.5);
+    }
+}
@@ -0,0 +1,169 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::game::{GivePlayers, GiveResult, ListMoves, PlayerResult};
+use crate::moves::Move;
+use crate::openings::{Opening, Openings};
+
+/// How quickly a player loses the games of one opening.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpeningLosses {
+    opening: Option<Opening>,
+    games: usize,
+    loss_lengths: Vec<usize>,
+    quick_moves: usize,
+}
+
+impl OpeningLosses {
+    /// Returns the opening, or `None` for games of no known opening.
+    pub fn opening(&self) -> Option<&Opening> {
+        self.opening.as_ref()
+    }
+
+    /// Returns the number of games the player played in the opening.
+    pub fn games(&self) -> usize {
+        self.games
+    }
+
+    /// Returns the number of games the player lost in the opening.
+    pub fn losses(&self) -> usize {
+        self.loss_lengths.len()
+    }
+
+    /// Returns the length in moves of each game the player lost in the opening, shortest first.
+    pub fn loss_lengths(&self) -> &[usize] {
+        &self.loss_lengths
+    }
+
+    /// Returns the median length in moves of the games the player lost in the opening.
+    pub fn median_loss_length(&self) -> Option<usize> {
+        self.loss_lengths.get(self.loss_lengths.len() / 2).copied()
+    }
+
+    /// Returns the number of losses that were over in fewer moves than the limit given to
+    /// `fast_losses`.
+    pub fn quick_losses(&self) -> usize {
+        self.loss_lengths
+            .iter()
+            .take_while(|length| **length < self.quick_moves)
+            .count()
+    }
+
+    /// Returns the fraction of losses that were quick, or `None` if the player never lost in the
+    /// opening.
+    pub fn quick_loss_rate(&self) -> Option<f64> {
+        match self.losses() {
+            0 => None,
+            losses => Some(self.quick_losses() as f64 / losses as f64),
+        }
+    }
+}
+
+/// Returns the openings `player` lost at least `min_losses` games in among the games of
+/// `game_iter`, ranked by how quickly they lost them: by the median length of their losses, and
+/// then by the fraction of losses over in fewer than `quick_moves` moves. Openings at the top
+/// are those the player gets blown off the board in, rather than ground down. Games `player`
+/// didn't play in are ignored.
+pub fn fast_losses<'a, M, G>(
+    game_iter: &mut dyn Iterator<Item = &'a G>,
+    player: &str,
+    openings: &Openings,
+    quick_moves: usize,
+    min_losses: usize,
+) -> Vec<OpeningLosses>
+where
+    M: 'static + Clone + Move,
+    G: 'a + GivePlayers + GiveResult + ListMoves<M>,
+{
+    let mut by_opening: HashMap<Option<&str>, OpeningLosses> = HashMap::new();
+
+    for game in game_iter {
+        let color = match game.color_of(player) {
+            Some(color) => color,
+            None => continue,
+        };
+
+        let opening = openings.classify(game);
+        let losses = by_opening
+            .entry(opening.map(Opening::name))
+            .or_insert_with(|| OpeningLosses {
+                opening: opening.cloned(),
+                games: 0,
+                loss_lengths: Vec::new(),
+                quick_moves,
+            });
+
+        losses.games += 1;
+
+        if game.result().for_color(color) == PlayerResult::Lost {
+            losses
+                .loss_lengths
+                .push(game.list_moves().count().div_ceil(2));
+        }
+    }
+
+    let mut losses = by_opening
+        .into_values()
+        .filter(|losses| losses.losses() > 0 && losses.losses() >= min_losses)
+        .map(|mut losses| {
+            losses.loss_lengths.sort_unstable();
+            losses
+        })
+        .collect::<Vec<_>>();
+
+    losses.sort_by(|a, b| {
+        a.median_loss_length()
+            .cmp(&b.median_loss_length())
+            .then_with(|| {
+                b.quick_loss_rate()
+                    .partial_cmp(&a.quick_loss_rate())
+                    .unwrap_or(Ordering::Equal)
+            })
+            .then_with(|| b.losses().cmp(&a.losses()))
+            .then_with(|| {
+                let name_a = a.opening.as_ref().map(Opening::name);
+                name_a.cmp(&b.opening.as_ref().map(Opening::name))
+            })
+    });
+
+    losses
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::fast_losses;
+    use crate::game::test_utils::*;
+    use crate::openings::Openings;
+
+    const SCHOLARS_MATE: &str = "1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7#";
+
+    #[rstest]
+    fn fast_losses_should_rank_openings_by_how_quickly_they_are_lost() {
+        let games = [
+            game("bob", "alice", "1-0", SCHOLARS_MATE),
+            game("bob", "alice", "1-0", SCHOLARS_MATE),
+            game("alice", "bob", "1-0", SCHOLARS_MATE),
+            game(
+                "alice",
+                "carol",
+                "0-1",
+                "1. d4 d5 2. c4 e6 3. Nc3 Nf6 4. a3 a6 5. h3 h6 6. g3 g6",
+            ),
+            game("alice", "carol", "0-1", "1. d4 d5 2. c4 e6 3. Nc3 Nf6"),
+            game("alice", "dave", "0-1", "1. c4 e5"),
+        ];
+
+        let losses = fast_losses(&mut games.iter(), "alice", Openings::embedded(), 5, 2);
+
+        assert_eq!(losses.len(), 2);
+        assert_eq!(losses[0].games(), 3);
+        assert_eq!(losses[0].median_loss_length(), Some(4));
+        assert_eq!(losses[0].quick_loss_rate(), Some(1.));
+        assert_eq!(losses[1].games(), 2);
+        assert_eq!(losses[1].loss_lengths(), &[3, 6]);
+        assert_eq!(losses[1].median_loss_length(), Some(6));
+        assert_eq!(losses[1].quick_losses(), 1);
+    }
+}
@@ -0,0 +1,334 @@
+use std::collections::HashMap;
+
+use shakmaty::{File, Position, Rank, Role, Square};
+
+use crate::game::{Color, GivePlayers, ListMoves};
+use crate::moves::Move;
+use crate::openings::{Opening, Openings};
+use crate::position;
+
+/// Size of the margin around each board drawn by `stack_svg`, in pixels, for its title and
+/// coordinates.
+const MARGIN: u32 = 24;
+
+/// Where a player's pieces went in the games of one opening played with one color.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heatmap {
+    opening: Option<Opening>,
+    color: Color,
+    games: usize,
+    positions: usize,
+    visits: [[usize; 64]; 6],
+    occupancy: [[usize; 64]; 6],
+}
+
+impl Heatmap {
+    /// Returns the opening, or `None` for games of no known opening.
+    pub fn opening(&self) -> Option<&Opening> {
+        self.opening.as_ref()
+    }
+
+    /// Returns the color the player had.
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /// Returns the number of games counted.
+    pub fn games(&self) -> usize {
+        self.games
+    }
+
+    /// Returns the number of positions counted, one for each position of each game.
+    pub fn positions(&self) -> usize {
+        self.positions
+    }
+
+    /// Returns the number of times one of the player's pieces of type `role` moved to `square`,
+    /// or of any type when `role` is `None`.
+    pub fn visits(&self, role: Option<Role>, square: Square) -> usize {
+        count(&self.visits, role, square)
+    }
+
+    /// Returns the number of positions one of the player's pieces of type `role` stood on
+    /// `square` in, or of any type when `role` is `None`.
+    pub fn occupancy(&self, role: Option<Role>, square: Square) -> usize {
+        count(&self.occupancy, role, square)
+    }
+
+    /// Returns the fraction of positions one of the player's pieces of type `role` stood on
+    /// `square` in, or of any type when `role` is `None`.
+    pub fn occupancy_rate(&self, role: Option<Role>, square: Square) -> Option<f64> {
+        match self.positions {
+            0 => None,
+            positions => Some(self.occupancy(role, square) as f64 / positions as f64),
+        }
+    }
+
+    /// Returns the heatmap of where pieces of type `role`, or of any type when `role` is `None`,
+    /// stood, as an SVG image of a board with squares `cell` pixels wide, shaded by how often
+    /// they were occupied. The board is seen from the player's side.
+    pub fn to_svg(&self, role: Option<Role>, cell: u32) -> String {
+        stack_svg(std::slice::from_ref(self), role, cell)
+    }
+
+    /// Returns the elements drawing the board with its squares `cell` pixels wide.
+    fn svg_body(&self, role: Option<Role>, cell: u32) -> String {
+        let title = match &self.opening {
+            Some(opening) => format!("{} {}", opening.eco(), opening.name()),
+            None => String::from("Unknown opening"),
+        };
+        let color = match self.color {
+            Color::White => "white",
+            Color::Black => "black",
+        };
+
+        let mut output = format!(
+            "<text x=\"{}\" y=\"16\">{} as {} ({} games)</text>",
+            MARGIN,
+            escape(&title),
+            color,
+            self.games
+        );
+
+        let most = (0..64)
+            .map(|index| self.occupancy(role, Square::new(index)))
+            .max()
+            .unwrap_or(0)
+            .max(1);
+
+        for square in (0..64).map(Square::new) {
+            // Files run left to right and ranks bottom to top from the player's side.
+            let (column, row) = match self.color {
+                Color::White => (u32::from(square.file()), 7 - u32::from(square.rank())),
+                Color::Black => (7 - u32::from(square.file()), u32::from(square.rank())),
+            };
+            let heat = self.occupancy(role, square) as f64 / most as f64;
+
+            output += &format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{cell}\" height=\"{cell}\" \
+                 fill=\"{}\" fill-opacity=\"{:.3}\" stroke=\"#ccc\"/>",
+                MARGIN + column * cell,
+                MARGIN + row * cell,
+                if square.is_light() {
+                    "#d62728"
+                } else {
+                    "#a50f15"
+                },
+                0.05 + 0.95 * heat,
+                cell = cell
+            );
+        }
+
+        for index in 0..8 {
+            let (file, rank) = match self.color {
+                Color::White => (File::new(index), Rank::new(7 - index)),
+                Color::Black => (File::new(7 - index), Rank::new(index)),
+            };
+
+            output += &format!(
+                "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>\
+                 <text x=\"{}\" y=\"{}\" text-anchor=\"middle\" dominant-baseline=\"middle\">{}</text>",
+                MARGIN + index * cell + cell / 2,
+                MARGIN + 8 * cell + 14,
+                file.char(),
+                MARGIN / 2,
+                MARGIN + index * cell + cell / 2,
+                rank.char()
+            );
+        }
+
+        output
+    }
+}
+
+/// Returns the count of pieces of type `role` on `square` in `counts`, or of every type when
+/// `role` is `None`.
+fn count(counts: &[[usize; 64]; 6], role: Option<Role>, square: Square) -> usize {
+    match role {
+        Some(role) => counts[role as usize - 1][usize::from(square)],
+        None => counts
+            .iter()
+            .map(|counts| counts[usize::from(square)])
+            .sum(),
+    }
+}
+
+/// Returns `heatmaps` drawn one above the other as a single SVG image, each a board with squares
+/// `cell` pixels wide showing where pieces of type `role`, or of any type when `role` is `None`,
+/// stood.
+pub fn stack_svg(heatmaps: &[Heatmap], role: Option<Role>, cell: u32) -> String {
+    let (width, height) = (8 * cell + 2 * MARGIN, 8 * cell + 2 * MARGIN);
+
+    let panels = heatmaps
+        .iter()
+        .enumerate()
+        .map(|(index, heatmap)| {
+            format!(
+                "<g transform=\"translate(0 {})\">{}</g>",
+                index as u32 * height,
+                heatmap.svg_body(role, cell)
+            )
+        })
+        .collect::<String>();
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+         viewBox=\"0 0 {width} {height}\" font-family=\"sans-serif\" font-size=\"11\">{}</svg>",
+        panels,
+        width = width,
+        height = height * heatmaps.len() as u32
+    )
+}
+
+/// Escapes `text` to be written within an SVG element.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Returns a heatmap of where `player`'s pieces went for each opening and color they played at
+/// least `min_games` games of in `game_iter`, with the most played first. Moves and positions
+/// after an illegal move in a game are left out. Games `player` didn't play in are ignored.
+pub fn piece_heatmaps<'a, M, G>(
+    game_iter: &mut dyn Iterator<Item = &'a G>,
+    player: &str,
+    openings: &Openings,
+    min_games: usize,
+) -> Vec<Heatmap>
+where
+    M: 'static + Clone + Move,
+    G: 'a + GivePlayers + ListMoves<M>,
+{
+    let mut heatmaps: HashMap<(Option<&str>, Color), Heatmap> = HashMap::new();
+
+    for game in game_iter {
+        let color = match game.color_of(player) {
+            Some(color) => color,
+            None => continue,
+        };
+
+        let opening = openings.classify(game);
+        let heatmap = heatmaps
+            .entry((opening.map(Opening::name), color))
+            .or_insert_with(|| Heatmap {
+                opening: opening.cloned(),
+                color,
+                games: 0,
+                positions: 0,
+                visits: [[0; 64]; 6],
+                occupancy: [[0; 64]; 6],
+            });
+
+        heatmap.games += 1;
+
+        for position in position::replay(game.list_moves()) {
+            heatmap.positions += 1;
+
+            for (square, piece) in position.board() {
+                if piece.color == color {
+                    heatmap.occupancy[piece.role as usize - 1][usize::from(square)] += 1;
+                }
+            }
+        }
+
+        // White's moves are at even indices and black's at odd ones.
+        for legal_move in position::legal_moves(game.list_moves())
+            .into_iter()
+            .skip((color == Color::Black) as usize)
+            .step_by(2)
+        {
+            let mut visit = |role: Role, square: Square| {
+                heatmap.visits[role as usize - 1][usize::from(square)] += 1;
+            };
+
+            match legal_move.castling_side() {
+                Some(side) => {
+                    visit(Role::King, side.king_to(color));
+                    visit(Role::Rook, side.rook_to(color));
+                }
+                None => visit(
+                    legal_move.promotion().unwrap_or_else(|| legal_move.role()),
+                    legal_move.to(),
+                ),
+            }
+        }
+    }
+
+    let mut heatmaps = heatmaps
+        .into_values()
+        .filter(|heatmap| heatmap.games >= min_games)
+        .collect::<Vec<_>>();
+
+    heatmaps.sort_by(|a, b| {
+        b.games
+            .cmp(&a.games)
+            .then_with(|| {
+                a.opening
+                    .as_ref()
+                    .map(Opening::name)
+                    .cmp(&b.opening.as_ref().map(Opening::name))
+            })
+            .then_with(|| (a.color == Color::Black).cmp(&(b.color == Color::Black)))
+    });
+
+    heatmaps
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use shakmaty::{Role, Square};
+
+    use super::{piece_heatmaps, stack_svg};
+    use crate::game::test_utils::*;
+    use crate::game::Color;
+    use crate::openings::Openings;
+
+    #[rstest]
+    fn piece_heatmaps_should_count_player_pieces() {
+        let games = [
+            game(
+                "alice",
+                "bob",
+                "1-0",
+                "1. e4 c5 2. Nf3 Nc6 3. Bc4 e6 4. O-O",
+            ),
+            game("alice", "carol", "1-0", "1. e4 c5 2. Nf3 Nc6 3. Bc4 Nf6"),
+            game("dave", "alice", "1-0", "1. e4 c5 2. Nf3 Nc6"),
+            game("bob", "carol", "1-0", "1. e4 c5"),
+        ];
+
+        let heatmaps = piece_heatmaps(&mut games.iter(), "alice", Openings::embedded(), 2);
+
+        assert_eq!(heatmaps.len(), 1);
+
+        let heatmap = &heatmaps[0];
+
+        assert_eq!(
+            heatmap.opening().unwrap().name(),
+            "Sicilian Defense: Old Sicilian"
+        );
+        assert_eq!(heatmap.color(), Color::White);
+        assert_eq!(heatmap.games(), 2);
+        assert_eq!(heatmap.positions(), 15);
+        assert_eq!(heatmap.visits(Some(Role::Knight), Square::F3), 2);
+        assert_eq!(heatmap.visits(Some(Role::King), Square::G1), 1);
+        assert_eq!(heatmap.visits(Some(Role::Rook), Square::F1), 1);
+        assert_eq!(heatmap.visits(None, Square::C5), 0);
+        assert_eq!(heatmap.occupancy(Some(Role::Pawn), Square::E4), 13);
+        assert_eq!(heatmap.occupancy(Some(Role::Pawn), Square::C5), 0);
+        assert_eq!(heatmap.occupancy(None, Square::G1), 7);
+        assert_eq!(
+            heatmap.occupancy_rate(Some(Role::Pawn), Square::E2),
+            Some(2. / 15.)
+        );
+
+        let svg = stack_svg(&heatmaps, Some(Role::Knight), 10);
+
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<rect").count(), 64);
+        assert!(svg.ends_with("</svg>"));
+    }
+}
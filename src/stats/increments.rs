@@ -0,0 +1,302 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::clock::TimeControl;
+use crate::game::{
+    Color, GameResult, GiveClocks, GiveMetadata, GivePlayers, GiveResult, ListMoves, PlayerResult,
+};
+use crate::moves::Move;
+use crate::stats::time_forfeits::lost_on_time;
+use crate::stats::Score;
+
+/// How much more time a player who lost on time must have been left with by a simulated
+/// increment, when they flagged, for the game to count as saved from the flag.
+const SAVING_TIME: Duration = Duration::from_secs(10);
+
+/// How a player's games with one time control would have gone with a different increment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IncrementSimulation {
+    time_control: TimeControl,
+    increment: Duration,
+    games: usize,
+    score: Score,
+    simulated_score: Score,
+    lost_on_time: usize,
+    won_on_time: usize,
+    simulated_lost_on_time: usize,
+    simulated_won_on_time: usize,
+    undecided: usize,
+}
+
+impl IncrementSimulation {
+    fn new(time_control: TimeControl, increment: Duration) -> IncrementSimulation {
+        IncrementSimulation {
+            time_control,
+            increment,
+            games: 0,
+            score: Score::default(),
+            simulated_score: Score::default(),
+            lost_on_time: 0,
+            won_on_time: 0,
+            simulated_lost_on_time: 0,
+            simulated_won_on_time: 0,
+            undecided: 0,
+        }
+    }
+
+    /// Returns the time control the games were played with.
+    pub fn time_control(&self) -> TimeControl {
+        self.time_control
+    }
+
+    /// Returns the increment the games were simulated with.
+    pub fn increment(&self) -> Duration {
+        self.increment
+    }
+
+    /// Returns the number of games simulated.
+    pub fn games(&self) -> usize {
+        self.games
+    }
+
+    /// Returns the player's score in the games as they were played.
+    pub fn score(&self) -> Score {
+        self.score
+    }
+
+    /// Returns the player's score in the games with the simulated increment, leaving out the
+    /// games where a time forfeit would have been avoided, since how they'd have ended can't be
+    /// known.
+    pub fn simulated_score(&self) -> Score {
+        self.simulated_score
+    }
+
+    /// Returns the number of games the player lost on time as they were played.
+    pub fn lost_on_time(&self) -> usize {
+        self.lost_on_time
+    }
+
+    /// Returns the number of games the player won on time as they were played.
+    pub fn won_on_time(&self) -> usize {
+        self.won_on_time
+    }
+
+    /// Returns the number of games the player would have lost on time with the simulated
+    /// increment.
+    pub fn simulated_lost_on_time(&self) -> usize {
+        self.simulated_lost_on_time
+    }
+
+    /// Returns the number of games the player would have won on time with the simulated
+    /// increment.
+    pub fn simulated_won_on_time(&self) -> usize {
+        self.simulated_won_on_time
+    }
+
+    /// Returns the number of games lost on time that the simulated increment would have left
+    /// the loser with plenty of time in, which are left out of the simulated score.
+    pub fn undecided(&self) -> usize {
+        self.undecided
+    }
+}
+
+/// Returns the time each move of a game took from `clocks`, the time left after each move, in a
+/// game with `time_control`. Moves that seem to have taken less than no time, such as from clock
+/// times rounded to the second, count as taking none.
+fn move_times(clocks: &[Duration], time_control: TimeControl) -> Vec<Duration> {
+    let mut previous = [time_control.base(); 2];
+
+    clocks
+        .iter()
+        .enumerate()
+        .map(|(ply, clock)| {
+            let spent = (previous[ply % 2] + time_control.increment()).saturating_sub(*clock);
+            previous[ply % 2] = *clock;
+
+            spent
+        })
+        .collect()
+}
+
+/// Returns how `player`'s games among the games of `game_iter` would have gone with each of
+/// `increments`, for each time control, with the most played time controls first and the
+/// smallest increments first within them.
+///
+/// Each game is replayed with the players taking the same time for each move as they did, and
+/// with the simulated increment added to their clocks instead. A player whose clock would have
+/// run out loses on time there, and a player who lost on time but would have had at least ten
+/// seconds more when they flagged is saved from it. Games without a known time control or
+/// without a clock time for every move are left out, as are games `player` didn't play in.
+pub fn simulate_increments<'a, M, G>(
+    game_iter: &mut dyn Iterator<Item = &'a G>,
+    player: &str,
+    increments: &[Duration],
+) -> Vec<IncrementSimulation>
+where
+    M: 'static + Clone + Move,
+    G: 'a + GiveClocks + GiveMetadata + GivePlayers + GiveResult + ListMoves<M>,
+{
+    let mut simulations: HashMap<(TimeControl, Duration), IncrementSimulation> = HashMap::new();
+
+    for game in game_iter {
+        let (color, time_control) = match (game.color_of(player), game.time_control()) {
+            (Some(color), Some(time_control)) => (color, time_control),
+            _ => continue,
+        };
+
+        let clocks = match game.clocks().iter().copied().collect::<Option<Vec<_>>>() {
+            Some(clocks) if !clocks.is_empty() && clocks.len() == game.list_moves().count() => {
+                clocks
+            }
+            _ => continue,
+        };
+
+        let result = game.result().for_color(color);
+        let time_loser = match game.result() {
+            GameResult::WhiteWon => Some(Color::Black),
+            GameResult::BlackWon => Some(Color::White),
+            GameResult::Draw => None,
+        }
+        .filter(|loser| lost_on_time(game, *loser));
+
+        let move_times = move_times(&clocks, time_control);
+
+        // The time each player had left after their last move.
+        let mut last_clocks = [time_control.base(); 2];
+
+        for (ply, clock) in clocks.iter().enumerate() {
+            last_clocks[ply % 2] = *clock;
+        }
+
+        for increment in increments {
+            let simulation = simulations
+                .entry((time_control, *increment))
+                .or_insert_with(|| IncrementSimulation::new(time_control, *increment));
+
+            simulation.games += 1;
+            simulation.score.add(result);
+
+            match time_loser {
+                Some(loser) if loser == color => simulation.lost_on_time += 1,
+                Some(_) => simulation.won_on_time += 1,
+                None => {}
+            }
+
+            let mut simulated_clocks = [time_control.base(); 2];
+            let mut flagged = None;
+
+            for (ply, spent) in move_times.iter().enumerate() {
+                match simulated_clocks[ply % 2].checked_sub(*spent) {
+                    Some(left) => simulated_clocks[ply % 2] = left + *increment,
+                    None => {
+                        flagged = Some(if ply % 2 == 0 {
+                            Color::White
+                        } else {
+                            Color::Black
+                        });
+                        break;
+                    }
+                }
+            }
+
+            // White's clock is first and black's second.
+            let index = |color: Color| (color == Color::Black) as usize;
+
+            let flagged = flagged.or_else(|| {
+                time_loser.filter(|loser| {
+                    simulated_clocks[index(*loser)] < last_clocks[index(*loser)] + SAVING_TIME
+                })
+            });
+
+            let simulated_result = match flagged {
+                Some(flagged) if flagged == color => {
+                    simulation.simulated_lost_on_time += 1;
+                    PlayerResult::Lost
+                }
+                Some(_) => {
+                    simulation.simulated_won_on_time += 1;
+                    PlayerResult::Won
+                }
+                None if time_loser.is_some() => {
+                    simulation.undecided += 1;
+                    continue;
+                }
+                None => result,
+            };
+
+            simulation.simulated_score.add(simulated_result);
+        }
+    }
+
+    let mut simulations = simulations.into_values().collect::<Vec<_>>();
+
+    simulations.sort_by_key(|simulation| {
+        (
+            Reverse(simulation.games),
+            simulation.time_control.base(),
+            simulation.time_control.increment(),
+            simulation.increment,
+        )
+    });
+
+    simulations
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rstest::*;
+
+    use super::simulate_increments;
+    use crate::game::test_utils::*;
+
+    #[rstest]
+    fn simulate_increments_should_flag_players_short_of_time() {
+        let games = [
+            // alice lost on time, but had been getting two seconds a move.
+            parse_game(
+                "[White \"alice\"]\n[Black \"bob\"]\n[Result \"0-1\"]\n[TimeControl \"60+2\"]\n\
+                 [Termination \"Time forfeit\"]\n\
+                 1. e4 { [%clk 0:00:40] } 1... e5 { [%clk 0:01:00] } \
+                 2. Nf3 { [%clk 0:00:20] } 2... Nc6 { [%clk 0:01:00] } \
+                 3. Bc4 { [%clk 0:00:01] } 3... Nf6 { [%clk 0:01:00] } 0-1",
+            ),
+            // alice won with little time to spare, which she wouldn't have had without increment.
+            parse_game(
+                "[White \"bob\"]\n[Black \"alice\"]\n[Result \"0-1\"]\n[TimeControl \"60+2\"]\n\
+                 1. e4 { [%clk 0:01:00] } 1... e5 { [%clk 0:00:30] } \
+                 2. Qh5 { [%clk 0:01:00] } 2... Nc6 { [%clk 0:00:03] } \
+                 3. Bc4 { [%clk 0:01:00] } 3... g6 { [%clk 0:00:01] } \
+                 4. Qf3 { [%clk 0:01:00] } 4... Nf6 { [%clk 0:00:02] } 0-1",
+            ),
+            // Without clock times.
+            parse_game(
+                "[White \"alice\"]\n[Black \"carol\"]\n[Result \"1-0\"]\n[TimeControl \"60+2\"]\n\
+                 1. e4 e5 1-0",
+            ),
+        ];
+
+        let increments = [Duration::from_secs(0), Duration::from_secs(10)];
+        let simulations = simulate_increments(&mut games.iter(), "alice", &increments);
+
+        assert_eq!(simulations.len(), 2);
+
+        let (without, with_more) = (&simulations[0], &simulations[1]);
+
+        assert_eq!(without.time_control().to_string(), "60+2");
+        assert_eq!(without.increment(), Duration::from_secs(0));
+        assert_eq!(without.games(), 2);
+        assert_eq!(without.score().wins(), 1);
+        assert_eq!(without.lost_on_time(), 1);
+        assert_eq!(without.simulated_lost_on_time(), 2);
+        assert_eq!(without.simulated_score().losses(), 2);
+        assert_eq!(without.undecided(), 0);
+
+        assert_eq!(with_more.simulated_lost_on_time(), 0);
+        assert_eq!(with_more.undecided(), 1);
+        assert_eq!(with_more.simulated_score().games(), 1);
+        assert_eq!(with_more.simulated_score().wins(), 1);
+    }
+}
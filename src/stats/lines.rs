@@ -0,0 +1,262 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::game::{Color, GivePlayers, GiveResult, ListMoves};
+use crate::moves::Move;
+use crate::stats::Score;
+
+/// How a player scored in the games that started with one line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineScore<M: Move> {
+    moves: Vec<M>,
+    color: Color,
+    score: Score,
+}
+
+impl<M: Move> LineScore<M> {
+    /// Returns the moves that make up the line.
+    pub fn moves(&self) -> &[M] {
+        &self.moves
+    }
+
+    /// Returns the color the player had in the line.
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /// Returns the player's score in games starting with the line.
+    pub fn score(&self) -> Score {
+        self.score
+    }
+}
+
+/// A move a player chose in a position they kept reaching, with how reliably they chose it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PredictableMove<M: Move> {
+    line: Vec<M>,
+    color: Color,
+    move_: M,
+    games: usize,
+    total: usize,
+}
+
+impl<M: Move> PredictableMove<M> {
+    /// Returns the moves leading to the position the player chose the move in.
+    pub fn line(&self) -> &[M] {
+        &self.line
+    }
+
+    /// Returns the color the player had.
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /// Returns the move the player chose most often.
+    pub fn move_(&self) -> &M {
+        &self.move_
+    }
+
+    /// Returns the number of games the player chose the move in.
+    pub fn games(&self) -> usize {
+        self.games
+    }
+
+    /// Returns the number of games the player had to make a choice in the position.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Returns the fraction of games in the position where the player chose the move.
+    pub fn rate(&self) -> f64 {
+        self.games as f64 / self.total as f64
+    }
+}
+
+/// Returns `moves` in SAN, for ordering lines the same way every time.
+fn san<M: Clone + Move>(moves: &[M]) -> Vec<String> {
+    moves
+        .iter()
+        .map(|move_| move_.clone().to_algebraic())
+        .collect()
+}
+
+/// Returns `player`'s score in each line made up of the first `line_length` moves of the games
+/// of `game_iter`, with the lines they scored worst in first. Only lines `player` played at least
+/// `min_games` times with the same color are given, and games shorter than the line length are
+/// left out. Games `player` didn't play in are ignored.
+pub fn worst_lines<'a, M, G>(
+    game_iter: &mut dyn Iterator<Item = &'a G>,
+    player: &str,
+    line_length: usize,
+    min_games: usize,
+) -> Vec<LineScore<M>>
+where
+    M: 'static + Clone + Move + Eq + Hash,
+    G: 'a + GivePlayers + GiveResult + ListMoves<M>,
+{
+    let mut scores: HashMap<(Color, Vec<M>), Score> = HashMap::new();
+
+    for game in game_iter {
+        let color = match game.color_of(player) {
+            Some(color) => color,
+            None => continue,
+        };
+
+        let moves = game.list_moves().take(line_length).collect::<Vec<_>>();
+
+        if moves.len() < line_length {
+            continue;
+        }
+
+        scores
+            .entry((color, moves))
+            .or_default()
+            .add(game.result().for_color(color));
+    }
+
+    let mut lines = scores
+        .into_iter()
+        .filter(|(_, score)| score.games() >= min_games)
+        .map(|((color, moves), score)| LineScore {
+            moves,
+            color,
+            score,
+        })
+        .collect::<Vec<_>>();
+
+    lines.sort_by(|a, b| {
+        a.score
+            .rate()
+            .partial_cmp(&b.score.rate())
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| b.score.games().cmp(&a.score.games()))
+            .then_with(|| san(&a.moves).cmp(&san(&b.moves)))
+    });
+
+    lines
+}
+
+/// Returns the move `player` chose most often in each position they reached in their first
+/// `depth` moves of the games of `game_iter`, with the moves they chose most reliably first, so
+/// that an opponent knows what to expect. Only positions `player` was to move in at least
+/// `min_games` times are looked at, and positions are told apart by the moves leading to them.
+/// Games `player` didn't play in are ignored.
+pub fn predictable_moves<'a, M, G>(
+    game_iter: &mut dyn Iterator<Item = &'a G>,
+    player: &str,
+    depth: usize,
+    min_games: usize,
+) -> Vec<PredictableMove<M>>
+where
+    M: 'static + Clone + Move + Eq + Hash,
+    G: 'a + GivePlayers + ListMoves<M>,
+{
+    let mut choices: HashMap<(Color, Vec<M>), HashMap<M, usize>> = HashMap::new();
+
+    for game in game_iter {
+        let color = match game.color_of(player) {
+            Some(color) => color,
+            None => continue,
+        };
+
+        let moves = game.list_moves().take(2 * depth).collect::<Vec<_>>();
+
+        // White's moves are at even indices and black's at odd ones.
+        for ply in ((color == Color::Black) as usize..moves.len()).step_by(2) {
+            *choices
+                .entry((color, moves[..ply].to_vec()))
+                .or_default()
+                .entry(moves[ply].clone())
+                .or_default() += 1;
+        }
+    }
+
+    let mut predictable = choices
+        .into_iter()
+        .filter_map(|((color, line), counts)| {
+            let total = counts.values().sum::<usize>();
+            let (move_, games) = counts.into_iter().max_by(|(move_a, a), (move_b, b)| {
+                a.cmp(b).then_with(|| {
+                    move_b
+                        .clone()
+                        .to_algebraic()
+                        .cmp(&move_a.clone().to_algebraic())
+                })
+            })?;
+
+            Some(PredictableMove {
+                line,
+                color,
+                move_,
+                games,
+                total,
+            })
+        })
+        .filter(|predictable| predictable.total >= min_games)
+        .collect::<Vec<_>>();
+
+    predictable.sort_by(|a, b| {
+        b.rate()
+            .partial_cmp(&a.rate())
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| b.total.cmp(&a.total))
+            .then_with(|| a.line.len().cmp(&b.line.len()))
+            .then_with(|| san(&a.line).cmp(&san(&b.line)))
+    });
+
+    predictable
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::{predictable_moves, worst_lines};
+    use crate::game::test_utils::*;
+    use crate::game::Color;
+    use crate::moves::Move;
+    use crate::AlgebraicMove;
+
+    fn san(moves: &[AlgebraicMove]) -> String {
+        moves
+            .iter()
+            .map(|move_| move_.clone().to_algebraic())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[rstest]
+    fn lines_should_find_weak_and_predictable_lines() {
+        let games = [
+            game("alice", "bob", "0-1", "1. e4 c5 2. Nf3 d6"),
+            game("alice", "carol", "1/2-1/2", "1. e4 c5 2. Nf3 e6"),
+            game("alice", "dave", "1-0", "1. e4 e5 2. Nf3 Nc6"),
+            game("alice", "bob", "1-0", "1. e4 e5 2. Nf3 Nf6"),
+            game("erin", "alice", "0-1", "1. d4 Nf6 2. c4 e6"),
+            game("frank", "alice", "0-1", "1. d4 Nf6 2. Nf3 e6"),
+            game("bob", "carol", "1-0", "1. e4 c5 2. Nf3 d6"),
+        ];
+
+        let lines = worst_lines(&mut games.iter(), "alice", 2, 2);
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(san(lines[0].moves()), "e4 c5");
+        assert_eq!(lines[0].color(), Color::White);
+        assert_eq!(lines[0].score().rate(), Some(0.25));
+        assert_eq!(san(lines[1].moves()), "d4 Nf6");
+        assert_eq!(lines[1].color(), Color::Black);
+
+        let predictable = predictable_moves(&mut games.iter(), "alice", 2, 2);
+
+        assert_eq!(predictable.len(), 4);
+        assert_eq!(predictable[0].move_().clone().to_algebraic(), "e4");
+        assert_eq!(predictable[0].total(), 4);
+        assert_eq!(predictable[0].rate(), 1.);
+        assert_eq!(san(predictable[1].line()), "d4");
+        assert_eq!(predictable[1].games(), 2);
+        assert!(predictable
+            .iter()
+            .all(|predictable| san(predictable.line()) != "d4 Nf6 c4"));
+    }
+}
@@ -0,0 +1,233 @@
+use crate::game::GameResult;
+use crate::game::GiveResult;
+use crate::game::PlayerResult;
+
+/// Statistics on how accurately players play, based on evaluations recorded in games.
+pub mod accuracy;
+/// Statistics on where players' blunders cluster.
+pub mod blunders;
+/// Statistics comparing two sets of games.
+pub mod compare;
+/// Statistics on how soon players develop their pieces, castle, and start capturing.
+pub mod development;
+/// Statistics on how often games are drawn.
+pub mod draw_rates;
+/// Statistics on which openings players lose quickly in.
+pub mod fast_losses;
+/// Where players' pieces go in the games of each opening.
+pub mod heatmaps;
+/// Simulations of how players' games would have gone with a different increment.
+pub mod increments;
+/// Statistics on how players score in and how predictably they choose their opening lines.
+pub mod lines;
+/// Statistics on how players do against each of their opponents.
+pub mod opponents;
+/// Statistics on how players do in different middlegame pawn structures.
+pub mod pawn_structures;
+/// Statistics on captures, promotions, and other things pieces do.
+pub mod pieces;
+/// Statistics on how players do with and without queens on the board.
+pub mod queen_trades;
+/// Statistics on how players' ratings change over time.
+pub mod rating_history;
+/// Statistics on which openings players choose.
+pub mod repertoire;
+/// Statistics on how often players sacrifice material and how their sacrifices work out.
+pub mod sacrifices;
+/// Statistics on how players do over sessions of games played one after another.
+pub mod sessions;
+/// Statistics on how double-edged opening lines are.
+pub mod sharpness;
+/// A common interface for statistics computed in a single pass over games.
+pub mod statistic;
+/// Statistics on how many games in a row players win, lose, or go unbeaten.
+pub mod streaks;
+/// Statistics on how long games stay undecided in each opening.
+pub mod survival;
+/// Statistics on how players do from lost and winning positions.
+pub mod swindles;
+/// Statistics on how players' games end, such as by checkmate or by resignation.
+pub mod terminations;
+/// Statistics on how often players lose or win on time from winning or losing positions.
+pub mod time_forfeits;
+/// Statistics on how players perform when short on time.
+pub mod time_trouble;
+/// Statistics on how players use their clocks.
+pub mod time_usage;
+/// The traps players spring or fall into in short decisive games.
+pub mod traps;
+/// Statistics on how players perform against stronger and weaker opponents.
+pub mod upsets;
+
+/// A tally of one player's results over a set of games.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Score {
+    wins: usize,
+    draws: usize,
+    losses: usize,
+}
+
+impl Score {
+    /// Creates a new `Score` with no games counted.
+    pub fn new() -> Score {
+        Score::default()
+    }
+
+    /// Counts `result` towards this score.
+    pub fn add(&mut self, result: PlayerResult) {
+        match result {
+            PlayerResult::Won => self.wins += 1,
+            PlayerResult::Drew => self.draws += 1,
+            PlayerResult::Lost => self.losses += 1,
+        }
+    }
+
+    /// Adds the games counted by `other` to this score.
+    pub fn merge(&mut self, other: Score) {
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.losses += other.losses;
+    }
+
+    /// Returns the number of games won.
+    pub fn wins(&self) -> usize {
+        self.wins
+    }
+
+    /// Returns the number of games drawn.
+    pub fn draws(&self) -> usize {
+        self.draws
+    }
+
+    /// Returns the number of games lost.
+    pub fn losses(&self) -> usize {
+        self.losses
+    }
+
+    /// Returns the number of games counted.
+    pub fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Returns the number of points scored, counting a win as one point and a draw as half.
+    pub fn points(&self) -> f64 {
+        self.wins as f64 + self.draws as f64 / 2.
+    }
+
+    /// Returns the fraction of available points that were scored, or `None` if no games were
+    /// counted.
+    pub fn rate(&self) -> Option<f64> {
+        if self.games() > 0 {
+            Some(self.points() / self.games() as f64)
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns the percentage of white wins, black wins, and draws in `game_iter`.
+pub fn results<G: GiveResult>(game_iter: &mut dyn Iterator<Item = &G>) -> (f64, f64, f64) {
+    let mut white_wins = 0.;
+    let mut black_wins = 0.;
+    let mut draws = 0.;
+
+    for game in &mut *game_iter {
+        match game.result() {
+            GameResult::WhiteWon => {
+                white_wins += 1.;
+            }
+            GameResult::BlackWon => {
+                black_wins += 1.;
+            }
+            GameResult::Draw => {
+                draws += 1.;
+            }
+        };
+    }
+
+    let total_games: f64 = white_wins + black_wins + draws;
+
+    (
+        white_wins / total_games,
+        black_wins / total_games,
+        draws / total_games,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use float_cmp::approx_eq;
+
+    use crate::game::test_utils::results::*;
+    use crate::game::{GameResult, PlayerResult};
+
+    use super::{results, Score};
+
+    fn close(a: f64, b: f64) -> bool {
+        approx_eq!(f64, a, b, epsilon = 0.00000001)
+    }
+
+    fn more_white_wins() -> Vec<GameResult> {
+        vec![white_won(), white_won(), black_won(), draw(), white_won()]
+    }
+
+    fn more_black_wins() -> Vec<GameResult> {
+        vec![
+            draw(),
+            white_won(),
+            black_won(),
+            black_won(),
+            draw(),
+            white_won(),
+            black_won(),
+        ]
+    }
+
+    fn more_draws() -> Vec<GameResult> {
+        vec![
+            black_won(),
+            draw(),
+            draw(),
+            draw(),
+            draw(),
+            white_won(),
+            black_won(),
+        ]
+    }
+
+    #[rstest(game_list, expected_results,
+        case(more_white_wins(), (0.6, 0.2, 0.2)),
+        case(more_black_wins(), (2. / 7., 3. / 7., 2. / 7.)),
+        case(more_draws(), (1. / 7., 2. / 7., 4. / 7.)),
+    )]
+    fn results_should_give_correct_rates(
+        game_list: Vec<GameResult>,
+        expected_results: (f64, f64, f64),
+    ) {
+        let (white_win_rate, black_win_rate, draw_rate) = results(&mut game_list.iter());
+
+        assert!(close(white_win_rate, expected_results.0));
+        assert!(close(black_win_rate, expected_results.1));
+        assert!(close(draw_rate, expected_results.2));
+    }
+
+    #[rstest(results, expected_rate,
+        case(vec![PlayerResult::Won, PlayerResult::Drew], Some(0.75)),
+        case(vec![PlayerResult::Lost, PlayerResult::Lost, PlayerResult::Drew], Some(1. / 6.)),
+        case(vec![], None),
+    )]
+    fn score_should_give_correct_rate(results: Vec<PlayerResult>, expected_rate: Option<f64>) {
+        let mut score = Score::new();
+
+        for result in results {
+            score.add(result);
+        }
+
+        match (score.rate(), expected_rate) {
+            (Some(rate), Some(expected_rate)) => assert!(close(rate, expected_rate)),
+            (rate, expected_rate) => assert_eq!(rate, expected_rate),
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+
+use crate::game::{Color, GiveDate, GivePlayers, GiveResult};
+use crate::player::name_key;
+use crate::stats::Score;
+
+/// A player's record against one opponent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpponentRecord {
+    opponent: String,
+    score: Score,
+    games_as_white: usize,
+    last_played: Option<NaiveDate>,
+}
+
+impl OpponentRecord {
+    /// Returns the name of the opponent.
+    pub fn opponent(&self) -> &str {
+        &self.opponent
+    }
+
+    /// Returns the player's score against the opponent.
+    pub fn score(&self) -> Score {
+        self.score
+    }
+
+    /// Returns the number of games against the opponent.
+    pub fn games(&self) -> usize {
+        self.score.games()
+    }
+
+    /// Returns the number of games the player had white against the opponent.
+    pub fn games_as_white(&self) -> usize {
+        self.games_as_white
+    }
+
+    /// Returns the number of games the player had black against the opponent.
+    pub fn games_as_black(&self) -> usize {
+        self.score.games() - self.games_as_white
+    }
+
+    /// Returns the most recent date the player played the opponent, if any of their games were
+    /// dated.
+    pub fn last_played(&self) -> Option<NaiveDate> {
+        self.last_played
+    }
+}
+
+/// Returns `player`'s record against each of their opponents in the games of `game_iter`, with
+/// the most frequent opponents first, keeping at most `limit` opponents. Games `player` didn't
+/// play in, or played against someone whose name isn't known, are ignored. Names are matched ignoring case, and each opponent is named as in the
+/// first game they were found in.
+pub fn opponents<'a, G>(
+    game_iter: &mut dyn Iterator<Item = &'a G>,
+    player: &str,
+    limit: usize,
+) -> Vec<OpponentRecord>
+where
+    G: 'a + GiveDate + GivePlayers + GiveResult,
+{
+    let mut records: HashMap<String, OpponentRecord> = HashMap::new();
+
+    for game in game_iter {
+        let color = match game.color_of(player) {
+            Some(color) => color,
+            None => continue,
+        };
+
+        let opponent = match game.player_of(!color) {
+            Some(opponent) => opponent,
+            None => continue,
+        };
+        let record = records
+            .entry(name_key(opponent))
+            .or_insert_with(|| OpponentRecord {
+                opponent: String::from(opponent),
+                score: Score::new(),
+                games_as_white: 0,
+                last_played: None,
+            });
+
+        record.score.add(game.result().for_color(color));
+
+        if color == Color::White {
+            record.games_as_white += 1;
+        }
+
+        record.last_played = record.last_played.max(game.date());
+    }
+
+    let mut records = records.into_values().collect::<Vec<_>>();
+
+    records
+        .sort_by(|a, b| (Reverse(a.games()), &a.opponent).cmp(&(Reverse(b.games()), &b.opponent)));
+    records.truncate(limit);
+
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use chrono::NaiveDate;
+
+    use super::opponents;
+    use crate::game::test_utils::*;
+    use crate::game::Game;
+    use crate::AlgebraicMove;
+
+    #[fixture]
+    fn games() -> Vec<Game<AlgebraicMove>> {
+        vec![
+            dated_game("alice", "bob", "1-0", "2021.03.01", "1. e4 e5"),
+            dated_game("bob", "alice", "1/2-1/2", "2021.05.01", "1. e4 e5"),
+            dated_game("alice", "bob", "0-1", "2021.04.01", "1. e4 e5"),
+            dated_game("carol", "alice", "0-1", "????.??.??", "1. e4 e5"),
+            dated_game("alice", "dave", "1-0", "2021.01.01", "1. e4 e5"),
+            dated_game("bob", "carol", "1-0", "2021.06.01", "1. e4 e5"),
+            dated_game("Alice", "Dave", "1-0", "2021.02.01", "1. e4 e5"),
+        ]
+    }
+
+    #[rstest]
+    fn opponents_should_summarize_each_opponent(games: Vec<Game<AlgebraicMove>>) {
+        let records = opponents(&mut games.iter(), "alice", 10);
+
+        let names = records
+            .iter()
+            .map(|record| record.opponent())
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, vec!["bob", "dave", "carol"]);
+
+        let bob = &records[0];
+
+        assert_eq!(bob.score().wins(), 1);
+        assert_eq!(bob.score().draws(), 1);
+        assert_eq!(bob.score().losses(), 1);
+        assert_eq!(bob.games_as_white(), 2);
+        assert_eq!(bob.games_as_black(), 1);
+        assert_eq!(bob.last_played(), NaiveDate::from_ymd_opt(2021, 5, 1));
+        assert_eq!(records[1].games(), 2);
+        assert_eq!(records[2].last_played(), None);
+    }
+
+    #[rstest]
+    fn opponents_should_keep_most_frequent(games: Vec<Game<AlgebraicMove>>) {
+        let records = opponents(&mut games.iter(), "alice", 1);
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].opponent(), "bob");
+    }
+}
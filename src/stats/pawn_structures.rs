@@ -0,0 +1,113 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use crate::game::{GivePlayers, GiveResult, ListMoves};
+use crate::moves::Move;
+use crate::stats::Score;
+use crate::structure::{self, PawnStructure};
+
+/// A player's score in games with a particular pawn structure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructureScore {
+    structure: PawnStructure,
+    own: bool,
+    score: Score,
+}
+
+impl StructureScore {
+    /// Returns the pawn structure.
+    pub fn structure(&self) -> PawnStructure {
+        self.structure
+    }
+
+    /// Returns whether the structure was the player's own, rather than their opponent's.
+    pub fn own(&self) -> bool {
+        self.own
+    }
+
+    /// Returns the player's score in games with the structure.
+    pub fn score(&self) -> Score {
+        self.score
+    }
+}
+
+/// Returns how `player` scored in the games of `game_iter` by the pawn structure of each game's
+/// middlegame, separating structures the player had from structures their opponents had, with the
+/// most common first. Games `player` didn't play in and games without a known structure are
+/// ignored.
+pub fn pawn_structures<'a, M, G>(
+    game_iter: &mut dyn Iterator<Item = &'a G>,
+    player: &str,
+) -> Vec<StructureScore>
+where
+    M: 'static + Clone + Move,
+    G: 'a + GivePlayers + GiveResult + ListMoves<M>,
+{
+    let mut scores: HashMap<(PawnStructure, bool), Score> = HashMap::new();
+
+    for game in game_iter {
+        let color = match game.color_of(player) {
+            Some(color) => color,
+            None => continue,
+        };
+
+        if let Some((structure, owner)) = structure::structure(game.list_moves()) {
+            scores
+                .entry((structure, owner == color))
+                .or_default()
+                .add(game.result().for_color(color));
+        }
+    }
+
+    let mut scores = scores
+        .into_iter()
+        .map(|((structure, own), score)| StructureScore {
+            structure,
+            own,
+            score,
+        })
+        .collect::<Vec<_>>();
+
+    scores.sort_by_key(|score| {
+        (
+            Reverse(score.score.games()),
+            score.structure,
+            Reverse(score.own),
+        )
+    });
+
+    scores
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::pawn_structures;
+    use crate::game::test_utils::*;
+    use crate::structure::PawnStructure;
+
+    const CARLSBAD: &str = "1. d4 d5 2. c4 e6 3. Nc3 Nf6 4. cxd5 exd5 5. Bg5 c6 6. e3 Be7 \
+                            7. Bd3 O-O 8. Qc2 Nbd7 9. Nge2 Re8 10. O-O Nf8";
+
+    #[rstest]
+    fn pawn_structures_should_score_each_side_of_a_structure() {
+        let games = [
+            game("alice", "bob", "1-0", CARLSBAD),
+            game("carol", "alice", "1/2-1/2", CARLSBAD),
+            game("alice", "carol", "0-1", CARLSBAD),
+            game("alice", "bob", "1-0", "1. e4 e5"),
+            game("bob", "carol", "1-0", CARLSBAD),
+        ];
+
+        let scores = pawn_structures(&mut games.iter(), "alice");
+
+        assert_eq!(scores.len(), 2);
+        assert_eq!(scores[0].structure(), PawnStructure::Carlsbad);
+        assert!(scores[0].own());
+        assert_eq!(scores[0].score().wins(), 1);
+        assert_eq!(scores[0].score().losses(), 1);
+        assert!(!scores[1].own());
+        assert_eq!(scores[1].score().draws(), 1);
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+
+use shakmaty::{CastlingSide, Role};
+
+use crate::game::{Color, GivePlayers, ListMoves};
+use crate::moves::Move;
+use crate::position;
+
+/// Counts of what pieces did across a set of games.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PieceStats {
+    games: usize,
+    captures_by: HashMap<Role, usize>,
+    captured: HashMap<Role, usize>,
+    early_exchanges: usize,
+    promotions: HashMap<Role, usize>,
+    en_passant_captures: usize,
+    castles: HashMap<CastlingSide, usize>,
+}
+
+impl PieceStats {
+    /// Returns the number of games counted.
+    pub fn games(&self) -> usize {
+        self.games
+    }
+
+    /// Returns the number of captures made by pieces of type `role`.
+    pub fn captures_by(&self, role: Role) -> usize {
+        self.captures_by.get(&role).copied().unwrap_or(0)
+    }
+
+    /// Returns the number of pieces of type `role` that were captured.
+    pub fn captured(&self, role: Role) -> usize {
+        self.captured.get(&role).copied().unwrap_or(0)
+    }
+
+    /// Returns the average number of exchanges per game before move 20, where an exchange is a
+    /// capture immediately answered by a recapture on the same square.
+    pub fn average_exchanges_before_move_20(&self) -> Option<f64> {
+        if self.games > 0 {
+            Some(self.early_exchanges as f64 / self.games as f64)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the number of pawns promoted to pieces of type `role`.
+    pub fn promotions(&self, role: Role) -> usize {
+        self.promotions.get(&role).copied().unwrap_or(0)
+    }
+
+    /// Returns the fraction of promotions that were to something other than a queen, or `None`
+    /// if there were no promotions.
+    pub fn underpromotion_rate(&self) -> Option<f64> {
+        let total: usize = self.promotions.values().sum();
+
+        if total > 0 {
+            Some((total - self.promotions(Role::Queen)) as f64 / total as f64)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the number of en passant captures.
+    pub fn en_passant_captures(&self) -> usize {
+        self.en_passant_captures
+    }
+
+    /// Returns the number of times castling happened on `side`.
+    pub fn castles(&self, side: CastlingSide) -> usize {
+        self.castles.get(&side).copied().unwrap_or(0)
+    }
+}
+
+/// Returns counts of captures, promotions, and other piece-level events in the games of
+/// `game_iter`.
+///
+/// When `player` is given, only their games are counted, and captures, promotions, and castling
+/// only count when made by them. Exchanges always count both sides. Moves after an illegal move
+/// in a game are ignored, since the pieces involved can't be known.
+pub fn piece_stats<'a, M, G>(
+    game_iter: &mut dyn Iterator<Item = &'a G>,
+    player: Option<&str>,
+) -> PieceStats
+where
+    M: 'static + Clone + Move,
+    G: 'a + GivePlayers + ListMoves<M>,
+{
+    let mut stats = PieceStats::default();
+
+    for game in game_iter {
+        let color = match player {
+            Some(player) => match game.color_of(player) {
+                Some(color) => Some(color),
+                None => continue,
+            },
+            None => None,
+        };
+
+        let legal_moves = position::legal_moves(game.list_moves());
+
+        stats.games += 1;
+
+        for (ply, legal_move) in legal_moves.iter().enumerate() {
+            let mover = if ply % 2 == 0 {
+                Color::White
+            } else {
+                Color::Black
+            };
+
+            if color.is_some_and(|color| color != mover) {
+                continue;
+            }
+
+            if let Some(captured) = legal_move.capture() {
+                *stats.captures_by.entry(legal_move.role()).or_default() += 1;
+                *stats.captured.entry(captured).or_default() += 1;
+            }
+
+            if let Some(promotion) = legal_move.promotion() {
+                *stats.promotions.entry(promotion).or_default() += 1;
+            }
+
+            if legal_move.is_en_passant() {
+                stats.en_passant_captures += 1;
+            }
+
+            if let Some(side) = legal_move.castling_side() {
+                *stats.castles.entry(side).or_default() += 1;
+            }
+        }
+
+        // Move 20 starts at ply 38.
+        let mut ply = 0;
+
+        while ply + 1 < legal_moves.len().min(38) {
+            let (capture, recapture) = (&legal_moves[ply], &legal_moves[ply + 1]);
+
+            if capture.is_capture() && recapture.is_capture() && capture.to() == recapture.to() {
+                stats.early_exchanges += 1;
+                ply += 2;
+            } else {
+                ply += 1;
+            }
+        }
+    }
+
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use shakmaty::{CastlingSide, Role};
+
+    use super::piece_stats;
+    use crate::game::test_utils::*;
+    use crate::game::Game;
+    use crate::AlgebraicMove;
+
+    #[fixture]
+    fn games() -> Vec<Game<AlgebraicMove>> {
+        vec![
+            // Two exchanges, the second followed by a lone recapture.
+            game(
+                "alice",
+                "bob",
+                "1-0",
+                "1. e4 d5 2. exd5 Qxd5 3. Nc3 Qa5 4. d4 Nf6 5. Nf3 Bf5 6. Bc4 e6 7. O-O c6 \
+                 8. d5 Nxd5 9. Nxd5 cxd5",
+            ),
+            // One exchange, started by en passant.
+            game(
+                "bob",
+                "alice",
+                "1-0",
+                "1. e4 e6 2. e5 d5 3. exd6 Bxd6 4. d4 Nf6 5. Nf3 O-O",
+            ),
+            // Promotes to a knight.
+            game(
+                "carol",
+                "dave",
+                "1-0",
+                "1. h4 g5 2. hxg5 h6 3. gxh6 Bg7 4. hxg7 Nf6 5. gxh8=N",
+            ),
+        ]
+    }
+
+    #[rstest]
+    fn piece_stats_should_count_every_move_without_player(games: Vec<Game<AlgebraicMove>>) {
+        let stats = piece_stats(&mut games.iter(), None);
+
+        assert_eq!(stats.games(), 3);
+        assert_eq!(stats.captures_by(Role::Pawn), 7);
+        assert_eq!(stats.captures_by(Role::Queen), 1);
+        assert_eq!(stats.captures_by(Role::Knight), 2);
+        assert_eq!(stats.captures_by(Role::Bishop), 1);
+        assert_eq!(stats.captured(Role::Bishop), 1);
+        assert_eq!(stats.captured(Role::Rook), 1);
+        assert_eq!(stats.promotions(Role::Knight), 1);
+        assert_eq!(stats.underpromotion_rate(), Some(1.));
+        assert_eq!(stats.en_passant_captures(), 1);
+        assert_eq!(stats.castles(CastlingSide::KingSide), 2);
+        assert_eq!(stats.average_exchanges_before_move_20(), Some(1.));
+    }
+
+    #[rstest]
+    fn piece_stats_should_only_count_player_moves(games: Vec<Game<AlgebraicMove>>) {
+        let stats = piece_stats(&mut games.iter(), Some("alice"));
+
+        assert_eq!(stats.games(), 2);
+        assert_eq!(stats.captures_by(Role::Pawn), 1);
+        assert_eq!(stats.captures_by(Role::Knight), 1);
+        assert_eq!(stats.captures_by(Role::Bishop), 1);
+        assert_eq!(stats.en_passant_captures(), 0);
+        assert_eq!(stats.castles(CastlingSide::KingSide), 2);
+        assert_eq!(stats.underpromotion_rate(), None);
+    }
+}
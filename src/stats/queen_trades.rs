@@ -0,0 +1,199 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use shakmaty::Position;
+
+use crate::game::{GivePlayers, GiveResult, ListMoves};
+use crate::moves::Move;
+use crate::position;
+use crate::stats::Score;
+
+/// A player's score in games where queens were traded and games where they stayed on, among
+/// games starting with a particular line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineQueenTrades<M: Move> {
+    moves: Vec<M>,
+    queens_on: Score,
+    queens_off: Score,
+}
+
+impl<M: Move> LineQueenTrades<M> {
+    /// Returns the moves that make up this line.
+    pub fn moves(&self) -> &[M] {
+        &self.moves
+    }
+
+    /// Returns the player's score in games in this line where the queens stayed on.
+    pub fn queens_on(&self) -> Score {
+        self.queens_on
+    }
+
+    /// Returns the player's score in games in this line where the queens came off.
+    pub fn queens_off(&self) -> Score {
+        self.queens_off
+    }
+}
+
+/// How a player scores depending on whether the queens come off the board.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueenTrades<M: Move> {
+    queens_on: Score,
+    queens_off: Score,
+    trade_moves: Vec<usize>,
+    lines: Vec<LineQueenTrades<M>>,
+}
+
+impl<M: Move> QueenTrades<M> {
+    /// Returns the player's score in games where the queens stayed on.
+    pub fn queens_on(&self) -> Score {
+        self.queens_on
+    }
+
+    /// Returns the player's score in games where the queens came off.
+    pub fn queens_off(&self) -> Score {
+        self.queens_off
+    }
+
+    /// Returns the move number at which the queens came off in each game where they did, in
+    /// ascending order.
+    pub fn trade_moves(&self) -> &[usize] {
+        &self.trade_moves
+    }
+
+    /// Returns the median move number at which the queens came off.
+    pub fn typical_trade_move(&self) -> Option<usize> {
+        self.trade_moves.get(self.trade_moves.len() / 2).copied()
+    }
+
+    /// Returns the player's scores with and without queens for each opening line, with the most
+    /// frequently played lines first.
+    pub fn lines(&self) -> &[LineQueenTrades<M>] {
+        &self.lines
+    }
+}
+
+/// Returns the number of moves played before no queens were left on the board, or `None` if a
+/// queen was on the board throughout.
+fn queens_off_ply<M: Move, I: IntoIterator<Item = M>>(moves: I) -> Option<usize> {
+    position::replay(moves)
+        .iter()
+        .position(|position| position.board().queens().is_empty())
+}
+
+/// Returns how `player` scored in the games of `game_iter` depending on whether the queens came
+/// off the board, where queens are considered off once neither side has one. Games `player`
+/// didn't play in are ignored.
+///
+/// Opening lines are identified by the first `line_length` moves of each game.
+pub fn queen_trades<'a, M, G>(
+    game_iter: &mut dyn Iterator<Item = &'a G>,
+    player: &str,
+    line_length: usize,
+) -> QueenTrades<M>
+where
+    M: 'static + Clone + Move + Eq + Hash,
+    G: 'a + GivePlayers + GiveResult + ListMoves<M>,
+{
+    let mut queens_on = Score::new();
+    let mut queens_off = Score::new();
+    let mut trade_moves = Vec::new();
+    let mut lines: HashMap<Vec<M>, (Score, Score)> = HashMap::new();
+
+    for game in game_iter {
+        let color = match game.color_of(player) {
+            Some(color) => color,
+            None => continue,
+        };
+
+        let result = game.result().for_color(color);
+        let line = game.list_moves().take(line_length).collect::<Vec<_>>();
+        let (line_queens_on, line_queens_off) = lines.entry(line).or_default();
+
+        match queens_off_ply(game.list_moves()) {
+            Some(ply) => {
+                queens_off.add(result);
+                line_queens_off.add(result);
+                // The queens came off with the move at index `ply - 1`.
+                trade_moves.push((ply - 1) / 2 + 1);
+            }
+            None => {
+                queens_on.add(result);
+                line_queens_on.add(result);
+            }
+        }
+    }
+
+    trade_moves.sort_unstable();
+
+    let mut lines = lines
+        .into_iter()
+        .map(|(moves, (queens_on, queens_off))| LineQueenTrades {
+            moves,
+            queens_on,
+            queens_off,
+        })
+        .collect::<Vec<_>>();
+
+    lines.sort_by_key(|line| Reverse(line.queens_on.games() + line.queens_off.games()));
+
+    QueenTrades {
+        queens_on,
+        queens_off,
+        trade_moves,
+        lines,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::queen_trades;
+    use crate::game::test_utils::*;
+    use crate::game::Game;
+    use crate::AlgebraicMove;
+
+    #[fixture]
+    fn games() -> Vec<Game<AlgebraicMove>> {
+        vec![
+            // Queens come off on move 5.
+            game(
+                "alice",
+                "bob",
+                "1/2-1/2",
+                "1. d4 d5 2. c4 dxc4 3. e4 e5 4. dxe5 Qxd1+ 5. Kxd1",
+            ),
+            game("bob", "alice", "0-1", "1. d4 d5 2. c4 e6"),
+            // The queens stay on until mate.
+            game(
+                "alice",
+                "carol",
+                "1-0",
+                "1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7#",
+            ),
+        ]
+    }
+
+    #[rstest]
+    fn queen_trades_should_split_scores_by_queens(games: Vec<Game<AlgebraicMove>>) {
+        let trades = queen_trades(&mut games.iter(), "alice", 1);
+
+        assert_eq!(trades.queens_off().draws(), 1);
+        assert_eq!(trades.queens_off().games(), 1);
+        assert_eq!(trades.queens_on().wins(), 2);
+        assert_eq!(trades.trade_moves(), &[5]);
+        assert_eq!(trades.typical_trade_move(), Some(5));
+    }
+
+    #[rstest]
+    fn queen_trades_should_break_down_by_line(games: Vec<Game<AlgebraicMove>>) {
+        let trades = queen_trades(&mut games.iter(), "alice", 1);
+
+        let d4 = &trades.lines()[0];
+
+        assert_eq!(d4.queens_off().games(), 1);
+        assert_eq!(d4.queens_on().games(), 1);
+        assert_eq!(trades.lines()[1].queens_off().games(), 0);
+    }
+}
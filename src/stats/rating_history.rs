@@ -0,0 +1,181 @@
+use std::collections::BTreeMap;
+
+use chrono::{NaiveDate, NaiveTime};
+
+use crate::clock::Speed;
+use crate::game::{GiveClocks, GiveDate, GiveLink, GivePlayers, GiveRatings, ListMoves};
+use crate::game_id::GameId;
+use crate::moves::Move;
+
+/// A player's rating at the start of one of their games.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RatingPoint {
+    date: NaiveDate,
+    time: Option<NaiveTime>,
+    rating: u16,
+}
+
+impl RatingPoint {
+    /// Returns the date of the game the rating was taken from.
+    pub fn date(&self) -> NaiveDate {
+        self.date
+    }
+
+    /// Returns the start time of the game the rating was taken from, if known.
+    pub fn time(&self) -> Option<NaiveTime> {
+        self.time
+    }
+
+    /// Returns the player's rating going into the game.
+    pub fn rating(&self) -> u16 {
+        self.rating
+    }
+}
+
+/// Returns the rating history of `player` for each speed of game found in `game_iter`, using the
+/// WhiteElo and BlackElo tags of their games. Games without a known time control are grouped
+/// under `None`.
+///
+/// Each history is in chronological order. Games played at the same time, or on the same day at
+/// an unknown time, are ordered by their [`GameId`], so the order doesn't depend on the order
+/// games were read in. Games `player` didn't play in, and games without a date or rating for
+/// `player`, are ignored.
+pub fn rating_history<'a, M, G>(
+    game_iter: &mut dyn Iterator<Item = &'a G>,
+    player: &str,
+) -> BTreeMap<Option<Speed>, Vec<RatingPoint>>
+where
+    M: Clone + Move,
+    G: 'a + GiveClocks + GiveDate + GiveLink + GivePlayers + GiveRatings + ListMoves<M>,
+{
+    let mut histories: BTreeMap<Option<Speed>, Vec<(RatingPoint, GameId)>> = BTreeMap::new();
+
+    for game in game_iter {
+        let rating = game
+            .color_of(player)
+            .and_then(|color| game.rating_of(color));
+
+        if let (Some(rating), Some(date)) = (rating, game.date()) {
+            let speed = game.time_control().map(|time_control| time_control.speed());
+
+            let point = RatingPoint {
+                date,
+                time: game.time(),
+                rating,
+            };
+
+            histories
+                .entry(speed)
+                .or_default()
+                .push((point, GameId::of(game)));
+        }
+    }
+
+    histories
+        .into_iter()
+        .map(|(speed, mut history)| {
+            history.sort_by(|(a, a_id), (b, b_id)| {
+                (a.date, a.time, a_id).cmp(&(b.date, b.time, b_id))
+            });
+
+            (speed, history.into_iter().map(|(point, _)| point).collect())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use chrono::NaiveDate;
+
+    use super::rating_history;
+    use crate::clock::Speed;
+    use crate::game::test_utils::*;
+    use crate::game::Game;
+    use crate::AlgebraicMove;
+
+    fn rated_game(
+        white: &str,
+        black: &str,
+        date: &str,
+        time_control: &str,
+        elos: (u16, u16),
+    ) -> String {
+        format!(
+            "[White \"{}\"]\n[Black \"{}\"]\n[Result \"1-0\"]\n[UTCDate \"{}\"]\n\
+             [TimeControl \"{}\"]\n[WhiteElo \"{}\"]\n[BlackElo \"{}\"]\n1. e4 e5 1-0",
+            white, black, date, time_control, elos.0, elos.1
+        )
+    }
+
+    #[rstest]
+    fn rating_history_should_group_by_speed_in_date_order() {
+        let games = [
+            parse_game(&rated_game("alice", "bob", "2021.03.01", "180+0", (1510, 1400))),
+            parse_game(&rated_game("bob", "alice", "2021.01.01", "180+2", (1390, 1500))),
+            parse_game(&rated_game("alice", "carol", "2021.02.01", "600+0", (1700, 1650))),
+            parse_game(&rated_game("bob", "carol", "2021.02.01", "60+0", (1200, 1250))),
+            parse_game(
+                "[White \"alice\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n[WhiteElo \"1600\"]\n1. e4 1-0",
+            ),
+        ];
+
+        let histories = rating_history(&mut games.iter(), "alice");
+
+        let blitz = histories[&Some(Speed::Blitz)]
+            .iter()
+            .map(|point| (point.date(), point.rating()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(histories.len(), 2);
+        assert_eq!(
+            blitz,
+            vec![
+                (NaiveDate::from_ymd_opt(2021, 1, 1).unwrap(), 1500),
+                (NaiveDate::from_ymd_opt(2021, 3, 1).unwrap(), 1510),
+            ]
+        );
+        assert_eq!(histories[&Some(Speed::Rapid)][0].rating(), 1700);
+    }
+
+    #[rstest]
+    fn rating_history_should_not_depend_on_the_order_of_same_day_games() {
+        let games = [
+            parse_game(&rated_game(
+                "alice",
+                "bob",
+                "2021.03.01",
+                "180+0",
+                (1510, 1400),
+            )),
+            parse_game(&rated_game(
+                "alice",
+                "carol",
+                "2021.03.01",
+                "180+0",
+                (1520, 1400),
+            )),
+            parse_game(&rated_game(
+                "dave",
+                "alice",
+                "2021.03.01",
+                "180+0",
+                (1400, 1530),
+            )),
+        ];
+
+        let ratings = |games: &[&Game<AlgebraicMove>]| {
+            rating_history(&mut games.iter().copied(), "alice")[&Some(Speed::Blitz)]
+                .iter()
+                .map(|point| point.rating())
+                .collect::<Vec<_>>()
+        };
+
+        let forwards = ratings(&games.iter().collect::<Vec<_>>());
+        let backwards = ratings(&games.iter().rev().collect::<Vec<_>>());
+
+        assert_eq!(forwards.len(), 3);
+        assert_eq!(forwards, backwards);
+    }
+}
@@ -0,0 +1,356 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::game::{Color, GivePlayers, ListMoves};
+use crate::moves::Move;
+
+/// How often a move was chosen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveFrequency<M: Move> {
+    move_: M,
+    games: usize,
+    total: usize,
+}
+
+impl<M: Move> MoveFrequency<M> {
+    /// Returns the move that was chosen.
+    pub fn move_(&self) -> &M {
+        &self.move_
+    }
+
+    /// Returns the number of games the move was chosen in.
+    pub fn games(&self) -> usize {
+        self.games
+    }
+
+    /// Returns the fraction of games where the move was chosen, out of all games where a choice
+    /// was made at the same point.
+    pub fn rate(&self) -> f64 {
+        self.games as f64 / self.total as f64
+    }
+}
+
+/// The replies a player chose as black to one of white's first moves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replies<M: Move> {
+    first_move: M,
+    replies: Vec<MoveFrequency<M>>,
+}
+
+impl<M: Move> Replies<M> {
+    /// Returns white's first move.
+    pub fn first_move(&self) -> &M {
+        &self.first_move
+    }
+
+    /// Returns the player's replies, most frequent first.
+    pub fn replies(&self) -> &[MoveFrequency<M>] {
+        &self.replies
+    }
+
+    /// Returns the number of games where white started with this move.
+    pub fn games(&self) -> usize {
+        self.replies.first().map_or(0, |reply| reply.total)
+    }
+}
+
+/// How varied a player's choices are at one of their moves.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecisionPoint {
+    move_number: usize,
+    games: usize,
+    entropy: f64,
+}
+
+impl DecisionPoint {
+    /// Returns the move number of the decision.
+    pub fn move_number(&self) -> usize {
+        self.move_number
+    }
+
+    /// Returns the number of games that reached this decision.
+    pub fn games(&self) -> usize {
+        self.games
+    }
+
+    /// Returns the entropy in bits of the player's choice, given the moves played before it. Zero
+    /// means the player always plays the same move in the same line.
+    pub fn entropy(&self) -> f64 {
+        self.entropy
+    }
+
+    /// Returns the number of equally likely choices that would be as unpredictable as the
+    /// player's actual choices.
+    pub fn effective_choices(&self) -> f64 {
+        self.entropy.exp2()
+    }
+}
+
+/// A summary of the openings a player actually plays.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Repertoire<M: Move> {
+    first_moves: Vec<MoveFrequency<M>>,
+    replies: Vec<Replies<M>>,
+    white_decisions: Vec<DecisionPoint>,
+    black_decisions: Vec<DecisionPoint>,
+}
+
+impl<M: Move> Repertoire<M> {
+    /// Returns the player's first moves as white, most frequent first.
+    pub fn first_moves(&self) -> &[MoveFrequency<M>] {
+        &self.first_moves
+    }
+
+    /// Returns the player's replies as black to each of white's first moves, with the most
+    /// frequently faced first moves first.
+    pub fn replies(&self) -> &[Replies<M>] {
+        &self.replies
+    }
+
+    /// Returns the player's replies as black to `first_move`, if they ever faced it.
+    pub fn replies_to(&self, first_move: &M) -> Option<&Replies<M>>
+    where
+        M: PartialEq,
+    {
+        self.replies
+            .iter()
+            .find(|replies| &replies.first_move == first_move)
+    }
+
+    /// Returns how varied the player's choices are at each of their first moves as `color`.
+    pub fn decisions(&self, color: Color) -> &[DecisionPoint] {
+        match color {
+            Color::White => &self.white_decisions,
+            Color::Black => &self.black_decisions,
+        }
+    }
+}
+
+fn frequencies<M: Clone + Move>(counts: HashMap<M, usize>) -> Vec<MoveFrequency<M>> {
+    let total = counts.values().sum();
+
+    let mut frequencies = counts
+        .into_iter()
+        .map(|(move_, games)| MoveFrequency {
+            move_,
+            games,
+            total,
+        })
+        .collect::<Vec<_>>();
+
+    frequencies.sort_by_cached_key(|frequency| {
+        (
+            Reverse(frequency.games),
+            frequency.move_.clone().to_algebraic(),
+        )
+    });
+
+    frequencies
+}
+
+/// Returns the conditional entropy of the choices in `choices`, a map from the line leading up to
+/// a decision to the number of times each move was chosen there.
+fn decision_point<M>(
+    move_number: usize,
+    choices: &HashMap<Vec<M>, HashMap<M, usize>>,
+) -> DecisionPoint {
+    let games: usize = choices.values().flat_map(|counts| counts.values()).sum();
+
+    let entropy = choices
+        .values()
+        .map(|counts| {
+            let line_games: usize = counts.values().sum();
+
+            let line_entropy: f64 = counts
+                .values()
+                .map(|count| {
+                    let p = *count as f64 / line_games as f64;
+                    -p * p.log2()
+                })
+                .sum();
+
+            line_entropy * line_games as f64 / games as f64
+        })
+        .sum::<f64>()
+        .max(0.);
+
+    DecisionPoint {
+        move_number,
+        games,
+        entropy,
+    }
+}
+
+/// Returns a summary of the openings `player` chose in the games of `game_iter`, including how
+/// varied their choices are at each of their first `depth` moves with each color. Games `player`
+/// didn't play in are ignored.
+pub fn repertoire<'a, M, G>(
+    game_iter: &mut dyn Iterator<Item = &'a G>,
+    player: &str,
+    depth: usize,
+) -> Repertoire<M>
+where
+    M: 'static + Clone + Move + Eq + Hash,
+    G: 'a + GivePlayers + ListMoves<M>,
+{
+    let mut first_moves: HashMap<M, usize> = HashMap::new();
+    let mut replies: HashMap<M, HashMap<M, usize>> = HashMap::new();
+    let mut white_choices: Vec<HashMap<Vec<M>, HashMap<M, usize>>> = vec![HashMap::new(); depth];
+    let mut black_choices: Vec<HashMap<Vec<M>, HashMap<M, usize>>> = vec![HashMap::new(); depth];
+
+    for game in game_iter {
+        let color = match game.color_of(player) {
+            Some(color) => color,
+            None => continue,
+        };
+
+        let moves = game.list_moves().take(depth * 2).collect::<Vec<_>>();
+
+        let (first_ply, choices) = match color {
+            Color::White => (0, &mut white_choices),
+            Color::Black => (1, &mut black_choices),
+        };
+
+        for (decision, ply) in (first_ply..moves.len()).step_by(2).enumerate() {
+            *choices[decision]
+                .entry(moves[..ply].to_vec())
+                .or_default()
+                .entry(moves[ply].clone())
+                .or_default() += 1;
+        }
+
+        match (color, moves.first(), moves.get(1)) {
+            (Color::White, Some(first_move), _) => {
+                *first_moves.entry(first_move.clone()).or_default() += 1;
+            }
+            (Color::Black, Some(first_move), Some(reply)) => {
+                *replies
+                    .entry(first_move.clone())
+                    .or_default()
+                    .entry(reply.clone())
+                    .or_default() += 1;
+            }
+            _ => {}
+        }
+    }
+
+    let mut replies = replies
+        .into_iter()
+        .map(|(first_move, replies)| Replies {
+            first_move,
+            replies: frequencies(replies),
+        })
+        .collect::<Vec<_>>();
+
+    replies.sort_by_cached_key(|replies| {
+        (
+            Reverse(replies.games()),
+            replies.first_move.clone().to_algebraic(),
+        )
+    });
+
+    let decisions = |choices: Vec<HashMap<Vec<M>, HashMap<M, usize>>>| {
+        choices
+            .iter()
+            .enumerate()
+            .take_while(|(_, choices)| !choices.is_empty())
+            .map(|(decision, choices)| decision_point(decision + 1, choices))
+            .collect::<Vec<_>>()
+    };
+
+    Repertoire {
+        first_moves: frequencies(first_moves),
+        replies,
+        white_decisions: decisions(white_choices),
+        black_decisions: decisions(black_choices),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use float_cmp::approx_eq;
+
+    use super::repertoire;
+    use crate::game::test_utils::*;
+    use crate::game::{Color, Game};
+    use crate::moves::Move;
+    use crate::AlgebraicMove;
+
+    fn close(a: f64, b: f64) -> bool {
+        approx_eq!(f64, a, b, epsilon = 0.00000001)
+    }
+
+    fn algebraic(san: &str) -> AlgebraicMove {
+        AlgebraicMove::from_algebraic(String::from(san))
+    }
+
+    #[fixture]
+    fn games() -> Vec<Game<AlgebraicMove>> {
+        vec![
+            game("alice", "bob", "1-0", "1. e4 e5 2. Nf3 Nc6"),
+            game("alice", "bob", "1-0", "1. e4 c5 2. Nf3 d6"),
+            game("alice", "bob", "1-0", "1. e4 c5 2. c3 d5"),
+            game("alice", "bob", "1-0", "1. d4 d5 2. c4 e6"),
+            game("bob", "alice", "1-0", "1. e4 c5 2. Nf3 d6"),
+            game("bob", "alice", "1-0", "1. e4 c5 2. Nf3 Nc6"),
+            game("bob", "alice", "1-0", "1. d4 Nf6 2. c4 e6"),
+        ]
+    }
+
+    #[rstest]
+    fn repertoire_should_count_first_moves(games: Vec<Game<AlgebraicMove>>) {
+        let repertoire = repertoire(&mut games.iter(), "alice", 2);
+
+        let first_moves = repertoire
+            .first_moves()
+            .iter()
+            .map(|frequency| (frequency.move_().clone().to_algebraic(), frequency.games()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            first_moves,
+            vec![(String::from("e4"), 3), (String::from("d4"), 1)]
+        );
+        assert!(close(repertoire.first_moves()[0].rate(), 0.75));
+    }
+
+    #[rstest]
+    fn repertoire_should_count_replies(games: Vec<Game<AlgebraicMove>>) {
+        let repertoire = repertoire(&mut games.iter(), "alice", 2);
+
+        let replies_to_e4 = repertoire.replies_to(&algebraic("e4")).unwrap();
+
+        assert_eq!(replies_to_e4.games(), 2);
+        assert_eq!(replies_to_e4.replies()[0].move_(), &algebraic("c5"));
+        assert_eq!(
+            repertoire.replies_to(&algebraic("d4")).unwrap().replies()[0].move_(),
+            &algebraic("Nf6")
+        );
+        assert!(repertoire.replies_to(&algebraic("c4")).is_none());
+    }
+
+    #[rstest]
+    fn repertoire_should_measure_decision_entropy(games: Vec<Game<AlgebraicMove>>) {
+        let repertoire = repertoire(&mut games.iter(), "alice", 2);
+
+        let white_decisions = repertoire.decisions(Color::White);
+
+        // 1. e4 three times and 1. d4 once.
+        assert!(close(
+            white_decisions[0].entropy(),
+            -(0.75f64 * 0.75f64.log2() + 0.25 * 0.25f64.log2())
+        ));
+        // After 1. e4 c5, Nf3 and c3 are played equally often, in half of the games.
+        assert!(close(white_decisions[1].entropy(), 0.5));
+        assert!(close(white_decisions[1].effective_choices(), 0.5f64.exp2()));
+
+        let black_decisions = repertoire.decisions(Color::Black);
+
+        assert!(close(black_decisions[0].entropy(), 0.));
+        assert!(close(black_decisions[1].entropy(), 2. / 3.));
+        assert_eq!(black_decisions[1].games(), 3);
+    }
+}
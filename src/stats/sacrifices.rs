@@ -0,0 +1,241 @@
+use shakmaty::Position;
+
+use crate::game::{Color, GiveEvals, GivePlayers, GiveResult, ListMoves};
+use crate::moves::Move;
+use crate::position;
+use crate::stats::Score;
+
+/// How many points of material a player must be down for a loss of material to count as a
+/// sacrifice, so that giving up a pawn for an initiative isn't counted.
+const SACRIFICED_MATERIAL: i32 = 2;
+
+/// A move where a player gave up material and stayed down for it.
+#[derive(Debug)]
+pub struct Sacrifice<'a, G> {
+    game: &'a G,
+    move_number: usize,
+    material: i32,
+    checked: bool,
+}
+
+impl<'a, G> Sacrifice<'a, G> {
+    /// Returns the game the sacrifice was made in.
+    pub fn game(&self) -> &'a G {
+        self.game
+    }
+
+    /// Returns the number of the move the sacrifice was made with.
+    pub fn move_number(&self) -> usize {
+        self.move_number
+    }
+
+    /// Returns how many points of material the player gave up, once the opponent took it.
+    pub fn material(&self) -> i32 {
+        self.material
+    }
+
+    /// Returns whether the game's evaluations confirmed the sacrifice wasn't a blunder. Without
+    /// evaluations around the move, a sacrifice is only judged by material.
+    pub fn checked(&self) -> bool {
+        self.checked
+    }
+}
+
+/// How often a player sacrifices material and how it works out for them.
+#[derive(Debug)]
+pub struct Sacrifices<'a, G> {
+    games: usize,
+    with_sacrifice: Score,
+    sacrifices: Vec<Sacrifice<'a, G>>,
+    blunders: usize,
+}
+
+impl<'a, G> Sacrifices<'a, G> {
+    /// Returns the number of games the player played.
+    pub fn games(&self) -> usize {
+        self.games
+    }
+
+    /// Returns the player's score in games where they sacrificed material.
+    pub fn with_sacrifice(&self) -> Score {
+        self.with_sacrifice
+    }
+
+    /// Returns the fraction of the player's games in which they sacrificed material, or `None` if
+    /// they played no games.
+    pub fn frequency(&self) -> Option<f64> {
+        if self.games > 0 {
+            Some(self.with_sacrifice.games() as f64 / self.games as f64)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the fraction of available points the player scored in games where they sacrificed
+    /// material, or `None` if they never did.
+    pub fn success_rate(&self) -> Option<f64> {
+        self.with_sacrifice.rate()
+    }
+
+    /// Returns the player's sacrifices, in the order their games were given.
+    pub fn sacrifices(&self) -> &[Sacrifice<'a, G>] {
+        &self.sacrifices
+    }
+
+    /// Returns the number of times the player lost material in a way that looked like a
+    /// sacrifice, but the game's evaluations showed to be a blunder.
+    pub fn blunders(&self) -> usize {
+        self.blunders
+    }
+}
+
+/// Returns how often `player` sacrificed material in the games of `game_iter` and how they scored
+/// when they did. Games `player` didn't play in are ignored.
+///
+/// A sacrifice is a move after which the player is left more than a pawn down, counting pawns as
+/// 1, minor pieces as 3, rooks as 5, and queens as 9, and more than a pawn worse off than before
+/// the move, and stays so for the next `moves` of their moves, so that pieces traded or won back
+/// straight away aren't counted. Where the game has evaluations before and after the move, a
+/// move that lost the player at least `threshold` centipawns is counted as a blunder instead.
+pub fn sacrifices<'a, M, G>(
+    game_iter: &mut dyn Iterator<Item = &'a G>,
+    player: &str,
+    moves: usize,
+    threshold: u32,
+) -> Sacrifices<'a, G>
+where
+    M: 'static + Clone + Move,
+    G: 'a + GiveEvals + GivePlayers + GiveResult + ListMoves<M>,
+{
+    let mut games = 0;
+    let mut with_sacrifice = Score::new();
+    let mut sacrifices = Vec::new();
+    let mut blunders = 0;
+
+    for game in game_iter {
+        let color = match game.color_of(player) {
+            Some(color) => color,
+            None => continue,
+        };
+
+        games += 1;
+
+        let balances = position::replay(game.list_moves())
+            .iter()
+            .map(|position| position::material_balance(position.board(), color))
+            .collect::<Vec<_>>();
+        let evals = game.evals();
+        let mut sacrificed = false;
+
+        // The player's moves are played from the positions at every other index, starting with
+        // the first for white and the second for black.
+        let mut ply = (color == Color::Black) as usize;
+
+        while ply + 2 < balances.len() {
+            let before = balances[ply];
+            let down = |balance: i32| {
+                balance <= -SACRIFICED_MATERIAL && balance <= before - SACRIFICED_MATERIAL
+            };
+
+            // The positions from the opponent taking the material up to the player's last move
+            // it must stay taken for, or as many of them as the game lasted.
+            let last = (ply + 1 + 2 * moves).min(balances.len() - 1);
+
+            if !balances[ply + 2..=last]
+                .iter()
+                .all(|balance| down(*balance))
+            {
+                ply += 2;
+                continue;
+            }
+
+            // Evaluations are given after each move, so the one before the move at `ply` is at
+            // `ply - 1`.
+            let eval_before = ply.checked_sub(1).and_then(|index| evals.get(index));
+            let eval_drop = match (eval_before, evals.get(ply)) {
+                (Some(Some(before)), Some(Some(after))) => {
+                    Some(before.centipawns_for(color) - after.centipawns_for(color))
+                }
+                _ => None,
+            };
+
+            match eval_drop {
+                Some(drop) if drop >= threshold as i32 => blunders += 1,
+                _ => {
+                    sacrificed = true;
+                    sacrifices.push(Sacrifice {
+                        game,
+                        move_number: ply / 2 + 1,
+                        material: before - balances[ply + 2],
+                        checked: eval_drop.is_some(),
+                    });
+                }
+            }
+
+            // A deficit is only counted once, however long it lasts.
+            let mut next = last + 1;
+
+            while next < balances.len() && down(balances[next]) {
+                next += 1;
+            }
+
+            ply = next + (next + ply) % 2;
+        }
+
+        if sacrificed {
+            with_sacrifice.add(game.result().for_color(color));
+        }
+    }
+
+    Sacrifices {
+        games,
+        with_sacrifice,
+        sacrifices,
+        blunders,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::sacrifices;
+    use crate::game::test_utils::*;
+    use crate::game::GivePlayers;
+
+    #[rstest]
+    fn sacrifices_should_tell_sacrifices_from_blunders() {
+        let games = [
+            // alice sacrifices a knight on f7 and wins.
+            parse_game(
+                "[White \"alice\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n\
+                 1. e4 e5 2. Nf3 Nc6 3. Bc4 Nf6 4. Ng5 d5 5. exd5 Nxd5 6. Nxf7 Kxf7 \
+                 7. Qf3+ Ke6 8. Nc3 Ncb4 1-0",
+            ),
+            // alice gives up her queen, which the evaluations show to be a blunder.
+            parse_game(
+                "[White \"alice\"]\n[Black \"dave\"]\n[Result \"0-1\"]\n\
+                 1. e4 e5 2. Qh5 Nf6 { [%eval 0.0] } 3. Qxf7+ { [%eval -8.0] } 3... Kxf7 \
+                 4. d3 d6 5. Nf3 Be7 0-1",
+            ),
+            // bob's knight is won back straight away.
+            parse_game(
+                "[White \"bob\"]\n[Black \"alice\"]\n[Result \"1/2-1/2\"]\n\
+                 1. e4 e5 2. Nf3 Nc6 3. Nxe5 Nxe5 4. d4 Nc6 5. d5 Nb4 1/2-1/2",
+            ),
+        ];
+
+        let stats = sacrifices(&mut games.iter(), "alice", 2, 300);
+
+        assert_eq!(stats.games(), 3);
+        assert_eq!(stats.blunders(), 1);
+        assert_eq!(stats.with_sacrifice().wins(), 1);
+        assert_eq!(stats.with_sacrifice().games(), 1);
+        assert_eq!(stats.success_rate(), Some(1.));
+        assert_eq!(stats.sacrifices().len(), 1);
+        assert_eq!(stats.sacrifices()[0].game().black_player(), Some("bob"));
+        assert_eq!(stats.sacrifices()[0].move_number(), 6);
+        assert_eq!(stats.sacrifices()[0].material(), 2);
+        assert!(!stats.sacrifices()[0].checked());
+    }
+}
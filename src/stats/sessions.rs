@@ -0,0 +1,191 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+
+use crate::clock;
+use crate::game::{GiveClocks, GiveDate, GivePlayers, GiveResult, PlayerResult};
+use crate::stats::Score;
+
+/// A run of games a player played one after another, without a long break between them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    results: Vec<PlayerResult>,
+}
+
+impl Session {
+    /// Returns when the first game of the session started, in UTC.
+    pub fn start(&self) -> NaiveDateTime {
+        self.start
+    }
+
+    /// Returns when the last game of the session ended, in UTC, as estimated from the time its
+    /// players spent on their moves. Games without clock times are taken to end as they start.
+    pub fn end(&self) -> NaiveDateTime {
+        self.end
+    }
+
+    /// Returns the number of games played in the session.
+    pub fn games(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Returns the player's result in each game of the session, in the order they were played.
+    pub fn results(&self) -> &[PlayerResult] {
+        &self.results
+    }
+
+    /// Returns the player's score over the session.
+    pub fn score(&self) -> Score {
+        let mut score = Score::new();
+
+        for result in &self.results {
+            score.add(*result);
+        }
+
+        score
+    }
+}
+
+/// Returns how long `game` took, as the sum of the time its players spent on the moves whose
+/// times are known.
+fn game_length<G: GiveClocks>(game: &G) -> Duration {
+    clock::move_times(game.clocks(), game.time_control())
+        .into_iter()
+        .flatten()
+        .sum()
+}
+
+/// Returns `player`'s games among the games of `game_iter` grouped into sessions, in the order
+/// they were played. A game starting more than `max_gap` after the previous game ended starts a
+/// new session. Games `player` didn't play in, and games without a known start time, are
+/// ignored.
+pub fn sessions<'a, G>(
+    game_iter: &mut dyn Iterator<Item = &'a G>,
+    player: &str,
+    max_gap: Duration,
+) -> Vec<Session>
+where
+    G: 'a + GiveClocks + GiveDate + GivePlayers + GiveResult,
+{
+    let mut games = game_iter
+        .filter_map(|game| {
+            let color = game.color_of(player)?;
+            let start = game.date()?.and_time(game.time()?);
+            let end = start + chrono::Duration::from_std(game_length(game)).ok()?;
+
+            Some((start, end, game.result().for_color(color)))
+        })
+        .collect::<Vec<_>>();
+
+    games.sort_by_key(|(start, _, _)| *start);
+
+    let max_gap = chrono::Duration::from_std(max_gap).unwrap_or(chrono::Duration::MAX);
+    let mut sessions: Vec<Session> = Vec::new();
+
+    for (start, end, result) in games {
+        match sessions.last_mut() {
+            Some(session) if start - session.end <= max_gap => {
+                session.end = session.end.max(end);
+                session.results.push(result);
+            }
+            _ => sessions.push(Session {
+                start,
+                end,
+                results: vec![result],
+            }),
+        }
+    }
+
+    sessions
+}
+
+/// Returns the player's score in the first game of each of `sessions`, in the second, and so on,
+/// to show whether they do worse the longer they keep playing.
+pub fn score_by_game_number(sessions: &[Session]) -> Vec<Score> {
+    let mut scores: Vec<Score> = Vec::new();
+
+    for session in sessions {
+        for (index, result) in session.results.iter().enumerate() {
+            if scores.len() <= index {
+                scores.push(Score::new());
+            }
+
+            scores[index].add(*result);
+        }
+    }
+
+    scores
+}
+
+/// Returns the number of `sessions` of each length in games.
+pub fn session_lengths(sessions: &[Session]) -> BTreeMap<usize, usize> {
+    let mut lengths = BTreeMap::new();
+
+    for session in sessions {
+        *lengths.entry(session.games()).or_insert(0) += 1;
+    }
+
+    lengths
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rstest::*;
+
+    use super::{score_by_game_number, session_lengths, sessions};
+    use crate::game::test_utils::*;
+    use crate::game::{Game, PlayerResult};
+    use crate::AlgebraicMove;
+
+    fn game(result: &str, date: &str, time: &str, clocks: &str) -> Game<AlgebraicMove> {
+        parse_game(&format!(
+            "[White \"alice\"]\n[Black \"bob\"]\n[Result \"{}\"]\n[UTCDate \"{}\"]\n\
+             [UTCTime \"{}\"]\n[TimeControl \"180+0\"]\n1. e4 {} 1... e5 {} {}",
+            result, date, time, clocks, clocks, result
+        ))
+    }
+
+    #[rstest]
+    fn sessions_should_split_games_at_long_breaks() {
+        let games = [
+            // Each side spends two minutes, so the game ends at 20:04.
+            game("1-0", "2021.01.01", "20:00:00", "{ [%clk 0:01:00] }"),
+            game("0-1", "2021.01.01", "20:10:00", ""),
+            game("1/2-1/2", "2021.01.01", "20:30:00", ""),
+            game("0-1", "2021.01.01", "20:20:00", ""),
+            game("1-0", "2021.01.02", "09:00:00", ""),
+            game("1-0", "????.??.??", "09:05:00", ""),
+        ];
+
+        let sessions = sessions(&mut games.iter(), "alice", Duration::from_secs(15 * 60));
+
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].games(), 4);
+        assert_eq!(
+            sessions[0].results(),
+            [
+                PlayerResult::Won,
+                PlayerResult::Lost,
+                PlayerResult::Lost,
+                PlayerResult::Drew
+            ]
+        );
+        assert_eq!(sessions[0].end().to_string(), "2021-01-01 20:30:00");
+        assert_eq!(sessions[0].score().rate(), Some(1.5 / 4.));
+
+        let scores = score_by_game_number(&sessions);
+
+        assert_eq!(scores.len(), 4);
+        assert_eq!(scores[0].wins(), 2);
+        assert_eq!(scores[1].losses(), 1);
+        assert_eq!(
+            session_lengths(&sessions).into_iter().collect::<Vec<_>>(),
+            [(1, 1), (4, 1)]
+        );
+    }
+}
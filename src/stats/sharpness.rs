@@ -0,0 +1,163 @@
+use std::hash::Hash;
+
+use crate::game::{GameResult, GiveResult, ListMoves};
+use crate::move_tree::MoveTreeView;
+use crate::moves::Move;
+
+/// How double-edged a line is, judged by how often its games ended decisively.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Sharpness {
+    games: usize,
+    decisive: usize,
+}
+
+impl Sharpness {
+    /// Returns the number of games counted.
+    pub fn games(&self) -> usize {
+        self.games
+    }
+
+    /// Returns the number of games that ended in a win for either side.
+    pub fn decisive(&self) -> usize {
+        self.decisive
+    }
+
+    /// Returns the fraction of games that ended in a win for either side, or `None` if no games
+    /// were counted. Higher values mean sharper lines.
+    pub fn rate(&self) -> Option<f64> {
+        if self.games > 0 {
+            Some(self.decisive as f64 / self.games as f64)
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns the sharpness of the games in `game_iter`.
+pub fn sharpness<'a, G>(game_iter: &mut dyn Iterator<Item = &'a G>) -> Sharpness
+where
+    G: 'a + GiveResult,
+{
+    let mut sharpness = Sharpness::default();
+
+    for game in game_iter {
+        sharpness.games += 1;
+
+        if game.result() != GameResult::Draw {
+            sharpness.decisive += 1;
+        }
+    }
+
+    sharpness
+}
+
+/// Returns the sharpness of each move played from the position `view` is at, sharpest first and
+/// then most played first. Moves played in fewer than `min_games` games are left out, since a
+/// handful of games says little about how sharp a line is.
+pub fn branch_sharpness<'a, M, G>(
+    view: &MoveTreeView<'a, M, G>,
+    min_games: usize,
+) -> Vec<(M, Sharpness)>
+where
+    M: Clone + Move + Eq + Hash,
+    G: GiveResult + ListMoves<M>,
+{
+    let mut branches = match view.branches() {
+        Some(branches) => branches
+            .map(|move_| {
+                let branch = view.clone().with_next(move_);
+
+                (move_.clone(), sharpness(&mut branch.iter()))
+            })
+            .filter(|(_, sharpness)| sharpness.games >= min_games)
+            .collect::<Vec<_>>(),
+        None => Vec::new(),
+    };
+
+    branches.sort_by(|(move_a, a), (move_b, b)| {
+        b.rate()
+            .partial_cmp(&a.rate())
+            .unwrap()
+            .then(b.games.cmp(&a.games))
+            .then_with(|| {
+                move_a
+                    .clone()
+                    .to_algebraic()
+                    .cmp(&move_b.clone().to_algebraic())
+            })
+    });
+
+    branches
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::{branch_sharpness, sharpness};
+    use crate::game::test_utils::*;
+    use crate::game::Game;
+    use crate::moves::Move;
+    use crate::{AlgebraicMove, MoveTree};
+
+    fn game(result: &str, moves: &str) -> Game<AlgebraicMove> {
+        parse_game(&format!(
+            "[White \"a\"]\n[Black \"b\"]\n[Result \"{}\"]\n{} {}",
+            result, moves, result
+        ))
+    }
+
+    #[fixture]
+    fn games() -> Vec<Game<AlgebraicMove>> {
+        vec![
+            game("1-0", "1. e4 c5"),
+            game("0-1", "1. e4 c5"),
+            game("1/2-1/2", "1. e4 e5"),
+            game("1-0", "1. e4 e5"),
+            game("1/2-1/2", "1. d4 d5"),
+            game("1/2-1/2", "1. d4 d5"),
+            game("1-0", "1. c4 c5"),
+        ]
+    }
+
+    #[rstest]
+    fn sharpness_should_count_decisive_games(games: Vec<Game<AlgebraicMove>>) {
+        let sharpness = sharpness(&mut games.iter());
+
+        assert_eq!(sharpness.games(), 7);
+        assert_eq!(sharpness.decisive(), 4);
+        assert_eq!(sharpness.rate(), Some(4. / 7.));
+    }
+
+    #[rstest]
+    fn branch_sharpness_should_order_sharpest_first(games: Vec<Game<AlgebraicMove>>) {
+        let move_tree = MoveTree::new(games);
+        let e4 = AlgebraicMove::from_algebraic(String::from("e4"));
+
+        let first_moves = branch_sharpness(&move_tree.view(), 2)
+            .into_iter()
+            .map(|(move_, sharpness)| (move_.to_algebraic(), sharpness.rate()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            first_moves,
+            vec![
+                (String::from("e4"), Some(0.75)),
+                (String::from("d4"), Some(0.))
+            ]
+        );
+
+        let replies = branch_sharpness(&move_tree.view().with_next(&e4), 1)
+            .into_iter()
+            .map(|(move_, sharpness)| (move_.to_algebraic(), sharpness.rate()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            replies,
+            vec![
+                (String::from("c5"), Some(1.)),
+                (String::from("e5"), Some(0.5))
+            ]
+        );
+    }
+}
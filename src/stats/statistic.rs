@@ -0,0 +1,574 @@
+use std::fmt;
+
+use rayon::prelude::*;
+
+use crate::game::{GameResult, GivePlayers, GiveResult};
+use crate::stats::Score;
+
+/// The final value of a statistic.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// No value, such as a rate over zero games.
+    Missing,
+    /// A number of things counted.
+    Count(usize),
+    /// Any other number, such as a rate or an average.
+    Number(f64),
+    /// A yes or no answer, such as whether a game was rated.
+    Bool(bool),
+    /// A piece of text, such as a move or a player's name.
+    Text(String),
+    /// A list of values.
+    List(Vec<Value>),
+    /// A list of named values, in order.
+    Map(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Creates a `Value::Map` from `entries`.
+    pub fn map<'a>(entries: impl IntoIterator<Item = (&'a str, Value)>) -> Value {
+        Value::Map(
+            entries
+                .into_iter()
+                .map(|(name, value)| (String::from(name), value))
+                .collect(),
+        )
+    }
+
+    /// Returns the value as a table of rows separated by `separator`, such as `,` for CSV or
+    /// `\t` for TSV, with a header row of column names. A list of maps gives a row for each map,
+    /// with the columns named by the first map, and a single map gives a single row. Missing
+    /// values are left empty, lists and maps within a row are written as JSON, and fields holding
+    /// the separator, quotes, or line breaks are quoted.
+    pub fn to_table(&self, separator: char) -> String {
+        let rows = match self {
+            Value::List(values) => values.iter().collect::<Vec<_>>(),
+            value => vec![value],
+        };
+
+        let columns = match rows.first() {
+            Some(Value::Map(entries)) => entries
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>(),
+            Some(_) => vec!["value"],
+            None => Vec::new(),
+        };
+
+        let join = |fields: Vec<String>| {
+            let fields = fields
+                .iter()
+                .map(|field| table_field(field, separator))
+                .collect::<Vec<_>>();
+
+            fields.join(&separator.to_string()) + "\n"
+        };
+
+        let mut output = join(columns.iter().map(|column| String::from(*column)).collect());
+
+        for row in rows {
+            let fields = match row {
+                Value::Map(entries) => columns
+                    .iter()
+                    .map(|column| {
+                        entries
+                            .iter()
+                            .find(|(name, _)| name == column)
+                            .map_or_else(String::new, |(_, value)| value.to_field())
+                    })
+                    .collect(),
+                value => vec![value.to_field()],
+            };
+
+            output += &join(fields);
+        }
+
+        output
+    }
+
+    /// Returns the value as a single field of a table.
+    fn to_field(&self) -> String {
+        match self {
+            Value::Missing => String::new(),
+            Value::Text(text) => text.clone(),
+            Value::List(_) | Value::Map(_) => self.to_json(),
+            value => value.to_string(),
+        }
+    }
+
+    /// Returns the value as JSON. Missing values and numbers that aren't finite become `null`,
+    /// and maps become objects with their entries in order.
+    pub fn to_json(&self) -> String {
+        match self {
+            Value::Missing => String::from("null"),
+            Value::Count(count) => count.to_string(),
+            Value::Number(number) if number.is_finite() => number.to_string(),
+            Value::Number(_) => String::from("null"),
+            Value::Bool(answer) => answer.to_string(),
+            Value::Text(text) => json_string(text),
+            Value::List(values) => format!(
+                "[{}]",
+                values
+                    .iter()
+                    .map(Value::to_json)
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+            Value::Map(entries) => format!(
+                "{{{}}}",
+                entries
+                    .iter()
+                    .map(|(name, value)| format!("{}:{}", json_string(name), value.to_json()))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+        }
+    }
+}
+
+/// Returns `field` quoted for a table with `separator` if it needs to be.
+fn table_field(field: &str, separator: char) -> String {
+    if field.contains(['"', '\n', '\r', separator]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        String::from(field)
+    }
+}
+
+/// Returns `text` as a JSON string literal.
+fn json_string(text: &str) -> String {
+    let mut output = String::from("\"");
+
+    for character in text.chars() {
+        match character {
+            '"' => output += "\\\"",
+            '\\' => output += "\\\\",
+            '\n' => output += "\\n",
+            '\r' => output += "\\r",
+            '\t' => output += "\\t",
+            character if character.is_control() => {
+                output += &format!("\\u{:04x}", character as u32)
+            }
+            character => output.push(character),
+        }
+    }
+
+    output.push('"');
+    output
+}
+
+impl From<usize> for Value {
+    fn from(count: usize) -> Value {
+        Value::Count(count)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(number: f64) -> Value {
+        Value::Number(number)
+    }
+}
+
+impl From<String> for Value {
+    fn from(text: String) -> Value {
+        Value::Text(text)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(text: &str) -> Value {
+        Value::Text(String::from(text))
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Value {
+        value.map_or(Value::Missing, Into::into)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(values: Vec<T>) -> Value {
+        Value::List(values.into_iter().map(Into::into).collect())
+    }
+}
+
+impl From<Score> for Value {
+    fn from(score: Score) -> Value {
+        Value::map(vec![
+            ("wins", score.wins().into()),
+            ("draws", score.draws().into()),
+            ("losses", score.losses().into()),
+            ("rate", score.rate().into()),
+        ])
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Missing => write!(f, "-"),
+            Value::Count(count) => fmt::Display::fmt(count, f),
+            Value::Number(number) => fmt::Display::fmt(number, f),
+            Value::Bool(answer) => fmt::Display::fmt(answer, f),
+            Value::Text(text) => write!(f, "{}", text),
+            Value::List(values) => {
+                write!(f, "[")?;
+
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+
+                    value.fmt(f)?;
+                }
+
+                write!(f, "]")
+            }
+            Value::Map(entries) => {
+                write!(f, "{{")?;
+
+                for (i, (name, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+
+                    write!(f, "{}: ", name)?;
+                    value.fmt(f)?;
+                }
+
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// A statistic that is built up one game at a time, so that many statistics can be computed in a
+/// single pass over a set of games.
+pub trait Statistic<G> {
+    /// Returns a short name describing the statistic.
+    fn name(&self) -> &str;
+
+    /// Counts `game` towards the statistic.
+    fn accumulate(&mut self, game: &G);
+
+    /// Returns the value of the statistic over all games accumulated so far.
+    fn finalize(&self) -> Value;
+}
+
+/// A statistic that can absorb another of the same kind, so that partial statistics over
+/// separate batches of games can be combined.
+pub trait Merge {
+    /// Adds everything counted by `other` to this statistic.
+    fn merge(&mut self, other: Self);
+}
+
+/// Feeds each game of `game_iter` to every statistic in `statistics` in a single pass, then
+/// returns the name and final value of each statistic in order.
+pub fn compute<'a, G: 'a>(
+    game_iter: &mut dyn Iterator<Item = &'a G>,
+    statistics: &mut [Box<dyn Statistic<G>>],
+) -> Vec<(String, Value)> {
+    for game in game_iter {
+        for statistic in statistics.iter_mut() {
+            statistic.accumulate(game);
+        }
+    }
+
+    statistics
+        .iter()
+        .map(|statistic| (String::from(statistic.name()), statistic.finalize()))
+        .collect()
+}
+
+/// Computes `statistic` over `games` on multiple threads, returning the result of accumulating
+/// every game into a copy of `statistic`.
+///
+/// Games are split into batches of `batch_size`, each batch is accumulated into its own copy of
+/// `statistic`, and the copies are then merged together.
+///
+/// # Panics
+///
+/// Panics if `batch_size` is zero.
+pub fn compute_parallel<G, S>(games: &[G], statistic: &S, batch_size: usize) -> S
+where
+    G: Sync,
+    S: Statistic<G> + Merge + Clone + Send + Sync,
+{
+    assert!(batch_size > 0, "Batch size must be positive");
+
+    games
+        .par_chunks(batch_size)
+        .fold(
+            || statistic.clone(),
+            |mut partial, batch| {
+                for game in batch {
+                    partial.accumulate(game);
+                }
+
+                partial
+            },
+        )
+        .reduce(
+            || statistic.clone(),
+            |mut total, partial| {
+                total.merge(partial);
+                total
+            },
+        )
+}
+
+/// The fraction of games won by white, won by black, and drawn. This is the streaming form of
+/// [`results`](crate::stats::results).
+#[derive(Debug, Clone, Default)]
+pub struct ResultRates {
+    white_wins: usize,
+    black_wins: usize,
+    draws: usize,
+}
+
+impl ResultRates {
+    /// Creates a new `ResultRates` with no games counted.
+    pub fn new() -> ResultRates {
+        ResultRates::default()
+    }
+
+    /// Returns the number of games counted.
+    pub fn games(&self) -> usize {
+        self.white_wins + self.black_wins + self.draws
+    }
+
+    /// Returns the fraction of games won by white, won by black, and drawn, or `None` if no games
+    /// were counted.
+    pub fn rates(&self) -> Option<(f64, f64, f64)> {
+        let games = self.games() as f64;
+
+        if games > 0. {
+            Some((
+                self.white_wins as f64 / games,
+                self.black_wins as f64 / games,
+                self.draws as f64 / games,
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+impl Merge for ResultRates {
+    fn merge(&mut self, other: ResultRates) {
+        self.white_wins += other.white_wins;
+        self.black_wins += other.black_wins;
+        self.draws += other.draws;
+    }
+}
+
+impl<G: GiveResult> Statistic<G> for ResultRates {
+    fn name(&self) -> &str {
+        "results"
+    }
+
+    fn accumulate(&mut self, game: &G) {
+        match game.result() {
+            GameResult::WhiteWon => self.white_wins += 1,
+            GameResult::BlackWon => self.black_wins += 1,
+            GameResult::Draw => self.draws += 1,
+        }
+    }
+
+    fn finalize(&self) -> Value {
+        let rates = self.rates();
+
+        Value::map(vec![
+            ("games", self.games().into()),
+            ("white_wins", rates.map(|rates| rates.0).into()),
+            ("black_wins", rates.map(|rates| rates.1).into()),
+            ("draws", rates.map(|rates| rates.2).into()),
+        ])
+    }
+}
+
+/// One player's score. Games the player didn't play in are ignored.
+#[derive(Debug, Clone)]
+pub struct PlayerScore {
+    player: String,
+    score: Score,
+}
+
+impl PlayerScore {
+    /// Creates a new `PlayerScore` for `player` with no games counted.
+    pub fn new(player: &str) -> PlayerScore {
+        PlayerScore {
+            player: String::from(player),
+            score: Score::new(),
+        }
+    }
+}
+
+impl Merge for PlayerScore {
+    fn merge(&mut self, other: PlayerScore) {
+        self.score.merge(other.score);
+    }
+}
+
+impl<G: GivePlayers + GiveResult> Statistic<G> for PlayerScore {
+    fn name(&self) -> &str {
+        "score"
+    }
+
+    fn accumulate(&mut self, game: &G) {
+        if let Some(color) = game.color_of(&self.player) {
+            self.score.add(game.result().for_color(color));
+        }
+    }
+
+    fn finalize(&self) -> Value {
+        self.score.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::{compute, compute_parallel, PlayerScore, ResultRates, Statistic, Value};
+    use crate::game::test_utils::*;
+    use crate::game::Game;
+    use crate::AlgebraicMove;
+
+    #[fixture]
+    fn games() -> Vec<Game<AlgebraicMove>> {
+        vec![
+            game("alice", "bob", "1-0", "1. e4 e5"),
+            game("bob", "alice", "1-0", "1. e4 e5"),
+            game("alice", "carol", "1/2-1/2", "1. e4 e5"),
+            game("bob", "carol", "0-1", "1. e4 e5"),
+        ]
+    }
+
+    #[rstest]
+    fn compute_should_finalize_every_statistic(games: Vec<Game<AlgebraicMove>>) {
+        let mut statistics: Vec<Box<dyn Statistic<Game<AlgebraicMove>>>> = vec![
+            Box::new(ResultRates::new()),
+            Box::new(PlayerScore::new("alice")),
+        ];
+
+        let values = compute(&mut games.iter(), &mut statistics);
+
+        assert_eq!(
+            values,
+            vec![
+                (
+                    String::from("results"),
+                    Value::map(vec![
+                        ("games", Value::Count(4)),
+                        ("white_wins", Value::Number(0.5)),
+                        ("black_wins", Value::Number(0.25)),
+                        ("draws", Value::Number(0.25)),
+                    ])
+                ),
+                (
+                    String::from("score"),
+                    Value::map(vec![
+                        ("wins", Value::Count(1)),
+                        ("draws", Value::Count(1)),
+                        ("losses", Value::Count(1)),
+                        ("rate", Value::Number(0.5)),
+                    ])
+                ),
+            ]
+        );
+    }
+
+    #[rstest(batch_size, case(1), case(3), case(10))]
+    fn compute_parallel_should_match_single_pass(
+        games: Vec<Game<AlgebraicMove>>,
+        batch_size: usize,
+    ) {
+        let rates = compute_parallel(&games, &ResultRates::new(), batch_size);
+        let score = compute_parallel(&games, &PlayerScore::new("alice"), batch_size);
+
+        let mut statistics: Vec<Box<dyn Statistic<Game<AlgebraicMove>>>> = vec![
+            Box::new(ResultRates::new()),
+            Box::new(PlayerScore::new("alice")),
+        ];
+        let values = compute(&mut games.iter(), &mut statistics);
+
+        assert_eq!(rates.games(), 4);
+        assert_eq!(
+            Statistic::<Game<AlgebraicMove>>::finalize(&rates),
+            values[0].1
+        );
+        assert_eq!(
+            Statistic::<Game<AlgebraicMove>>::finalize(&score),
+            values[1].1
+        );
+    }
+
+    #[rstest]
+    fn result_rates_should_be_missing_without_games() {
+        let rates = ResultRates::new();
+
+        assert_eq!(
+            Statistic::<Game<AlgebraicMove>>::finalize(&rates).to_string(),
+            "{games: 0, white_wins: -, black_wins: -, draws: -}"
+        );
+    }
+
+    #[rstest]
+    fn value_should_display_nested_values() {
+        let value = Value::map(vec![
+            ("player", "alice".into()),
+            ("rates", vec![0.5, 0.25].into()),
+        ]);
+
+        assert_eq!(value.to_string(), "{player: alice, rates: [0.5, 0.25]}");
+        assert_eq!(format!("{:.1}", Value::Number(0.25)), "0.2");
+    }
+
+    #[rstest]
+    fn value_should_convert_to_table() {
+        let value = Value::List(vec![
+            Value::map(vec![
+                ("move", "e4".into()),
+                ("games", Value::Count(2)),
+                ("rate", Value::Missing),
+            ]),
+            Value::map(vec![
+                ("move", "say \"hi\", e5".into()),
+                ("games", Value::Count(1)),
+                ("rate", Value::Number(0.5)),
+            ]),
+        ]);
+
+        assert_eq!(
+            value.to_table(','),
+            "move,games,rate\ne4,2,\n\"say \"\"hi\"\", e5\",1,0.5\n"
+        );
+        assert_eq!(
+            value.to_table('\t'),
+            "move\tgames\trate\ne4\t2\t\n\"say \"\"hi\"\", e5\"\t1\t0.5\n"
+        );
+        assert_eq!(
+            Value::map(vec![("games", Value::Count(3))]).to_table(','),
+            "games\n3\n"
+        );
+    }
+
+    #[rstest]
+    fn value_should_convert_to_json() {
+        let value = Value::map(vec![
+            ("games", Value::Count(2)),
+            ("rate", Value::Number(0.5)),
+            ("missing", Value::Missing),
+            ("infinite", Value::Number(f64::INFINITY)),
+            ("moves", vec!["e4", "say \"hi\"\n"].into()),
+        ]);
+
+        assert_eq!(
+            value.to_json(),
+            "{\"games\":2,\"rate\":0.5,\"missing\":null,\"infinite\":null,\
+             \"moves\":[\"e4\",\"say \\\"hi\\\"\\n\"]}"
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use crate::game::{GiveDate, GivePlayers, GiveResult, PlayerResult};
+
+/// The longest runs of results a player had in a row, and the run they're on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Streaks {
+    longest_wins: usize,
+    longest_losses: usize,
+    longest_unbeaten: usize,
+    current: Option<(PlayerResult, usize)>,
+}
+
+impl Streaks {
+    /// Returns the most games the player won in a row.
+    pub fn longest_wins(&self) -> usize {
+        self.longest_wins
+    }
+
+    /// Returns the most games the player lost in a row.
+    pub fn longest_losses(&self) -> usize {
+        self.longest_losses
+    }
+
+    /// Returns the most games the player won or drew in a row.
+    pub fn longest_unbeaten(&self) -> usize {
+        self.longest_unbeaten
+    }
+
+    /// Returns the result of the player's most recent game and how many games in a row ended
+    /// that way, or `None` if they played no games.
+    pub fn current(&self) -> Option<(PlayerResult, usize)> {
+        self.current
+    }
+}
+
+/// Returns `player`'s streaks over the games of `game_iter`, taken in the order they were played.
+/// Games `player` didn't play in, and games without a date, are ignored.
+pub fn streaks<'a, G>(game_iter: &mut dyn Iterator<Item = &'a G>, player: &str) -> Streaks
+where
+    G: 'a + GiveDate + GivePlayers + GiveResult,
+{
+    let mut results = game_iter
+        .filter_map(|game| {
+            let color = game.color_of(player)?;
+
+            Some((game.date()?, game.time(), game.result().for_color(color)))
+        })
+        .collect::<Vec<_>>();
+
+    // Stable, so games on the same day without start times stay in the order given.
+    results.sort_by_key(|(date, time, _)| (*date, *time));
+
+    let mut streaks = Streaks::default();
+    let mut unbeaten = 0;
+
+    for (_, _, result) in results {
+        let run = match streaks.current {
+            Some((previous, run)) if previous == result => run + 1,
+            _ => 1,
+        };
+
+        streaks.current = Some((result, run));
+
+        match result {
+            PlayerResult::Won => streaks.longest_wins = streaks.longest_wins.max(run),
+            PlayerResult::Lost => streaks.longest_losses = streaks.longest_losses.max(run),
+            PlayerResult::Drew => {}
+        }
+
+        unbeaten = match result {
+            PlayerResult::Lost => 0,
+            _ => unbeaten + 1,
+        };
+        streaks.longest_unbeaten = streaks.longest_unbeaten.max(unbeaten);
+    }
+
+    streaks
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::streaks;
+    use crate::game::test_utils::*;
+    use crate::game::PlayerResult;
+
+    #[rstest]
+    fn streaks_should_follow_the_order_games_were_played() {
+        let games = [
+            dated_game("alice", "bob", "1-0", "2021.01.03", "1. e4 e5"),
+            dated_game("bob", "alice", "0-1", "2021.01.01", "1. e4 e5"),
+            dated_game("alice", "bob", "1-0", "2021.01.02", "1. e4 e5"),
+            dated_game("alice", "bob", "1/2-1/2", "2021.01.04", "1. e4 e5"),
+            dated_game("alice", "bob", "0-1", "2021.01.05", "1. e4 e5"),
+            dated_game("carol", "alice", "1-0", "2021.01.06", "1. e4 e5"),
+            dated_game("alice", "bob", "0-1", "????.??.??", "1. e4 e5"),
+            dated_game("bob", "carol", "1-0", "2021.01.07", "1. e4 e5"),
+        ];
+
+        let streaks = streaks(&mut games.iter(), "alice");
+
+        assert_eq!(streaks.longest_wins(), 3);
+        assert_eq!(streaks.longest_losses(), 2);
+        assert_eq!(streaks.longest_unbeaten(), 4);
+        assert_eq!(streaks.current(), Some((PlayerResult::Lost, 2)));
+    }
+}
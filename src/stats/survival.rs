@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+
+use crate::game::{Color, GameResult, GiveResult, ListMoves};
+use crate::moves::Move;
+use crate::openings::{Opening, Openings};
+
+/// How long the games of one opening stay undecided.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SurvivalCurve {
+    opening: Option<Opening>,
+    games: usize,
+    white_wins: Vec<usize>,
+    black_wins: Vec<usize>,
+}
+
+impl SurvivalCurve {
+    /// Returns the opening, or `None` for games of no known opening.
+    pub fn opening(&self) -> Option<&Opening> {
+        self.opening.as_ref()
+    }
+
+    /// Returns the number of games played in the opening.
+    pub fn games(&self) -> usize {
+        self.games
+    }
+
+    /// Returns the length in moves of each game `color` won in the opening, shortest first.
+    pub fn win_lengths(&self, color: Color) -> &[usize] {
+        match color {
+            Color::White => &self.white_wins,
+            Color::Black => &self.black_wins,
+        }
+    }
+
+    /// Returns the number of games `color` won in exactly `move_number` moves.
+    pub fn wins_at(&self, color: Color, move_number: usize) -> usize {
+        let lengths = self.win_lengths(color);
+
+        lengths.partition_point(|length| *length <= move_number)
+            - lengths.partition_point(|length| *length < move_number)
+    }
+
+    /// Returns the median length in moves of the games `color` won in the opening.
+    pub fn median_win_length(&self, color: Color) -> Option<usize> {
+        let lengths = self.win_lengths(color);
+
+        lengths.get(lengths.len() / 2).copied()
+    }
+
+    /// Returns the fraction of games still undecided when `move_number` was reached, that is,
+    /// the games that were drawn or won in `move_number` moves or more.
+    pub fn undecided_at(&self, move_number: usize) -> f64 {
+        let decided = self
+            .white_wins
+            .partition_point(|length| *length < move_number)
+            + self
+                .black_wins
+                .partition_point(|length| *length < move_number);
+
+        (self.games - decided) as f64 / self.games as f64
+    }
+
+    /// Returns the length in moves of the longest game won in the opening, or zero if none were.
+    pub fn longest_win(&self) -> usize {
+        self.white_wins
+            .last()
+            .copied()
+            .max(self.black_wins.last().copied())
+            .unwrap_or(0)
+    }
+}
+
+/// Returns a survival curve for each opening played at least `min_games` times in `game_iter`,
+/// with the most played openings first, for choosing lines that suit how long a player likes
+/// their games to go.
+pub fn survival_curves<'a, M, G>(
+    game_iter: &mut dyn Iterator<Item = &'a G>,
+    openings: &Openings,
+    min_games: usize,
+) -> Vec<SurvivalCurve>
+where
+    M: 'static + Clone + Move,
+    G: 'a + GiveResult + ListMoves<M>,
+{
+    let mut by_opening: HashMap<Option<&str>, SurvivalCurve> = HashMap::new();
+
+    for game in game_iter {
+        let opening = openings.classify(game);
+        let curve = by_opening
+            .entry(opening.map(Opening::name))
+            .or_insert_with(|| SurvivalCurve {
+                opening: opening.cloned(),
+                games: 0,
+                white_wins: Vec::new(),
+                black_wins: Vec::new(),
+            });
+
+        curve.games += 1;
+
+        let length = || game.list_moves().count().div_ceil(2);
+
+        match game.result() {
+            GameResult::WhiteWon => curve.white_wins.push(length()),
+            GameResult::BlackWon => curve.black_wins.push(length()),
+            GameResult::Draw => {}
+        }
+    }
+
+    let mut curves = by_opening
+        .into_values()
+        .filter(|curve| curve.games >= min_games)
+        .map(|mut curve| {
+            curve.white_wins.sort_unstable();
+            curve.black_wins.sort_unstable();
+            curve
+        })
+        .collect::<Vec<_>>();
+
+    curves.sort_by(|a, b| {
+        b.games.cmp(&a.games).then_with(|| {
+            a.opening
+                .as_ref()
+                .map(Opening::name)
+                .cmp(&b.opening.as_ref().map(Opening::name))
+        })
+    });
+
+    curves
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::survival_curves;
+    use crate::game::test_utils::*;
+    use crate::game::{Color, Game};
+    use crate::openings::Openings;
+    use crate::AlgebraicMove;
+
+    fn game(result: &str, moves: &str) -> Game<AlgebraicMove> {
+        parse_game(&format!("[Result \"{}\"]\n{} {}", result, moves, result))
+    }
+
+    #[rstest]
+    fn survival_curves_should_track_decisive_games_by_move() {
+        let games = [
+            game("1-0", "1. e4 c5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7#"),
+            game("0-1", "1. e4 c5 2. g3 d6"),
+            game("1/2-1/2", "1. e4 c5 2. b3 Nc6 3. d4"),
+            game("1-0", "1. e4 c5 2. a3 e6 3. d4"),
+            game("1-0", "1. d4 d5"),
+        ];
+
+        let curves = survival_curves(&mut games.iter(), Openings::embedded(), 2);
+
+        assert_eq!(curves.len(), 1);
+
+        let curve = &curves[0];
+
+        assert_eq!(curve.opening().unwrap().name(), "Sicilian Defense");
+        assert_eq!(curve.games(), 4);
+        assert_eq!(curve.win_lengths(Color::White), [3, 4]);
+        assert_eq!(curve.win_lengths(Color::Black), [2]);
+        assert_eq!(curve.wins_at(Color::White, 4), 1);
+        assert_eq!(curve.median_win_length(Color::White), Some(4));
+        assert_eq!(curve.undecided_at(1), 1.);
+        assert_eq!(curve.undecided_at(3), 0.75);
+        assert_eq!(curve.undecided_at(5), 0.25);
+        assert_eq!(curve.longest_win(), 4);
+    }
+}
@@ -0,0 +1,245 @@
+use shakmaty::Position;
+
+use crate::game::{Color, GiveEvals, GivePlayers, GiveResult, ListMoves, PlayerResult};
+use crate::moves::Move;
+use crate::position;
+use crate::stats::Score;
+
+/// How many points of material a player must be down for their position to count as lost when
+/// a game has no evaluations, which is about a rook.
+const DECISIVE_MATERIAL: i32 = 5;
+
+/// How many positions in a row a material deficit must last to count, so that a piece that is
+/// about to be recaptured isn't counted as lost.
+const DEFICIT_PLIES: usize = 3;
+
+/// A game where a player came back from a lost position to win or draw.
+#[derive(Debug)]
+pub struct Comeback<'a, G> {
+    game: &'a G,
+    lost_from_move: usize,
+}
+
+impl<'a, G> Comeback<'a, G> {
+    /// Returns the game the comeback happened in.
+    pub fn game(&self) -> &'a G {
+        self.game
+    }
+
+    /// Returns the move number at which the player's position was first lost.
+    pub fn lost_from_move(&self) -> usize {
+        self.lost_from_move
+    }
+}
+
+/// How a player does from lost and winning positions.
+#[derive(Debug)]
+pub struct Swindles<'a, G> {
+    games: usize,
+    from_lost: Score,
+    from_winning: Score,
+    comebacks: Vec<Comeback<'a, G>>,
+}
+
+impl<'a, G> Swindles<'a, G> {
+    /// Returns the number of games the player played.
+    pub fn games(&self) -> usize {
+        self.games
+    }
+
+    /// Returns the player's score in games where their position was lost at some point.
+    pub fn from_lost(&self) -> Score {
+        self.from_lost
+    }
+
+    /// Returns the player's score in games where their position was winning at some point.
+    pub fn from_winning(&self) -> Score {
+        self.from_winning
+    }
+
+    /// Returns the fraction of lost positions the player went on to win or draw, or `None` if
+    /// they were never lost.
+    pub fn comeback_rate(&self) -> Option<f64> {
+        if self.from_lost.games() > 0 {
+            Some(self.comebacks.len() as f64 / self.from_lost.games() as f64)
+        } else {
+            None
+        }
+    }
+
+    /// Returns how resourceful the player was as the fraction of available points they scored
+    /// from lost positions, or `None` if they were never lost.
+    pub fn resourcefulness(&self) -> Option<f64> {
+        self.from_lost.rate()
+    }
+
+    /// Returns the fraction of winning positions the player went on to win, or `None` if they
+    /// were never winning.
+    pub fn conversion(&self) -> Option<f64> {
+        if self.from_winning.games() > 0 {
+            Some(self.from_winning.wins() as f64 / self.from_winning.games() as f64)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the games the player won or drew from a lost position, in the order they were
+    /// given.
+    pub fn comebacks(&self) -> &[Comeback<'a, G>] {
+        &self.comebacks
+    }
+}
+
+/// Returns the first ply after which `color`'s position in `game` was lost, judged by the
+/// game's evaluations if it has any, or else by `color` staying a decisive amount of material
+/// down.
+fn first_lost_ply<M, G>(game: &G, color: Color, threshold: u32) -> Option<usize>
+where
+    M: 'static + Clone + Move,
+    G: GiveEvals + ListMoves<M>,
+{
+    let evals = game.evals();
+
+    if evals.iter().any(Option::is_some) {
+        return evals
+            .iter()
+            .position(|eval| {
+                matches!(eval, Some(eval) if eval.centipawns_for(color) <= -(threshold as i32))
+            })
+            .map(|index| index + 1);
+    }
+
+    let down = position::replay(game.list_moves())
+        .iter()
+        .map(|position| position::material_balance(position.board(), color) <= -DECISIVE_MATERIAL)
+        .collect::<Vec<_>>();
+
+    down.windows(DEFICIT_PLIES)
+        .position(|window| window.iter().all(|down| *down))
+}
+
+/// Returns how `player` did from lost and winning positions in the games of `game_iter`. Games
+/// `player` didn't play in are ignored.
+///
+/// A position is lost once the evaluations recorded in the game show the player at least
+/// `threshold` centipawns down, or winning once they show the player at least `threshold`
+/// centipawns up. Games without evaluations are judged by material instead, with a player lost
+/// once they stay about a rook down.
+pub fn swindles<'a, M, G>(
+    game_iter: &mut dyn Iterator<Item = &'a G>,
+    player: &str,
+    threshold: u32,
+) -> Swindles<'a, G>
+where
+    M: 'static + Clone + Move,
+    G: 'a + GiveEvals + GivePlayers + GiveResult + ListMoves<M>,
+{
+    let mut games = 0;
+    let mut from_lost = Score::new();
+    let mut from_winning = Score::new();
+    let mut comebacks = Vec::new();
+
+    for game in game_iter {
+        let color = match game.color_of(player) {
+            Some(color) => color,
+            None => continue,
+        };
+
+        let result = game.result().for_color(color);
+        games += 1;
+
+        if let Some(ply) = first_lost_ply(game, color, threshold) {
+            from_lost.add(result);
+
+            if result != PlayerResult::Lost {
+                comebacks.push(Comeback {
+                    game,
+                    // The position was lost by the move at index `ply - 1`.
+                    lost_from_move: (ply - 1) / 2 + 1,
+                });
+            }
+        }
+
+        if first_lost_ply(game, !color, threshold).is_some() {
+            from_winning.add(result);
+        }
+    }
+
+    Swindles {
+        games,
+        from_lost,
+        from_winning,
+        comebacks,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::swindles;
+    use crate::game::test_utils::*;
+    use crate::game::{Game, GivePlayers};
+    use crate::AlgebraicMove;
+
+    #[fixture]
+    fn games() -> Vec<Game<AlgebraicMove>> {
+        vec![
+            // alice is lost after 2... Nc6 but swindles a win.
+            parse_game(
+                "[White \"alice\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n\
+                 1. e4 { [%eval 0.3] } 1... e5 { [%eval 0.3] } \
+                 2. Nf3 { [%eval 0.3] } 2... Nc6 { [%eval -4.0] } 3. Bc4 { [%eval 2.0] } 1-0",
+            ),
+            // alice is winning but only draws.
+            parse_game(
+                "[White \"carol\"]\n[Black \"alice\"]\n[Result \"1/2-1/2\"]\n\
+                 1. d4 { [%eval 0.2] } 1... d5 { [%eval #-5] } 2. c4 { [%eval 0.0] } 1/2-1/2",
+            ),
+            // alice gives up her queen for nothing and loses, judged by material.
+            parse_game(
+                "[White \"alice\"]\n[Black \"dave\"]\n[Result \"0-1\"]\n\
+                 1. e4 e5 2. Qh5 Nf6 3. Qxf7+ Kxf7 4. d3 d6 5. Nf3 Be7 0-1",
+            ),
+            parse_game(
+                "[White \"erin\"]\n[Black \"alice\"]\n[Result \"1-0\"]\n\
+                 1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 1-0",
+            ),
+        ]
+    }
+
+    #[rstest]
+    fn swindles_should_split_lost_and_winning_positions(games: Vec<Game<AlgebraicMove>>) {
+        let stats = swindles(&mut games.iter(), "alice", 300);
+
+        assert_eq!(stats.games(), 4);
+        assert_eq!(stats.from_lost().wins(), 1);
+        assert_eq!(stats.from_lost().losses(), 1);
+        assert_eq!(stats.resourcefulness(), Some(0.5));
+        assert_eq!(stats.from_winning().draws(), 1);
+        assert_eq!(stats.conversion(), Some(0.));
+    }
+
+    #[rstest]
+    fn swindles_should_list_comebacks(games: Vec<Game<AlgebraicMove>>) {
+        let stats = swindles(&mut games.iter(), "alice", 300);
+
+        assert_eq!(stats.comebacks().len(), 1);
+        assert_eq!(stats.comebacks()[0].game().black_player(), Some("bob"));
+        assert_eq!(stats.comebacks()[0].lost_from_move(), 2);
+        assert_eq!(stats.comeback_rate(), Some(0.5));
+    }
+
+    #[rstest]
+    fn swindles_should_judge_material_without_evals() {
+        let game = parse_game(
+            "[White \"alice\"]\n[Black \"dave\"]\n[Result \"1-0\"]\n\
+             1. e4 e5 2. Qh5 Nc6 3. Qxf7+ Kxf7 4. Nf3 d6 5. d4 Nf6 1-0",
+        );
+
+        let stats = swindles(&mut std::iter::once(&game), "alice", 300);
+
+        assert_eq!(stats.from_lost().wins(), 1);
+        assert_eq!(stats.comebacks()[0].lost_from_move(), 3);
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use shakmaty::Position;
+
+use crate::game::{GameResult, GivePlayers, GiveResult, ListMoves};
+use crate::moves::Move;
+use crate::position;
+use crate::stats::Score;
+use crate::zobrist;
+
+/// How a game ended, as far as can be told from its moves and result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Termination {
+    /// The losing player was checkmated.
+    Checkmate,
+    /// The losing player resigned or ran out of time, which the moves alone can't tell apart.
+    Resignation,
+    /// The player to move had no legal moves but wasn't in check.
+    Stalemate,
+    /// Neither player had enough material left to checkmate.
+    InsufficientMaterial,
+    /// The final position had been reached three times.
+    Repetition,
+    /// The players agreed to a draw, or it was drawn for a reason the moves don't show.
+    Agreement,
+}
+
+impl fmt::Display for Termination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Termination::Checkmate => write!(f, "checkmate"),
+            Termination::Resignation => write!(f, "resignation or time"),
+            Termination::Stalemate => write!(f, "stalemate"),
+            Termination::InsufficientMaterial => write!(f, "insufficient material"),
+            Termination::Repetition => write!(f, "repetition"),
+            Termination::Agreement => write!(f, "agreement"),
+        }
+    }
+}
+
+/// Returns how `game` ended, judged from its final position. Games with an illegal move are
+/// judged from the position before it, so they count as resigned or agreed drawn.
+pub fn termination<M, G>(game: &G) -> Termination
+where
+    M: 'static + Clone + Move,
+    G: GiveResult + ListMoves<M>,
+{
+    let positions = position::replay(game.list_moves());
+    let last = positions.last().unwrap();
+
+    match game.result() {
+        GameResult::Draw if last.is_stalemate() => Termination::Stalemate,
+        GameResult::Draw if last.is_insufficient_material() => Termination::InsufficientMaterial,
+        GameResult::Draw => {
+            let hash = zobrist::hash_position(last);
+            let repetitions = positions
+                .iter()
+                .filter(|position| zobrist::hash_position(position) == hash)
+                .count();
+
+            if repetitions >= 3 {
+                Termination::Repetition
+            } else {
+                Termination::Agreement
+            }
+        }
+        _ if last.is_checkmate() => Termination::Checkmate,
+        _ => Termination::Resignation,
+    }
+}
+
+/// Returns `player`'s score in the games of `game_iter` that ended in each way, such as their
+/// wins and losses by checkmate. Games `player` didn't play in are ignored.
+pub fn terminations<'a, M, G>(
+    game_iter: &mut dyn Iterator<Item = &'a G>,
+    player: &str,
+) -> BTreeMap<Termination, Score>
+where
+    M: 'static + Clone + Move,
+    G: 'a + GivePlayers + GiveResult + ListMoves<M>,
+{
+    let mut scores: BTreeMap<Termination, Score> = BTreeMap::new();
+
+    for game in game_iter {
+        if let Some(color) = game.color_of(player) {
+            scores
+                .entry(termination(game))
+                .or_default()
+                .add(game.result().for_color(color));
+        }
+    }
+
+    scores
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::{termination, terminations, Termination};
+    use crate::game::test_utils::*;
+
+    #[rstest(
+        result,
+        moves,
+        expected,
+        case("0-1", "1. f3 e5 2. g4 Qh4#", Termination::Checkmate),
+        case("1-0", "1. e4 e5 2. Qh5", Termination::Resignation),
+        case(
+            "1/2-1/2",
+            "1. Nf3 Nf6 2. Ng1 Ng8 3. Nf3 Nf6 4. Ng1 Ng8",
+            Termination::Repetition
+        ),
+        case("1/2-1/2", "1. e4 e5 2. Nf3 Nf6", Termination::Agreement),
+        case(
+            "1/2-1/2",
+            "1. e3 a5 2. Qh5 Ra6 3. Qxa5 h5 4. h4 Rah6 5. Qxc7 f6 6. Qxd7+ Kf7 7. Qxb7 Qd3 \
+             8. Qxb8 Qh7 9. Qxc8 Kg6 10. Qe6",
+            Termination::Stalemate
+        )
+    )]
+    fn termination_should_tell_how_games_ended(result: &str, moves: &str, expected: Termination) {
+        assert_eq!(termination(&game("a", "b", result, moves)), expected);
+    }
+
+    #[rstest]
+    fn terminations_should_score_each_way_games_ended() {
+        let games = [
+            game("alice", "bob", "0-1", "1. f3 e5 2. g4 Qh4#"),
+            game("bob", "alice", "0-1", "1. f3 e5 2. g4 Qh4#"),
+            game("alice", "bob", "1-0", "1. e4 e5"),
+            game("carol", "bob", "1-0", "1. d4 d5"),
+        ];
+
+        let scores = terminations(&mut games.iter(), "alice");
+
+        assert_eq!(
+            scores.keys().collect::<Vec<_>>(),
+            vec![&Termination::Checkmate, &Termination::Resignation]
+        );
+        assert_eq!(scores[&Termination::Checkmate].wins(), 1);
+        assert_eq!(scores[&Termination::Checkmate].losses(), 1);
+        assert_eq!(scores[&Termination::Resignation].wins(), 1);
+    }
+}
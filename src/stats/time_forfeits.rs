@@ -0,0 +1,241 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use shakmaty::Position;
+
+use crate::clock::TimeControl;
+use crate::game::{
+    Color, GameResult, GiveClocks, GiveEvals, GiveMetadata, GivePlayers, GiveResult, ListMoves,
+};
+use crate::moves::Move;
+use crate::position;
+
+/// How many points of material a player must be up at the end of a game for their position to
+/// count as winning when the game has no final evaluation, which is about a piece.
+const WINNING_MATERIAL: i32 = 3;
+
+/// The most time a player can have left after their last move for a game without a Termination
+/// tag, lost by them with them to move, to count as lost on time.
+const FLAG_CLOCK: Duration = Duration::from_secs(3);
+
+/// How a player's games with one time control ended on time, and what their position was like
+/// when they did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeForfeits {
+    time_control: Option<TimeControl>,
+    games: usize,
+    lost_on_time: usize,
+    lost_on_time_winning: usize,
+    won_on_time: usize,
+    won_on_time_losing: usize,
+}
+
+impl TimeForfeits {
+    fn new(time_control: Option<TimeControl>) -> TimeForfeits {
+        TimeForfeits {
+            time_control,
+            games: 0,
+            lost_on_time: 0,
+            lost_on_time_winning: 0,
+            won_on_time: 0,
+            won_on_time_losing: 0,
+        }
+    }
+
+    /// Returns the time control the games were played with, or `None` for games without a known
+    /// time control.
+    pub fn time_control(&self) -> Option<TimeControl> {
+        self.time_control
+    }
+
+    /// Returns the number of games the player played with the time control.
+    pub fn games(&self) -> usize {
+        self.games
+    }
+
+    /// Returns the number of games the player lost on time.
+    pub fn lost_on_time(&self) -> usize {
+        self.lost_on_time
+    }
+
+    /// Returns the number of games the player lost on time from a winning position.
+    pub fn lost_on_time_winning(&self) -> usize {
+        self.lost_on_time_winning
+    }
+
+    /// Returns the number of games the player won on time.
+    pub fn won_on_time(&self) -> usize {
+        self.won_on_time
+    }
+
+    /// Returns the number of games the player won on time from a losing position.
+    pub fn won_on_time_losing(&self) -> usize {
+        self.won_on_time_losing
+    }
+}
+
+/// Returns whether `game` was lost on time by `loser`. Games are judged by their Termination tag
+/// when they have one, as lichess and chess.com write it. Otherwise, a game counts as lost on
+/// time if `loser` was to move in the final position, without being checkmated, with under
+/// three seconds left after their last move.
+pub(crate) fn lost_on_time<M, G>(game: &G, loser: Color) -> bool
+where
+    M: 'static + Clone + Move,
+    G: GiveClocks + GiveMetadata + ListMoves<M>,
+{
+    if let Some(termination) = game.metadata().termination() {
+        let termination = termination.to_lowercase();
+
+        return termination.contains("time forfeit") || termination.contains("on time");
+    }
+
+    let final_position = match position::replay(game.list_moves()).pop() {
+        Some(final_position) => final_position,
+        None => return false,
+    };
+
+    if final_position.turn() != loser || final_position.is_checkmate() {
+        return false;
+    }
+
+    // White's clock times are at even indices and black's at odd ones.
+    let parity = (loser == Color::Black) as usize;
+    let last_clock = game
+        .clocks()
+        .iter()
+        .enumerate()
+        .rev()
+        .find(|(ply, _)| ply % 2 == parity)
+        .and_then(|(_, clock)| *clock);
+
+    matches!(last_clock, Some(clock) if clock < FLAG_CLOCK)
+}
+
+/// Returns whether `color` was winning at the end of `game`, by at least `threshold` centipawns
+/// by the game's final evaluation if it has one, or else by a piece's worth of material.
+fn was_winning<M, G>(game: &G, color: Color, threshold: u32) -> bool
+where
+    M: 'static + Clone + Move,
+    G: GiveEvals + ListMoves<M>,
+{
+    match game.evals().last() {
+        Some(Some(eval)) => eval.centipawns_for(color) >= threshold as i32,
+        _ => position::replay(game.list_moves())
+            .last()
+            .is_some_and(|position| {
+                position::material_balance(position.board(), color) >= WINNING_MATERIAL
+            }),
+    }
+}
+
+/// Returns how the games `player` played among the games of `game_iter` ended on time, for each
+/// time control, with the most played first. Games lost on time count as lost while winning if
+/// the loser was at least `threshold` centipawns up by the game's final evaluation, or if it has
+/// none, a piece's worth of material up. Games `player` didn't play in are ignored.
+pub fn time_forfeits<'a, M, G>(
+    game_iter: &mut dyn Iterator<Item = &'a G>,
+    player: &str,
+    threshold: u32,
+) -> Vec<TimeForfeits>
+where
+    M: 'static + Clone + Move,
+    G: 'a + GiveClocks + GiveEvals + GiveMetadata + GivePlayers + GiveResult + ListMoves<M>,
+{
+    let mut forfeits: HashMap<Option<TimeControl>, TimeForfeits> = HashMap::new();
+
+    for game in game_iter {
+        let color = match game.color_of(player) {
+            Some(color) => color,
+            None => continue,
+        };
+
+        let time_control = game.time_control();
+        let entry = forfeits
+            .entry(time_control)
+            .or_insert_with(|| TimeForfeits::new(time_control));
+        entry.games += 1;
+
+        let loser = match game.result() {
+            GameResult::WhiteWon => Color::Black,
+            GameResult::BlackWon => Color::White,
+            GameResult::Draw => continue,
+        };
+
+        if !lost_on_time(game, loser) {
+            continue;
+        }
+
+        let loser_was_winning = was_winning(game, loser, threshold) as usize;
+
+        if loser == color {
+            entry.lost_on_time += 1;
+            entry.lost_on_time_winning += loser_was_winning;
+        } else {
+            entry.won_on_time += 1;
+            entry.won_on_time_losing += loser_was_winning;
+        }
+    }
+
+    let mut forfeits = forfeits.into_values().collect::<Vec<_>>();
+
+    forfeits.sort_by_key(|forfeits| {
+        (
+            Reverse(forfeits.games),
+            forfeits.time_control.is_none(),
+            forfeits
+                .time_control
+                .map(|time_control| (time_control.base(), time_control.increment())),
+        )
+    });
+
+    forfeits
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::time_forfeits;
+    use crate::game::test_utils::*;
+
+    #[rstest]
+    fn time_forfeits_should_judge_the_final_position() {
+        let games = [
+            // alice flags with a queen more.
+            parse_game(
+                "[White \"alice\"]\n[Black \"bob\"]\n[Result \"0-1\"]\n[TimeControl \"60+0\"]\n\
+                 [Termination \"Time forfeit\"]\n1. e4 d5 2. exd5 Nf6 3. Bb5+ c6 4. dxc6 Qb6 \
+                 5. cxb7+ Nc6 6. bxa8=Q 0-1",
+            ),
+            // bob flags with a lost position, going by the evaluation.
+            parse_game(
+                "[White \"bob\"]\n[Black \"alice\"]\n[Result \"0-1\"]\n[TimeControl \"60+0\"]\n\
+                 [Termination \"alice won on time\"]\n\
+                 1. e4 { [%eval 0.2] } 1... e5 { [%eval -5.0] } 0-1",
+            ),
+            // No Termination tag, but alice ran out of time to move with an equal position.
+            parse_game(
+                "[White \"carol\"]\n[Black \"alice\"]\n[Result \"1-0\"]\n[TimeControl \"180+2\"]\n\
+                 1. e4 { [%clk 0:02:50] } 1... e5 { [%clk 0:00:01] } \
+                 2. Nf3 { [%clk 0:02:40] } 1-0",
+            ),
+            parse_game(
+                "[White \"alice\"]\n[Black \"carol\"]\n[Result \"1-0\"]\n[TimeControl \"180+2\"]\n\
+                 [Termination \"Normal\"]\n1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0",
+            ),
+        ];
+
+        let forfeits = time_forfeits(&mut games.iter(), "alice", 300);
+
+        assert_eq!(forfeits.len(), 2);
+        assert_eq!(forfeits[0].time_control().unwrap().to_string(), "60+0");
+        assert_eq!(forfeits[0].lost_on_time(), 1);
+        assert_eq!(forfeits[0].lost_on_time_winning(), 1);
+        assert_eq!(forfeits[0].won_on_time(), 1);
+        assert_eq!(forfeits[0].won_on_time_losing(), 0);
+        assert_eq!(forfeits[1].games(), 2);
+        assert_eq!(forfeits[1].lost_on_time(), 1);
+        assert_eq!(forfeits[1].lost_on_time_winning(), 0);
+    }
+}
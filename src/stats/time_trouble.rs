@@ -0,0 +1,184 @@
+use std::time::Duration;
+
+use crate::game::{Color, GiveClocks, GivePlayers, GiveResult};
+use crate::stats::Score;
+
+/// The point at which a player is considered to be in time trouble.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeTroubleThreshold {
+    /// A fixed amount of time left on the clock.
+    Remaining(Duration),
+    /// A fraction of the starting time left on the clock. Games without a known time control
+    /// are ignored when using this threshold.
+    FractionOfBase(f64),
+}
+
+impl TimeTroubleThreshold {
+    fn for_game<G: GiveClocks>(&self, game: &G) -> Option<Duration> {
+        match self {
+            TimeTroubleThreshold::Remaining(remaining) => Some(*remaining),
+            TimeTroubleThreshold::FractionOfBase(fraction) => game
+                .time_control()
+                .map(|time_control| time_control.base().mul_f64(*fraction)),
+        }
+    }
+}
+
+/// How a player performs in games where they got into time trouble compared to games where they
+/// didn't.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeTrouble {
+    in_time_trouble: Score,
+    not_in_time_trouble: Score,
+    entry_moves: Vec<usize>,
+}
+
+impl TimeTrouble {
+    /// Returns the player's score in games where they got into time trouble.
+    pub fn in_time_trouble(&self) -> Score {
+        self.in_time_trouble
+    }
+
+    /// Returns the player's score in games where they never got into time trouble.
+    pub fn not_in_time_trouble(&self) -> Score {
+        self.not_in_time_trouble
+    }
+
+    /// Returns the move number at which the player got into time trouble in each game where they
+    /// did, in ascending order.
+    pub fn entry_moves(&self) -> &[usize] {
+        &self.entry_moves
+    }
+
+    /// Returns the median move number at which the player got into time trouble.
+    pub fn typical_entry_move(&self) -> Option<usize> {
+        self.entry_moves.get(self.entry_moves.len() / 2).copied()
+    }
+}
+
+/// Returns how `player` scored in the games of `game_iter` depending on whether their clock fell
+/// below `threshold`. Games `player` didn't play in or without any of their clock times recorded
+/// are ignored.
+pub fn time_trouble<'a, G>(
+    game_iter: &mut dyn Iterator<Item = &'a G>,
+    player: &str,
+    threshold: TimeTroubleThreshold,
+) -> TimeTrouble
+where
+    G: 'a + GiveClocks + GivePlayers + GiveResult,
+{
+    let mut in_time_trouble = Score::new();
+    let mut not_in_time_trouble = Score::new();
+    let mut entry_moves = Vec::new();
+
+    for game in game_iter {
+        let color = match game.color_of(player) {
+            Some(color) => color,
+            None => continue,
+        };
+
+        let threshold = match threshold.for_game(game) {
+            Some(threshold) => threshold,
+            None => continue,
+        };
+
+        let first_ply = match color {
+            Color::White => 0,
+            Color::Black => 1,
+        };
+
+        let mut player_clocks = game
+            .clocks()
+            .iter()
+            .enumerate()
+            .skip(first_ply)
+            .step_by(2)
+            .filter_map(|(ply, clock)| clock.map(|clock| (ply, clock)))
+            .peekable();
+
+        if player_clocks.peek().is_none() {
+            continue;
+        }
+
+        let result = game.result().for_color(color);
+
+        match player_clocks.find(|(_, clock)| *clock < threshold) {
+            Some((ply, _)) => {
+                in_time_trouble.add(result);
+                entry_moves.push(ply / 2 + 1);
+            }
+            None => not_in_time_trouble.add(result),
+        }
+    }
+
+    entry_moves.sort_unstable();
+
+    TimeTrouble {
+        in_time_trouble,
+        not_in_time_trouble,
+        entry_moves,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use std::time::Duration;
+
+    use super::{time_trouble, TimeTroubleThreshold};
+    use crate::game::test_utils::*;
+    use crate::game::Game;
+    use crate::AlgebraicMove;
+
+    #[fixture]
+    fn timed_games() -> Vec<Game<AlgebraicMove>> {
+        vec![
+            parse_game(
+                "[White \"alice\"]\n[Black \"bob\"]\n[Result \"0-1\"]\n[TimeControl \"60+0\"]\n\
+                 1. e4 { [%clk 0:00:30] } 1... e5 { [%clk 0:00:59] } \
+                 2. Nf3 { [%clk 0:00:05] } 2... Nc6 { [%clk 0:00:55] } 0-1",
+            ),
+            parse_game(
+                "[White \"bob\"]\n[Black \"alice\"]\n[Result \"0-1\"]\n[TimeControl \"60+0\"]\n\
+                 1. d4 { [%clk 0:00:50] } 1... d5 { [%clk 0:00:40] } \
+                 2. c4 { [%clk 0:00:45] } 2... e6 { [%clk 0:00:20] } 0-1",
+            ),
+            parse_game(
+                "[White \"alice\"]\n[Black \"bob\"]\n[Result \"1/2-1/2\"]\n\
+                 1. c4 { [%clk 0:00:08] } 1... c5 { [%clk 0:00:30] } 1/2-1/2",
+            ),
+            parse_game("[White \"alice\"]\n[Black \"carol\"]\n[Result \"1-0\"]\n1. e4 e5 1-0"),
+        ]
+    }
+
+    #[rstest]
+    fn time_trouble_should_split_by_remaining_time(timed_games: Vec<Game<AlgebraicMove>>) {
+        let trouble = time_trouble(
+            &mut timed_games.iter(),
+            "alice",
+            TimeTroubleThreshold::Remaining(Duration::from_secs(10)),
+        );
+
+        assert_eq!(trouble.in_time_trouble().losses(), 1);
+        assert_eq!(trouble.in_time_trouble().draws(), 1);
+        assert_eq!(trouble.not_in_time_trouble().wins(), 1);
+        assert_eq!(trouble.entry_moves(), &[1, 2]);
+        assert_eq!(trouble.typical_entry_move(), Some(2));
+    }
+
+    #[rstest]
+    fn time_trouble_should_ignore_unknown_time_controls_for_fractions(
+        timed_games: Vec<Game<AlgebraicMove>>,
+    ) {
+        let trouble = time_trouble(
+            &mut timed_games.iter(),
+            "alice",
+            TimeTroubleThreshold::FractionOfBase(0.5),
+        );
+
+        assert_eq!(trouble.in_time_trouble().games(), 2);
+        assert_eq!(trouble.not_in_time_trouble().games(), 0);
+        assert_eq!(trouble.entry_moves(), &[2, 2]);
+    }
+}
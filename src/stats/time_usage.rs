@@ -0,0 +1,263 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::Duration;
+
+use crate::clock;
+use crate::game::{Color, GiveClocks, GivePlayers, ListMoves};
+use crate::moves::Move;
+use crate::phase::{self, Phase};
+
+/// How a player used their clock across a set of games.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeUsage<M: Move> {
+    average_move_time: Option<Duration>,
+    average_phase_times: HashMap<Phase, Duration>,
+    average_clock_at_move_40: Option<Duration>,
+    average_clock_at_end: Option<Duration>,
+    lines: Vec<LineTimeUsage<M>>,
+}
+
+impl<M: Move> TimeUsage<M> {
+    /// Returns the average time the player spent per move.
+    pub fn average_move_time(&self) -> Option<Duration> {
+        self.average_move_time
+    }
+
+    /// Returns the average time the player spent per move in `phase`.
+    pub fn average_phase_time(&self, phase: Phase) -> Option<Duration> {
+        self.average_phase_times.get(&phase).copied()
+    }
+
+    /// Returns the average time the player had left after their 40th move, in games that lasted
+    /// that long.
+    pub fn average_clock_at_move_40(&self) -> Option<Duration> {
+        self.average_clock_at_move_40
+    }
+
+    /// Returns the average time the player had left after their last move.
+    pub fn average_clock_at_end(&self) -> Option<Duration> {
+        self.average_clock_at_end
+    }
+
+    /// Returns the time usage for each opening line, with the lines the player spends the most
+    /// time per move in first.
+    pub fn lines(&self) -> &[LineTimeUsage<M>] {
+        &self.lines
+    }
+}
+
+/// How a player used their clock in games starting with a particular line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineTimeUsage<M: Move> {
+    moves: Vec<M>,
+    games: usize,
+    average_move_time: Duration,
+}
+
+impl<M: Move> LineTimeUsage<M> {
+    /// Returns the moves that make up this line.
+    pub fn moves(&self) -> &[M] {
+        &self.moves
+    }
+
+    /// Returns the number of games with clock data that started with this line.
+    pub fn games(&self) -> usize {
+        self.games
+    }
+
+    /// Returns the average time the player spent per move in games starting with this line.
+    pub fn average_move_time(&self) -> Duration {
+        self.average_move_time
+    }
+}
+
+#[derive(Default)]
+struct Average {
+    total: Duration,
+    count: u32,
+}
+
+impl Average {
+    fn add(&mut self, duration: Duration) {
+        self.total += duration;
+        self.count += 1;
+    }
+
+    fn mean(&self) -> Option<Duration> {
+        if self.count > 0 {
+            Some(self.total / self.count)
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns how `player` used their clock in the games of `game_iter`, using the `[%clk]` data
+/// recorded in each game. Games `player` didn't play in are ignored.
+///
+/// Opening lines are identified by the first `line_length` moves of each game.
+pub fn time_usage<'a, M, G>(
+    game_iter: &mut dyn Iterator<Item = &'a G>,
+    player: &str,
+    line_length: usize,
+) -> TimeUsage<M>
+where
+    M: 'static + Clone + Move + Eq + Hash,
+    G: 'a + GiveClocks + GivePlayers + ListMoves<M>,
+{
+    let mut move_time = Average::default();
+    let mut phase_times: HashMap<Phase, Average> = HashMap::new();
+    let mut clock_at_move_40 = Average::default();
+    let mut clock_at_end = Average::default();
+    let mut lines: HashMap<Vec<M>, (usize, Average)> = HashMap::new();
+
+    for game in game_iter {
+        let color = match game.color_of(player) {
+            Some(color) => color,
+            None => continue,
+        };
+
+        let moves = game.list_moves().collect::<Vec<_>>();
+        let clocks = game.clocks();
+        let move_times = clock::move_times(clocks, game.time_control());
+        let phases = phase::phases(moves.clone());
+
+        let first_ply = match color {
+            Color::White => 0,
+            Color::Black => 1,
+        };
+
+        let mut line_time = Average::default();
+
+        for ply in (first_ply..move_times.len()).step_by(2) {
+            if let Some(time) = move_times[ply] {
+                move_time.add(time);
+                line_time.add(time);
+
+                if let Some(phase) = phases.get(ply) {
+                    phase_times.entry(*phase).or_default().add(time);
+                }
+            }
+        }
+
+        if let Some(Some(clock)) = clocks.get(78 + first_ply) {
+            clock_at_move_40.add(*clock);
+        }
+
+        let last_clock = clocks
+            .iter()
+            .skip(first_ply)
+            .step_by(2)
+            .filter_map(|clock| *clock)
+            .next_back();
+
+        if let Some(clock) = last_clock {
+            clock_at_end.add(clock);
+        }
+
+        if line_time.count > 0 {
+            let line = moves.into_iter().take(line_length).collect::<Vec<_>>();
+            let (games, average) = lines.entry(line).or_default();
+
+            *games += 1;
+            average.total += line_time.total;
+            average.count += line_time.count;
+        }
+    }
+
+    let mut lines = lines
+        .into_iter()
+        .map(|(moves, (games, average))| LineTimeUsage {
+            moves,
+            games,
+            average_move_time: average.mean().unwrap(),
+        })
+        .collect::<Vec<_>>();
+
+    lines.sort_by_key(|line| Reverse(line.average_move_time));
+
+    TimeUsage {
+        average_move_time: move_time.mean(),
+        average_phase_times: phase_times
+            .into_iter()
+            .filter_map(|(phase, average)| average.mean().map(|mean| (phase, mean)))
+            .collect(),
+        average_clock_at_move_40: clock_at_move_40.mean(),
+        average_clock_at_end: clock_at_end.mean(),
+        lines,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use std::time::Duration;
+
+    use super::time_usage;
+    use crate::game::test_utils::*;
+    use crate::game::Game;
+    use crate::moves::Move;
+    use crate::phase::Phase;
+    use crate::AlgebraicMove;
+
+    fn seconds(seconds: u64) -> Duration {
+        Duration::from_secs(seconds)
+    }
+
+    #[fixture]
+    fn timed_games() -> Vec<Game<AlgebraicMove>> {
+        vec![
+            parse_game(
+                "[White \"alice\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n[TimeControl \"60+0\"]\n\
+                 1. e4 { [%clk 0:00:58] } 1... e5 { [%clk 0:00:59] } \
+                 2. Nf3 { [%clk 0:00:50] } 2... Nc6 { [%clk 0:00:55] } 1-0",
+            ),
+            parse_game(
+                "[White \"bob\"]\n[Black \"alice\"]\n[Result \"0-1\"]\n[TimeControl \"60+1\"]\n\
+                 1. d4 { [%clk 0:00:60] } 1... d5 { [%clk 0:00:57] } \
+                 2. c4 { [%clk 0:00:55] } 2... e6 { [%clk 0:00:50] } 0-1",
+            ),
+            parse_game(
+                "[White \"bob\"]\n[Black \"carol\"]\n[Result \"0-1\"]\n[TimeControl \"60+0\"]\n\
+                 1. d4 { [%clk 0:00:30] } 1... d5 { [%clk 0:00:30] } 0-1",
+            ),
+        ]
+    }
+
+    #[rstest]
+    fn time_usage_should_average_player_moves(timed_games: Vec<Game<AlgebraicMove>>) {
+        let usage = time_usage(&mut timed_games.iter(), "alice", 2);
+
+        // alice spent 2s and 8s as white, then 4s and 8s as black.
+        assert_eq!(usage.average_move_time(), Some(seconds(22) / 4));
+        assert_eq!(
+            usage.average_phase_time(Phase::Opening),
+            Some(seconds(22) / 4)
+        );
+        assert_eq!(usage.average_phase_time(Phase::Endgame), None);
+        assert_eq!(usage.average_clock_at_move_40(), None);
+        assert_eq!(usage.average_clock_at_end(), Some(seconds(50)));
+    }
+
+    #[rstest]
+    fn time_usage_should_rank_lines_by_time_spent(timed_games: Vec<Game<AlgebraicMove>>) {
+        let usage = time_usage(&mut timed_games.iter(), "alice", 2);
+
+        let lines = usage
+            .lines()
+            .iter()
+            .map(|line| {
+                line.moves()
+                    .iter()
+                    .map(|move_| move_.clone().to_algebraic())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(lines, vec![vec!["d4", "d5"], vec!["e4", "e5"]]);
+        assert_eq!(usage.lines()[0].average_move_time(), seconds(6));
+        assert_eq!(usage.lines()[1].average_move_time(), seconds(5));
+    }
+}
@@ -0,0 +1,212 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use shakmaty::Chess;
+
+use crate::game::{Color, GameResult, GivePlayers, GiveResult, ListMoves};
+use crate::moves::Move;
+use crate::position;
+
+/// A position short decisive games kept reaching just before they were decided, with how often a
+/// player sprang it or fell into it.
+#[derive(Debug, Clone)]
+pub struct Trap {
+    line: Vec<String>,
+    position: Chess,
+    reply: Option<String>,
+    sprung: usize,
+    fallen_into: usize,
+}
+
+impl Trap {
+    /// Returns the moves leading to the trap in SAN, in the order of the first game found to
+    /// reach it.
+    pub fn line(&self) -> &[String] {
+        &self.line
+    }
+
+    /// Returns the critical position, reached by the losing side's last move.
+    pub fn position(&self) -> &Chess {
+        &self.position
+    }
+
+    /// Returns the critical position in FEN.
+    pub fn fen(&self) -> String {
+        position::fen(&self.position)
+    }
+
+    /// Returns the move the winning side most often answered with in SAN, or `None` if the losing
+    /// side never saw an answer, such as when they resigned or ran out of time right away.
+    pub fn reply(&self) -> Option<&str> {
+        self.reply.as_deref()
+    }
+
+    /// Returns the number of games the player won by the trap.
+    pub fn sprung(&self) -> usize {
+        self.sprung
+    }
+
+    /// Returns the number of games the player lost to the trap.
+    pub fn fallen_into(&self) -> usize {
+        self.fallen_into
+    }
+
+    /// Returns the number of games the player reached the trap in.
+    pub fn games(&self) -> usize {
+        self.sprung + self.fallen_into
+    }
+}
+
+/// Returns the traps in the miniatures `player` played among the games of `game_iter`, with the
+/// most common first. A miniature is a decisive game of at most `max_moves` moves, and its trap
+/// is the position after the losing side's last move, so miniatures reaching the same position by
+/// different move orders count as the same trap. Games `player` didn't play in are ignored.
+pub fn traps<'a, M, G>(
+    game_iter: &mut dyn Iterator<Item = &'a G>,
+    player: &str,
+    max_moves: usize,
+) -> Vec<Trap>
+where
+    M: 'static + Clone + Move,
+    G: 'a + GivePlayers + GiveResult + ListMoves<M>,
+{
+    let mut traps: HashMap<String, (Trap, HashMap<String, usize>)> = HashMap::new();
+
+    for game in game_iter {
+        let color = match game.color_of(player) {
+            Some(color) => color,
+            None => continue,
+        };
+
+        let loser = match game.result() {
+            GameResult::WhiteWon => Color::Black,
+            GameResult::BlackWon => Color::White,
+            _ => continue,
+        };
+
+        let moves = game.list_moves().collect::<Vec<_>>();
+
+        if moves.len() > 2 * max_moves {
+            continue;
+        }
+
+        // White's moves are at even indices and black's at odd ones.
+        let parity = (loser == Color::Black) as usize;
+        let last_move = match (0..moves.len()).rev().find(|ply| ply % 2 == parity) {
+            Some(last_move) => last_move,
+            None => continue,
+        };
+
+        let positions = position::replay(moves.iter().cloned());
+
+        // Games with illegal moves before the trap have no position to give.
+        let position = match positions.get(last_move + 1) {
+            Some(position) => position.clone(),
+            None => continue,
+        };
+
+        let (trap, replies) = traps.entry(position::epd(&position)).or_insert_with(|| {
+            let trap = Trap {
+                line: moves[..=last_move]
+                    .iter()
+                    .map(|move_| move_.clone().to_algebraic())
+                    .collect(),
+                position,
+                reply: None,
+                sprung: 0,
+                fallen_into: 0,
+            };
+
+            (trap, HashMap::new())
+        });
+
+        if color == loser {
+            trap.fallen_into += 1;
+        } else {
+            trap.sprung += 1;
+        }
+
+        if let Some(reply) = moves.get(last_move + 1) {
+            *replies.entry(reply.clone().to_algebraic()).or_default() += 1;
+        }
+    }
+
+    let mut traps = traps
+        .into_values()
+        .map(|(mut trap, replies)| {
+            trap.reply = replies
+                .into_iter()
+                .max_by(|(reply_a, count_a), (reply_b, count_b)| {
+                    count_a.cmp(count_b).then_with(|| reply_b.cmp(reply_a))
+                })
+                .map(|(reply, _)| reply);
+            trap
+        })
+        .collect::<Vec<_>>();
+
+    traps.sort_by(|a, b| {
+        Reverse(a.games())
+            .cmp(&Reverse(b.games()))
+            .then_with(|| a.line.cmp(&b.line))
+    });
+
+    traps
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::traps;
+    use crate::game::test_utils::*;
+
+    #[rstest]
+    fn traps_should_group_miniatures_by_critical_position() {
+        let games = [
+            game(
+                "alice",
+                "bob",
+                "1-0",
+                "1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6 4. Qxf7#",
+            ),
+            game(
+                "alice",
+                "carol",
+                "1-0",
+                "1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7#",
+            ),
+            game("alice", "dave", "0-1", "1. f3 e5 2. g4 Qh4#"),
+            game("alice", "dave", "1/2-1/2", "1. f3 e5 2. g4 Qh4"),
+            game(
+                "erin",
+                "frank",
+                "1-0",
+                "1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6 4. Qxf7#",
+            ),
+            game(
+                "alice",
+                "bob",
+                "1-0",
+                "1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6 4. Qxf7#",
+            ),
+        ];
+
+        let found = traps(&mut games.iter(), "alice", 4);
+
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].line().join(" "), "e4 e5 Bc4 Nc6 Qh5 Nf6");
+        assert_eq!(found[0].sprung(), 3);
+        assert_eq!(found[0].fallen_into(), 0);
+        assert_eq!(found[0].reply(), Some("Qxf7#"));
+        assert_eq!(found[1].line().join(" "), "f3 e5 g4");
+        assert_eq!(found[1].fallen_into(), 1);
+        assert_eq!(
+            found[1].fen(),
+            "rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2"
+        );
+
+        assert!(traps(&mut games.iter(), "alice", 3)
+            .iter()
+            .all(|trap| trap.line().len() == 3));
+    }
+}
@@ -0,0 +1,339 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::game::{
+    GiveDate, GiveLink, GivePlayers, GiveRatings, GiveResult, ListMoves, PlayerResult,
+};
+use crate::game_id::GameId;
+use crate::moves::Move;
+use crate::stats::Score;
+
+/// A game where the lower-rated player won.
+#[derive(Debug)]
+pub struct Upset<'a, G> {
+    game: &'a G,
+    rating_gap: u16,
+}
+
+impl<'a, G> Upset<'a, G> {
+    /// Returns the game the upset happened in.
+    pub fn game(&self) -> &'a G {
+        self.game
+    }
+
+    /// Returns how much higher rated the losing player was than the winner.
+    pub fn rating_gap(&self) -> u16 {
+        self.rating_gap
+    }
+}
+
+/// A player's score against stronger and weaker opponents in games starting with a particular
+/// line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineUpsets<M: Move> {
+    moves: Vec<M>,
+    against_higher_rated: Score,
+    against_lower_rated: Score,
+}
+
+impl<M: Move> LineUpsets<M> {
+    /// Returns the moves that make up this line.
+    pub fn moves(&self) -> &[M] {
+        &self.moves
+    }
+
+    /// Returns the player's score against higher-rated opponents in this line.
+    pub fn against_higher_rated(&self) -> Score {
+        self.against_higher_rated
+    }
+
+    /// Returns the player's score against lower-rated opponents in this line.
+    pub fn against_lower_rated(&self) -> Score {
+        self.against_lower_rated
+    }
+}
+
+/// How a player performs against opponents rated above and below them.
+#[derive(Debug)]
+pub struct Upsets<'a, M: Move, G> {
+    against_higher_rated: Score,
+    against_lower_rated: Score,
+    against_equal_rated: Score,
+    biggest_wins: Vec<Upset<'a, G>>,
+    biggest_losses: Vec<Upset<'a, G>>,
+    lines: Vec<LineUpsets<M>>,
+}
+
+impl<'a, M: Move, G> Upsets<'a, M, G> {
+    /// Returns the player's score against higher-rated opponents.
+    pub fn against_higher_rated(&self) -> Score {
+        self.against_higher_rated
+    }
+
+    /// Returns the player's score against lower-rated opponents.
+    pub fn against_lower_rated(&self) -> Score {
+        self.against_lower_rated
+    }
+
+    /// Returns the player's score against opponents with exactly the same rating.
+    pub fn against_equal_rated(&self) -> Score {
+        self.against_equal_rated
+    }
+
+    /// Returns the player's wins against higher-rated opponents, largest rating gap first. Wins
+    /// with the same gap are in the order they were played, then by [`GameId`].
+    pub fn biggest_wins(&self) -> &[Upset<'a, G>] {
+        &self.biggest_wins
+    }
+
+    /// Returns the player's losses to lower-rated opponents, largest rating gap first, ordered
+    /// like [`biggest_wins`](Upsets::biggest_wins) when gaps are the same.
+    pub fn biggest_losses(&self) -> &[Upset<'a, G>] {
+        &self.biggest_losses
+    }
+
+    /// Returns the player's scores against stronger and weaker opponents for each opening line,
+    /// with the most frequently played lines first and equally played lines in order of their
+    /// moves.
+    pub fn lines(&self) -> &[LineUpsets<M>] {
+        &self.lines
+    }
+}
+
+/// Returns how `player` scored against higher and lower-rated opponents in the games of
+/// `game_iter`, keeping the `limit` biggest upsets they won and lost. Games `player` didn't play
+/// in, or where either rating is unknown, are ignored.
+///
+/// Opening lines are identified by the first `line_length` moves of each game.
+pub fn upsets<'a, M, G>(
+    game_iter: &mut dyn Iterator<Item = &'a G>,
+    player: &str,
+    line_length: usize,
+    limit: usize,
+) -> Upsets<'a, M, G>
+where
+    M: 'static + Clone + Move + Eq + Hash,
+    G: 'a + GiveDate + GiveLink + GivePlayers + GiveRatings + GiveResult + ListMoves<M>,
+{
+    let mut against_higher_rated = Score::new();
+    let mut against_lower_rated = Score::new();
+    let mut against_equal_rated = Score::new();
+    let mut biggest_wins = Vec::new();
+    let mut biggest_losses = Vec::new();
+    let mut lines: HashMap<Vec<M>, (Score, Score)> = HashMap::new();
+
+    for game in game_iter {
+        let color = match game.color_of(player) {
+            Some(color) => color,
+            None => continue,
+        };
+
+        let (rating, opponent_rating) = match (game.rating_of(color), game.rating_of(!color)) {
+            (Some(rating), Some(opponent_rating)) => (rating, opponent_rating),
+            _ => continue,
+        };
+
+        let result = game.result().for_color(color);
+        let line = game.list_moves().take(line_length).collect::<Vec<_>>();
+        let (line_higher_rated, line_lower_rated) = lines.entry(line).or_default();
+
+        match opponent_rating.cmp(&rating) {
+            Ordering::Greater => {
+                against_higher_rated.add(result);
+                line_higher_rated.add(result);
+
+                if result == PlayerResult::Won {
+                    biggest_wins.push(Upset {
+                        game,
+                        rating_gap: opponent_rating - rating,
+                    });
+                }
+            }
+            Ordering::Less => {
+                against_lower_rated.add(result);
+                line_lower_rated.add(result);
+
+                if result == PlayerResult::Lost {
+                    biggest_losses.push(Upset {
+                        game,
+                        rating_gap: rating - opponent_rating,
+                    });
+                }
+            }
+            Ordering::Equal => against_equal_rated.add(result),
+        }
+    }
+
+    let biggest_first = |upset: &Upset<G>| {
+        (
+            Reverse(upset.rating_gap),
+            upset.game.date(),
+            GameId::of(upset.game),
+        )
+    };
+
+    biggest_wins.sort_by_cached_key(biggest_first);
+    biggest_wins.truncate(limit);
+    biggest_losses.sort_by_cached_key(biggest_first);
+    biggest_losses.truncate(limit);
+
+    let mut lines = lines
+        .into_iter()
+        .map(
+            |(moves, (against_higher_rated, against_lower_rated))| LineUpsets {
+                moves,
+                against_higher_rated,
+                against_lower_rated,
+            },
+        )
+        .filter(|line| line.against_higher_rated.games() + line.against_lower_rated.games() > 0)
+        .collect::<Vec<_>>();
+
+    lines.sort_by_cached_key(|line| {
+        let san = line
+            .moves
+            .iter()
+            .map(|move_| move_.clone().to_algebraic())
+            .collect::<Vec<_>>();
+
+        (
+            Reverse(line.against_higher_rated.games() + line.against_lower_rated.games()),
+            san,
+        )
+    });
+
+    Upsets {
+        against_higher_rated,
+        against_lower_rated,
+        against_equal_rated,
+        biggest_wins,
+        biggest_losses,
+        lines,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::upsets;
+    use crate::game::test_utils::*;
+    use crate::game::{Game, GivePlayers};
+    use crate::moves::Move;
+    use crate::AlgebraicMove;
+
+    fn rated_game(white: (&str, u16), black: (&str, u16), result: &str, moves: &str) -> String {
+        format!(
+            "[White \"{}\"]\n[Black \"{}\"]\n[Result \"{}\"]\n[WhiteElo \"{}\"]\n\
+             [BlackElo \"{}\"]\n{} {}",
+            white.0, black.0, result, white.1, black.1, moves, result
+        )
+    }
+
+    #[fixture]
+    fn rated_games() -> Vec<Game<AlgebraicMove>> {
+        vec![
+            parse_game(&rated_game(
+                ("alice", 1500),
+                ("bob", 1700),
+                "1-0",
+                "1. e4 e5",
+            )),
+            parse_game(&rated_game(
+                ("carol", 1900),
+                ("alice", 1500),
+                "0-1",
+                "1. d4 d5",
+            )),
+            parse_game(&rated_game(
+                ("alice", 1500),
+                ("dave", 1300),
+                "0-1",
+                "1. e4 c5",
+            )),
+            parse_game(&rated_game(
+                ("erin", 1400),
+                ("alice", 1500),
+                "1/2-1/2",
+                "1. e4 e5",
+            )),
+            parse_game(&rated_game(
+                ("alice", 1500),
+                ("frank", 1500),
+                "1-0",
+                "1. e4 e5",
+            )),
+            parse_game("[White \"alice\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n1. e4 1-0"),
+        ]
+    }
+
+    #[rstest]
+    fn upsets_should_split_scores_by_opponent_rating(rated_games: Vec<Game<AlgebraicMove>>) {
+        let upsets = upsets(&mut rated_games.iter(), "alice", 1, 5);
+
+        assert_eq!(upsets.against_higher_rated().wins(), 2);
+        assert_eq!(upsets.against_lower_rated().losses(), 1);
+        assert_eq!(upsets.against_lower_rated().draws(), 1);
+        assert_eq!(upsets.against_equal_rated().games(), 1);
+    }
+
+    #[rstest]
+    fn upsets_should_rank_biggest_upsets(rated_games: Vec<Game<AlgebraicMove>>) {
+        let upsets = upsets(&mut rated_games.iter(), "alice", 1, 1);
+
+        assert_eq!(upsets.biggest_wins().len(), 1);
+        assert_eq!(upsets.biggest_wins()[0].rating_gap(), 400);
+        assert_eq!(
+            upsets.biggest_wins()[0].game().white_player(),
+            Some("carol")
+        );
+        assert_eq!(upsets.biggest_losses()[0].rating_gap(), 200);
+    }
+
+    #[rstest]
+    fn upsets_should_break_down_by_line(rated_games: Vec<Game<AlgebraicMove>>) {
+        let upsets = upsets(&mut rated_games.iter(), "alice", 1, 5);
+
+        let e4 = &upsets.lines()[0];
+
+        assert_eq!(
+            e4.moves(),
+            &[AlgebraicMove::from_algebraic(String::from("e4"))]
+        );
+        assert_eq!(e4.against_higher_rated().wins(), 1);
+        assert_eq!(e4.against_lower_rated().games(), 2);
+        assert_eq!(upsets.lines().len(), 2);
+    }
+
+    #[rstest]
+    fn upsets_should_not_depend_on_the_order_of_games() {
+        let games = [
+            parse_game(&rated_game(
+                ("alice", 1500),
+                ("bob", 1700),
+                "1-0",
+                "1. e4 e5",
+            )),
+            parse_game(&rated_game(
+                ("alice", 1500),
+                ("carol", 1700),
+                "1-0",
+                "1. d4 d5",
+            )),
+        ];
+
+        let forwards = upsets(&mut games.iter(), "alice", 1, 1);
+        let backwards = upsets(&mut games.iter().rev(), "alice", 1, 1);
+
+        assert_eq!(
+            forwards.biggest_wins()[0].game().black_player(),
+            backwards.biggest_wins()[0].game().black_player()
+        );
+        assert_eq!(forwards.lines(), backwards.lines());
+        assert_eq!(
+            forwards.lines()[0].moves(),
+            &[AlgebraicMove::from_algebraic(String::from("d4"))]
+        );
+    }
+}
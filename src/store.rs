@@ -0,0 +1,283 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+use chrono::NaiveDate;
+use shakmaty::Setup;
+
+use crate::filter::GameFilter;
+use crate::game::{Game, GiveDate, GivePlayers};
+use crate::index::PositionIndex;
+use crate::move_tree::MoveTree;
+use crate::moves::Move;
+use crate::openings::Openings;
+use crate::player;
+
+/// A lightweight handle to a game held by a [`GameStore`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StoreId(u32);
+
+impl StoreId {
+    /// Returns the position of the game within the games the store was built from.
+    pub fn index(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// A set of games held once, with indexes by player, date, ECO code, and position, so that
+/// several views of the same games can be made without cloning them. Lookups hand out
+/// [`StoreId`]s, which can be narrowed further with a filter and then turned into references to
+/// the games, or into a move tree of them.
+///
+/// Lists of ids returned by a store are always in the order the games were given, so they can be
+/// combined with [`intersect`].
+///
+/// ```
+/// use chesshound::parsing::read_games;
+/// use chesshound::store::{intersect, GameStore};
+///
+/// let pgn = "[White \"alice\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n[Date \"2021.06.01\"]\n\n\
+///            1. e4 e5 1-0\n\n\
+///            [White \"bob\"]\n[Black \"carol\"]\n[Result \"0-1\"]\n[Date \"2021.05.01\"]\n\n\
+///            1. d4 d5 0-1\n";
+/// let store = GameStore::new(read_games(pgn.as_bytes()).unwrap());
+///
+/// let ids = intersect(&store.with_player("bob"), &store.with_eco("C"));
+/// let tree = store.move_tree(&ids);
+///
+/// assert_eq!(tree.view().iter().count(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct GameStore<M: Move> {
+    games: Vec<Game<M>>,
+    by_player: HashMap<String, Vec<StoreId>>,
+    by_date: BTreeMap<NaiveDate, Vec<StoreId>>,
+    by_eco: BTreeMap<String, Vec<StoreId>>,
+    positions: PositionIndex,
+}
+
+impl<M: 'static + Clone + Move> GameStore<M> {
+    /// Creates a store of `games`, indexing them as it goes.
+    pub fn new(games: Vec<Game<M>>) -> GameStore<M> {
+        let mut by_player: HashMap<String, Vec<StoreId>> = HashMap::new();
+        let mut by_date: BTreeMap<NaiveDate, Vec<StoreId>> = BTreeMap::new();
+        let mut by_eco: BTreeMap<String, Vec<StoreId>> = BTreeMap::new();
+        let openings = Openings::embedded();
+
+        for (index, game) in games.iter().enumerate() {
+            let id = StoreId(index as u32);
+            let white = game.white_player().map(player::name_key);
+            let black = game.black_player().map(player::name_key);
+
+            if let Some(white) = &white {
+                by_player.entry(white.clone()).or_default().push(id);
+            }
+
+            // A game someone played against themselves is only listed once for them.
+            if let Some(black) = black.filter(|black| Some(black) != white.as_ref()) {
+                by_player.entry(black).or_default().push(id);
+            }
+
+            if let Some(date) = game.date() {
+                by_date.entry(date).or_default().push(id);
+            }
+
+            if let Some(opening) = openings.classify(game) {
+                by_eco
+                    .entry(String::from(opening.eco()))
+                    .or_default()
+                    .push(id);
+            }
+        }
+
+        let positions = PositionIndex::new(&games);
+
+        GameStore {
+            games,
+            by_player,
+            by_date,
+            by_eco,
+            positions,
+        }
+    }
+
+    /// Returns the number of games in the store.
+    pub fn len(&self) -> usize {
+        self.games.len()
+    }
+
+    /// Returns whether the store holds no games.
+    pub fn is_empty(&self) -> bool {
+        self.games.is_empty()
+    }
+
+    /// Returns the game `id` refers to.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` was handed out by a different store holding fewer games.
+    pub fn get(&self, id: StoreId) -> &Game<M> {
+        &self.games[id.index()]
+    }
+
+    /// Returns the ids of every game in the store.
+    pub fn ids(&self) -> Vec<StoreId> {
+        (0..self.games.len() as u32).map(StoreId).collect()
+    }
+
+    /// Returns the ids of the games `player` played in, with either color. Names are matched
+    /// ignoring case.
+    pub fn with_player(&self, player: &str) -> Vec<StoreId> {
+        self.by_player
+            .get(&player::name_key(player))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Returns the ids of the games played between `since` and `until`, both included, with no
+    /// bound on a side that's `None`. Games without a known date are left out.
+    pub fn with_dates(&self, since: Option<NaiveDate>, until: Option<NaiveDate>) -> Vec<StoreId> {
+        let mut ids = self
+            .by_date
+            .range(since.unwrap_or(NaiveDate::MIN)..=until.unwrap_or(NaiveDate::MAX))
+            .flat_map(|(_, ids)| ids.iter().copied())
+            .collect::<Vec<_>>();
+
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Returns the ids of the games whose opening has an ECO code starting with `prefix`, so that
+    /// `B` finds every semi-open game and `B20` only the games classified as `B20`.
+    pub fn with_eco(&self, prefix: &str) -> Vec<StoreId> {
+        let mut ids = self
+            .by_eco
+            .range(String::from(prefix)..)
+            .take_while(|(eco, _)| eco.starts_with(prefix))
+            .flat_map(|(_, ids)| ids.iter().copied())
+            .collect::<Vec<_>>();
+
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Returns the ids of the games that reached `setup`, however they got there.
+    pub fn with_position(&self, setup: &Setup) -> Vec<StoreId> {
+        // Each game is found once, in the order the games were given.
+        self.positions
+            .find(setup)
+            .iter()
+            .map(|occurrence| StoreId(occurrence.game() as u32))
+            .collect()
+    }
+
+    /// Returns the ids among `ids` of the games matching `filter`.
+    pub fn filter(&self, ids: &[StoreId], filter: &GameFilter<Game<M>>) -> Vec<StoreId> {
+        ids.iter()
+            .copied()
+            .filter(|id| filter.matches(self.get(*id)))
+            .collect()
+    }
+
+    /// Returns the games `ids` refer to, in the same order.
+    pub fn games(&self, ids: &[StoreId]) -> Vec<&Game<M>> {
+        ids.iter().map(|id| self.get(*id)).collect()
+    }
+
+    /// Returns a move tree of the games `ids` refer to, which borrows the games from the store
+    /// rather than holding copies of them.
+    pub fn move_tree(&self, ids: &[StoreId]) -> MoveTree<M, &Game<M>>
+    where
+        M: Eq + Hash,
+    {
+        MoveTree::new(self.games(ids))
+    }
+}
+
+/// Returns the ids found in both `a` and `b`, which must each be in order, as the lists a
+/// [`GameStore`] returns are.
+pub fn intersect(a: &[StoreId], b: &[StoreId]) -> Vec<StoreId> {
+    let (mut a, mut b) = (a.iter().peekable(), b.iter().peekable());
+    let mut both = Vec::new();
+
+    while let (Some(x), Some(y)) = (a.peek(), b.peek()) {
+        match x.cmp(y) {
+            Ordering::Less => {
+                a.next();
+            }
+            Ordering::Greater => {
+                b.next();
+            }
+            Ordering::Equal => {
+                both.push(**x);
+                a.next();
+                b.next();
+            }
+        }
+    }
+
+    both
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use rstest::*;
+
+    use shakmaty::fen::Fen;
+
+    use super::{intersect, GameStore};
+    use crate::filter::GameFilter;
+    use crate::game::test_utils::*;
+    use crate::game::{Color, GivePlayers};
+
+    #[rstest]
+    fn store_should_index_games_for_each_view() {
+        let store = GameStore::new(vec![
+            parse_game(
+                "[White \"Alice\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n[Date \"2021.01.05\"]\n\
+                 1. e4 c5 2. Nf3 Nc6 1-0",
+            ),
+            parse_game(
+                "[White \"bob\"]\n[Black \"alice\"]\n[Result \"0-1\"]\n[Date \"2021.02.10\"]\n\
+                 1. Nf3 c5 2. e4 Nc6 0-1",
+            ),
+            parse_game(
+                "[White \"carol\"]\n[Black \"alice\"]\n[Result \"1/2-1/2\"]\n\
+                 1. d4 d5 1/2-1/2",
+            ),
+        ]);
+
+        assert_eq!(store.len(), 3);
+
+        let alice = store.with_player("ALICE");
+        let in_january = store.with_dates(
+            NaiveDate::from_ymd_opt(2021, 1, 1),
+            NaiveDate::from_ymd_opt(2021, 1, 31),
+        );
+
+        assert_eq!(alice.len(), 3);
+        assert_eq!(store.with_player("bob").len(), 2);
+        assert_eq!(in_january.len(), 1);
+        assert_eq!(store.with_dates(None, None).len(), 2);
+        assert_eq!(store.with_eco("B").len(), 2);
+        assert_eq!(store.with_eco("D0").len(), 1);
+        assert!(store.with_eco("E").is_empty());
+
+        let fen = "r1bqkbnr/pp1ppppp/2n5/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3"
+            .parse::<Fen>()
+            .unwrap();
+        let transposed = store.with_position(fen.as_setup());
+
+        assert_eq!(
+            transposed.iter().map(|id| id.index()).collect::<Vec<_>>(),
+            [0, 1]
+        );
+
+        let as_black = store.filter(&alice, &GameFilter::color("alice", Color::Black));
+
+        assert_eq!(intersect(&as_black, &transposed).len(), 1);
+        assert_eq!(store.get(as_black[0]).white_player(), Some("bob"));
+        assert_eq!(store.move_tree(&transposed).view().iter().count(), 2);
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use shakmaty::{Bitboard, Chess, Color, File, Position, Square};
+
+use crate::moves::Move;
+use crate::phase::{self, Phase};
+use crate::position;
+
+/// Well known middlegame pawn structures, each belonging to the side whose pawns define it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PawnStructure {
+    /// A d-pawn with no pawns of its side on the c- or e-files, against no d-pawn.
+    IsolatedQueensPawn,
+    /// Pawns side by side on the c- and d-files with none of their side on the b- or e-files,
+    /// against no c- or d-pawns.
+    HangingPawns,
+    /// Pawns on d4 and the e-file against pawns on d5 and the c-file, as after the Queen's Gambit
+    /// Declined exchange variation. It belongs to the side without a c-pawn.
+    Carlsbad,
+    /// Pawns on a6, b6, d6, and e6 against pawns on c4 and e4, as in many Sicilians and English
+    /// openings. It belongs to the side with the pawns on the sixth rank.
+    Hedgehog,
+    /// Pawns on c4 and e4 against no c-pawn, as after an open Sicilian. It belongs to the side
+    /// with the pawns on c4 and e4.
+    MaroczyBind,
+    /// Pawns on c3, d4, e3, and f4.
+    Stonewall,
+}
+
+impl PawnStructure {
+    /// Returns the structure in `position` and the side it belongs to, or `None` if the position
+    /// doesn't have one of the structures known.
+    pub fn of_position(position: &Chess) -> Option<(PawnStructure, Color)> {
+        let board = position.board();
+
+        [
+            PawnStructure::Hedgehog,
+            PawnStructure::MaroczyBind,
+            PawnStructure::Stonewall,
+            PawnStructure::Carlsbad,
+            PawnStructure::HangingPawns,
+            PawnStructure::IsolatedQueensPawn,
+        ]
+        .iter()
+        .flat_map(|&structure| [(structure, Color::White), (structure, Color::Black)])
+        .find(|(structure, color)| {
+            // Pawns are seen from the side of `color`, so that both sides are checked with the
+            // squares white's pawns would stand on.
+            let view = |pawns: Bitboard| match color {
+                Color::White => pawns,
+                Color::Black => pawns.flip_vertical(),
+            };
+            let own = view(board.pawns() & board.by_color(*color));
+            let their = view(board.pawns() & board.by_color(!*color));
+
+            structure.matches(own, their)
+        })
+    }
+
+    /// Returns whether `own` pawns against `their` pawns form the structure, with both seen from
+    /// the side of the player owning `own`.
+    fn matches(self, own: Bitboard, their: Bitboard) -> bool {
+        let on = |pawns: Bitboard, squares: &[Square]| {
+            squares.iter().all(|square| pawns.contains(*square))
+        };
+        let on_file = |pawns: Bitboard, file: File| (pawns & Bitboard::from(file)).any();
+
+        match self {
+            PawnStructure::IsolatedQueensPawn => {
+                (own & Bitboard::from(File::D)).count() == 1
+                    && !on_file(own, File::C)
+                    && !on_file(own, File::E)
+                    && !on_file(their, File::D)
+            }
+            PawnStructure::HangingPawns => {
+                (on(own, &[Square::C4, Square::D4]) || on(own, &[Square::C3, Square::D3]))
+                    && !on_file(own, File::B)
+                    && !on_file(own, File::E)
+                    && !on_file(their, File::C)
+                    && !on_file(their, File::D)
+            }
+            PawnStructure::Carlsbad => {
+                on(own, &[Square::D4])
+                    && on_file(own, File::E)
+                    && !on_file(own, File::C)
+                    && on(their, &[Square::D5])
+                    && on_file(their, File::C)
+                    && !on_file(their, File::E)
+            }
+            PawnStructure::Hedgehog => {
+                on(own, &[Square::A3, Square::B3, Square::D3, Square::E3])
+                    && !on_file(own, File::C)
+                    && on(their, &[Square::C5, Square::E5])
+                    && !on_file(their, File::D)
+            }
+            PawnStructure::MaroczyBind => {
+                on(own, &[Square::C4, Square::E4])
+                    && !on_file(own, File::D)
+                    && !on_file(their, File::C)
+                    && on_file(their, File::D)
+            }
+            PawnStructure::Stonewall => on(own, &[Square::C3, Square::D4, Square::E3, Square::F4]),
+        }
+    }
+}
+
+impl fmt::Display for PawnStructure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PawnStructure::IsolatedQueensPawn => "Isolated queen's pawn",
+            PawnStructure::HangingPawns => "Hanging pawns",
+            PawnStructure::Carlsbad => "Carlsbad",
+            PawnStructure::Hedgehog => "Hedgehog",
+            PawnStructure::MaroczyBind => "Maróczy bind",
+            PawnStructure::Stonewall => "Stonewall",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// Returns the pawn structure of a game and the side it belongs to, as the structure found in
+/// the most of its middlegame positions. Ties go to the structure reached first. Returns `None`
+/// if no middlegame position has a known structure.
+pub fn structure<M: Clone + Move, I: IntoIterator<Item = M>>(
+    moves: I,
+) -> Option<(PawnStructure, Color)> {
+    let moves = moves.into_iter().collect::<Vec<_>>();
+    let positions = position::replay(moves.iter().cloned());
+    let mut counts: HashMap<(PawnStructure, Color), (usize, usize)> = HashMap::new();
+
+    for (ply, (position, phase)) in positions.iter().zip(phase::phases(moves)).enumerate() {
+        if phase != Phase::Middlegame {
+            continue;
+        }
+
+        if let Some(structure) = PawnStructure::of_position(position) {
+            counts.entry(structure).or_insert((0, ply)).0 += 1;
+        }
+    }
+
+    counts
+        .into_iter()
+        .max_by(|(_, (count_a, first_a)), (_, (count_b, first_b))| {
+            count_a.cmp(count_b).then_with(|| first_b.cmp(first_a))
+        })
+        .map(|(structure, _)| structure)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use shakmaty::fen::Fen;
+    use shakmaty::{CastlingMode, Chess, Color};
+
+    use super::{structure, PawnStructure};
+    use crate::game::test_utils::*;
+    use crate::game::ListMoves;
+
+    #[rstest(fen, expected,
+        case("r1bq1rk1/pp2bppp/2n1pn2/8/3P4/2NB1N2/PP3PPP/R1BQ1RK1 w - - 0 1",
+             Some((PawnStructure::IsolatedQueensPawn, Color::White))),
+        case("r1bq1rk1/pp2bppp/2p2n2/3p4/3P4/2NBPN2/PP3PPP/R2Q1RK1 w - - 0 1",
+             Some((PawnStructure::Carlsbad, Color::White))),
+        case("r1bq1rk1/4bppp/pp1ppn2/8/2P1P3/2N2N2/PP2BPPP/R2Q1RK1 w - - 0 1",
+             Some((PawnStructure::Hedgehog, Color::Black))),
+        case("r1bq1rk1/pp2ppbp/2np1np1/8/2P1P3/2N1B3/PP2BPPP/R2QK2R w KQ - 0 1",
+             Some((PawnStructure::MaroczyBind, Color::White))),
+        case("rnbq1rk1/ppp1b1pp/4pn2/3p1p2/2PP4/5NP1/PP2PPBP/RNBQ1RK1 w - - 0 1", None),
+    )]
+    fn of_position_should_recognize_structures(
+        fen: &str,
+        expected: Option<(PawnStructure, Color)>,
+    ) {
+        let position = fen
+            .parse::<Fen>()
+            .unwrap()
+            .into_position::<Chess>(CastlingMode::Standard)
+            .unwrap();
+
+        assert_eq!(PawnStructure::of_position(&position), expected);
+    }
+
+    #[rstest]
+    fn structure_should_look_at_the_middlegame() {
+        let game = parse_game(
+            "[Result \"1/2-1/2\"]\n1. d4 d5 2. c4 e6 3. Nc3 Nf6 4. cxd5 exd5 5. Bg5 c6 6. e3 Be7 \
+             7. Bd3 O-O 8. Qc2 Nbd7 9. Nge2 Re8 10. O-O Nf8 11. f3 Be6 1/2-1/2",
+        );
+
+        assert_eq!(
+            structure(game.list_moves()),
+            Some((PawnStructure::Carlsbad, Color::White))
+        );
+        assert_eq!(structure(queens_gambit().list_moves()), None);
+    }
+}
@@ -0,0 +1,48 @@
+use std::thread;
+
+use crate::error::{Error, Result};
+
+/// Returns the number of threads used when none is chosen, which is the number of cores.
+pub fn default_threads() -> usize {
+    thread::available_parallelism().map_or(1, |threads| threads.get())
+}
+
+/// Sets the number of threads that work done in parallel, such as
+/// [`compute_parallel`](crate::stats::statistic::compute_parallel), is spread across. Without
+/// this, one thread per core is used.
+///
+/// # Errors
+///
+/// Returns an error if `threads` is zero, or if the number of threads was already set or
+/// parallel work has already started, since the threads can only be chosen once.
+pub fn set_threads(threads: usize) -> Result<()> {
+    if threads == 0 {
+        return Err(Error::InvalidArgument(String::from(
+            "At least one thread is needed",
+        )));
+    }
+
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build_global()?;
+
+    Ok(())
+}
+
+/// Returns the number of threads that work done in parallel is spread across.
+pub fn threads() -> usize {
+    rayon::current_num_threads()
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::{default_threads, set_threads};
+
+    #[rstest]
+    fn set_threads_should_reject_zero() {
+        assert!(default_threads() >= 1);
+        assert!(set_threads(0).is_err());
+    }
+}
@@ -0,0 +1,126 @@
+use std::cmp::Reverse;
+
+use wasm_bindgen::prelude::*;
+
+use crate::parsing;
+use crate::stats::statistic::{compute, PlayerScore, ResultRates, Statistic, Value};
+use crate::{AlgebraicMove, Game, Move, MoveTree, MoveTreeView};
+
+type Tree = MoveTree<AlgebraicMove, Game<AlgebraicMove>>;
+type TreeView<'a> = MoveTreeView<'a, AlgebraicMove, Game<AlgebraicMove>>;
+
+/// A set of games parsed from PGN and arranged by their moves, for JavaScript to query.
+///
+/// ```js
+/// const games = Games.fromPgn(pgn);
+/// const stats = JSON.parse(games.stats("e4 c5", "alice"));
+/// ```
+#[wasm_bindgen]
+pub struct Games {
+    tree: Tree,
+    length: usize,
+}
+
+#[wasm_bindgen]
+impl Games {
+    /// Parses the games in `pgn`, throwing an error if it can't be read or a game has no
+    /// result.
+    #[wasm_bindgen(js_name = fromPgn)]
+    pub fn from_pgn(pgn: &str) -> Result<Games, JsError> {
+        let games = parsing::read_games(pgn.as_bytes())?;
+
+        Ok(Games {
+            length: games.len(),
+            tree: MoveTree::new(games),
+        })
+    }
+
+    /// Returns the number of games.
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// Returns statistics on the games reaching the position after `moves`, given in SAN
+    /// separated by spaces, as JSON. The statistics are the rates of results, `player`'s score
+    /// when given, and the same for each move played next, most played first. Throws an error if
+    /// a move isn't valid SAN.
+    pub fn stats(&self, moves: &str, player: Option<String>) -> Result<String, JsError> {
+        let mut view = self.tree.view();
+
+        for move_ in moves.split_whitespace() {
+            view = view.with_next(&AlgebraicMove::try_from_algebraic(String::from(move_))?);
+        }
+
+        Ok(tree_stats(&view, player.as_deref()).to_json())
+    }
+}
+
+/// Returns the statistics of the games in `view` and of each move played next in them.
+fn tree_stats(view: &TreeView, player: Option<&str>) -> Value {
+    let mut children = view
+        .children()
+        .into_iter()
+        .map(|(move_, child)| (move_.clone().to_algebraic(), child))
+        .collect::<Vec<_>>();
+
+    children.sort_by_key(|(move_, child)| (Reverse(child.iter().count()), move_.clone()));
+
+    let mut entries = node_stats(view, player);
+
+    entries.push((
+        String::from("moves"),
+        Value::List(
+            children
+                .into_iter()
+                .map(|(move_, child)| {
+                    let mut entries = vec![(String::from("move"), Value::Text(move_))];
+                    entries.extend(node_stats(&child, player));
+
+                    Value::Map(entries)
+                })
+                .collect(),
+        ),
+    ));
+
+    Value::Map(entries)
+}
+
+/// Returns the name and value of each statistic of the games in `view`.
+fn node_stats(view: &TreeView, player: Option<&str>) -> Vec<(String, Value)> {
+    let mut statistics: Vec<Box<dyn Statistic<Game<AlgebraicMove>>>> =
+        vec![Box::new(ResultRates::new())];
+
+    if let Some(player) = player {
+        statistics.push(Box::new(PlayerScore::new(player)));
+    }
+
+    compute(&mut view.iter(), &mut statistics)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::tree_stats;
+    use crate::parsing::read_games;
+    use crate::{AlgebraicMove, Move, MoveTree};
+
+    const PGN: &str = "[White \"alice\"]\n[Black \"bob\"]\n[Result \"1-0\"]\n\n1. e4 c5 2. Nf3 1-0\n\n\
+                       [White \"bob\"]\n[Black \"alice\"]\n[Result \"0-1\"]\n\n1. e4 e5 0-1\n\n\
+                       [White \"carol\"]\n[Black \"alice\"]\n[Result \"1/2-1/2\"]\n\n1. e4 c5 1/2-1/2\n";
+
+    #[rstest]
+    fn tree_stats_should_describe_next_moves() {
+        let tree = MoveTree::new(read_games(PGN.as_bytes()).unwrap());
+        let view = tree
+            .view()
+            .with_next(&AlgebraicMove::from_algebraic(String::from("e4")));
+
+        let json = tree_stats(&view, Some("alice")).to_json();
+
+        assert!(json.starts_with("{\"results\":{\"games\":3,"));
+        assert!(json.contains("\"moves\":[{\"move\":\"c5\",\"results\":{\"games\":2,"));
+        assert!(json.contains("{\"move\":\"e5\",\"results\":{\"games\":1,"));
+    }
+}
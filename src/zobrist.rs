@@ -0,0 +1,182 @@
+use shakmaty::{Chess, Color, EnPassantMode, Position, Setup, Square};
+
+/// Number of keys for pieces, one for each kind of piece of each color on each square.
+const PIECE_KEYS: usize = 2 * 6 * 64;
+/// Offset of the keys for castling rights, one for each square a castling rook could be on.
+const CASTLING_OFFSET: usize = PIECE_KEYS;
+/// Offset of the keys for the file of the en passant square.
+const EN_PASSANT_OFFSET: usize = CASTLING_OFFSET + 64;
+/// Offset of the key for black being the side to move.
+const TURN_OFFSET: usize = EN_PASSANT_OFFSET + 8;
+
+/// Random keys that make up the hashes, generated with SplitMix64 from a fixed seed so that
+/// hashes are stable between builds and can be stored.
+const KEYS: [u64; TURN_OFFSET + 1] = generate_keys();
+
+const fn generate_keys() -> [u64; TURN_OFFSET + 1] {
+    let mut keys = [0; TURN_OFFSET + 1];
+    let mut state: u64 = 0x6368_6573_7368_6f75;
+    let mut index = 0;
+
+    while index < keys.len() {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+        let mut key = state;
+        key = (key ^ (key >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        key = (key ^ (key >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        keys[index] = key ^ (key >> 31);
+
+        index += 1;
+    }
+
+    keys
+}
+
+/// Returns the Zobrist hash of `setup`, which is the same for positions with the same pieces,
+/// side to move, castling rights, and en passant square, however they were reached. Move counters
+/// aren't part of the hash.
+///
+/// Hashes are stable between runs, so they can be used as keys in data kept on disk.
+pub fn hash(setup: &Setup) -> u64 {
+    hash_as(setup, false)
+}
+
+/// Returns the Zobrist hash of `position`, like `hash`, counting its en passant square only when a
+/// pawn can capture on it, so that positions reached by different moves hash the same.
+pub fn hash_position(position: &Chess) -> u64 {
+    hash(&position.to_setup(EnPassantMode::Legal))
+}
+
+/// Returns the Zobrist hash of `setup` with the colors reversed: the board flipped top to bottom,
+/// every piece given to the other side, and the other side to move. A position's reversed hash is
+/// the `hash` of the position with white and black swapped, so positions can be matched whichever
+/// color reached them.
+pub fn reversed_hash(setup: &Setup) -> u64 {
+    hash_as(setup, true)
+}
+
+fn hash_as(setup: &Setup, reversed: bool) -> u64 {
+    let square_key = |square: Square| {
+        usize::from(if reversed {
+            square.flip_vertical()
+        } else {
+            square
+        })
+    };
+    let mut hash = 0;
+
+    for (square, piece) in &setup.board {
+        let color = match (piece.color, reversed) {
+            (Color::White, false) | (Color::Black, true) => 0,
+            _ => 1,
+        };
+        let role = piece.role as usize - 1;
+
+        hash ^= KEYS[(color * 6 + role) * 64 + square_key(square)];
+    }
+
+    for square in setup.castling_rights {
+        hash ^= KEYS[CASTLING_OFFSET + square_key(square)];
+    }
+
+    if let Some(square) = setup.ep_square {
+        hash ^= KEYS[EN_PASSANT_OFFSET + usize::from(square.file())];
+    }
+
+    if (setup.turn == Color::Black) != reversed {
+        hash ^= KEYS[TURN_OFFSET];
+    }
+
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use shakmaty::fen::Fen;
+    use shakmaty::{CastlingMode, Chess, EnPassantMode, Position, Setup};
+
+    use super::{hash, hash_position, reversed_hash};
+
+    fn position(fen: &str) -> Chess {
+        fen.parse::<Fen>()
+            .unwrap()
+            .into_position(CastlingMode::Standard)
+            .unwrap()
+    }
+
+    fn setup(fen: &str) -> Setup {
+        position(fen).to_setup(EnPassantMode::Legal)
+    }
+
+    #[rstest(
+        fen,
+        same_fen,
+        // Move counters are ignored.
+        case(
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 4 9"
+        ),
+        // An en passant square that no pawn can capture on doesn't matter.
+        case(
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"
+        )
+    )]
+    fn hash_should_match_same_positions(fen: &str, same_fen: &str) {
+        assert_eq!(
+            hash_position(&position(fen)),
+            hash_position(&position(same_fen))
+        );
+    }
+
+    #[rstest(
+        fen,
+        other_fen,
+        case(
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 1"
+        ),
+        case(
+            "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R w Kkq - 0 1"
+        ),
+        case(
+            "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"
+        ),
+        case(
+            "rnbqkbnr/pppppppp/8/8/8/5N2/PPPPPPPP/RNBQKB1R b KQkq - 1 1",
+            "rnbqkbnr/pppppppp/8/8/8/5N2/PPPPPPPP/RNBQKBR1 b Qkq - 1 1"
+        )
+    )]
+    fn hash_should_differ_between_positions(fen: &str, other_fen: &str) {
+        assert_ne!(
+            hash_position(&position(fen)),
+            hash_position(&position(other_fen))
+        );
+    }
+
+    #[rstest(
+        fen,
+        reversed_fen,
+        case(
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+            "rnbqkbnr/pppp1ppp/8/4p3/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+        ),
+        case(
+            "r3k2r/8/8/8/8/8/8/R3K2R w Kq - 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R b Qk - 0 1"
+        ),
+        case(
+            "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            "rnbqkbnr/pppp1ppp/8/3Pp3/8/8/PPP1PPPP/RNBQKBNR w KQkq e6 0 1"
+        )
+    )]
+    fn reversed_hash_should_match_position_with_colors_swapped(fen: &str, reversed_fen: &str) {
+        assert_eq!(reversed_hash(&setup(fen)), hash(&setup(reversed_fen)));
+        assert_eq!(hash(&setup(fen)), reversed_hash(&setup(reversed_fen)));
+        assert_ne!(reversed_hash(&setup(fen)), hash(&setup(fen)));
+    }
+}
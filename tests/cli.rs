@@ -0,0 +1,451 @@
+//! Tests of the command-line tool, run as a user would run it.
+
+use std::env;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+use rstest::*;
+
+/// Runs chesshound with `args` and `stdin` as its standard input, without the config file or
+/// `CHESSHOUND_` variables of whoever runs the tests.
+fn chesshound(args: &[&str], stdin: &str) -> Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_chesshound"));
+    command
+        .args(args)
+        .env("XDG_CONFIG_HOME", env!("CARGO_TARGET_TMPDIR"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    for (name, _) in env::vars().filter(|(name, _)| name.starts_with("CHESSHOUND_")) {
+        command.env_remove(name);
+    }
+
+    let mut child = command.spawn().unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+
+    child.wait_with_output().unwrap()
+}
+
+/// Returns the standard output of `output`, after checking that the command succeeded.
+fn stdout(output: Output) -> String {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    String::from(String::from_utf8(output.stdout).unwrap().trim_end())
+}
+
+/// Returns the path of a file named `name` in a directory for the tests, after removing anything
+/// left there by earlier runs.
+fn temp_path(name: &str) -> PathBuf {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_file(&path);
+
+    path
+}
+
+/// Writes a UCI engine named `name` that gives every position the same evaluation, and stops
+/// responding after `searches` searches as if it had crashed, and returns its path.
+#[cfg(unix)]
+fn fake_engine(name: &str, searches: usize) -> PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = temp_path(name);
+    let script = format!(
+        "#!/bin/sh
+searches=0
+while read -r command; do
+    case \"$command\" in
+        uci) echo uciok ;;
+        isready) echo readyok ;;
+        go*)
+            [ \"$searches\" -ge {} ] && exit 1
+            searches=$((searches + 1))
+            echo 'info depth 1 nodes 10 score cp 20'
+            echo 'bestmove 0000' ;;
+        quit) exit 0 ;;
+    esac
+done
+",
+        searches
+    );
+
+    fs::write(&path, script).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+
+    path
+}
+
+const GAMES: &str = r#"[White "alice"]
+[Black "bob"]
+[Result "1-0"]
+[Date "2021.03.01"]
+
+1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0
+
+[White "bob"]
+[Black "alice"]
+[Result "1/2-1/2"]
+[Date "2021.05.01"]
+
+1. e4 e5 1/2-1/2
+
+[White "alice"]
+[Black "carol"]
+[Result "0-1"]
+[Date "2021.04.01"]
+
+1. d4 d5 0-1
+"#;
+
+/// A game still being played, which can't be analysed without a result.
+const UNFINISHED: &str = r#"[White "alice"]
+[Black "dave"]
+[Result "*"]
+
+1. e4 *
+"#;
+
+/// A game alice lost before a move was played, which counts as aborted.
+const ABORTED: &str = r#"[White "alice"]
+[Black "erin"]
+[Result "0-1"]
+
+0-1
+"#;
+
+#[rstest]
+fn opponents_should_list_each_opponent() {
+    let output = stdout(chesshound(&["opponents", "--player", "alice"], GAMES));
+
+    assert_eq!(
+        output,
+        "bob: 2 games, 1 wins, 1 draws, 0 losses, scoring 75.0%, 1 as white and 1 as black, \
+         last played 2021-05-01\n\
+         carol: 1 games, 0 wins, 0 draws, 1 losses, scoring 0.0%, 1 as white and 0 as black, \
+         last played 2021-04-01"
+    );
+}
+
+#[rstest(format, expected,
+    case(
+        "csv",
+        "opponent,games,wins,draws,losses,score,games_as_white,games_as_black,last_played\n\
+         bob,2,1,1,0,0.75,1,1,2021-05-01\n\
+         carol,1,0,0,1,0,1,0,2021-04-01"
+    ),
+    case(
+        "tsv",
+        "opponent\tgames\twins\tdraws\tlosses\tscore\tgames_as_white\tgames_as_black\tlast_played\n\
+         bob\t2\t1\t1\t0\t0.75\t1\t1\t2021-05-01\n\
+         carol\t1\t0\t0\t1\t0\t1\t0\t2021-04-01"
+    )
+)]
+fn opponents_should_write_tables(format: &str, expected: &str) {
+    let output = stdout(chesshound(
+        &["opponents", "--player", "alice", "--format", format],
+        GAMES,
+    ));
+
+    assert_eq!(output, expected);
+}
+
+#[cfg(unix)]
+#[rstest]
+fn analyze_should_save_the_cache_when_the_engine_fails() {
+    let engine = fake_engine("failing-engine", 2);
+    let cache = temp_path("failing-engine.cache");
+
+    let output = chesshound(
+        &[
+            "analyze",
+            "--engine",
+            engine.to_str().unwrap(),
+            "--blunders",
+            "--engines",
+            "1",
+            "--depth",
+            "1",
+            "--cache",
+            cache.to_str().unwrap(),
+        ],
+        GAMES,
+    );
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(fs::read_to_string(&cache).unwrap().lines().count(), 2);
+}
+
+#[cfg(unix)]
+#[rstest]
+fn analyze_should_fail_when_the_cache_cant_be_opened() {
+    let engine = fake_engine("cache-engine", 100);
+    let not_a_directory = temp_path("not-a-directory");
+    fs::write(&not_a_directory, "").unwrap();
+
+    let output = chesshound(
+        &[
+            "analyze",
+            "--engine",
+            engine.to_str().unwrap(),
+            "--acpl",
+            "--depth",
+            "1",
+            "--cache",
+            not_a_directory.join("engine.cache").to_str().unwrap(),
+        ],
+        GAMES,
+    );
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+}
+
+#[rstest]
+fn dedupe_should_report_games_that_cant_be_read() {
+    let output = chesshound(
+        &["dedupe"],
+        &format!("{}\n{}\n{}", GAMES, GAMES, UNFINISHED),
+    );
+    let stderr = String::from_utf8(output.stderr.clone()).unwrap();
+
+    assert_eq!(stdout(output).matches("[White ").count(), 3);
+    assert!(stderr.contains("Removed 3 duplicate games"));
+    assert!(stderr.contains("Left out 1 games that couldn't be read"));
+}
+
+#[rstest]
+fn split_should_fail_when_games_cant_be_read() {
+    let directory = temp_path("split-unread");
+    let _ = fs::remove_dir_all(&directory);
+
+    let output = chesshound(
+        &[
+            "split",
+            "--by",
+            "count",
+            "--games",
+            "2",
+            "--directory",
+            directory.to_str().unwrap(),
+        ],
+        &format!("{}\n{}", GAMES, UNFINISHED),
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert_eq!(output.status.code(), Some(3));
+    assert!(stderr.contains("1.pgn: 2 games"));
+    assert!(stderr.contains("2.pgn: 1 games"));
+    assert!(stderr.contains("Left out 1 games that couldn't be read"));
+}
+
+#[rstest]
+fn merge_should_fail_when_games_cant_be_read() {
+    let games = temp_path("merge-games.pgn");
+    let unfinished = temp_path("merge-unfinished.pgn");
+    fs::write(&games, GAMES).unwrap();
+    fs::write(&unfinished, UNFINISHED).unwrap();
+
+    let output = chesshound(
+        &[
+            "merge",
+            games.to_str().unwrap(),
+            unfinished.to_str().unwrap(),
+        ],
+        "",
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .matches("[White ")
+            .count(),
+        3
+    );
+    assert!(stderr.contains("Left out 1 games that couldn't be read"));
+}
+
+#[rstest]
+fn dedupe_should_preserve_games_that_cant_be_read() {
+    let output = chesshound(
+        &["dedupe", "--preserve"],
+        &format!("{}\n{}\n{}", UNFINISHED, GAMES, UNFINISHED),
+    );
+    let stderr = String::from_utf8(output.stderr.clone()).unwrap();
+
+    assert_eq!(
+        stdout(output),
+        format!("{}\n{}", UNFINISHED, GAMES).trim_end()
+    );
+    assert!(stderr.contains("Removed 1 duplicate games"));
+    assert!(!stderr.contains("couldn't be read"));
+}
+
+#[rstest]
+fn split_should_preserve_games_that_cant_be_read() {
+    let directory = temp_path("split-preserved");
+    let _ = fs::remove_dir_all(&directory);
+
+    stdout(chesshound(
+        &[
+            "split",
+            "--by",
+            "month",
+            "--preserve",
+            "--directory",
+            directory.to_str().unwrap(),
+        ],
+        &format!("{}\n{}", GAMES, UNFINISHED),
+    ));
+
+    assert_eq!(
+        fs::read_to_string(directory.join("unknown.pgn")).unwrap(),
+        UNFINISHED
+    );
+    assert_eq!(fs::read_dir(&directory).unwrap().count(), 4);
+}
+
+#[rstest(args, expected_rank,
+    case(&["fen", "--board", "e4"], "8 ♜ ♞ ♝ ♛ ♚ ♝ ♞ ♜"),
+    case(&["fen", "--board=ascii", "e4"], "8 r n b q k b n r"),
+    case(&["fen", "e4", "--board"], "8 ♜ ♞ ♝ ♛ ♚ ♝ ♞ ♜")
+)]
+fn fen_should_draw_the_board(args: &[&str], expected_rank: &str) {
+    let output = stdout(chesshound(args, ""));
+
+    assert!(output.starts_with("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"));
+    assert!(output.contains(expected_rank), "{}", output);
+}
+
+#[rstest(args, case(&["opponents", "--player", "alice"]), case(&["completions", "bash"]))]
+fn output_should_stop_cleanly_when_the_pipe_closes(args: &[&str]) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_chesshound"))
+        .args(args)
+        .env("XDG_CONFIG_HOME", env!("CARGO_TARGET_TMPDIR"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // Closed before anything is written, as `head` closes it after reading enough. Commands that
+    // don't read their input may already have exited, so failing to write it is fine.
+    drop(child.stdout.take());
+    let _ = child.stdin.take().unwrap().write_all(GAMES.as_bytes());
+
+    let output = child.wait_with_output().unwrap();
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(
+        output.stderr.is_empty(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[rstest]
+fn completions_should_complete_option_choices() {
+    let script = stdout(chesshound(&["completions", "bash"], ""));
+    let help = stdout(chesshound(&["completions", "--help"], ""));
+
+    assert!(script.contains("opponents"));
+    assert!(script.contains("csv"));
+    assert!(help.contains("such as player names, aren't completed"));
+}
+
+#[rstest]
+fn report_should_not_match_players_whose_name_is_unknown() {
+    let output = stdout(chesshound(
+        &["report", "--player", "?"],
+        "[Result \"1-0\"]\n\n1. e4 e5 1-0\n\n[Result \"0-1\"]\n\n1. d4 d5 0-1\n",
+    ));
+
+    assert!(output.contains("Overall: 0 games"), "{}", output);
+}
+
+#[rstest(args, expected,
+    case(&["tree", "--player", "alice"], "Starting position (3 games, white scores 50.0%)"),
+    case(
+        &["tree", "--player", "alice", "--min-plies", "0"],
+        "Starting position (4 games, white scores 37.5%)"
+    ),
+    case(&["report", "--player", "alice"], "Overall: 3 games, 1 wins, 1 draws, 1 losses")
+)]
+fn subcommands_should_leave_out_aborted_games(args: &[&str], expected: &str) {
+    let output = stdout(chesshound(args, &format!("{}\n{}", GAMES, ABORTED)));
+
+    assert!(output.contains(expected), "{}", output);
+}
+
+#[rstest]
+fn repl_should_leave_out_aborted_games() {
+    let path = temp_path("repl-aborted.pgn");
+    fs::write(&path, format!("{}\n{}", GAMES, ABORTED)).unwrap();
+
+    let output = stdout(chesshound(&["repl", path.to_str().unwrap()], "stats\n"));
+
+    assert!(output.starts_with("Loaded 3 games."), "{}", output);
+}
+
+#[rstest]
+fn compare_should_leave_out_aborted_games() {
+    let before = temp_path("compare-before.pgn");
+    let after = temp_path("compare-after.pgn");
+    fs::write(&before, GAMES).unwrap();
+    fs::write(&after, format!("{}\n{}", GAMES, ABORTED)).unwrap();
+
+    let output = stdout(chesshound(
+        &[
+            "compare",
+            "--player",
+            "alice",
+            before.to_str().unwrap(),
+            after.to_str().unwrap(),
+        ],
+        "",
+    ));
+
+    assert!(
+        output.contains("After: 3 games, 50.00% score"),
+        "{}",
+        output
+    );
+}
+
+#[rstest]
+fn watch_should_leave_out_aborted_games() {
+    let path = temp_path("watch-aborted.pgn");
+    fs::write(&path, format!("{}\n{}", GAMES, ABORTED)).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_chesshound"))
+        .args(["watch", "--player", "alice", path.to_str().unwrap()])
+        .env("XDG_CONFIG_HOME", env!("CARGO_TARGET_TMPDIR"))
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // Watching never ends by itself, so it's stopped once the first score is printed.
+    let mut line = String::new();
+    BufReader::new(child.stdout.take().unwrap())
+        .read_line(&mut line)
+        .unwrap();
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    assert_eq!(
+        line.trim_end(),
+        "alice: 3 games, 1 wins, 1 draws, 1 losses, scoring 50.0%"
+    );
+}